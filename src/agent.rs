@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fs;
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
@@ -15,17 +15,17 @@ use serde_json;
 
 use crate::claude::{call_agent_hook, call_claude, call_claude_with_model, call_critic};
 use crate::{
-    append_log_jsonl, base_tool_names, build_context_pack, build_kg_context,
+    append_log_jsonl, DEFAULT_FAST_PATH_MARGIN, DEFAULT_SEARCH_JOBS, SNIPPET_ELLIPSIS, base_tool_names, build_context_pack, build_kg_context, ContextFormat,
     collect_mid_loop_reminders, compute_drift_score, critic_should_fire, detect_cycle, env_optional,
-    execute_tool, find_kg_entities, log_dir_path,
+    execute_tool, AethervaultError, find_kg_entities, log_dir_path,
     config_file_path, format_tool_message_content, load_capsule_config, load_config_from_file,
     load_kg_graph, load_session_turns, load_workspace_context, open_or_create_db, requires_approval,
     resolve_hook_spec, resolve_workspace,
-    save_session_turns, tool_catalog_map, tool_definitions_json,
-    tools_from_active, AgentHookRequest, AgentLogEntry, AgentMessage,
-    AgentProgress, AgentRunOutput, AgentSession, AgentToolCall, AgentToolResult,
+    forced_tool_args, register_custom_tools, relevant_tool_names, save_session_turns, tool_catalog_map, tool_definitions_json, tool_registry, disable_tools, is_tool_disabled,
+    tools_from_active, AgentHookRequest, AGENT_HOOK_PROTOCOL_VERSION, AgentLogEntry, AgentMessage,
+    AgentEvent, AgentEventSink, AgentProgress, AgentRunOptions, AgentRunOutput, AgentSession, AgentToolCall, AgentToolResult,
     ContinuationCheckpoint,
-    CommandSpec, DriftState, HookSpec, McpRegistry, McpServerConfig, QueryArgs, ReminderState, SessionTurn,
+    CommandSpec, DriftState, HookSpec, McpRegistry, ModelTimeoutPolicy, McpServerConfig, QueryArgs, ReminderState, SessionTurn,
     ToolExecution, BackgroundTaskRegistry,
     open_skill_db, list_skills, search_skills, record_skill_use,
 };
@@ -42,7 +42,7 @@ fn check_capsule_health(mv2: &Path) {
     };
     let size_mb = size_bytes / (1024 * 1024);
     if size_mb > 2000 {
-        eprintln!("[capsule-health] capsule is {size_mb}MB — consider running VACUUM");
+        log_warn!("[capsule-health] capsule is {size_mb}MB — consider running VACUUM");
     }
 }
 
@@ -102,7 +102,13 @@ pub(crate) fn run_agent(
     log_commit_interval: usize,
     json: bool,
     log: bool,
+    events_file: Option<PathBuf>,
+    options: AgentRunOptions<'_>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let events = events_file
+        .as_deref()
+        .map(AgentEventSink::create)
+        .transpose()?;
     let prompt_text = if let Some(file) = file {
         fs::read_to_string(file)?
     } else if let Some(prompt) = prompt {
@@ -134,7 +140,14 @@ pub(crate) fn run_agent(
         log_commit_interval,
         log,
         None,
+        AgentRunOptions { events: events.as_ref(), ..options },
     )?;
+    if let Some(events) = &events {
+        events.emit(&AgentEvent::Final {
+            text: output.final_text.as_deref(),
+            tool_calls: output.tool_results.len(),
+        });
+    }
 
     // Save session turns for CLI agent continuity (mirrors Telegram bridge behaviour)
     if let Some(ref sess_id) = session_for_save {
@@ -404,6 +417,7 @@ pub(crate) fn compact_messages(
         ],
         tools: Vec::new(),
         session: None,
+        protocol_version: AGENT_HOOK_PROTOCOL_VERSION,
     };
 
     let summary_response = call_claude_with_model(&summary_request, Some(&sonnet_model))
@@ -511,6 +525,20 @@ fn process_tool_result(
         }
     }
 
+    // skill_run activates the tools its plan needs; approval gates still apply per call.
+    if call.name == "skill_run" && !is_error {
+        if let Some(names) = result.details.get("tools").and_then(|v| v.as_array()) {
+            for name in names.iter().filter_map(|v| v.as_str()) {
+                if active_tools.insert(name.to_string()) {
+                    tools_changed = true;
+                }
+            }
+        }
+        if let Some(name) = result.details.get("name").and_then(|v| v.as_str()) {
+            retrieved_skills.push(name.to_string());
+        }
+    }
+
     // SkillRL R4: Track skill names retrieved via skill_search
     if call.name == "skill_search" && !is_error {
         if let Some(results_arr) = result.details.get("results").and_then(|v| v.as_array()) {
@@ -531,13 +559,61 @@ fn process_tool_result(
             ts_utc: Some(Utc::now().timestamp()),
         };
         if let Err(e) = append_log_jsonl(log_dir, &entry) {
-            eprintln!("[harness] failed to write agent log: {e}");
+            log_error!("[harness] failed to write agent log: {e}");
         }
     }
 
     (is_error, tools_changed)
 }
 
+fn emit_tool_result_event(
+    events: Option<&AgentEventSink>,
+    step: usize,
+    result: Option<&AgentToolResult>,
+) {
+    if let (Some(events), Some(result)) = (events, result) {
+        events.emit(&AgentEvent::ToolResult {
+            step,
+            id: &result.id,
+            name: &result.name,
+            is_error: result.is_error,
+            output: &result.output,
+        });
+    }
+}
+
+/// A model hook call that produced no message.
+enum ModelCallError {
+    Failed(String),
+    TimedOut(String),
+}
+
+/// Call the model hook, giving up after `timeout_ms`. External hooks are
+/// killed by `run_hook_command`; built-in hooks run on a helper thread that is
+/// abandoned on timeout (their HTTP client ends it eventually).
+fn call_model_with_timeout(
+    spec: &HookSpec,
+    request: AgentHookRequest,
+    timeout_ms: Option<u64>,
+) -> Result<AgentMessage, ModelCallError> {
+    let Some(timeout_ms) = timeout_ms else {
+        return call_agent_hook(spec, &request).map_err(|e| ModelCallError::Failed(e.to_string()));
+    };
+    let timed_out = || ModelCallError::TimedOut(format!("model hook timed out after {timeout_ms}ms"));
+    let mut spec = spec.clone();
+    spec.timeout_ms = Some(timeout_ms);
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(call_agent_hook(&spec, &request));
+    });
+    match rx.recv_timeout(std::time::Duration::from_millis(timeout_ms)) {
+        Ok(Ok(message)) => Ok(message),
+        Ok(Err(e)) if e.to_string().contains("timed out after") => Err(timed_out()),
+        Ok(Err(e)) => Err(ModelCallError::Failed(e.to_string())),
+        Err(_) => Err(timed_out()),
+    }
+}
+
 pub(crate) fn run_agent_with_prompt(
     mv2: PathBuf,
     prompt_text: String,
@@ -552,13 +628,23 @@ pub(crate) fn run_agent_with_prompt(
     _log_commit_interval: usize,
     log: bool,
     progress: Option<Arc<Mutex<AgentProgress>>>,
+    options: AgentRunOptions<'_>,
 ) -> Result<AgentRunOutput, Box<dyn std::error::Error>> {
     if prompt_text.trim().is_empty() {
         return Err("agent prompt is empty".into());
     }
+    let AgentRunOptions {
+        events,
+        tool_concurrency,
+        all_tools,
+        model_timeout_ms,
+        model_timeout_policy,
+        force_tool,
+    } = options;
 
     // One-time capsule size check at session start
     check_capsule_health(&mv2);
+    crate::set_agent_session(session.clone());
 
     let db = open_or_create_db(&mv2)?;
 
@@ -575,6 +661,17 @@ pub(crate) fn run_agent_with_prompt(
         load_capsule_config(&db).unwrap_or_default()
     };
     let agent_cfg = config.agent.clone().unwrap_or_default();
+    disable_tools(agent_cfg.disabled_tools.clone());
+    register_custom_tools(&agent_cfg.custom_tools);
+    // Max tool calls from one assistant message run at once (1 = one at a time).
+    let tool_concurrency = tool_concurrency
+        .or(agent_cfg.tool_concurrency)
+        .unwrap_or_else(|| std::thread::available_parallelism().map(|v| v.get()).unwrap_or(4))
+        .max(1);
+    let model_timeout_ms = model_timeout_ms.or(agent_cfg.model_timeout_ms).filter(|ms| *ms > 0);
+    let model_timeout_policy = model_timeout_policy
+        .or(agent_cfg.model_timeout_policy)
+        .unwrap_or_default();
     let agent_workspace = resolve_workspace(None, &agent_cfg);
     let hook_cfg = config.hooks.clone().unwrap_or_default();
     // No wall-clock deadline for model hooks — zombie detection handles stuck processes.
@@ -744,6 +841,7 @@ pub(crate) fn run_agent_with_prompt(
             embed_cache: 4096,
             embed_no_cache: false,
             rerank_docs: 40,
+            rerank_weight: None,
             rerank_chunk_chars: 1200,
             rerank_chunk_overlap: 200,
             plan: false,
@@ -751,12 +849,25 @@ pub(crate) fn run_agent_with_prompt(
             before: None,
             after: None,
             feedback_weight: 0.15,
+            recency_halflife_days: 0.0,
+            hook_debug: false,
+            vec_debug: false,
+            plain_snippets: false,
+            raw_snippets: false,
+            snippet_ellipsis: SNIPPET_ELLIPSIS.to_string(),
+            explain: false,
+            no_skip_expansion_probe: false,
+            fast_path_margin: DEFAULT_FAST_PATH_MARGIN,
+            fast_path: false,
+            search_jobs: DEFAULT_SEARCH_JOBS,
         };
         if let Ok(pack) = build_context_pack(
             &db,
             qargs,
             agent_cfg.max_context_bytes.unwrap_or(context_max_bytes),
             false,
+            0,
+            ContextFormat::Plain,
         ) {
             if !pack.context.trim().is_empty() {
                 system_dynamic.push_str("\n\n# Memory Context\n");
@@ -783,10 +894,37 @@ pub(crate) fn run_agent_with_prompt(
         }
     }
 
+    // Tools advertised from the first step: base tools plus those relevant to the
+    // prompt (or the whole catalog with --all-tools). tool_search finds the rest.
+    let initial_tools: HashSet<String> = {
+        let catalog = tool_definitions_json();
+        let mut names: HashSet<String> =
+            base_tool_names().into_iter().filter(|n| !is_tool_disabled(n)).collect();
+        if all_tools || agent_cfg.all_tools.unwrap_or(false) {
+            names.extend(catalog.iter().filter_map(|t| t.get("name").and_then(|n| n.as_str()).map(str::to_string)));
+        } else {
+            let relevant = relevant_tool_names(
+                &catalog,
+                &names,
+                &prompt_text,
+                agent_cfg.tool_activation_min_score.unwrap_or(4),
+                agent_cfg.tool_activation_limit.unwrap_or(6),
+            );
+            if !relevant.is_empty() {
+                log_debug!("[harness] activated for this prompt: {}", relevant.join(", "));
+            }
+            names.extend(relevant);
+        }
+        if !is_tool_disabled("tool_search") {
+            names.insert("tool_search".to_string());
+        }
+        names
+    };
+
     // Inject tool capability inventory so the agent knows what it can do
     {
         let all_tools = tool_definitions_json();
-        let active_names = base_tool_names();
+        let active_names = &initial_tools;
         let discoverable: Vec<String> = all_tools.iter()
             .filter_map(|t| t.get("name").and_then(|n| n.as_str()).map(|s| s.to_string()))
             .filter(|n| !active_names.contains(n))
@@ -882,7 +1020,7 @@ pub(crate) fn run_agent_with_prompt(
                 cfgs.push(McpServerConfig {
                     name: "excalidraw".to_string(),
                     command: cmd,
-                    env: HashMap::new(),
+                    env: BTreeMap::new(),
                 });
             }
         }
@@ -893,11 +1031,15 @@ pub(crate) fn run_agent_with_prompt(
         match McpRegistry::start(&mcp_configs) {
             Ok(registry) => {
                 let mcp_tools = registry.tool_definitions();
-                full_catalog.extend(mcp_tools);
+                full_catalog.extend(
+                    mcp_tools
+                        .into_iter()
+                        .filter(|t| !t.get("name").and_then(|n| n.as_str()).is_some_and(is_tool_disabled)),
+                );
                 Some(registry)
             }
             Err(e) => {
-                eprintln!("[harness] MCP registry failed: {e}");
+                log_error!("[harness] MCP registry failed: {e}");
                 None
             }
         }
@@ -906,13 +1048,17 @@ pub(crate) fn run_agent_with_prompt(
     };
 
     let tool_map = tool_catalog_map(&full_catalog);
-    let mut active_tools = base_tool_names();
+    let mut active_tools = initial_tools.clone();
     // Add MCP tool names to active set
     if let Some(ref registry) = mcp_registry {
         for name in registry.route_map.keys() {
             active_tools.insert(name.clone());
         }
     }
+    let forced_tool = force_tool
+        .or_else(|| agent_cfg.force_first_tool.clone())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
     let mut tools = tools_from_active(&tool_map, &active_tools);
     let mut tool_results: Vec<AgentToolResult> = Vec::new();
     let should_log = log || agent_cfg.log.unwrap_or(false);
@@ -940,7 +1086,7 @@ pub(crate) fn run_agent_with_prompt(
             ts_utc: Some(Utc::now().timestamp()),
         };
         if let Err(e) = append_log_jsonl(&log_dir, &entry) {
-            eprintln!("[harness] failed to write agent log: {e}");
+            log_error!("[harness] failed to write agent log: {e}");
         }
     }
 
@@ -957,7 +1103,7 @@ pub(crate) fn run_agent_with_prompt(
             // immediately hit LEVEL 3/4 thresholds.
             drift_state.critic_history = persisted.critic_history;
             let prev_count = persisted.violations.get("critic_correction").copied().unwrap_or(0);
-            eprintln!("[drift] loaded {prev_count} persisted violations (reset to 0 for new session)");
+            log_debug!("[drift] loaded {prev_count} persisted violations (reset to 0 for new session)");
         }
     }
     let mut recent_actions: VecDeque<String> = VecDeque::with_capacity(30);
@@ -984,6 +1130,68 @@ pub(crate) fn run_agent_with_prompt(
         Some((guard.chat_id?, guard.bg_registry.clone()?))
     });
 
+    // The forced first tool runs before the model's first turn. Its result goes in
+    // as a user message rather than a synthesized assistant tool call, which the
+    // model never produced (and which extended thinking would reject).
+    if let Some(name) = forced_tool {
+        match tool_map.get(&name) {
+            Some(schema) => {
+                let call = AgentToolCall {
+                    id: "forced_first_tool".to_string(),
+                    args: forced_tool_args(schema, &prompt_text),
+                    name,
+                };
+                log_info!("[harness] running forced first tool {}", call.name);
+                if let Some(events) = events {
+                    events.emit(&AgentEvent::ToolCall { step: 0, id: &call.id, name: &call.name, args: &call.args });
+                }
+                let result = if call.name.starts_with("mcp__") {
+                    match mcp_registry.as_mut() {
+                        Some(registry) => registry.call_tool(&call.name, call.args.clone()).unwrap_or_else(|err| ToolExecution {
+                            output: format!("Tool error: {err}"),
+                            details: serde_json::json!({ "error": err }),
+                            is_error: true,
+                        }),
+                        None => ToolExecution {
+                            output: "MCP registry not initialized".to_string(),
+                            details: serde_json::json!({ "error": "no registry" }),
+                            is_error: true,
+                        },
+                    }
+                } else {
+                    execute_tool(&call.name, call.args.clone(), &mv2, &db, false, bg_registry_ref.clone())
+                        .unwrap_or_else(|err| ToolExecution {
+                            output: format!("Tool error: {err}"),
+                            details: serde_json::json!({ "error": err.to_string(), "error_kind": err.kind() }),
+                            is_error: true,
+                        })
+                };
+                let result = truncate_tool_output(result, 8000);
+                let mut tool_messages = Vec::new();
+                let (_, tools_changed) = process_tool_result(
+                    &call, result,
+                    &mut tool_results, &mut tool_messages, &mut active_tools,
+                    &mut retrieved_skills, should_log, &session, &log_dir,
+                );
+                emit_tool_result_event(events, 0, tool_results.last());
+                if tools_changed {
+                    tools = tools_from_active(&tool_map, &active_tools);
+                }
+                let output = tool_messages.pop().and_then(|m| m.content).unwrap_or_default();
+                messages.push(AgentMessage {
+                    role: "user".to_string(),
+                    content: Some(format!("[System] Result of `{}`, run before your first turn:\n{output}", call.name)),
+                    tool_calls: Vec::new(),
+                    name: None,
+                    tool_call_id: None,
+                    is_error: None,
+                    thinking_blocks: vec![],
+                });
+            }
+            None => log_warn!("[harness] forced first tool {name:?} is not in the tool catalog; ignoring it"),
+        }
+    }
+
     let mut completed = false;
     let mut current_max_steps = effective_max_steps;
     let mut step = 0;
@@ -1025,7 +1233,7 @@ pub(crate) fn run_agent_with_prompt(
                 if !steering.is_empty() {
                     let combined = steering.join("\n\n");
                     drop(p);
-                    eprintln!("[harness] injecting {} steering message(s) from user", steering.len());
+                    log_info!("[harness] injecting {} steering message(s) from user", steering.len());
                     messages.push(AgentMessage {
                         role: "user".to_string(),
                         content: Some(combined),
@@ -1046,20 +1254,23 @@ pub(crate) fn run_agent_with_prompt(
                 p.phase = "thinking".to_string();
             }
         }
+        if let Some(events) = events {
+            events.emit(&AgentEvent::Step { step, max_steps: current_max_steps });
+        }
 
         // Auto-compact when context exceeds configurable budget
         let token_estimate = estimate_tokens(&messages);
         let compact_at = compaction_budget_tokens();
         let compact_keep = keep_recent_turns().max(2);
         if token_estimate > compact_at {
-            eprintln!("[harness] context at ~{token_estimate} tokens (budget {compact_at}), compacting...");
+            log_info!("[harness] context at ~{token_estimate} tokens (budget {compact_at}), compacting...");
             match compact_messages(&mut messages, &model_spec, compact_keep) {
                 Ok(Some(goal)) => {
                     current_plan = Some(goal);
                 }
                 Ok(None) => {}
                 Err(e) => {
-                    eprintln!("[harness] compaction failed: {e}");
+                    log_error!("[harness] compaction failed: {e}");
                 }
             }
         }
@@ -1068,7 +1279,7 @@ pub(crate) fn run_agent_with_prompt(
         if opus_escalation_remaining > 0 {
             opus_escalation_remaining -= 1;
             if opus_escalation_remaining == 0 {
-                eprintln!("[harness] Opus escalation window ended, reverting to base model");
+                log_info!("[harness] Opus escalation window ended, reverting to base model");
                 model_spec = base_model_spec.clone();
             }
         }
@@ -1077,19 +1288,28 @@ pub(crate) fn run_agent_with_prompt(
             messages: messages.clone(),
             tools: tools.clone(),
             session: session.clone(),
+            protocol_version: AGENT_HOOK_PROTOCOL_VERSION,
         };
-        let message = match call_agent_hook(&model_spec, &request) {
+        if let Some(mut p) = progress.as_ref().and_then(|prog| prog.lock().ok()) {
+            p.phase = "waiting on model".to_string();
+        }
+        let message = match call_model_with_timeout(&model_spec, request, model_timeout_ms) {
             Ok(msg) => {
                 consecutive_hook_failures = 0;
                 msg
             }
-            Err(e) => {
+            Err(ModelCallError::TimedOut(e)) if model_timeout_policy == ModelTimeoutPolicy::Abort => {
+                log_error!("[harness] {e}; ending run (--model-timeout-policy abort)");
+                final_text = Some(format!("(Agent terminated: {e}.)"));
+                break;
+            }
+            Err(ModelCallError::TimedOut(e) | ModelCallError::Failed(e)) => {
                 consecutive_hook_failures += 1;
-                eprintln!(
+                log_warn!(
                     "[harness] hook failed ({consecutive_hook_failures}/{MAX_CONSECUTIVE_HOOK_FAILURES}): {e}"
                 );
                 if consecutive_hook_failures >= MAX_CONSECUTIVE_HOOK_FAILURES {
-                    eprintln!(
+                    log_error!(
                         "[harness] {MAX_CONSECUTIVE_HOOK_FAILURES} consecutive failures, ending run"
                     );
                     final_text = Some(format!(
@@ -1121,6 +1341,9 @@ pub(crate) fn run_agent_with_prompt(
                     p.text_preview = Some(content.chars().take(100).collect());
                 }
             }
+            if let Some(events) = events {
+                events.emit(&AgentEvent::Message { step, text: &content });
+            }
             // Track turns for observational memory extraction
             turns_since_fact_extract += 1;
 
@@ -1133,7 +1356,7 @@ pub(crate) fn run_agent_with_prompt(
                     ts_utc: Some(Utc::now().timestamp()),
                 };
                 if let Err(e) = append_log_jsonl(&log_dir, &entry) {
-                    eprintln!("[harness] failed to write agent log: {e}");
+                    log_error!("[harness] failed to write agent log: {e}");
                 }
             }
 
@@ -1180,6 +1403,7 @@ pub(crate) fn run_agent_with_prompt(
                             ],
                             tools: Vec::new(),
                             session: session_clone,
+                            protocol_version: AGENT_HOOK_PROTOCOL_VERSION,
                         };
                         if let Ok(response) = call_claude(&extract_request) {
                             if let Some(facts) = response.message.content {
@@ -1189,7 +1413,7 @@ pub(crate) fn run_agent_with_prompt(
                                     {
                                         let mut seen = OBSERVATION_DEDUP.lock().unwrap_or_else(|e| e.into_inner());
                                         if !seen.insert(hash) {
-                                            eprintln!("[observation-dedup] skipped duplicate: {}...", &facts.chars().take(60).collect::<String>());
+                                            log_debug!("[observation-dedup] skipped duplicate: {}...", &facts.chars().take(60).collect::<String>());
                                             return;
                                         }
                                     }
@@ -1207,21 +1431,21 @@ pub(crate) fn run_agent_with_prompt(
                                             Ok(result) => {
                                                 let decision_str = format!("{:?}", result.decision);
                                                 if result.frame_id.is_none() {
-                                                    eprintln!("[observation-consolidation] NOOP: {decision_str}");
+                                                    log_debug!("[observation-consolidation] NOOP: {decision_str}");
                                                 } else {
-                                                    eprintln!("[observation-consolidation] {decision_str}");
+                                                    log_debug!("[observation-consolidation] {decision_str}");
                                                 }
                                             }
                                             Err(e) => {
-                                                eprintln!("[observation] consolidation failed: {e}");
+                                                log_error!("[observation] consolidation failed: {e}");
                                             }
                                         }
                                         if let Err(e) = obs_db.commit() {
-                                            eprintln!("[observation] commit failed: {e}");
+                                            log_error!("[observation] commit failed: {e}");
                                         }
                                     }
                                 } else if !facts.trim().is_empty() {
-                                    eprintln!("[observation-gate] skipped: {}...", &facts.chars().take(60).collect::<String>());
+                                    log_debug!("[observation-gate] skipped: {}...", &facts.chars().take(60).collect::<String>());
                                 }
                             }
                         }
@@ -1256,6 +1480,16 @@ pub(crate) fn run_agent_with_prompt(
                 return Err("tool call is missing a name".into());
             }
         }
+        if let Some(events) = events {
+            for call in &tool_calls {
+                events.emit(&AgentEvent::ToolCall {
+                    step,
+                    id: &call.id,
+                    name: &call.name,
+                    args: &call.args,
+                });
+            }
+        }
 
         let max_tool_output = 8000; // chars (~2000 tokens)
 
@@ -1313,7 +1547,7 @@ pub(crate) fn run_agent_with_prompt(
                     Ok(result) => result,
                     Err(err) => ToolExecution {
                         output: format!("Tool error: {err}"),
-                        details: serde_json::json!({ "error": err }),
+                        details: serde_json::json!({ "error": err.to_string(), "error_kind": err.kind() }),
                         is_error: true,
                     },
                 }
@@ -1325,6 +1559,7 @@ pub(crate) fn run_agent_with_prompt(
                 &mut tool_results, &mut messages, &mut active_tools,
                 &mut retrieved_skills, should_log, &session, &log_dir,
             );
+            emit_tool_result_event(events, step, tool_results.last());
             if tools_changed {
                 tools = tools_from_active(&tool_map, &active_tools);
             }
@@ -1345,27 +1580,36 @@ pub(crate) fn run_agent_with_prompt(
             if requires_approval(&call.name, &call.args) {
                 reminder_state.approval_required_count += 1;
             }
-            let read_only_tools = ["search", "query", "get", "list", "tool_search", "skill_search", "reflect"];
+            let read_only_tools = ["search", "query", "get", "similar", "list", "tool_search", "skill_search", "reflect"];
             if read_only_tools.iter().any(|t| call.name.contains(t)) {
                 reminder_state.sequential_read_ops += 1;
             } else {
                 reminder_state.sequential_read_ops = 0;
             }
         } else {
-            // Multiple tool calls — execute in parallel (non-MCP), MCP calls sequentially
+            // Multiple tool calls — execute in parallel (non-MCP), MCP calls sequentially.
+            // Each result keeps its call index so tool_results follow the model's order.
             let (mcp_calls, regular_calls): (Vec<_>, Vec<_>) = tool_calls.iter()
-                .partition(|c| c.name.starts_with("mcp__"));
+                .enumerate()
+                .partition(|(_, c)| c.name.starts_with("mcp__"));
 
-            let mut results: Vec<(AgentToolCall, ToolExecution)> = Vec::new();
+            let mut results: Vec<(usize, AgentToolCall, ToolExecution)> = Vec::new();
 
-            // Regular tools run in a bounded worker pool.
+            // Regular tools run in a pool of up to `tool_concurrency` workers, each on a
+            // fresh capsule handle; writes take `write_lock` so they never overlap.
             if !regular_calls.is_empty() {
                 let mv2_ref = &mv2;
                 let bg_reg_ref = &bg_registry_ref;
-                let execute_regular_call = |call: &&AgentToolCall| -> (AgentToolCall, ToolExecution) {
+                let registry = tool_registry();
+                let write_lock = Mutex::new(());
+                let execute_regular_call = |(index, call): &(usize, &AgentToolCall)| -> (usize, AgentToolCall, ToolExecution) {
                     let call = *call;
+                    let _write_guard = registry
+                        .get(&call.name)
+                        .is_some_and(|t| t.is_write())
+                        .then(|| write_lock.lock().unwrap_or_else(|e| e.into_inner()));
                     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        let local_db = open_or_create_db(mv2_ref).map_err(|e| e.to_string())?;
+                        let local_db = open_or_create_db(mv2_ref).map_err(AethervaultError::store)?;
                         execute_tool(&call.name, call.args.clone(), mv2_ref, &local_db, false, bg_reg_ref.clone())
                     }));
 
@@ -1373,7 +1617,7 @@ pub(crate) fn run_agent_with_prompt(
                         Ok(Ok(r)) => r,
                         Ok(Err(err)) => ToolExecution {
                             output: format!("Tool error: {err}"),
-                            details: serde_json::json!({ "error": err }),
+                            details: serde_json::json!({ "error": err.to_string(), "error_kind": err.kind() }),
                             is_error: true,
                         },
                         Err(panic_info) => {
@@ -1384,7 +1628,7 @@ pub(crate) fn run_agent_with_prompt(
                             } else {
                                 "unknown panic".to_string()
                             };
-                            eprintln!("[harness] tool thread panicked on '{}': {msg}", call.name);
+                            log_error!("[harness] tool thread panicked on '{}': {msg}", call.name);
                             ToolExecution {
                                 output: format!(
                                     "Internal error: tool execution panicked: {msg}"
@@ -1395,24 +1639,24 @@ pub(crate) fn run_agent_with_prompt(
                         }
                     };
 
-                    (call.clone(), execution)
+                    (*index, call.clone(), execution)
                 };
 
-                let parallel_results: Vec<(AgentToolCall, ToolExecution)> = ThreadPoolBuilder::new()
-                    .num_threads(
-                        std::thread::available_parallelism()
-                            .map(|v| v.get())
-                            .unwrap_or(4)
-                            .min(regular_calls.len())
-                    )
-                    .build()
-                    .map(|pool| pool.install(|| regular_calls.par_iter().map(execute_regular_call).collect()))
-                    .unwrap_or_else(|_| regular_calls.iter().map(execute_regular_call).collect());
+                let workers = tool_concurrency.min(regular_calls.len());
+                let parallel_results: Vec<(usize, AgentToolCall, ToolExecution)> = if workers <= 1 {
+                    regular_calls.iter().map(execute_regular_call).collect()
+                } else {
+                    ThreadPoolBuilder::new()
+                        .num_threads(workers)
+                        .build()
+                        .map(|pool| pool.install(|| regular_calls.par_iter().map(execute_regular_call).collect()))
+                        .unwrap_or_else(|_| regular_calls.iter().map(execute_regular_call).collect())
+                };
                 results.extend(parallel_results);
             }
 
             // MCP tools run sequentially (they share a mutable registry)
-            for call in &mcp_calls {
+            for (index, call) in &mcp_calls {
                 let result = match mcp_registry.as_mut() {
                     Some(registry) => match registry.call_tool(&call.name, call.args.clone()) {
                         Ok(r) => r,
@@ -1428,16 +1672,18 @@ pub(crate) fn run_agent_with_prompt(
                         is_error: true,
                     },
                 };
-                results.push(((*call).clone(), result));
+                results.push((*index, (*call).clone(), result));
             }
+            results.sort_by_key(|(index, _, _)| *index);
 
-            for (call, result) in results {
+            for (_, call, result) in results {
                 let result = truncate_tool_output(result, max_tool_output);
                 let (is_error, tools_changed) = process_tool_result(
                     &call, result,
                     &mut tool_results, &mut messages, &mut active_tools,
                     &mut retrieved_skills, should_log, &session, &log_dir,
                 );
+                emit_tool_result_event(events, step, tool_results.last());
                 if tools_changed {
                     tools = tools_from_active(&tool_map, &active_tools);
                 }
@@ -1614,7 +1860,7 @@ pub(crate) fn run_agent_with_prompt(
                 // Model escalation: swap to Opus for next N steps when critic fires
                 if let Some(ref opus_spec) = opus_escalation_spec {
                     if opus_escalation_remaining == 0 {
-                        eprintln!("[harness] critic fired — escalating to Opus for {opus_escalation_steps} steps");
+                        log_warn!("[harness] critic fired — escalating to Opus for {opus_escalation_steps} steps");
                         model_spec = opus_spec.clone();
                         opus_escalation_remaining = opus_escalation_steps;
                     }
@@ -1638,7 +1884,7 @@ pub(crate) fn run_agent_with_prompt(
                     }
                     5..=6 => {
                         // Level 3: Log severe warning
-                        eprintln!("[critic] LEVEL 3 escalation: {violation_count} violations — consider tool restriction");
+                        log_warn!("[critic] LEVEL 3 escalation: {violation_count} violations — consider tool restriction");
                         messages.push(AgentMessage {
                             role: "user".to_string(),
                             content: Some(format!("[SEVERE WARNING] {violation_count} grounding violations this session. STOP making claims not supported by tool output. Before EVERY response, re-read the most recent tool output and ONLY report what it literally says.")),
@@ -1651,12 +1897,12 @@ pub(crate) fn run_agent_with_prompt(
                         // Enforce: reduce remaining step budget by 1/3 (was halved — too aggressive)
                         let remaining = current_max_steps.saturating_sub(step);
                         current_max_steps = step + (remaining * 2 / 3).max(6);
-                        eprintln!("[critic] LEVEL 3 enforcement: step budget reduced to {current_max_steps} (was {})", step + remaining);
+                        log_warn!("[critic] LEVEL 3 enforcement: step budget reduced to {current_max_steps} (was {})", step + remaining);
                     }
                     _ => {
                         // Level 4: Graceful wind-down instead of hard kill.
                         // Give the agent enough steps to write partial results.
-                        eprintln!("[critic] LEVEL 4 escalation: {violation_count} violations — winding down gracefully");
+                        log_warn!("[critic] LEVEL 4 escalation: {violation_count} violations — winding down gracefully");
                         messages.push(AgentMessage {
                             role: "user".to_string(),
                             content: Some(format!("[CRITICAL — GRACEFUL WIND-DOWN] {violation_count} grounding violations. You have 6 steps remaining. IMMEDIATELY:\n1. Write any partial results to disk (files the user requested).\n2. Summarize what you actually accomplished vs. what failed.\n3. Do NOT make new claims — only report verified facts from tool outputs.\nAfter these 6 steps, the session will end.")),
@@ -1668,7 +1914,7 @@ pub(crate) fn run_agent_with_prompt(
                         });
                         // Enforce: allow 6 steps for graceful output (was 3 — too aggressive)
                         current_max_steps = step + 6;
-                        eprintln!("[critic] LEVEL 4 enforcement: graceful wind-down in 6 steps (step={step}, max={current_max_steps})");
+                        log_warn!("[critic] LEVEL 4 enforcement: graceful wind-down in 6 steps (step={step}, max={current_max_steps})");
                     }
                 }
             }
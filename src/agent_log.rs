@@ -1,8 +1,9 @@
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use chrono::Utc;
-use crate::AgentLogEntry;
+use crate::{AgentEvent, AgentLogEntry};
 
 pub(crate) fn log_dir_path(workspace: &Path) -> PathBuf {
     workspace.join("logs")
@@ -78,3 +79,70 @@ pub(crate) fn load_session_logs(
     }
     collected
 }
+
+/// NDJSON sink for `agent --events-file`. Each event is written as one line
+/// and flushed immediately so the file can be tailed while the agent runs.
+pub(crate) struct AgentEventSink {
+    file: Mutex<fs::File>,
+}
+
+impl AgentEventSink {
+    pub(crate) fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub(crate) fn emit(&self, event: &AgentEvent) {
+        let mut line = match serde_json::to_value(event) {
+            Ok(value) => value,
+            Err(e) => {
+                log_warn!("[harness] failed to encode agent event: {e}");
+                return;
+            }
+        };
+        line["ts_utc"] = serde_json::json!(Utc::now().timestamp());
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if let Err(e) = writeln!(file, "{line}").and_then(|_| file.flush()) {
+            log_warn!("[harness] failed to write agent event: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_sink_writes_one_flushed_line_per_event() {
+        let dir = std::env::temp_dir().join(format!("aethervault_events_{}", std::process::id()));
+        let path = dir.join("events").join("run.ndjson");
+        let sink = AgentEventSink::create(&path).unwrap();
+        let args = serde_json::json!({"query": "tea"});
+        sink.emit(&AgentEvent::Step { step: 0, max_steps: 4 });
+        sink.emit(&AgentEvent::ToolCall { step: 0, id: "t1", name: "query", args: &args });
+        // Readable before the sink is dropped: every event is flushed.
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "step");
+        assert_eq!(lines[1]["event"], "tool_call");
+        assert_eq!(lines[1]["args"]["query"], "tea");
+        assert!(lines[1]["ts_utc"].is_i64());
+        drop(sink);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,197 @@
+//! Library entry point for the hybrid query pipeline, for callers that want
+//! `query`/`context` results without going through the CLI.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::memory_db::{MemoryDb, PutOptions};
+use crate::{
+    build_context_pack, execute_query, AethervaultError, ContextFormat, open_or_create_db, ContextPack, QueryArgs,
+    QueryResponse,
+    DEFAULT_FAST_PATH_MARGIN, DEFAULT_SEARCH_JOBS, SNIPPET_ELLIPSIS,
+};
+
+/// Options for [`HybridSearch::query`]. `Default` matches the CLI defaults,
+/// which also fill any field missing from a deserialized request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub query: String,
+    /// Restrict to one collection (`aether://<collection>/...`).
+    pub collection: Option<String>,
+    pub limit: usize,
+    pub snippet_chars: usize,
+    /// Skip query expansion entirely.
+    pub no_expand: bool,
+    pub max_expansions: usize,
+    /// Skip the Qdrant vector lane even when `QDRANT_URL` is set.
+    pub no_vector: bool,
+    /// `local`, `hook` or `none`.
+    pub rerank: String,
+    /// Fixed rerank share (0-1) of the blended score; `None` trusts RRF more near the top.
+    pub rerank_weight: Option<f32>,
+    /// Point-in-time query: `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM`, or `#N` to pin to frame id N.
+    pub asof: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub feedback_weight: f32,
+    /// Boost newer frames with this half-life in days (0 = off).
+    pub recency_halflife_days: f32,
+    /// Max concurrent lane searches (lexical expansions and vector queries).
+    pub search_jobs: usize,
+    /// Return a dominant lexical hit without fusion or rerank.
+    pub fast_path: bool,
+    /// Strip Markdown markup from snippets (raw text kept in `raw_snippet`).
+    pub plain_snippets: bool,
+    /// Keep snippets as extracted: no whitespace cleanup or cut marker.
+    pub raw_snippets: bool,
+    /// Shown where a snippet was cut short.
+    pub snippet_ellipsis: String,
+}
+
+impl SearchOptions {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn to_query_args(&self) -> QueryArgs {
+        QueryArgs {
+            raw_query: self.query.clone(),
+            collection: self.collection.clone(),
+            limit: self.limit,
+            snippet_chars: self.snippet_chars,
+            no_expand: self.no_expand,
+            max_expansions: self.max_expansions,
+            expand_hook: None,
+            expand_hook_timeout_ms: u64::MAX,
+            no_vector: self.no_vector,
+            rerank: self.rerank.clone(),
+            rerank_hook: None,
+            rerank_hook_timeout_ms: u64::MAX,
+            rerank_hook_full_text: false,
+            embed_model: None,
+            embed_cache: 4096,
+            embed_no_cache: false,
+            rerank_docs: 40,
+            rerank_weight: self.rerank_weight,
+            rerank_chunk_chars: 1200,
+            rerank_chunk_overlap: 200,
+            plan: false,
+            asof: self.asof.clone(),
+            before: self.before.clone(),
+            after: self.after.clone(),
+            feedback_weight: self.feedback_weight,
+            recency_halflife_days: self.recency_halflife_days,
+            hook_debug: false,
+            vec_debug: false,
+            plain_snippets: self.plain_snippets,
+            raw_snippets: self.raw_snippets,
+            snippet_ellipsis: self.snippet_ellipsis.clone(),
+            explain: false,
+            no_skip_expansion_probe: false,
+            fast_path_margin: DEFAULT_FAST_PATH_MARGIN,
+            fast_path: self.fast_path,
+            search_jobs: self.search_jobs,
+        }
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            collection: None,
+            limit: 10,
+            snippet_chars: 300,
+            no_expand: false,
+            max_expansions: 2,
+            no_vector: false,
+            rerank: "local".to_string(),
+            rerank_weight: None,
+            asof: None,
+            before: None,
+            after: None,
+            feedback_weight: 0.15,
+            recency_halflife_days: 0.0,
+            search_jobs: DEFAULT_SEARCH_JOBS,
+            fast_path: false,
+            plain_snippets: false,
+            raw_snippets: false,
+            snippet_ellipsis: SNIPPET_ELLIPSIS.to_string(),
+        }
+    }
+}
+
+/// Hybrid retrieval (lexical + optional vector lanes, RRF fusion, rerank) over
+/// one capsule. Hooks and thresholds come from the capsule's own config, as
+/// they do for `aethervault query`.
+///
+/// ```
+/// use aethervault::{HybridSearch, SearchOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("aethervault_doctest_{}", std::process::id()));
+/// std::fs::create_dir_all(&dir)?;
+/// let path = dir.join("notes.mv2");
+/// let _ = std::fs::remove_file(&path);
+///
+/// let search = HybridSearch::open(&path)?;
+/// search.put_text("aether://notes/rust.md", Some("Rust"), "Ownership makes memory safety explicit.")?;
+/// search.put_text("aether://notes/tea.md", Some("Tea"), "Steep green tea for two minutes.")?;
+///
+/// let response = search.query(&SearchOptions::new("ownership"))?;
+/// assert_eq!(response.results[0].uri, "aether://notes/rust.md");
+///
+/// let pack = search.context(&SearchOptions::new("green tea"), 4096, false)?;
+/// assert!(pack.context.contains("aether://notes/tea.md"));
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct HybridSearch {
+    db: MemoryDb,
+}
+
+impl HybridSearch {
+    /// Open a capsule, creating an empty one if `path` does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AethervaultError> {
+        Ok(Self {
+            db: open_or_create_db(path.as_ref()).map_err(AethervaultError::store)?,
+        })
+    }
+
+    /// Store `text` under `uri`, superseding any earlier frame with that URI.
+    /// Returns the new frame id.
+    pub fn put_text(&self, uri: &str, title: Option<&str>, text: &str) -> Result<u64, AethervaultError> {
+        let options = PutOptions {
+            uri: Some(uri.to_string()),
+            title: title.map(|t| t.to_string()),
+            kind: Some("text/markdown".to_string()),
+            ..PutOptions::default()
+        };
+        let id = self
+            .db
+            .put_bytes_with_options(text.as_bytes(), options)
+            .map_err(AethervaultError::Store)?;
+        self.db.commit().map_err(AethervaultError::Store)?;
+        Ok(id)
+    }
+
+    /// Run the full query pipeline and return ranked results plus the plan.
+    pub fn query(&self, options: &SearchOptions) -> Result<QueryResponse, AethervaultError> {
+        execute_query(&self.db, options.to_query_args())
+    }
+
+    /// Run a query and pack the results into at most `max_bytes` of context.
+    /// With `full`, whole frame text is used instead of snippets.
+    pub fn context(
+        &self,
+        options: &SearchOptions,
+        max_bytes: usize,
+        full: bool,
+    ) -> Result<ContextPack, AethervaultError> {
+        build_context_pack(&self.db, options.to_query_args(), max_bytes, full, 0, ContextFormat::Plain)
+    }
+}
@@ -0,0 +1,2423 @@
+// The CLI dispatcher reaches into every module through the crate-root re-exports.
+use super::*;
+
+// External crate imports used directly in run_cli()
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::memory_db::{Frame, FrameId, FrameStatus, MemoryDb, PutOptions, SearchRequest};
+use chrono::Utc;
+use clap::Parser;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct ArchiveSummary {
+    source: String,
+    target: String,
+    before: String,
+    collection: String,
+    scanned: usize,
+    eligible: usize,
+    archived: usize,
+    deleted: usize,
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DedupSummary {
+    source: String,
+    scanned: usize,
+    unique_uris: usize,
+    duplicates_removed: usize,
+    keep_versions: usize,
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct StatsSummary {
+    source: String,
+    total_frames: u64,
+    active_frames: u64,
+    by_collection: Vec<(String, usize)>,
+    by_age_days: Vec<(String, usize)>,
+    by_size: Vec<(String, usize)>,
+    duplicate_uris: usize,
+    duplicate_frames: usize,
+    total_breakdown: Vec<(String, u64)>,
+}
+
+fn frame_collection_name(frame: &Frame) -> String {
+    if let Some(track) = frame.track.as_deref() {
+        let normalized = normalize_collection(track);
+        if !normalized.is_empty() {
+            return normalized;
+        }
+    }
+
+    let Some(uri) = frame.uri.as_deref() else {
+        return "<no-uri>".to_string();
+    };
+
+    let Some(rest) = uri.strip_prefix("aethervault://") else {
+        return "<non-aethervault-uri>".to_string();
+    };
+    rest
+        .split('/')
+        .next()
+        .filter(|collection| !collection.is_empty())
+        .map(normalize_collection)
+        .unwrap_or_else(|| "<invalid-uri>".to_string())
+}
+
+fn frame_matches_collection(frame: &Frame, collection: &str) -> bool {
+    let expected = normalize_collection(collection);
+    if frame.track.as_deref() == Some(expected.as_str()) {
+        return true;
+    }
+
+    frame
+        .uri
+        .as_deref()
+        .is_some_and(|uri| uri.starts_with(&scope_prefix(&expected)))
+}
+
+/// Copy the active frames of `collection` into `target`, oldest first.
+/// Parent links are remapped to the new ids, or dropped when the parent
+/// is not part of the export. Returns the number of frames written.
+fn export_collection(
+    source: &MemoryDb,
+    target: &MemoryDb,
+    collection: &str,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let prefix = scope_prefix(collection);
+    let mut new_ids: HashMap<FrameId, FrameId> = HashMap::new();
+    for frame_id in source.collect_active_frame_ids(Some(&prefix)) {
+        let frame = source.frame_by_id(frame_id).map_err(Box::<dyn std::error::Error>::from)?;
+        if !frame.uri.as_deref().is_some_and(|uri| uri.starts_with(&prefix)) {
+            continue;
+        }
+        let parent_id = frame.parent_id.and_then(|id| new_ids.get(&id).copied());
+        let new_id = copy_frame_to_archive(source, target, &frame, parent_id)?;
+        new_ids.insert(frame.id, new_id);
+    }
+    target.commit().map_err(Box::<dyn std::error::Error>::from)?;
+    Ok(new_ids.len())
+}
+
+/// Active frames at `from` (or, with `prefix`, under it) paired with their new
+/// URIs. Fails if a destination is already taken, unless `force`.
+fn plan_uri_moves(
+    db: &MemoryDb,
+    from: &str,
+    to: &str,
+    prefix: bool,
+    force: bool,
+) -> Result<Vec<(Frame, String)>, Box<dyn std::error::Error>> {
+    if from == to {
+        return Err("source and destination are the same".into());
+    }
+    let frames = if prefix {
+        db.collect_active_frame_ids(Some(from))
+            .into_iter()
+            .filter_map(|id| db.frame_by_id(id).ok())
+            .filter(|frame| frame.uri.as_deref().is_some_and(|uri| uri.starts_with(from)))
+            .collect()
+    } else {
+        vec![db.frame_by_uri(from).map_err(Box::<dyn std::error::Error>::from)?]
+    };
+    let moves: Vec<(Frame, String)> = frames
+        .into_iter()
+        .map(|frame| {
+            let uri = frame.uri.as_deref().unwrap_or_default();
+            let new_uri = format!("{to}{}", &uri[from.len()..]);
+            (frame, new_uri)
+        })
+        .collect();
+    if !force {
+        let moving: std::collections::HashSet<&str> =
+            moves.iter().filter_map(|(frame, _)| frame.uri.as_deref()).collect();
+        let taken: Vec<&str> = moves
+            .iter()
+            .map(|(_, new_uri)| new_uri.as_str())
+            .filter(|new_uri| !moving.contains(new_uri) && db.frame_by_uri(new_uri).is_ok())
+            .collect();
+        if !taken.is_empty() {
+            return Err(format!("destination already exists (use --force to replace): {}", taken.join(", ")).into());
+        }
+    }
+    Ok(moves)
+}
+
+/// Re-put each frame under its new URI with the same fields and timestamp,
+/// then mark the original deleted. Returns the new frame ids.
+fn apply_uri_moves(db: &MemoryDb, moves: &[(Frame, String)]) -> Result<Vec<FrameId>, Box<dyn std::error::Error>> {
+    let mut new_ids: HashMap<FrameId, FrameId> = HashMap::new();
+    let mut batch = db.write_batch(0).map_err(Box::<dyn std::error::Error>::from)?;
+    for (frame, new_uri) in moves {
+        let moved = Frame {
+            uri: Some(new_uri.clone()),
+            ..frame.clone()
+        };
+        let parent_id = frame.parent_id.map(|id| new_ids.get(&id).copied().unwrap_or(id));
+        let new_id = copy_frame_to_archive(db, db, &moved, parent_id)?;
+        db.delete_frame(frame.id).map_err(Box::<dyn std::error::Error>::from)?;
+        new_ids.insert(frame.id, new_id);
+        batch.record().map_err(Box::<dyn std::error::Error>::from)?;
+    }
+    batch.finish().map_err(Box::<dyn std::error::Error>::from)?;
+    Ok(moves.iter().map(|(frame, _)| new_ids[&frame.id]).collect())
+}
+
+fn frame_age_bucket(age_days: i64) -> String {
+    if age_days < 0 {
+        return "future".to_string();
+    }
+    if age_days <= 1 {
+        return "0-1 day".to_string();
+    }
+    if age_days <= 7 {
+        return "2-7 days".to_string();
+    }
+    if age_days <= 30 {
+        return "8-30 days".to_string();
+    }
+    if age_days <= 90 {
+        return "31-90 days".to_string();
+    }
+    if age_days <= 365 {
+        return "91-365 days".to_string();
+    }
+    "> 365 days".to_string()
+}
+
+fn frame_size_bucket(size_bytes: u64) -> String {
+    if size_bytes < 1_024 {
+        return "<1 KB".to_string();
+    }
+    if size_bytes < 10 * 1_024 {
+        return "1-10 KB".to_string();
+    }
+    if size_bytes < 100 * 1_024 {
+        return "10-100 KB".to_string();
+    }
+    if size_bytes < 1_024 * 1_024 {
+        return "100 KB-1 MB".to_string();
+    }
+    if size_bytes < 10 * 1_024 * 1_024 {
+        return "1-10 MB".to_string();
+    }
+    "10 MB+".to_string()
+}
+
+fn to_sorted_stats(map: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut entries: Vec<(String, usize)> = map.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn copy_frame_to_archive(
+    source: &MemoryDb,
+    archive: &MemoryDb,
+    frame: &Frame,
+    parent_id: Option<FrameId>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let payload = source.frame_canonical_payload(frame.id).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+    let mut options = PutOptions::default();
+    options.timestamp = Some(frame.timestamp);
+    options.track = frame.track.clone();
+    options.kind = frame.kind.clone();
+    options.uri = frame.uri.clone();
+    options.title = frame.title.clone();
+    options.metadata = frame.metadata.clone();
+    options.search_text = frame.search_text.clone();
+    options.tags = frame.tags.clone();
+    options.labels = frame.labels.clone();
+    options.extra_metadata = frame.extra_metadata.clone();
+    options.role = frame.role;
+    options.parent_id = parent_id;
+    let id = archive.put_bytes_with_options(&payload, options).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+    Ok(id)
+}
+
+fn print_result_groups(
+    query: String,
+    plan: QueryPlan,
+    warnings: Vec<String>,
+    groups: &[ResultGroup],
+    output: OutputFormat,
+    color: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match output {
+        OutputFormat::Json => {
+            #[derive(Serialize)]
+            struct GroupedResponse<'a> {
+                query: String,
+                plan: QueryPlan,
+                warnings: Vec<String>,
+                groups: &'a [ResultGroup],
+            }
+            let payload = GroupedResponse {
+                query,
+                plan,
+                warnings,
+                groups,
+            };
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        }
+        OutputFormat::Ndjson | OutputFormat::Csv => {
+            #[derive(Serialize)]
+            struct GroupedRow<'a> {
+                group: &'a str,
+                #[serde(flatten)]
+                result: &'a QueryResult,
+            }
+            let rows: Vec<GroupedRow> = groups
+                .iter()
+                .flat_map(|g| g.results.iter().map(|result| GroupedRow { group: &g.key, result }))
+                .collect();
+            print!("{}", format_rows(&rows, output)?);
+        }
+        OutputFormat::Text => {
+            if groups.is_empty() {
+                println!("No results found.");
+            }
+            for group in groups {
+                println!("{} ({})", group.key, group.results.len());
+                let rows: Vec<ResultRow> = group
+                    .results
+                    .iter()
+                    .map(|r| ResultRow {
+                        rank: r.rank,
+                        score: Some(r.score),
+                        uri: &r.uri,
+                        title: r.title.as_deref().unwrap_or_default(),
+                        snippet: &r.snippet,
+                        why: r.why.as_deref(),
+                    })
+                    .collect();
+                print!("{}", render_result_table(&rows, color));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One line of `diff --output ndjson|csv`.
+#[derive(Serialize)]
+struct DiffRow<'a> {
+    change: &'static str,
+    uri: &'a str,
+    left_frame_id: Option<u64>,
+    right_frame_id: Option<u64>,
+    left_checksum: Option<&'a str>,
+    right_checksum: Option<&'a str>,
+}
+
+fn diff_rows(report: &DiffReport) -> Vec<DiffRow<'_>> {
+    let mut rows = Vec::new();
+    for frame in &report.only_left {
+        rows.push(DiffRow {
+            change: "only_left",
+            uri: &frame.uri,
+            left_frame_id: Some(frame.frame_id),
+            right_frame_id: None,
+            left_checksum: Some(&frame.checksum),
+            right_checksum: None,
+        });
+    }
+    for frame in &report.only_right {
+        rows.push(DiffRow {
+            change: "only_right",
+            uri: &frame.uri,
+            left_frame_id: None,
+            right_frame_id: Some(frame.frame_id),
+            left_checksum: None,
+            right_checksum: Some(&frame.checksum),
+        });
+    }
+    for change in &report.changed {
+        rows.push(DiffRow {
+            change: "changed",
+            uri: &change.uri,
+            left_frame_id: Some(change.left.frame_id),
+            right_frame_id: Some(change.right.frame_id),
+            left_checksum: Some(&change.left.checksum),
+            right_checksum: Some(&change.right.checksum),
+        });
+    }
+    rows
+}
+
+/// Parse the command line and run the selected subcommand. The `aethervault`
+/// binary is a thin wrapper around this.
+pub fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    crate::logging::init_logging(cli.verbose, cli.quiet);
+    let color = use_color(cli.no_color);
+    // The global `-q`; doctor/compact used to take their own `--quiet`.
+    let quiet = cli.quiet > 0;
+    if cli.verify {
+        crate::memory_db::set_verify_reads(true);
+    }
+    if let Some(ms) = cli.lock_timeout_ms {
+        crate::memory_db::set_lock_timeout_ms(ms);
+    }
+
+    match cli.command {
+        Command::Init { mv2 } => {
+            if mv2.exists() {
+                log_error!("Refusing to overwrite existing file: {}", mv2.display());
+                std::process::exit(2);
+            }
+            let _ = open_or_create_db(&mv2)?;
+            println!("Created {}", mv2.display());
+            Ok(())
+        }
+
+        Command::Ingest {
+            mv2,
+            collection,
+            root,
+            exts,
+            dry_run,
+            prune,
+            excludes,
+            no_ignore,
+            binary,
+            compress,
+            watch,
+            debounce_ms,
+        } => {
+            let store_binary = match binary.as_str() {
+                "skip" => false,
+                "store" => true,
+                other => return Err(format!("--binary must be skip or store, got {other:?}").into()),
+            };
+            if watch && dry_run {
+                return Err("--watch cannot be combined with --dry-run".into());
+            }
+            #[cfg(not(feature = "watch"))]
+            if watch {
+                let _ = debounce_ms;
+                return Err("--watch needs a build with the `watch` feature (cargo build --features watch)".into());
+            }
+            #[cfg(not(feature = "compress"))]
+            if compress {
+                return Err("--compress needs a build with the `compress` feature (cargo build --features compress)".into());
+            }
+            let root = root.canonicalize().unwrap_or(root);
+            if !root.exists() {
+                log_error!("Root does not exist: {}", root.display());
+                std::process::exit(2);
+            }
+
+            let db = open_or_create_db(&mv2)?;
+            let includes = load_capsule_config(&db)
+                .and_then(|config| config.collections.get(&collection).map(|c| c.globs.clone()))
+                .unwrap_or_default();
+            let opts = IngestOptions {
+                includes: glob_matcher(&root, &includes)?,
+                excludes: glob_matcher(&root, &excludes)?,
+                collection,
+                root,
+                exts,
+                no_ignore,
+                store_binary,
+                compress,
+                dry_run,
+            };
+
+            let stats = ingest_tree(&db, &opts)?;
+            let mut summary = stats.summary();
+            if prune {
+                // A mistyped --root would otherwise look like every file was deleted.
+                if stats.scanned == 0 {
+                    return Err(format!(
+                        "refusing to prune: no ingestable files under {}",
+                        opts.root.display()
+                    )
+                    .into());
+                }
+                let stale: Vec<_> = db
+                    .ingested_frames(&scope_prefix(&opts.collection))
+                    .into_iter()
+                    .filter(|(_, uri)| !stats.seen_uris.contains(uri))
+                    .collect();
+                for (id, uri) in &stale {
+                    if dry_run {
+                        println!("would prune {uri}");
+                    } else {
+                        db.delete_frame(*id)?;
+                    }
+                }
+                summary.push_str(&format!(" prune={}", stale.len()));
+            }
+
+            if dry_run {
+                println!("Dry run: {summary}");
+                return Ok(());
+            }
+
+            db.commit().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            println!("Done: {summary}");
+            #[cfg(feature = "watch")]
+            if watch {
+                io::stdout().flush()?;
+                watch_ingest(&db, &opts, prune, std::time::Duration::from_millis(debounce_ms))?;
+            }
+            Ok(())
+        }
+
+        Command::Put {
+            mv2,
+            uri,
+            collection,
+            path,
+            title,
+            track,
+            kind,
+            text,
+            file,
+            compress,
+            json,
+        } => {
+            #[cfg(not(feature = "compress"))]
+            if compress {
+                return Err("--compress needs a build with the `compress` feature (cargo build --features compress)".into());
+            }
+            let payload = if let Some(file) = file {
+                fs::read(file)?
+            } else if let Some(text) = text {
+                text.into_bytes()
+            } else {
+                return Err("put requires --text or --file".into());
+            };
+
+            let uri = if let Some(uri) = uri {
+                uri
+            } else if let (Some(collection), Some(path)) = (collection, path) {
+                uri_for_path(&collection, Path::new(&path))
+            } else {
+                return Err("put requires --uri or --collection + --path".into());
+            };
+
+            let inferred_title = uri
+                .split('/')
+                .next_back()
+                .filter(|s| !s.is_empty())
+                .unwrap_or(&uri)
+                .to_string();
+
+            let mut options = PutOptions::default();
+            options.uri = Some(uri.clone());
+            options.title = Some(title.unwrap_or(inferred_title));
+            options.track = track;
+            options.kind = kind;
+            options.compress = compress;
+            if let Ok(text) = String::from_utf8(payload.clone()) {
+                options.search_text = Some(text);
+            }
+
+            let db = open_or_create_db(&mv2)?;
+            let frame_id = db.put_bytes_with_options(&payload, options).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            db.commit().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+
+            if json {
+                let response = serde_json::json!({
+                    "frame_id": frame_id,
+                    "uri": uri
+                });
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            } else {
+                println!("Added frame #{frame_id} {}", uri);
+            }
+            Ok(())
+        }
+
+        Command::Search {
+            mv2,
+            query,
+            limit,
+            collection,
+            snippet_chars,
+            plain_snippets,
+            snippet_ellipsis,
+            raw_snippets,
+            output,
+            json,
+        } => {
+            let output = output.resolve(json);
+            let (mv2, query) = mv2_and_operand(mv2, query, "QUERY")?;
+            let db = open_or_create_db(&mv2)?;
+            let scope = collection.as_deref().map(scope_prefix);
+
+            let request = SearchRequest {
+                query: query.clone(),
+                top_k: limit,
+                snippet_chars,
+                scope,
+                temporal: None,
+                as_of_frame: None,
+                as_of_ts: None,
+            };
+
+            let mut response = db.search(request).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            if plain_snippets {
+                for hit in &mut response.hits {
+                    let plain = strip_markdown(&hit.text);
+                    hit.raw_text = Some(std::mem::replace(&mut hit.text, plain));
+                }
+            }
+            if !raw_snippets {
+                for hit in &mut response.hits {
+                    hit.text = tidy_snippet(&hit.text, snippet_chars, &snippet_ellipsis);
+                }
+            }
+
+            match output {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&response)?);
+                    return Ok(());
+                }
+                OutputFormat::Ndjson | OutputFormat::Csv => {
+                    print!("{}", format_rows(&response.hits, output)?);
+                    return Ok(());
+                }
+                OutputFormat::Text => {}
+            }
+
+            let rows: Vec<ResultRow> = response
+                .hits
+                .iter()
+                .map(|hit| ResultRow {
+                    rank: hit.rank,
+                    score: hit.score,
+                    uri: &hit.uri,
+                    title: hit.title.as_deref().unwrap_or_default(),
+                    snippet: &hit.text,
+                    why: None,
+                })
+                .collect();
+            print!("{}", render_result_table(&rows, color));
+
+            Ok(())
+        }
+
+        Command::Similar {
+            mv2,
+            id,
+            limit,
+            collection,
+            snippet_chars,
+            output,
+        } => {
+            let (mv2, id) = mv2_and_operand(mv2, id, "ID")?;
+            let db = open_or_create_db(&mv2)?;
+            let response = find_similar(&db, &id, limit, collection.as_deref(), snippet_chars)?;
+
+            match output {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&response)?);
+                    return Ok(());
+                }
+                OutputFormat::Ndjson | OutputFormat::Csv => {
+                    print!("{}", format_rows(&response.hits, output)?);
+                    return Ok(());
+                }
+                OutputFormat::Text => {}
+            }
+
+            for warning in &response.warnings {
+                log_warn!("{warning}");
+            }
+            if response.method == "lexical" {
+                log_info!("Similar to {} via terms: {}", response.source_uri, response.terms.join(" "));
+            }
+            let rows: Vec<ResultRow> = response
+                .hits
+                .iter()
+                .map(|hit| ResultRow {
+                    rank: hit.rank,
+                    score: hit.score,
+                    uri: &hit.uri,
+                    title: hit.title.as_deref().unwrap_or_default(),
+                    snippet: &hit.text,
+                    why: None,
+                })
+                .collect();
+            print!("{}", render_result_table(&rows, color));
+
+            Ok(())
+        }
+
+        Command::Query {
+            mv2,
+            query,
+            limit,
+            collection,
+            snippet_chars,
+            no_expand,
+            max_expansions,
+            expand_hook,
+            expand_hook_timeout_ms,
+            no_vector,
+            rerank,
+            rerank_hook,
+            rerank_hook_timeout_ms,
+            rerank_hook_full_text,
+            embed_model,
+            embed_cache,
+            embed_no_cache,
+            rerank_docs,
+            rerank_weight,
+            rerank_chunk_chars,
+            rerank_chunk_overlap,
+            output,
+            json,
+            files,
+            group_by,
+            per_group,
+            plan,
+            log,
+            read_only,
+            asof,
+            before,
+            after,
+            feedback_weight,
+            recency_halflife_days,
+            search_jobs,
+            fast_path,
+            fast_path_margin,
+            no_skip_expansion_probe,
+            hook_debug,
+            vec_debug,
+            explain,
+            plain_snippets,
+            snippet_ellipsis,
+            raw_snippets,
+        } => {
+            let output = output.resolve(json);
+            let (mv2, query) = mv2_and_operand(mv2, query, "QUERY")?;
+            let db = if read_only {
+                open_db_read_only(&mv2)?
+            } else {
+                open_or_create_db(&mv2)?
+            };
+
+            let args = QueryArgs {
+                raw_query: query.clone(),
+                collection,
+                limit,
+                snippet_chars,
+                no_expand,
+                max_expansions,
+                expand_hook,
+                expand_hook_timeout_ms,
+                no_vector,
+                rerank,
+                rerank_hook,
+                rerank_hook_timeout_ms,
+                rerank_hook_full_text,
+                embed_model,
+                embed_cache,
+                embed_no_cache,
+                rerank_docs,
+                rerank_weight,
+                rerank_chunk_chars,
+                rerank_chunk_overlap,
+                plan,
+                asof,
+                before,
+                after,
+                feedback_weight,
+                recency_halflife_days,
+                hook_debug,
+                vec_debug,
+                plain_snippets,
+                raw_snippets,
+                snippet_ellipsis,
+                explain,
+                no_skip_expansion_probe,
+                fast_path_margin,
+                fast_path,
+                search_jobs,
+            };
+
+            let response = execute_query(&db, args)?;
+
+            if log {
+                #[derive(Serialize)]
+                struct QueryLog<'a> {
+                    query: &'a str,
+                    plan: &'a QueryPlan,
+                    results: &'a [QueryResult],
+                }
+
+                let log_payload = QueryLog {
+                    query: &response.query,
+                    plan: &response.plan,
+                    results: &response.results,
+                };
+                let bytes = serde_json::to_vec(&log_payload)?;
+                let ts = Utc::now().timestamp();
+                let hash = blake3_hash(&bytes);
+                let uri = format!("aethervault://query-log/{ts}-{}", hash.to_hex());
+                let mut options = PutOptions::default();
+                options.uri = Some(uri);
+                options.title = Some("aethervault query log".to_string());
+                options.kind = Some("application/json".to_string());
+                options.track = Some("aethervault.query".to_string());
+                options.search_text = Some(response.plan.cleaned_query.clone());
+                db.put_bytes_with_options(&bytes, options).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                db.commit().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+
+            if !response.warnings.is_empty() && output == OutputFormat::Text {
+                for warning in &response.warnings {
+                    log_warn!("Warning: {warning}");
+                }
+            }
+
+            if let Some(group_by) = group_by {
+                let QueryResponse {
+                    query,
+                    plan,
+                    warnings,
+                    results,
+                    ..
+                } = response;
+                let groups = group_results(results, per_group, |r| {
+                    let key = match group_by {
+                        GroupBy::Collection => uri_collection(&r.uri),
+                        GroupBy::Track => db
+                            .frame_by_id(r.frame_id)
+                            .ok()
+                            .and_then(|frame| frame.track)
+                            .map(|track| normalize_collection(&track)),
+                    };
+                    key.unwrap_or_else(|| "<none>".to_string())
+                });
+                return print_result_groups(query, plan, warnings, &groups, output, color);
+            }
+
+            match output {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&response)?);
+                    return Ok(());
+                }
+                OutputFormat::Ndjson | OutputFormat::Csv => {
+                    print!("{}", format_rows(&response.results, output)?);
+                    return Ok(());
+                }
+                OutputFormat::Text => {}
+            }
+
+            if files {
+                for r in response.results {
+                    println!(
+                        "{:.4}\t{}\t{}\t{}",
+                        r.score,
+                        r.frame_id,
+                        r.uri,
+                        r.title.unwrap_or_default()
+                    );
+                }
+                return Ok(());
+            }
+
+            if response.results.is_empty() {
+                println!("No results found.");
+                return Ok(());
+            }
+
+            let rows: Vec<ResultRow> = response
+                .results
+                .iter()
+                .map(|r| ResultRow {
+                    rank: r.rank,
+                    score: Some(r.score),
+                    uri: &r.uri,
+                    title: r.title.as_deref().unwrap_or_default(),
+                    snippet: &r.snippet,
+                    why: r.why.as_deref(),
+                })
+                .collect();
+            print!("{}", render_result_table(&rows, color));
+
+            Ok(())
+        }
+
+        Command::Context {
+            mv2,
+            query,
+            collection,
+            limit,
+            snippet_chars,
+            max_bytes,
+            full,
+            window,
+            format,
+            no_expand,
+            max_expansions,
+            expand_hook,
+            expand_hook_timeout_ms,
+            no_vector,
+            rerank,
+            rerank_hook,
+            rerank_hook_timeout_ms,
+            rerank_hook_full_text,
+            embed_model,
+            embed_cache,
+            embed_no_cache,
+            plan,
+            asof,
+            before,
+            after,
+            feedback_weight,
+            rerank_weight,
+            recency_halflife_days,
+            search_jobs,
+            fast_path,
+            fast_path_margin,
+            no_skip_expansion_probe,
+            hook_debug,
+            plain_snippets,
+            snippet_ellipsis,
+            raw_snippets,
+        } => {
+            let (mv2, query) = mv2_and_operand(mv2, query, "QUERY")?;
+            let db = open_or_create_db(&mv2)?;
+            let args = QueryArgs {
+                raw_query: query.clone(),
+                collection,
+                limit,
+                snippet_chars,
+                no_expand,
+                max_expansions,
+                expand_hook,
+                expand_hook_timeout_ms,
+                no_vector,
+                rerank,
+                rerank_hook,
+                rerank_hook_timeout_ms,
+                rerank_hook_full_text,
+                embed_model,
+                embed_cache,
+                embed_no_cache,
+                rerank_docs: limit.max(20),
+                rerank_weight,
+                rerank_chunk_chars: 1200,
+                rerank_chunk_overlap: 200,
+                plan,
+                asof,
+                before,
+                after,
+                feedback_weight,
+                recency_halflife_days,
+                hook_debug,
+                vec_debug: false,
+                plain_snippets,
+                raw_snippets,
+                snippet_ellipsis,
+                explain: false,
+                no_skip_expansion_probe,
+                fast_path_margin,
+                fast_path,
+                search_jobs,
+            };
+
+            let pack = build_context_pack(&db, args, max_bytes, full, window, format)?;
+            if !pack.warnings.is_empty() {
+                for warning in &pack.warnings {
+                    log_warn!("Warning: {warning}");
+                }
+            }
+            println!("{}", serde_json::to_string_pretty(&pack)?);
+            Ok(())
+        }
+
+        Command::Log {
+            mv2,
+            session,
+            role,
+            text,
+            file,
+            meta,
+            batch,
+            commit_interval,
+        } => {
+            if batch {
+                let db = open_or_create_db(&mv2)?;
+                let mut writes = db.write_batch(commit_interval)?;
+                let mut count = 0usize;
+                for (lineno, line) in io::stdin().lock().lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let mut value: serde_json::Value = serde_json::from_str(&line)
+                        .map_err(|e| format!("stdin line {}: {e}", lineno + 1))?;
+                    if let Some(obj) = value.as_object_mut() {
+                        obj.entry("role").or_insert_with(|| role.clone().into());
+                        if let Some(session) = &session {
+                            obj.entry("session").or_insert_with(|| session.clone().into());
+                        }
+                    }
+                    let mut entry: AgentLogEntry = serde_json::from_value(value)
+                        .map_err(|e| format!("stdin line {}: {e}", lineno + 1))?;
+                    entry.ts_utc.get_or_insert_with(|| Utc::now().timestamp());
+                    append_agent_log(&db, &entry)?;
+                    writes.record()?;
+                    count += 1;
+                }
+                writes.finish()?;
+                println!("Logged {count} agent turns.");
+                return Ok(());
+            }
+
+            let payload_text = if let Some(path) = file {
+                fs::read_to_string(path)?
+            } else if let Some(text) = text {
+                text
+            } else {
+                return Err("log requires --text or --file".into());
+            };
+
+            let meta_value = if let Some(meta) = meta {
+                Some(serde_json::from_str(&meta)?)
+            } else {
+                None
+            };
+
+            let entry = AgentLogEntry {
+                session: session.clone(),
+                role: role.clone(),
+                text: payload_text.clone(),
+                meta: meta_value,
+                ts_utc: Some(Utc::now().timestamp()),
+            };
+            let db = open_or_create_db(&mv2)?;
+            let _ = append_agent_log(&db, &entry)?;
+            println!("Logged agent turn.");
+            Ok(())
+        }
+
+        Command::Feedback {
+            mv2,
+            uri,
+            score,
+            note,
+            session,
+            batch,
+            commit_interval,
+        } => {
+            if batch {
+                let db = open_or_create_db(&mv2)?;
+                let mut writes = db.write_batch(commit_interval)?;
+                let mut count = 0usize;
+                for (lineno, line) in io::stdin().lock().lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let mut event: FeedbackEvent = serde_json::from_str(&line)
+                        .map_err(|e| format!("stdin line {}: {e}", lineno + 1))?;
+                    event.score = event.score.clamp(-1.0, 1.0);
+                    if event.session.is_none() {
+                        event.session = session.clone();
+                    }
+                    append_feedback(&db, &event)?;
+                    writes.record()?;
+                    count += 1;
+                }
+                writes.finish()?;
+                println!("Recorded {count} feedback events.");
+                return Ok(());
+            }
+
+            let (Some(uri), Some(score)) = (uri, score) else {
+                return Err("feedback requires --uri and --score (or --batch)".into());
+            };
+            let score = score.clamp(-1.0, 1.0);
+            let event = FeedbackEvent {
+                uri: uri.clone(),
+                score,
+                note: note.clone(),
+                session: session.clone(),
+                ts_utc: Some(Utc::now().timestamp()),
+            };
+            let db = open_or_create_db(&mv2)?;
+            let _ = append_feedback(&db, &event).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            println!("Feedback recorded.");
+            Ok(())
+        }
+
+        Command::Embed {
+            mv2,
+            collection,
+            limit,
+            batch,
+            force,
+            model,
+            embed_cache,
+            embed_no_cache,
+            dry_run,
+            json,
+        } => {
+            #[cfg(feature = "vec")]
+            {
+                let _ = (mv2, collection, limit, batch, force, model, embed_cache, embed_no_cache, dry_run, json);
+                log_error!("Local embedding is not supported with SQLite backend. Use Qdrant for vector search.");
+                std::process::exit(2);
+            }
+            #[cfg(not(feature = "vec"))]
+            {
+                let _ = (
+                    mv2,
+                    collection,
+                    limit,
+                    batch,
+                    force,
+                    model,
+                    embed_cache,
+                    embed_no_cache,
+                    dry_run,
+                    json,
+                );
+                log_error!("Embed requires --features vec");
+                std::process::exit(2);
+            }
+        }
+
+        Command::Get { mv2, id, json } => {
+            let (mv2, id) = mv2_and_operand(mv2, id, "ID")?;
+            let db = open_or_create_db(&mv2)?;
+
+            if let Some(rest) = id.strip_prefix('#')
+                && rest.parse::<u64>().is_err()
+            {
+                return Err("invalid frame id (expected #123)".into());
+            }
+            let frame = match lookup_frame(&db, &id)? {
+                FrameLookup::Found(frame) => frame,
+                FrameLookup::Ambiguous(candidates) => {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "candidates": candidates }))?);
+                    } else {
+                        println!("{}", format_frame_candidates(&candidates));
+                    }
+                    return Err(format!("{id} matches {} frames; pass one of the URIs above", candidates.len()).into());
+                }
+            };
+            let frame_id = frame.id;
+
+            let text = db.frame_text_by_id(frame_id).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+
+            if json {
+                let payload = GetResponse {
+                    frame_id,
+                    uri: frame.uri.clone(),
+                    title: frame.title.clone(),
+                    text,
+                };
+                println!("{}", serde_json::to_string_pretty(&payload)?);
+                return Ok(());
+            }
+
+            println!("{text}");
+            Ok(())
+        }
+
+        Command::Status { mv2, output, json } => {
+            let mv2 = resolve_existing_mv2(mv2)?;
+            let db = open_or_create_db(&mv2)?;
+            let payload = StatusResponse {
+                mv2: mv2.display().to_string(),
+                frame_count: db.frame_count(),
+                next_frame_id: db.frame_count() as u64,
+            };
+
+            match output.resolve(json) {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&payload)?),
+                OutputFormat::Text => {
+                    println!("mv2: {}", payload.mv2);
+                    println!("frames: {}", payload.frame_count);
+                    println!("next_frame_id: {}", payload.next_frame_id);
+                }
+                format => print!("{}", format_rows(&[&payload], format)?),
+            }
+
+            Ok(())
+        }
+
+        Command::Config { mv2, command } => match command {
+            ConfigCommand::Set {
+                key,
+                file,
+                json,
+                pretty,
+            } => {
+                let bytes = if let Some(path) = file {
+                    fs::read(path)?
+                } else if let Some(json) = json {
+                    json.into_bytes()
+                } else {
+                    return Err("config set requires --file or --json".into());
+                };
+                let value: serde_json::Value = serde_json::from_slice(&bytes)?;
+                let payload = if pretty {
+                    serde_json::to_vec_pretty(&value)?
+                } else {
+                    serde_json::to_vec(&value)?
+                };
+                let db = open_or_create_db(&mv2)?;
+                if save_config_entry(&db, &key, &payload).map_err(|e| Box::<dyn std::error::Error>::from(e))? {
+                    println!("Stored config {key}");
+                } else {
+                    println!("Config {key} unchanged");
+                }
+                Ok(())
+            }
+            ConfigCommand::Get { key, raw } => {
+                let db = open_or_create_db(&mv2)?;
+                let Some(bytes) = load_config_entry(&db, &key) else {
+                    return Err("config not found".into());
+                };
+                if raw {
+                    io::stdout().write_all(&bytes)?;
+                } else {
+                    let value: serde_json::Value = serde_json::from_slice(&bytes)?;
+                    println!("{}", serde_json::to_string_pretty(&value)?);
+                }
+                Ok(())
+            }
+            ConfigCommand::List { output, json } => {
+                let db = open_or_create_db(&mv2)?;
+                let entries = list_config_entries(&db);
+                match output.resolve(json) {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+                    OutputFormat::Text => {
+                        for entry in entries {
+                            println!("{}\t{}\t{}", entry.key, entry.frame_id, entry.timestamp);
+                        }
+                    }
+                    format => print!("{}", format_rows(&entries, format)?),
+                }
+                Ok(())
+            }
+        },
+
+        Command::Collections { mv2, command } => {
+            let db = open_or_create_db(&mv2)?;
+            let date = |ts: Option<i64>| {
+                ts.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            match command {
+                CollectionsCommand::List { output, json } => {
+                    let collections = list_collections(&db);
+                    match output.resolve(json) {
+                        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&collections)?),
+                        OutputFormat::Text => {
+                            for c in &collections {
+                                let mut line = format!("{}\t{}\t{}", c.name, c.frames, date(c.last_updated));
+                                if let Some(description) = &c.description {
+                                    line.push_str(&format!("\t{description}"));
+                                }
+                                println!("{line}");
+                            }
+                        }
+                        format => print!("{}", format_rows(&collections, format)?),
+                    }
+                }
+                CollectionsCommand::Info { name, json } => {
+                    let info = collection_info(&db, &name)?;
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&info)?);
+                    } else {
+                        let s = &info.summary;
+                        println!("collection: {}", s.name);
+                        if let Some(description) = &s.description {
+                            println!("description: {description}");
+                        }
+                        println!("configured: {}", s.configured);
+                        println!("frames: {}", s.frames);
+                        println!("updated: {} .. {}", date(info.first_updated), date(s.last_updated));
+                        for root in &info.roots {
+                            println!("root: {root}");
+                        }
+                        for glob in &info.globs {
+                            println!("glob: {glob}");
+                        }
+                        for uri in &info.recent {
+                            println!("recent: {uri}");
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Command::Trigger { mv2, command } => match command {
+            TriggerCommand::Test {
+                id,
+                workspace,
+                timezone,
+                json,
+            } => {
+                let db = open_or_create_db(&mv2)?;
+                let agent_cfg = load_capsule_config(&db).unwrap_or_default().agent.unwrap_or_default();
+                let trigger = load_triggers(&db)
+                    .into_iter()
+                    .find(|t| t.id == id)
+                    .ok_or_else(|| format!("no trigger with id {id}"))?;
+                let now = Utc::now().with_timezone(&resolve_timezone(&agent_cfg, timezone));
+                let workspace = resolve_workspace(workspace, &agent_cfg);
+                let outcome = evaluate_trigger(&trigger, &mv2, now, workspace.as_deref())?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&outcome)?);
+                } else {
+                    match outcome {
+                        TriggerOutcome::Idle { reason } => println!("Would not fire: {reason}"),
+                        TriggerOutcome::Baseline { .. } => {
+                            println!("Would record the first webhook response as the baseline without firing")
+                        }
+                        TriggerOutcome::Fire { matched, prompt, session, .. } => {
+                            println!("Would fire ({matched}) in session {session} with prompt:\n\n{prompt}")
+                        }
+                    }
+                }
+                Ok(())
+            }
+        },
+
+        Command::Memory { mv2, command } => match command {
+            MemoryCommand::Status {
+                workspace,
+                strategy,
+                json,
+            } => {
+                let workspace = match workspace {
+                    Some(path) => path,
+                    None => {
+                        let db = open_or_create_db(&mv2)?;
+                        let agent_cfg = load_capsule_config(&db).unwrap_or_default().agent.unwrap_or_default();
+                        resolve_workspace(None, &agent_cfg).unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR))
+                    }
+                };
+                let statuses = memory_sync_status(&mv2, &workspace, strategy)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&statuses)?);
+                } else {
+                    for status in &statuses {
+                        match &status.resolved {
+                            Some(resolved) => println!("{:<14}{}\t{resolved}", status.state.as_str(), status.path),
+                            None => println!("{:<14}{}", status.state.as_str(), status.path),
+                        }
+                    }
+                    let conflicts = statuses
+                        .iter()
+                        .filter(|s| s.state == MemorySyncState::Conflict && s.resolved.is_none())
+                        .count();
+                    if conflicts > 0 {
+                        println!("{conflicts} conflict(s); rerun with --strategy local|capsule|both to resolve");
+                    }
+                }
+                Ok(())
+            }
+            MemoryCommand::Rollup {
+                period,
+                model_hook,
+                concat,
+                force,
+                deactivate,
+                workspace,
+                dry_run,
+                json,
+            } => {
+                let db = open_or_create_db(&mv2)?;
+                let config = load_capsule_config(&db).unwrap_or_default();
+                let hook = if concat {
+                    None
+                } else {
+                    let agent_cfg = config.agent.clone().unwrap_or_default();
+                    let hook_cfg = config.hooks.clone().unwrap_or_default();
+                    let hook = resolve_hook_spec(
+                        model_hook,
+                        u64::MAX,
+                        agent_cfg.model_hook.or(hook_cfg.llm),
+                        None,
+                    );
+                    if hook.is_none() {
+                        log_warn!("memory rollup: no model hook configured; concatenating notes instead");
+                    }
+                    hook
+                };
+                let mut summarize = |key: &str, notes: &str| -> Result<String, String> {
+                    let Some(hook) = hook.as_ref() else {
+                        return Ok(notes.to_string());
+                    };
+                    let message = |role: &str, content: String| AgentMessage {
+                        role: role.to_string(),
+                        content: Some(content),
+                        tool_calls: Vec::new(),
+                        name: None,
+                        tool_call_id: None,
+                        is_error: None,
+                        thinking_blocks: vec![],
+                    };
+                    let request = AgentHookRequest {
+                        messages: vec![
+                            message(
+                                "system",
+                                "You condense daily memory notes into a rollup. Keep decisions, durable facts, people, dates and open tasks; drop chatter. Output Markdown bullets only.".to_string(),
+                            ),
+                            message("user", format!("Daily notes for {key}:\n\n{notes}")),
+                        ],
+                        tools: Vec::new(),
+                        session: None,
+                        protocol_version: AGENT_HOOK_PROTOCOL_VERSION,
+                    };
+                    let reply = call_agent_hook(hook, &request)?;
+                    reply
+                        .content
+                        .filter(|text| !text.trim().is_empty())
+                        .ok_or_else(|| format!("model hook returned an empty rollup for {key}"))
+                };
+                let today = Utc::now().date_naive();
+                let rollups = rollup_daily_memory(&db, period, today, force, deactivate, dry_run, &mut summarize)
+                    .map_err(Box::<dyn std::error::Error>::from)?;
+                if let Some(workspace) = workspace.filter(|_| !dry_run) {
+                    let memory_dir = workspace.join("memory");
+                    for rollup in rollups.iter().filter(|r| !r.skipped) {
+                        if let Some(text) = &rollup.text {
+                            fs::create_dir_all(memory_dir.join("rollup"))?;
+                            fs::write(memory_dir.join("rollup").join(format!("{}.md", rollup.key)), text)?;
+                        }
+                        // Keep memory sync from re-importing notes that were just deactivated.
+                        if deactivate {
+                            for date in &rollup.dates {
+                                let daily = memory_dir.join(format!("{date}.md"));
+                                if daily.exists() {
+                                    fs::create_dir_all(memory_dir.join("archive"))?;
+                                    fs::rename(&daily, memory_dir.join("archive").join(format!("{date}.md")))?;
+                                }
+                            }
+                        }
+                    }
+                }
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&rollups)?);
+                } else {
+                    for rollup in &rollups {
+                        let status = if rollup.skipped {
+                            "exists"
+                        } else if dry_run {
+                            "would write"
+                        } else {
+                            "wrote"
+                        };
+                        println!("{}\t{status}\t{} daily notes", rollup.uri, rollup.dates.len());
+                    }
+                    if rollups.is_empty() {
+                        println!("No past daily notes to roll up");
+                    }
+                }
+                Ok(())
+            }
+        },
+
+        Command::Diff {
+            left,
+            right,
+            all,
+            limit,
+            output,
+            json,
+        } => {
+            let left_db = open_or_create_db(&left)?;
+            let right_db = open_or_create_db(&right)?;
+            let left_map = collect_latest_frames(&left_db, all);
+            let right_map = collect_latest_frames(&right_db, all);
+
+            let mut only_left = Vec::new();
+            let mut only_right = Vec::new();
+            let mut changed = Vec::new();
+
+            for (uri, left_summary) in &left_map {
+                if let Some(right_summary) = right_map.get(uri) {
+                    if left_summary.checksum != right_summary.checksum {
+                        changed.push(DiffChange {
+                            uri: uri.clone(),
+                            left: left_summary.clone(),
+                            right: right_summary.clone(),
+                        });
+                    }
+                } else {
+                    only_left.push(left_summary.clone());
+                }
+            }
+
+            for (uri, right_summary) in &right_map {
+                if !left_map.contains_key(uri) {
+                    only_right.push(right_summary.clone());
+                }
+            }
+
+            only_left.sort_by(|a, b| a.uri.cmp(&b.uri));
+            only_right.sort_by(|a, b| a.uri.cmp(&b.uri));
+            changed.sort_by(|a, b| a.uri.cmp(&b.uri));
+
+            if limit > 0 {
+                only_left.truncate(limit);
+                only_right.truncate(limit);
+                changed.truncate(limit);
+            }
+
+            let report = DiffReport {
+                left: left.display().to_string(),
+                right: right.display().to_string(),
+                only_left,
+                only_right,
+                changed,
+            };
+
+            match output.resolve(json) {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                OutputFormat::Text => {
+                    println!("left: {}", report.left);
+                    println!("right: {}", report.right);
+                    println!("only_left: {}", report.only_left.len());
+                    println!("only_right: {}", report.only_right.len());
+                    println!("changed: {}", report.changed.len());
+                }
+                format => print!("{}", format_rows(&diff_rows(&report), format)?),
+            }
+            Ok(())
+        }
+
+        Command::Merge {
+            left,
+            right,
+            out,
+            force,
+            no_dedup,
+            json,
+        } => {
+            let _ = (left, right, out, force, no_dedup, json);
+            log_error!("Merge is not supported with SQLite backend. Copy the .sqlite file instead.");
+            std::process::exit(2);
+        }
+
+        Command::Move {
+            mv2,
+            from,
+            to,
+            prefix,
+            force,
+            dry_run,
+            json,
+        } => {
+            let db = open_or_create_db(&mv2)?;
+            let moves = plan_uri_moves(&db, &from, &to, prefix, force)?;
+            if moves.is_empty() {
+                return Err(format!("no active documents under {from}").into());
+            }
+            let new_ids = if dry_run { Vec::new() } else { apply_uri_moves(&db, &moves)? };
+            if json {
+                let entries: Vec<serde_json::Value> = moves
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (frame, new_uri))| {
+                        serde_json::json!({
+                            "from": frame.uri,
+                            "to": new_uri,
+                            "old_frame_id": frame.id,
+                            "frame_id": new_ids.get(i),
+                        })
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "moved": new_ids.len(),
+                        "dry_run": dry_run,
+                        "moves": entries,
+                    }))?
+                );
+            } else {
+                for (frame, new_uri) in &moves {
+                    println!("{} -> {new_uri}", frame.uri.as_deref().unwrap_or_default());
+                }
+                if dry_run {
+                    println!("Would move {} frames", moves.len());
+                } else {
+                    println!("Moved {} frames", new_ids.len());
+                }
+            }
+            Ok(())
+        }
+
+        Command::Export {
+            mv2,
+            out,
+            collection,
+            force,
+            json,
+        } => {
+            if out == mv2 {
+                return Err("export destination must differ from source".into());
+            }
+            if out.exists() {
+                if !force {
+                    return Err(format!("{} already exists (use --force to overwrite)", out.display()).into());
+                }
+                fs::remove_file(&out)?;
+                for suffix in ["-wal", "-shm"] {
+                    let mut sidecar = out.clone().into_os_string();
+                    sidecar.push(suffix);
+                    let _ = fs::remove_file(sidecar);
+                }
+            }
+            let source = open_db_read_only(&mv2)?;
+            let target = open_or_create_db(&out)?;
+            let written = export_collection(&source, &target, &collection)?;
+            let collection = normalize_collection(&collection);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "source": mv2.display().to_string(),
+                        "out": out.display().to_string(),
+                        "collection": collection,
+                        "frames": written,
+                    })
+                );
+            } else {
+                println!("Exported {written} frames from collection '{collection}' to {}", out.display());
+            }
+            if written > 0 {
+                log_warn!(
+                    "Note: vectors live in the Qdrant collection keyed by frame id and are not copied; \
+                     index the exported capsule separately before relying on vector search there."
+                );
+            }
+            Ok(())
+        }
+
+        Command::Mcp {
+            mv2,
+            read_only,
+            disable_tools: disabled,
+        } => {
+            disable_tools(disabled);
+            run_mcp_server(mv2, read_only)
+        }
+
+        Command::Serve {
+            mv2,
+            bind,
+            port,
+            read_only,
+            cors_origins,
+            query_cache_ttl,
+        } => {
+            if let Some(secs) = query_cache_ttl {
+                set_query_cache_ttl(secs);
+            }
+            run_serve(resolve_existing_mv2(mv2)?, bind, port, read_only, cors_origins)
+        }
+
+        Command::Agent {
+            mv2,
+            prompt,
+            file,
+            session,
+            model_hook,
+            system,
+            system_file,
+            no_memory,
+            context_query,
+            context_results,
+            context_max_bytes,
+            max_steps,
+            log_commit_interval,
+            json,
+            log,
+            events_file,
+            tool_concurrency,
+            all_tools,
+            model_timeout_ms,
+            model_timeout_policy,
+            disable_tools: disabled,
+            force_tool,
+            ..
+        } => {
+            disable_tools(disabled);
+            run_agent(
+            mv2,
+            prompt,
+            file,
+            session,
+            model_hook,
+            system,
+            system_file,
+            no_memory,
+            context_query,
+            context_results,
+            context_max_bytes,
+            max_steps,
+            log_commit_interval,
+            json,
+            log,
+            events_file,
+            AgentRunOptions {
+                tool_concurrency,
+                all_tools,
+                model_timeout_ms,
+                model_timeout_policy,
+                force_tool,
+                ..Default::default()
+            },
+            )
+        }
+
+        Command::Hook { provider } => match provider {
+            HookCommand::Claude => run_claude_hook(),
+            HookCommand::Openai => run_openai_hook(),
+        },
+
+        Command::Bootstrap {
+            mv2,
+            workspace,
+            timezone,
+            force,
+        } => {
+            let workspace = workspace
+                .or_else(|| env_optional("AETHERVAULT_WORKSPACE").map(PathBuf::from))
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+            bootstrap_workspace(&mv2, &workspace, timezone, force)?;
+            println!(
+                "bootstrapped workspace at {} (mv2: {})",
+                workspace.display(),
+                mv2.display()
+            );
+            Ok(())
+        }
+
+        Command::Schedule {
+            mv2,
+            workspace,
+            timezone,
+            telegram_token,
+            telegram_chat_id,
+            model_hook,
+            max_steps,
+            log,
+            log_commit_interval,
+        } => run_schedule_loop(
+            mv2,
+            workspace,
+            timezone,
+            telegram_token,
+            telegram_chat_id,
+            model_hook,
+            max_steps,
+            log,
+            log_commit_interval,
+        ),
+
+        Command::Watch {
+            mv2,
+            workspace,
+            timezone,
+            model_hook,
+            max_steps,
+            log,
+            log_commit_interval,
+            poll_seconds,
+        } => run_watch_loop(
+            mv2,
+            workspace,
+            timezone,
+            model_hook,
+            max_steps,
+            log,
+            log_commit_interval,
+            poll_seconds,
+        ),
+
+        Command::Connect {
+            mv2,
+            provider,
+            bind,
+            port,
+            redirect_base,
+        } => run_oauth_broker(mv2, provider, bind, port, redirect_base),
+
+        Command::Approve { mv2, id, execute } => {
+            let output = approve_and_maybe_execute(&mv2, &id, execute)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            println!("{output}");
+            Ok(())
+        }
+
+        Command::Tools { mv2, json } => {
+            if let Some(mv2) = mv2 {
+                let db = open_db_read_only(&mv2)?;
+                let agent = load_capsule_config(&db).unwrap_or_default().agent.unwrap_or_default();
+                disable_tools(agent.disabled_tools);
+                register_custom_tools(&agent.custom_tools);
+            }
+            let tools = tool_catalog_with_policies();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tools)?);
+                return Ok(());
+            }
+            for tool in &tools {
+                let flag = |key: &str, label: &'static str| (tool[key] == true).then_some(label);
+                let flags: Vec<&str> = [flag("requires_approval", "approval"), flag("write", "write")]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let description = tool["description"].as_str().unwrap_or_default();
+                println!(
+                    "{}\t{}\t{}",
+                    tool["name"].as_str().unwrap_or_default(),
+                    if flags.is_empty() { "-".to_string() } else { flags.join(",") },
+                    description.lines().next().unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+
+        Command::Reject { mv2, id } => {
+            let output =
+                reject_approval(&mv2, &id).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            println!("{output}");
+            Ok(())
+        }
+
+        Command::Bridge { command } => run_bridge(command),
+
+        Command::Verify {
+            mv2,
+            checksums,
+            lex_sample,
+            json,
+        } => {
+            let db = open_db_read_only(&mv2)?;
+            let structure = db.verify_structure().map_err(Box::<dyn std::error::Error>::from)?;
+            let mut findings: Vec<String> = structure
+                .integrity
+                .iter()
+                .map(|line| format!("integrity: {line}"))
+                .collect();
+            if structure.unknown_status > 0 {
+                findings.push(format!("{} frames have an unknown status", structure.unknown_status));
+            }
+            for uri in &structure.duplicate_active_uris {
+                findings.push(format!("more than one active frame for {uri}"));
+            }
+            findings.extend(config_problems(&db));
+            let lex_report = if lex_sample > 0 {
+                Some(db.verify_fts(lex_sample).map_err(Box::<dyn std::error::Error>::from)?)
+            } else {
+                None
+            };
+            for miss in lex_report.iter().flat_map(|r| &r.misses) {
+                findings.push(format!(
+                    "lex index miss: frame {} {}",
+                    miss.frame_id,
+                    miss.uri.as_deref().unwrap_or("-")
+                ));
+            }
+            let checksum_report = if checksums {
+                Some(db.verify_checksums().map_err(Box::<dyn std::error::Error>::from)?)
+            } else {
+                None
+            };
+            for mismatch in checksum_report.iter().flat_map(|r| &r.mismatches) {
+                findings.push(format!(
+                    "checksum mismatch: frame {} {}",
+                    mismatch.frame_id,
+                    mismatch.uri.as_deref().unwrap_or("-")
+                ));
+            }
+            let pass = findings.is_empty();
+            if json {
+                let mut out = serde_json::json!({
+                    "status": if pass { "pass" } else { "fail" },
+                    "structure": structure,
+                    "findings": findings,
+                });
+                if let Some(report) = &lex_report {
+                    out["verify_lex"] = serde_json::to_value(report)?;
+                }
+                if let Some(report) = &checksum_report {
+                    out["verify_checksums"] = serde_json::to_value(report)?;
+                }
+                println!("{out}");
+            } else {
+                for finding in &findings {
+                    println!("FAIL {finding}");
+                }
+                println!(
+                    "{}: {} frames ({} active), {} findings.",
+                    if pass { "PASS" } else { "FAIL" },
+                    structure.frames,
+                    structure.active,
+                    findings.len()
+                );
+            }
+            if !pass {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+
+        Command::Doctor {
+            mv2,
+            vacuum,
+            rebuild_time,
+            rebuild_lex,
+            rebuild_vec,
+            verify_lex,
+            verify_sample,
+            verify_checksums,
+            dry_run,
+            json,
+        } => {
+            let _ = (rebuild_time, rebuild_vec, dry_run);
+            let db = open_or_create_db(&mv2)?;
+            // --verify-lex / --verify-checksums on their own are read-only
+            // checks, safe on a live capsule.
+            let maintenance = vacuum || rebuild_lex || !(verify_lex || verify_checksums);
+            if maintenance {
+                // Always purge superseded frames — they are dead weight
+                let purged = db.purge_superseded().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                if purged > 0 {
+                    log_info!("[doctor] purged {purged} superseded frames");
+                }
+            }
+            if rebuild_lex {
+                db.rebuild_fts().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+            if vacuum {
+                db.vacuum().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+            let lex_report = if verify_lex {
+                Some(db.verify_fts(verify_sample).map_err(Box::<dyn std::error::Error>::from)?)
+            } else {
+                None
+            };
+            let checksum_report = if verify_checksums {
+                Some(db.verify_checksums().map_err(Box::<dyn std::error::Error>::from)?)
+            } else {
+                None
+            };
+            let size = db.file_size(&mv2);
+            if json {
+                let mut out = serde_json::json!({"status": "ok", "size_bytes": size});
+                if let Some(report) = &lex_report {
+                    if !report.misses.is_empty() {
+                        out["status"] = serde_json::json!("findings");
+                    }
+                    out["verify_lex"] = serde_json::to_value(report)?;
+                }
+                if let Some(report) = &checksum_report {
+                    if !report.mismatches.is_empty() {
+                        out["status"] = serde_json::json!("findings");
+                    }
+                    out["verify_checksums"] = serde_json::to_value(report)?;
+                }
+                println!("{out}");
+            } else {
+                if let Some(report) = &checksum_report {
+                    for mismatch in &report.mismatches {
+                        println!(
+                            "checksum mismatch: frame {} {}",
+                            mismatch.frame_id,
+                            mismatch.uri.as_deref().unwrap_or("-")
+                        );
+                    }
+                    if report.mismatches.is_empty() {
+                        println!("Payload checksums OK ({} active frames).", report.checked);
+                    } else {
+                        println!(
+                            "Payload corruption: {} of {} active frames do not match their checksum.",
+                            report.mismatches.len(),
+                            report.checked
+                        );
+                    }
+                }
+                if let Some(report) = &lex_report {
+                    for miss in &report.misses {
+                        println!(
+                            "lex index miss: frame {} {} (token '{}')",
+                            miss.frame_id,
+                            miss.uri.as_deref().unwrap_or("-"),
+                            miss.token
+                        );
+                    }
+                    if report.misses.is_empty() {
+                        println!("Lexical index OK ({} of {} sampled frames checked).", report.checked, report.sampled);
+                    } else {
+                        println!(
+                            "Lexical index inconsistent: {} of {} checked frames missing; run doctor --rebuild-lex.",
+                            report.misses.len(),
+                            report.checked
+                        );
+                    }
+                }
+                println!("Doctor complete. Size: {} bytes", size);
+            }
+            Ok(())
+        }
+
+        Command::Compact {
+            mv2,
+            dry_run,
+            purge_superseded,
+            json,
+        } => {
+            let db = open_or_create_db(&mv2)?;
+            let bytes_before = db.disk_size(&mv2);
+            if dry_run {
+                let (frames, estimate) =
+                    db.reclaimable_estimate(purge_superseded).map_err(Box::<dyn std::error::Error>::from)?;
+                if json {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "status": "dry_run",
+                            "bytes_before": bytes_before,
+                            "superseded_frames": frames,
+                            "purge_superseded": purge_superseded,
+                            "estimated_reclaimable_bytes": estimate,
+                        })
+                    );
+                } else if purge_superseded {
+                    println!(
+                        "Compact dry run. Size: {bytes_before} bytes; ~{estimate} bytes reclaimable ({frames} superseded frames + free pages)"
+                    );
+                } else {
+                    println!(
+                        "Compact dry run. Size: {bytes_before} bytes; ~{estimate} bytes reclaimable (free pages; {frames} superseded frames kept)"
+                    );
+                }
+                return Ok(());
+            }
+
+            let started = std::time::Instant::now();
+            let purged = if purge_superseded {
+                db.purge_superseded().map_err(Box::<dyn std::error::Error>::from)?
+            } else {
+                0
+            };
+            let purge_ms = started.elapsed().as_millis() as u64;
+            let started = std::time::Instant::now();
+            db.rebuild_fts().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            let lex_ms = started.elapsed().as_millis() as u64;
+            let started = std::time::Instant::now();
+            db.vacuum().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            db.checkpoint_truncate().map_err(Box::<dyn std::error::Error>::from)?;
+            let vacuum_ms = started.elapsed().as_millis() as u64;
+            let bytes_after = db.disk_size(&mv2);
+            let reclaimed = bytes_before.saturating_sub(bytes_after);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": "ok",
+                        "size_bytes": bytes_after,
+                        "bytes_before": bytes_before,
+                        "bytes_after": bytes_after,
+                        "bytes_reclaimed": reclaimed,
+                        "purged_frames": purged,
+                        "durations_ms": {"purge": purge_ms, "lex": lex_ms, "vacuum": vacuum_ms},
+                    })
+                );
+            } else {
+                println!("Compact complete. Size: {bytes_before} -> {bytes_after} bytes ({reclaimed} reclaimed)");
+                if purge_superseded {
+                    println!("  {purged} superseded frames purged");
+                }
+                if !quiet {
+                    println!("  purge {purge_ms} ms, lex rebuild {lex_ms} ms, vacuum {vacuum_ms} ms");
+                }
+            }
+            Ok(())
+        }
+
+        Command::Prune {
+            mv2,
+            keep_versions,
+            older_than,
+            dry_run,
+            compact,
+            json,
+        } => {
+            if keep_versions == 0 {
+                return Err("--keep-versions must be at least 1".into());
+            }
+            let older_than = match older_than {
+                Some(value) => Some(
+                    parse_date_to_ts(&value).ok_or_else(|| format!("invalid older-than date: {value}"))?,
+                ),
+                None => None,
+            };
+            let db = open_or_create_db(&mv2)?;
+            let candidates = db
+                .prune_candidates(keep_versions, older_than)
+                .map_err(Box::<dyn std::error::Error>::from)?;
+            let bytes: u64 = candidates.iter().map(|(_, size)| size).sum();
+            let bytes_before = db.disk_size(&mv2);
+            let pruned = if dry_run {
+                0
+            } else {
+                let ids: Vec<FrameId> = candidates.iter().map(|(id, _)| *id).collect();
+                let pruned = db.purge_frames(&ids).map_err(Box::<dyn std::error::Error>::from)?;
+                if compact {
+                    db.vacuum().map_err(Box::<dyn std::error::Error>::from)?;
+                    db.checkpoint_truncate().map_err(Box::<dyn std::error::Error>::from)?;
+                }
+                pruned
+            };
+            let bytes_after = db.disk_size(&mv2);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "status": if dry_run { "dry_run" } else { "ok" },
+                        "keep_versions": keep_versions,
+                        "older_than": older_than,
+                        "candidates": candidates.len(),
+                        "pruned": pruned,
+                        "payload_bytes": bytes,
+                        "bytes_before": bytes_before,
+                        "bytes_after": bytes_after,
+                    })
+                );
+            } else if dry_run {
+                println!(
+                    "Prune dry run: {} superseded versions ({bytes} bytes) would be deleted",
+                    candidates.len()
+                );
+            } else {
+                println!("Pruned {pruned} superseded versions ({bytes} bytes of content)");
+                if compact {
+                    println!("Size: {bytes_before} -> {bytes_after} bytes");
+                }
+            }
+            Ok(())
+        }
+
+        Command::Archive {
+            mv2,
+            before,
+            collection,
+            target,
+            dry_run,
+        } => {
+            let source_path = mv2.display().to_string();
+            let target = target.unwrap_or_else(|| {
+                mv2.parent()
+                    .unwrap_or(Path::new("."))
+                    .join("archive.mv2")
+            });
+            let cutoff = parse_date_to_ts(&before)
+                .ok_or_else(|| format!("invalid before date: {before}"))?;
+
+            let target_display = target.display().to_string();
+            if target == mv2 {
+                return Err("archive destination must differ from source".into());
+            }
+
+            let source = open_or_create_db(&mv2)?;
+            let target_mem = if dry_run {
+                None
+            } else {
+                Some(open_or_create_db(&target)?)
+            };
+
+            let mut scanned = 0usize;
+            let mut eligible = 0usize;
+            let mut archived = 0usize;
+            let mut deleted = 0usize;
+            let mut candidates = Vec::new();
+
+            let all_ids = source.collect_active_frame_ids(None);
+            for &frame_id in &all_ids {
+                let frame = match source.frame_by_id(frame_id) {
+                    Ok(frame) => frame,
+                    Err(_) => continue,
+                };
+                if frame.status != FrameStatus::Active {
+                    continue;
+                }
+                scanned += 1;
+                if frame.timestamp < cutoff && frame_matches_collection(&frame, &collection) {
+                    candidates.push(frame.id);
+                    eligible += 1;
+                }
+            }
+
+            if !dry_run {
+                for frame_id in candidates {
+                let frame = source.frame_by_id(frame_id).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                if frame.status != FrameStatus::Active
+                    || frame.timestamp >= cutoff
+                    || !frame_matches_collection(&frame, &collection)
+                {
+                    continue;
+                }
+                copy_frame_to_archive(&source, target_mem.as_ref().unwrap(), &frame, frame.parent_id)?;
+                source.delete_frame(frame_id).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                archived += 1;
+                deleted += 1;
+            }
+                target_mem
+                    .as_ref()
+                    .unwrap()
+                    .commit()
+                    .map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                source.vacuum().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+
+            let report = ArchiveSummary {
+                source: source_path.clone(),
+                target: target_display.clone(),
+                before: before.clone(),
+                collection: collection.clone(),
+                scanned,
+                eligible,
+                archived,
+                deleted,
+                dry_run,
+            };
+
+            let _ = report;
+            if dry_run {
+                println!(
+                    "Dry run: archive {} frames before {} in collection '{}' from {} to {} (scanned active={scanned}, eligible={eligible})",
+                    eligible, before, collection, source_path, target_display
+                );
+            } else {
+                println!(
+                    "Archived {} frames before {} in collection '{}' from {} to {} (scanned active={}, deleted={})",
+                    archived, before, collection, source_path, target_display, scanned, deleted
+                );
+            }
+            Ok(())
+        }
+
+        Command::Dedup {
+            mv2,
+            keep_versions,
+            dry_run,
+        } => {
+            let source_path = mv2.display().to_string();
+            let source = open_or_create_db(&mv2)?;
+
+            let mut by_uri_versions: HashMap<String, Vec<(i64, u64)>> = HashMap::new();
+            let mut scanned = 0usize;
+
+            let all_ids = source.collect_active_frame_ids(None);
+            for &frame_id in &all_ids {
+                let frame = match source.frame_by_id(frame_id) {
+                    Ok(frame) => frame,
+                    Err(_) => continue,
+                };
+                if frame.status != FrameStatus::Active {
+                    continue;
+                }
+                let Some(uri) = frame.uri.clone() else {
+                    continue;
+                };
+                by_uri_versions
+                    .entry(uri)
+                    .or_default()
+                    .push((frame.timestamp, frame.id));
+                scanned += 1;
+            }
+
+            let mut duplicate_ids = Vec::new();
+            for versions in by_uri_versions.values_mut() {
+                versions.sort_by(|left, right| {
+                    right
+                        .0
+                        .cmp(&left.0)
+                        .then_with(|| right.1.cmp(&left.1))
+                });
+                if versions.len() > keep_versions {
+                    duplicate_ids.extend(versions.iter().skip(keep_versions).map(|(_, frame_id)| *frame_id));
+                }
+            }
+
+            let deleted = duplicate_ids.len();
+            if !dry_run {
+                for frame_id in &duplicate_ids {
+                    source.delete_frame(*frame_id).map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+                }
+                source.vacuum().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+
+            let report = DedupSummary {
+                source: source_path.clone(),
+                scanned,
+                unique_uris: by_uri_versions.len(),
+                duplicates_removed: deleted,
+                keep_versions,
+                dry_run,
+            };
+
+            let _ = report;
+            if dry_run {
+                println!(
+                    "Dry run: dedup would remove {} duplicate frames from {} while keeping {} newest versions per URI",
+                    deleted, source_path, keep_versions
+                );
+            } else {
+                println!(
+                    "Dedup removed {} duplicate frames from {} while keeping {} newest versions per URI",
+                    deleted, source_path, keep_versions
+                );
+            }
+
+            Ok(())
+        }
+
+        Command::Stats { mv2 } => {
+            let source_path = mv2.display().to_string();
+            let source = open_or_create_db(&mv2)?;
+            let now = Utc::now().timestamp();
+            let total_frames = source.frame_count() as u64;
+            let active_frames = source.active_frame_count() as u64;
+            let file_size = source.file_size(&mv2);
+
+            let mut by_collection: HashMap<String, usize> = HashMap::new();
+            let mut by_age_days: HashMap<String, usize> = HashMap::new();
+            let mut by_size: HashMap<String, usize> = HashMap::new();
+            let mut uri_counts: HashMap<String, usize> = HashMap::new();
+
+            let all_ids = source.collect_active_frame_ids(None);
+            for &frame_id in &all_ids {
+                let frame = match source.frame_by_id(frame_id) {
+                    Ok(frame) => frame,
+                    Err(_) => continue,
+                };
+                if frame.status != FrameStatus::Active {
+                    continue;
+                }
+                *by_collection
+                    .entry(frame_collection_name(&frame))
+                    .or_insert(0) += 1;
+
+                let age_days = now.saturating_sub(frame.timestamp).div_euclid(86_400);
+                *by_age_days
+                    .entry(frame_age_bucket(age_days))
+                    .or_insert(0) += 1;
+                let payload_size = source.frame_canonical_payload(frame.id).map(|p| p.len() as u64).unwrap_or(0);
+                *by_size
+                    .entry(frame_size_bucket(payload_size))
+                    .or_insert(0) += 1;
+                let uri_key = frame.uri.unwrap_or_else(|| "<no-uri>".to_string());
+                *uri_counts.entry(uri_key).or_insert(0) += 1;
+            }
+
+            let duplicate_uris = uri_counts.values().filter(|count| **count > 1).count();
+            let duplicate_frames: usize = uri_counts
+                .values()
+                .map(|count| count.saturating_sub(1))
+                .sum();
+            let deleted_frames = total_frames.saturating_sub(active_frames);
+            let total_breakdown = vec![
+                ("total_frames".to_string(), total_frames),
+                ("active_frames".to_string(), active_frames),
+                ("deleted_frames".to_string(), deleted_frames),
+                ("size_bytes".to_string(), file_size),
+            ];
+
+            let report = StatsSummary {
+                source: source_path.clone(),
+                total_frames,
+                active_frames,
+                by_collection: to_sorted_stats(by_collection),
+                by_age_days: to_sorted_stats(by_age_days),
+                by_size: to_sorted_stats(by_size),
+                duplicate_uris,
+                duplicate_frames,
+                total_breakdown,
+            };
+
+            println!("Stats for {}", report.source);
+            println!("Active frames: {}", report.active_frames);
+            println!("Total frames: {}", report.total_frames);
+            println!("Duplicate URIs: {} ({} duplicate frames)", report.duplicate_uris, report.duplicate_frames);
+            println!("By collection:");
+            for (collection, count) in &report.by_collection {
+                println!("  {collection}: {count}");
+            }
+            println!("By age:");
+            for (bucket, count) in &report.by_age_days {
+                println!("  {bucket}: {count}");
+            }
+            println!("By size:");
+            for (bucket, count) in &report.by_size {
+                println!("  {bucket}: {count}");
+            }
+            println!("Total breakdown:");
+            for (name, value) in &report.total_breakdown {
+                println!("  {name}: {value}");
+            }
+
+            Ok(())
+        }
+
+        Command::MigrateHotMemories {
+            mv2,
+            jsonl,
+            dry_run,
+        } => {
+            let jsonl_path = jsonl.unwrap_or_else(|| {
+                let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
+                PathBuf::from(format!("{home}/.aethervault/data/hot-memories.jsonl"))
+            });
+            if !jsonl_path.exists() {
+                log_error!(
+                    "JSONL file not found: {}",
+                    jsonl_path.display()
+                );
+                std::process::exit(2);
+            }
+            let db = open_or_create_db(&mv2)?;
+            let report = db
+                .migrate_hot_memories(&jsonl_path, dry_run)
+                .map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+
+            if dry_run {
+                println!("Dry run — no writes performed.");
+            }
+            println!(
+                "Hot-memory migration: total={} added={} updated={} noop={} invalid={} errors={}",
+                report.total_lines,
+                report.added,
+                report.updated,
+                report.skipped_noop,
+                report.skipped_invalid,
+                report.errors.len()
+            );
+            for err in &report.errors {
+                log_error!("  error: {err}");
+            }
+            if !dry_run {
+                db.commit().map_err(|e| Box::<dyn std::error::Error>::from(e))?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub(crate) mod telegram;
 pub(crate) mod slack;
 pub(crate) mod whatsapp;
 pub(crate) mod webhook;
+pub(crate) mod signal;
 
 pub(crate) use telegram::*;
 
@@ -11,25 +12,99 @@ use std::thread;
 
 
 use crate::{
-    env_optional, run_agent_with_prompt,
-    AgentProgress, AgentRunOutput, BridgeAgentConfig, BridgeCommand,
+    env_optional, load_capsule_config, open_or_create_db, resolve_mv2_path, run_agent_with_prompt,
+    AgentProgress, AgentRunOptions, AgentRunOutput, BridgeAgentConfig, BridgeCommand, CommonBridgeArgs,
+    GroupSessions,
 };
 use self::telegram::run_telegram_bridge;
 use self::whatsapp::run_whatsapp_bridge;
 use self::slack::run_slack_bridge;
+use self::signal::run_signal_bridge;
 use self::webhook::{
     extract_discord_event, extract_imessage_event, extract_matrix_event, extract_signal_event,
     extract_teams_event, reply_none, run_webhook_bridge,
 };
 
-pub(crate) fn resolve_mv2_path(cli_mv2: Option<PathBuf>) -> PathBuf {
-    if let Some(path) = cli_mv2 {
-        return path;
+/// Who a bridge answers: `AETHERVAULT_<BRIDGE>_ALLOW` (comma-separated) if
+/// set, else `agent.bridge_allow.<bridge>` from the capsule config. An empty
+/// list lets everyone through.
+pub(crate) struct SenderAllowlist {
+    bridge: String,
+    allowed: Vec<String>,
+}
+
+impl SenderAllowlist {
+    pub(crate) fn load(bridge: &str, db_path: &Path) -> Self {
+        let env_key = format!("AETHERVAULT_{}_ALLOW", bridge.to_ascii_uppercase());
+        let entries = match env_optional(&env_key) {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => open_or_create_db(db_path)
+                .ok()
+                .and_then(|db| load_capsule_config(&db))
+                .and_then(|config| config.agent)
+                .and_then(|mut agent| agent.bridge_allow.remove(bridge))
+                .unwrap_or_default(),
+        };
+        let allowlist = Self::new(bridge, entries);
+        if allowlist.allowed.is_empty() {
+            log_warn!(
+                "[{bridge}] no sender allowlist ({env_key} or agent.bridge_allow.{bridge}); \
+                 anyone who can reach this bridge can drive the agent"
+            );
+        }
+        allowlist
     }
-    if let Some(value) = env_optional("AETHERVAULT_MV2") {
-        return PathBuf::from(value);
+
+    /// For bridges that cannot verify who sent a request: ids in an unsigned
+    /// body are whatever the caller wrote, so a configured list is reported as
+    /// unenforced and an open list is returned in its place.
+    pub(crate) fn unverified(bridge: &str, db_path: &Path) -> Self {
+        if !Self::load(bridge, db_path).allowed.is_empty() {
+            log_warn!(
+                "[{bridge}] sender ids come from unsigned requests, so the allowlist is not \
+                 enforced; keep this bridge on a private network or behind a proxy that \
+                 authenticates callers"
+            );
+        }
+        Self::new(bridge, Vec::new())
+    }
+
+    pub(crate) fn new(bridge: &str, entries: Vec<String>) -> Self {
+        Self {
+            bridge: bridge.to_string(),
+            allowed: entries
+                .into_iter()
+                .map(|e| e.trim().to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+        }
+    }
+
+    /// True when one of `ids` (sender, chat or room ids, or a `bridge:a:b`
+    /// session key) names a listed entry, ignoring case. Logs refusals.
+    pub(crate) fn allows(&self, ids: &[&str]) -> bool {
+        if self.allowed.is_empty() {
+            return true;
+        }
+        let ok = ids.iter().any(|id| {
+            let id = id.to_lowercase();
+            let spans = format!(":{id}:");
+            self.allowed.iter().any(|entry| id == *entry || spans.contains(&format!(":{entry}:")))
+        });
+        if !ok {
+            log_warn!("[{}] ignoring message from unlisted sender {}", self.bridge, ids.join(" / "));
+        }
+        ok
+    }
+}
+
+/// Session key for a message in `chat`. Direct messages pass no `sender`;
+/// group messages append it unless the group is configured as shared.
+pub(crate) fn group_session_key(mode: GroupSessions, chat: &str, sender: Option<&str>) -> String {
+    match (mode, sender) {
+        (GroupSessions::PerUser, Some(sender)) => format!("{chat}:{sender}"),
+        _ => chat.to_string(),
     }
-    PathBuf::from("./data/knowledge.mv2")
 }
 
 pub(crate) fn resolve_bridge_model_hook(cli: Option<String>) -> Option<String> {
@@ -67,6 +142,7 @@ pub(crate) fn build_bridge_agent_config(
         log,
         log_commit_interval: log_commit_interval.max(1),
         session_prefix: String::new(),
+        group_sessions: GroupSessions::default(),
     })
 }
 
@@ -152,8 +228,10 @@ pub(crate) fn run_agent_for_bridge(
     let max_steps = config.max_steps;
     let log_commit_interval = config.log_commit_interval;
     let log = config.log;
+    let subagent_depth = crate::current_subagent_depth();
 
     thread::spawn(move || {
+        crate::set_subagent_depth(subagent_depth);
         let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
             run_agent_with_prompt(
                 mv2,
@@ -169,6 +247,7 @@ pub(crate) fn run_agent_for_bridge(
                 log_commit_interval,
                 log,
                 progress,
+                AgentRunOptions::default(),
             )
             .map_err(|e| e.to_string())
         })) {
@@ -186,98 +265,71 @@ pub(crate) fn run_agent_for_bridge(
     rx.recv().map_err(|err| format!("Agent channel error: {err}"))?.map_err(|e| e)
 }
 
+/// Build the agent config shared by every bridge from its common CLI options.
+pub(crate) fn bridge_config_from_args(
+    common: CommonBridgeArgs,
+) -> Result<BridgeAgentConfig, Box<dyn std::error::Error>> {
+    let mut config = build_bridge_agent_config(
+        resolve_mv2_path(common.mv2),
+        common.model_hook,
+        common.system,
+        common.no_memory,
+        common.context_query,
+        common.context_results,
+        common.context_max_bytes,
+        common.max_steps,
+        common.log,
+        common.log_commit_interval,
+    )?;
+    config.group_sessions = match common.group_sessions {
+        Some(mode) => mode,
+        None => open_or_create_db(&config.db_path)
+            .ok()
+            .and_then(|db| load_capsule_config(&db))
+            .and_then(|config| config.agent)
+            .and_then(|agent| agent.group_sessions)
+            .unwrap_or_default(),
+    };
+    Ok(config)
+}
+
 pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::error::Error>> {
     match command {
         BridgeCommand::Telegram {
-            mv2,
+            common,
             token,
             poll_timeout,
             poll_limit,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
         } => {
-            let mv2 = resolve_mv2_path(mv2);
             let token = token
                 .or_else(|| env_optional("TELEGRAM_BOT_TOKEN"))
                 .ok_or("Missing TELEGRAM_BOT_TOKEN")?;
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+            let config = bridge_config_from_args(common)?;
             run_telegram_bridge(token, poll_timeout, poll_limit, config)
         }
         BridgeCommand::Whatsapp {
-            mv2,
+            common,
             bind,
             port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
+            auth_token,
+            public_url,
+            insecure_skip_verify,
         } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
-            run_whatsapp_bridge(bind, port, config)
+            let auth_token = match auth_token.or_else(|| env_optional("TWILIO_AUTH_TOKEN")) {
+                Some(token) => Some(token),
+                None if insecure_skip_verify => None,
+                None => return Err("Missing TWILIO_AUTH_TOKEN (or pass --insecure-skip-verify)".into()),
+            };
+            let config = bridge_config_from_args(common)?;
+            run_whatsapp_bridge(bind, port, auth_token.filter(|_| !insecure_skip_verify), public_url, config)
         }
         BridgeCommand::Slack {
-            mv2,
+            common,
             bot_token,
             app_token,
             signing_secret,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
         } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+            let config = bridge_config_from_args(common)?;
             run_slack_bridge(
                 config,
                 bot_token,
@@ -285,33 +337,8 @@ pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::erro
                 signing_secret,
             )
         }
-        BridgeCommand::Discord {
-            mv2,
-            bind,
-            port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
-        } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+        BridgeCommand::Discord { common, bind, port } => {
+            let config = bridge_config_from_args(common)?;
             run_webhook_bridge(
                 "discord",
                 bind,
@@ -321,63 +348,21 @@ pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::erro
                 reply_none,
             )
         }
-        BridgeCommand::Teams {
-            mv2,
-            bind,
-            port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
-        } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+        BridgeCommand::Teams { common, bind, port } => {
+            let config = bridge_config_from_args(common)?;
             run_webhook_bridge("teams", bind, port, config, extract_teams_event, reply_none)
         }
         BridgeCommand::Signal {
-            mv2,
+            common,
+            sender,
+            poll_seconds,
             bind,
             port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
-            sender: _,
         } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+            let config = bridge_config_from_args(common)?;
+            if let Some(sender) = sender.or_else(|| env_optional("SIGNAL_SENDER")) {
+                return run_signal_bridge(sender, poll_seconds, config);
+            }
             run_webhook_bridge(
                 "signal",
                 bind,
@@ -388,33 +373,12 @@ pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::erro
             )
         }
         BridgeCommand::Matrix {
-            mv2,
+            common,
+            room: _,
             bind,
             port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
-            room: _,
         } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+            let config = bridge_config_from_args(common)?;
             run_webhook_bridge(
                 "matrix",
                 bind,
@@ -424,33 +388,8 @@ pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::erro
                 reply_none,
             )
         }
-        BridgeCommand::IMessage {
-            mv2,
-            bind,
-            port,
-            model_hook,
-            system,
-            no_memory,
-            context_query,
-            context_results,
-            context_max_bytes,
-            max_steps,
-            log,
-            log_commit_interval,
-        } => {
-            let mv2 = resolve_mv2_path(mv2);
-            let config = build_bridge_agent_config(
-                mv2,
-                model_hook,
-                system,
-                no_memory,
-                context_query,
-                context_results,
-                context_max_bytes,
-                max_steps,
-                log,
-                log_commit_interval,
-            )?;
+        BridgeCommand::IMessage { common, bind, port } => {
+            let config = bridge_config_from_args(common)?;
             run_webhook_bridge(
                 "imessage",
                 bind,
@@ -462,3 +401,110 @@ pub(crate) fn run_bridge(command: BridgeCommand) -> Result<(), Box<dyn std::erro
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn group_sessions_split_by_sender_unless_shared() {
+        let chat = "telegram:-100";
+        assert_eq!(group_session_key(GroupSessions::PerUser, chat, Some("42")), "telegram:-100:42");
+        assert_eq!(group_session_key(GroupSessions::Shared, chat, Some("42")), "telegram:-100");
+        assert_eq!(group_session_key(GroupSessions::PerUser, "telegram:7", None), "telegram:7");
+    }
+
+    #[test]
+    fn sender_allowlist_matches_ids_and_session_spans() {
+        let open = SenderAllowlist::new("telegram", vec![" ".into()]);
+        assert!(open.allows(&["anyone"]));
+
+        let list = SenderAllowlist::new("matrix", vec!["@Alice:example.org".into(), "555".into()]);
+        assert!(list.allows(&["matrix:!room:example.org:@alice:example.org"]));
+        assert!(list.allows(&["nobody", "555"]));
+        assert!(!list.allows(&["matrix:!room:example.org:@mallory:example.org"]));
+        assert!(!list.allows(&["5555"]));
+    }
+
+    fn parse_bridge(args: &[&str]) -> BridgeCommand {
+        let mut argv = vec!["aethervault".to_string(), "bridge".to_string()];
+        argv.extend(args.iter().map(|a| a.to_string()));
+        // Building the full clap tree needs more than the 2 MiB test-thread
+        // stack in debug builds; give it what `main` gets.
+        let cli = std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(move || crate::Cli::parse_from(argv))
+            .unwrap()
+            .join()
+            .unwrap();
+        match cli.command {
+            crate::Command::Bridge { command } => command,
+            _ => panic!("expected bridge command"),
+        }
+    }
+
+    fn common_of(command: BridgeCommand) -> CommonBridgeArgs {
+        match command {
+            BridgeCommand::Telegram { common, .. }
+            | BridgeCommand::Whatsapp { common, .. }
+            | BridgeCommand::Slack { common, .. }
+            | BridgeCommand::Discord { common, .. }
+            | BridgeCommand::Teams { common, .. }
+            | BridgeCommand::Signal { common, .. }
+            | BridgeCommand::Matrix { common, .. }
+            | BridgeCommand::IMessage { common, .. } => common,
+        }
+    }
+
+    #[test]
+    fn every_bridge_builds_the_same_config() {
+        let shared = [
+            "--mv2",
+            "/tmp/bridge-test.mv2",
+            "--model-hook",
+            "my-hook",
+            "--system",
+            "be brief",
+            "--no-memory",
+            "--context-query",
+            "q",
+            "--context-results",
+            "7",
+            "--context-max-bytes",
+            "1234",
+            "--max-steps",
+            "9",
+            "--log",
+            "--log-commit-interval",
+            "0",
+        ];
+        let bridges = [
+            "telegram", "whatsapp", "slack", "discord", "teams", "signal", "matrix", "i-message",
+        ];
+        let configs: Vec<BridgeAgentConfig> = bridges
+            .iter()
+            .map(|name| {
+                let mut args = vec![*name];
+                args.extend_from_slice(&shared);
+                bridge_config_from_args(common_of(parse_bridge(&args))).unwrap()
+            })
+            .collect();
+        let first = &configs[0];
+        assert_eq!(first.db_path, PathBuf::from("/tmp/bridge-test.mv2"));
+        assert_eq!(first.model_hook.as_deref(), Some("my-hook"));
+        assert_eq!(first.log_commit_interval, 1);
+        for cfg in &configs[1..] {
+            assert_eq!(cfg.db_path, first.db_path);
+            assert_eq!(cfg.model_hook, first.model_hook);
+            assert_eq!(cfg.system, first.system);
+            assert_eq!(cfg.no_memory, first.no_memory);
+            assert_eq!(cfg.context_query, first.context_query);
+            assert_eq!(cfg.context_results, first.context_results);
+            assert_eq!(cfg.context_max_bytes, first.context_max_bytes);
+            assert_eq!(cfg.max_steps, first.max_steps);
+            assert_eq!(cfg.log, first.log);
+            assert_eq!(cfg.log_commit_interval, first.log_commit_interval);
+        }
+    }
+}
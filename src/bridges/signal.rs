@@ -0,0 +1,208 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::{build_external_command, external_bin, open_or_create_db, try_handle_approval_chat, BridgeAgentConfig, GroupSessions};
+use crate::bridges::{group_session_key, run_agent_for_bridge, split_text_chunks};
+
+const SIGNAL_LAST_TS_KEY: &str = "bridge.signal.last_timestamp";
+const MAX_TEXT_CHUNK_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SignalIncoming {
+    pub(crate) source: String,
+    pub(crate) group_id: Option<String>,
+    pub(crate) timestamp: i64,
+    pub(crate) text: String,
+}
+
+impl SignalIncoming {
+    fn session_key(&self, mode: GroupSessions) -> String {
+        match &self.group_id {
+            Some(group) => group_session_key(mode, &format!("signal:group:{group}"), Some(&self.source)),
+            None => format!("signal:{}", self.source),
+        }
+    }
+}
+
+/// Parse one line of `signal-cli --output json receive` into a text message.
+/// Receipts, typing indicators and other non-data envelopes yield `None`.
+pub(crate) fn parse_signal_envelope(line: &str) -> Option<SignalIncoming> {
+    let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let envelope = value.get("envelope")?;
+    let data = envelope.get("dataMessage")?;
+    let text = data.get("message").and_then(|v| v.as_str())?.trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    let source = envelope
+        .get("sourceNumber")
+        .and_then(|v| v.as_str())
+        .or_else(|| envelope.get("source").and_then(|v| v.as_str()))
+        .or_else(|| envelope.get("sourceUuid").and_then(|v| v.as_str()))?
+        .to_string();
+    let timestamp = data
+        .get("timestamp")
+        .and_then(|v| v.as_i64())
+        .or_else(|| envelope.get("timestamp").and_then(|v| v.as_i64()))?;
+    let group_id = data
+        .get("groupInfo")
+        .and_then(|g| g.get("groupId"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    Some(SignalIncoming {
+        source,
+        group_id,
+        timestamp,
+        text,
+    })
+}
+
+/// Send a Signal message via `signal-cli send`. Shared by the bridge and the `signal_send` tool.
+pub(crate) fn send_signal_message(
+    sender: &str,
+    to: &str,
+    group_id: Option<&str>,
+    text: &str,
+) -> Result<(), String> {
+    let mut cmd = build_external_command(&external_bin("signal-cli"), &[]);
+    cmd.arg("-u").arg(sender).arg("send").arg("-m").arg(text);
+    match group_id {
+        Some(group) => {
+            cmd.arg("-g").arg(group);
+        }
+        None => {
+            cmd.arg(to);
+        }
+    }
+    let output = cmd.output().map_err(|e| format!("signal-cli: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("signal-cli error: {stderr}"));
+    }
+    Ok(())
+}
+
+fn receive_signal_messages(sender: &str) -> Result<Vec<SignalIncoming>, String> {
+    let mut cmd = build_external_command(&external_bin("signal-cli"), &[]);
+    cmd.arg("-u")
+        .arg(sender)
+        .arg("--output")
+        .arg("json")
+        .arg("receive");
+    let output = cmd.output().map_err(|e| format!("signal-cli: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("signal-cli receive error: {stderr}"));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut messages: Vec<SignalIncoming> = stdout.lines().filter_map(parse_signal_envelope).collect();
+    messages.sort_by_key(|m| m.timestamp);
+    Ok(messages)
+}
+
+fn load_last_timestamp(config: &BridgeAgentConfig) -> i64 {
+    open_or_create_db(&config.db_path)
+        .ok()
+        .and_then(|db| db.config_get(SIGNAL_LAST_TS_KEY))
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .unwrap_or(0)
+}
+
+fn save_last_timestamp(config: &BridgeAgentConfig, ts: i64) {
+    let result = open_or_create_db(&config.db_path)
+        .map_err(|e| e.to_string())
+        .and_then(|db| db.config_set(SIGNAL_LAST_TS_KEY, ts.to_string().as_bytes()));
+    if let Err(err) = result {
+        eprintln!("[signal] failed to persist last timestamp: {err}");
+    }
+}
+
+pub(crate) fn run_signal_bridge(
+    sender: String,
+    poll_seconds: u64,
+    agent_config: BridgeAgentConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!("Signal bridge polling signal-cli for {sender} every {poll_seconds}s");
+    super::cleanup_orphaned_temp_files(&agent_config.db_path);
+    let allowlist = super::SenderAllowlist::load("signal", &agent_config.db_path);
+
+    let mut last_ts = load_last_timestamp(&agent_config);
+    loop {
+        let messages = match receive_signal_messages(&sender) {
+            Ok(messages) => messages,
+            Err(err) => {
+                eprintln!("[signal] {err}");
+                thread::sleep(Duration::from_secs(poll_seconds.max(1)));
+                continue;
+            }
+        };
+        for msg in messages {
+            // Restarts replay nothing older than the last handled message.
+            if msg.timestamp <= last_ts {
+                continue;
+            }
+            let group = msg.group_id.as_deref().unwrap_or_default();
+            if !allowlist.allows(&[&msg.source, group]) {
+                last_ts = msg.timestamp;
+                save_last_timestamp(&agent_config, last_ts);
+                continue;
+            }
+            let output = match try_handle_approval_chat(&agent_config.db_path, &msg.text) {
+                Some(output) => output,
+                None => {
+                    let session = format!("{}{}", agent_config.session_prefix, msg.session_key(agent_config.group_sessions));
+                    match run_agent_for_bridge(&agent_config, &msg.text, session, None, None, None) {
+                        Ok(result) => result.final_text.unwrap_or_else(|| "\u{2705}".to_string()),
+                        Err(err) => format!("Agent error: {err}"),
+                    }
+                }
+            };
+            for chunk in split_text_chunks(&output, MAX_TEXT_CHUNK_CHARS) {
+                if let Err(err) =
+                    send_signal_message(&sender, &msg.source, msg.group_id.as_deref(), &chunk)
+                {
+                    eprintln!("[signal] reply to {} failed: {err}", msg.source);
+                    break;
+                }
+            }
+            last_ts = msg.timestamp;
+            save_last_timestamp(&agent_config, last_ts);
+        }
+        thread::sleep(Duration::from_secs(poll_seconds.max(1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_direct_message() {
+        let line = r#"{"envelope":{"source":"+15550001","sourceNumber":"+15550001","timestamp":1700000000000,"dataMessage":{"timestamp":1700000000000,"message":"hello"}},"account":"+15559999"}"#;
+        let msg = parse_signal_envelope(line).unwrap();
+        assert_eq!(msg.source, "+15550001");
+        assert_eq!(msg.timestamp, 1_700_000_000_000);
+        assert_eq!(msg.text, "hello");
+        assert_eq!(msg.group_id, None);
+        assert_eq!(msg.session_key(GroupSessions::PerUser), "signal:+15550001");
+    }
+
+    #[test]
+    fn parse_group_message() {
+        let line = r#"{"envelope":{"sourceNumber":"+15550001","timestamp":5,"dataMessage":{"timestamp":5,"message":"hi all","groupInfo":{"groupId":"abc=="}}}}"#;
+        let msg = parse_signal_envelope(line).unwrap();
+        assert_eq!(msg.group_id.as_deref(), Some("abc=="));
+        assert_eq!(msg.session_key(GroupSessions::Shared), "signal:group:abc==");
+        assert_eq!(msg.session_key(GroupSessions::PerUser), "signal:group:abc==:+15550001");
+    }
+
+    #[test]
+    fn parse_ignores_receipts_and_garbage() {
+        let receipt = r#"{"envelope":{"source":"+15550001","timestamp":5,"receiptMessage":{"isRead":true}}}"#;
+        assert!(parse_signal_envelope(receipt).is_none());
+        let empty = r#"{"envelope":{"source":"+15550001","timestamp":5,"dataMessage":{"timestamp":5,"message":"  "}}}"#;
+        assert!(parse_signal_envelope(empty).is_none());
+        assert!(parse_signal_envelope("not json").is_none());
+    }
+}
@@ -12,7 +12,7 @@ use tungstenite::{connect, Message};
 
 use crate::{
     load_session_turns, run_agent_with_prompt, save_session_turns, try_handle_approval_chat,
-    AgentRunOutput, BridgeAgentConfig, SessionTurn,
+    AgentRunOptions, AgentRunOutput, BridgeAgentConfig, GroupSessions, SessionTurn,
 };
 
 const DEFAULT_HTTP_TIMEOUT_MS: u64 = 120_000;
@@ -26,6 +26,7 @@ const MAX_TEXT_CHUNK_CHARS: usize = 3900;
 
 #[derive(Debug)]
 struct SlackIncomingEvent {
+    user_id: String,
     session_key: String,
     channel_id: String,
     thread_ts: Option<String>,
@@ -395,6 +396,7 @@ fn parse_message_event(
 
     Some(SlackIncomingEvent {
         session_key: format!("{user_id}:{channel_id}:{thread_key}"),
+        user_id,
         channel_id,
         thread_ts,
         text: parts.join("\n\n"),
@@ -460,6 +462,7 @@ fn parse_slash_command_payload(
 
     Some(SlackIncomingEvent {
         session_key: format!("{user_id}:{channel_id}:{thread_key}"),
+        user_id,
         channel_id,
         thread_ts: payload
             .get("thread_ts")
@@ -724,6 +727,7 @@ fn spawn_slack_run(
                 config.log_commit_interval,
                 config.log,
                 None,
+                AgentRunOptions::default(),
             )
             .map_err(|e| e.to_string())
         }));
@@ -857,7 +861,7 @@ fn handle_slack_completion(
 }
 
 fn handle_incoming_message(
-    incoming: SlackIncomingEvent,
+    mut incoming: SlackIncomingEvent,
     http_agent: &ureq::Agent,
     bot_token: &str,
     config: &Arc<BridgeAgentConfig>,
@@ -877,6 +881,13 @@ fn handle_incoming_message(
         return;
     }
 
+    // Keys are `user:channel:thread`; a shared group session drops the user.
+    if config.group_sessions == GroupSessions::Shared
+        && let Some(shared) = incoming.session_key.strip_prefix(&format!("{}:", incoming.user_id))
+    {
+        incoming.session_key = shared.to_string();
+    }
+
     if let Some(state) = active_runs.get_mut(&incoming.session_key) {
         if state.queued_messages.len() < MAX_QUEUED_PER_SESSION {
             state.queued_messages
@@ -1058,6 +1069,7 @@ pub(crate) fn run_slack_bridge(
 
     // Best-effort cleanup of orphaned temp files from previous sessions.
     super::cleanup_orphaned_temp_files(&agent_config.db_path);
+    let allowlist = super::SenderAllowlist::load("slack", &agent_config.db_path);
 
     let mut active_runs: HashMap<String, SlackRunState> = HashMap::new();
     let (completion_tx, completion_rx) = mpsc::channel::<SlackCompletionEvent>();
@@ -1109,6 +1121,7 @@ pub(crate) fn run_slack_bridge(
 
                     if let Some(incoming) =
                         parse_slack_incoming(payload, &bot_user_id, &http_agent, &bot_token)
+                        && allowlist.allows(&[&incoming.user_id, &incoming.channel_id])
                     {
                         handle_incoming_message(
                             incoming,
@@ -15,7 +15,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use base64::Engine;
 
 use crate::{
-    AgentProgress, BridgeAgentConfig, CompletionEvent, ActiveRun,
+    AgentProgress, AgentRunOptions, BridgeAgentConfig, CompletionEvent, ActiveRun,
     BackgroundTaskRegistry,
     SessionTurn, load_session_turns, save_session_turns,
     run_agent_with_prompt, try_handle_approval_chat,
@@ -318,6 +318,47 @@ fn normalize_mime_for_deepgram(raw_mime: &str) -> String {
     if base.is_empty() { "audio/ogg".to_string() } else { base }
 }
 
+/// Chat and sending user behind `update`, whether a message or a button press.
+fn telegram_chat_and_sender(update: &TelegramUpdate) -> (Option<&TelegramChat>, Option<&TelegramUser>) {
+    match &update.callback_query {
+        Some(cb) => (cb.message.as_ref().map(|m| &m.chat), cb.from.as_ref()),
+        None => {
+            let msg = update
+                .message
+                .as_ref()
+                .or(update.edited_message.as_ref())
+                .or(update.channel_post.as_ref());
+            (msg.map(|m| &m.chat), msg.and_then(|m| m.from.as_ref()))
+        }
+    }
+}
+
+/// Chat id, user id and username (bare and `@`-prefixed) behind `update`,
+/// for the sender allowlist.
+pub(crate) fn telegram_sender_ids(update: &TelegramUpdate) -> Vec<String> {
+    let (chat, from) = telegram_chat_and_sender(update);
+    let mut ids: Vec<String> = chat.map(|c| c.id.to_string()).into_iter().collect();
+    if let Some(user) = from {
+        ids.push(user.id.to_string());
+        if let Some(name) = &user.username {
+            ids.push(name.clone());
+            ids.push(format!("@{name}"));
+        }
+    }
+    ids
+}
+
+/// Session for `update` in `chat_id`. Group chats (negative ids) get one
+/// session per sender unless the bridge runs with shared group sessions.
+pub(crate) fn telegram_session(config: &BridgeAgentConfig, update: &TelegramUpdate, chat_id: i64) -> String {
+    let sender = telegram_chat_and_sender(update)
+        .1
+        .filter(|_| chat_id < 0)
+        .map(|user| user.id.to_string());
+    let key = super::group_session_key(config.group_sessions, &format!("telegram:{chat_id}"), sender.as_deref());
+    format!("{}{key}", config.session_prefix)
+}
+
 /// Extract content from a Telegram update. Returns (chat_id, message_id, text).
 /// For photos, the text will contain an [AV_IMAGE:base64:media_type:DATA] marker.
 /// For voice/audio, the transcription is prepended to any caption/text.
@@ -740,7 +781,7 @@ pub(crate) fn spawn_agent_run(
     let log_commit_interval = agent_config.log_commit_interval;
     let log = agent_config.log;
     let worker_prompt = user_text.to_string();
-    let worker_session = session;
+    let worker_session = session.clone();
     let worker_tx = completion_tx.clone();
     thread::spawn(move || {
         let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
@@ -758,6 +799,7 @@ pub(crate) fn spawn_agent_run(
                 log_commit_interval,
                 log,
                 Some(worker_progress.clone()),
+                AgentRunOptions::default(),
             )
             .map_err(|e| e.to_string())
         }));
@@ -768,12 +810,14 @@ pub(crate) fn spawn_agent_run(
         let event = match result {
             Ok(agent_result) => CompletionEvent {
                 chat_id,
+                session,
                 reply_to_id,
                 result: agent_result,
             },
             Err(panic_info) => {
                 CompletionEvent {
                     chat_id,
+                    session,
                     reply_to_id,
                     result: Err(format!("Agent crashed: {}", super::panic_to_string(panic_info))),
                 }
@@ -888,7 +932,7 @@ pub(crate) fn handle_telegram_completion(
     http_agent: &ureq::Agent,
     base_url: &str,
     agent_config: &BridgeAgentConfig,
-    active_runs: &mut HashMap<i64, ActiveRun>,
+    active_runs: &mut HashMap<String, ActiveRun>,
     completion_tx: &mpsc::Sender<CompletionEvent>,
     bg_registry: Option<Arc<Mutex<BackgroundTaskRegistry>>>,
 ) {
@@ -909,7 +953,7 @@ pub(crate) fn handle_telegram_completion(
     };
 
     // Save conversation turns for session continuity
-    let session_id = format!("{}telegram:{chat_id}", agent_config.session_prefix);
+    let session_id = event.session;
     {
         let mut turns = load_session_turns(&session_id, 20);
         let now = SystemTime::now()
@@ -931,9 +975,9 @@ pub(crate) fn handle_telegram_completion(
     }
 
     // Check for queued messages -- merge all into one prompt
-    if let Some(run) = active_runs.get_mut(&chat_id) {
+    if let Some(run) = active_runs.get_mut(&session_id) {
         if run.queued_messages.is_empty() {
-            active_runs.remove(&chat_id);
+            active_runs.remove(&session_id);
         } else {
             // Merge all queued messages into a single prompt
             let merged_text = if run.queued_messages.len() == 1 {
@@ -948,7 +992,7 @@ pub(crate) fn handle_telegram_completion(
             let last_reply_id = run.queued_messages.last().map(|(_, rid)| *rid).flatten();
             run.queued_messages.clear();
 
-            let session = format!("{}telegram:{chat_id}", agent_config.session_prefix);
+            let session = session_id;
 
             // Save merged user message to session turns
             {
@@ -999,8 +1043,9 @@ pub(crate) fn run_telegram_bridge(
 
     // Clean up orphaned vault temp files from previous crashes.
     super::cleanup_orphaned_temp_files(&agent_config.db_path);
+    let allowlist = super::SenderAllowlist::load("telegram", &agent_config.db_path);
 
-    let mut active_runs: HashMap<i64, ActiveRun> = HashMap::new();
+    let mut active_runs: HashMap<String, ActiveRun> = HashMap::new();
     let (completion_tx, completion_rx) = mpsc::channel::<CompletionEvent>();
     let bg_registry = Arc::new(Mutex::new(BackgroundTaskRegistry::new()));
     let mut last_scorecard_send = std::time::Instant::now();
@@ -1071,24 +1116,24 @@ pub(crate) fn run_telegram_bridge(
                 );
 
                 // If a foreground run is active, inject as steering messages
-                if let Some(run) = active_runs.get_mut(&chat_id) {
+                let session = format!("{}telegram:{chat_id}", agent_config.session_prefix);
+                if let Some(run) = active_runs.get_mut(&session) {
                     let mut guard = run.progress.lock().unwrap_or_else(|e| e.into_inner());
                     guard.steering_messages.push(synthesis_prompt);
                 } else {
                     // No active run — spawn a synthesis run
-                    let session = format!("{}telegram:{chat_id}", agent_config.session_prefix);
                     let progress = spawn_agent_run(
                         &agent_config,
                         chat_id,
                         None,
                         &synthesis_prompt,
-                        session,
+                        session.clone(),
                         &completion_tx,
                         &http_agent,
                         &base_url,
                         Some(bg_registry.clone()),
                     );
-                    active_runs.insert(chat_id, ActiveRun {
+                    active_runs.insert(session, ActiveRun {
                         progress,
                         queued_messages: Vec::new(),
                     });
@@ -1165,6 +1210,11 @@ pub(crate) fn run_telegram_bridge(
         for entry in update.result {
             offset = Some(entry.update_id);
 
+            let sender_ids = telegram_sender_ids(&entry);
+            if !allowlist.allows(&sender_ids.iter().map(String::as_str).collect::<Vec<_>>()) {
+                continue;
+            }
+
             // Handle callback queries (inline keyboard presses)
             if let Some(cb) = &entry.callback_query {
                 telegram_answer_callback(&http_agent, &base_url, &cb.id, None);
@@ -1173,6 +1223,7 @@ pub(crate) fn run_telegram_bridge(
             let Some((chat_id, reply_to_id, user_text)) = extract_telegram_content(&entry, &http_agent, &base_url) else {
                 continue;
             };
+            let session = telegram_session(&agent_config, &entry, chat_id);
             if let Some(output) = try_handle_approval_chat(&agent_config.db_path, &user_text) {
                 if let Err(err) = telegram_send_message(&http_agent, &base_url, chat_id, &output) {
                     eprintln!("Telegram send failed: {err}");
@@ -1180,9 +1231,9 @@ pub(crate) fn run_telegram_bridge(
                 continue;
             }
 
-            // Check if there's already an active run for this chat
+            // Check if there's already an active run for this session
             const MAX_QUEUED_PER_CHAT: usize = 5;
-            if let Some(run) = active_runs.get_mut(&chat_id) {
+            if let Some(run) = active_runs.get_mut(&session) {
                 // Check if user is responding to a checkpoint
                 let lower = user_text.trim().to_lowercase();
                 let is_checkpoint_response = {
@@ -1238,8 +1289,6 @@ pub(crate) fn run_telegram_bridge(
 
             telegram_send_typing(&http_agent, &base_url, chat_id);
 
-            let session = format!("{}telegram:{chat_id}", agent_config.session_prefix);
-
             // Save user message to session turns
             {
                 let mut turns = load_session_turns(&session, 20);
@@ -1260,14 +1309,14 @@ pub(crate) fn run_telegram_bridge(
                 chat_id,
                 reply_to_id,
                 &user_text,
-                session,
+                session.clone(),
                 &completion_tx,
                 &http_agent,
                 &base_url,
                 Some(bg_registry.clone()),
             );
 
-            active_runs.insert(chat_id, ActiveRun {
+            active_runs.insert(session, ActiveRun {
                 progress,
                 queued_messages: Vec::new(),
             });
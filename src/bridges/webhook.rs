@@ -4,7 +4,7 @@ use tiny_http::{Method, Response, Server};
 
 use std::io;
 
-use crate::{blake3_hash, try_handle_approval_chat, BridgeAgentConfig};
+use crate::{blake3_hash, health_response, try_handle_approval_chat, BridgeAgentConfig};
 use crate::bridges::run_agent_for_bridge;
 
 pub(crate) fn parse_json_body(request: &mut tiny_http::Request) -> Result<serde_json::Value, String> {
@@ -31,8 +31,13 @@ pub(crate) fn run_webhook_bridge(
     let server = Server::http(&addr)
         .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("server: {e}")))?;
     eprintln!("{name} bridge listening on http://{addr}");
+    super::SenderAllowlist::unverified(name, &agent_config.db_path);
 
     for mut request in server.incoming_requests() {
+        if let Some(response) = health_response(&request, &agent_config.db_path) {
+            let _ = request.respond(response);
+            continue;
+        }
         if *request.method() != Method::Post {
             let response = Response::from_string("ok");
             let _ = request.respond(response);
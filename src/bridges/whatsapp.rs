@@ -1,14 +1,20 @@
 use std::collections::HashMap;
 use std::io;
 
-use tiny_http::{Header, Method, Response, Server};
+use base64::Engine;
+use ring::hmac;
+use tiny_http::{Header, Method, Request, Response, Server};
 use url::form_urlencoded;
 
 use crate::{
-    try_handle_approval_chat, BridgeAgentConfig,
+    health_response, try_handle_approval_chat, BridgeAgentConfig,
 };
-use crate::bridges::run_agent_for_bridge;
+use crate::bridges::{run_agent_for_bridge, split_text_chunks};
 
+/// Twilio rejects WhatsApp message bodies over 1600 characters.
+const MAX_WHATSAPP_MESSAGE_CHARS: usize = 1600;
+
+/// Escapes XML specials and drops control characters XML 1.0 cannot carry.
 pub(crate) fn escape_xml(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     for ch in input.chars() {
@@ -18,23 +24,108 @@ pub(crate) fn escape_xml(input: &str) -> String {
             '>' => out.push_str("&gt;"),
             '"' => out.push_str("&quot;"),
             '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(ch),
+            c if c.is_control() => {}
             _ => out.push(ch),
         }
     }
     out
 }
 
+/// TwiML reply with one `<Message>` per chunk of `text`; an empty
+/// `<Response/>` (no reply) when `text` is blank.
+pub(crate) fn twiml_response(text: &str) -> String {
+    let mut twiml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    if text.trim().is_empty() {
+        twiml.push_str("<Response/>");
+        return twiml;
+    }
+    twiml.push_str("<Response>");
+    for chunk in split_text_chunks(text, MAX_WHATSAPP_MESSAGE_CHARS) {
+        twiml.push_str("<Message>");
+        twiml.push_str(&escape_xml(&chunk));
+        twiml.push_str("</Message>");
+    }
+    twiml.push_str("</Response>");
+    twiml
+}
+
+fn respond_twiml(request: Request, text: &str) {
+    let mut response = Response::from_string(twiml_response(text));
+    if let Ok(header) = Header::from_bytes("Content-Type", "text/xml; charset=utf-8") {
+        response.add_header(header);
+    }
+    let _ = request.respond(response);
+}
+
+/// What Twilio signs: the full webhook URL followed by every POST param as
+/// `key` + `value`, sorted by key.
+fn twilio_signed_data(url: &str, params: &[(String, String)]) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort();
+    let mut data = url.to_string();
+    for (key, value) in sorted {
+        data.push_str(key);
+        data.push_str(value);
+    }
+    data
+}
+
+/// Checks `X-Twilio-Signature` (base64 HMAC-SHA1 keyed by the auth token)
+/// in constant time.
+pub(crate) fn twilio_signature_valid(
+    auth_token: &str,
+    url: &str,
+    params: &[(String, String)],
+    signature: &str,
+) -> bool {
+    let Ok(tag) = base64::engine::general_purpose::STANDARD.decode(signature.trim()) else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, auth_token.as_bytes());
+    hmac::verify(&key, twilio_signed_data(url, params).as_bytes(), &tag).is_ok()
+}
+
+fn request_header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+/// The URL Twilio signed: `public_url` (or `https://<Host>`) plus the request path and query.
+fn twilio_request_url(request: &Request, public_url: Option<&str>) -> String {
+    let base = match public_url {
+        Some(base) => base.trim_end_matches('/').to_string(),
+        None => format!("https://{}", request_header(request, "Host").unwrap_or_default()),
+    };
+    format!("{base}{}", request.url())
+}
+
 pub(crate) fn run_whatsapp_bridge(
     bind: String,
     port: u16,
+    auth_token: Option<String>,
+    public_url: Option<String>,
     agent_config: BridgeAgentConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let addr = format!("{bind}:{port}");
     let server = Server::http(&addr)
         .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("server: {e}")))?;
     eprintln!("WhatsApp bridge listening on http://{addr}");
+    let allowlist = if auth_token.is_some() {
+        super::SenderAllowlist::load("whatsapp", &agent_config.db_path)
+    } else {
+        log_warn!("[whatsapp] --insecure-skip-verify: accepting unsigned requests");
+        super::SenderAllowlist::unverified("whatsapp", &agent_config.db_path)
+    };
 
     for mut request in server.incoming_requests() {
+        if let Some(response) = health_response(&request, &agent_config.db_path) {
+            let _ = request.respond(response);
+            continue;
+        }
         if *request.method() != Method::Post {
             let response = Response::from_string("ok");
             let _ = request.respond(response);
@@ -43,28 +134,30 @@ pub(crate) fn run_whatsapp_bridge(
 
         let mut body = String::new();
         request.as_reader().read_to_string(&mut body)?;
-        let params: HashMap<String, String> = form_urlencoded::parse(body.as_bytes())
+        let pairs: Vec<(String, String)> = form_urlencoded::parse(body.as_bytes())
             .into_owned()
             .collect();
+        if let Some(token) = &auth_token {
+            let url = twilio_request_url(&request, public_url.as_deref());
+            let signature = request_header(&request, "X-Twilio-Signature").unwrap_or_default();
+            if !twilio_signature_valid(token, &url, &pairs, &signature) {
+                log_warn!("[whatsapp] rejecting request with bad X-Twilio-Signature for {url}");
+                let _ = request.respond(Response::from_string("forbidden").with_status_code(403));
+                continue;
+            }
+        }
+        let params: HashMap<String, String> = pairs.into_iter().collect();
 
         let from = params.get("From").cloned().unwrap_or_default();
         let text = params.get("Body").cloned().unwrap_or_default();
-        if from.trim().is_empty() || text.trim().is_empty() {
-            let response = Response::from_string("missing body");
-            let _ = request.respond(response);
+        // Media-only messages and unlisted senders get an empty reply.
+        if from.trim().is_empty() || text.trim().is_empty() || !allowlist.allows(&[&from]) {
+            respond_twiml(request, "");
             continue;
         }
 
         if let Some(output) = try_handle_approval_chat(&agent_config.db_path, &text) {
-            let twiml = format!(
-                "<Response><Message>{}</Message></Response>",
-                escape_xml(&output)
-            );
-            let mut response = Response::from_string(twiml);
-            let header = Header::from_bytes("Content-Type", "text/xml; charset=utf-8")
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "invalid header"))?;
-            response.add_header(header);
-            let _ = request.respond(response);
+            respond_twiml(request, &output);
             continue;
         }
 
@@ -77,16 +170,7 @@ pub(crate) fn run_whatsapp_bridge(
         if output.trim().is_empty() {
             output = "\u{2705}".to_string();
         }
-
-        let twiml = format!(
-            "<Response><Message>{}</Message></Response>",
-            escape_xml(&output)
-        );
-        let mut response = Response::from_string(twiml);
-        let header = Header::from_bytes("Content-Type", "text/xml; charset=utf-8")
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "invalid header"))?;
-        response.add_header(header);
-        let _ = request.respond(response);
+        respond_twiml(request, &output);
     }
     Ok(())
 }
@@ -95,6 +179,44 @@ pub(crate) fn run_whatsapp_bridge(
 mod tests {
     use super::*;
 
+    #[test]
+    fn twilio_signature_matches_documented_vector() {
+        // Example from Twilio's webhook security docs.
+        let url = "https://mycompany.com/myapp.php?foo=1&bar=2";
+        let params: Vec<(String, String)> = [
+            ("CallSid", "CA1234567890ABCDE"),
+            ("Caller", "+12349013030"),
+            ("Digits", "1234"),
+            ("From", "+12349013030"),
+            ("To", "+18005551212"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let signature = "0/KCTR6DLpKmkAf8muzZqo1nDgQ=";
+        assert!(twilio_signature_valid("12345", url, &params, signature));
+        assert!(!twilio_signature_valid("54321", url, &params, signature));
+        assert!(!twilio_signature_valid("12345", "https://mycompany.com/other", &params, signature));
+        assert!(!twilio_signature_valid("12345", url, &params[1..], signature));
+        assert!(!twilio_signature_valid("12345", url, &params, "not base64!"));
+    }
+
+    #[test]
+    fn twiml_wraps_escapes_and_splits_replies() {
+        assert_eq!(
+            twiml_response("   "),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response/>"
+        );
+        assert_eq!(
+            twiml_response("a < b\u{0}"),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response><Message>a &lt; b</Message></Response>"
+        );
+        let long = "&".repeat(MAX_WHATSAPP_MESSAGE_CHARS + 1);
+        let twiml = twiml_response(&long);
+        assert_eq!(twiml.matches("<Message>").count(), 2);
+        assert!(twiml.contains(&format!("<Message>{}</Message></Response>", "&amp;")));
+    }
+
     #[test]
     fn escape_xml_no_special_chars() {
         assert_eq!(escape_xml("hello world"), "hello world");
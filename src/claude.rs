@@ -6,9 +6,10 @@ use std::time::Duration;
 use serde_json;
 
 use crate::{
-    command_spec_to_vec, env_bool, env_f64, env_optional, env_required, env_u64, env_usize,
-    jitter_ratio, parse_retry_after, run_hook_command, AgentHookRequest, AgentHookResponse,
-    AgentMessage, AgentToolCall, CommandSpec, HookSpec,
+    command_spec_to_vec, env_bool, env_optional, env_required, env_u64,
+    run_hook_command, send_with_retry, HookHttpError, HookRetryPolicy, AgentHookRequest, AgentHookResponse,
+    AgentMessage, AgentToolCall, CommandSpec, HookSpec, AGENT_HOOK_PROTOCOL_VERSION,
+    call_openai, check_hook_protocol_version,
 };
 
 const CRITIC_SYSTEM_PROMPT: &str = "\
@@ -210,7 +211,7 @@ pub(crate) fn to_anthropic_messages(messages: &[AgentMessage]) -> Vec<serde_json
                                         }));
                                     }
                                     Err(reason) => {
-                                        eprintln!("[to_anthropic_messages] image validation failed: {reason}");
+                                        log_warn!("[to_anthropic_messages] image validation failed: {reason}");
                                         blocks.push(serde_json::json!({
                                             "type": "text",
                                             "text": format!("[Image could not be included: {reason}]")
@@ -368,6 +369,7 @@ pub(crate) fn parse_claude_response(
     };
 
     Ok(AgentHookResponse {
+        protocol_version: Some(AGENT_HOOK_PROTOCOL_VERSION),
         message: AgentMessage {
             role: "assistant".to_string(),
             content: content_text,
@@ -410,9 +412,7 @@ pub(crate) fn call_claude_with_model(
         .transpose()
         .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid ANTHROPIC_TOP_P"))?;
     let timeout = env_u64("ANTHROPIC_TIMEOUT", u64::MAX)?;
-    let max_retries = env_usize("ANTHROPIC_MAX_RETRIES", 2)?;
-    let retry_base = env_f64("ANTHROPIC_RETRY_BASE", 0.5)?;
-    let retry_max = env_f64("ANTHROPIC_RETRY_MAX", 4.0)?;
+    let retry_policy = HookRetryPolicy::from_env("ANTHROPIC")?;
     let version = env_optional("ANTHROPIC_VERSION").unwrap_or_else(|| "2023-06-01".to_string());
     let beta = env_optional("ANTHROPIC_BETA");
     let token_efficient = env_bool("ANTHROPIC_TOKEN_EFFICIENT", false);
@@ -513,12 +513,11 @@ pub(crate) fn call_claude_with_model(
         .timeout_write(Duration::from_secs(timeout))
         .build();
 
-    let retryable = |status: u16| matches!(status, 429 | 500 | 502 | 503 | 504 | 529);
     let mut body = None;
     // Track 400 error body for potential repair
     let mut last_400_body: Option<String> = None;
 
-    for attempt in 0..=max_retries {
+    let primary = send_with_retry("call_claude", &retry_policy, &payload, || {
         let mut request = agent
             .post(&base_url)
             .set("content-type", "application/json")
@@ -527,46 +526,16 @@ pub(crate) fn call_claude_with_model(
         if !beta_values.is_empty() {
             request = request.set("anthropic-beta", &beta_values.join(","));
         }
-
-        let response = request.send_json(payload.clone());
-        match response {
-            Ok(resp) => {
-                body = Some(resp.into_string()?);
-                break;
-            }
-            Err(ureq::Error::Status(code, resp)) => {
-                let retry_after = parse_retry_after(&resp);
-                let text = resp.into_string().unwrap_or_default();
-                if code == 400 {
-                    eprintln!("[call_claude] got 400 from primary: {text}");
-                    last_400_body = Some(text);
-                    break; // don't retry 400s in the normal loop — handled below via repair
-                }
-                if attempt < max_retries && retryable(code) {
-                    let mut delay = (retry_base * 2.0_f64.powi(attempt as i32)).min(retry_max);
-                    if let Some(retry_after) = retry_after {
-                        delay = delay.max(retry_after);
-                    }
-                    let jitter = jitter_ratio() * 0.2;
-                    delay *= 1.0 + jitter;
-                    thread::sleep(Duration::from_secs_f64(delay));
-                    continue;
-                }
-                eprintln!("[call_claude] primary API failed after {} retries: {code} {text}", max_retries);
-                break; // fall through to fallback/Vertex
-            }
-            Err(ureq::Error::Transport(err)) => {
-                if attempt < max_retries {
-                    let mut delay = (retry_base * 2.0_f64.powi(attempt as i32)).min(retry_max);
-                    let jitter = jitter_ratio() * 0.2;
-                    delay *= 1.0 + jitter;
-                    thread::sleep(Duration::from_secs_f64(delay));
-                    continue;
-                }
-                eprintln!("[call_claude] primary API transport error after {} retries: {err}", max_retries);
-                break; // fall through to fallback/Vertex
-            }
+        request
+    });
+    match primary {
+        Ok(text) => body = Some(text),
+        Err(HookHttpError::Status(400, text)) => {
+            log_warn!("[call_claude] got 400 from primary: {text}");
+            last_400_body = Some(text); // handled below via repair
         }
+        // fall through to fallback/Vertex
+        Err(err) => log_warn!("[call_claude] primary API failed: {err}"),
     }
 
     // REPAIR on 400: try to fix the request and retry primary once
@@ -580,7 +549,7 @@ pub(crate) fn call_claude_with_model(
                 .unwrap_or_default();
 
             if repair_request_for_400(&mut repaired_messages, error_text) {
-                eprintln!("[call_claude] repaired request (stripped images), retrying primary once");
+                log_info!("[call_claude] repaired request (stripped images), retrying primary once");
                 let mut repaired_payload = payload.clone();
                 repaired_payload["messages"] = serde_json::json!(repaired_messages);
 
@@ -600,12 +569,12 @@ pub(crate) fn call_claude_with_model(
                     }
                     Err(ureq::Error::Status(code, resp)) => {
                         let text = resp.into_string().unwrap_or_default();
-                        eprintln!("[call_claude] repaired request also failed: {code} {text}");
+                        log_warn!("[call_claude] repaired request also failed: {code} {text}");
                         // Update payload so Vertex/Sonnet use the repaired version
                         payload = repaired_payload;
                     }
                     Err(ureq::Error::Transport(err)) => {
-                        eprintln!("[call_claude] repaired request transport error: {err}");
+                        log_warn!("[call_claude] repaired request transport error: {err}");
                         payload = repaired_payload;
                     }
                 }
@@ -619,10 +588,10 @@ pub(crate) fn call_claude_with_model(
             .unwrap_or_else(|| "http://localhost:11436/v1/messages".to_string());
         let vertex_enabled = env_optional("VERTEX_FALLBACK").unwrap_or_else(|| "1".to_string()) == "1";
         if vertex_enabled {
-            eprintln!("Anthropic direct failed, falling back to Vertex proxy at {vertex_url}");
+            log_warn!("Anthropic direct failed, falling back to Vertex proxy at {vertex_url}");
             payload["model"] = serde_json::json!(model);
             let vertex_key = env_optional("VERTEX_API_KEY").unwrap_or_else(|| api_key.clone());
-            for attempt in 0..=max_retries {
+            let vertex = send_with_retry("call_claude:vertex", &retry_policy, &payload, || {
                 let mut request = agent
                     .post(&vertex_url)
                     .set("content-type", "application/json")
@@ -631,31 +600,11 @@ pub(crate) fn call_claude_with_model(
                 if !beta_values.is_empty() {
                     request = request.set("anthropic-beta", &beta_values.join(","));
                 }
-                match request.send_json(payload.clone()) {
-                    Ok(resp) => {
-                        body = Some(resp.into_string()?);
-                        break;
-                    }
-                    Err(ureq::Error::Status(code, resp)) => {
-                        let text = resp.into_string().unwrap_or_default();
-                        eprintln!("[call_claude] Vertex fallback failed: {code} {text}");
-                        if code == 400 {
-                            break; // 400 = bad request (prompt too long, etc.) — don't retry
-                        }
-                        if attempt < max_retries {
-                            let delay = (retry_base * 2.0_f64.powi(attempt as i32)).min(retry_max);
-                            thread::sleep(Duration::from_secs_f64(delay));
-                        }
-                    }
-                    Err(ureq::Error::Transport(err)) => {
-                        if attempt == max_retries {
-                            eprintln!("[call_claude] Vertex fallback transport error: {err}");
-                        } else {
-                            let delay = (retry_base * 2.0_f64.powi(attempt as i32)).min(retry_max);
-                            thread::sleep(Duration::from_secs_f64(delay));
-                        }
-                    }
-                }
+                request
+            });
+            match vertex {
+                Ok(text) => body = Some(text),
+                Err(err) => log_warn!("[call_claude] Vertex fallback failed: {err}"),
             }
         }
     }
@@ -663,7 +612,7 @@ pub(crate) fn call_claude_with_model(
     // Sonnet fallback — last resort, different (cheaper/faster) model
     if body.is_none() {
         if let Ok(fallback_model) = std::env::var("ANTHROPIC_FALLBACK_MODEL") {
-            eprintln!("All primary endpoints failed, trying Sonnet fallback: {fallback_model}");
+            log_warn!("All primary endpoints failed, trying Sonnet fallback: {fallback_model}");
             payload["model"] = serde_json::json!(fallback_model);
 
             // Strip thinking/output_config — Sonnet doesn't support adaptive thinking
@@ -723,6 +672,7 @@ pub(crate) fn run_claude_hook() -> Result<(), Box<dyn std::error::Error>> {
         return Err("Claude hook received empty input".into());
     }
     let req: AgentHookRequest = serde_json::from_str(&input)?;
+    check_hook_protocol_version(Some(req.protocol_version))?;
     let response = call_claude(&req)?;
     println!("{}", serde_json::to_string(&response)?);
     Ok(())
@@ -743,7 +693,7 @@ pub(crate) fn call_critic(
 
     // Circuit breaker: skip critic after too many consecutive failures
     if CRITIC_CONSECUTIVE_FAILURES.load(Ordering::Relaxed) >= CRITIC_MAX_CONSECUTIVE_FAILURES {
-        eprintln!("[critic] circuit breaker open — skipping for rest of session");
+        log_debug!("[critic] circuit breaker open — skipping for rest of session");
         return None;
     }
 
@@ -832,13 +782,13 @@ pub(crate) fn call_critic(
         Ok(resp) => match resp.into_string() {
             Ok(b) => b,
             Err(e) => {
-                eprintln!("[critic] response read error: {e}");
+                log_debug!("[critic] response read error: {e}");
                 CRITIC_CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed);
                 return None;
             }
         },
         Err(e) => {
-            eprintln!("[critic] API error: {e}");
+            log_debug!("[critic] API error: {e}");
             CRITIC_CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed);
             return None;
         }
@@ -847,7 +797,7 @@ pub(crate) fn call_critic(
     let parsed: serde_json::Value = match serde_json::from_str(&body) {
         Ok(v) => v,
         Err(e) => {
-            eprintln!("[critic] JSON parse error: {e}");
+            log_debug!("[critic] JSON parse error: {e}");
             CRITIC_CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed);
             return None;
         }
@@ -864,7 +814,7 @@ pub(crate) fn call_critic(
     let verdict = match extract_critic_json(text) {
         Some(v) => v,
         None => {
-            eprintln!("[critic] verdict parse error: could not extract JSON from response");
+            log_debug!("[critic] verdict parse error: could not extract JSON from response");
             CRITIC_CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed);
             return None;
         }
@@ -879,7 +829,7 @@ pub(crate) fn call_critic(
         .unwrap_or(true);
 
     if grounded {
-        eprintln!("[critic] grounded=true");
+        log_debug!("[critic] grounded=true");
         return None;
     }
 
@@ -903,7 +853,7 @@ pub(crate) fn call_critic(
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
-    eprintln!(
+    log_info!(
         "[critic] grounded=false issues=[{issues}] claim=[{agent_claim}] evidence=[{evidence_shows}]"
     );
 
@@ -914,7 +864,14 @@ pub(crate) fn call_critic(
         .map(|s| s.to_string())
 }
 
-pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Result<AgentMessage, String> {
+/// Ask the model hook for the next assistant message. Every failure, from the
+/// builtin API clients or an external hook command, is an
+/// [`AethervaultError::Hook`](crate::AethervaultError::Hook).
+pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Result<AgentMessage, crate::AethervaultError> {
+    agent_hook_reply(hook, request).map_err(crate::AethervaultError::Hook)
+}
+
+fn agent_hook_reply(hook: &HookSpec, request: &AgentHookRequest) -> Result<AgentMessage, String> {
     let hook_cmd = match &hook.command {
         CommandSpec::String(cmd) => cmd.trim().to_ascii_lowercase(),
         CommandSpec::Array(items) => items
@@ -925,6 +882,12 @@ pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Re
     let is_builtin_claude = hook_cmd == "builtin:claude" || hook_cmd == "claude";
     let is_builtin_sonnet = hook_cmd == "builtin:sonnet" || hook_cmd == "sonnet";
 
+    if hook_cmd == "builtin:openai" || hook_cmd == "openai" {
+        return call_openai(request)
+            .map(|resp| resp.message)
+            .map_err(|e| format!("API error: {e}"));
+    }
+
     if is_builtin_claude || is_builtin_sonnet {
         // For builtin:sonnet, override model to Sonnet via env or hardcoded default
         let model_override = if is_builtin_sonnet {
@@ -942,7 +905,7 @@ pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Re
             Err(e) => {
                 let err_str = e.to_string();
                 if err_str.contains("transport") || err_str.contains("timed out") || err_str.contains("Network") {
-                    eprintln!("[call_agent_hook] first attempt failed ({err_str}), retrying in 3s...");
+                    log_warn!("[call_agent_hook] first attempt failed ({err_str}), retrying in 3s...");
                     thread::sleep(Duration::from_secs(3));
                     return call_claude_with_model(request, model_override.as_deref())
                         .map(|resp| resp.message)
@@ -968,7 +931,7 @@ pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Re
     for attempt in 0..=max_retries {
         if attempt > 0 {
             let delay = Duration::from_secs(3u64.pow(attempt as u32).min(30));
-            eprintln!(
+            log_warn!(
                 "[call_agent_hook] attempt {}/{} failed ({last_err}), retrying in {delay:?}...",
                 attempt,
                 max_retries + 1
@@ -978,7 +941,10 @@ pub(crate) fn call_agent_hook(hook: &HookSpec, request: &AgentHookRequest) -> Re
         match run_hook_command(&cmd, &value, timeout, "agent") {
             Ok(raw) => {
                 match serde_json::from_str::<AgentHookResponse>(&raw) {
-                    Ok(response) => return Ok(response.message),
+                    Ok(response) => {
+                        check_hook_protocol_version(response.protocol_version)?;
+                        return Ok(response.message);
+                    }
                     Err(e) => {
                         // JSON parse failure = NOT retryable (hook ran but returned garbage)
                         return Err(format!(
@@ -1014,3 +980,25 @@ fn is_hook_error_retryable(err: &str) -> bool {
     .iter()
     .any(|p| err.contains(p))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failing_model_hook_is_a_hook_error() {
+        let hook = HookSpec {
+            command: CommandSpec::Array(vec!["echo".to_string(), "not json".to_string()]),
+            timeout_ms: Some(5_000),
+            full_text: None,
+        };
+        let request = AgentHookRequest {
+            messages: Vec::new(),
+            tools: Vec::new(),
+            session: None,
+            protocol_version: AGENT_HOOK_PROTOCOL_VERSION,
+        };
+        let err = call_agent_hook(&hook, &request).unwrap_err();
+        assert_eq!(err.kind(), "hook");
+    }
+}
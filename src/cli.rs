@@ -1,15 +1,121 @@
 use std::path::PathBuf;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "aethervault")]
 #[command(about = "Hybrid retrieval over single-file .mv2 capsules", long_about = None)]
 #[command(version)]
 pub(crate) struct Cli {
+    /// More diagnostics on stderr (repeatable; see also AETHERVAULT_LOG).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub(crate) verbose: u8,
+    /// Fewer diagnostics on stderr (repeatable).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub(crate) quiet: u8,
+    /// Plain output even on a terminal (also honors NO_COLOR).
+    #[arg(long, global = true)]
+    pub(crate) no_color: bool,
+    /// Check payload checksums on every read (also AETHERVAULT_VERIFY_READS=1).
+    #[arg(long, global = true)]
+    pub(crate) verify: bool,
+    /// Wait this long for another process's write lock before failing with
+    /// "capsule busy" (default 5000; also AETHERVAULT_LOCK_TIMEOUT_MS).
+    #[arg(long, global = true)]
+    pub(crate) lock_timeout_ms: Option<u64>,
     #[command(subcommand)]
     pub(crate) command: Command,
 }
 
+/// Output format for list-producing commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// One pretty-printed JSON document
+    Json,
+    /// One JSON object per row
+    Ndjson,
+    /// Comma-separated rows with a header line
+    Csv,
+}
+
+/// How `query --group-by` buckets results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum GroupBy {
+    /// First URI segment after the scheme
+    Collection,
+    /// Frame track
+    Track,
+}
+
+/// Layout of the assembled `context` text. Every format keeps the `[rank]`
+/// (or `index="rank"`) anchor that matches `citations[].rank`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ContextFormat {
+    /// `[rank] uri title` header, then the body
+    #[default]
+    Plain,
+    /// A `###` heading per document with the URI underneath
+    Markdown,
+    /// `<document index uri title>` elements
+    Xml,
+}
+
+/// How `memory rollup` groups daily notes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum RollupPeriod {
+    /// One rollup per calendar day
+    Day,
+    /// One rollup per ISO week (e.g. 2026-W41)
+    #[default]
+    Week,
+}
+
+/// How `memory status` resolves files changed on both sides since the last sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ConflictStrategy {
+    /// Keep the workspace file and sync it into the capsule
+    Local,
+    /// Overwrite the workspace file with the capsule version
+    Capsule,
+    /// Keep the workspace file and write the capsule version next to it as `<file>.conflict`
+    Both,
+}
+
+/// Whether a bridge group chat is one conversation or one per member.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GroupSessions {
+    /// Each sender gets their own session and memory within the group
+    #[default]
+    PerUser,
+    /// Everyone in the group shares one session
+    Shared,
+}
+
+/// What the agent does when the model hook misses `--model-timeout-ms`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ModelTimeoutPolicy {
+    /// Count it as a hook failure and try again on the next step
+    #[default]
+    Retry,
+    /// End the run with the timeout as the final message
+    Abort,
+}
+
+impl OutputFormat {
+    /// Fold the deprecated `--json` flag into `--output`.
+    pub(crate) fn resolve(self, json: bool) -> Self {
+        if json {
+            OutputFormat::Json
+        } else {
+            self
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub(crate) enum Command {
     /// Create a new empty MV2 capsule.
@@ -28,6 +134,30 @@ pub(crate) enum Command {
         /// Do not write anything; only report what would change.
         #[arg(long)]
         dry_run: bool,
+        /// Delete frames under the collection whose source file no longer exists under --root.
+        #[arg(long)]
+        prune: bool,
+        /// Skip paths matching this gitignore-style glob (repeatable).
+        #[arg(long = "exclude")]
+        excludes: Vec<String>,
+        /// Do not read .gitignore / .aethervaultignore files.
+        #[arg(long)]
+        no_ignore: bool,
+        /// Binary files (NUL bytes or mostly non-UTF-8): `skip` them, or `store` the
+        /// payload unindexed with a kind inferred from the extension.
+        #[arg(long, default_value = "skip")]
+        binary: String,
+        /// Store payloads zstd-compressed (search text stays uncompressed;
+        /// needs the `compress` build feature).
+        #[arg(long)]
+        compress: bool,
+        /// After ingesting, keep watching --root and re-ingest changed files
+        /// (needs the `watch` build feature).
+        #[arg(long)]
+        watch: bool,
+        /// Quiet period before a burst of changes is ingested in --watch mode.
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
     },
 
     /// Put a single text payload into the capsule.
@@ -57,6 +187,10 @@ pub(crate) enum Command {
         /// Read payload from file
         #[arg(long)]
         file: Option<PathBuf>,
+        /// Store the payload zstd-compressed (search text stays uncompressed;
+        /// needs the `compress` build feature).
+        #[arg(long)]
+        compress: bool,
         /// Output JSON summary
         #[arg(long)]
         json: bool,
@@ -64,8 +198,12 @@ pub(crate) enum Command {
 
     /// Lexical search (BM25 via Tantivy) over the capsule.
     Search {
-        mv2: PathBuf,
-        query: String,
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Query text (MV2 may be omitted before it)
+        #[arg(value_name = "QUERY")]
+        query: Option<String>,
         /// Number of results
         #[arg(short = 'n', long, default_value_t = 10)]
         limit: usize,
@@ -75,15 +213,53 @@ pub(crate) enum Command {
         /// Snippet size in characters
         #[arg(long, default_value_t = 300)]
         snippet_chars: usize,
-        /// Output JSON (full search response)
-        #[arg(long)]
+        /// Strip Markdown markup from snippets (raw text stays in JSON output)
+        #[arg(long)]
+        plain_snippets: bool,
+        /// Marker shown where a snippet is cut short
+        #[arg(long, default_value = "…")]
+        snippet_ellipsis: String,
+        /// Keep snippets exactly as extracted (no whitespace cleanup or cut marker)
+        #[arg(long)]
+        raw_snippets: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Deprecated alias for `--output json`
+        #[arg(long, hide = true)]
         json: bool,
     },
 
+    /// Documents most like a given frame (`#N` or URI), excluding itself.
+    Similar {
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Frame id (`#N`) or URI of the source document (MV2 may be omitted before it)
+        #[arg(value_name = "ID")]
+        id: Option<String>,
+        /// Number of results
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        /// Restrict neighbours to a collection (URI prefix)
+        #[arg(short, long)]
+        collection: Option<String>,
+        /// Snippet size in characters
+        #[arg(long, default_value_t = 300)]
+        snippet_chars: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+
     /// Hybrid query: expansion → multi-lane retrieval → RRF → rerank → blend.
     Query {
-        mv2: PathBuf,
-        query: String,
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Query text (MV2 may be omitted before it)
+        #[arg(value_name = "QUERY")]
+        query: Option<String>,
         /// Number of results
         #[arg(short = 'n', long, default_value_t = 10)]
         limit: usize,
@@ -96,10 +272,31 @@ pub(crate) enum Command {
         /// Disable query expansion
         #[arg(long)]
         no_expand: bool,
+        /// Always expand, even when the lexical probe finds a strong hit
+        #[arg(long)]
+        no_skip_expansion_probe: bool,
+        /// Report expansion/rerank hook stderr as warnings even when the hook succeeds
+        #[arg(long)]
+        hook_debug: bool,
+        /// Include each vector-lane query and its raw per-hit similarities in JSON output (`vec_debug`)
+        #[arg(long)]
+        vec_debug: bool,
+        /// Attach a short `why` to each result: the lanes that found it, rerank/feedback effects, matched terms
+        #[arg(long)]
+        explain: bool,
+        /// Strip Markdown markup from snippets (raw text stays in JSON output)
+        #[arg(long)]
+        plain_snippets: bool,
+        /// Marker shown where a snippet is cut short
+        #[arg(long, default_value = "…")]
+        snippet_ellipsis: String,
+        /// Keep snippets exactly as extracted (no whitespace cleanup or cut marker)
+        #[arg(long)]
+        raw_snippets: bool,
         /// Max expansions per lane (lex/vector)
         #[arg(long, default_value_t = 2)]
         max_expansions: usize,
-        /// Expansion hook command (overrides built-in expansion)
+        /// Expansion hook command (overrides built-in expansion); shell string or JSON array
         #[arg(long)]
         expand_hook: Option<String>,
         /// Expansion hook timeout (ms)
@@ -111,7 +308,7 @@ pub(crate) enum Command {
         /// Reranker mode: local | hook | none
         #[arg(long, default_value = "local")]
         rerank: String,
-        /// Rerank hook command (overrides local rerank)
+        /// Rerank hook command (overrides local rerank); shell string or JSON array
         #[arg(long)]
         rerank_hook: Option<String>,
         /// Rerank hook timeout (ms)
@@ -132,25 +329,40 @@ pub(crate) enum Command {
         /// Max docs to rerank
         #[arg(long, default_value_t = 40)]
         rerank_docs: usize,
+        /// Share of the blended score taken from rerank (0-1) at every rank, instead of the position-aware default
+        #[arg(long)]
+        rerank_weight: Option<f32>,
         /// Chunk size (chars) for reranking
         #[arg(long, default_value_t = 1200)]
         rerank_chunk_chars: usize,
         /// Overlap (chars) between rerank chunks
         #[arg(long, default_value_t = 200)]
         rerank_chunk_overlap: usize,
-        /// Output JSON (includes plan + scores)
-        #[arg(long)]
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Deprecated alias for `--output json`
+        #[arg(long, hide = true)]
         json: bool,
         /// Output machine-friendly file list
         #[arg(long)]
         files: bool,
+        /// Group results by collection or track (ranking is unchanged)
+        #[arg(long, value_enum)]
+        group_by: Option<GroupBy>,
+        /// Max results per group with --group-by (0 = no cap)
+        #[arg(long, default_value_t = 0)]
+        per_group: usize,
         /// Print the query plan / expansion tree to stderr
         #[arg(long)]
         plan: bool,
         /// Log query + results back into the capsule (append-only)
         #[arg(long)]
         log: bool,
-        /// As-of timestamp (YYYY-MM-DD or YYYY-MM-DDTHH:MM)
+        /// Open the capsule read-only: no writes, never waits on or blocks a writer
+        #[arg(long, conflicts_with = "log")]
+        read_only: bool,
+        /// As-of point: date (YYYY-MM-DD or YYYY-MM-DDTHH:MM) or frame id (#N)
         #[arg(long)]
         asof: Option<String>,
         /// Temporal filter: before date (YYYY-MM-DD or YYYY-MM-DDTHH:MM)
@@ -162,12 +374,28 @@ pub(crate) enum Command {
         /// Feedback influence weight (0 disables)
         #[arg(long, default_value_t = 0.15)]
         feedback_weight: f32,
+        /// Prefer newer frames: scores get up to +30% for fresh frames, halving every N days (0 = off)
+        #[arg(long, default_value_t = 0.0)]
+        recency_halflife_days: f32,
+        /// Max concurrent lane searches (lexical expansions and vector queries)
+        #[arg(long, default_value_t = 4)]
+        search_jobs: usize,
+        /// Return the probe's top hits directly when one result clearly dominates
+        #[arg(long)]
+        fast_path: bool,
+        /// Top/second score ratio required to take the fast path
+        #[arg(long, default_value_t = 3.0)]
+        fast_path_margin: f32,
     },
 
     /// Build a prompt-ready context pack for agent harnesses.
     Context {
-        mv2: PathBuf,
-        query: String,
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Query text (MV2 may be omitted before it)
+        #[arg(value_name = "QUERY")]
+        query: Option<String>,
         /// Restrict to a collection (URI prefix)
         #[arg(short, long)]
         collection: Option<String>,
@@ -183,13 +411,34 @@ pub(crate) enum Command {
         /// Use full document text instead of snippets
         #[arg(long)]
         full: bool,
+        /// Widen each citation to its matched chunk plus N neighbouring chunks per side (ignored with --full)
+        #[arg(long, default_value_t = 0)]
+        window: usize,
+        /// Layout of the assembled context text
+        #[arg(long, value_enum, default_value_t = ContextFormat::Plain)]
+        format: ContextFormat,
         /// Disable query expansion
         #[arg(long)]
         no_expand: bool,
+        /// Always expand, even when the lexical probe finds a strong hit
+        #[arg(long)]
+        no_skip_expansion_probe: bool,
+        /// Report expansion/rerank hook stderr as warnings even when the hook succeeds
+        #[arg(long)]
+        hook_debug: bool,
+        /// Strip Markdown markup from snippets (raw text stays in JSON output)
+        #[arg(long)]
+        plain_snippets: bool,
+        /// Marker shown where a snippet is cut short
+        #[arg(long, default_value = "…")]
+        snippet_ellipsis: String,
+        /// Keep snippets exactly as extracted (no whitespace cleanup or cut marker)
+        #[arg(long)]
+        raw_snippets: bool,
         /// Max expansions per lane (lex/vector)
         #[arg(long, default_value_t = 2)]
         max_expansions: usize,
-        /// Expansion hook command (overrides built-in expansion)
+        /// Expansion hook command (overrides built-in expansion); shell string or JSON array
         #[arg(long)]
         expand_hook: Option<String>,
         /// Expansion hook timeout (ms)
@@ -201,7 +450,7 @@ pub(crate) enum Command {
         /// Reranker mode: local | hook | none
         #[arg(long, default_value = "local")]
         rerank: String,
-        /// Rerank hook command (overrides local rerank)
+        /// Rerank hook command (overrides local rerank); shell string or JSON array
         #[arg(long)]
         rerank_hook: Option<String>,
         /// Rerank hook timeout (ms)
@@ -222,7 +471,7 @@ pub(crate) enum Command {
         /// Print the query plan / expansion tree to stderr
         #[arg(long)]
         plan: bool,
-        /// As-of timestamp (YYYY-MM-DD or YYYY-MM-DDTHH:MM)
+        /// As-of point: date (YYYY-MM-DD or YYYY-MM-DDTHH:MM) or frame id (#N)
         #[arg(long)]
         asof: Option<String>,
         /// Temporal filter: before date (YYYY-MM-DD or YYYY-MM-DDTHH:MM)
@@ -234,15 +483,30 @@ pub(crate) enum Command {
         /// Feedback influence weight (0 disables)
         #[arg(long, default_value_t = 0.15)]
         feedback_weight: f32,
+        /// Share of the blended score taken from rerank (0-1) at every rank, instead of the position-aware default
+        #[arg(long)]
+        rerank_weight: Option<f32>,
+        /// Prefer newer frames: scores get up to +30% for fresh frames, halving every N days (0 = off)
+        #[arg(long, default_value_t = 0.0)]
+        recency_halflife_days: f32,
+        /// Max concurrent lane searches (lexical expansions and vector queries)
+        #[arg(long, default_value_t = 4)]
+        search_jobs: usize,
+        /// Return the probe's top hits directly when one result clearly dominates
+        #[arg(long)]
+        fast_path: bool,
+        /// Top/second score ratio required to take the fast path
+        #[arg(long, default_value_t = 3.0)]
+        fast_path_margin: f32,
     },
 
     /// Log an agent turn into the capsule.
     Log {
         mv2: PathBuf,
-        /// Session identifier (optional)
+        /// Session identifier (optional; default for `--batch` lines)
         #[arg(long)]
         session: Option<String>,
-        /// Role (user | assistant | system | tool)
+        /// Role (user | assistant | system | tool; default for `--batch` lines)
         #[arg(long, default_value = "user")]
         role: String,
         /// Text payload
@@ -254,23 +518,35 @@ pub(crate) enum Command {
         /// Extra JSON metadata (string)
         #[arg(long)]
         meta: Option<String>,
+        /// Read entries as JSON lines from stdin ({role,text,session,meta})
+        #[arg(long, conflicts_with_all = ["text", "file", "meta"])]
+        batch: bool,
+        /// With --batch, commit every N entries (0 = once at the end)
+        #[arg(long, default_value_t = 0, requires = "batch")]
+        commit_interval: usize,
     },
 
     /// Record feedback for a result (used to boost or suppress future rankings).
     Feedback {
         mv2: PathBuf,
         /// URI of the item
-        #[arg(long)]
-        uri: String,
+        #[arg(long, required_unless_present = "batch")]
+        uri: Option<String>,
         /// Score in [-1.0, 1.0] (negative suppresses)
-        #[arg(long)]
-        score: f32,
+        #[arg(long, required_unless_present = "batch", allow_hyphen_values = true)]
+        score: Option<f32>,
         /// Optional note or reason
         #[arg(long)]
         note: Option<String>,
-        /// Session identifier (optional)
+        /// Session identifier (optional; default for `--batch` lines)
         #[arg(long)]
         session: Option<String>,
+        /// Read events as JSON lines from stdin ({uri,score,note,session})
+        #[arg(long, conflicts_with_all = ["uri", "score", "note"])]
+        batch: bool,
+        /// With --batch, commit every N events (0 = once at the end)
+        #[arg(long, default_value_t = 0, requires = "batch")]
+        commit_interval: usize,
     },
 
     /// Precompute local embeddings for active frames (vector lane acceleration).
@@ -307,16 +583,25 @@ pub(crate) enum Command {
 
     /// Retrieve a document by URI (aether://...) or frame id (#123).
     Get {
-        mv2: PathBuf,
-        id: String,
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Frame id (`#N`) or URI (MV2 may be omitted before it)
+        #[arg(value_name = "ID")]
+        id: Option<String>,
         #[arg(long)]
         json: bool,
     },
 
     /// Capsule summary.
     Status {
-        mv2: PathBuf,
-        #[arg(long)]
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        mv2: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Deprecated alias for `--output json`
+        #[arg(long, hide = true)]
         json: bool,
     },
 
@@ -327,6 +612,27 @@ pub(crate) enum Command {
         command: ConfigCommand,
     },
 
+    /// Browse the collections (first URI segment) in a capsule.
+    Collections {
+        mv2: PathBuf,
+        #[command(subcommand)]
+        command: CollectionsCommand,
+    },
+
+    /// Inspect event triggers.
+    Trigger {
+        mv2: PathBuf,
+        #[command(subcommand)]
+        command: TriggerCommand,
+    },
+
+    /// Maintain agent memory stored in the capsule.
+    Memory {
+        mv2: PathBuf,
+        #[command(subcommand)]
+        command: MemoryCommand,
+    },
+
     /// Diff two capsules (by latest URI version).
     Diff {
         left: PathBuf,
@@ -337,8 +643,11 @@ pub(crate) enum Command {
         /// Limit listing size (0 = unlimited)
         #[arg(long, default_value_t = 200)]
         limit: usize,
-        /// Output JSON
-        #[arg(long)]
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Deprecated alias for `--output json`
+        #[arg(long, hide = true)]
         json: bool,
     },
 
@@ -358,12 +667,71 @@ pub(crate) enum Command {
         json: bool,
     },
 
+    /// Move a document to a new URI, or with --prefix every document under a URI prefix.
+    Move {
+        mv2: PathBuf,
+        from: String,
+        to: String,
+        /// Treat FROM and TO as URI prefixes (e.g. aether://notes/ aether://archive/notes/)
+        #[arg(long)]
+        prefix: bool,
+        /// Replace documents that already exist at the destination
+        #[arg(long)]
+        force: bool,
+        /// List the moves without writing
+        #[arg(long)]
+        dry_run: bool,
+        /// Output JSON summary
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Export one collection's active frames into a new standalone capsule.
+    Export {
+        mv2: PathBuf,
+        out: PathBuf,
+        /// Collection to export (frames under aethervault://<collection>/)
+        #[arg(long)]
+        collection: String,
+        /// Overwrite output if it exists
+        #[arg(long)]
+        force: bool,
+        /// Output JSON summary
+        #[arg(long)]
+        json: bool,
+    },
+
     /// MCP-compatible tool server (stdio JSON-RPC).
     Mcp {
         mv2: PathBuf,
         /// Read-only mode (disables write tools)
         #[arg(long)]
         read_only: bool,
+        /// Hide and refuse this tool (repeatable; adds to agent.disabled_tools)
+        #[arg(long = "disable-tool", value_name = "NAME")]
+        disable_tools: Vec<String>,
+    },
+
+    /// HTTP JSON API for web UIs: POST /query, POST /context, GET /get, GET /status.
+    Serve {
+        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+        #[arg(value_name = "MV2")]
+        mv2: Option<PathBuf>,
+        /// Bind address
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+        /// Bind port
+        #[arg(long, default_value_t = 8790)]
+        port: u16,
+        /// Open the capsule read-only (never writes or takes the write lock)
+        #[arg(long)]
+        read_only: bool,
+        /// Allow browser calls from this origin (repeatable; `*` allows any). Off by default.
+        #[arg(long = "cors-origin", value_name = "ORIGIN")]
+        cors_origins: Vec<String>,
+        /// Reuse results of identical queries for this many seconds while the capsule is unchanged (0 = off; default AETHERVAULT_QUERY_CACHE_TTL)
+        #[arg(long)]
+        query_cache_ttl: Option<u64>,
     },
 
     /// Minimal agent harness (hook-based LLM).
@@ -378,7 +746,7 @@ pub(crate) enum Command {
         /// Session identifier
         #[arg(long)]
         session: Option<String>,
-        /// LLM hook command (overrides config)
+        /// LLM hook command (overrides config); a JSON array runs without a shell
         #[arg(long)]
         model_hook: Option<String>,
         /// System prompt text
@@ -414,6 +782,27 @@ pub(crate) enum Command {
         /// Emit incremental progress lines in JSON for long-running sessions
         #[arg(long)]
         progress: bool,
+        /// Stream step, tool call and tool result events as NDJSON to this file
+        #[arg(long)]
+        events_file: Option<PathBuf>,
+        /// Max tool calls from one model turn run in parallel (default: CPU count or agent.tool_concurrency; 1 = sequential)
+        #[arg(long)]
+        tool_concurrency: Option<usize>,
+        /// Advertise every tool instead of base tools plus those relevant to the prompt
+        #[arg(long)]
+        all_tools: bool,
+        /// Give up on a model hook call after this many ms (default: agent.model_timeout_ms, else none)
+        #[arg(long)]
+        model_timeout_ms: Option<u64>,
+        /// After a model timeout: retry on the next step or abort the run
+        #[arg(long, value_enum)]
+        model_timeout_policy: Option<ModelTimeoutPolicy>,
+        /// Hide and refuse this tool (repeatable; adds to agent.disabled_tools)
+        #[arg(long = "disable-tool", value_name = "NAME")]
+        disable_tools: Vec<String>,
+        /// Run this tool before the first model turn, e.g. `context` (default: agent.force_first_tool)
+        #[arg(long, value_name = "NAME")]
+        force_tool: Option<String>,
     },
 
     /// Built-in model hooks (stdio JSON).
@@ -451,7 +840,7 @@ pub(crate) enum Command {
         /// Telegram chat id (env: AETHERVAULT_TELEGRAM_CHAT_ID)
         #[arg(long)]
         telegram_chat_id: Option<String>,
-        /// Override model hook command
+        /// Override model hook command (shell string or JSON array)
         #[arg(long)]
         model_hook: Option<String>,
         /// Max tool/LLM steps
@@ -474,7 +863,7 @@ pub(crate) enum Command {
         /// Timezone offset (e.g. -05:00)
         #[arg(long)]
         timezone: Option<String>,
-        /// Override model hook command
+        /// Override model hook command (shell string or JSON array)
         #[arg(long)]
         model_hook: Option<String>,
         /// Max tool/LLM steps
@@ -507,6 +896,15 @@ pub(crate) enum Command {
         redirect_base: Option<String>,
     },
 
+    /// List the agent tool catalog with each tool's approval and write policy.
+    Tools {
+        /// Also apply this capsule's `agent.disabled_tools` and `agent.custom_tools`
+        mv2: Option<PathBuf>,
+        /// Dump the full catalog, input schemas included, as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Approve a pending tool execution (human-in-the-loop).
     Approve {
         mv2: PathBuf,
@@ -525,6 +923,20 @@ pub(crate) enum Command {
         command: BridgeCommand,
     },
 
+    /// Read-only health gate for CI: exits 1 when any check fails.
+    Verify {
+        mv2: PathBuf,
+        /// Also recompute every active frame's payload checksum
+        #[arg(long)]
+        checksums: bool,
+        /// Also check that this many sampled frames are findable in the lexical index (0 = skip)
+        #[arg(long, default_value_t = 0)]
+        lex_sample: usize,
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Capsule maintenance (verification, index rebuild, compaction).
     Doctor {
         mv2: PathBuf,
@@ -540,12 +952,18 @@ pub(crate) enum Command {
         /// Rebuild vector index
         #[arg(long)]
         rebuild_vec: bool,
+        /// Check (read-only) that sampled frames are findable in the lexical index
+        #[arg(long)]
+        verify_lex: bool,
+        /// Number of frames sampled by --verify-lex
+        #[arg(long, default_value = "200")]
+        verify_sample: usize,
+        /// Check (read-only) every active frame's payload against its stored checksum
+        #[arg(long)]
+        verify_checksums: bool,
         /// Plan only (no changes)
         #[arg(long)]
         dry_run: bool,
-        /// Suppress debug output
-        #[arg(long)]
-        quiet: bool,
         /// Output JSON
         #[arg(long)]
         json: bool,
@@ -557,9 +975,29 @@ pub(crate) enum Command {
         /// Plan only (no changes)
         #[arg(long)]
         dry_run: bool,
-        /// Suppress debug output
+        /// Also delete every superseded version first (breaks `--asof` history; see `prune`)
+        #[arg(long)]
+        purge_superseded: bool,
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete superseded versions outside a retention policy.
+    Prune {
+        mv2: PathBuf,
+        /// Versions to keep per URI, including the current one
+        #[arg(long, default_value_t = 1)]
+        keep_versions: usize,
+        /// Only prune versions older than this date (YYYY-MM-DD or YYYY-MM-DDTHH:MM)
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Report what would be pruned without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Vacuum afterwards so the space is returned to disk
         #[arg(long)]
-        quiet: bool,
+        compact: bool,
         /// Output JSON
         #[arg(long)]
         json: bool,
@@ -615,15 +1053,54 @@ pub(crate) enum Command {
 pub(crate) enum HookCommand {
     /// Anthropic Claude hook (stdio JSON)
     Claude,
+    /// OpenAI-compatible chat completions hook (stdio JSON)
+    Openai,
+}
+
+/// Agent options shared by every bridge.
+#[derive(Args, Debug, Clone)]
+pub(crate) struct CommonBridgeArgs {
+    /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
+    #[arg(long)]
+    pub(crate) mv2: Option<PathBuf>,
+    /// Override model hook command (shell string or JSON array)
+    #[arg(long)]
+    pub(crate) model_hook: Option<String>,
+    /// Override system prompt
+    #[arg(long)]
+    pub(crate) system: Option<String>,
+    /// Disable memory context
+    #[arg(long)]
+    pub(crate) no_memory: bool,
+    /// Override memory query
+    #[arg(long)]
+    pub(crate) context_query: Option<String>,
+    /// Max results for memory context
+    #[arg(long, default_value_t = 20)]
+    pub(crate) context_results: usize,
+    /// Max bytes for memory context
+    #[arg(long, default_value_t = 40_000)]
+    pub(crate) context_max_bytes: usize,
+    /// Max tool/LLM steps
+    #[arg(long, default_value_t = 64)]
+    pub(crate) max_steps: usize,
+    /// Log turns to capsule
+    #[arg(long)]
+    pub(crate) log: bool,
+    /// Commit agent logs every N entries (1 = fsync each log)
+    #[arg(long, default_value_t = 1)]
+    pub(crate) log_commit_interval: usize,
+    /// Group chat sessions: per sender or shared (default: agent.group_sessions, else per-user)
+    #[arg(long, value_enum)]
+    pub(crate) group_sessions: Option<GroupSessions>,
 }
 
 #[derive(Subcommand)]
 pub(crate) enum BridgeCommand {
     /// Telegram long-polling bridge.
     Telegram {
-        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
         /// Telegram bot token (env: TELEGRAM_BOT_TOKEN)
         #[arg(long)]
         token: Option<String>,
@@ -633,77 +1110,31 @@ pub(crate) enum BridgeCommand {
         /// Max updates per poll
         #[arg(long, default_value_t = 50)]
         poll_limit: usize,
-        /// Override model hook command
-        #[arg(long)]
-        model_hook: Option<String>,
-        /// Override system prompt
-        #[arg(long)]
-        system: Option<String>,
-        /// Disable memory context
-        #[arg(long)]
-        no_memory: bool,
-        /// Override memory query
-        #[arg(long)]
-        context_query: Option<String>,
-        /// Max results for memory context
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        /// Max bytes for memory context
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        /// Max tool/LLM steps
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        /// Log turns to capsule
-        #[arg(long)]
-        log: bool,
-        /// Commit agent logs every N entries (1 = fsync each log)
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// WhatsApp (Twilio) webhook bridge.
     Whatsapp {
-        /// Capsule path (defaults to AETHERVAULT_MV2 or ./data/knowledge.mv2)
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
         /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
         /// Bind port
         #[arg(long, default_value_t = 8080)]
         port: u16,
-        /// Override model hook command
+        /// Twilio auth token for X-Twilio-Signature checks (env: TWILIO_AUTH_TOKEN)
         #[arg(long)]
-        model_hook: Option<String>,
-        /// Override system prompt
+        auth_token: Option<String>,
+        /// Webhook URL as configured in Twilio, e.g. https://example.com (default: https://<Host header>)
         #[arg(long)]
-        system: Option<String>,
-        /// Disable memory context
-        #[arg(long)]
-        no_memory: bool,
-        /// Override memory query
-        #[arg(long)]
-        context_query: Option<String>,
-        /// Max results for memory context
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        /// Max bytes for memory context
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        /// Max tool/LLM steps
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        /// Log turns to capsule
+        public_url: Option<String>,
+        /// Accept unsigned requests (local development only)
         #[arg(long)]
-        log: bool,
-        /// Commit agent logs every N entries (1 = fsync each log)
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
+        insecure_skip_verify: bool,
     },
     /// Slack Socket Mode bridge.
     Slack {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
         /// Slack bot token (env: SLACK_BOT_TOKEN)
         #[arg(long)]
         bot_token: Option<String>,
@@ -713,163 +1144,70 @@ pub(crate) enum BridgeCommand {
         /// Slack signing secret (env: SLACK_SIGNING_SECRET)
         #[arg(long)]
         signing_secret: Option<String>,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// Discord bridge (webhook receiver).
     Discord {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
+        /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        /// Bind port
         #[arg(long, default_value_t = 8082)]
         port: u16,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// Teams bridge (webhook receiver).
     Teams {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
+        /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        /// Bind port
         #[arg(long, default_value_t = 8083)]
         port: u16,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// Signal bridge (requires signal-cli).
     Signal {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
+        /// Account to receive as; enables `signal-cli receive` polling (env: SIGNAL_SENDER)
         #[arg(long)]
         sender: Option<String>,
+        /// Poll interval in seconds when receiving via signal-cli
+        #[arg(long, default_value_t = 5)]
+        poll_seconds: u64,
+        /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        /// Bind port
         #[arg(long, default_value_t = 8084)]
         port: u16,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// Matrix bridge (webhook receiver).
     Matrix {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
+        /// Matrix room id
         #[arg(long)]
         room: Option<String>,
+        /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        /// Bind port
         #[arg(long, default_value_t = 8085)]
         port: u16,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
     /// iMessage bridge (macOS only).
     IMessage {
-        #[arg(long)]
-        mv2: Option<PathBuf>,
+        #[command(flatten)]
+        common: CommonBridgeArgs,
+        /// Bind address
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
+        /// Bind port
         #[arg(long, default_value_t = 8086)]
         port: u16,
-        #[arg(long)]
-        model_hook: Option<String>,
-        #[arg(long)]
-        system: Option<String>,
-        #[arg(long)]
-        no_memory: bool,
-        #[arg(long)]
-        context_query: Option<String>,
-        #[arg(long, default_value_t = 20)]
-        context_results: usize,
-        #[arg(long, default_value_t = 40_000)]
-        context_max_bytes: usize,
-        #[arg(long, default_value_t = 64)]
-        max_steps: usize,
-        #[arg(long)]
-        log: bool,
-        #[arg(long, default_value_t = 1)]
-        log_commit_interval: usize,
     },
 }
 
@@ -901,6 +1239,90 @@ pub(crate) enum ConfigCommand {
     },
     /// List available config keys.
     List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Deprecated alias for `--output json`
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum CollectionsCommand {
+    /// List collections from frame URIs and config, with frame counts.
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// Alias for `--output json`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show one collection's config (roots, globs, description) and stats.
+    Info {
+        name: String,
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum TriggerCommand {
+    /// Evaluate a trigger now and show the prompt it would send, without running the agent or saving state.
+    Test {
+        /// Trigger id (see the trigger_list tool)
+        id: String,
+        /// Workspace folder added to the prompt (default: ./assistant or AETHERVAULT_WORKSPACE)
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Timezone offset like -05:00 (default: config.agent.timezone)
+        #[arg(long)]
+        timezone: Option<String>,
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum MemoryCommand {
+    /// Compare workspace memory files with the capsule (in-sync, local-ahead, capsule-ahead, conflict).
+    Status {
+        /// Workspace directory (defaults to config.agent.workspace or ./assistant)
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Resolve conflicts by keeping the local file, the capsule copy, or both
+        #[arg(long, value_enum)]
+        strategy: Option<ConflictStrategy>,
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Condense past daily notes into rollup notes under aethervault://memory/rollup/.
+    Rollup {
+        /// Group daily notes per day or per ISO week
+        #[arg(long, value_enum, default_value_t = RollupPeriod::Week)]
+        period: RollupPeriod,
+        /// Summarize with this hook instead of config.agent.model_hook / config.hooks.llm
+        #[arg(long)]
+        model_hook: Option<String>,
+        /// Concatenate the notes instead of summarizing them with a model
+        #[arg(long)]
+        concat: bool,
+        /// Rebuild rollups that already exist
+        #[arg(long)]
+        force: bool,
+        /// Mark the rolled-up daily notes as deleted
+        #[arg(long)]
+        deactivate: bool,
+        /// Also write rollups to <workspace>/memory/rollup/ (daily files move to memory/archive/ with --deactivate)
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Report the rollups that would be written without changing anything
+        #[arg(long)]
+        dry_run: bool,
         /// Output JSON
         #[arg(long)]
         json: bool,
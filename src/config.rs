@@ -1,5 +1,5 @@
 #[allow(unused_imports)]
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
 use std::io::Write;
 use std::process::Stdio;
@@ -10,8 +10,8 @@ use std::thread;
 use std::time::Instant;
 
 use super::{
-    build_external_command, dedup_keep_order, CapsuleConfig, CommandSpec, ConfigEntry,
-    ExpansionHookInput, ExpansionHookOutput, HookSpec, RerankHookInput, RerankHookOutput,
+    blake3_hash, build_external_command, dedup_keep_order, normalize_collection,
+    uri_collection, CapsuleConfig, CollectionInfo, CollectionSummary, CommandSpec, ConfigEntry, ExpansionHookInput, ExpansionHookOutput, HookSpec, RerankHookInput, RerankHookOutput,
 };
 
 use crate::memory_db::MemoryDb;
@@ -19,6 +19,32 @@ use crate::memory_db::MemoryDb;
 const NO_DEADLINE_TIMEOUT_MS: u64 = u64::MAX;
 const HOOK_STREAM_CAP_BYTES: usize = 64 * 1024;
 const HOOK_STREAM_READ_SLEEP_MS: u64 = 10;
+const HOOK_STDERR_TAIL_CHARS: usize = 500;
+
+/// Stdout and stderr of a hook process that exited successfully.
+pub(crate) struct HookCapture {
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+}
+
+/// Last few hundred characters of a hook's stderr, for warnings and errors.
+pub(crate) fn stderr_tail(stderr: &str) -> String {
+    let trimmed = stderr.trim();
+    let count = trimmed.chars().count();
+    if count <= HOOK_STDERR_TAIL_CHARS {
+        return trimmed.to_string();
+    }
+    let tail: String = trimmed.chars().skip(count - HOOK_STDERR_TAIL_CHARS).collect();
+    format!("...{tail}")
+}
+
+fn with_stderr_tail(message: String, stderr: &str) -> String {
+    if stderr.trim().is_empty() {
+        message
+    } else {
+        format!("{message} (stderr: {})", stderr_tail(stderr))
+    }
+}
 
 pub(crate) fn load_config_entry(db: &MemoryDb, key: &str) -> Option<Vec<u8>> {
     db.config_get(key)
@@ -29,12 +55,40 @@ pub(crate) fn load_capsule_config(db: &MemoryDb) -> Option<CapsuleConfig> {
     serde_json::from_slice(&bytes).ok()
 }
 
+/// Config entries that would be ignored at load time: values that are not
+/// JSON, and an `index` entry that does not parse as a `CapsuleConfig`.
+pub(crate) fn config_problems(db: &MemoryDb) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (key, _) in db.config_list() {
+        let Some(bytes) = load_config_entry(db, &key) else {
+            continue;
+        };
+        if let Err(e) = serde_json::from_slice::<serde_json::Value>(&bytes) {
+            problems.push(format!("config '{key}' is not valid JSON: {e}"));
+        } else if key == "index"
+            && let Err(e) = serde_json::from_slice::<CapsuleConfig>(&bytes)
+        {
+            problems.push(format!("config 'index' does not match the capsule config schema: {e}"));
+        }
+    }
+    problems
+}
+
+/// Store `bytes` under `key`. Returns `false` (and writes nothing) when the
+/// stored content already hashes the same, so re-saves don't churn versions.
 pub(crate) fn save_config_entry(
     db: &MemoryDb,
     key: &str,
     bytes: &[u8],
-) -> Result<(), String> {
-    db.config_set(key, bytes)
+) -> Result<bool, String> {
+    if db
+        .config_get(key)
+        .is_some_and(|current| blake3_hash(&current) == blake3_hash(bytes))
+    {
+        return Ok(false);
+    }
+    db.config_set(key, bytes)?;
+    Ok(true)
 }
 
 pub(crate) fn list_config_entries(db: &MemoryDb) -> Vec<ConfigEntry> {
@@ -48,6 +102,62 @@ pub(crate) fn list_config_entries(db: &MemoryDb) -> Vec<ConfigEntry> {
         .collect()
 }
 
+const COLLECTION_RECENT_URIS: usize = 5;
+
+/// Every collection with active frames or a `config.collections` entry, by name.
+pub(crate) fn list_collections(db: &MemoryDb) -> Vec<CollectionSummary> {
+    let config = load_capsule_config(db).unwrap_or_default();
+    let mut stats: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+    for (uri, timestamp) in db.active_uri_timestamps() {
+        if let Some(name) = uri_collection(&uri) {
+            let entry = stats.entry(name).or_insert((0, i64::MIN));
+            entry.0 += 1;
+            entry.1 = entry.1.max(timestamp);
+        }
+    }
+    for name in config.collections.keys() {
+        stats.entry(normalize_collection(name)).or_insert((0, i64::MIN));
+    }
+    stats
+        .into_iter()
+        .map(|(name, (frames, newest))| {
+            let configured = config.collections.get(&name);
+            CollectionSummary {
+                frames,
+                last_updated: (frames > 0).then_some(newest),
+                configured: configured.is_some(),
+                description: configured.and_then(|c| c.description.clone()),
+                name,
+            }
+        })
+        .collect()
+}
+
+/// Config and stats for one collection; errors when it has neither.
+pub(crate) fn collection_info(db: &MemoryDb, name: &str) -> Result<CollectionInfo, String> {
+    let name = normalize_collection(name);
+    let summary = list_collections(db)
+        .into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("no collection named {name:?}"))?;
+    let config = load_capsule_config(db).unwrap_or_default();
+    let configured = config.collections.get(&name).cloned().unwrap_or_default();
+    let mut frames: Vec<(String, i64)> = db
+        .active_uri_timestamps()
+        .into_iter()
+        .filter(|(uri, _)| uri_collection(uri).as_deref() == Some(name.as_str()))
+        .collect();
+    frames.sort_by_key(|(_, ts)| std::cmp::Reverse(*ts));
+    Ok(CollectionInfo {
+        summary,
+        first_updated: frames.last().map(|(_, ts)| *ts),
+        roots: configured.roots,
+        globs: configured.globs,
+        context: configured.context,
+        recent: frames.into_iter().take(COLLECTION_RECENT_URIS).map(|(uri, _)| uri).collect(),
+    })
+}
+
 pub(crate) fn command_spec_to_vec(spec: &CommandSpec) -> Vec<String> {
     match spec {
         CommandSpec::Array(items) => items.clone(),
@@ -67,6 +177,17 @@ pub(crate) fn run_hook_command(
     timeout_ms: u64,
     kind: &str,
 ) -> Result<String, String> {
+    run_hook_command_capture(command, input, timeout_ms, kind).map(|capture| capture.stdout)
+}
+
+/// Like [`run_hook_command`], but also hands back stderr on success. Errors
+/// carry a tail of stderr whenever the hook wrote any.
+pub(crate) fn run_hook_command_capture(
+    command: &[String],
+    input: &serde_json::Value,
+    timeout_ms: u64,
+    kind: &str,
+) -> Result<HookCapture, String> {
     if command.is_empty() {
         return Err("hook command is empty".into());
     }
@@ -74,7 +195,8 @@ pub(crate) fn run_hook_command(
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .env("KAIROS_HOOK", kind);
+        .env("KAIROS_HOOK", kind)
+        .env("AETHERVAULT_SUBAGENT_DEPTH", crate::current_subagent_depth().to_string());
 
     let mut child = cmd.spawn().map_err(|e| format!("spawn failed: {e}"))?;
     if let Some(mut stdin) = child.stdin.take() {
@@ -182,33 +304,47 @@ pub(crate) fn run_hook_command(
     let (stdout, stdout_truncated) = collect(&mut stdout_handle);
     let (stderr, stderr_truncated) = collect(&mut stderr_handle);
 
+    let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+
     if timed_out {
-        return Err(format!("hook '{kind}' timed out after {effective_timeout_ms}ms"));
+        return Err(with_stderr_tail(
+            format!("hook '{kind}' timed out after {effective_timeout_ms}ms"),
+            &stderr,
+        ));
     }
 
     let status = status?;
     if !status.success() {
-        let mut stderr = String::from_utf8_lossy(&stderr).trim().to_string();
         if stderr.is_empty() {
             if stderr_truncated {
                 return Err("hook error: stderr output exceeded capture limit".into());
             }
             return Err("hook exited with error".into());
         }
+        let mut message = format!("hook error: {}", stderr_tail(&stderr));
         if stderr_truncated {
-            stderr.push_str(" (stderr output truncated)");
+            message.push_str(" (stderr output truncated)");
         }
-        return Err(format!("hook error: {stderr}"));
+        return Err(message);
     }
 
     let stdout = String::from_utf8_lossy(&stdout).trim().to_string();
     if stdout.is_empty() {
         if stdout_truncated {
-            return Err("hook output exceeded capture limit".into());
+            return Err(with_stderr_tail("hook output exceeded capture limit".into(), &stderr));
         }
-        return Err("hook returned empty output".into());
+        return Err(with_stderr_tail("hook returned empty output".into(), &stderr));
+    }
+    Ok(HookCapture { stdout, stderr })
+}
+
+/// A hook command from the command line: a JSON array of strings
+/// (`["python", "hook.py"]`) runs directly, anything else through the shell.
+pub(crate) fn parse_cli_command(cmd: String) -> CommandSpec {
+    match serde_json::from_str::<Vec<String>>(&cmd) {
+        Ok(items) if !items.is_empty() => CommandSpec::Array(items),
+        _ => CommandSpec::String(cmd),
     }
-    Ok(stdout)
 }
 
 pub(crate) fn resolve_hook_spec(
@@ -219,7 +355,7 @@ pub(crate) fn resolve_hook_spec(
 ) -> Option<HookSpec> {
     if let Some(cmd) = cli_command {
         return Some(HookSpec {
-            command: CommandSpec::String(cmd),
+            command: parse_cli_command(cmd),
             timeout_ms: Some(cli_timeout_ms),
             full_text: force_full_text,
         });
@@ -242,9 +378,10 @@ pub(crate) fn run_expansion_hook(
     let cmd = command_spec_to_vec(&hook.command);
     let timeout = hook.timeout_ms.unwrap_or(NO_DEADLINE_TIMEOUT_MS);
     let value = serde_json::to_value(input).map_err(|e| format!("hook input: {e}"))?;
-    let raw = run_hook_command(&cmd, &value, timeout, "expansion")?;
-    let mut output: ExpansionHookOutput =
-        serde_json::from_str(&raw).map_err(|e| format!("hook output: {e}"))?;
+    let capture = run_hook_command_capture(&cmd, &value, timeout, "expansion")?;
+    let mut output: ExpansionHookOutput = serde_json::from_str(&capture.stdout)
+        .map_err(|e| with_stderr_tail(format!("hook output: {e}"), &capture.stderr))?;
+    output.stderr = capture.stderr;
     output.lex = dedup_keep_order(output.lex);
     output.vec = dedup_keep_order(output.vec);
     Ok(output)
@@ -254,9 +391,10 @@ pub(crate) fn run_rerank_hook(hook: &HookSpec, input: &RerankHookInput) -> Resul
     let cmd = command_spec_to_vec(&hook.command);
     let timeout = hook.timeout_ms.unwrap_or(NO_DEADLINE_TIMEOUT_MS);
     let value = serde_json::to_value(input).map_err(|e| format!("hook input: {e}"))?;
-    let raw = run_hook_command(&cmd, &value, timeout, "rerank")?;
-    let mut output: RerankHookOutput =
-        serde_json::from_str(&raw).map_err(|e| format!("hook output: {e}"))?;
+    let capture = run_hook_command_capture(&cmd, &value, timeout, "rerank")?;
+    let mut output: RerankHookOutput = serde_json::from_str(&capture.stdout)
+        .map_err(|e| with_stderr_tail(format!("hook output: {e}"), &capture.stderr))?;
+    output.stderr = capture.stderr;
     for item in output.items.drain(..) {
         output.scores.insert(item.key.clone(), item.score);
         if let Some(snippet) = item.snippet {
@@ -273,3 +411,93 @@ pub(crate) fn checksum_hex(checksum: &[u8; 32]) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hook that drains its JSON input before running `script`.
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), format!("cat >/dev/null; {script}")]
+    }
+
+    #[test]
+    fn cli_hooks_accept_json_arrays() {
+        let spec = resolve_hook_spec(Some(r#"["python", "my hooks/llm.py"]"#.into()), 1_000, None, None).unwrap();
+        assert!(matches!(&spec.command, CommandSpec::Array(a) if a[1] == "my hooks/llm.py"));
+        for shell in ["[ -f hook.py ] && python hook.py", "[]", "python hook.py"] {
+            assert!(matches!(parse_cli_command(shell.into()), CommandSpec::String(s) if s == shell));
+        }
+    }
+
+    #[test]
+    fn hook_failures_carry_a_stderr_tail() {
+        let hook = HookSpec {
+            command: CommandSpec::Array(sh("echo 'bad model path' >&2; echo 'not json'")),
+            timeout_ms: Some(5_000),
+            full_text: None,
+        };
+        let input = ExpansionHookInput {
+            schema_version: crate::EXPANSION_HOOK_SCHEMA_VERSION,
+            query: "q".into(),
+            max_expansions: 1,
+            scope: None,
+            temporal: None,
+        };
+        let err = run_expansion_hook(&hook, &input).unwrap_err();
+        assert!(err.contains("stderr: bad model path"), "{err}");
+
+        let empty = run_hook_command(&sh("echo oops >&2"), &serde_json::json!({}), 5_000, "test").unwrap_err();
+        assert_eq!(empty, "hook returned empty output (stderr: oops)");
+
+        let capture =
+            run_hook_command_capture(&sh("echo note >&2; echo '{}'"), &serde_json::json!({}), 5_000, "test").unwrap();
+        assert_eq!((capture.stdout.as_str(), capture.stderr.as_str()), ("{}", "note"));
+
+        let long = "x".repeat(HOOK_STDERR_TAIL_CHARS + 10);
+        assert_eq!(stderr_tail(&long).len(), HOOK_STDERR_TAIL_CHARS + 3);
+    }
+
+    #[test]
+    fn collections_come_from_uris_and_config() {
+        let path = std::env::temp_dir().join(format!("aethervault_collections_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for (uri, ts) in [("aether://notes/a.md", 100), ("aether://notes/b.md", 300), ("aether://docs/x.md", 200)] {
+            let options = crate::memory_db::PutOptions { uri: Some(uri.into()), timestamp: Some(ts), ..Default::default() };
+            db.put_bytes_with_options(b"x", options).unwrap();
+        }
+        let config = r#"{"collections":{"docs":{"roots":["~/docs"],"description":"Manuals"},"empty":{}}}"#;
+        save_config_entry(&db, "index", config.as_bytes()).unwrap();
+
+        let list = list_collections(&db);
+        let names: Vec<_> = list.iter().map(|c| (c.name.as_str(), c.frames, c.configured)).collect();
+        assert_eq!(names, [("docs", 1, true), ("empty", 0, true), ("notes", 2, false)]);
+
+        let info = collection_info(&db, "notes/").unwrap();
+        assert_eq!((info.first_updated, info.summary.last_updated), (Some(100), Some(300)));
+        assert_eq!(info.recent, ["aether://notes/b.md", "aether://notes/a.md"]);
+        assert_eq!(collection_info(&db, "docs").unwrap().roots, ["~/docs"]);
+        assert!(collection_info(&db, "missing").is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn identical_config_is_written_once() {
+        let path = std::env::temp_dir().join(format!("aethervault_config_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+
+        let raw = r#"{"zeta":1,"alpha":{"b":2,"a":1},"context":"hi","mid":[3]}"#;
+        let config: CapsuleConfig = serde_json::from_str(raw).unwrap();
+        let first = serde_json::to_vec_pretty(&config).unwrap();
+        let reparsed: CapsuleConfig = serde_json::from_slice(&first).unwrap();
+        let second = serde_json::to_vec_pretty(&reparsed).unwrap();
+        assert_eq!(first, second);
+
+        assert!(save_config_entry(&db, "index", &first).unwrap());
+        assert!(!save_config_entry(&db, "index", &second).unwrap());
+        assert!(save_config_entry(&db, "index", b"{}").unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{AgentConfig, ApprovalEntry, CapsuleConfig, HookConfig, TriggerEntry};
+use crate::{
+    AgentConfig, ApprovalEntry, CapsuleConfig, HookConfig, SignalThresholds, SubagentSpec, TriggerEntry,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub(crate) struct FileConfig {
@@ -19,6 +21,8 @@ pub(crate) struct FileConfig {
     pub(crate) oauth_google: Option<serde_json::Value>,
     #[serde(default)]
     pub(crate) oauth_microsoft: Option<serde_json::Value>,
+    #[serde(default)]
+    pub(crate) signal_thresholds: Option<SignalThresholds>,
 }
 
 pub(crate) fn config_file_path(workspace: &Path) -> PathBuf {
@@ -79,11 +83,7 @@ pub(crate) fn save_config_to_file(
             return Err("index config must be a JSON object".to_string());
         };
 
-        let json = serde_json::to_string_pretty(&raw).map_err(|e| e.to_string())?;
-        let tmp = path.with_extension("json.tmp");
-        std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
-        std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
-        return Ok(());
+        return write_config_atomic(&path, &raw);
     }
 
     let mut root = match std::fs::read_to_string(&path) {
@@ -98,22 +98,77 @@ pub(crate) fn save_config_to_file(
     if let serde_json::Value::Object(ref mut obj) = root {
         obj.insert(key.to_string(), value);
     }
-    let json = serde_json::to_string_pretty(&root).map_err(|e| e.to_string())?;
+    write_config_atomic(&path, &root)
+}
+
+/// Write via a temp file + rename so readers never see a half-written config.
+fn write_config_atomic(path: &Path, value: &serde_json::Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
     let tmp = path.with_extension("json.tmp");
     std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
-    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Replace `agent.subagents` in config.json, keeping every other key.
+pub(crate) fn save_subagents_to_file(
+    workspace: &Path,
+    subagents: &[SubagentSpec],
+) -> Result<(), String> {
+    let path = config_file_path(workspace);
+    let mut root = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str::<serde_json::Value>(&contents)
+            .map_err(|e| format!("config.json: {e}"))?,
+        Err(_) => serde_json::json!({}),
+    };
+    if !root.is_object() {
+        return Err("config.json must be a JSON object".to_string());
+    }
+    if !root.get("agent").is_some_and(|v| v.is_object()) {
+        root["agent"] = serde_json::json!({});
+    }
+    root["agent"]["subagents"] = serde_json::to_value(subagents).map_err(|e| e.to_string())?;
+    write_config_atomic(&path, &root)
+}
+
 impl FileConfig {
     fn into_capsule_config(self) -> CapsuleConfig {
         CapsuleConfig {
             context: None,
-            collections: HashMap::new(),
+            collections: BTreeMap::new(),
             hooks: self.hooks,
             agent: Some(self.agent),
-            extra: HashMap::new(),
+            signal_thresholds: self.signal_thresholds,
+            extra: BTreeMap::new(),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_subagents_keeps_other_keys() {
+        let dir = std::env::temp_dir().join(format!("aethervault_cfg_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            config_file_path(&dir),
+            r#"{"agent":{"max_steps":7},"other":1}"#,
+        )
+        .unwrap();
+        let spec: SubagentSpec =
+            serde_json::from_value(serde_json::json!({"name": "reviewer", "system": "Review."}))
+                .unwrap();
+        save_subagents_to_file(&dir, &[spec]).unwrap();
+
+        let root: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(config_file_path(&dir)).unwrap()).unwrap();
+        assert_eq!(root["other"], 1);
+        assert_eq!(root["agent"]["max_steps"], 7);
+        assert_eq!(root["agent"]["subagents"][0]["name"], "reviewer");
+        let config = load_config_from_file(&dir);
+        assert_eq!(config.agent.unwrap().subagents.len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -173,7 +173,7 @@ pub(crate) fn put_with_consolidation(
             })
         }
         ConsolidationDecision::Noop { existing_id } => {
-            eprintln!(
+            log_debug!(
                 "[consolidation] NOOP: content similar to frame #{existing_id}, skipping write"
             );
             Ok(ConsolidationResult {
@@ -0,0 +1,81 @@
+//! Error type for the query pipeline and tool execution, so embedding callers
+//! can tell a missing tool from a bad argument from a capsule failure.
+
+use std::fmt;
+use std::io;
+
+/// Failure from [`crate::HybridSearch`], the query pipeline or tool execution.
+/// `Display` is the bare message the CLI has always printed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AethervaultError {
+    /// Opening, reading or writing the capsule failed.
+    Store(String),
+    Io(io::Error),
+    /// The agent's model hook failed. Expansion and rerank hook failures only
+    /// add query warnings; the pipeline falls back without them.
+    Hook(String),
+    /// The request itself is unusable (empty query, malformed arguments).
+    Validation(String),
+    /// No tool, frame or entry by that name.
+    NotFound(String),
+    /// Refused by policy: read-only mode, a disabled tool, the exec allowlist.
+    Denied(String),
+    /// A tool ran and failed; the message is the tool's own.
+    Tool(String),
+}
+
+impl AethervaultError {
+    pub(crate) fn store(err: impl fmt::Display) -> Self {
+        Self::Store(err.to_string())
+    }
+
+    /// Stable snake_case name of the variant, for JSON error payloads.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Store(_) => "store",
+            Self::Io(_) => "io",
+            Self::Hook(_) => "hook",
+            Self::Validation(_) => "validation",
+            Self::NotFound(_) => "not_found",
+            Self::Denied(_) => "denied",
+            Self::Tool(_) => "tool",
+        }
+    }
+}
+
+impl fmt::Display for AethervaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Store(msg)
+            | Self::Hook(msg)
+            | Self::Validation(msg)
+            | Self::NotFound(msg)
+            | Self::Denied(msg)
+            | Self::Tool(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for AethervaultError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AethervaultError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Lets `String`-error helpers keep using `?` on these results.
+impl From<AethervaultError> for String {
+    fn from(err: AethervaultError) -> Self {
+        err.to_string()
+    }
+}
@@ -0,0 +1,325 @@
+//! Folder ingest: the one-shot walk behind `ingest` and, with the `watch`
+//! feature, the incremental re-ingest loop behind `ingest --watch`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::Gitignore;
+
+use crate::memory_db::{MemoryDb, PutOptions};
+use crate::{
+    blake3_hash, glob_matches, infer_title, is_extension_allowed, kind_for_extension,
+    looks_binary, normalize_collection, uri_for_path,
+};
+
+const CUSTOM_IGNORE_FILE: &str = ".aethervaultignore";
+
+/// Filters and write mode shared by the initial walk and watch updates.
+pub(crate) struct IngestOptions {
+    pub(crate) collection: String,
+    pub(crate) root: PathBuf,
+    pub(crate) exts: Vec<String>,
+    pub(crate) includes: Gitignore,
+    pub(crate) excludes: Gitignore,
+    pub(crate) no_ignore: bool,
+    pub(crate) store_binary: bool,
+    pub(crate) compress: bool,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct IngestStats {
+    pub(crate) scanned: usize,
+    pub(crate) ingested: usize,
+    pub(crate) updated: usize,
+    pub(crate) skipped: usize,
+    pub(crate) excluded: usize,
+    pub(crate) binary: usize,
+    /// URIs of every file seen under the root (used by `--prune`).
+    pub(crate) seen_uris: HashSet<String>,
+}
+
+impl IngestStats {
+    pub(crate) fn summary(&self) -> String {
+        format!(
+            "scanned={} ingest={} update={} skip={} excluded={} binary={}",
+            self.scanned, self.ingested, self.updated, self.skipped, self.excluded, self.binary
+        )
+    }
+}
+
+fn walk_files(start: &Path, no_ignore: bool) -> impl Iterator<Item = PathBuf> {
+    let mut walker = ignore::WalkBuilder::new(start);
+    walker
+        .follow_links(false)
+        .hidden(false)
+        .parents(false)
+        .ignore(false)
+        .git_global(false)
+        .git_exclude(false)
+        .git_ignore(!no_ignore)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    if !no_ignore {
+        walker.add_custom_ignore_filename(CUSTOM_IGNORE_FILE);
+    }
+    walker
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+}
+
+/// Walk the whole root, writing new versions for added or changed files.
+pub(crate) fn ingest_tree(
+    db: &MemoryDb,
+    opts: &IngestOptions,
+) -> Result<IngestStats, Box<dyn std::error::Error>> {
+    let mut stats = IngestStats::default();
+    for path in walk_files(&opts.root, opts.no_ignore) {
+        ingest_path(db, opts, &path, &mut stats)?;
+    }
+    Ok(stats)
+}
+
+fn ingest_path(
+    db: &MemoryDb,
+    opts: &IngestOptions,
+    path: &Path,
+    stats: &mut IngestStats,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !is_extension_allowed(path, &opts.exts) {
+        return Ok(());
+    }
+    if glob_matches(&opts.excludes, path, false)
+        || (!opts.includes.is_empty() && !glob_matches(&opts.includes, path, false))
+    {
+        stats.excluded += 1;
+        return Ok(());
+    }
+    let Ok(relative) = path.strip_prefix(&opts.root) else {
+        return Ok(());
+    };
+
+    stats.scanned += 1;
+
+    let bytes = fs::read(path)?;
+    let is_binary = looks_binary(&bytes);
+    if is_binary {
+        stats.binary += 1;
+        if !opts.store_binary {
+            return Ok(());
+        }
+    }
+    let file_hash = blake3_hash(&bytes);
+    let uri = uri_for_path(&opts.collection, relative);
+    stats.seen_uris.insert(uri.clone());
+    let title = if is_binary {
+        infer_title(path, &[])
+    } else {
+        infer_title(path, &bytes)
+    };
+
+    let existing_checksum = db.frame_by_uri(&uri).ok().map(|frame| frame.checksum);
+
+    if existing_checksum.is_some_and(|c| c == *file_hash.as_bytes()) {
+        stats.skipped += 1;
+        return Ok(());
+    }
+
+    if opts.dry_run {
+        if existing_checksum.is_some() {
+            stats.updated += 1;
+        } else {
+            stats.ingested += 1;
+        }
+        return Ok(());
+    }
+
+    let meta = fs::metadata(path).ok();
+    let mtime_ms = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis().to_string())
+        .unwrap_or_default();
+    let size_bytes = meta
+        .as_ref()
+        .map(|m| m.len().to_string())
+        .unwrap_or_default();
+
+    let mut options = PutOptions {
+        uri: Some(uri),
+        title: Some(title),
+        track: Some(normalize_collection(&opts.collection)),
+        compress: opts.compress,
+        ..Default::default()
+    };
+    if is_binary {
+        options.kind = Some(kind_for_extension(path).to_string());
+        options.search_text = Some(String::new());
+        options.binary = true;
+    } else {
+        options.kind = Some("text/markdown".to_string());
+    }
+    options
+        .extra_metadata
+        .insert("source_path".into(), path.to_string_lossy().into_owned());
+    options.extra_metadata.insert(
+        "relative_path".into(),
+        relative.to_string_lossy().into_owned(),
+    );
+    if !mtime_ms.is_empty() {
+        options.extra_metadata.insert("mtime_ms".into(), mtime_ms);
+    }
+    if !size_bytes.is_empty() {
+        options
+            .extra_metadata
+            .insert("size_bytes".into(), size_bytes);
+    }
+
+    db.put_bytes_with_options(&bytes, options)?;
+
+    if existing_checksum.is_some() {
+        stats.updated += 1;
+    } else {
+        stats.ingested += 1;
+    }
+    Ok(())
+}
+
+/// Whether a single changed path would be skipped by the walker: inside
+/// `.git`, outside the root, or matched by an ignore file between it and the
+/// root (nearest file wins, `.aethervaultignore` before `.gitignore`).
+#[cfg_attr(not(any(feature = "watch", test)), allow(dead_code))]
+fn path_is_ignored(root: &Path, path: &Path, no_ignore: bool) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    if relative.components().any(|c| c.as_os_str() == ".git") {
+        return true;
+    }
+    if no_ignore {
+        return false;
+    }
+    let is_dir = path.is_dir();
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        for name in [CUSTOM_IGNORE_FILE, ".gitignore"] {
+            let file = current.join(name);
+            if !file.is_file() {
+                continue;
+            }
+            let (matcher, _) = Gitignore::new(&file);
+            let matched = matcher.matched_path_or_any_parents(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        if current == root {
+            break;
+        }
+        dir = current.parent();
+    }
+    false
+}
+
+/// Watch the root and re-ingest changed files after `debounce` of quiet.
+/// With `prune`, deleted files drop their frames. Runs until interrupted.
+#[cfg(feature = "watch")]
+pub(crate) fn watch_ingest(
+    db: &MemoryDb,
+    opts: &IngestOptions,
+    prune: bool,
+    debounce: std::time::Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use notify::{RecursiveMode, Watcher};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&opts.root, RecursiveMode::Recursive)?;
+    log_info!("Watching {} for changes (Ctrl-C to stop)", opts.root.display());
+
+    let collect = |event: notify::Result<notify::Event>, changed: &mut HashSet<PathBuf>| match event {
+        Ok(event) if !event.kind.is_access() => changed.extend(event.paths),
+        Ok(_) => {}
+        Err(e) => log_warn!("watch error: {e}"),
+    };
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = HashSet::new();
+        collect(event, &mut changed);
+        // Debounce: keep draining until the tree has been quiet for a while.
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            collect(event, &mut changed);
+        }
+
+        let mut paths: Vec<PathBuf> = changed.into_iter().collect();
+        paths.sort();
+        let mut stats = IngestStats::default();
+        let mut pruned = 0usize;
+        for path in paths {
+            if path_is_ignored(&opts.root, &path, opts.no_ignore) {
+                continue;
+            }
+            if path.is_dir() {
+                // Directories moved into the tree arrive as a single event.
+                for file in walk_files(&path, opts.no_ignore) {
+                    if path_is_ignored(&opts.root, &file, opts.no_ignore) {
+                        continue;
+                    }
+                    if let Err(e) = ingest_path(db, opts, &file, &mut stats) {
+                        log_warn!("skipping {}: {e}", file.display());
+                    }
+                }
+            } else if path.is_file() {
+                if let Err(e) = ingest_path(db, opts, &path, &mut stats) {
+                    log_warn!("skipping {}: {e}", path.display());
+                }
+            } else if prune {
+                let Ok(relative) = path.strip_prefix(&opts.root) else {
+                    continue;
+                };
+                if let Ok(frame) = db.frame_by_uri(&uri_for_path(&opts.collection, relative)) {
+                    db.delete_frame(frame.id)?;
+                    pruned += 1;
+                }
+            }
+        }
+
+        if stats.ingested + stats.updated + pruned > 0 {
+            db.commit()?;
+            log_info!("Synced: {} prune={pruned}", stats.summary());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_paths_follow_nested_ignore_files() {
+        let root = std::env::temp_dir().join(format!("aethervault_ignore_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("notes/drafts")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "*.tmp\ndrafts/\n").unwrap();
+        fs::write(root.join("notes/.aethervaultignore"), "!keep.tmp\nprivate.md\n").unwrap();
+
+        assert!(path_is_ignored(&root, &root.join("a.tmp"), false));
+        assert!(!path_is_ignored(&root, &root.join("notes/keep.tmp"), false));
+        assert!(path_is_ignored(&root, &root.join("notes/private.md"), false));
+        assert!(path_is_ignored(&root, &root.join("notes/drafts/x.md"), false));
+        assert!(!path_is_ignored(&root, &root.join("notes/ok.md"), false));
+        assert!(path_is_ignored(&root, &root.join(".git/HEAD"), true));
+        assert!(!path_is_ignored(&root, &root.join("a.tmp"), true));
+        assert!(path_is_ignored(&root, Path::new("/elsewhere/a.md"), false));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
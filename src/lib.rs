@@ -0,0 +1,68 @@
+//! AetherVault: hybrid retrieval over single-file `.mv2` capsules.
+//!
+//! The `aethervault` binary wraps [`run_cli`]. Services that want the query
+//! pipeline without shelling out use [`HybridSearch`].
+
+// Module declarations
+#[macro_use]
+mod logging;
+mod cli;
+mod types;
+mod tool_args;
+mod util;
+mod config;
+mod query;
+mod tool_defs;
+mod tool_exec;
+mod tool_registry;
+mod mcp;
+mod claude;
+mod openai;
+mod agent;
+mod bridges;
+mod services;
+mod agent_log;
+mod config_file;
+mod ingest;
+mod memory_db;
+mod error;
+mod consolidation;
+mod skill_registry;
+mod scale;
+mod render;
+mod serve;
+mod app;
+pub mod api;
+
+// Re-export all module items at crate root so cross-module references work.
+// Before this split, everything lived in main.rs and shared a single namespace.
+// These wildcard re-exports preserve that behavior.
+pub(crate) use cli::*;
+pub(crate) use types::*;
+pub(crate) use tool_args::*;
+pub(crate) use util::*;
+pub(crate) use config::*;
+pub(crate) use query::*;
+pub(crate) use tool_defs::*;
+pub(crate) use tool_exec::*;
+pub(crate) use tool_registry::*;
+pub(crate) use mcp::*;
+pub(crate) use claude::*;
+pub(crate) use openai::*;
+pub(crate) use agent::*;
+pub(crate) use bridges::*;
+pub(crate) use services::*;
+pub(crate) use agent_log::*;
+pub(crate) use config_file::*;
+pub(crate) use ingest::*;
+pub(crate) use skill_registry::*;
+pub(crate) use scale::*;
+pub(crate) use render::*;
+pub(crate) use serve::*;
+
+
+pub use api::{HybridSearch, SearchOptions};
+pub use app::run_cli;
+pub use error::AethervaultError;
+pub use memory_db::TemporalFilter;
+pub use types::{ContextCitation, ContextPack, QueryPlan, QueryResponse, QueryResult, VecHitDebug, VecLaneDebug};
@@ -0,0 +1,113 @@
+//! Minimal leveled logger for diagnostics. Everything goes to stderr so that
+//! stdout stays clean for `--json` and other machine output.
+//!
+//! The level comes from `AETHERVAULT_LOG` (`error`, `warn`, `info`, `debug`,
+//! `trace`; default `info`), shifted by the CLI's `-v` / `-q` flags.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl LogLevel {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Error,
+            1 => Self::Warn,
+            2 => Self::Info,
+            3 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" | "warning" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+}
+
+const UNSET: u8 = u8::MAX;
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(UNSET);
+
+fn env_log_level() -> LogLevel {
+    std::env::var("AETHERVAULT_LOG")
+        .ok()
+        .and_then(|v| LogLevel::parse(&v))
+        .unwrap_or(LogLevel::Info)
+}
+
+pub(crate) fn log_level() -> LogLevel {
+    match LOG_LEVEL.load(Ordering::Relaxed) {
+        UNSET => env_log_level(),
+        value => LogLevel::from_u8(value),
+    }
+}
+
+pub(crate) fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Apply `-v` / `-q` counts on top of `AETHERVAULT_LOG`.
+pub(crate) fn init_logging(verbose: u8, quiet: u8) {
+    let base = env_log_level() as i16;
+    let level = (base + verbose as i16 - quiet as i16).clamp(0, LogLevel::Trace as i16);
+    set_log_level(LogLevel::from_u8(level as u8));
+}
+
+pub(crate) fn log_enabled(level: LogLevel) -> bool {
+    level <= log_level()
+}
+
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::logging::log_enabled($level) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => { log_at!($crate::logging::LogLevel::Error, $($arg)*) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log_at!($crate::logging::LogLevel::Warn, $($arg)*) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => { log_at!($crate::logging::LogLevel::Info, $($arg)*) };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log_at!($crate::logging::LogLevel::Debug, $($arg)*) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_flags_shift_the_level() {
+        assert_eq!(LogLevel::parse("WARNING"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("loud"), None);
+        let base = env_log_level();
+        init_logging(0, 9);
+        assert_eq!(log_level(), LogLevel::Error);
+        assert!(log_enabled(LogLevel::Error) && !log_enabled(LogLevel::Warn));
+        init_logging(9, 0);
+        assert_eq!(log_level(), LogLevel::Trace);
+        set_log_level(base);
+    }
+}
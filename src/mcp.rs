@@ -94,7 +94,7 @@ impl McpRegistry {
                     servers.push(handle);
                 }
                 Err(e) => {
-                    eprintln!("[mcp-registry] failed to start '{}': {e}", cfg.name);
+                    log_error!("[mcp-registry] failed to start '{}': {e}", cfg.name);
                     // Non-fatal: skip this server, continue with others
                 }
             }
@@ -130,6 +130,9 @@ impl McpRegistry {
 
     /// Call a tool on the appropriate server
     pub(crate) fn call_tool(&mut self, prefixed_name: &str, args: serde_json::Value) -> Result<super::ToolExecution, String> {
+        if super::is_tool_disabled(prefixed_name) {
+            return Err("tool disabled".into());
+        }
         let (server_idx, original_name) = self.route_map.get(prefixed_name)
             .ok_or_else(|| format!("mcp: unknown tool '{prefixed_name}'"))?
             .clone();
@@ -144,7 +147,7 @@ impl McpRegistry {
                         self.servers[server_idx].name
                     ));
                 }
-                eprintln!("[mcp:{}] server was marked dead, attempting reconnect", self.servers[server_idx].name);
+                log_warn!("[mcp:{}] server was marked dead, attempting reconnect", self.servers[server_idx].name);
                 self.servers[server_idx].restart()?;
                 retries += 1;
                 continue;
@@ -174,7 +177,7 @@ impl McpRegistry {
                     if retries >= 1 {
                         return Err(err);
                     }
-                    eprintln!(
+                    log_warn!(
                         "[mcp:{}] failed to send '{}' call: {err}",
                         self.servers[server_idx].name, prefixed_name
                     );
@@ -194,7 +197,7 @@ impl McpRegistry {
                         Ok(msg) => msg,
                         Err(err) => {
                             if is_recoverable_mcp_error(&err) && retries < 1 {
-                                eprintln!(
+                                log_warn!(
                                     "[mcp:{}] recoverable error while calling '{}': {err}",
                                     handle.name, prefixed_name
                                 );
@@ -207,7 +210,7 @@ impl McpRegistry {
 
                     if msg.get("id").is_none() {
                         let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("unknown");
-                        eprintln!("[mcp:{}] skipping notification: {method}", handle.name);
+                        log_debug!("[mcp:{}] skipping notification: {method}", handle.name);
                         continue;
                     }
                     if let Some(resp_id) = msg.get("id").and_then(|v| v.as_i64()) {
@@ -322,7 +325,7 @@ impl McpServerHandle {
             thread::spawn(move || {
                 let reader = BufReader::new(stderr);
                 for line in reader.lines().flatten() {
-                    eprintln!("[mcp:{name}:stderr] {line}");
+                    log_info!("[mcp:{name}:stderr] {line}");
                 }
             });
         }
@@ -376,13 +379,13 @@ impl McpServerHandle {
         let list_resp = self.read_msg()?;
         if let Some(err) = list_resp.get("error") {
             let msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown");
-            eprintln!("[mcp-registry] '{}': tools/list failed: {msg}", self.name);
+            log_error!("[mcp-registry] '{}': tools/list failed: {msg}", self.name);
         } else if let Some(tools_arr) = list_resp.get("result")
             .and_then(|r| r.get("tools"))
             .and_then(|t| t.as_array())
         {
             self.tools = tools_arr.clone();
-            eprintln!("[mcp-registry] '{}': discovered {} tools", self.name, self.tools.len());
+            log_info!("[mcp-registry] '{}': discovered {} tools", self.name, self.tools.len());
         }
 
         Ok(())
@@ -427,7 +430,7 @@ impl McpServerHandle {
                 Ok(ReaderEvent::Error(msg)) => return Err(msg),
                 Err(mpsc::RecvTimeoutError::Timeout) => {
                     if last_update.elapsed() >= Duration::from_secs(5) {
-                        eprintln!("[mcp:{}] polling for message (no deadline)", self.name);
+                        log_debug!("[mcp:{}] polling for message (no deadline)", self.name);
                         last_update = Instant::now();
                     }
                 }
@@ -580,15 +583,360 @@ pub(crate) fn write_mcp_response(writer: &mut impl Write, value: &serde_json::Va
     writer.flush()
 }
 
+const MCP_RESOURCE_PAGE_SIZE: usize = 100;
+
+/// `resources/list`: active frames with a URI, paged by an opaque offset cursor.
+fn mcp_resources_list(db: &crate::memory_db::MemoryDb, cursor: Option<&str>) -> serde_json::Value {
+    let ids = db.collect_active_frame_ids(None);
+    let offset = cursor.and_then(|c| c.parse::<usize>().ok()).unwrap_or(0);
+    let end = (offset + MCP_RESOURCE_PAGE_SIZE).min(ids.len());
+    let resources: Vec<serde_json::Value> = ids
+        .get(offset..end)
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|id| db.frame_by_id(*id).ok())
+        .filter_map(|frame| {
+            let uri = frame.uri?;
+            Some(serde_json::json!({
+                "uri": uri,
+                "name": frame.title.unwrap_or_else(|| uri.clone()),
+                "mimeType": "text/plain"
+            }))
+        })
+        .collect();
+    let mut result = serde_json::json!({ "resources": resources });
+    if end < ids.len() {
+        result["nextCursor"] = serde_json::json!(end.to_string());
+    }
+    result
+}
+
+/// `resources/read`: the latest active frame text for a URI.
+fn mcp_resources_read(db: &crate::memory_db::MemoryDb, uri: &str) -> Result<serde_json::Value, String> {
+    let frame = db.frame_by_uri(uri)?;
+    let text = db.frame_text_by_id(frame.id)?;
+    Ok(serde_json::json!({
+        "contents": [
+            { "uri": uri, "mimeType": "text/plain", "text": text }
+        ]
+    }))
+}
+
+/// Latest stored payload per skill name, from the `skill_store` frames under
+/// `aethervault://skills/`. Frames are versioned by URI, so later ids win.
+pub(crate) fn load_skill_payloads(db: &crate::memory_db::MemoryDb) -> Vec<serde_json::Value> {
+    load_skill_frames(db).into_iter().map(|(_, payload)| payload).collect()
+}
+
+/// Like [`load_skill_payloads`], keeping the frame id of each latest version.
+pub(crate) fn load_skill_frames(db: &crate::memory_db::MemoryDb) -> Vec<(u64, serde_json::Value)> {
+    let mut latest: std::collections::BTreeMap<String, (u64, serde_json::Value)> =
+        std::collections::BTreeMap::new();
+    for id in db.collect_active_frame_ids(Some("aethervault://skills/")) {
+        let Ok(text) = db.frame_text_by_id(id) else {
+            continue;
+        };
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        if let Some(name) = payload.get("name").and_then(|v| v.as_str()) {
+            latest.insert(name.to_string(), (id, payload));
+        }
+    }
+    latest.into_values().collect()
+}
+
+pub(crate) fn skill_prompt_text(skill: &serde_json::Value, task: Option<&str>) -> String {
+    let str_field = |key: &str| skill.get(key).and_then(|v| v.as_str()).unwrap_or("").trim();
+    let list_field = |key: &str| -> Vec<String> {
+        skill
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut text = format!("Follow the stored skill \"{}\".\n", str_field("name"));
+    if !str_field("trigger").is_empty() {
+        text.push_str(&format!("Use it when: {}\n", str_field("trigger")));
+    }
+    let steps = list_field("steps");
+    if !steps.is_empty() {
+        text.push_str("\nSteps:\n");
+        for (idx, step) in steps.iter().enumerate() {
+            text.push_str(&format!("{}. {}\n", idx + 1, step));
+        }
+    }
+    let tools = list_field("tools");
+    if !tools.is_empty() {
+        text.push_str(&format!("\nTools: {}\n", tools.join(", ")));
+    }
+    if !str_field("notes").is_empty() {
+        text.push_str(&format!("\nNotes: {}\n", str_field("notes")));
+    }
+    if let Some(task) = task.map(str::trim).filter(|t| !t.is_empty()) {
+        text.push_str(&format!("\nTask: {task}\n"));
+    }
+    text
+}
+
+/// `prompts/list`: one prompt per stored skill, with an optional `task` argument.
+fn mcp_prompts_list(db: &crate::memory_db::MemoryDb) -> serde_json::Value {
+    let prompts: Vec<serde_json::Value> = load_skill_payloads(db)
+        .iter()
+        .map(|skill| {
+            let description = skill
+                .get("trigger")
+                .and_then(|v| v.as_str())
+                .or_else(|| skill.get("notes").and_then(|v| v.as_str()))
+                .unwrap_or("");
+            serde_json::json!({
+                "name": skill.get("name"),
+                "description": description,
+                "arguments": [
+                    { "name": "task", "description": "What to apply the skill to", "required": false }
+                ]
+            })
+        })
+        .collect();
+    serde_json::json!({ "prompts": prompts })
+}
+
+/// `prompts/get`: a stored skill rendered as a single user message.
+fn mcp_prompts_get(
+    db: &crate::memory_db::MemoryDb,
+    name: &str,
+    arguments: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let skill = load_skill_payloads(db)
+        .into_iter()
+        .find(|s| s.get("name").and_then(|v| v.as_str()) == Some(name))
+        .ok_or_else(|| format!("unknown prompt: {name}"))?;
+    let task = arguments.get("task").and_then(|v| v.as_str());
+    Ok(serde_json::json!({
+        "description": skill.get("trigger").and_then(|v| v.as_str()).unwrap_or(""),
+        "messages": [
+            {
+                "role": "user",
+                "content": { "type": "text", "text": skill_prompt_text(&skill, task) }
+            }
+        ]
+    }))
+}
+
+enum ServerEvent {
+    Message(serde_json::Value),
+    StdinClosed,
+    ReadError(io::Error),
+    ToolDone {
+        key: String,
+        id: serde_json::Value,
+        name: String,
+        result: Result<super::ToolExecution, String>,
+    },
+}
+
+struct PendingToolCall {
+    key: String,
+    id: serde_json::Value,
+    name: String,
+    arguments: serde_json::Value,
+}
+
+/// Key used to match `notifications/cancelled` against in-flight request ids.
+fn request_key(id: &serde_json::Value) -> String {
+    id.to_string()
+}
+
+/// Tools that get a JSON content block next to the text one: none unless
+/// `AETHERVAULT_MCP_STRUCTURED_TOOLS` names them (comma list, `*` = all).
+fn mcp_structured_tools() -> Vec<String> {
+    super::env_optional("AETHERVAULT_MCP_STRUCTURED_TOOLS")
+        .map(|raw| {
+            raw.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn tool_result_content(
+    name: &str,
+    result: &super::ToolExecution,
+    structured_tools: &[String],
+) -> Vec<serde_json::Value> {
+    let mut content = vec![serde_json::json!({ "type": "text", "text": result.output })];
+    let wanted = structured_tools.iter().any(|t| t == "*" || t == name);
+    let meaningful = match &result.details {
+        serde_json::Value::Null => false,
+        serde_json::Value::Object(map) => !map.is_empty(),
+        serde_json::Value::Array(items) => !items.is_empty(),
+        _ => true,
+    };
+    if wanted && meaningful {
+        content.push(serde_json::json!({
+            "type": "resource",
+            "resource": {
+                "uri": format!("aethervault://tool/{name}/result"),
+                "mimeType": "application/json",
+                "text": result.details.to_string()
+            }
+        }));
+    }
+    content
+}
+
+fn tool_call_response(
+    id: serde_json::Value,
+    name: &str,
+    result: Result<super::ToolExecution, String>,
+    structured_tools: &[String],
+) -> serde_json::Value {
+    match result {
+        Ok(result) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": {
+                "content": tool_result_content(name, &result, structured_tools),
+                "details": result.details,
+                "isError": false
+            }
+        }),
+        Err(err) => serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32000, "message": err }
+        }),
+    }
+}
+
+fn cancelled_response(id: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": -32800, "message": "request cancelled" }
+    })
+}
+
+/// Run one tool call on a worker thread with its own capsule handle, so the
+/// server keeps reading stdin (and can see cancellations) while it runs.
+fn spawn_tool_worker(
+    call: PendingToolCall,
+    mv2: PathBuf,
+    read_only: bool,
+    cancel: Arc<AtomicBool>,
+    tx: mpsc::Sender<ServerEvent>,
+) {
+    thread::spawn(move || {
+        let result = super::open_or_create_db(&mv2)
+            .map_err(|e| e.to_string())
+            .and_then(|db| {
+                super::execute_tool_with_cancel(
+                    &call.name,
+                    call.arguments,
+                    &mv2,
+                    &db,
+                    read_only,
+                    None,
+                    &cancel,
+                )
+                .map_err(|e| e.to_string())
+            });
+        let _ = tx.send(ServerEvent::ToolDone {
+            key: call.key,
+            id: call.id,
+            name: call.name,
+            result,
+        });
+    });
+}
+
 pub(crate) fn run_mcp_server(mv2: PathBuf, read_only: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let mut reader = BufReader::new(io::stdin());
-    let mut writer = io::stdout();
+    {
+        let db = super::open_or_create_db(&mv2)?;
+        let config = super::load_capsule_config(&db).unwrap_or_default();
+        let agent = config.agent.unwrap_or_default();
+        super::disable_tools(agent.disabled_tools);
+        super::register_custom_tools(&agent.custom_tools);
+    }
+    serve_mcp(mv2, read_only, io::stdin(), io::stdout())
+}
+
+/// The server loop behind [`run_mcp_server`]. When `input` ends, no new
+/// requests are read, but running and queued tool calls still finish and
+/// get their responses before it returns.
+fn serve_mcp(
+    mv2: PathBuf,
+    read_only: bool,
+    input: impl Read + Send + 'static,
+    mut writer: impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (tx, rx) = mpsc::channel::<ServerEvent>();
+    {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(input);
+            loop {
+                let event = match read_mcp_message(&mut reader) {
+                    Ok(Some(msg)) => ServerEvent::Message(msg),
+                    Ok(None) => ServerEvent::StdinClosed,
+                    Err(err) => ServerEvent::ReadError(err),
+                };
+                let last = !matches!(event, ServerEvent::Message(_));
+                if tx.send(event).is_err() || last {
+                    break;
+                }
+            }
+        });
+    }
+    // Tool calls run one at a time, in arrival order, off the reader loop.
+    let mut running: Option<(String, Arc<AtomicBool>)> = None;
+    let mut queued: std::collections::VecDeque<PendingToolCall> = std::collections::VecDeque::new();
+    let mut cancelled: std::collections::HashSet<String> = std::collections::HashSet::new();
     let tools = super::tool_definitions_json();
     let db = super::open_or_create_db(&mv2)?;
+    let server_name =
+        super::env_optional("AETHERVAULT_MCP_NAME").unwrap_or_else(|| "kairos-vault".to_string());
+    let server_version = super::env_optional("AETHERVAULT_MCP_VERSION")
+        .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+    let structured_tools = mcp_structured_tools();
+    let mut input_open = true;
 
     loop {
-        let Some(msg) = read_mcp_message(&mut reader)? else {
+        if running.is_none()
+            && let Some(call) = queued.pop_front()
+        {
+            let cancel = Arc::new(AtomicBool::new(false));
+            running = Some((call.key.clone(), cancel.clone()));
+            spawn_tool_worker(call, mv2.clone(), read_only, cancel, tx.clone());
+        }
+        if !input_open && running.is_none() {
             break;
+        }
+
+        let msg = match rx.recv() {
+            Ok(ServerEvent::Message(msg)) => msg,
+            Ok(ServerEvent::ToolDone { key, id, name, result }) => {
+                running = None;
+                let response = if cancelled.remove(&key) {
+                    cancelled_response(id)
+                } else {
+                    tool_call_response(id, &name, result, &structured_tools)
+                };
+                write_mcp_response(&mut writer, &response)?;
+                continue;
+            }
+            Ok(ServerEvent::ReadError(err)) => return Err(err.into()),
+            Ok(ServerEvent::StdinClosed) => {
+                input_open = false;
+                continue;
+            }
+            Err(_) => break,
         };
         let id = msg.get("id").cloned();
         let has_id = id.as_ref().is_some_and(|v| !v.is_null());
@@ -613,11 +961,18 @@ pub(crate) fn run_mcp_server(mv2: PathBuf, read_only: bool) -> Result<(), Box<dy
                             "tools": {
                                 "list": true,
                                 "call": true
+                            },
+                            "resources": {
+                                "subscribe": false,
+                                "listChanged": false
+                            },
+                            "prompts": {
+                                "listChanged": false
                             }
                         },
                         "serverInfo": {
-                            "name": "kairos-vault",
-                            "version": env!("CARGO_PKG_VERSION")
+                            "name": server_name,
+                            "version": server_version
                         }
                     }
                 })
@@ -627,39 +982,85 @@ pub(crate) fn run_mcp_server(mv2: PathBuf, read_only: bool) -> Result<(), Box<dy
                 "id": id,
                 "result": { "tools": tools }
             }),
-            "tools/call" => {
+            "resources/list" => serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": mcp_resources_list(&db, params.get("cursor").and_then(|v| v.as_str()))
+            }),
+            "resources/read" => {
+                let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
+                match mcp_resources_read(&db, uri) {
+                    Ok(result) => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": result
+                    }),
+                    Err(err) => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32002, "message": format!("resource not found: {err}") }
+                    }),
+                }
+            }
+            "prompts/list" => serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": mcp_prompts_list(&db)
+            }),
+            "prompts/get" => {
                 let name = params.get("name").and_then(|v| v.as_str()).unwrap_or("");
                 let arguments = params
                     .get("arguments")
                     .cloned()
                     .unwrap_or_else(|| serde_json::json!({}));
-                match super::execute_tool(
-                    name,
-                    arguments,
-                    &mv2,
-                    &db,
-                    read_only,
-                    None,
-                ) {
+                match mcp_prompts_get(&db, name, &arguments) {
                     Ok(result) => serde_json::json!({
                         "jsonrpc": "2.0",
                         "id": id,
-                        "result": {
-                            "content": [
-                                { "type": "text", "text": result.output }
-                            ],
-                            "details": result.details,
-                            "isError": false
-                        }
+                        "result": result
                     }),
                     Err(err) => serde_json::json!({
                         "jsonrpc": "2.0",
                         "id": id,
-                        "error": { "code": -32000, "message": err }
+                        "error": { "code": -32602, "message": err }
                     }),
                 }
             }
+            "tools/call" => {
+                let id = id.unwrap_or(serde_json::Value::Null);
+                queued.push_back(PendingToolCall {
+                    key: request_key(&id),
+                    id,
+                    name: params
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string(),
+                    arguments: params
+                        .get("arguments")
+                        .cloned()
+                        .unwrap_or_else(|| serde_json::json!({})),
+                });
+                continue;
+            }
+            "notifications/cancelled" => {
+                let key = params.get("requestId").map(request_key).unwrap_or_default();
+                if let Some(pos) = queued.iter().position(|c| c.key == key) {
+                    if let Some(call) = queued.remove(pos) {
+                        write_mcp_response(&mut writer, &cancelled_response(call.id))?;
+                    }
+                } else if let Some((running_key, cancel)) = &running
+                    && *running_key == key
+                {
+                    cancel.store(true, Ordering::Release);
+                    cancelled.insert(key);
+                }
+                continue;
+            }
             "shutdown" => {
+                if let Some((_, cancel)) = &running {
+                    cancel.store(true, Ordering::Release);
+                }
                 let response = serde_json::json!({
                     "jsonrpc": "2.0",
                     "id": id,
@@ -680,10 +1081,142 @@ pub(crate) fn run_mcp_server(mv2: PathBuf, read_only: bool) -> Result<(), Box<dy
             }
         };
 
-        if has_id || method == "initialize" || method == "tools/list" || method == "tools/call" {
+        if has_id || method == "initialize" || method == "tools/list" {
             write_mcp_response(&mut writer, &response)?;
         }
     }
 
+    if let Some((_, cancel)) = &running {
+        cancel.store(true, Ordering::Release);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::{MemoryDb, PutOptions};
+
+    #[test]
+    fn resources_list_and_read_frames() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("mcp_resources_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for i in 0..(MCP_RESOURCE_PAGE_SIZE + 1) {
+            let opts = PutOptions {
+                uri: Some(format!("aether://notes/{i}.md")),
+                ..PutOptions::default()
+            };
+            db.put_bytes_with_options(format!("note {i}").as_bytes(), opts)
+                .unwrap();
+        }
+
+        let first = mcp_resources_list(&db, None);
+        assert_eq!(first["resources"].as_array().unwrap().len(), MCP_RESOURCE_PAGE_SIZE);
+        let cursor = first["nextCursor"].as_str().unwrap();
+        let rest = mcp_resources_list(&db, Some(cursor));
+        assert_eq!(rest["resources"].as_array().unwrap().len(), 1);
+        assert!(rest.get("nextCursor").is_none());
+
+        let read = mcp_resources_read(&db, "aether://notes/3.md").unwrap();
+        assert_eq!(read["contents"][0]["text"], "note 3");
+        assert!(mcp_resources_read(&db, "aether://missing").is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn prompts_expose_latest_skill_version() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("mcp_prompts_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for (ts, step) in [(1, "old step"), (2, "check disk usage")] {
+            let payload = serde_json::json!({
+                "name": "triage",
+                "trigger": "server is slow",
+                "steps": [step, "report findings"],
+                "tools": ["exec"],
+                "notes": null,
+                "ts_utc": ts
+            });
+            let opts = PutOptions {
+                uri: Some(format!("aethervault://skills/triage/{ts}-abc")),
+                ..PutOptions::default()
+            };
+            db.put_bytes_with_options(payload.to_string().as_bytes(), opts)
+                .unwrap();
+        }
+
+        let list = mcp_prompts_list(&db);
+        let prompts = list["prompts"].as_array().unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0]["name"], "triage");
+        assert_eq!(prompts[0]["description"], "server is slow");
+
+        let got = mcp_prompts_get(&db, "triage", &serde_json::json!({"task": "web-1"})).unwrap();
+        let text = got["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("1. check disk usage\n2. report findings"));
+        assert!(!text.contains("old step"));
+        assert!(text.contains("Tools: exec"));
+        assert!(text.ends_with("Task: web-1\n"));
+        assert!(mcp_prompts_get(&db, "nope", &serde_json::json!({})).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn structured_content_is_opt_in_per_tool() {
+        let result = crate::ToolExecution {
+            output: "200 OK".to_string(),
+            details: serde_json::json!({"status": 200}),
+            is_error: false,
+        };
+        let tools = vec!["http_request".to_string()];
+        let content = tool_result_content("http_request", &result, &tools);
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[0]["text"], "200 OK");
+        assert_eq!(content[1]["resource"]["mimeType"], "application/json");
+        assert_eq!(content[1]["resource"]["text"], r#"{"status":200}"#);
+        assert_eq!(tool_result_content("exec", &result, &tools).len(), 1);
+
+        let empty = crate::ToolExecution {
+            output: "done".to_string(),
+            details: serde_json::Value::Null,
+            is_error: false,
+        };
+        assert_eq!(tool_result_content("x", &empty, &["*".to_string()]).len(), 1);
+    }
+
+    #[test]
+    fn tool_call_answered_after_input_closes() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("mcp_eof_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let opts = PutOptions {
+            uri: Some("aether://notes/launch.md".into()),
+            ..PutOptions::default()
+        };
+        db.put_bytes_with_options(b"the launch date is May 3", opts).unwrap();
+        drop(db);
+
+        let mut input = Vec::new();
+        let call = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": "search", "arguments": { "query": "launch" } }
+        });
+        write_mcp_response(&mut input, &call).unwrap();
+        let mut output = Vec::new();
+        serve_mcp(path.clone(), true, std::io::Cursor::new(input), &mut output).unwrap();
+
+        let response = read_mcp_message(&mut BufReader::new(output.as_slice())).unwrap().unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["isError"], false);
+        std::fs::remove_file(&path).ok();
+    }
+}
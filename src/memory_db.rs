@@ -11,9 +11,11 @@
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 
 use chrono::Utc;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, ErrorCode, OpenFlags};
 use serde::{Deserialize, Serialize};
 
 // ── Type aliases ─────────────────────────────────────────────────────────
@@ -91,11 +93,11 @@ impl FrameRole {
 // JSON-compatible with aether_core::types::TemporalFilter
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct TemporalFilter {
+pub struct TemporalFilter {
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub(crate) start_utc: Option<i64>,
+    pub start_utc: Option<i64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub(crate) end_utc: Option<i64>,
+    pub end_utc: Option<i64>,
 }
 
 // ── Frame ────────────────────────────────────────────────────────────────
@@ -135,6 +137,9 @@ pub(crate) struct SearchHit {
     pub(crate) chunk_range: Option<(usize, usize)>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) chunk_text: Option<String>,
+    /// Original `text` when it was rewritten (e.g. `--plain-snippets`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) raw_text: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) score: Option<f32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -175,6 +180,10 @@ pub(crate) struct PutOptions {
     pub(crate) metadata: Option<serde_json::Value>,
     pub(crate) role: FrameRole,
     pub(crate) parent_id: Option<FrameId>,
+    /// Binary payload: stored as-is but kept out of `text_content` (and FTS).
+    pub(crate) binary: bool,
+    /// Store `payload` zstd-compressed; reads decompress it transparently.
+    pub(crate) compress: bool,
 }
 
 impl Default for PutOptions {
@@ -192,6 +201,8 @@ impl Default for PutOptions {
             metadata: None,
             role: FrameRole::default(),
             parent_id: None,
+            binary: false,
+            compress: false,
         }
     }
 }
@@ -216,12 +227,163 @@ pub(crate) struct HotMemoryMigrationReport {
     pub(crate) errors: Vec<String>,
 }
 
+/// An active frame whose distinctive token does not find it through FTS.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LexIndexMiss {
+    pub(crate) frame_id: FrameId,
+    pub(crate) uri: Option<String>,
+    pub(crate) token: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LexVerifyReport {
+    pub(crate) sampled: usize,
+    pub(crate) checked: usize,
+    pub(crate) misses: Vec<LexIndexMiss>,
+}
+
+/// An active frame whose payload no longer matches its stored checksum.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ChecksumMismatch {
+    pub(crate) frame_id: FrameId,
+    pub(crate) uri: Option<String>,
+    pub(crate) error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ChecksumVerifyReport {
+    pub(crate) checked: usize,
+    pub(crate) mismatches: Vec<ChecksumMismatch>,
+}
+
+/// SQLite-level and frame-table consistency of a capsule.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct StructureReport {
+    /// `PRAGMA quick_check` problems; empty when the file is sound.
+    pub(crate) integrity: Vec<String>,
+    pub(crate) frames: usize,
+    pub(crate) active: usize,
+    pub(crate) superseded: usize,
+    pub(crate) deleted: usize,
+    /// Frames whose status is none of active/superseded/deleted.
+    pub(crate) unknown_status: usize,
+    /// URIs with more than one active frame (writes supersede, so at most one).
+    pub(crate) duplicate_active_uris: Vec<String>,
+}
+
+/// Longest alphanumeric word (4..=40 chars) in the first few KB of `text`;
+/// long words are the least likely to be shared with many other frames.
+fn distinctive_token(text: &str) -> Option<String> {
+    let head = match text.char_indices().nth(4096) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
+    };
+    head.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| (4..=40).contains(&w.chars().count()))
+        .max_by_key(|w| w.chars().count())
+        .map(|w| w.to_lowercase())
+}
+
 // ═════════════════════════════════════════════════════════════════════════
 // MemoryDb — SQLite backend
 // ═════════════════════════════════════════════════════════════════════════
 
 pub(crate) struct MemoryDb {
     conn: Connection,
+    /// Recompute payload checksums on read (`--verify` / `AETHERVAULT_VERIFY_READS`).
+    verify_reads: bool,
+}
+
+static VERIFY_READS: AtomicBool = AtomicBool::new(false);
+
+/// Verify payload checksums on every read for databases opened from now on.
+pub(crate) fn set_verify_reads(on: bool) {
+    VERIFY_READS.store(on, Ordering::Relaxed);
+}
+
+fn verify_reads_enabled() -> bool {
+    VERIFY_READS.load(Ordering::Relaxed) || crate::env_bool("AETHERVAULT_VERIFY_READS", false)
+}
+
+const DEFAULT_LOCK_TIMEOUT_MS: u64 = 5_000;
+
+/// 0 = not set on the command line.
+static LOCK_TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// How long databases opened from now on wait for another process's write
+/// lock (`--lock-timeout-ms`) before failing with "capsule busy".
+pub(crate) fn set_lock_timeout_ms(ms: u64) {
+    LOCK_TIMEOUT_MS.store(ms.max(1), Ordering::Relaxed);
+}
+
+fn lock_timeout() -> Duration {
+    let ms = match LOCK_TIMEOUT_MS.load(Ordering::Relaxed) {
+        0 => crate::env_u64("AETHERVAULT_LOCK_TIMEOUT_MS", DEFAULT_LOCK_TIMEOUT_MS).unwrap_or(DEFAULT_LOCK_TIMEOUT_MS),
+        ms => ms,
+    };
+    Duration::from_millis(ms)
+}
+
+fn is_busy(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Compare `bytes` against a frame's stored blake3 checksum. Frames without
+/// a stored checksum (NULL or all zeros) are accepted as-is.
+fn check_payload(id: FrameId, stored: Option<&[u8]>, bytes: &[u8]) -> Result<(), String> {
+    let Some(stored) = stored.filter(|c| c.len() == 32 && c.iter().any(|b| *b != 0)) else {
+        return Ok(());
+    };
+    let computed = blake3::hash(bytes);
+    if computed.as_bytes().as_slice() == stored {
+        Ok(())
+    } else {
+        Err(format!(
+            "frame {id}: payload checksum mismatch (stored {}, computed {}); capsule may be corrupt",
+            blake3::Hash::from_slice(stored).map(|h| h.to_hex().to_string()).unwrap_or_default(),
+            computed.to_hex()
+        ))
+    }
+}
+
+/// `extra_metadata` key recording how `payload` is encoded (absent = raw bytes).
+pub(crate) const PAYLOAD_ENCODING_KEY: &str = "payload_encoding";
+const ZSTD_ENCODING: &str = "zstd";
+#[cfg(feature = "compress")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// SQL for a frame's payload encoding; NULL for raw payloads and for rows
+/// whose `extra_metadata` is not valid JSON.
+const PAYLOAD_ENCODING_SQL: &str =
+    "CASE WHEN json_valid(extra_metadata) THEN json_extract(extra_metadata, '$.payload_encoding') END";
+
+/// Turn a stored payload back into the bytes that were put.
+fn decode_payload(id: FrameId, encoding: Option<&str>, stored: Vec<u8>) -> Result<Vec<u8>, String> {
+    match encoding {
+        None => Ok(stored),
+        #[cfg(feature = "compress")]
+        Some(ZSTD_ENCODING) => {
+            zstd::decode_all(stored.as_slice()).map_err(|e| format!("frame {id}: decompress payload: {e}"))
+        }
+        #[cfg(not(feature = "compress"))]
+        Some(ZSTD_ENCODING) => Err(format!(
+            "frame {id}: payload is zstd-compressed; reading it needs a build with the `compress` feature"
+        )),
+        Some(other) => Err(format!("frame {id}: unknown payload encoding '{other}'")),
+    }
+}
+
+#[cfg(feature = "compress")]
+fn compress_payload(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    zstd::encode_all(bytes, ZSTD_LEVEL).map_err(|e| format!("compress payload: {e}"))
+}
+
+#[cfg(not(feature = "compress"))]
+fn compress_payload(_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    Err("payload compression needs a build with the `compress` feature".into())
 }
 
 // ── Schema SQL ───────────────────────────────────────────────────────────
@@ -299,6 +461,50 @@ CREATE TABLE IF NOT EXISTS feedback (
 CREATE INDEX IF NOT EXISTS idx_feedback_uri ON feedback(uri);
 ";
 
+/// Writes grouped into one transaction, committed every `commit_interval`
+/// records (0 = only at the end). Bulk `log`/`feedback` imports use it to
+/// avoid one fsync per row. Dropping an unfinished batch still commits what
+/// was recorded.
+pub(crate) struct WriteBatch<'a> {
+    db: &'a MemoryDb,
+    commit_interval: usize,
+    pending: usize,
+    open: bool,
+}
+
+impl WriteBatch<'_> {
+    /// Count one write, committing when the interval is reached.
+    pub(crate) fn record(&mut self) -> Result<(), String> {
+        self.pending += 1;
+        if self.commit_interval > 0 && self.pending >= self.commit_interval {
+            self.db
+                .conn
+                .execute_batch("COMMIT; BEGIN IMMEDIATE")
+                .map_err(|e| self.db.lock_error("commit batch", e))?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Commit the tail of the batch and checkpoint.
+    pub(crate) fn finish(mut self) -> Result<(), String> {
+        self.open = false;
+        self.db
+            .conn
+            .execute_batch("COMMIT")
+            .map_err(|e| format!("commit batch: {e}"))?;
+        self.db.commit()
+    }
+}
+
+impl Drop for WriteBatch<'_> {
+    fn drop(&mut self) {
+        if self.open {
+            let _ = self.db.conn.execute_batch("COMMIT");
+        }
+    }
+}
+
 // ── Core implementation ──────────────────────────────────────────────────
 
 impl MemoryDb {
@@ -308,17 +514,56 @@ impl MemoryDb {
             std::fs::create_dir_all(parent)?;
         }
         let conn = Connection::open(path)?;
-        let db = Self { conn };
-        db.apply_pragmas()?;
-        db.init_schema()?;
+        // Set before the WAL pragma, which itself may wait on another writer.
+        conn.busy_timeout(lock_timeout())?;
+        let db = Self {
+            conn,
+            verify_reads: verify_reads_enabled(),
+        };
+        db.apply_pragmas()
+            .and_then(|()| db.init_schema())
+            .map_err(|e| -> Box<dyn std::error::Error> {
+                match e.downcast::<rusqlite::Error>() {
+                    Ok(e) if is_busy(&e) => db.lock_error("open", *e).into(),
+                    Ok(e) => e,
+                    Err(e) => e,
+                }
+            })?;
         Ok(db)
     }
 
+    /// "capsule busy" for lock timeouts, the plain SQLite error otherwise.
+    fn lock_error(&self, action: &str, err: rusqlite::Error) -> String {
+        if is_busy(&err) {
+            format!(
+                "capsule busy: {} is locked by another process; {action} gave up after waiting \
+                 (raise --lock-timeout-ms or AETHERVAULT_LOCK_TIMEOUT_MS)",
+                self.conn.path().unwrap_or("capsule")
+            )
+        } else {
+            format!("{action}: {err}")
+        }
+    }
+
+    /// Open an existing database without write access: no schema setup, and
+    /// `query_only` so any stray write fails instead of taking the write lock.
+    pub(crate) fn open_read_only(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(lock_timeout())?;
+        conn.execute_batch("PRAGMA query_only = ON;")?;
+        Ok(Self {
+            conn,
+            verify_reads: verify_reads_enabled(),
+        })
+    }
+
     fn apply_pragmas(&self) -> Result<(), Box<dyn std::error::Error>> {
         self.conn.execute_batch(
             "PRAGMA journal_mode = WAL;
              PRAGMA synchronous = NORMAL;
-             PRAGMA busy_timeout = 5000;
              PRAGMA cache_size = -8000;
              PRAGMA mmap_size = 67108864;",
         )?;
@@ -343,6 +588,31 @@ impl MemoryDb {
 
     // ── Frame read operations ────────────────────────────────────────
 
+    /// Marker that changes whenever frames, feedback or config change, from
+    /// any connection. Caches keyed on capsule content compare against it.
+    pub(crate) fn content_version(&self) -> String {
+        self.conn
+            .query_row(
+                "SELECT (SELECT COALESCE(MAX(id), 0) || ':' || COUNT(*) || ':' || COALESCE(SUM(status = 'active'), 0) FROM frames)
+                     || '/' || (SELECT COALESCE(MAX(id), 0) || ':' || COUNT(*) FROM feedback)
+                     || '/' || (SELECT COALESCE(MAX(updated_at), 0) || ':' || COALESCE(SUM(length(value)), 0) FROM config)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or_default()
+    }
+
+    /// Path of the open capsule file (`None` for in-memory databases).
+    /// File another read-only connection can open to see exactly what this one
+    /// sees: `None` for in-memory databases and while a write batch is open.
+    pub(crate) fn reader_path(&self) -> Option<&str> {
+        self.path().filter(|path| !path.is_empty() && self.conn.is_autocommit())
+    }
+
+    pub(crate) fn path(&self) -> Option<&str> {
+        self.conn.path()
+    }
+
     pub(crate) fn frame_count(&self) -> usize {
         self.conn
             .query_row("SELECT COUNT(*) FROM frames", [], |row| {
@@ -387,19 +657,60 @@ impl MemoryDb {
     }
 
     pub(crate) fn frame_canonical_payload(&self, id: FrameId) -> Result<Vec<u8>, String> {
-        self.conn
-            .query_row("SELECT payload FROM frames WHERE id = ?", params![id as i64], |row| {
-                row.get::<_, Vec<u8>>(0)
-            })
-            .map_err(|e| format!("frame_payload({id}): {e}"))
+        let (payload, checksum, encoding) = self
+            .conn
+            .query_row(
+                &format!("SELECT payload, checksum, {PAYLOAD_ENCODING_SQL} FROM frames WHERE id = ?"),
+                params![id as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, Vec<u8>>(0)?,
+                        row.get::<_, Option<Vec<u8>>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                },
+            )
+            .map_err(|e| format!("frame_payload({id}): {e}"))?;
+        let payload = decode_payload(id, encoding.as_deref(), payload)?;
+        if self.verify_reads {
+            check_payload(id, checksum.as_deref(), &payload)?;
+        }
+        Ok(payload)
     }
 
     pub(crate) fn frame_text_by_id(&self, id: FrameId) -> Result<String, String> {
-        let result: Result<(Option<String>, Option<Vec<u8>>), _> = self.conn.query_row(
-            "SELECT text_content, payload FROM frames WHERE id = ?",
-            params![id as i64],
-            |row| Ok((row.get(0)?, row.get(1)?)),
-        );
+        type Row = (Option<String>, Option<Vec<u8>>, Option<Vec<u8>>);
+        let result: Result<Row, _> = self
+            .conn
+            .query_row(
+                &format!("SELECT text_content, payload, checksum, {PAYLOAD_ENCODING_SQL} FROM frames WHERE id = ?"),
+                params![id as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, Option<Vec<u8>>>(1)?,
+                        row.get::<_, Option<Vec<u8>>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|(text, payload, checksum, encoding)| {
+                let payload = payload
+                    .map(|p| decode_payload(id, encoding.as_deref(), p))
+                    .transpose()?;
+                Ok((text, payload, checksum))
+            });
+        let result = match result {
+            Ok((text, payload, checksum)) if self.verify_reads => {
+                let bytes = payload.as_deref().or(text.as_deref().map(str::as_bytes));
+                check_payload(id, checksum.as_deref(), bytes.unwrap_or_default())
+                    .map_err(|e| format!("frame_text({id}): {e}"))?;
+                Ok((text, payload))
+            }
+            Ok((text, payload, _)) => Ok((text, payload)),
+            Err(e) => Err(e),
+        };
         match result {
             Ok((Some(ref text), _)) if !text.is_empty() => Ok(text.clone()),
             Ok((_, Some(payload))) => {
@@ -420,11 +731,23 @@ impl MemoryDb {
         let timestamp = options.timestamp.unwrap_or_else(|| Utc::now().timestamp());
         let checksum = blake3::hash(bytes);
         let checksum_bytes = checksum.as_bytes().as_slice();
-        let text_content = std::str::from_utf8(bytes).ok().map(|s| s.to_string());
+        let text_content = if options.binary {
+            None
+        } else {
+            std::str::from_utf8(bytes).ok().map(|s| s.to_string())
+        };
         let tags_json = serde_json::to_string(&options.tags).unwrap_or_else(|_| "[]".into());
         let labels_json = serde_json::to_string(&options.labels).unwrap_or_else(|_| "[]".into());
-        let extra_json =
-            serde_json::to_string(&options.extra_metadata).unwrap_or_else(|_| "{}".into());
+        // The encoding describes this write's payload, never one copied from another frame.
+        let mut extra_metadata = options.extra_metadata.clone();
+        extra_metadata.remove(PAYLOAD_ENCODING_KEY);
+        let stored_payload = if options.compress {
+            extra_metadata.insert(PAYLOAD_ENCODING_KEY.to_string(), ZSTD_ENCODING.to_string());
+            std::borrow::Cow::Owned(compress_payload(bytes)?)
+        } else {
+            std::borrow::Cow::Borrowed(bytes)
+        };
+        let extra_json = serde_json::to_string(&extra_metadata).unwrap_or_else(|_| "{}".into());
         let meta_json = options
             .metadata
             .as_ref()
@@ -469,7 +792,7 @@ impl MemoryDb {
                     timestamp,
                     checksum_bytes,
                     options.search_text,
-                    bytes,
+                    &*stored_payload,
                     text_content,
                     options.role.as_str(),
                     options.parent_id.map(|v| v as i64),
@@ -510,6 +833,20 @@ impl MemoryDb {
         Ok(())
     }
 
+    /// Set one `extra_metadata` entry in place, without writing a new version.
+    pub(crate) fn set_extra_metadata(&self, id: FrameId, key: &str, value: &str) -> Result<(), String> {
+        let mut extra = self.frame_by_id(id)?.extra_metadata;
+        extra.insert(key.to_string(), value.to_string());
+        let extra_json = serde_json::to_string(&extra).map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "UPDATE frames SET extra_metadata = ? WHERE id = ?",
+                params![extra_json, id as i64],
+            )
+            .map_err(|e| format!("set_extra_metadata({id}): {e}"))?;
+        Ok(())
+    }
+
     /// Mark a frame as deleted.
     pub(crate) fn delete_frame(&self, id: FrameId) -> Result<(), String> {
         self.conn
@@ -527,6 +864,61 @@ impl MemoryDb {
         Ok(())
     }
 
+    /// Group the following writes into explicit transactions; see [`WriteBatch`].
+    /// Takes the write lock up front so contention waits out the lock
+    /// timeout here rather than failing midway through the batch.
+    pub(crate) fn write_batch(&self, commit_interval: usize) -> Result<WriteBatch<'_>, String> {
+        self.conn
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| self.lock_error("begin batch", e))?;
+        Ok(WriteBatch {
+            db: self,
+            commit_interval,
+            pending: 0,
+            open: true,
+        })
+    }
+
+    /// Latest active `(id, uri)` per URI containing `needle` (ASCII
+    /// case-insensitive), URIs starting with it first, at most `limit`.
+    pub(crate) fn uris_containing(&self, needle: &str, limit: usize) -> Vec<(FrameId, String)> {
+        let escaped = needle.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let mut stmt = match self.conn.prepare(
+            "SELECT MAX(id), uri FROM frames
+             WHERE status = 'active' AND uri LIKE ?1 ESCAPE '\\'
+             GROUP BY uri
+             ORDER BY uri LIKE ?2 ESCAPE '\\' DESC, uri
+             LIMIT ?3",
+        ) {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        let rows = stmt.query_map(
+            params![format!("%{escaped}%"), format!("{escaped}%"), limit as i64],
+            |row| Ok((row.get::<_, i64>(0)? as FrameId, row.get::<_, String>(1)?)),
+        );
+        match rows {
+            Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// `(uri, timestamp)` of every active frame that has a URI.
+    pub(crate) fn active_uri_timestamps(&self) -> Vec<(String, i64)> {
+        let mut stmt = match self
+            .conn
+            .prepare("SELECT uri, timestamp FROM frames WHERE status = 'active' AND uri IS NOT NULL")
+        {
+            Ok(s) => s,
+            Err(_) => return Vec::new(),
+        };
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)));
+        match rows {
+            Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     /// Collect all active frame IDs, optionally filtered by URI prefix scope.
     pub(crate) fn collect_active_frame_ids(&self, scope: Option<&str>) -> Vec<u64> {
         let (sql, bind): (String, Vec<Box<dyn rusqlite::types::ToSql>>) = match scope {
@@ -553,6 +945,17 @@ impl MemoryDb {
         rows.filter_map(|r| r.ok()).map(|id| id as u64).collect()
     }
 
+    /// Active frames under `scope` that `ingest` wrote (they carry a
+    /// `relative_path`), as `(id, uri)`.
+    pub(crate) fn ingested_frames(&self, scope: &str) -> Vec<(FrameId, String)> {
+        self.collect_active_frame_ids(Some(scope))
+            .into_iter()
+            .filter_map(|id| self.frame_by_id(id).ok())
+            .filter(|frame| frame.extra_metadata.contains_key("relative_path"))
+            .filter_map(|frame| frame.uri.map(|uri| (frame.id, uri)))
+            .collect()
+    }
+
     // ── Search (FTS5) ────────────────────────────────────────────────
 
     pub(crate) fn search(&self, request: SearchRequest) -> Result<SearchResponse, String> {
@@ -676,12 +1079,13 @@ impl MemoryDb {
                         matches: 0,
                         chunk_range: None,
                         chunk_text: None,
+                        raw_text: None,
                         score: Some(weighted_score as f32),
                         metadata: None,
                     });
                 }
                 Err(e) => {
-                    eprintln!("[memory_db] search row error: {e}");
+                    log_warn!("[memory_db] search row error: {e}");
                 }
             }
         }
@@ -752,6 +1156,7 @@ impl MemoryDb {
                     matches: 0,
                     chunk_range: None,
                     chunk_text: None,
+                    raw_text: None,
                     score: Some(1.0),
                     metadata: None,
                 })
@@ -1297,6 +1702,254 @@ impl MemoryDb {
             .map_err(|e| format!("rebuild FTS: {e}"))
     }
 
+    /// Sample up to `sample` active text frames and check each is returned by an
+    /// FTS match on a distinctive token from its own content. Read-only.
+    pub(crate) fn verify_fts(&self, sample: usize) -> Result<LexVerifyReport, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, uri, text_content FROM frames
+                 WHERE status = 'active' AND text_content IS NOT NULL AND text_content != ''
+                 ORDER BY random() LIMIT ?1",
+            )
+            .map_err(|e| format!("verify FTS: {e}"))?;
+        let frames: Vec<(i64, Option<String>, String)> = stmt
+            .query_map(params![sample as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| format!("verify FTS: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut report = LexVerifyReport {
+            sampled: frames.len(),
+            checked: 0,
+            misses: Vec::new(),
+        };
+        for (id, uri, text) in frames {
+            let Some(token) = distinctive_token(&text) else {
+                continue;
+            };
+            report.checked += 1;
+            let found = self
+                .conn
+                .query_row(
+                    "SELECT 1 FROM frames_fts WHERE frames_fts MATCH ?1 AND rowid = ?2",
+                    params![format!("\"{token}\""), id],
+                    |_| Ok(()),
+                )
+                .is_ok();
+            if !found {
+                report.misses.push(LexIndexMiss {
+                    frame_id: id as FrameId,
+                    uri,
+                    token,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run `PRAGMA quick_check` and count frames by status. Read-only.
+    pub(crate) fn verify_structure(&self) -> Result<StructureReport, String> {
+        // Checked per table: a whole-database check also validates the FTS5
+        // index, which needs write access (`verify_fts` covers that instead).
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM sqlite_schema WHERE type = 'table' AND sql NOT LIKE 'CREATE VIRTUAL%'")
+            .map_err(|e| format!("verify structure: {e}"))?;
+        let tables: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("verify structure: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let mut integrity = Vec::new();
+        for table in tables {
+            let mut stmt = self
+                .conn
+                .prepare(&format!("PRAGMA quick_check(\"{}\")", table.replace('"', "\"\"")))
+                .map_err(|e| format!("verify structure: {e}"))?;
+            integrity.extend(
+                stmt.query_map([], |row| row.get::<_, String>(0))
+                    .map_err(|e| format!("verify structure: {e}"))?
+                    .filter_map(|r| r.ok())
+                    .filter(|line| line != "ok"),
+            );
+        }
+        let (frames, active, superseded, deleted): (i64, i64, i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(status = 'active'), 0),
+                        COALESCE(SUM(status = 'superseded'), 0), COALESCE(SUM(status = 'deleted'), 0)
+                 FROM frames",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .map_err(|e| format!("verify structure: {e}"))?;
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT uri FROM frames WHERE status = 'active' AND uri IS NOT NULL
+                 GROUP BY uri HAVING COUNT(*) > 1 ORDER BY uri",
+            )
+            .map_err(|e| format!("verify structure: {e}"))?;
+        let duplicate_active_uris = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("verify structure: {e}"))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(StructureReport {
+            integrity,
+            frames: frames as usize,
+            active: active as usize,
+            superseded: superseded as usize,
+            deleted: deleted as usize,
+            unknown_status: (frames - active - superseded - deleted) as usize,
+            duplicate_active_uris,
+        })
+    }
+
+    /// Recompute the checksum of every active frame's payload. Read-only.
+    pub(crate) fn verify_checksums(&self) -> Result<ChecksumVerifyReport, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                &format!(
+                    "SELECT id, uri, payload, text_content, checksum, {PAYLOAD_ENCODING_SQL} FROM frames
+                     WHERE status = 'active' ORDER BY id"
+                ),
+            )
+            .map_err(|e| format!("verify checksums: {e}"))?;
+        let mut rows = stmt.query([]).map_err(|e| format!("verify checksums: {e}"))?;
+        let mut report = ChecksumVerifyReport {
+            checked: 0,
+            mismatches: Vec::new(),
+        };
+        while let Some(row) = rows.next().map_err(|e| format!("verify checksums: {e}"))? {
+            let id: i64 = row.get(0).map_err(|e| e.to_string())?;
+            let uri: Option<String> = row.get(1).map_err(|e| e.to_string())?;
+            let payload: Option<Vec<u8>> = row.get(2).map_err(|e| e.to_string())?;
+            let text: Option<String> = row.get(3).map_err(|e| e.to_string())?;
+            let checksum: Option<Vec<u8>> = row.get(4).map_err(|e| e.to_string())?;
+            let encoding: Option<String> = row.get(5).map_err(|e| e.to_string())?;
+            report.checked += 1;
+            let payload = match payload.map(|p| decode_payload(id as FrameId, encoding.as_deref(), p)).transpose() {
+                Ok(payload) => payload,
+                Err(error) => {
+                    report.mismatches.push(ChecksumMismatch {
+                        frame_id: id as FrameId,
+                        uri,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            let bytes = payload.as_deref().or(text.as_deref().map(str::as_bytes));
+            if let Err(error) = check_payload(id as FrameId, checksum.as_deref(), bytes.unwrap_or_default()) {
+                report.mismatches.push(ChecksumMismatch {
+                    frame_id: id as FrameId,
+                    uri,
+                    error,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Main file plus WAL, so sizes stay honest before a checkpoint.
+    pub(crate) fn disk_size(&self, path: &Path) -> u64 {
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        self.file_size(path) + std::fs::metadata(wal).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// Superseded frames and their stored bytes, plus free pages; what a
+    /// VACUUM (after a purge, when `with_superseded`) could roughly give back.
+    pub(crate) fn reclaimable_estimate(&self, with_superseded: bool) -> Result<(usize, u64), String> {
+        let (frames, bytes): (i64, i64) = self
+            .conn
+            .query_row(
+                "SELECT count(*), COALESCE(SUM(COALESCE(length(payload), 0)
+                     + COALESCE(length(text_content), 0)
+                     + COALESCE(length(search_text), 0)), 0)
+                 FROM frames WHERE status = 'superseded'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("estimate reclaimable: {e}"))?;
+        let free_pages: i64 = self
+            .conn
+            .query_row("PRAGMA freelist_count", [], |row| row.get(0))
+            .map_err(|e| format!("estimate reclaimable: {e}"))?;
+        let page_size: i64 = self
+            .conn
+            .query_row("PRAGMA page_size", [], |row| row.get(0))
+            .map_err(|e| format!("estimate reclaimable: {e}"))?;
+        let bytes = if with_superseded { bytes } else { 0 };
+        Ok((frames as usize, (bytes + free_pages * page_size) as u64))
+    }
+
+    /// Superseded versions outside the retention policy: per URI the newest
+    /// `keep_versions` versions stay, as does anything at or after
+    /// `older_than` (unix seconds). Config and OAuth frames are never listed.
+    /// Returns `(frame_id, stored bytes)` pairs.
+    pub(crate) fn prune_candidates(
+        &self,
+        keep_versions: usize,
+        older_than: Option<i64>,
+    ) -> Result<Vec<(FrameId, u64)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, bytes FROM (
+                     SELECT id, status, timestamp, uri,
+                            ROW_NUMBER() OVER (PARTITION BY uri ORDER BY id DESC) AS version,
+                            COALESCE(length(payload), 0) + COALESCE(length(text_content), 0)
+                                + COALESCE(length(search_text), 0) AS bytes
+                     FROM frames WHERE uri IS NOT NULL
+                 )
+                 WHERE status = 'superseded' AND version > ?1 AND (?2 IS NULL OR timestamp < ?2)
+                   AND uri NOT LIKE 'aethervault://config/%' AND uri NOT LIKE 'aethervault://oauth/%'
+                 ORDER BY id",
+            )
+            .map_err(|e| format!("prune candidates: {e}"))?;
+        let rows = stmt
+            .query_map(params![keep_versions.max(1) as i64, older_than], |row| {
+                Ok((row.get::<_, i64>(0)? as FrameId, row.get::<_, i64>(1)? as u64))
+            })
+            .map_err(|e| format!("prune candidates: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("prune candidates: {e}"))
+    }
+
+    /// Permanently remove frames (one transaction). Returns how many went.
+    pub(crate) fn purge_frames(&self, ids: &[FrameId]) -> Result<usize, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("purge frames: {e}"))?;
+        let mut removed = 0;
+        {
+            let mut stmt = tx
+                .prepare("DELETE FROM frames WHERE id = ?")
+                .map_err(|e| format!("purge frames: {e}"))?;
+            for id in ids {
+                removed += stmt
+                    .execute(params![*id as i64])
+                    .map_err(|e| format!("purge frame {id}: {e}"))?;
+            }
+        }
+        tx.commit().map_err(|e| format!("purge frames: {e}"))?;
+        Ok(removed)
+    }
+
+    /// Truncating WAL checkpoint, so VACUUM's savings show up on disk.
+    pub(crate) fn checkpoint_truncate(&self) -> Result<(), String> {
+        self.conn
+            .execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")
+            .map_err(|e| format!("checkpoint: {e}"))
+    }
+
     /// Run VACUUM to reclaim space.
     /// Delete all superseded frames (they are dead weight after URI supersede).
     pub(crate) fn purge_superseded(&self) -> Result<usize, String> {
@@ -1361,6 +2014,50 @@ mod tests {
         std::fs::remove_file(&path).ok();
     }
 
+    #[test]
+    fn set_extra_metadata_updates_in_place() {
+        let path = temp_db_path("set_extra_metadata");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let mut options = PutOptions {
+            uri: Some("aethervault://skills/a.json".into()),
+            ..PutOptions::default()
+        };
+        options.extra_metadata.insert("skill_id".into(), "a".into());
+        let id = db.put_bytes_with_options(b"{}", options).unwrap();
+        db.set_extra_metadata(id, "skill_uses", "3").unwrap();
+        let frame = db.frame_by_uri("aethervault://skills/a.json").unwrap();
+        assert_eq!(frame.id, id);
+        assert_eq!(frame.extra_metadata.get("skill_uses").map(String::as_str), Some("3"));
+        assert_eq!(frame.extra_metadata.get("skill_id").map(String::as_str), Some("a"));
+        assert_eq!(db.frame_count(), 1);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn ingested_frames_only_lists_ingest_writes() {
+        let path = temp_db_path("ingested_frames");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let mut ingested = PutOptions {
+            uri: Some("aethervault://notes/a.md".into()),
+            ..PutOptions::default()
+        };
+        ingested.extra_metadata.insert("relative_path".into(), "a.md".into());
+        let id = db.put_bytes_with_options(b"a", ingested).unwrap();
+        let manual = PutOptions {
+            uri: Some("aethervault://notes/manual.md".into()),
+            ..PutOptions::default()
+        };
+        db.put_bytes_with_options(b"m", manual).unwrap();
+
+        let frames = db.ingested_frames("aethervault://notes/");
+        assert_eq!(frames, vec![(id, "aethervault://notes/a.md".to_string())]);
+        db.delete_frame(id).unwrap();
+        assert!(db.ingested_frames("aethervault://notes/").is_empty());
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_put_and_get() {
         let path = temp_db_path("put_get");
@@ -1503,6 +2200,148 @@ mod tests {
         std::fs::remove_file(&path).ok();
     }
 
+    #[test]
+    fn verified_reads_reject_corrupt_payloads() {
+        let path = temp_db_path("verify_reads");
+        let _ = std::fs::remove_file(&path);
+        let mut db = MemoryDb::open_or_create(&path).unwrap();
+        let id = db
+            .put_bytes_with_options(b"intact text", PutOptions {
+                uri: Some("test://doc/v".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        db.verify_reads = true;
+        assert_eq!(db.frame_text_by_id(id).unwrap(), "intact text");
+        assert!(db.verify_checksums().unwrap().mismatches.is_empty());
+
+        db.conn
+            .execute(
+                "UPDATE frames SET payload = ?1, text_content = ?2 WHERE id = ?3",
+                params![b"bit rot".to_vec(), "bit rot", id as i64],
+            )
+            .unwrap();
+        assert!(db.frame_text_by_id(id).unwrap_err().contains("checksum mismatch"));
+        assert!(db.frame_canonical_payload(id).is_err());
+        let report = db.verify_checksums().unwrap();
+        assert_eq!((report.checked, report.mismatches[0].frame_id), (1, id));
+
+        db.verify_reads = false;
+        assert!(db.frame_canonical_payload(id).is_ok());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn compressed_payloads_round_trip() {
+        let path = temp_db_path("compressed_payload");
+        let _ = std::fs::remove_file(&path);
+        let mut db = MemoryDb::open_or_create(&path).unwrap();
+        let body = "the zeppelin logbook records altitude and heading. ".repeat(200);
+        let id = db
+            .put_bytes_with_options(body.as_bytes(), PutOptions {
+                uri: Some("test://doc/big".into()),
+                compress: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let stored: Vec<u8> = db
+            .conn
+            .query_row("SELECT payload FROM frames WHERE id = ?", params![id as i64], |row| row.get(0))
+            .unwrap();
+        assert!(stored.len() < body.len() / 4);
+        db.verify_reads = true;
+        assert_eq!(db.frame_canonical_payload(id).unwrap(), body.as_bytes());
+        assert_eq!(db.frame_text_by_id(id).unwrap(), body);
+        assert!(db.verify_checksums().unwrap().mismatches.is_empty());
+
+        let response = db
+            .search(SearchRequest {
+                query: "zeppelin altitude".to_string(),
+                top_k: 10,
+                snippet_chars: 80,
+                scope: None,
+                temporal: None,
+                as_of_frame: None,
+                as_of_ts: None,
+            })
+            .unwrap();
+        assert_eq!(response.hits[0].uri, "test://doc/big");
+
+        // A copy written without --compress must not inherit the encoding marker.
+        let frame = db.frame_by_id(id).unwrap();
+        let copy = db
+            .put_bytes_with_options(body.as_bytes(), PutOptions {
+                uri: Some("test://doc/copy".into()),
+                extra_metadata: frame.extra_metadata.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(db.frame_canonical_payload(copy).unwrap(), body.as_bytes());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn write_batch_commits_on_interval_and_drop() {
+        let path = temp_db_path("write_batch");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let count = |db: &MemoryDb| -> i64 {
+            db.conn.query_row("SELECT COUNT(*) FROM feedback", [], |r| r.get(0)).unwrap()
+        };
+
+        let mut batch = db.write_batch(2).unwrap();
+        for i in 0..3 {
+            db.append_feedback(&format!("test://doc/{i}"), 0.5, None, None).unwrap();
+            batch.record().unwrap();
+        }
+        // Two rows were committed by the interval; a second connection sees them.
+        let reader = MemoryDb::open_or_create(&path).unwrap();
+        assert_eq!(count(&reader), 2);
+        batch.finish().unwrap();
+        assert_eq!(count(&reader), 3);
+
+        {
+            let mut batch = db.write_batch(0).unwrap();
+            db.append_feedback("test://doc/late", 0.1, None, None).unwrap();
+            batch.record().unwrap();
+        }
+        assert_eq!(count(&reader), 4);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn contended_write_lock_waits_then_reports_busy() {
+        let path = temp_db_path("lock_busy");
+        let _ = std::fs::remove_file(&path);
+        let other = MemoryDb::open_or_create(&path).unwrap();
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let holder_path = path.clone();
+        let holder = std::thread::spawn(move || {
+            let db = MemoryDb::open_or_create(&holder_path).unwrap();
+            let batch = db.write_batch(0).unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            batch.finish().unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        other.conn.busy_timeout(Duration::from_millis(50)).unwrap();
+        let started = std::time::Instant::now();
+        let err = other.write_batch(0).err().unwrap();
+        assert!(err.starts_with("capsule busy:"), "{err}");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // Released within the timeout: the waiter gets the lock.
+        other.conn.busy_timeout(Duration::from_secs(5)).unwrap();
+        other.write_batch(0).unwrap().finish().unwrap();
+        holder.join().unwrap();
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_sanitize_fts_query() {
         assert_eq!(
@@ -1683,4 +2522,90 @@ mod tests {
 
         std::fs::remove_file(&path).ok();
     }
+
+    #[test]
+    fn test_verify_fts_detects_unindexed_frame() {
+        let path = temp_db_path("verify_fts");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+
+        let opts = PutOptions {
+            uri: Some("test://doc/indexed".to_string()),
+            ..PutOptions::default()
+        };
+        db.put_bytes_with_options(b"the quartermaster inventory", opts)
+            .unwrap();
+        let report = db.verify_fts(10).unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(report.misses.is_empty());
+
+        // Simulate a crash between the frame write and its FTS entry.
+        db.conn()
+            .execute_batch(
+                "DROP TRIGGER frames_ai;
+                 INSERT INTO frames (uri, status, timestamp, text_content)
+                 VALUES ('test://doc/orphan', 'active', 0, 'an unindexed zeppelin');",
+            )
+            .unwrap();
+        let report = db.verify_fts(10).unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.misses.len(), 1);
+        assert_eq!(report.misses[0].uri.as_deref(), Some("test://doc/orphan"));
+        assert_eq!(report.misses[0].token, "unindexed");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn verify_structure_flags_duplicate_active_uris() {
+        let path = temp_db_path("verify_structure");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+
+        for body in [&b"version 1"[..], b"version 2"] {
+            let opts = PutOptions {
+                uri: Some("test://doc/1".to_string()),
+                ..PutOptions::default()
+            };
+            db.put_bytes_with_options(body, opts).unwrap();
+        }
+        let report = db.verify_structure().unwrap();
+        assert!(report.integrity.is_empty());
+        assert_eq!((report.frames, report.active, report.superseded), (2, 1, 1));
+        assert!(report.duplicate_active_uris.is_empty());
+
+        // A write that skipped the supersede step leaves two active versions.
+        db.conn()
+            .execute_batch("UPDATE frames SET status = 'active'; INSERT INTO frames (status, timestamp) VALUES ('bogus', 0);")
+            .unwrap();
+        let report = db.verify_structure().unwrap();
+        assert_eq!(report.duplicate_active_uris, vec!["test://doc/1".to_string()]);
+        assert_eq!(report.unknown_status, 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn prune_keeps_latest_versions_per_uri() {
+        let path = temp_db_path("prune_versions");
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for body in ["v1", "v2", "v3"] {
+            let opts = PutOptions {
+                uri: Some("test://doc/versioned".to_string()),
+                ..PutOptions::default()
+            };
+            db.put_bytes_with_options(body.as_bytes(), opts).unwrap();
+        }
+
+        let candidates = db.prune_candidates(2, None).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert!(db.prune_candidates(1, Some(0)).unwrap().is_empty());
+        assert_eq!(db.purge_frames(&[candidates[0].0]).unwrap(), 1);
+        assert_eq!(db.prune_candidates(1, None).unwrap().len(), 1);
+        let latest = db.frame_by_uri("test://doc/versioned").unwrap();
+        assert_eq!(db.frame_text_by_id(latest.id).unwrap(), "v3");
+
+        std::fs::remove_file(&path).ok();
+    }
 }
@@ -0,0 +1,279 @@
+use std::io::{self, Read};
+use std::time::Duration;
+
+use crate::{
+    check_hook_protocol_version, collect_system_blocks, env_optional, env_required, env_u64,
+    send_with_retry, AgentHookRequest, AgentHookResponse, AgentMessage, AgentToolCall,
+    HookRetryPolicy, AGENT_HOOK_PROTOCOL_VERSION,
+};
+
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+// ---------------------------------------------------------------------------
+// Message conversion helpers
+// ---------------------------------------------------------------------------
+
+/// User content as chat-completions parts, turning `[AV_IMAGE:media_type:base64]`
+/// markers into `image_url` data URIs. Plain text stays a string.
+fn to_openai_user_content(content: &str) -> serde_json::Value {
+    if !content.contains("[AV_IMAGE:") {
+        return serde_json::json!(content);
+    }
+    let mut parts = Vec::new();
+    let mut remaining = content;
+    while let Some(start) = remaining.find("[AV_IMAGE:") {
+        let before = remaining[..start].trim();
+        if !before.is_empty() {
+            parts.push(serde_json::json!({"type": "text", "text": before}));
+        }
+        let after_prefix = &remaining[start + "[AV_IMAGE:".len()..];
+        let Some(end) = after_prefix.find(']') else {
+            remaining = after_prefix;
+            break;
+        };
+        if let Some((media_type, data)) = after_prefix[..end].split_once(':') {
+            parts.push(serde_json::json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:{media_type};base64,{data}") }
+            }));
+        }
+        remaining = &after_prefix[end + 1..];
+    }
+    if !remaining.trim().is_empty() {
+        parts.push(serde_json::json!({"type": "text", "text": remaining.trim()}));
+    }
+    serde_json::json!(parts)
+}
+
+pub(crate) fn to_openai_messages(messages: &[AgentMessage]) -> Vec<serde_json::Value> {
+    let mut out = Vec::new();
+    let system_blocks = collect_system_blocks(messages);
+    if !system_blocks.is_empty() {
+        out.push(serde_json::json!({"role": "system", "content": system_blocks.join("\n\n")}));
+    }
+    for msg in messages {
+        match msg.role.as_str() {
+            "user" => {
+                let content = msg.content.clone().unwrap_or_default();
+                out.push(serde_json::json!({"role": "user", "content": to_openai_user_content(&content)}));
+            }
+            "assistant" => {
+                let mut entry = serde_json::json!({
+                    "role": "assistant",
+                    "content": msg.content.clone().filter(|c| !c.is_empty()),
+                });
+                if !msg.tool_calls.is_empty() {
+                    let calls: Vec<serde_json::Value> = msg
+                        .tool_calls
+                        .iter()
+                        .map(|call| {
+                            serde_json::json!({
+                                "id": call.id,
+                                "type": "function",
+                                "function": { "name": call.name, "arguments": call.args.to_string() }
+                            })
+                        })
+                        .collect();
+                    entry["tool_calls"] = serde_json::json!(calls);
+                }
+                out.push(entry);
+            }
+            "tool" => {
+                let Some(tool_id) = msg.tool_call_id.clone() else {
+                    continue;
+                };
+                let content = msg.content.clone().unwrap_or_default();
+                let content = if msg.is_error.unwrap_or(false) {
+                    format!("[error] {content}")
+                } else {
+                    content
+                };
+                out.push(serde_json::json!({"role": "tool", "tool_call_id": tool_id, "content": content}));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+pub(crate) fn to_openai_tools(tools: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    let mut out = Vec::new();
+    for tool in tools {
+        let Some(name) = tool.get("name").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let mut function = serde_json::json!({ "name": name });
+        if let Some(desc) = tool.get("description").and_then(|v| v.as_str()) {
+            function["description"] = serde_json::json!(desc);
+        }
+        if let Some(schema) = tool.get("inputSchema").or_else(|| tool.get("input_schema")) {
+            function["parameters"] = schema.clone();
+        }
+        out.push(serde_json::json!({ "type": "function", "function": function }));
+    }
+    out
+}
+
+pub(crate) fn parse_openai_response(
+    payload: &serde_json::Value,
+) -> Result<AgentHookResponse, Box<dyn std::error::Error>> {
+    let message = payload
+        .get("choices")
+        .and_then(|v| v.get(0))
+        .and_then(|v| v.get("message"))
+        .ok_or("chat completion response missing choices[0].message")?;
+    let content = message
+        .get("content")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    let mut tool_calls = Vec::new();
+    if let Some(calls) = message.get("tool_calls").and_then(|v| v.as_array()) {
+        for call in calls {
+            tool_calls.push(serde_json::from_value::<AgentToolCall>(call.clone())?);
+        }
+    }
+    Ok(AgentHookResponse {
+        protocol_version: Some(AGENT_HOOK_PROTOCOL_VERSION),
+        message: AgentMessage {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            name: None,
+            tool_call_id: None,
+            is_error: None,
+            thinking_blocks: Vec::new(),
+        },
+    })
+}
+
+/// Chat completions against `OPENAI_BASE_URL` (any compatible server).
+/// `OPENAI_API_KEY` may be unset for local servers that do not check it.
+pub(crate) fn call_openai(
+    request: &AgentHookRequest,
+) -> Result<AgentHookResponse, Box<dyn std::error::Error>> {
+    let api_key = env_optional("OPENAI_API_KEY");
+    let model = env_required("OPENAI_MODEL")?;
+    let base_url = env_optional("OPENAI_BASE_URL")
+        .unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.to_string());
+    let endpoint = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    let max_tokens = env_optional("OPENAI_MAX_TOKENS")
+        .map(|v| v.parse::<u64>())
+        .transpose()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid OPENAI_MAX_TOKENS"))?;
+    let temperature = env_optional("OPENAI_TEMPERATURE")
+        .map(|v| v.parse::<f64>())
+        .transpose()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid OPENAI_TEMPERATURE"))?;
+    let timeout = env_u64("OPENAI_TIMEOUT", u64::MAX)?;
+    let retry_policy = HookRetryPolicy::from_env("OPENAI")?;
+
+    let mut payload = serde_json::json!({
+        "model": model,
+        "messages": to_openai_messages(&request.messages),
+    });
+    let tools = to_openai_tools(&request.tools);
+    if !tools.is_empty() {
+        payload["tools"] = serde_json::json!(tools);
+    }
+    if let Some(max_tokens) = max_tokens {
+        payload["max_tokens"] = serde_json::json!(max_tokens);
+    }
+    if let Some(temp) = temperature {
+        payload["temperature"] = serde_json::json!(temp);
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(timeout))
+        .timeout_read(Duration::from_secs(timeout))
+        .timeout_write(Duration::from_secs(timeout))
+        .build();
+
+    let body = send_with_retry("call_openai", &retry_policy, &payload, || {
+        let mut req = agent.post(&endpoint).set("content-type", "application/json");
+        if let Some(key) = &api_key {
+            req = req.set("authorization", &format!("Bearer {key}"));
+        }
+        req
+    })
+    .map_err(|e| format!("chat completions request failed: {e}"))?;
+    let payload: serde_json::Value = serde_json::from_str(&body)?;
+    parse_openai_response(&payload)
+}
+
+pub(crate) fn run_openai_hook() -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    if input.trim().is_empty() {
+        return Err("OpenAI hook received empty input".into());
+    }
+    let req: AgentHookRequest = serde_json::from_str(&input)?;
+    check_hook_protocol_version(Some(req.protocol_version))?;
+    let response = call_openai(&req)?;
+    println!("{}", serde_json::to_string(&response)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: Option<&str>) -> AgentMessage {
+        AgentMessage {
+            role: role.to_string(),
+            content: content.map(|c| c.to_string()),
+            tool_calls: Vec::new(),
+            name: None,
+            tool_call_id: None,
+            is_error: None,
+            thinking_blocks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn translates_multi_turn_tool_use() {
+        let mut assistant = message("assistant", None);
+        assistant.tool_calls.push(AgentToolCall {
+            id: "call_1".to_string(),
+            name: "query".to_string(),
+            args: serde_json::json!({"query": "rust"}),
+        });
+        let mut result = message("tool", Some("no hits"));
+        result.tool_call_id = Some("call_1".to_string());
+        result.is_error = Some(true);
+        let messages = vec![
+            message("system", Some("be brief")),
+            message("user", Some("find rust")),
+            assistant,
+            result,
+        ];
+
+        let out = to_openai_messages(&messages);
+        assert_eq!(out[0], serde_json::json!({"role": "system", "content": "be brief"}));
+        assert_eq!(out[1]["content"], "find rust");
+        assert_eq!(out[2]["content"], serde_json::Value::Null);
+        assert_eq!(out[2]["tool_calls"][0]["function"]["arguments"], r#"{"query":"rust"}"#);
+        assert_eq!(out[3]["tool_call_id"], "call_1");
+        assert_eq!(out[3]["content"], "[error] no hits");
+
+        let tools = to_openai_tools(&[serde_json::json!({
+            "name": "query", "description": "search", "inputSchema": {"type": "object"}
+        })]);
+        assert_eq!(tools[0]["function"]["parameters"]["type"], "object");
+    }
+
+    #[test]
+    fn parses_tool_calls_from_completion() {
+        let payload = serde_json::json!({"choices": [{"message": {
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{"id": "call_2", "type": "function",
+                            "function": {"name": "search", "arguments": "{\"query\":\"tea\"}"}}]
+        }}]});
+        let response = parse_openai_response(&payload).unwrap();
+        assert!(response.message.content.is_none());
+        assert_eq!(response.message.tool_calls[0].name, "search");
+        assert_eq!(response.message.tool_calls[0].args["query"], "tea");
+        assert!(parse_openai_response(&serde_json::json!({"choices": []})).is_err());
+    }
+}
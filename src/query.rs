@@ -1,8 +1,12 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use crate::memory_db::{
-    Frame, MemoryDb, SearchHit, SearchRequest, TemporalFilter,
+    Frame, MemoryDb, SearchHit, SearchRequest, SearchResponse, TemporalFilter,
 };
 use chrono::Utc;
+use crate::bridges::whatsapp::escape_xml;
 use serde_json;
 
 use super::*;
@@ -32,20 +36,35 @@ pub(crate) fn collect_latest_frames(db: &MemoryDb, include_inactive: bool) -> Ha
     out
 }
 
-pub(crate) fn has_strong_signal(hits: &[SearchHit]) -> bool {
+pub(crate) fn has_strong_signal(hits: &[SearchHit], t: &SignalThresholds) -> bool {
     let s1 = hits.first().and_then(|h| h.score).unwrap_or(0.0);
     let s2 = hits.get(1).and_then(|h| h.score).unwrap_or(0.0);
     if s1 <= 0.0 {
         return false;
     }
-    if s1 <= 1.5 {
-        s1 >= 0.85 && (s1 - s2) >= 0.15
+    if s1 <= t.low_score_ceiling {
+        s1 >= t.low_min_score && (s1 - s2) >= t.low_min_gap
     } else {
         let ratio = if s2 > 0.0 { s1 / s2 } else { 10.0 };
-        s1 >= 2.0 && ratio >= 1.3
+        s1 >= t.high_min_score && ratio >= t.high_min_ratio
     }
 }
 
+/// Probe depth when `--fast-path` is on: the dominant hit plus two runners-up.
+pub(crate) const FAST_PATH_RESULTS: usize = 3;
+pub(crate) const DEFAULT_FAST_PATH_MARGIN: f32 = 3.0;
+
+/// True when the top hit outscores the runner-up by at least `margin`x
+/// (or has no positive-scoring competitor at all).
+pub(crate) fn is_dominant_hit(hits: &[SearchHit], margin: f32) -> bool {
+    let s1 = hits.first().and_then(|h| h.score).unwrap_or(0.0);
+    let s2 = hits.get(1).and_then(|h| h.score).unwrap_or(0.0);
+    if s1 <= 0.0 {
+        return false;
+    }
+    s2 <= 0.0 || s1 >= s2 * margin.max(1.0)
+}
+
 pub(crate) fn build_ranked_list(lane: LaneKind, query: &str, is_base: bool, hits: &[SearchHit]) -> RankedList {
     let items = hits
         .iter()
@@ -70,11 +89,27 @@ pub(crate) fn build_ranked_list(lane: LaneKind, query: &str, is_base: bool, hits
     }
 }
 
+/// RRF weight of a ranked list: the base query counts double.
+fn rrf_weight(list: &RankedList) -> f32 {
+    if list.is_base { 2.0 } else { 1.0 }
+}
+
+/// Best fused score any candidate could reach over `lists`: rank 1 in every
+/// list, bonus included. Dividing by it maps RRF totals into [0,1], the same
+/// scale as the rerank blend, so `score` means the same thing across queries.
+pub(crate) fn rrf_ceiling(lists: &[RankedList], k: f32) -> f32 {
+    lists
+        .iter()
+        .filter(|list| !list.items.is_empty())
+        .map(|list| rrf_weight(list) / (k + 1.0) + 0.05)
+        .sum()
+}
+
 pub(crate) fn rrf_fuse(lists: &[RankedList], k: f32) -> Vec<FusedCandidate> {
     let mut map: HashMap<String, FusedCandidate> = HashMap::new();
 
     for list in lists {
-        let weight = if list.is_base { 2.0 } else { 1.0 };
+        let weight = rrf_weight(list);
         for (i, item) in list.items.iter().enumerate() {
             let rank = i + 1;
             let rrf = weight / (k + rank as f32);
@@ -159,6 +194,50 @@ pub(crate) fn chunk_text(text: &str, max_chars: usize, overlap: usize) -> Vec<(S
     chunks
 }
 
+/// A `max_chars` window of `text` centered on the first occurrence of a query
+/// term, trimmed to word boundaries, with `SNIPPET_ELLIPSIS` on each side that
+/// was cut. `None` when no term occurs.
+pub(crate) fn centered_snippet(text: &str, query: &str, max_chars: usize) -> Option<String> {
+    let tokens = tokenize(query);
+    let mut terms: Vec<&String> = tokens
+        .iter()
+        .filter(|t| t.len() >= 3 && !is_stopword(t))
+        .collect();
+    if terms.is_empty() {
+        terms = tokens.iter().filter(|t| !t.is_empty()).collect();
+    }
+    let lower = text.to_ascii_lowercase();
+    let (hit, term_len) = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()).map(|pos| (pos, t.len())))
+        .min()?;
+
+    let total = text.chars().count();
+    if max_chars == 0 || total <= max_chars {
+        return Some(text.trim().to_string());
+    }
+    let center = text[..hit].chars().count() + text[hit..hit + term_len].chars().count() / 2;
+    let start_char = center.saturating_sub(max_chars / 2).min(total - max_chars);
+    let byte_at = |n: usize| text.char_indices().nth(n).map_or(text.len(), |(b, _)| b);
+    let (mut start, mut end) = (byte_at(start_char), byte_at(start_char + max_chars));
+    // Do not cut words in half, unless that would drop the matched term.
+    if start > 0
+        && start < hit
+        && let Some(ws) = text[start..hit].find(char::is_whitespace)
+    {
+        start += ws;
+    }
+    if end < text.len()
+        && end > hit + term_len
+        && let Some(ws) = text[hit + term_len..end].rfind(char::is_whitespace)
+    {
+        end = hit + term_len + ws;
+    }
+    let head = if start > 0 { SNIPPET_ELLIPSIS } else { "" };
+    let tail = if end < text.len() { SNIPPET_ELLIPSIS } else { "" };
+    Some(format!("{head}{}{tail}", text[start..end].trim()))
+}
+
 pub(crate) fn rerank_score(query: &str, chunk: &str) -> f32 {
     let query_lower = query.to_ascii_lowercase();
     let terms: Vec<String> = query_lower
@@ -191,19 +270,54 @@ pub(crate) fn rerank_score(query: &str, chunk: &str) -> f32 {
 }
 
 pub(crate) fn print_plan(plan: &QueryPlan) {
-    eprintln!("├─ {}", plan.cleaned_query);
-    if !plan.lex_queries.is_empty() {
-        for (i, q) in plan.lex_queries.iter().enumerate() {
-            let prefix = if i == plan.lex_queries.len() - 1 {
-                "└─"
-            } else {
-                "├─"
-            };
-            eprintln!("{prefix} lex: {q}");
-        }
+    log_info!("├─ {}", plan.cleaned_query);
+    let lanes: Vec<(&str, &String)> = plan
+        .lex_queries
+        .iter()
+        .map(|q| ("lex", q))
+        .chain(plan.vec_queries.iter().map(|q| ("vec", q)))
+        .collect();
+    for (i, (lane, q)) in lanes.iter().enumerate() {
+        let prefix = if i == lanes.len() - 1 { "└─" } else { "├─" };
+        log_info!("{prefix} {lane}: {q}");
     }
 }
 
+/// Score multiplier favouring newer frames: 1.3 for a frame written now, 1.15
+/// one half-life later, tending to 1.0 (the same shape as the lexical lane's boost).
+fn recency_factor(timestamp: i64, now: i64, halflife_days: f32) -> f32 {
+    let age_days = (now - timestamp).max(0) as f32 / 86_400.0;
+    1.0 + 0.3 * 0.5f32.powf(age_days / halflife_days)
+}
+
+pub(crate) const DEFAULT_SEARCH_JOBS: usize = 4;
+
+/// Map `f` over `items` with at most `jobs` threads in flight, returning
+/// results in input order so callers see the same output as a serial loop.
+pub(crate) fn parallel_map_ordered<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.max(1);
+    if jobs == 1 || items.len() <= 1 {
+        return items.iter().map(&f).collect();
+    }
+    let mut out = Vec::with_capacity(items.len());
+    for chunk in items.chunks(jobs) {
+        let chunk_results: Vec<R> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk.iter().map(|item| scope.spawn(|| f(item))).collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("search worker panicked"))
+                .collect()
+        });
+        out.extend(chunk_results);
+    }
+    out
+}
+
 pub(crate) fn load_feedback_scores(
     db: &MemoryDb,
     targets: &std::collections::HashSet<String>,
@@ -211,19 +325,176 @@ pub(crate) fn load_feedback_scores(
     db.load_feedback_scores(targets)
 }
 
+/// Seconds a cached `QueryResponse` stays valid; `u64::MAX` means "not set,
+/// read `AETHERVAULT_QUERY_CACHE_TTL`". 0 disables the cache.
+static QUERY_CACHE_TTL: AtomicU64 = AtomicU64::new(u64::MAX);
+const QUERY_CACHE_MAX_ENTRIES: usize = 256;
+
+struct CachedQuery {
+    version: String,
+    stored: Instant,
+    response: QueryResponse,
+}
+
+/// In-process results for repeated identical queries (long-running `serve`,
+/// bridges and schedules), keyed by capsule path and every query option.
+static QUERY_CACHE: LazyLock<Mutex<HashMap<String, CachedQuery>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set_query_cache_ttl(secs: u64) {
+    QUERY_CACHE_TTL.store(secs, Ordering::Relaxed);
+    if secs == 0 {
+        clear_query_cache();
+    }
+}
+
+fn query_cache_ttl() -> Duration {
+    let secs = match QUERY_CACHE_TTL.load(Ordering::Relaxed) {
+        u64::MAX => {
+            let secs = env_optional("AETHERVAULT_QUERY_CACHE_TTL")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(0);
+            QUERY_CACHE_TTL.store(secs, Ordering::Relaxed);
+            secs
+        }
+        secs => secs,
+    };
+    Duration::from_secs(secs)
+}
+
+/// Drop every cached query result; returns how many were dropped.
+pub(crate) fn clear_query_cache() -> usize {
+    let mut cache = QUERY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let count = cache.len();
+    cache.clear();
+    count
+}
+
+/// Runs the query pipeline, answering from the query cache when it is enabled
+/// and the capsule's `content_version` is unchanged since the entry was stored.
 pub(crate) fn execute_query(
     db: &MemoryDb,
     args: QueryArgs,
-) -> Result<QueryResponse, Box<dyn std::error::Error>> {
+) -> Result<QueryResponse, AethervaultError> {
+    let ttl = query_cache_ttl();
+    // `--plan` prints as it runs, so it always goes through the pipeline.
+    let cache_key = (!ttl.is_zero() && !args.plan).then(|| format!("{}\u{0}{args:?}", db.path().unwrap_or_default()));
+    let Some(key) = cache_key else {
+        return run_uncached_query(db, args);
+    };
+    let version = db.content_version();
+    {
+        let mut cache = QUERY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        match cache.get(&key) {
+            Some(entry) if entry.version == version && entry.stored.elapsed() < ttl => {
+                return Ok(entry.response.clone());
+            }
+            Some(_) => {
+                cache.remove(&key);
+            }
+            None => {}
+        }
+    }
+    let response = run_uncached_query(db, args)?;
+    let mut cache = QUERY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.retain(|_, entry| entry.stored.elapsed() < ttl);
+    if cache.len() >= QUERY_CACHE_MAX_ENTRIES
+        && let Some(oldest) = cache.iter().min_by_key(|(_, e)| e.stored).map(|(k, _)| k.clone())
+    {
+        cache.remove(&oldest);
+    }
+    cache.insert(
+        key,
+        CachedQuery {
+            version,
+            stored: Instant::now(),
+            response: response.clone(),
+        },
+    );
+    Ok(response)
+}
+
+fn run_uncached_query(
+    db: &MemoryDb,
+    args: QueryArgs,
+) -> Result<QueryResponse, AethervaultError> {
+    let plain_snippets = args.plain_snippets;
+    let tidy = (!args.raw_snippets).then(|| (args.snippet_chars, args.snippet_ellipsis.clone()));
+    let explain = args.explain;
+    let mut response = run_query_pipeline(db, args)?;
+    if plain_snippets {
+        for result in &mut response.results {
+            let plain = strip_markdown(&result.snippet);
+            result.raw_snippet = Some(std::mem::replace(&mut result.snippet, plain));
+        }
+    }
+    if let Some((max_chars, ellipsis)) = tidy {
+        for result in &mut response.results {
+            result.snippet = tidy_snippet(&result.snippet, max_chars, &ellipsis);
+        }
+    }
+    if explain {
+        for result in &mut response.results {
+            result.why = Some(explain_result(result, &response.plan.cleaned_query));
+        }
+    }
+    Ok(response)
+}
+
+/// One line on why `r` ranked where it did, from its fields alone: each lane
+/// hit in `sources`, the rerank/feedback/recency adjustments, and the query
+/// terms its snippet contains.
+pub(crate) fn explain_result(r: &QueryResult, query: &str) -> String {
+    let mut parts: Vec<String> = r
+        .sources
+        .iter()
+        .map(|source| match source.split_once(':').and_then(|(lane, rest)| {
+            rest.rsplit_once('#').map(|(q, rank)| (lane, q, rank))
+        }) {
+            Some((lane, q, rank)) => format!("matched {lane} '{q}' at rank {rank}"),
+            None => format!("matched {source}"),
+        })
+        .collect();
+    if let Some(score) = r.rerank_score {
+        parts.push(format!("rerank {score:.2}"));
+    }
+    if let Some(feedback) = r.feedback_score.filter(|f| *f != 0.0) {
+        parts.push(format!("feedback {feedback:+.2}"));
+    }
+    if let Some(factor) = r.recency_factor {
+        parts.push(format!("recency x{factor:.2}"));
+    }
+    let text = r.raw_snippet.as_deref().unwrap_or(&r.snippet).to_ascii_lowercase();
+    let terms = dedup_keep_order(
+        tokenize(query)
+            .into_iter()
+            .filter(|t| t.len() >= 3 && !is_stopword(t) && text.contains(t.as_str()))
+            .collect(),
+    );
+    if !terms.is_empty() {
+        parts.push(format!("terms: {}", terms.join(", ")));
+    }
+    parts.join("; ")
+}
+
+fn run_query_pipeline(
+    db: &MemoryDb,
+    args: QueryArgs,
+) -> Result<QueryResponse, AethervaultError> {
     let mut warnings = Vec::new();
 
     let (cleaned_query, parsed) = parse_query_markup(&args.raw_query);
     if cleaned_query.trim().is_empty() {
-        return Err("Query is empty after removing markup tokens.".into());
+        return Err(AethervaultError::Validation(
+            "Query is empty after removing markup tokens.".into(),
+        ));
     }
 
     let config = load_capsule_config(db);
     let hook_config = config.as_ref().and_then(|c| c.hooks.clone());
+    let signal_thresholds = config
+        .as_ref()
+        .and_then(|c| c.signal_thresholds.clone())
+        .unwrap_or_default();
     let expansion_hook = resolve_hook_spec(
         args.expand_hook.clone(),
         args.expand_hook_timeout_ms,
@@ -242,13 +513,22 @@ pub(crate) fn execute_query(
     );
 
     let scope_collection = args.collection.or(parsed.collection);
-    let scope = scope_collection.as_deref().map(scope_prefix);
+    let mut scope = scope_collection.as_deref().map(scope_prefix);
 
-    let asof_ts = args
+    // An explicit `#N` (flag or markup) pins by frame id and wins over any date.
+    let asof_frame = args
         .asof
         .as_deref()
-        .and_then(parse_date_to_ts)
-        .or(parsed.asof_ts);
+        .and_then(parse_asof_frame)
+        .or(parsed.asof_frame);
+    let asof_ts = if asof_frame.is_some() {
+        None
+    } else {
+        args.asof
+            .as_deref()
+            .and_then(parse_date_to_ts)
+            .or(parsed.asof_ts)
+    };
 
     let before_ts = args
         .before
@@ -261,7 +541,7 @@ pub(crate) fn execute_query(
         .and_then(parse_date_to_ts)
         .or(parsed.after_ts);
 
-    let temporal = if before_ts.is_some() || after_ts.is_some() {
+    let mut temporal = if before_ts.is_some() || after_ts.is_some() {
         Some(TemporalFilter {
             start_utc: after_ts,
             end_utc: before_ts,
@@ -274,19 +554,22 @@ pub(crate) fn execute_query(
 
     // Probe for strong lexical signal to optionally skip expansion.
     let mut strong_signal = false;
-    if !args.no_expand {
+    let mut probe_hits: Vec<SearchHit> = Vec::new();
+    if !args.no_expand || args.fast_path {
         let probe_request = SearchRequest {
             query: cleaned_query.clone(),
-            top_k: 2,
-            snippet_chars: 80,
+            top_k: if args.fast_path { FAST_PATH_RESULTS } else { 2 },
+            snippet_chars: if args.fast_path { args.snippet_chars } else { 80 },
             scope: scope.clone(),
             temporal: temporal.clone(),
-            as_of_frame: None,
+            as_of_frame: asof_frame,
             as_of_ts: asof_ts,
         };
         match db.search(probe_request) {
             Ok(resp) => {
-                strong_signal = has_strong_signal(&resp.hits);
+                strong_signal = !args.no_skip_expansion_probe
+                    && has_strong_signal(&resp.hits, &signal_thresholds);
+                probe_hits = resp.hits;
             }
             Err(err) => {
                 warnings.push(format!("lex probe failed: {err}"));
@@ -294,11 +577,63 @@ pub(crate) fn execute_query(
         }
     }
 
+    if args.fast_path && strong_signal && is_dominant_hit(&probe_hits, args.fast_path_margin) {
+        let plan_obj = QueryPlan {
+            cleaned_query: cleaned_query.clone(),
+            scope,
+            as_of_ts: asof_ts,
+            as_of_frame: asof_frame,
+            temporal,
+            skipped_expansion: true,
+            lex_queries: vec![cleaned_query.clone()],
+            vec_queries: Vec::new(),
+            fast_path: true,
+            recency_halflife_days: None,
+        };
+        if args.plan {
+            print_plan(&plan_obj);
+            log_info!("   (fast path: dominant probe hit)");
+        }
+        let lists = vec![build_ranked_list(LaneKind::Lex, &cleaned_query, true, &probe_hits)];
+        let ceiling = rrf_ceiling(&lists, 60.0);
+        let mut results: Vec<QueryResult> = rrf_fuse(&lists, 60.0)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, cand)| QueryResult {
+                rank: idx + 1,
+                frame_id: cand.frame_id,
+                uri: cand.uri,
+                title: cand.title,
+                snippet: cand.snippet,
+                raw_snippet: None,
+                recency_factor: None,
+                score: (cand.rrf_score + cand.rrf_bonus) / ceiling,
+                confidence: 0.0,
+                rrf_rank: idx + 1,
+                rrf_score: cand.rrf_score + cand.rrf_bonus,
+                rerank_score: None,
+                feedback_score: None,
+                sources: cand.sources,
+                why: None,
+            })
+            .collect();
+        apply_confidence(&mut results, &mut warnings);
+        results.truncate(args.limit);
+        return Ok(QueryResponse {
+            query: args.raw_query,
+            plan: plan_obj,
+            warnings,
+            results,
+            vec_debug: args.vec_debug.then(Vec::new),
+        });
+    }
+
     let skipped_expansion = !args.no_expand && strong_signal;
-    let lex_queries = if args.no_expand || strong_signal {
+    let expanded = if args.no_expand || strong_signal {
         vec![cleaned_query.clone()]
     } else if let Some(hook) = expansion_hook.as_ref() {
         let input = ExpansionHookInput {
+            schema_version: EXPANSION_HOOK_SCHEMA_VERSION,
             query: cleaned_query.clone(),
             max_expansions: args.max_expansions,
             scope: scope.clone(),
@@ -309,6 +644,21 @@ pub(crate) fn execute_query(
                 if !output.warnings.is_empty() {
                     warnings.extend(output.warnings);
                 }
+                if args.hook_debug && !output.stderr.is_empty() {
+                    warnings.push(format!("expansion hook stderr: {}", stderr_tail(&output.stderr)));
+                }
+                if let Some(proposed) = output.scope.as_deref() {
+                    match narrow_scope(scope.as_deref(), proposed) {
+                        Ok(narrowed) => scope = Some(narrowed),
+                        Err(e) => warnings.push(format!("expansion hook scope ignored: {e}")),
+                    }
+                }
+                if let Some(proposed) = output.temporal.as_ref() {
+                    match narrow_temporal(temporal.as_ref(), proposed) {
+                        Ok(narrowed) => temporal = Some(narrowed),
+                        Err(e) => warnings.push(format!("expansion hook temporal ignored: {e}")),
+                    }
+                }
                 let mut lex = output.lex;
                 if lex.is_empty() {
                     lex = vec![cleaned_query.clone()];
@@ -323,14 +673,36 @@ pub(crate) fn execute_query(
     } else {
         build_expansions(&cleaned_query, args.max_expansions)
     };
+    // Each distinct string is searched once per lane; a repeat would only
+    // double-count the same hits in fusion.
+    let expanded_count = expanded.len();
+    let lex_queries = dedup_keep_order(expanded);
+    if lex_queries.len() < expanded_count {
+        log_debug!(
+            "lex lane: {} searches, {} duplicate expansions skipped",
+            lex_queries.len(),
+            expanded_count - lex_queries.len()
+        );
+    }
+
+    // The vector lane searches the raw query only; expansions stay lexical.
+    let vec_queries: Vec<String> = if args.no_vector || env_optional("QDRANT_URL").is_none() {
+        Vec::new()
+    } else {
+        vec![args.raw_query.clone()]
+    };
 
     let plan_obj = QueryPlan {
         cleaned_query: cleaned_query.clone(),
         scope: scope.clone(),
         as_of_ts: asof_ts,
+        as_of_frame: asof_frame,
         temporal: temporal.clone(),
         skipped_expansion,
         lex_queries: lex_queries.clone(),
+        vec_queries: vec_queries.clone(),
+        fast_path: false,
+        recency_halflife_days: (args.recency_halflife_days > 0.0).then_some(args.recency_halflife_days),
     };
 
     if args.plan {
@@ -339,17 +711,29 @@ pub(crate) fn execute_query(
 
     let mut lists: Vec<RankedList> = Vec::new();
 
-    for (i, q) in lex_queries.iter().enumerate() {
-        let request = SearchRequest {
-            query: q.clone(),
-            top_k: lane_limit,
-            snippet_chars: args.snippet_chars,
-            scope: scope.clone(),
-            temporal: temporal.clone(),
-            as_of_frame: None,
-            as_of_ts: asof_ts,
-        };
-        let hits = match db.search(request) {
+    let lex_request = |q: &String| SearchRequest {
+        query: q.clone(),
+        top_k: lane_limit,
+        snippet_chars: args.snippet_chars,
+        scope: scope.clone(),
+        temporal: temporal.clone(),
+        as_of_frame: asof_frame,
+        as_of_ts: asof_ts,
+    };
+    // Expansions run on their own read-only connections when there is more
+    // than one; a connection cannot be shared across threads.
+    let lex_results: Vec<Result<SearchResponse, String>> = match db.reader_path() {
+        Some(path) if args.search_jobs > 1 && lex_queries.len() > 1 => {
+            parallel_map_ordered(&lex_queries, args.search_jobs, |q| {
+                MemoryDb::open_read_only(std::path::Path::new(path))
+                    .map_err(|e| e.to_string())
+                    .and_then(|reader| reader.search(lex_request(q)))
+            })
+        }
+        _ => lex_queries.iter().map(|q| db.search(lex_request(q))).collect(),
+    };
+    for (i, (q, result)) in lex_queries.iter().zip(lex_results).enumerate() {
+        let hits = match result {
             Ok(resp) => resp.hits,
             Err(err) => {
                 warnings.push(format!("lex search failed for '{q}': {err}"));
@@ -362,16 +746,25 @@ pub(crate) fn execute_query(
     }
 
     // --- Qdrant external vector lane ---
-    if !args.no_vector {
+    let mut vec_debug = args.vec_debug.then(Vec::new);
+    if !vec_queries.is_empty() {
         if let Some(qdrant_url) = env_optional("QDRANT_URL") {
             let collection = env_optional("QDRANT_COLLECTION").unwrap_or_else(|| "aethervault".to_string());
-            match qdrant_search_text(&qdrant_url, &collection, &args.raw_query, lane_limit) {
-                Ok(hits) if !hits.is_empty() => {
-                    lists.push(build_ranked_list(LaneKind::Vec, &args.raw_query, false, &hits));
+            let results = parallel_map_ordered(&vec_queries, args.search_jobs, |q| {
+                qdrant_search_text(&qdrant_url, &collection, q, lane_limit)
+            });
+            for (q, result) in vec_queries.iter().zip(results) {
+                if let Some(lanes) = vec_debug.as_mut() {
+                    lanes.push(vec_lane_debug(q, &result));
                 }
-                Ok(_) => {}
-                Err(e) => {
-                    warnings.push(format!("qdrant search failed: {e}"));
+                match result {
+                    Ok(hits) if !hits.is_empty() => {
+                        lists.push(build_ranked_list(LaneKind::Vec, q, false, &hits));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warnings.push(format!("qdrant search failed for '{q}': {e}"));
+                    }
                 }
             }
         }
@@ -383,10 +776,12 @@ pub(crate) fn execute_query(
             plan: plan_obj,
             warnings,
             results: Vec::new(),
+            vec_debug,
         });
     }
 
     let fused = rrf_fuse(&lists, 60.0);
+    let ceiling = rrf_ceiling(&lists, 60.0);
 
     let rerank_mode = if rerank_hook.is_some() {
         "hook"
@@ -414,7 +809,8 @@ pub(crate) fn execute_query(
                         best_chunk = chunk;
                     }
                 }
-                rerank_scores.insert(cand.key.clone(), (best_score, Some(best_chunk)));
+                let snippet = centered_snippet(&best_chunk, &cleaned_query, args.snippet_chars);
+                rerank_scores.insert(cand.key.clone(), (best_score, snippet));
             }
             rerank_active = !rerank_scores.is_empty();
         }
@@ -446,6 +842,9 @@ pub(crate) fn execute_query(
                         if !output.warnings.is_empty() {
                             warnings.extend(output.warnings);
                         }
+                        if args.hook_debug && !output.stderr.is_empty() {
+                            warnings.push(format!("rerank hook stderr: {}", stderr_tail(&output.stderr)));
+                        }
                         for (key, score) in output.scores {
                             let snippet = output.snippets.get(&key).cloned();
                             rerank_scores.insert(key, (score, snippet));
@@ -473,24 +872,23 @@ pub(crate) fn execute_query(
         feedback_scores = load_feedback_scores(db, &targets);
     }
 
+    let recency_halflife = plan_obj.recency_halflife_days;
+    let now = Utc::now().timestamp();
+
     let mut results: Vec<QueryResult> = Vec::new();
+    // Results whose snippet came from rerank; the rest get re-windowed below.
+    let mut centered: std::collections::HashSet<u64> = std::collections::HashSet::new();
     for (idx, cand) in fused.iter().enumerate() {
         let rrf_rank = idx + 1;
         let rrf_total = cand.rrf_score + cand.rrf_bonus;
         let rerank_score_opt = rerank_scores.get(&cand.key).map(|(s, _)| *s);
         let base_score = if rerank_active {
-            let weight = if rrf_rank <= 3 {
-                0.75
-            } else if rrf_rank <= 10 {
-                0.60
-            } else {
-                0.40
-            };
+            let rerank_share = rerank_blend_weight(rrf_rank, args.rerank_weight);
             let rrf_rank_score = 1.0 / (rrf_rank as f32);
             let rerank_score = rerank_score_opt.unwrap_or(0.0);
-            weight * rrf_rank_score + (1.0 - weight) * rerank_score
+            (1.0 - rerank_share) * rrf_rank_score + rerank_share * rerank_score
         } else {
-            rrf_total
+            rrf_total / ceiling
         };
         let feedback_score = feedback_scores.get(&cand.uri).copied();
         let score = if let Some(fb) = feedback_score {
@@ -498,11 +896,23 @@ pub(crate) fn execute_query(
         } else {
             base_score
         };
+        // Recency scales the blended score last, so it composes with rerank and feedback.
+        let recency = recency_halflife.and_then(|halflife| {
+            db.frame_by_id(cand.frame_id)
+                .ok()
+                .map(|frame| recency_factor(frame.timestamp, now, halflife))
+        });
+        let score = match recency {
+            Some(factor) if score >= 0.0 => score * factor,
+            Some(factor) => score / factor,
+            None => score,
+        };
 
         let mut snippet = cand.snippet.clone();
         if let Some((_, Some(override_snippet))) = rerank_scores.get(&cand.key) {
             if !override_snippet.trim().is_empty() {
                 snippet = override_snippet.clone();
+                centered.insert(cand.frame_id);
             }
         }
 
@@ -512,12 +922,16 @@ pub(crate) fn execute_query(
             uri: cand.uri.clone(),
             title: cand.title.clone(),
             snippet,
+            raw_snippet: None,
+            recency_factor: recency,
             score,
+            confidence: 0.0,
             rrf_rank,
             rrf_score: rrf_total,
             rerank_score: rerank_score_opt,
             feedback_score,
             sources: cand.sources.clone(),
+            why: None,
         });
     }
 
@@ -526,9 +940,20 @@ pub(crate) fn execute_query(
             .partial_cmp(&a.score)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
+    // Calibrate against the whole candidate pool so `--limit 1` still tells
+    // a clear winner from one of many near-ties.
+    apply_confidence(&mut results, &mut warnings);
     results.truncate(args.limit);
     for (i, r) in results.iter_mut().enumerate() {
         r.rank = i + 1;
+        // The lexical snippet may come from the document head; window around
+        // the first query term instead when the match sits deeper.
+        if !centered.contains(&r.frame_id)
+            && let Ok(text) = db.frame_text_by_id(r.frame_id)
+            && let Some(snippet) = centered_snippet(&text, &cleaned_query, args.snippet_chars)
+        {
+            r.snippet = snippet;
+        }
     }
 
     Ok(QueryResponse {
@@ -536,15 +961,398 @@ pub(crate) fn execute_query(
         plan: plan_obj,
         warnings,
         results,
+        vec_debug,
+    })
+}
+
+/// Debug record of one vector-lane search; scores are the store's raw
+/// similarities, untouched by fusion or rerank.
+fn vec_lane_debug(query: &str, result: &Result<Vec<SearchHit>, String>) -> VecLaneDebug {
+    match result {
+        Ok(hits) => VecLaneDebug {
+            query: query.to_string(),
+            hits: hits
+                .iter()
+                .map(|hit| VecHitDebug {
+                    rank: hit.rank + 1,
+                    frame_id: hit.frame_id,
+                    uri: hit.uri.clone(),
+                    similarity: hit.score.unwrap_or(0.0),
+                })
+                .collect(),
+            error: None,
+        },
+        Err(e) => VecLaneDebug {
+            query: query.to_string(),
+            hits: Vec::new(),
+            error: Some(e.clone()),
+        },
+    }
+}
+
+/// Share of a candidate's blended score taken from its rerank score. A fixed
+/// `rerank_weight` (clamped to [0,1]) applies at every rank; without one the
+/// top RRF ranks are protected: 0.25 for ranks 1-3, 0.40 to rank 10, 0.60 after.
+pub(crate) fn rerank_blend_weight(rrf_rank: usize, rerank_weight: Option<f32>) -> f32 {
+    if let Some(weight) = rerank_weight {
+        return weight.clamp(0.0, 1.0);
+    }
+    match rrf_rank {
+        0..=3 => 0.25,
+        4..=10 => 0.40,
+        _ => 0.60,
+    }
+}
+
+/// Softmax temperature applied to scores scaled by the top score.
+const CONFIDENCE_TEMPERATURE: f32 = 0.1;
+/// Top-result confidence below which the response carries a warning.
+const LOW_CONFIDENCE_THRESHOLD: f32 = 0.35;
+
+/// Fill `confidence` with a softmax over the candidate scores, each divided by
+/// the top score so the raw scale (rerank blend vs. RRF) does not matter.
+/// A clear winner approaches 1.0; a flat set spreads towards 1/n. Callers pass
+/// the pool before `--limit` truncation.
+pub(crate) fn calibrate_confidence(results: &mut [QueryResult]) {
+    let max = results
+        .iter()
+        .map(|r| r.score)
+        .fold(f32::NEG_INFINITY, f32::max);
+    if results.is_empty() || !max.is_finite() {
+        return;
+    }
+    if max <= 0.0 {
+        let uniform = 1.0 / results.len() as f32;
+        for r in results.iter_mut() {
+            r.confidence = uniform;
+        }
+        return;
+    }
+    let weights: Vec<f32> = results
+        .iter()
+        .map(|r| ((r.score / max - 1.0) / CONFIDENCE_TEMPERATURE).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+    for (r, w) in results.iter_mut().zip(weights) {
+        r.confidence = w / total;
+    }
+}
+
+fn apply_confidence(results: &mut [QueryResult], warnings: &mut Vec<String>) {
+    calibrate_confidence(results);
+    if let Some(top) = results.first()
+        && top.confidence < LOW_CONFIDENCE_THRESHOLD
+    {
+        warnings.push(format!(
+            "low-confidence results (top confidence {:.2})",
+            top.confidence
+        ));
+    }
+}
+
+/// Accept a hook-proposed scope only if it stays inside the requested one.
+pub(crate) fn narrow_scope(requested: Option<&str>, proposed: &str) -> Result<String, String> {
+    let proposed = proposed.trim();
+    if !proposed.contains("://") {
+        return Err(format!("{proposed:?} is not a URI prefix"));
+    }
+    match requested {
+        Some(requested) if !proposed.starts_with(requested) => {
+            Err(format!("{proposed} is wider than the requested {requested}"))
+        }
+        _ => Ok(proposed.to_string()),
+    }
+}
+
+/// Intersect a hook-proposed time window with the requested one, so a hook
+/// can tighten but never widen the user's bounds.
+pub(crate) fn narrow_temporal(
+    requested: Option<&TemporalFilter>,
+    proposed: &TemporalFilter,
+) -> Result<TemporalFilter, String> {
+    let pick = |a: Option<i64>, b: Option<i64>, tighter: fn(i64, i64) -> i64| match (a, b) {
+        (Some(a), Some(b)) => Some(tighter(a, b)),
+        (a, b) => a.or(b),
+    };
+    let start_utc = pick(requested.and_then(|t| t.start_utc), proposed.start_utc, i64::max);
+    let end_utc = pick(requested.and_then(|t| t.end_utc), proposed.end_utc, i64::min);
+    if let (Some(start), Some(end)) = (start_utc, end_utc)
+        && start > end
+    {
+        return Err(format!("window {start}..{end} is empty"));
+    }
+    Ok(TemporalFilter { start_utc, end_utc })
+}
+
+/// The most frequent non-stopword terms of a document (ties keep first
+/// occurrence), used as a lexical stand-in for its embedding.
+pub(crate) fn salient_terms(text: &str, max: usize) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (pos, token) in tokenize(text).into_iter().enumerate() {
+        let token = token.trim_matches('\'').to_string();
+        if token.chars().count() < 3
+            || token.chars().all(|c| c.is_ascii_digit())
+            || is_stopword(&token)
+        {
+            continue;
+        }
+        counts.entry(token).or_insert((0, pos)).0 += 1;
+    }
+    let mut terms: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+    terms.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.1.1.cmp(&b.1.1)));
+    terms.into_iter().take(max).map(|(term, _)| term).collect()
+}
+
+/// Most URIs offered back when a partial id matches several frames.
+pub(crate) const FRAME_CANDIDATE_LIMIT: usize = 8;
+
+pub(crate) enum FrameLookup {
+    Found(Box<Frame>),
+    Ambiguous(Vec<FrameCandidate>),
+}
+
+/// `#N` is a frame id; anything else is a URI, or failing that a fragment of
+/// one. A fragment resolves when it is the prefix of exactly one URI or occurs
+/// in exactly one; otherwise up to [`FRAME_CANDIDATE_LIMIT`] matches come back.
+pub(crate) fn lookup_frame(db: &MemoryDb, id: &str) -> Result<FrameLookup, String> {
+    if let Some(rest) = id.strip_prefix('#') {
+        let frame_id: u64 = rest.parse().map_err(|_| format!("invalid frame id: {id}"))?;
+        return db.frame_by_id(frame_id).map(|f| FrameLookup::Found(Box::new(f)));
+    }
+    let exact_err = match db.frame_by_uri(id) {
+        Ok(frame) => return Ok(FrameLookup::Found(Box::new(frame))),
+        Err(e) => e,
+    };
+    let needle = id.trim();
+    if needle.is_empty() {
+        return Err(exact_err);
+    }
+    let matches = db.uris_containing(needle, FRAME_CANDIDATE_LIMIT + 1);
+    let prefixed: Vec<_> = matches
+        .iter()
+        .filter(|(_, uri)| uri.to_ascii_lowercase().starts_with(&needle.to_ascii_lowercase()))
+        .collect();
+    let single = match (prefixed.as_slice(), matches.as_slice()) {
+        ([(frame_id, _)], _) | (_, [(frame_id, _)]) => Some(*frame_id),
+        _ => None,
+    };
+    if let Some(frame_id) = single {
+        return db.frame_by_id(frame_id).map(|f| FrameLookup::Found(Box::new(f)));
+    }
+    if matches.is_empty() {
+        return Err(exact_err);
+    }
+    let candidates = matches
+        .into_iter()
+        .take(FRAME_CANDIDATE_LIMIT)
+        .map(|(frame_id, uri)| FrameCandidate {
+            frame_id,
+            title: db.frame_by_id(frame_id).ok().and_then(|f| f.title),
+            uri,
+        })
+        .collect();
+    Ok(FrameLookup::Ambiguous(candidates))
+}
+
+/// One line per candidate: `uri (#id) title`.
+pub(crate) fn format_frame_candidates(candidates: &[FrameCandidate]) -> String {
+    candidates
+        .iter()
+        .map(|c| match &c.title {
+            Some(title) => format!("- {} (#{}) {title}", c.uri, c.frame_id),
+            None => format!("- {} (#{})", c.uri, c.frame_id),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Like [`lookup_frame`], but more than one match is an error listing them.
+pub(crate) fn resolve_frame_ref(db: &MemoryDb, id: &str) -> Result<Frame, String> {
+    match lookup_frame(db, id)? {
+        FrameLookup::Found(frame) => Ok(*frame),
+        FrameLookup::Ambiguous(candidates) => Err(format!(
+            "{id} matches several frames:\n{}",
+            format_frame_candidates(&candidates)
+        )),
+    }
+}
+
+/// More-like-this: neighbours of the frame named by `id`, excluding the frame
+/// itself. Uses its stored vector when `QDRANT_URL` is set and falls back to
+/// an OR query over its salient terms otherwise (or when Qdrant fails).
+pub(crate) fn find_similar(
+    db: &MemoryDb,
+    id: &str,
+    limit: usize,
+    collection: Option<&str>,
+    snippet_chars: usize,
+) -> Result<SimilarResponse, String> {
+    let source = resolve_frame_ref(db, id)?;
+    let scope = collection.map(scope_prefix);
+    let keep = |hit: &SearchHit| {
+        hit.frame_id != source.id
+            && source.uri.as_deref() != Some(hit.uri.as_str())
+            && scope.as_deref().is_none_or(|s| hit.uri.starts_with(s))
+    };
+    let mut warnings = Vec::new();
+
+    if let Some(qdrant_url) = env_optional("QDRANT_URL") {
+        let qdrant_collection =
+            env_optional("QDRANT_COLLECTION").unwrap_or_else(|| "aethervault".to_string());
+        // Over-fetch: the source and out-of-scope points are dropped afterwards.
+        let fetch = limit.saturating_mul(2).max(limit + 1);
+        match qdrant_search_similar(&qdrant_url, &qdrant_collection, source.id, fetch) {
+            Ok(hits) if !hits.is_empty() => {
+                return Ok(SimilarResponse {
+                    source_frame_id: source.id,
+                    source_uri: source.uri.clone().unwrap_or_default(),
+                    method: "vector".to_string(),
+                    terms: Vec::new(),
+                    warnings,
+                    hits: rerank_hits(hits.into_iter().filter(|h| keep(h)), limit),
+                });
+            }
+            Ok(_) => warnings.push("qdrant returned no neighbours; using salient terms".to_string()),
+            Err(e) => warnings.push(format!("qdrant similar failed ({e}); using salient terms")),
+        }
+    }
+
+    let text = db.frame_text_by_id(source.id).unwrap_or_default();
+    let terms = salient_terms(&text, 12);
+    if terms.is_empty() {
+        return Err(format!("frame #{} has no text to compare", source.id));
+    }
+    let response = db
+        .search(SearchRequest {
+            query: terms.join(" "),
+            top_k: limit + 1,
+            snippet_chars,
+            scope: scope.clone(),
+            temporal: None,
+            as_of_frame: None,
+            as_of_ts: None,
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(SimilarResponse {
+        source_frame_id: source.id,
+        source_uri: source.uri.clone().unwrap_or_default(),
+        method: "lexical".to_string(),
+        terms,
+        warnings,
+        hits: rerank_hits(response.hits.into_iter().filter(|h| keep(h)), limit),
     })
 }
 
+fn rerank_hits(hits: impl Iterator<Item = SearchHit>, limit: usize) -> Vec<SearchHit> {
+    hits.take(limit)
+        .enumerate()
+        .map(|(rank, hit)| SearchHit { rank, ..hit })
+        .collect()
+}
+
+/// Bucket ranked results by `key_of`, keeping ranking inside each group and
+/// ordering groups by their best hit. `per_group` caps each bucket (0 = all).
+pub(crate) fn group_results(
+    results: Vec<QueryResult>,
+    per_group: usize,
+    key_of: impl Fn(&QueryResult) -> String,
+) -> Vec<ResultGroup> {
+    let mut groups: Vec<ResultGroup> = Vec::new();
+    for result in results {
+        let key = key_of(&result);
+        let index = match groups.iter().position(|g| g.key == key) {
+            Some(index) => index,
+            None => {
+                groups.push(ResultGroup { key, results: Vec::new() });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        if per_group == 0 || group.results.len() < per_group {
+            group.results.push(result);
+        }
+    }
+    groups
+}
+
+/// Opening and closing text around one document's body in `format`.
+fn context_frame(format: ContextFormat, r: &QueryResult) -> (String, String) {
+    let title = r.title.as_deref().unwrap_or_default();
+    match format {
+        ContextFormat::Plain => (format!("[{}] {} {}\n", r.rank, r.uri, title), "\n\n".to_string()),
+        ContextFormat::Markdown => {
+            let heading = if title.is_empty() { &r.uri } else { title };
+            (format!("### [{}] {heading}\n<{}>\n\n", r.rank, r.uri), "\n\n".to_string())
+        }
+        ContextFormat::Xml => (
+            format!(
+                "<document index=\"{}\" uri=\"{}\" title=\"{}\">\n",
+                r.rank,
+                escape_xml(&r.uri),
+                escape_xml(title)
+            ),
+            "\n</document>\n".to_string(),
+        ),
+    }
+}
+
+/// The chunk of `text` holding `snippet` (or, when it cannot be found, the
+/// chunk scoring best against `query`) widened by `window` chunks per side.
+/// The snippet may be tidied: whitespace is matched loosely and its edge
+/// words, which may be cut or carry an ellipsis, are ignored.
+pub(crate) fn chunk_window(
+    text: &str,
+    snippet: &str,
+    query: &str,
+    window: usize,
+    chunk_chars: usize,
+    chunk_overlap: usize,
+) -> Option<String> {
+    let chunks = chunk_text(text, chunk_chars, chunk_overlap);
+    if chunks.is_empty() {
+        return None;
+    }
+    let mut words: Vec<&str> = snippet
+        .trim_matches(|c: char| c == '…' || c.is_whitespace())
+        .split_whitespace()
+        .collect();
+    if words.len() > 2 {
+        words = words[1..words.len() - 1].to_vec();
+    }
+    let pattern = words.iter().map(|w| regex::escape(w)).collect::<Vec<_>>().join(r"\s+");
+    let found = (!words.is_empty())
+        .then(|| regex::Regex::new(&pattern).ok()?.find(text).map(|m| m.start()))
+        .flatten()
+        .map(|pos| chunks.iter().rposition(|(_, start)| *start <= pos).unwrap_or(0));
+    let center = found.unwrap_or_else(|| {
+        let mut best = (0, f32::MIN);
+        for (i, (chunk, _)) in chunks.iter().enumerate() {
+            let score = rerank_score(query, chunk);
+            if score > best.1 {
+                best = (i, score);
+            }
+        }
+        best.0
+    });
+    let first = center.saturating_sub(window);
+    let last = (center + window).min(chunks.len() - 1);
+    let start = chunks[first].1;
+    let end = chunks[last].1 + chunks[last].0.len();
+    Some(text[start..end].trim().to_string())
+}
+
+/// Pack the query results into at most `max_bytes`, highest rank first, so a
+/// budget overrun trims lower-ranked citations. `window` > 0 widens each
+/// snippet to its surrounding chunks; `full` uses whole frame text instead.
 pub(crate) fn build_context_pack(
     db: &MemoryDb,
     args: QueryArgs,
     max_bytes: usize,
     full: bool,
-) -> Result<ContextPack, Box<dyn std::error::Error>> {
+    window: usize,
+    format: ContextFormat,
+) -> Result<ContextPack, AethervaultError> {
+    let (chunk_chars, chunk_overlap) = (args.rerank_chunk_chars, args.rerank_chunk_overlap);
     let response = execute_query(db, args)?;
     let mut context = String::new();
     let mut citations = Vec::new();
@@ -553,28 +1361,39 @@ pub(crate) fn build_context_pack(
         if context.len() >= max_bytes {
             break;
         }
-        let header = format!(
-            "[{}] {} {}\n",
-            r.rank,
-            r.uri,
-            r.title.clone().unwrap_or_default()
-        );
+        let (header, footer) = context_frame(format, r);
         let mut body = if full {
             db.frame_text_by_id(r.frame_id)
                 .unwrap_or_else(|_| r.snippet.clone())
+        } else if window > 0 {
+            let matched = r.raw_snippet.as_deref().unwrap_or(&r.snippet);
+            db.frame_text_by_id(r.frame_id)
+                .ok()
+                .and_then(|text| {
+                    chunk_window(&text, matched, &response.plan.cleaned_query, window, chunk_chars, chunk_overlap)
+                })
+                .unwrap_or_else(|| r.snippet.clone())
         } else {
             r.snippet.clone()
         };
-        let remaining = max_bytes.saturating_sub(context.len() + header.len());
+        if format == ContextFormat::Xml {
+            // Keep a body from closing its own element early.
+            body = body.replace("</document>", "&lt;/document&gt;");
+        }
+        let remaining = max_bytes.saturating_sub(context.len() + header.len() + footer.len());
         if remaining == 0 {
             break;
         }
         if body.len() > remaining {
-            body.truncate(remaining);
+            let mut cut = remaining;
+            while !body.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            body.truncate(cut);
         }
         context.push_str(&header);
         context.push_str(&body);
-        context.push_str("\n\n");
+        context.push_str(&footer);
 
         citations.push(ContextCitation {
             rank: r.rank,
@@ -603,7 +1422,7 @@ pub(crate) fn append_agent_log(
         .unwrap_or_else(|| std::path::PathBuf::from(DEFAULT_WORKSPACE_DIR));
     let log_dir = log_dir_path(&workspace);
     if let Err(e) = append_log_jsonl(&log_dir, entry) {
-        eprintln!("[agent-log] JSONL write failed: {e}");
+        log_error!("[agent-log] JSONL write failed: {e}");
     }
 
     let bytes = serde_json::to_vec(entry)?;
@@ -795,7 +1614,7 @@ pub(crate) fn critic_should_fire(
             .unwrap_or("");
 
         if scan_confidence_markers(last_assistant) {
-            eprintln!("[critic] triggered: confidence markers detected");
+            log_debug!("[critic] triggered: confidence markers detected");
             *last_critic_step = step;
             return true;
         }
@@ -808,7 +1627,7 @@ pub(crate) fn critic_should_fire(
                 || lower.contains("problem")
                 || lower.contains("sorry");
             if !acknowledges {
-                eprintln!("[critic] triggered: unacknowledged tool failure");
+                log_debug!("[critic] triggered: unacknowledged tool failure");
                 *last_critic_step = step;
                 return true;
             }
@@ -820,9 +1639,9 @@ pub(crate) fn critic_should_fire(
     *last_critic_step = step;
 
     if tool_calls.len() >= 3 {
-        eprintln!("[critic] triggered: periodic + large tool batch ({})", tool_calls.len());
+        log_debug!("[critic] triggered: periodic + large tool batch ({})", tool_calls.len());
     } else {
-        eprintln!("[critic] triggered: periodic (step {})", step);
+        log_debug!("[critic] triggered: periodic (step {})", step);
     }
 
     true
@@ -843,8 +1662,221 @@ pub(crate) fn tool_autonomy_for(tool_name: &str) -> ToolAutonomyLevel {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn parallel_map_matches_serial_order() {
+        let items: Vec<usize> = (0..11).collect();
+        let serial: Vec<usize> = items.iter().map(|i| i * i).collect();
+        for jobs in [0, 1, 3, 4, 16] {
+            let parallel = parallel_map_ordered(&items, jobs, |i| {
+                // Finish out of order to prove ordering doesn't depend on timing.
+                std::thread::sleep(std::time::Duration::from_millis((11 - *i as u64) * 2));
+                i * i
+            });
+            assert_eq!(parallel, serial, "jobs={jobs}");
+        }
+    }
+
+    #[test]
+    fn parallel_lex_lane_matches_serial() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("query_jobs_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for (i, text) in [
+            "how we deploy the staging cluster",
+            "staging deploy checklist and rollback",
+            "cluster capacity planning notes",
+            "deploying to production safely",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let opts = crate::memory_db::PutOptions {
+                uri: Some(format!("aether://notes/{i}.md")),
+                ..Default::default()
+            };
+            db.put_bytes_with_options(text.as_bytes(), opts).unwrap();
+        }
+
+        let run = |jobs: usize| {
+            let mut args = crate::SearchOptions {
+                query: "deploy staging cluster".to_string(),
+                no_vector: true,
+                search_jobs: jobs,
+                ..Default::default()
+            }
+            .to_query_args();
+            args.no_skip_expansion_probe = true;
+            let response = execute_query(&db, args).unwrap();
+            let ranked: Vec<(String, f32)> = response.results.iter().map(|r| (r.uri.clone(), r.score)).collect();
+            (response.plan.lex_queries.len(), ranked)
+        };
+        assert!(db.reader_path().is_some());
+        let (searches, serial) = run(1);
+        assert!(searches > 1);
+        assert!(!serial.is_empty());
+        assert_eq!(run(4).1, serial);
+        std::fs::remove_file(&path).ok();
+    }
+
     use std::collections::VecDeque;
 
+    #[test]
+    fn recency_factor_halves_its_boost_each_halflife() {
+        let now = 1_700_000_000;
+        assert!((recency_factor(now, now, 7.0) - 1.3).abs() < 1e-6);
+        assert!((recency_factor(now - 7 * 86_400, now, 7.0) - 1.15).abs() < 1e-6);
+        assert!(recency_factor(now - 365 * 86_400, now, 7.0) < 1.001);
+        assert!((recency_factor(now + 100, now, 7.0) - 1.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn partial_ids_resolve_or_list_candidates() {
+        use crate::memory_db::PutOptions;
+        let path = std::env::temp_dir().join(format!("aethervault_lookup_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for uri in ["aether://notes/tea.md", "aether://notes/tea_2.md", "aether://notes/coffee.md", "aether://archive/notes/tea.md"] {
+            let options = PutOptions { uri: Some(uri.into()), ..Default::default() };
+            db.put_bytes_with_options(b"x", options).unwrap();
+        }
+        let found = |id: &str| match lookup_frame(&db, id).unwrap() {
+            FrameLookup::Found(frame) => frame.uri.unwrap(),
+            FrameLookup::Ambiguous(c) => panic!("{id}: {}", format_frame_candidates(&c)),
+        };
+        assert_eq!(found("coffee"), "aether://notes/coffee.md");
+        assert_eq!(found("TEA_2"), "aether://notes/tea_2.md");
+        let Ok(FrameLookup::Ambiguous(candidates)) = lookup_frame(&db, "tea") else { panic!("expected candidates") };
+        assert_eq!(candidates.len(), 3);
+        assert!(lookup_frame(&db, "nothing-like-this").is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn query_cache_is_invalidated_by_writes() {
+        use crate::memory_db::PutOptions;
+        let path = std::env::temp_dir().join(format!("aethervault_query_cache_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let put = |uri: &str, text: &str| {
+            let options = PutOptions { uri: Some(uri.into()), ..Default::default() };
+            db.put_bytes_with_options(text.as_bytes(), options).unwrap();
+        };
+        put("aethervault://notes/a.md", "oolong tea notes");
+        set_query_cache_ttl(60);
+        let args = || crate::api::SearchOptions { rerank: "none".into(), ..crate::api::SearchOptions::new("oolong") }.to_query_args();
+
+        let key_prefix = format!("{}\u{0}", db.path().unwrap());
+        let first = execute_query(&db, args()).unwrap();
+        assert_eq!(first.results.len(), 1);
+        let cached = |prefix: &str| QUERY_CACHE.lock().unwrap().keys().filter(|k| k.starts_with(prefix)).count();
+        assert_eq!(cached(&key_prefix), 1);
+        assert_eq!(execute_query(&db, args()).unwrap().results.len(), 1);
+
+        put("aethervault://notes/b.md", "more oolong");
+        assert_eq!(execute_query(&db, args()).unwrap().results.len(), 2);
+        let empty = execute_query(&db, crate::api::SearchOptions::new(" ").to_query_args()).unwrap_err();
+        assert!(matches!(empty, AethervaultError::Validation(_)), "{empty}");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn context_formats_keep_rank_anchors() {
+        let result = QueryResult {
+            rank: 2,
+            frame_id: 7,
+            uri: "aethervault://notes/a&b.md".into(),
+            title: Some("A \"B\"".into()),
+            snippet: String::new(),
+            raw_snippet: None,
+            recency_factor: None,
+            score: 0.0,
+            confidence: 0.0,
+            rrf_rank: 2,
+            rrf_score: 0.0,
+            rerank_score: None,
+            feedback_score: None,
+            sources: Vec::new(),
+            why: None,
+        };
+        let (plain, _) = context_frame(ContextFormat::Plain, &result);
+        assert_eq!(plain, "[2] aethervault://notes/a&b.md A \"B\"\n");
+        let (markdown, _) = context_frame(ContextFormat::Markdown, &result);
+        assert!(markdown.starts_with("### [2] A \"B\"\n<aethervault://notes/a&b.md>"));
+        let (xml, close) = context_frame(ContextFormat::Xml, &result);
+        assert!(xml.starts_with("<document index=\"2\" uri=\"aethervault://notes/a&amp;b.md\" title=\"A &quot;B&quot;\">"), "{xml}");
+        assert_eq!(close, "\n</document>\n");
+    }
+
+    #[test]
+    fn snippets_center_on_the_first_query_term() {
+        let text = format!("{} the quarterly budget review happens here {}", "intro ".repeat(40), "tail ".repeat(40));
+        let snippet = centered_snippet(&text, "budget review", 40).unwrap();
+        assert!(snippet.contains("budget"), "{snippet}");
+        let body = snippet.strip_prefix(SNIPPET_ELLIPSIS).and_then(|s| s.strip_suffix(SNIPPET_ELLIPSIS)).unwrap();
+        assert!(body.chars().count() <= 40);
+        assert!(!body.starts_with("ntro") && !body.ends_with("ai"));
+        assert!(centered_snippet(&text, "missing", 40).is_none());
+        assert_eq!(centered_snippet(&text, "quarterly", 3).unwrap().trim_matches('…').chars().count(), 3);
+        assert_eq!(centered_snippet("short Budget note", "budget", 300).unwrap(), "short Budget note");
+    }
+
+    #[test]
+    fn salient_terms_rank_by_frequency_then_position() {
+        let text = "The roadmap covers Qdrant. Qdrant vectors; roadmap roadmap at 2024 of it's";
+        assert_eq!(salient_terms(text, 3), vec!["roadmap", "qdrant", "covers"]);
+        assert!(salient_terms("a an the 42 of", 5).is_empty());
+    }
+
+    #[test]
+    fn expansion_hook_can_only_narrow_scope_and_time() {
+        let notes = "aethervault://notes/";
+        assert_eq!(narrow_scope(Some(notes), "aethervault://notes/2024/").unwrap(), "aethervault://notes/2024/");
+        assert!(narrow_scope(Some(notes), "aethervault://").is_err());
+        assert!(narrow_scope(None, "notes").is_err());
+        assert_eq!(narrow_scope(None, "aethervault://email/").unwrap(), "aethervault://email/");
+
+        let window = |start_utc, end_utc| TemporalFilter { start_utc, end_utc };
+        let narrowed = narrow_temporal(Some(&window(Some(100), Some(500))), &window(Some(50), Some(300))).unwrap();
+        assert_eq!((narrowed.start_utc, narrowed.end_utc), (Some(100), Some(300)));
+        let added = narrow_temporal(None, &window(Some(200), None)).unwrap();
+        assert_eq!((added.start_utc, added.end_utc), (Some(200), None));
+        assert!(narrow_temporal(Some(&window(None, Some(100))), &window(Some(200), None)).is_err());
+    }
+
+    #[test]
+    fn group_results_keeps_rank_order_and_caps_groups() {
+        let uris = [
+            "aethervault://docs/a.md",
+            "aethervault://email/x",
+            "aethervault://docs/b.md",
+            "aethervault://docs/c.md",
+            "plain-uri",
+        ];
+        let results = uris
+            .iter()
+            .enumerate()
+            .map(|(i, uri)| QueryResult {
+                rank: i + 1,
+                uri: uri.to_string(),
+                ..result(1.0)
+            })
+            .collect();
+        let groups = group_results(results, 2, |r| {
+            uri_collection(&r.uri).unwrap_or_else(|| "<none>".to_string())
+        });
+        let summary: Vec<(&str, Vec<usize>)> = groups
+            .iter()
+            .map(|g| (g.key.as_str(), g.results.iter().map(|r| r.rank).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("docs", vec![1, 3]), ("email", vec![2]), ("<none>", vec![5])]
+        );
+    }
+
     #[test]
     fn detect_cycle_single_repeat() {
         let mut actions = VecDeque::new();
@@ -970,6 +2002,49 @@ mod tests {
         let fused = rrf_fuse(&lists, 60.0);
         assert_eq!(fused.len(), 1);
         assert_eq!(fused[0].sources.len(), 2);
+        // Rank 1 in every list is the best possible fused score.
+        let ceiling = rrf_ceiling(&lists, 60.0);
+        assert!(((fused[0].rrf_score + fused[0].rrf_bonus) / ceiling - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rerank_blend_weight_tiers_and_override() {
+        assert_eq!(rerank_blend_weight(1, None), 0.25);
+        assert_eq!(rerank_blend_weight(10, None), 0.40);
+        assert_eq!(rerank_blend_weight(11, None), 0.60);
+        assert_eq!(rerank_blend_weight(1, Some(0.9)), 0.9);
+        assert_eq!(rerank_blend_weight(50, Some(0.9)), 0.9);
+        assert_eq!(rerank_blend_weight(1, Some(1.5)), 1.0);
+    }
+
+    #[test]
+    fn chunk_window_adds_neighbours_of_the_matched_chunk() {
+        let text: String = (0..10).map(|i| format!("para{i} ").repeat(10)).collect();
+        // Each paragraph is exactly one 60-char chunk when there is no overlap.
+        let one = chunk_window(&text, "para5", "", 0, 60, 0).unwrap();
+        assert!(one.starts_with("para5") && !one.contains("para4"));
+        let wide = chunk_window(&text, "para5", "", 1, 60, 0).unwrap();
+        assert!(wide.contains("para4") && wide.contains("para5") && wide.contains("para6"));
+        assert!(!wide.contains("para3") && !wide.contains("para7"));
+        // An unmatched snippet falls back to the chunk that best fits the query.
+        let fallback = chunk_window(&text, "missing", "para8", 0, 60, 0).unwrap();
+        assert!(fallback.contains("para8"));
+    }
+
+    #[test]
+    fn explain_result_summarizes_lanes_adjustments_and_terms() {
+        let hit = QueryResult {
+            snippet: "How we deploy the staging cluster".to_string(),
+            rerank_score: Some(0.42),
+            feedback_score: Some(0.3),
+            sources: vec!["lex:deploy staging#2".to_string(), "vec:deploy to staging?#1".to_string()],
+            ..result(0.8)
+        };
+        assert_eq!(
+            explain_result(&hit, "deploy the staging cluster"),
+            "matched lex 'deploy staging' at rank 2; matched vec 'deploy to staging?' at rank 1; \
+             rerank 0.42; feedback +0.30; terms: deploy, staging, cluster"
+        );
     }
 
     #[test]
@@ -977,4 +2052,118 @@ mod tests {
         let fused = rrf_fuse(&[], 60.0);
         assert!(fused.is_empty());
     }
+
+    fn hit(score: f32) -> SearchHit {
+        SearchHit {
+            rank: 0,
+            frame_id: 0,
+            uri: String::new(),
+            title: None,
+            range: (0, 0),
+            text: String::new(),
+            matches: 0,
+            chunk_range: None,
+            chunk_text: None,
+            raw_text: None,
+            score: Some(score),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn dominant_hit_requires_margin() {
+        assert!(is_dominant_hit(&[hit(9.0), hit(2.0)], 3.0));
+        assert!(!is_dominant_hit(&[hit(5.0), hit(2.0)], 3.0));
+        assert!(is_dominant_hit(&[hit(1.0)], 3.0));
+        assert!(!is_dominant_hit(&[hit(0.0)], 3.0));
+        assert!(!is_dominant_hit(&[], 3.0));
+    }
+
+    #[test]
+    fn strong_signal_uses_thresholds() {
+        let defaults = SignalThresholds::default();
+        assert!(has_strong_signal(&[hit(0.9), hit(0.5)], &defaults));
+        assert!(!has_strong_signal(&[hit(0.9), hit(0.8)], &defaults));
+        assert!(has_strong_signal(&[hit(4.0), hit(2.0)], &defaults));
+        assert!(!has_strong_signal(&[hit(4.0), hit(3.5)], &defaults));
+        let strict = SignalThresholds {
+            high_min_ratio: 3.0,
+            ..SignalThresholds::default()
+        };
+        assert!(!has_strong_signal(&[hit(4.0), hit(2.0)], &strict));
+        let parsed: SignalThresholds = serde_json::from_str(r#"{"low_min_gap":0.05}"#).unwrap();
+        assert_eq!(parsed.low_min_gap, 0.05);
+        assert_eq!(parsed.high_min_score, 2.0);
+    }
+
+    fn result(score: f32) -> QueryResult {
+        QueryResult {
+            rank: 0,
+            frame_id: 0,
+            uri: String::new(),
+            title: None,
+            snippet: String::new(),
+            raw_snippet: None,
+            recency_factor: None,
+            score,
+            confidence: 0.0,
+            rrf_rank: 0,
+            rrf_score: 0.0,
+            rerank_score: None,
+            feedback_score: None,
+            sources: Vec::new(),
+            why: None,
+        }
+    }
+
+    #[test]
+    fn confidence_is_scale_free_and_flags_flat_sets() {
+        let mut rrf = vec![result(0.03), result(0.015), result(0.01)];
+        let mut blended = vec![result(0.9), result(0.45), result(0.3)];
+        calibrate_confidence(&mut rrf);
+        calibrate_confidence(&mut blended);
+        assert!((rrf[0].confidence - blended[0].confidence).abs() < 1e-4);
+        assert!(rrf[0].confidence > 0.9);
+        let total: f32 = rrf.iter().map(|r| r.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-4);
+
+        let mut flat: Vec<QueryResult> = (0..5).map(|_| result(0.5)).collect();
+        let mut warnings = Vec::new();
+        apply_confidence(&mut flat, &mut warnings);
+        assert!((flat[0].confidence - 0.2).abs() < 1e-4);
+        assert!(warnings[0].starts_with("low-confidence results"));
+    }
+
+    #[test]
+    fn single_result_confidence_reflects_the_pool() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("query_confidence_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for i in 0..5 {
+            let opts = crate::memory_db::PutOptions {
+                uri: Some(format!("aether://notes/{i}.md")),
+                ..Default::default()
+            };
+            db.put_bytes_with_options(format!("rollout notes, draft {i}").as_bytes(), opts).unwrap();
+        }
+
+        let top = |limit: usize| {
+            let args = crate::SearchOptions {
+                query: "rollout notes".to_string(),
+                limit,
+                no_vector: true,
+                ..Default::default()
+            }
+            .to_query_args();
+            let response = execute_query(&db, args).unwrap();
+            assert_eq!(response.results.len(), limit);
+            response.results[0].confidence
+        };
+        let single = top(1);
+        assert!(single < 1.0);
+        assert!((single - top(5)).abs() < 1e-6);
+        std::fs::remove_file(&path).ok();
+    }
 }
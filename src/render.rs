@@ -0,0 +1,200 @@
+//! Human-readable result tables for `search` and `query`, plus the row
+//! serializers behind `--output ndjson|csv`.
+
+use std::io::IsTerminal;
+
+use serde::Serialize;
+
+use crate::OutputFormat;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+
+/// One ranked hit as shown in the terminal.
+pub(crate) struct ResultRow<'a> {
+    pub(crate) rank: usize,
+    pub(crate) score: Option<f32>,
+    pub(crate) uri: &'a str,
+    pub(crate) title: &'a str,
+    pub(crate) snippet: &'a str,
+    /// `query --explain` reason, printed under the snippet.
+    pub(crate) why: Option<&'a str>,
+}
+
+/// Color only when stdout is a terminal, `NO_COLOR` is unset/empty and
+/// `--no-color` was not passed.
+pub(crate) fn use_color(no_color: bool) -> bool {
+    if no_color {
+        return false;
+    }
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    std::io::stdout().is_terminal()
+}
+
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{code}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Rank, score and URI aligned into columns, followed by the title; the
+/// snippet (and `why`, when present) go on indented (dimmed) lines underneath.
+pub(crate) fn render_result_table(rows: &[ResultRow], color: bool) -> String {
+    let rank_width = rows
+        .iter()
+        .map(|r| r.rank.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max(2);
+    let uri_width = rows.iter().map(|r| r.uri.chars().count()).max().unwrap_or(0);
+    let has_scores = rows.iter().any(|r| r.score.is_some());
+
+    let mut out = String::new();
+    for row in rows {
+        let rank = format!("{:>rank_width$}.", row.rank);
+        out.push_str(&paint(&rank, BOLD, color));
+        if has_scores {
+            let score = row
+                .score
+                .map(|s| format!("{s:>7.3}"))
+                .unwrap_or_else(|| format!("{:>7}", "-"));
+            out.push_str(&paint(&score, GREEN, color));
+        }
+        out.push_str("  ");
+        let uri = format!("{:<uri_width$}", row.uri);
+        out.push_str(&paint(&uri, CYAN, color));
+        if !row.title.is_empty() {
+            out.push_str("  ");
+            out.push_str(row.title);
+        }
+        let line_end = out.trim_end_matches(' ').len();
+        out.truncate(line_end);
+        out.push('\n');
+
+        let snippet = row.snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !snippet.is_empty() {
+            out.push_str(&" ".repeat(rank_width + 2));
+            out.push_str(&paint(&snippet, DIM, color));
+            out.push('\n');
+        }
+        if let Some(why) = row.why {
+            out.push_str(&" ".repeat(rank_width + 2));
+            out.push_str(&paint(&format!("why: {why}"), DIM, color));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn csv_field(value: Option<&serde_json::Value>) -> String {
+    let text = match value {
+        None | Some(serde_json::Value::Null) => return String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Serialize rows as NDJSON (one object per line) or CSV (header from the
+/// union of field names, nested values as JSON text). Other formats are the
+/// caller's job and yield an empty string.
+pub(crate) fn format_rows<T: Serialize>(
+    rows: &[T],
+    format: OutputFormat,
+) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    match format {
+        OutputFormat::Ndjson => {
+            for row in rows {
+                out.push_str(&serde_json::to_string(row)?);
+                out.push('\n');
+            }
+        }
+        OutputFormat::Csv => {
+            let values = rows
+                .iter()
+                .map(|row| {
+                    serde_json::to_value(row).map(|v| match v {
+                        serde_json::Value::Object(map) => map,
+                        other => {
+                            let mut map = serde_json::Map::new();
+                            map.insert("value".to_string(), other);
+                            map
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut columns: Vec<&String> = Vec::new();
+            for map in &values {
+                for key in map.keys() {
+                    if !columns.contains(&key) {
+                        columns.push(key);
+                    }
+                }
+            }
+            let header: Vec<String> = columns
+                .iter()
+                .map(|c| csv_field(Some(&serde_json::Value::String(c.to_string()))))
+                .collect();
+            out.push_str(&header.join(","));
+            out.push('\n');
+            for map in &values {
+                let fields: Vec<String> = columns.iter().map(|c| csv_field(map.get(*c))).collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+        }
+        OutputFormat::Text | OutputFormat::Json => {}
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_columns_and_only_colors_on_request() {
+        let rows = [
+            ResultRow { rank: 1, score: Some(1.5), uri: "av://a", title: "Alpha", snippet: "first\nline", why: None },
+            ResultRow { rank: 10, score: None, uri: "av://longer", title: "", snippet: "", why: Some("lex") },
+        ];
+        let plain = render_result_table(&rows, false);
+        assert_eq!(
+            plain,
+            " 1.  1.500  av://a       Alpha\n    first line\n10.      -  av://longer\n    why: lex\n"
+        );
+        assert!(!plain.contains('\x1b'));
+        let colored = render_result_table(&rows, true);
+        assert!(colored.contains(&format!("{CYAN}av://a     {RESET}")));
+        assert!(colored.contains(&format!("{DIM}first line{RESET}")));
+        assert!(!use_color(true));
+    }
+
+    #[test]
+    fn rows_serialize_to_ndjson_and_csv() {
+        let rows = vec![
+            serde_json::json!({"uri": "av://a", "score": 1.5}),
+            serde_json::json!({"uri": "av://b,c", "title": "say \"hi\"", "range": [0, 4]}),
+        ];
+        let ndjson = format_rows(&rows, OutputFormat::Ndjson).unwrap();
+        assert_eq!(ndjson.lines().count(), 2);
+        assert_eq!(ndjson.lines().next().unwrap(), r#"{"score":1.5,"uri":"av://a"}"#);
+        let csv = format_rows(&rows, OutputFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "score,uri,range,title\n1.5,av://a,,\n,\"av://b,c\",\"[0,4]\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+}
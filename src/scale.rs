@@ -0,0 +1,450 @@
+use crate::{env_optional, ToolExecution};
+
+/// Backend for the `scale` tool. `status` is local-only by default; providers
+/// override `sizes`/`resize` to talk to their cloud API.
+pub(crate) trait ScaleProvider {
+    fn name(&self) -> &'static str;
+
+    fn status(&self) -> Result<ToolExecution, String> {
+        local_status()
+    }
+
+    fn sizes(&self) -> Result<ToolExecution, String>;
+
+    fn resize(&self, size: &str) -> Result<ToolExecution, String>;
+}
+
+/// Select the provider from `AETHERVAULT_SCALE_PROVIDER` (default: digitalocean).
+pub(crate) fn scale_provider_from_env() -> Result<Box<dyn ScaleProvider>, String> {
+    let name = env_optional("AETHERVAULT_SCALE_PROVIDER").unwrap_or_else(|| "digitalocean".to_string());
+    scale_provider_by_name(&name)
+}
+
+pub(crate) fn scale_provider_by_name(name: &str) -> Result<Box<dyn ScaleProvider>, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "digitalocean" | "do" => Ok(Box::new(DigitalOceanProvider)),
+        "local" | "none" => Ok(Box::new(LocalProvider)),
+        other => Err(format!(
+            "unknown scale provider '{other}' (AETHERVAULT_SCALE_PROVIDER: digitalocean, local)"
+        )),
+    }
+}
+
+/// Reports host stats only; any cloud operation is refused.
+pub(crate) struct LocalProvider;
+
+impl ScaleProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn sizes(&self) -> Result<ToolExecution, String> {
+        Err(format!(
+            "scale sizes unsupported by the {} provider (set AETHERVAULT_SCALE_PROVIDER to a cloud provider)",
+            self.name()
+        ))
+    }
+
+    fn resize(&self, _size: &str) -> Result<ToolExecution, String> {
+        Err(format!(
+            "scale resize unsupported by the {} provider (set AETHERVAULT_SCALE_PROVIDER to a cloud provider)",
+            self.name()
+        ))
+    }
+}
+
+/// DigitalOcean droplets via the v2 API (DO_TOKEN, optional DO_DROPLET_ID).
+pub(crate) struct DigitalOceanProvider;
+
+impl ScaleProvider for DigitalOceanProvider {
+    fn name(&self) -> &'static str {
+        "digitalocean"
+    }
+
+    fn sizes(&self) -> Result<ToolExecution, String> {
+        let do_token = env_optional("DO_TOKEN")
+            .ok_or_else(|| "DO_TOKEN not set — cannot query DigitalOcean API".to_string())?;
+        let out = std::process::Command::new("curl")
+            .args([
+                "-s",
+                "-X", "GET",
+                "https://api.digitalocean.com/v2/sizes",
+                "-H", &format!("Authorization: Bearer {}", do_token),
+            ])
+            .output()
+            .map_err(|e| format!("curl failed: {e}"))?;
+        let body: serde_json::Value =
+            serde_json::from_slice(&out.stdout)
+                .map_err(|e| format!("invalid JSON from DO API: {e}"))?;
+        let sizes = body
+            .get("sizes")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        // Filter to ≤8 vCPU / ≤32GB to prevent cost overruns
+        let filtered: Vec<serde_json::Value> = sizes
+            .into_iter()
+            .filter(|s| {
+                let vcpus = s.get("vcpus").and_then(|v| v.as_u64()).unwrap_or(99);
+                let mem = s.get("memory").and_then(|v| v.as_u64()).unwrap_or(999999);
+                let available = s.get("available").and_then(|v| v.as_bool()).unwrap_or(false);
+                vcpus <= 8 && mem <= 32768 && available
+            })
+            .map(|s| {
+                serde_json::json!({
+                    "slug": s.get("slug").and_then(|v| v.as_str()).unwrap_or(""),
+                    "vcpus": s.get("vcpus").and_then(|v| v.as_u64()).unwrap_or(0),
+                    "memory_mb": s.get("memory").and_then(|v| v.as_u64()).unwrap_or(0),
+                    "disk_gb": s.get("disk").and_then(|v| v.as_u64()).unwrap_or(0),
+                    "price_monthly": s.get("price_monthly").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                })
+            })
+            .collect();
+        let details = serde_json::json!({ "sizes": filtered });
+        Ok(ToolExecution {
+            output: format!("{} available sizes (≤8 vCPU, ≤32GB).", filtered.len()),
+            details,
+            is_error: false,
+        })
+    }
+
+    fn resize(&self, target_size: &str) -> Result<ToolExecution, String> {
+        let do_token = env_optional("DO_TOKEN")
+            .ok_or_else(|| "DO_TOKEN not set — cannot call DigitalOcean API".to_string())?;
+        // Get droplet ID: env var or auto-detect via DO metadata
+        let droplet_id = env_optional("DO_DROPLET_ID").or_else(|| {
+            std::process::Command::new("curl")
+                .args(["-s", "http://169.254.169.254/metadata/v1/id"])
+                .output()
+                .ok()
+                .and_then(|o| {
+                    let id = String::from_utf8_lossy(&o.stdout).trim().to_string();
+                    if id.chars().all(|c| c.is_ascii_digit()) && !id.is_empty() {
+                        Some(id)
+                    } else {
+                        None
+                    }
+                })
+        }).ok_or_else(|| "DO_DROPLET_ID not set and metadata API unreachable".to_string())?;
+        let url = format!(
+            "https://api.digitalocean.com/v2/droplets/{}/actions",
+            droplet_id
+        );
+        let payload = serde_json::json!({
+            "type": "resize",
+            "disk": false,
+            "size": target_size,
+        });
+        let out = std::process::Command::new("curl")
+            .args([
+                "-s",
+                "-X", "POST",
+                &url,
+                "-H", &format!("Authorization: Bearer {}", do_token),
+                "-H", "Content-Type: application/json",
+                "-d", &payload.to_string(),
+            ])
+            .output()
+            .map_err(|e| format!("curl failed: {e}"))?;
+        let resp: serde_json::Value =
+            serde_json::from_slice(&out.stdout)
+                .map_err(|e| format!("invalid JSON from DO API: {e}"))?;
+        let action_status = resp
+            .get("action")
+            .and_then(|a| a.get("status"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        let action_id = resp
+            .get("action")
+            .and_then(|a| a.get("id"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        if action_status == "errored" || resp.get("id").is_some_and(|v| v.as_str() == Some("not_found")) {
+            let msg = resp.get("message").and_then(|v| v.as_str()).unwrap_or("resize failed");
+            return Err(format!("DO resize error: {msg}"));
+        }
+        Ok(ToolExecution {
+            output: format!(
+                "Resize to {} initiated (action {}, status: {}). Note: CPU resizes require a power cycle to take effect.",
+                target_size, action_id, action_status
+            ),
+            details: resp,
+            is_error: false,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct HostStats {
+    pub(crate) load_1m: f64,
+    pub(crate) load_5m: f64,
+    pub(crate) mem_total_mb: u64,
+    pub(crate) mem_avail_mb: u64,
+    pub(crate) swap_total_mb: u64,
+    pub(crate) swap_used_mb: u64,
+    pub(crate) iowait_pct: f64,
+}
+
+/// Host CPU/RAM/swap/disk stats. Linux reads /proc; macOS shells out to
+/// `sysctl` and `vm_stat`. The `details` shape is identical on both.
+pub(crate) fn local_status() -> Result<ToolExecution, String> {
+    let cpu_count = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let stats = if cfg!(target_os = "macos") {
+        macos_host_stats()
+    } else {
+        linux_host_stats()
+    };
+    let mem_used_pct = if stats.mem_total_mb > 0 {
+        (stats.mem_total_mb.saturating_sub(stats.mem_avail_mb) as f64 / stats.mem_total_mb as f64
+            * 100.0)
+            .round()
+    } else {
+        0.0
+    };
+    let swap_used_pct = if stats.swap_total_mb > 0 {
+        (stats.swap_used_mb as f64 / stats.swap_total_mb as f64 * 100.0).round()
+    } else {
+        0.0
+    };
+    // Disk via POSIX df output, which has the same columns on Linux and macOS.
+    let (disk_total_gb, disk_used_gb, disk_used_pct) = std::process::Command::new("df")
+        .args(["-kP", "/"])
+        .output()
+        .ok()
+        .and_then(|out| parse_df_posix(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or((0.0, 0.0, 0.0));
+    let details = serde_json::json!({
+        "cpu_count": cpu_count,
+        "load_1m": stats.load_1m,
+        "load_5m": stats.load_5m,
+        "iowait_pct": stats.iowait_pct,
+        "mem_total_mb": stats.mem_total_mb,
+        "mem_avail_mb": stats.mem_avail_mb,
+        "mem_used_pct": mem_used_pct,
+        "swap_total_mb": stats.swap_total_mb,
+        "swap_used_mb": stats.swap_used_mb,
+        "swap_used_pct": swap_used_pct,
+        "disk_total_gb": disk_total_gb,
+        "disk_used_gb": disk_used_gb,
+        "disk_used_pct": disk_used_pct,
+    });
+    Ok(ToolExecution {
+        output: format!(
+            "CPU: {} cores, load {:.1}/{:.1}, iowait {:.1}% | RAM: {}MB/{} MB ({:.0}% used) | Swap: {}MB/{} MB | Disk: {:.0}G/{:.0}G ({:.0}% used)",
+            cpu_count, stats.load_1m, stats.load_5m, stats.iowait_pct,
+            stats.mem_total_mb.saturating_sub(stats.mem_avail_mb), stats.mem_total_mb, mem_used_pct,
+            stats.swap_used_mb, stats.swap_total_mb,
+            disk_used_gb, disk_total_gb, disk_used_pct,
+        ),
+        details,
+        is_error: false,
+    })
+}
+
+fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let out = std::process::Command::new(program).args(args).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+fn linux_host_stats() -> HostStats {
+    let mut stats = HostStats::default();
+    if let Some((l1, l5)) = std::fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|s| parse_loadavg(&s))
+    {
+        stats.load_1m = l1;
+        stats.load_5m = l5;
+    }
+    if let Ok(text) = std::fs::read_to_string("/proc/meminfo") {
+        let (total, avail, swap_total, swap_free) = parse_proc_meminfo(&text);
+        stats.mem_total_mb = total;
+        stats.mem_avail_mb = avail;
+        stats.swap_total_mb = swap_total;
+        stats.swap_used_mb = swap_total.saturating_sub(swap_free);
+    }
+    // /proc/stat counters are cumulative since boot; sample twice for a current ratio.
+    let first = std::fs::read_to_string("/proc/stat").ok().and_then(|s| parse_proc_stat_cpu(&s));
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let second = std::fs::read_to_string("/proc/stat").ok().and_then(|s| parse_proc_stat_cpu(&s));
+    if let (Some((io_a, total_a)), Some((io_b, total_b))) = (first, second) {
+        let total = total_b.saturating_sub(total_a);
+        if total > 0 {
+            stats.iowait_pct =
+                (io_b.saturating_sub(io_a) as f64 / total as f64 * 1000.0).round() / 10.0;
+        }
+    }
+    stats
+}
+
+fn macos_host_stats() -> HostStats {
+    let mut stats = HostStats::default();
+    if let Some((l1, l5)) = command_stdout("sysctl", &["-n", "vm.loadavg"]).and_then(|s| {
+        parse_loadavg(s.trim().trim_start_matches('{').trim_end_matches('}'))
+    }) {
+        stats.load_1m = l1;
+        stats.load_5m = l5;
+    }
+    stats.mem_total_mb = command_stdout("sysctl", &["-n", "hw.memsize"])
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(0)
+        / (1024 * 1024);
+    stats.mem_avail_mb = command_stdout("vm_stat", &[])
+        .map(|s| parse_vm_stat_available(&s))
+        .unwrap_or(0)
+        / (1024 * 1024);
+    if let Some((total, used)) =
+        command_stdout("sysctl", &["-n", "vm.swapusage"]).and_then(|s| parse_swapusage(&s))
+    {
+        stats.swap_total_mb = total;
+        stats.swap_used_mb = used;
+    }
+    // macOS exposes no iowait counter; it stays 0.
+    stats
+}
+
+fn parse_loadavg(text: &str) -> Option<(f64, f64)> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    if parts.len() >= 2 {
+        Some((
+            parts[0].parse::<f64>().unwrap_or(0.0),
+            parts[1].parse::<f64>().unwrap_or(0.0),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Returns (mem_total_mb, mem_avail_mb, swap_total_mb, swap_free_mb).
+fn parse_proc_meminfo(text: &str) -> (u64, u64, u64, u64) {
+    let field_mb = |line: &str| {
+        line.split_whitespace()
+            .nth(1)
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0)
+            / 1024
+    };
+    let (mut total, mut avail, mut swap_total, mut swap_free) = (0, 0, 0, 0);
+    for line in text.lines() {
+        if line.starts_with("MemTotal:") {
+            total = field_mb(line);
+        } else if line.starts_with("MemAvailable:") {
+            avail = field_mb(line);
+        } else if line.starts_with("SwapTotal:") {
+            swap_total = field_mb(line);
+        } else if line.starts_with("SwapFree:") {
+            swap_free = field_mb(line);
+        }
+    }
+    (total, avail, swap_total, swap_free)
+}
+
+/// Returns (iowait_ticks, total_ticks) from the aggregate `cpu` line.
+fn parse_proc_stat_cpu(text: &str) -> Option<(u64, u64)> {
+    let line = text.lines().find(|l| l.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse::<u64>().ok())
+        .collect();
+    let iowait = *values.get(4)?;
+    Some((iowait, values.iter().sum()))
+}
+
+/// Free + inactive + speculative pages, in bytes.
+fn parse_vm_stat_available(text: &str) -> u64 {
+    let page_size = text
+        .lines()
+        .next()
+        .and_then(|l| l.split("page size of ").nth(1))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(4096);
+    let mut pages = 0u64;
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if matches!(key.trim(), "Pages free" | "Pages inactive" | "Pages speculative") {
+            pages += value.trim().trim_end_matches('.').parse::<u64>().unwrap_or(0);
+        }
+    }
+    pages * page_size
+}
+
+/// Parses `total = 2048.00M  used = 1024.50M  free = ...` into (total_mb, used_mb).
+fn parse_swapusage(text: &str) -> Option<(u64, u64)> {
+    let value_mb = |key: &str| -> Option<u64> {
+        let rest = text.split(&format!("{key} = ")).nth(1)?;
+        let raw = rest.split_whitespace().next()?;
+        let (num, mult) = match raw.chars().last()? {
+            'G' => (&raw[..raw.len() - 1], 1024.0),
+            'M' => (&raw[..raw.len() - 1], 1.0),
+            'K' => (&raw[..raw.len() - 1], 1.0 / 1024.0),
+            _ => (raw, 1.0),
+        };
+        Some((num.parse::<f64>().ok()? * mult).round() as u64)
+    };
+    Some((value_mb("total")?, value_mb("used")?))
+}
+
+/// Parses `df -kP` output into (total_gb, used_gb, used_pct).
+fn parse_df_posix(text: &str) -> Option<(f64, f64, f64)> {
+    let line = text.lines().nth(1)?;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 5 {
+        return None;
+    }
+    let kb_to_gb = |v: &str| v.parse::<f64>().unwrap_or(0.0) / (1024.0 * 1024.0);
+    let pct = parts[4].trim_end_matches('%').parse::<f64>().unwrap_or(0.0);
+    Some((kb_to_gb(parts[1]), kb_to_gb(parts[2]), pct))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_selection_by_name() {
+        assert_eq!(scale_provider_by_name("digitalocean").unwrap().name(), "digitalocean");
+        assert_eq!(scale_provider_by_name("DO").unwrap().name(), "digitalocean");
+        assert_eq!(scale_provider_by_name("local").unwrap().name(), "local");
+        assert!(scale_provider_by_name("aws").is_err());
+    }
+
+    #[test]
+    fn local_provider_refuses_cloud_actions() {
+        let provider = LocalProvider;
+        let err = provider.resize("s-2vcpu-4gb").err().unwrap();
+        assert!(err.contains("unsupported"));
+        assert!(provider.sizes().is_err());
+        assert!(provider.status().is_ok());
+    }
+
+    #[test]
+    fn parses_linux_meminfo_and_stat() {
+        let meminfo = "MemTotal:        8192000 kB\nMemAvailable:    4096000 kB\nSwapTotal:       2048000 kB\nSwapFree:        1024000 kB\n";
+        assert_eq!(parse_proc_meminfo(meminfo), (8000, 4000, 2000, 1000));
+        let stat = "cpu  100 0 50 800 40 0 10 0 0 0\ncpu0 50 0 25 400 20 0 5 0 0 0\n";
+        assert_eq!(parse_proc_stat_cpu(stat), Some((40, 1000)));
+    }
+
+    #[test]
+    fn parses_macos_outputs() {
+        let vm_stat = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\nPages free:                               10.\nPages active:                             99.\nPages inactive:                           20.\nPages speculative:                         2.\n";
+        assert_eq!(parse_vm_stat_available(vm_stat), 32 * 16384);
+        let swap = "total = 2048.00M  used = 1024.50M  free = 1023.50M  (encrypted)";
+        assert_eq!(parse_swapusage(swap), Some((2048, 1025)));
+        assert_eq!(parse_loadavg("1.50 2.25 3.00"), Some((1.5, 2.25)));
+    }
+
+    #[test]
+    fn parses_posix_df() {
+        let df = "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 104857600 52428800 52428800 50% /\n";
+        assert_eq!(parse_df_posix(df), Some((100.0, 50.0, 50.0)));
+    }
+}
@@ -0,0 +1,320 @@
+//! `serve`: the query pipeline behind a small JSON-over-HTTP API for web UIs.
+//! Requests are handled one at a time on a single capsule connection.
+
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use tiny_http::{Header, Method, Response, Server};
+use url::form_urlencoded;
+
+use crate::api::SearchOptions;
+use crate::memory_db::MemoryDb;
+use crate::{
+    blake3_hash, build_context_pack, clear_query_cache, env_optional, execute_query, health_reply,
+    open_db_read_only, open_or_create_db, resolve_frame_ref, AethervaultError, ContextFormat, GetResponse, StatusResponse,
+};
+
+/// When set, every request must carry `Authorization: Bearer <token>`.
+const TOKEN_ENV: &str = "AETHERVAULT_SERVE_TOKEN";
+
+/// `POST /context` body: query options plus the packing limits.
+#[derive(Deserialize)]
+struct ContextRequest {
+    #[serde(flatten)]
+    options: SearchOptions,
+    #[serde(default = "default_max_bytes")]
+    max_bytes: usize,
+    #[serde(default)]
+    full: bool,
+    #[serde(default)]
+    window: usize,
+    #[serde(default)]
+    format: ContextFormat,
+}
+
+fn default_max_bytes() -> usize {
+    12_000
+}
+
+pub(crate) struct ApiServer {
+    db: MemoryDb,
+    mv2: PathBuf,
+    token: Option<String>,
+    /// Origins allowed by CORS (`--cors-origin`); empty disables CORS, `*` allows any.
+    cors_origins: Vec<String>,
+}
+
+type ApiReply = (u16, serde_json::Value);
+
+fn error(status: u16, message: impl std::fmt::Display) -> ApiReply {
+    (status, serde_json::json!({ "error": message.to_string() }))
+}
+
+/// Bad requests are the caller's fault; everything else is ours.
+fn pipeline_error(err: AethervaultError) -> ApiReply {
+    let status = match err {
+        AethervaultError::Validation(_) => 400,
+        AethervaultError::NotFound(_) => 404,
+        _ => 500,
+    };
+    error(status, err)
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, ApiReply> {
+    let body = if body.trim().is_empty() { "{}" } else { body };
+    serde_json::from_str(body).map_err(|e| error(400, format!("invalid JSON body: {e}")))
+}
+
+fn require_query(options: &SearchOptions) -> Result<(), ApiReply> {
+    if options.query.trim().is_empty() {
+        return Err(error(400, "missing \"query\""));
+    }
+    Ok(())
+}
+
+impl ApiServer {
+    pub(crate) fn new(db: MemoryDb, mv2: PathBuf, token: Option<String>, cors_origins: Vec<String>) -> Self {
+        Self {
+            db,
+            mv2,
+            token,
+            cors_origins,
+        }
+    }
+
+    /// CORS response headers for a request from `origin`. Nothing unless the
+    /// origin is allowed; preflights also get the allowed methods and headers
+    /// (including `Authorization`, so bearer tokens can be sent).
+    pub(crate) fn cors_headers(&self, origin: Option<&str>, preflight: bool) -> Vec<(&'static str, String)> {
+        let Some(origin) = origin else {
+            return Vec::new();
+        };
+        let allow = if self.cors_origins.iter().any(|o| o == "*") {
+            "*".to_string()
+        } else if self.cors_origins.iter().any(|o| o.trim_end_matches('/') == origin) {
+            origin.to_string()
+        } else {
+            return Vec::new();
+        };
+        let mut headers = vec![("Access-Control-Allow-Origin", allow), ("Vary", "Origin".to_string())];
+        if preflight {
+            headers.push(("Access-Control-Allow-Methods", "GET, POST, OPTIONS".to_string()));
+            headers.push(("Access-Control-Allow-Headers", "Authorization, Content-Type".to_string()));
+            headers.push(("Access-Control-Max-Age", "600".to_string()));
+        }
+        headers
+    }
+
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let expected = format!("Bearer {token}");
+        // Compare digests so the check does not short-circuit on a prefix.
+        authorization.is_some_and(|got| blake3_hash(got.as_bytes()) == blake3_hash(expected.as_bytes()))
+    }
+
+    /// Route one request and return the status code and JSON body.
+    pub(crate) fn handle(
+        &self,
+        method: &Method,
+        url: &str,
+        authorization: Option<&str>,
+        body: &str,
+    ) -> ApiReply {
+        // Preflights carry no credentials; answer them and health checks before auth.
+        if *method == Method::Options {
+            return (204, serde_json::Value::Null);
+        }
+        if let Some(reply) = health_reply(method, url, &self.mv2) {
+            return reply;
+        }
+        if !self.authorized(authorization) {
+            return error(401, "missing or invalid bearer token");
+        }
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let result = match (method, path) {
+            (Method::Post, "/query") => self.query(body),
+            (Method::Post, "/context") => self.context(body),
+            (Method::Get, "/get") => self.get(query),
+            (Method::Get, "/status") => self.status(),
+            (Method::Post, "/cache/clear") => Ok((200, serde_json::json!({ "cleared": clear_query_cache() }))),
+            (_, "/query" | "/context" | "/get" | "/status" | "/cache/clear") => Err(error(405, "method not allowed")),
+            _ => Err(error(404, format!("no route for {path}"))),
+        };
+        result.unwrap_or_else(|reply| reply)
+    }
+
+    fn query(&self, body: &str) -> Result<ApiReply, ApiReply> {
+        let options: SearchOptions = parse_body(body)?;
+        require_query(&options)?;
+        let response = execute_query(&self.db, options.to_query_args()).map_err(pipeline_error)?;
+        Ok((200, serde_json::to_value(response).map_err(|e| error(500, e))?))
+    }
+
+    fn context(&self, body: &str) -> Result<ApiReply, ApiReply> {
+        let request: ContextRequest = parse_body(body)?;
+        require_query(&request.options)?;
+        let pack = build_context_pack(
+            &self.db,
+            request.options.to_query_args(),
+            request.max_bytes,
+            request.full,
+            request.window,
+            request.format,
+        )
+        .map_err(pipeline_error)?;
+        Ok((200, serde_json::to_value(pack).map_err(|e| error(500, e))?))
+    }
+
+    fn get(&self, query: &str) -> Result<ApiReply, ApiReply> {
+        let id = form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "id")
+            .map(|(_, value)| value.into_owned())
+            .ok_or_else(|| error(400, "missing ?id= (frame id #N or URI)"))?;
+        let frame = resolve_frame_ref(&self.db, &id).map_err(|e| error(404, e))?;
+        let text = self.db.frame_text_by_id(frame.id).map_err(|e| error(500, e))?;
+        let response = GetResponse {
+            frame_id: frame.id,
+            uri: frame.uri,
+            title: frame.title,
+            text,
+        };
+        Ok((200, serde_json::to_value(response).map_err(|e| error(500, e))?))
+    }
+
+    fn status(&self) -> Result<ApiReply, ApiReply> {
+        let response = StatusResponse {
+            mv2: self.mv2.display().to_string(),
+            frame_count: self.db.frame_count(),
+            next_frame_id: self.db.frame_count() as u64,
+        };
+        Ok((200, serde_json::to_value(response).map_err(|e| error(500, e))?))
+    }
+}
+
+fn header_value(request: &tiny_http::Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+pub(crate) fn run_serve(
+    mv2: PathBuf,
+    bind: String,
+    port: u16,
+    read_only: bool,
+    cors_origins: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = if read_only {
+        open_db_read_only(&mv2)?
+    } else {
+        open_or_create_db(&mv2)?
+    };
+    let token = env_optional(TOKEN_ENV);
+    let loopback = matches!(bind.as_str(), "127.0.0.1" | "localhost" | "::1");
+    if token.is_none() && !loopback {
+        log_warn!("serve: {TOKEN_ENV} is not set; anyone who can reach {bind}:{port} can read the capsule");
+    }
+    if cors_origins.iter().any(|o| o == "*") {
+        log_warn!("serve: --cors-origin '*' lets any web page in a visitor's browser call this API");
+    }
+    let api = ApiServer::new(db, mv2, token, cors_origins);
+
+    let addr = format!("{bind}:{port}");
+    let server = Server::http(&addr)
+        .map_err(|e| io::Error::other(format!("server: {e}")))?;
+    log_info!("Query API listening on http://{addr}{}", if read_only { " (read-only)" } else { "" });
+
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .map_err(|_| io::Error::other("invalid header"))?;
+    for mut request in server.incoming_requests() {
+        let authorization = header_value(&request, "Authorization");
+        let origin = header_value(&request, "Origin");
+        let mut body = String::new();
+        let (status, payload) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => api.handle(request.method(), request.url(), authorization.as_deref(), &body),
+            Err(e) => error(400, format!("read body: {e}")),
+        };
+        log_debug!("serve: {} {} -> {status}", request.method(), request.url());
+        let preflight = *request.method() == Method::Options;
+        let mut response = if preflight {
+            Response::from_string(String::new()).with_status_code(status)
+        } else {
+            Response::from_string(payload.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone())
+        };
+        for (name, value) in api.cors_headers(origin.as_deref(), preflight) {
+            if let Ok(header) = Header::from_bytes(name, value) {
+                response.add_header(header);
+            }
+        }
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_db::PutOptions;
+
+    fn server(token: Option<&str>) -> ApiServer {
+        let path = std::env::temp_dir().join(format!("aethervault_serve_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let options = PutOptions {
+            uri: Some("aethervault://notes/tea.md".into()),
+            title: Some("Tea".into()),
+            ..Default::default()
+        };
+        db.put_bytes_with_options(b"Steep green tea for two minutes.", options).unwrap();
+        ApiServer::new(db, path, token.map(str::to_string), vec!["https://ui.example".into()])
+    }
+
+    #[test]
+    fn routes_queries_lookups_and_auth() {
+        let api = server(None);
+        let (status, body) = api.handle(&Method::Post, "/query", None, r#"{"query":"green tea","limit":3}"#);
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["results"][0]["uri"], "aethervault://notes/tea.md");
+
+        let (status, body) = api.handle(&Method::Post, "/context", None, r#"{"query":"tea","max_bytes":2000}"#);
+        assert_eq!(status, 200, "{body}");
+        assert!(body["context"].as_str().unwrap().contains("tea.md"));
+
+        let (status, body) = api.handle(&Method::Get, "/get?id=aethervault%3A%2F%2Fnotes%2Ftea.md", None, "");
+        assert_eq!((status, body["title"].as_str()), (200, Some("Tea")));
+        assert_eq!(api.handle(&Method::Get, "/get?id=%2399", None, "").0, 404);
+        assert_eq!(api.handle(&Method::Get, "/status", None, "").1["frame_count"], 1);
+        assert_eq!(api.handle(&Method::Post, "/query", None, "{}").0, 400);
+        assert_eq!(api.handle(&Method::Get, "/query", None, "").0, 405);
+        assert_eq!(api.handle(&Method::Get, "/nope", None, "").0, 404);
+
+        let locked = server(Some("s3cret"));
+        assert_eq!(locked.handle(&Method::Get, "/status", None, "").0, 401);
+        assert_eq!(locked.handle(&Method::Get, "/status", Some("Bearer wrong"), "").0, 401);
+        assert_eq!(locked.handle(&Method::Get, "/status", Some("Bearer s3cret"), "").0, 200);
+        assert_eq!(locked.handle(&Method::Options, "/query", None, "").0, 204);
+        assert_eq!(locked.handle(&Method::Get, "/healthz", None, "").1["capsule"], true);
+    }
+
+    #[test]
+    fn cors_headers_only_for_allowed_origins() {
+        let api = server(None);
+        assert!(api.cors_headers(None, true).is_empty());
+        assert!(api.cors_headers(Some("https://evil.example"), true).is_empty());
+        let simple = api.cors_headers(Some("https://ui.example"), false);
+        assert_eq!(simple[0], ("Access-Control-Allow-Origin", "https://ui.example".to_string()));
+        assert_eq!(simple.len(), 2);
+        let preflight = api.cors_headers(Some("https://ui.example"), true);
+        assert!(preflight.iter().any(|(k, v)| *k == "Access-Control-Allow-Headers" && v.contains("Authorization")));
+
+        let open = ApiServer { cors_origins: vec!["*".into()], ..server(None) };
+        assert_eq!(open.cors_headers(Some("https://any.example"), false)[0].1, "*");
+    }
+}
@@ -1,5 +1,5 @@
 #[allow(unused_imports)]
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::io;
@@ -9,8 +9,8 @@ use std::time::Duration;
 
 #[allow(unused_imports)]
 use crate::memory_db::{FrameStatus, MemoryDb, PutOptions, SearchHit};
-use chrono::{Datelike, Timelike, Utc};
-use serde::Deserialize;
+use chrono::{Datelike, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json;
 use url::form_urlencoded;
 
@@ -18,8 +18,10 @@ use url::form_urlencoded;
 use crate::{
     open_or_create_db, save_config_entry, load_config_entry, blake3_hash, execute_tool,
     env_optional, env_u64, tool_autonomy_for, ToolAutonomyLevel, ApprovalEntry, TriggerEntry,
+    IdempotencyEntry, ToolExecution, http_with_retry, HttpBody, HttpRetry,
     AgentConfig, CronExpr, load_capsule_config, resolve_workspace,
-    build_bridge_agent_config, run_agent_for_bridge, telegram_send_message,
+    build_bridge_agent_config, run_agent_for_bridge, telegram_send_message, tool_registry,
+    ConflictStrategy, RollupPeriod, render_trigger_prompt, approval_summary, health_response,
 };
 use tiny_http::{Response, Server};
 use walkdir::WalkDir;
@@ -79,112 +81,403 @@ pub(crate) fn sync_workspace_memory(
 ) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
     let db = open_or_create_db(mv2)?;
     let mut ids = Vec::new();
-    let soul = workspace.join("SOUL.md");
-    let user = workspace.join("USER.md");
-    let memory = workspace.join("MEMORY.md");
-    if soul.exists() {
-        ids.push(sync_memory_file(
-            &db,
-            &soul,
-            memory_uri("soul"),
-            "memory soul",
-            "aethervault.memory",
-        )?);
-    }
-    if user.exists() {
-        ids.push(sync_memory_file(
-            &db,
-            &user,
-            memory_uri("user"),
-            "memory user",
-            "aethervault.memory",
-        )?);
-    }
-    if memory.exists() {
+    let mut state = load_memory_sync_state(workspace);
+    for file in workspace_memory_files(&db, workspace, include_daily) {
+        if !file.path.exists() {
+            continue;
+        }
         ids.push(sync_memory_file(
             &db,
-            &memory,
-            memory_uri("longterm"),
-            "memory longterm",
+            &file.path,
+            file.uri,
+            &file.title,
             "aethervault.memory",
         )?);
+        state.insert(file.key, blake3_hash(&fs::read(&file.path)?).to_hex().to_string());
     }
+    save_memory_sync_state(workspace, &state)?;
+    Ok(ids)
+}
+
+/// Per-workspace record of the content hash each memory file had at its last
+/// sync or export, keyed by workspace-relative path. It is the merge base
+/// `memory status` uses to tell local edits from capsule edits.
+const MEMORY_SYNC_STATE_FILE: &str = ".memory-sync.json";
+
+pub(crate) fn load_memory_sync_state(workspace: &Path) -> BTreeMap<String, String> {
+    fs::read(workspace.join(MEMORY_SYNC_STATE_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn save_memory_sync_state(workspace: &Path, state: &BTreeMap<String, String>) -> io::Result<()> {
+    fs::create_dir_all(workspace)?;
+    fs::write(workspace.join(MEMORY_SYNC_STATE_FILE), serde_json::to_vec_pretty(state)?)
+}
+
+/// A workspace memory file and the capsule URI it mirrors.
+pub(crate) struct MemoryFile {
+    /// Workspace-relative path, e.g. `MEMORY.md` or `memory/2026-10-14.md`.
+    pub(crate) key: String,
+    pub(crate) path: PathBuf,
+    pub(crate) uri: String,
+    pub(crate) title: String,
+}
+
+/// SOUL/USER/MEMORY plus, with `include_daily`, every daily note present
+/// either in `<workspace>/memory/` or in the capsule.
+pub(crate) fn workspace_memory_files(db: &MemoryDb, workspace: &Path, include_daily: bool) -> Vec<MemoryFile> {
+    let mut files: Vec<MemoryFile> = [("SOUL.md", "soul"), ("USER.md", "user"), ("MEMORY.md", "longterm")]
+        .into_iter()
+        .map(|(name, kind)| MemoryFile {
+            key: name.to_string(),
+            path: workspace.join(name),
+            uri: memory_uri(kind),
+            title: format!("memory {kind}"),
+        })
+        .collect();
     if include_daily {
-        let daily_dir = workspace.join("memory");
-        if daily_dir.exists() {
-            for entry in WalkDir::new(&daily_dir).max_depth(1) {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
-                if !entry.file_type().is_file() {
-                    continue;
+        let mut stems = std::collections::BTreeSet::new();
+        for entry in WalkDir::new(workspace.join("memory")).max_depth(1).into_iter().flatten() {
+            let path = entry.path();
+            if entry.file_type().is_file() && path.extension().is_some_and(|ext| ext == "md") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    stems.insert(stem.to_string());
                 }
-                let path = entry.path();
-                let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
-                    continue;
-                };
-                let uri = memory_daily_uri(stem);
-                let title = format!("memory daily {stem}");
-                ids.push(sync_memory_file(
-                    &db,
-                    path,
-                    uri,
-                    &title,
-                    "aethervault.memory",
-                )?);
             }
         }
+        for frame_id in db.collect_active_frame_ids(Some(MEMORY_DAILY_PREFIX)) {
+            if let Some(stem) = db
+                .frame_by_id(frame_id)
+                .ok()
+                .and_then(|frame| frame.uri)
+                .and_then(|uri| uri.strip_prefix(MEMORY_DAILY_PREFIX).map(|name| name.trim_end_matches(".md").to_string()))
+            {
+                stems.insert(stem);
+            }
+        }
+        for stem in stems {
+            files.push(MemoryFile {
+                key: format!("memory/{stem}.md"),
+                path: workspace.join("memory").join(format!("{stem}.md")),
+                uri: memory_daily_uri(&stem),
+                title: format!("memory daily {stem}"),
+            });
+        }
+    }
+    files
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct MemoryExport {
+    pub(crate) written: Vec<String>,
+    /// Files left alone because the local copy was modified after the capsule version.
+    pub(crate) skipped: Vec<String>,
+}
+
+impl MemoryExport {
+    /// Write `text` to `path` and stamp it with the frame's timestamp, so the
+    /// file carries the capsule date. With `if_newer`, a local file modified
+    /// at or after `timestamp` is kept. Returns whether the file was written.
+    fn write(&mut self, path: &Path, text: &str, timestamp: i64, if_newer: bool) -> io::Result<bool> {
+        let modified = std::time::UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64);
+        if if_newer
+            && let Ok(local) = fs::metadata(path).and_then(|meta| meta.modified())
+            && local >= modified
+        {
+            self.skipped.push(path.display().to_string());
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, text)?;
+        fs::File::options().write(true).open(path)?.set_modified(modified)?;
+        self.written.push(path.display().to_string());
+        Ok(true)
     }
-    Ok(ids)
 }
 
 pub(crate) fn export_capsule_memory(
     mv2: &Path,
     workspace: &Path,
     include_daily: bool,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if_newer: bool,
+) -> Result<MemoryExport, Box<dyn std::error::Error>> {
     let db = open_or_create_db(mv2)?;
-    let mut paths = Vec::new();
-    let items = vec![
-        (memory_uri("soul"), workspace.join("SOUL.md")),
-        (memory_uri("user"), workspace.join("USER.md")),
-        (memory_uri("longterm"), workspace.join("MEMORY.md")),
-    ];
-    for (uri, path) in items {
-        if let Ok(frame) = db.frame_by_uri(&uri) {
-            if let Ok(text) = db.frame_text_by_id(frame.id) {
-                fs::create_dir_all(workspace)?;
-                fs::write(&path, text)?;
-                paths.push(path.display().to_string());
-            }
+    let mut export = MemoryExport::default();
+    let mut state = load_memory_sync_state(workspace);
+    // Only active frames are exported, so daily notes deactivated by a rollup
+    // stay out of the workspace; their rollups are written instead.
+    for file in workspace_memory_files(&db, workspace, include_daily) {
+        let Ok(frame) = db.frame_by_uri(&file.uri) else {
+            continue;
+        };
+        let Ok(text) = db.frame_text_by_id(frame.id) else {
+            continue;
+        };
+        if export.write(&file.path, &text, frame.timestamp, if_newer)? {
+            state.insert(file.key, blake3_hash(text.as_bytes()).to_hex().to_string());
         }
     }
     if include_daily {
-        let daily_dir = workspace.join("memory");
-        fs::create_dir_all(&daily_dir)?;
-        let total = db.frame_count() as u64;
-        for frame_id in 0..total {
-            let frame = match db.frame_by_id(frame_id) {
-                Ok(f) => f,
-                Err(_) => continue,
-            };
-            let Some(uri) = frame.uri.as_deref() else {
+        let rollup_dir = workspace.join("memory").join("rollup");
+        for frame_id in db.collect_active_frame_ids(Some(MEMORY_ROLLUP_PREFIX)) {
+            let Ok(frame) = db.frame_by_id(frame_id) else {
                 continue;
             };
-            if !uri.starts_with("aethervault://memory/daily/") {
+            let Some(name) = frame.uri.as_deref().and_then(|uri| uri.rsplit('/').next()) else {
                 continue;
+            };
+            if let Ok(text) = db.frame_text_by_id(frame_id) {
+                export.write(&rollup_dir.join(name), &text, frame.timestamp, if_newer)?;
             }
-            if let Some(name) = uri.rsplit('/').next() {
-                let path = daily_dir.join(name);
-                if let Ok(text) = db.frame_text_by_id(frame_id) {
-                    fs::write(&path, text)?;
-                    paths.push(path.display().to_string());
+        }
+    }
+    save_memory_sync_state(workspace, &state)?;
+    Ok(export)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum MemorySyncState {
+    InSync,
+    LocalAhead,
+    CapsuleAhead,
+    Conflict,
+}
+
+impl MemorySyncState {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            MemorySyncState::InSync => "in-sync",
+            MemorySyncState::LocalAhead => "local-ahead",
+            MemorySyncState::CapsuleAhead => "capsule-ahead",
+            MemorySyncState::Conflict => "conflict",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct MemoryFileStatus {
+    pub(crate) path: String,
+    pub(crate) uri: String,
+    pub(crate) state: MemorySyncState,
+    pub(crate) frame_id: Option<u64>,
+    /// How a conflict was resolved, when a strategy was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) resolved: Option<String>,
+}
+
+/// Classify one file from its local hash, capsule hash and the hash recorded
+/// at the last sync/export. Without a recorded base, the newer of the file
+/// mtime and frame timestamp wins.
+pub(crate) fn classify_memory_file(
+    local: Option<&str>,
+    capsule: Option<&str>,
+    base: Option<&str>,
+    local_is_newer: bool,
+) -> MemorySyncState {
+    match (local, capsule) {
+        (Some(local), Some(capsule)) if local == capsule => MemorySyncState::InSync,
+        (Some(_), None) => MemorySyncState::LocalAhead,
+        (None, _) => MemorySyncState::CapsuleAhead,
+        (Some(local), Some(capsule)) => match base {
+            Some(base) if base == local => MemorySyncState::CapsuleAhead,
+            Some(base) if base == capsule => MemorySyncState::LocalAhead,
+            Some(_) => MemorySyncState::Conflict,
+            None if local_is_newer => MemorySyncState::LocalAhead,
+            None => MemorySyncState::CapsuleAhead,
+        },
+    }
+}
+
+/// Compare workspace memory files against the capsule. With a `strategy`,
+/// conflicts are resolved: `Local` syncs the file into the capsule, `Capsule`
+/// overwrites the file, `Both` keeps the file and writes the capsule copy to
+/// `<file>.conflict` for a manual merge.
+pub(crate) fn memory_sync_status(
+    mv2: &Path,
+    workspace: &Path,
+    strategy: Option<ConflictStrategy>,
+) -> Result<Vec<MemoryFileStatus>, Box<dyn std::error::Error>> {
+    let db = open_or_create_db(mv2)?;
+    let mut state = load_memory_sync_state(workspace);
+    let mut statuses = Vec::new();
+    for file in workspace_memory_files(&db, workspace, true) {
+        let local = fs::read(&file.path).ok();
+        let local_hash = local.as_ref().map(|bytes| blake3_hash(bytes).to_hex().to_string());
+        let frame = db.frame_by_uri(&file.uri).ok();
+        let capsule_text = frame.as_ref().and_then(|frame| db.frame_text_by_id(frame.id).ok());
+        if local.is_none() && capsule_text.is_none() {
+            continue;
+        }
+        let capsule_hash = capsule_text
+            .as_ref()
+            .map(|text| blake3_hash(text.as_bytes()).to_hex().to_string());
+        let local_is_newer = match (fs::metadata(&file.path).and_then(|meta| meta.modified()), &frame) {
+            (Ok(modified), Some(frame)) => {
+                modified > std::time::UNIX_EPOCH + Duration::from_secs(frame.timestamp.max(0) as u64)
+            }
+            _ => false,
+        };
+        let status = classify_memory_file(
+            local_hash.as_deref(),
+            capsule_hash.as_deref(),
+            state.get(&file.key).map(String::as_str),
+            local_is_newer,
+        );
+        let mut resolved = None;
+        if let (MemorySyncState::Conflict, Some(strategy), Some(frame), Some(text)) =
+            (status, strategy, &frame, &capsule_text)
+        {
+            match strategy {
+                ConflictStrategy::Local => {
+                    sync_memory_file(&db, &file.path, file.uri.clone(), &file.title, "aethervault.memory")?;
+                    state.insert(file.key.clone(), local_hash.clone().unwrap_or_default());
+                    resolved = Some("synced local file into capsule".to_string());
+                }
+                ConflictStrategy::Capsule => {
+                    MemoryExport::default().write(&file.path, text, frame.timestamp, false)?;
+                    state.insert(file.key.clone(), capsule_hash.clone().unwrap_or_default());
+                    resolved = Some("overwrote local file with capsule version".to_string());
+                }
+                ConflictStrategy::Both => {
+                    let mut copy = file.path.clone().into_os_string();
+                    copy.push(".conflict");
+                    fs::write(&copy, text)?;
+                    resolved = Some(format!("wrote capsule version to {}", Path::new(&copy).display()));
                 }
             }
         }
+        statuses.push(MemoryFileStatus {
+            path: file.key,
+            uri: file.uri,
+            state: status,
+            frame_id: frame.map(|frame| frame.id),
+            resolved,
+        });
     }
-    Ok(paths)
+    if strategy.is_some() {
+        save_memory_sync_state(workspace, &state)?;
+    }
+    Ok(statuses)
+}
+
+const MEMORY_DAILY_PREFIX: &str = "aethervault://memory/daily/";
+const MEMORY_ROLLUP_PREFIX: &str = "aethervault://memory/rollup/";
+
+pub(crate) fn memory_rollup_uri(key: &str) -> String {
+    format!("{MEMORY_ROLLUP_PREFIX}{key}.md")
+}
+
+/// `2026-10-14` for day rollups, `2026-W42` for week rollups.
+pub(crate) fn memory_rollup_key(date: NaiveDate, period: RollupPeriod) -> String {
+    match period {
+        RollupPeriod::Day => date.format("%Y-%m-%d").to_string(),
+        RollupPeriod::Week => {
+            let week = date.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct MemoryRollup {
+    pub(crate) key: String,
+    pub(crate) uri: String,
+    /// Dates of the daily notes folded into this rollup.
+    pub(crate) dates: Vec<String>,
+    pub(crate) frame_id: Option<u64>,
+    /// Set when the rollup already existed and `force` was off.
+    pub(crate) skipped: bool,
+    #[serde(skip)]
+    pub(crate) text: Option<String>,
+}
+
+/// Fold active daily notes from periods before `today` into one rollup note
+/// per period. `summarize` turns the concatenated notes into the rollup body.
+pub(crate) fn rollup_daily_memory(
+    db: &MemoryDb,
+    period: RollupPeriod,
+    today: NaiveDate,
+    force: bool,
+    deactivate: bool,
+    dry_run: bool,
+    summarize: &mut dyn FnMut(&str, &str) -> Result<String, String>,
+) -> Result<Vec<MemoryRollup>, String> {
+    let current = memory_rollup_key(today, period);
+    let mut groups: BTreeMap<String, Vec<(NaiveDate, u64)>> = BTreeMap::new();
+    for frame_id in db.collect_active_frame_ids(Some(MEMORY_DAILY_PREFIX)) {
+        let Ok(frame) = db.frame_by_id(frame_id) else {
+            continue;
+        };
+        let Some(date) = frame
+            .uri
+            .as_deref()
+            .and_then(|uri| uri.strip_prefix(MEMORY_DAILY_PREFIX))
+            .and_then(|name| NaiveDate::parse_from_str(name.trim_end_matches(".md"), "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        let key = memory_rollup_key(date, period);
+        // The current period is still being written.
+        if key >= current {
+            continue;
+        }
+        groups.entry(key).or_default().push((date, frame_id));
+    }
+
+    let mut rollups = Vec::new();
+    for (key, mut notes) in groups {
+        notes.sort();
+        let uri = memory_rollup_uri(&key);
+        let dates: Vec<String> = notes.iter().map(|(date, _)| date.to_string()).collect();
+        let mut rollup = MemoryRollup {
+            key,
+            uri,
+            dates,
+            frame_id: None,
+            skipped: false,
+            text: None,
+        };
+        if !force && db.frame_by_uri(&rollup.uri).is_ok() {
+            rollup.skipped = true;
+            rollups.push(rollup);
+            continue;
+        }
+        if dry_run {
+            rollups.push(rollup);
+            continue;
+        }
+        let mut combined = String::new();
+        for (date, frame_id) in &notes {
+            let text = db.frame_text_by_id(*frame_id)?;
+            combined.push_str(&format!("## {date}\n\n{}\n\n", text.trim()));
+        }
+        let summary = summarize(&rollup.key, combined.trim_end())?;
+        let text = format!("# Memory rollup {}\n\n{}\n", rollup.key, summary.trim());
+        let options = PutOptions {
+            uri: Some(rollup.uri.clone()),
+            title: Some(format!("memory rollup {}", rollup.key)),
+            kind: Some("text/markdown".to_string()),
+            track: Some("aethervault.memory".to_string()),
+            search_text: Some(text.clone()),
+            extra_metadata: BTreeMap::from([("rollup_dates".to_string(), rollup.dates.join(","))]),
+            ..PutOptions::default()
+        };
+        rollup.frame_id = Some(db.put_bytes_with_options(text.as_bytes(), options)?);
+        if deactivate {
+            for (_, frame_id) in &notes {
+                db.delete_frame(*frame_id)?;
+            }
+        }
+        db.commit()?;
+        rollup.text = Some(text);
+        rollups.push(rollup);
+    }
+    Ok(rollups)
 }
 
 // ── OAuth ───────────────────────────────────────────────────────────────
@@ -314,9 +607,13 @@ pub(crate) fn run_oauth_broker(
     let addr = format!("{bind}:{port}");
     let server = Server::http(&addr)
         .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("server: {e}")))?;
-    eprintln!("OAuth broker listening on http://{addr}");
+    log_info!("OAuth broker listening on http://{addr}");
 
     for request in server.incoming_requests() {
+        if let Some(response) = health_response(&request, &mv2) {
+            let _ = request.respond(response);
+            continue;
+        }
         let url = request.url().to_string();
         if !url.starts_with(&format!("/oauth/{provider}/callback")) {
             let response = Response::from_string("ok");
@@ -375,6 +672,215 @@ pub(crate) fn save_approvals(db: &MemoryDb, approvals: &[ApprovalEntry]) -> Resu
     Ok(())
 }
 
+/// Channels that hear about approvals queued while nobody is watching the
+/// conversation (`schedule` and `watch` runs).
+pub(crate) struct ApprovalNotifier {
+    telegram: Option<(String, i64)>,
+    webhook: Option<String>,
+}
+
+impl ApprovalNotifier {
+    pub(crate) fn new(
+        agent_cfg: &AgentConfig,
+        telegram_token: Option<String>,
+        telegram_chat_id: Option<String>,
+    ) -> Self {
+        let token = telegram_token
+            .or_else(|| agent_cfg.telegram_token.clone())
+            .or_else(|| env_optional("TELEGRAM_BOT_TOKEN"));
+        let chat_id = telegram_chat_id
+            .or_else(|| agent_cfg.telegram_chat_id.clone())
+            .or_else(|| env_optional("AETHERVAULT_TELEGRAM_CHAT_ID"))
+            .and_then(|id| id.trim().parse::<i64>().ok());
+        let webhook = agent_cfg
+            .approval_webhook
+            .clone()
+            .or_else(|| env_optional("AETHERVAULT_APPROVAL_WEBHOOK"));
+        Self {
+            telegram: token.zip(chat_id),
+            webhook,
+        }
+    }
+
+    pub(crate) fn is_configured(&self) -> bool {
+        self.telegram.is_some() || self.webhook.is_some()
+    }
+
+    /// Delivers `text` everywhere configured; succeeds if any channel took it.
+    pub(crate) fn send(&self, text: &str) -> Result<(), String> {
+        let timeout = Duration::from_secs(30);
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(timeout)
+            .timeout_read(timeout)
+            .timeout_write(timeout)
+            .build();
+        let mut errors = Vec::new();
+        let mut delivered = false;
+        if let Some((token, chat_id)) = &self.telegram {
+            let base_url = match std::env::var("TELEGRAM_API_BASE") {
+                Ok(base) => format!("{base}/bot{token}"),
+                Err(_) => format!("https://api.telegram.org/bot{token}"),
+            };
+            match telegram_send_message(&agent, &base_url, *chat_id, text) {
+                Ok(()) => delivered = true,
+                Err(e) => errors.push(format!("telegram: {e}")),
+            }
+        }
+        if let Some(webhook) = &self.webhook {
+            // `text` covers Slack and Teams, `content` covers Discord.
+            let payload = serde_json::json!({ "text": text, "content": text });
+            match agent
+                .post(webhook)
+                .set("content-type", "application/json")
+                .send_json(payload)
+            {
+                Ok(_) => delivered = true,
+                Err(e) => errors.push(format!("webhook: {e}")),
+            }
+        }
+        if delivered || errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+/// Announces each pending approval that hasn't been announced yet through
+/// `send`, then stamps it `notified_at` so the next poll stays quiet. Entries
+/// whose send fails are retried next time. Returns how many were announced.
+pub(crate) fn notify_pending_approvals(
+    db: &MemoryDb,
+    send: &mut dyn FnMut(&str) -> Result<(), String>,
+) -> Result<usize, String> {
+    let mut approvals = load_approvals(db);
+    let mut notified = 0;
+    for entry in approvals
+        .iter_mut()
+        .filter(|e| e.status == "pending" && e.notified_at.is_none())
+    {
+        let summary = if entry.summary.is_empty() {
+            approval_summary(&entry.tool, &entry.args)
+        } else {
+            entry.summary.clone()
+        };
+        let id = &entry.id;
+        let text = format!("Approval needed: {summary}\nReply `approve {id}` or `reject {id}`.");
+        match send(&text) {
+            Ok(()) => {
+                entry.notified_at = Some(Utc::now().to_rfc3339());
+                notified += 1;
+            }
+            Err(e) => log_warn!("[approvals] could not announce {id}: {e}"),
+        }
+    }
+    if notified > 0 {
+        save_approvals(db, &approvals)?;
+    }
+    Ok(notified)
+}
+
+/// Runs [`notify_pending_approvals`] for a polling loop; errors only get logged.
+fn announce_new_approvals(mv2: &Path, notifier: &ApprovalNotifier, source: &str) {
+    if !notifier.is_configured() {
+        return;
+    }
+    let result = open_or_create_db(mv2)
+        .map_err(|e| e.to_string())
+        .and_then(|db| notify_pending_approvals(&db, &mut |text| notifier.send(text)));
+    if let Err(e) = result {
+        log_warn!("[{source}] approval notification failed: {e}");
+    }
+}
+
+/// Default replay window for `idempotency_key` results.
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+/// Most keys kept per capsule; the oldest go first.
+const MAX_IDEMPOTENCY_ENTRIES: usize = 1000;
+
+/// Drop entries whose RFC 3339 `created_at` is older than `ttl` (or unparseable).
+pub(crate) fn prune_expired<T>(
+    entries: &mut Vec<T>,
+    ttl: chrono::Duration,
+    now: chrono::DateTime<chrono::Utc>,
+    created_at: impl Fn(&T) -> &str,
+) {
+    entries.retain(|entry| {
+        chrono::DateTime::parse_from_rfc3339(created_at(entry))
+            .is_ok_and(|ts| now.signed_duration_since(ts) <= ttl)
+    });
+}
+
+fn load_idempotency(db: &MemoryDb) -> Vec<IdempotencyEntry> {
+    let mut entries: Vec<IdempotencyEntry> = load_config_json(db, "idempotency")
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let ttl_secs = env_u64("AETHERVAULT_IDEMPOTENCY_TTL_SECS", DEFAULT_IDEMPOTENCY_TTL_SECS)
+        .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS);
+    let ttl = chrono::Duration::seconds(ttl_secs.min(i64::MAX as u64) as i64);
+    prune_expired(&mut entries, ttl, chrono::Utc::now(), |e| &e.created_at);
+    entries
+}
+
+/// Remove and return a non-empty `idempotency_key` from tool args.
+pub(crate) fn take_idempotency_key(args: &mut serde_json::Value) -> Option<String> {
+    let key = args.as_object_mut()?.remove("idempotency_key")?;
+    key.as_str().map(str::trim).filter(|k| !k.is_empty()).map(str::to_string)
+}
+
+/// The stored result for `key`, if it was seen within the TTL. Reusing a key
+/// with another tool or different arguments is an error, not a replay.
+pub(crate) fn idempotent_replay(
+    db: &MemoryDb,
+    key: &str,
+    tool: &str,
+    args_hash: &str,
+) -> Result<Option<ToolExecution>, String> {
+    let entries = load_idempotency(db);
+    let Some(entry) = entries.iter().find(|e| e.key == key) else {
+        return Ok(None);
+    };
+    if entry.tool != tool || entry.args_hash != args_hash {
+        return Err(format!(
+            "idempotency_key {key:?} was already used for a different {} call",
+            entry.tool
+        ));
+    }
+    let mut details = entry.details.clone();
+    if let Some(obj) = details.as_object_mut() {
+        obj.insert("idempotent_replay".into(), serde_json::json!(true));
+    }
+    Ok(Some(ToolExecution {
+        output: entry.output.clone(),
+        details,
+        is_error: false,
+    }))
+}
+
+pub(crate) fn remember_idempotent(
+    db: &MemoryDb,
+    key: &str,
+    tool: &str,
+    args_hash: &str,
+    result: &ToolExecution,
+) -> Result<(), String> {
+    let mut entries = load_idempotency(db);
+    entries.retain(|e| e.key != key);
+    entries.push(IdempotencyEntry {
+        key: key.to_string(),
+        tool: tool.to_string(),
+        args_hash: args_hash.to_string(),
+        output: result.output.clone(),
+        details: result.details.clone(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    });
+    if entries.len() > MAX_IDEMPOTENCY_ENTRIES {
+        entries.drain(..entries.len() - MAX_IDEMPOTENCY_ENTRIES);
+    }
+    let bytes = serde_json::to_vec(&entries).map_err(|e| e.to_string())?;
+    save_config_entry(db, "idempotency", &bytes).map(|_| ())
+}
+
 pub(crate) enum ApprovalChatCommand {
     Approve(String),
     Reject(String),
@@ -467,24 +973,7 @@ pub(crate) fn requires_approval(name: &str, args: &serde_json::Value) -> bool {
     if name.starts_with("mcp__") {
         return true;
     }
-    match name {
-        "exec" | "email_send" | "email_archive" | "config_set" | "gmail_send" | "gcal_create"
-        | "ms_calendar_create" | "trigger_add" | "trigger_remove" | "notify" | "signal_send"
-        | "imessage_send" | "memory_export" | "fs_write" | "browser" | "excalidraw"
-        | "self_upgrade" => true,
-        "http_request" => {
-            let method = args
-                .get("method")
-                .and_then(|v| v.as_str())
-                .unwrap_or("GET")
-                .to_ascii_uppercase();
-            method != "GET"
-        }
-        "scale" => {
-            args.get("action").and_then(|v| v.as_str()) == Some("resize")
-        }
-        _ => false,
-    }
+    tool_registry().requires_approval(name, args)
 }
 
 // ── Triggers ────────────────────────────────────────────────────────────
@@ -1023,11 +1512,12 @@ pub(crate) fn run_schedule_loop(
     let tz = resolve_timezone(&agent_cfg, timezone);
     let workspace = resolve_workspace(workspace, &agent_cfg);
     let telegram_token = telegram_token
-        .or(agent_cfg.telegram_token)
+        .or(agent_cfg.telegram_token.clone())
         .or_else(|| env_optional("TELEGRAM_BOT_TOKEN"));
     let telegram_chat_id = telegram_chat_id
-        .or(agent_cfg.telegram_chat_id)
+        .or(agent_cfg.telegram_chat_id.clone())
         .or_else(|| env_optional("AETHERVAULT_TELEGRAM_CHAT_ID"));
+    let notifier = ApprovalNotifier::new(&agent_cfg, telegram_token.clone(), telegram_chat_id.clone());
 
     let agent_config = build_bridge_agent_config(
         mv2.clone(),
@@ -1090,12 +1580,292 @@ pub(crate) fn run_schedule_loop(
                     }
                 }
             }
+            announce_new_approvals(&mv2, &notifier, "schedule");
         }
 
         thread::sleep(Duration::from_secs(30));
     }
 }
 
+/// The trigger's prompt (or `default`) with its `{{...}}` placeholders filled
+/// from `vars`; see [`TriggerEntry::template_vars`].
+fn trigger_prompt(trigger: &TriggerEntry, default: String, mut vars: BTreeMap<&str, String>, fired_at: &str) -> String {
+    let Some(template) = trigger.prompt.as_deref() else {
+        return default;
+    };
+    vars.insert("trigger_id", trigger.id.clone());
+    vars.insert("trigger_name", trigger.name.clone().unwrap_or_else(|| trigger.id.clone()));
+    vars.insert("fired_at", fired_at.to_string());
+    let (prompt, unknown) = render_trigger_prompt(template, &vars);
+    if !unknown.is_empty() {
+        log_warn!(
+            "[watch] trigger '{}' prompt has unknown placeholders: {} (available: {})",
+            trigger.id,
+            unknown.join(", "),
+            TriggerEntry::template_vars(&trigger.kind).join(", ")
+        );
+    }
+    prompt
+}
+
+/// From/Subject/Date headers and the snippet of a Gmail message, for trigger templates.
+fn gmail_message_vars(agent: &ureq::Agent, token: &str, id: &str) -> BTreeMap<&'static str, String> {
+    let url = format!(
+        "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=metadata&metadataHeaders=From&metadataHeaders=Subject&metadataHeaders=Date",
+        urlencoding::encode(id)
+    );
+    let mut vars = BTreeMap::new();
+    let Ok(payload) = agent
+        .get(&url)
+        .set("authorization", &format!("Bearer {token}"))
+        .call()
+        .map(|resp| resp.into_json::<serde_json::Value>().unwrap_or_default())
+    else {
+        return vars;
+    };
+    for header in payload["payload"]["headers"].as_array().into_iter().flatten() {
+        let value = header["value"].as_str().unwrap_or_default().to_string();
+        match header["name"].as_str().map(str::to_ascii_lowercase).as_deref() {
+            Some("from") => vars.insert("sender", value),
+            Some("subject") => vars.insert("subject", value),
+            Some("date") => vars.insert("date", value),
+            _ => None,
+        };
+    }
+    if let Some(snippet) = payload["snippet"].as_str() {
+        vars.insert("snippet", snippet.to_string());
+    }
+    vars
+}
+
+/// What a trigger would do if the watch loop evaluated it now.
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub(crate) enum TriggerOutcome {
+    /// Nothing new; `reason` says why.
+    Idle { reason: String },
+    /// First webhook poll: record the response hash without firing.
+    Baseline { last_seen: String },
+    /// The agent would run with `prompt`.
+    Fire {
+        matched: String,
+        prompt: String,
+        session: String,
+        last_seen: Option<String>,
+        last_fired: String,
+    },
+}
+
+/// Run one trigger's matching logic against the live source. Shared by the
+/// watch loop and `trigger test`, which must not drift apart.
+pub(crate) fn evaluate_trigger(
+    trigger: &TriggerEntry,
+    mv2: &Path,
+    now: chrono::DateTime<chrono::FixedOffset>,
+    workspace: Option<&Path>,
+) -> Result<TriggerOutcome, String> {
+    let idle = |reason: String| Ok(TriggerOutcome::Idle { reason });
+    if !trigger.enabled {
+        return idle("trigger is disabled".to_string());
+    }
+    let with_workspace = |mut prompt: String| {
+        if let Some(ws) = workspace {
+            prompt.push_str(&format!("\nWorkspace: {}", ws.display()));
+        }
+        prompt
+    };
+    let http = || {
+        ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(86400))
+            .timeout_read(Duration::from_secs(86400))
+            .build()
+    };
+    match trigger.kind.as_str() {
+        "email" => {
+            let query = match &trigger.query {
+                Some(q) if !q.trim().is_empty() => q.clone(),
+                _ => return idle("no query set".to_string()),
+            };
+            let token = get_oauth_token(mv2, "google").map_err(|e| format!("google oauth: {e}"))?;
+            let agent = http();
+            let mut url =
+                "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults=1".to_string();
+            url.push_str("&q=");
+            url.push_str(&urlencoding::encode(&query));
+            let payload = agent
+                .get(&url)
+                .set("authorization", &format!("Bearer {}", token))
+                .call()
+                .map_err(|e| format!("gmail: {e}"))?
+                .into_json::<serde_json::Value>()
+                .unwrap_or_default();
+            let id = payload
+                .get("messages")
+                .and_then(|m| m.as_array())
+                .and_then(|arr| arr.first())
+                .and_then(|m| m.get("id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            let Some(id) = id else {
+                return idle(format!("no messages match '{query}'"));
+            };
+            if trigger.last_seen.as_deref() == Some(&id) {
+                return idle(format!("latest match {id} was already seen"));
+            }
+            let mut vars = if trigger.prompt.as_deref().is_some_and(|p| p.contains("{{")) {
+                gmail_message_vars(&agent, &token, &id)
+            } else {
+                BTreeMap::new()
+            };
+            vars.insert("query", query.clone());
+            vars.insert("message_id", id.clone());
+            let mut prompt = trigger_prompt(
+                trigger,
+                "New email received. Review and take action.".to_string(),
+                vars,
+                &now.to_rfc3339(),
+            );
+            prompt.push_str(&format!(
+                "\n\nQuery: {query}\nMessage ID: {id}\nUse gmail_read to inspect."
+            ));
+            Ok(TriggerOutcome::Fire {
+                matched: format!("message {id}"),
+                prompt: with_workspace(prompt),
+                session: format!("trigger:email:{}", trigger.id),
+                last_seen: Some(id),
+                last_fired: now.to_rfc3339(),
+            })
+        }
+        "calendar_free" => {
+            let (Some(start), Some(end)) = (trigger.start.clone(), trigger.end.clone()) else {
+                return idle("start and end are required".to_string());
+            };
+            let token = get_oauth_token(mv2, "google").map_err(|e| format!("google oauth: {e}"))?;
+            let url = format!(
+                "https://www.googleapis.com/calendar/v3/calendars/primary/events?timeMin={}&timeMax={}&maxResults=1&singleEvents=true",
+                urlencoding::encode(&start),
+                urlencoding::encode(&end)
+            );
+            let payload = http()
+                .get(&url)
+                .set("authorization", &format!("Bearer {}", token))
+                .call()
+                .map_err(|e| format!("calendar: {e}"))?
+                .into_json::<serde_json::Value>()
+                .unwrap_or_default();
+            let has_events = payload
+                .get("items")
+                .and_then(|v| v.as_array())
+                .map(|arr| !arr.is_empty())
+                .unwrap_or(false);
+            if has_events {
+                return idle(format!("calendar has events between {start} and {end}"));
+            }
+            let fired_today = trigger
+                .last_fired
+                .as_deref()
+                .and_then(|v| v.split('T').next())
+                .map(|d| d == now.date_naive().to_string())
+                .unwrap_or(false);
+            if fired_today {
+                return idle("already fired today".to_string());
+            }
+            let vars = BTreeMap::from([("start", start.clone()), ("end", end.clone())]);
+            let mut prompt = trigger_prompt(
+                trigger,
+                "Calendar is free in the requested window. Schedule task.".to_string(),
+                vars,
+                &now.to_rfc3339(),
+            );
+            prompt.push_str(&format!("\n\nWindow: {start} → {end}\nNo events detected."));
+            Ok(TriggerOutcome::Fire {
+                matched: format!("free window {start} → {end}"),
+                prompt: with_workspace(prompt),
+                session: format!("trigger:calendar:{}", trigger.id),
+                last_seen: trigger.last_seen.clone(),
+                last_fired: now.to_rfc3339(),
+            })
+        }
+        "cron" => {
+            let cron_str = match &trigger.cron {
+                Some(c) if !c.trim().is_empty() => c.clone(),
+                _ => return idle("no cron expression set".to_string()),
+            };
+            let cron_expr = CronExpr::parse(&cron_str).map_err(|e| format!("bad cron: {e}"))?;
+            // chrono dow: Mon=1..Sun=7; cron: Sun=0..Sat=6
+            let dow = now.weekday().num_days_from_sunday();
+            let current_minute = format!("{}-{:02}-{:02}T{:02}:{:02}",
+                now.year(), now.month(), now.day(), now.hour(), now.minute());
+            if !cron_expr.matches(now.minute(), now.hour(), now.day(), now.month(), dow) {
+                return idle(format!("'{cron_str}' does not match {current_minute}"));
+            }
+            // Don't fire more than once in the same minute
+            if trigger.last_fired.as_deref() == Some(&current_minute) {
+                return idle(format!("already fired at {current_minute}"));
+            }
+            let prompt = trigger_prompt(
+                trigger,
+                format!("Cron trigger '{}' fired.", trigger.name.as_deref().unwrap_or(&trigger.id)),
+                BTreeMap::from([("cron", cron_str.clone())]),
+                &now.to_rfc3339(),
+            );
+            Ok(TriggerOutcome::Fire {
+                matched: format!("'{cron_str}' at {current_minute}"),
+                prompt: with_workspace(prompt),
+                session: format!("trigger:cron:{}", trigger.id),
+                last_seen: trigger.last_seen.clone(),
+                last_fired: current_minute,
+            })
+        }
+        "webhook" => {
+            let url = match &trigger.webhook_url {
+                Some(u) if !u.trim().is_empty() => u.clone(),
+                _ => return idle("no webhook_url set".to_string()),
+            };
+            let method = trigger.webhook_method.as_deref().unwrap_or("GET").to_uppercase();
+            let agent = http();
+            let resp = match method.as_str() {
+                "POST" => agent.post(&url).call(),
+                _ => agent.get(&url).call(),
+            };
+            let payload = resp
+                .map_err(|e| format!("webhook error: {e}"))?
+                .into_string()
+                .unwrap_or_default();
+            // Only fire if response changed since last check
+            let payload_hash = blake3::hash(payload.as_bytes()).to_hex().to_string();
+            if trigger.last_seen.as_deref() == Some(&payload_hash) {
+                return idle("response unchanged".to_string());
+            }
+            // First poll: record baseline without firing
+            if trigger.last_seen.is_none() {
+                return Ok(TriggerOutcome::Baseline { last_seen: payload_hash });
+            }
+            let preview_end = payload.char_indices()
+                .take_while(|&(i, _)| i < 500)
+                .last()
+                .map(|(i, c)| i + c.len_utf8())
+                .unwrap_or(0);
+            let vars = BTreeMap::from([("url", url.clone()), ("response", payload[..preview_end].to_string())]);
+            let mut prompt = trigger_prompt(
+                trigger,
+                format!("Webhook trigger '{}' detected a change.", trigger.name.as_deref().unwrap_or(&trigger.id)),
+                vars,
+                &now.to_rfc3339(),
+            );
+            prompt.push_str(&format!("\n\nWebhook URL: {url}\nResponse preview: {}", &payload[..preview_end]));
+            Ok(TriggerOutcome::Fire {
+                matched: format!("response {payload_hash}"),
+                prompt: with_workspace(prompt),
+                session: format!("trigger:webhook:{}", trigger.id),
+                last_seen: Some(payload_hash),
+                last_fired: now.to_rfc3339(),
+            })
+        }
+        other => idle(format!("unknown trigger kind '{other}'")),
+    }
+}
+
 pub(crate) fn run_watch_loop(
     mv2: PathBuf,
     workspace: Option<PathBuf>,
@@ -1111,6 +1881,7 @@ pub(crate) fn run_watch_loop(
     let agent_cfg = config.agent.clone().unwrap_or_default();
     let tz = resolve_timezone(&agent_cfg, timezone);
     let workspace = resolve_workspace(workspace, &agent_cfg);
+    let notifier = ApprovalNotifier::new(&agent_cfg, None, None);
     let agent_config = build_bridge_agent_config(
         mv2.clone(),
         model_hook,
@@ -1134,229 +1905,34 @@ pub(crate) fn run_watch_loop(
             if !trigger.enabled {
                 continue;
             }
-            match trigger.kind.as_str() {
-                "email" => {
-                    let query = match &trigger.query {
-                        Some(q) if !q.trim().is_empty() => q.clone(),
-                        _ => continue,
-                    };
-                    let token = match get_oauth_token(&mv2, "google") {
-                        Ok(token) => token,
-                        Err(_) => continue,
-                    };
-                    let agent = ureq::AgentBuilder::new()
-                        .timeout_connect(Duration::from_secs(86400))
-                        .timeout_read(Duration::from_secs(86400))
-                        .build();
-                    let mut url =
-                        "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults=1"
-                            .to_string();
-                    url.push_str("&q=");
-                    url.push_str(&urlencoding::encode(&query));
-                    let resp = agent
-                        .get(&url)
-                        .set("authorization", &format!("Bearer {}", token))
-                        .call();
-                    let payload = match resp {
-                        Ok(resp) => resp.into_json::<serde_json::Value>().unwrap_or_default(),
-                        Err(_) => continue,
-                    };
-                    let id = payload
-                        .get("messages")
-                        .and_then(|m| m.as_array())
-                        .and_then(|arr| arr.get(0))
-                        .and_then(|m| m.get("id"))
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string());
-                    if let Some(id) = id {
-                        if trigger.last_seen.as_deref() != Some(&id) {
-                            trigger.last_seen = Some(id.clone());
-                            trigger.last_fired = Some(now.to_rfc3339());
-                            updated = true;
-                            let mut prompt = trigger.prompt.clone().unwrap_or_else(|| {
-                                "New email received. Review and take action.".to_string()
-                            });
-                            prompt.push_str(&format!(
-                                "\n\nQuery: {query}\nMessage ID: {id}\nUse gmail_read to inspect."
-                            ));
-                            if let Some(ws) = &workspace {
-                                prompt.push_str(&format!("\nWorkspace: {}", ws.display()));
-                            }
-                            let session = format!("trigger:email:{}", trigger.id);
-                            let _ =
-                                run_agent_for_bridge(&agent_config, &prompt, session, None, None, None);
-                        }
-                    }
-                }
-                "calendar_free" => {
-                    let start = match &trigger.start {
-                        Some(s) => s.clone(),
-                        None => continue,
-                    };
-                    let end = match &trigger.end {
-                        Some(e) => e.clone(),
-                        None => continue,
-                    };
-                    let token = match get_oauth_token(&mv2, "google") {
-                        Ok(token) => token,
-                        Err(_) => continue,
-                    };
-                    let agent = ureq::AgentBuilder::new()
-                        .timeout_connect(Duration::from_secs(86400))
-                        .timeout_read(Duration::from_secs(86400))
-                        .build();
-                    let url = format!(
-                        "https://www.googleapis.com/calendar/v3/calendars/primary/events?timeMin={}&timeMax={}&maxResults=1&singleEvents=true",
-                        urlencoding::encode(&start),
-                        urlencoding::encode(&end)
-                    );
-                    let resp = agent
-                        .get(&url)
-                        .set("authorization", &format!("Bearer {}", token))
-                        .call();
-                    let payload = match resp {
-                        Ok(resp) => resp.into_json::<serde_json::Value>().unwrap_or_default(),
-                        Err(_) => continue,
-                    };
-                    let has_events = payload
-                        .get("items")
-                        .and_then(|v| v.as_array())
-                        .map(|arr| !arr.is_empty())
-                        .unwrap_or(false);
-                    if !has_events {
-                        let fired_today = trigger
-                            .last_fired
-                            .as_deref()
-                            .and_then(|v| v.split('T').next())
-                            .map(|d| d == now.date_naive().to_string())
-                            .unwrap_or(false);
-                        if !fired_today {
-                            trigger.last_fired = Some(now.to_rfc3339());
-                            updated = true;
-                            let mut prompt = trigger.prompt.clone().unwrap_or_else(|| {
-                                "Calendar is free in the requested window. Schedule task."
-                                    .to_string()
-                            });
-                            prompt.push_str(&format!(
-                                "\n\nWindow: {start} → {end}\nNo events detected."
-                            ));
-                            if let Some(ws) = &workspace {
-                                prompt.push_str(&format!("\nWorkspace: {}", ws.display()));
-                            }
-                            let session = format!("trigger:calendar:{}", trigger.id);
-                            let _ =
-                                run_agent_for_bridge(&agent_config, &prompt, session, None, None, None);
-                        }
-                    }
-                }
-                "cron" => {
-                    let cron_str = match &trigger.cron {
-                        Some(c) if !c.trim().is_empty() => c.clone(),
-                        _ => continue,
-                    };
-                    let cron_expr = match CronExpr::parse(&cron_str) {
-                        Ok(expr) => expr,
-                        Err(e) => {
-                            eprintln!("[watch] trigger '{}' bad cron: {e}", trigger.id);
-                            continue;
-                        }
-                    };
-                    // chrono dow: Mon=1..Sun=7; cron: Sun=0..Sat=6
-                    let dow = match now.weekday() {
-                        chrono::Weekday::Sun => 0,
-                        chrono::Weekday::Mon => 1,
-                        chrono::Weekday::Tue => 2,
-                        chrono::Weekday::Wed => 3,
-                        chrono::Weekday::Thu => 4,
-                        chrono::Weekday::Fri => 5,
-                        chrono::Weekday::Sat => 6,
-                    };
-                    if cron_expr.matches(
-                        now.minute(),
-                        now.hour(),
-                        now.day(),
-                        now.month(),
-                        dow,
-                    ) {
-                        // Don't fire more than once in the same minute
-                        let current_minute = format!("{}-{:02}-{:02}T{:02}:{:02}",
-                            now.year(), now.month(), now.day(), now.hour(), now.minute());
-                        if trigger.last_fired.as_deref() == Some(&current_minute) {
-                            continue;
-                        }
-                        trigger.last_fired = Some(current_minute);
-                        updated = true;
-                        let mut prompt = trigger.prompt.clone().unwrap_or_else(|| {
-                            format!("Cron trigger '{}' fired.", trigger.name.as_deref().unwrap_or(&trigger.id))
-                        });
-                        if let Some(ws) = &workspace {
-                            prompt.push_str(&format!("\nWorkspace: {}", ws.display()));
-                        }
-                        let session = format!("trigger:cron:{}", trigger.id);
-                        if let Err(e) = run_agent_for_bridge(&agent_config, &prompt, session, None, None, None) {
-                            eprintln!("[watch] trigger '{}' agent failed: {e}", trigger.id);
-                        }
-                    }
+            match evaluate_trigger(trigger, &mv2, now, workspace.as_deref()) {
+                Ok(TriggerOutcome::Idle { .. }) => {}
+                Ok(TriggerOutcome::Baseline { last_seen }) => {
+                    trigger.last_seen = Some(last_seen);
+                    updated = true;
                 }
-                "webhook" => {
-                    let url = match &trigger.webhook_url {
-                        Some(u) if !u.trim().is_empty() => u.clone(),
-                        _ => continue,
-                    };
-                    let method = trigger.webhook_method.as_deref().unwrap_or("GET").to_uppercase();
-                    let agent = ureq::AgentBuilder::new()
-                        .timeout_connect(Duration::from_secs(86400))
-                        .timeout_read(Duration::from_secs(86400))
-                        .build();
-                    let resp = match method.as_str() {
-                        "POST" => agent.post(&url).call(),
-                        _ => agent.get(&url).call(),
-                    };
-                    let payload = match resp {
-                        Ok(resp) => resp.into_string().unwrap_or_default(),
-                        Err(e) => {
-                            eprintln!("[watch] trigger '{}' webhook error: {e}", trigger.id);
-                            continue;
-                        }
-                    };
-                    // Only fire if response changed since last check
-                    let payload_hash = blake3::hash(payload.as_bytes()).to_hex().to_string();
-                    if trigger.last_seen.as_deref() == Some(&payload_hash) {
-                        continue;
-                    }
-                    // First poll: record baseline without firing
-                    if trigger.last_seen.is_none() {
-                        trigger.last_seen = Some(payload_hash);
-                        updated = true;
-                        continue;
-                    }
-                    trigger.last_seen = Some(payload_hash);
-                    trigger.last_fired = Some(now.to_rfc3339());
+                Ok(TriggerOutcome::Fire {
+                    prompt,
+                    session,
+                    last_seen,
+                    last_fired,
+                    ..
+                }) => {
+                    trigger.last_seen = last_seen;
+                    trigger.last_fired = Some(last_fired);
                     updated = true;
-                    let mut prompt = trigger.prompt.clone().unwrap_or_else(|| {
-                        format!("Webhook trigger '{}' detected a change.", trigger.name.as_deref().unwrap_or(&trigger.id))
-                    });
-                    let preview_end = payload.char_indices()
-                        .take_while(|&(i, _)| i < 500)
-                        .last()
-                        .map(|(i, c)| i + c.len_utf8())
-                        .unwrap_or(0);
-                    prompt.push_str(&format!("\n\nWebhook URL: {url}\nResponse preview: {}", &payload[..preview_end]));
-                    if let Some(ws) = &workspace {
-                        prompt.push_str(&format!("\nWorkspace: {}", ws.display()));
-                    }
-                    let session = format!("trigger:webhook:{}", trigger.id);
                     if let Err(e) = run_agent_for_bridge(&agent_config, &prompt, session, None, None, None) {
-                        eprintln!("[watch] trigger '{}' agent failed: {e}", trigger.id);
+                        log_error!("[watch] trigger '{}' agent failed: {e}", trigger.id);
                     }
+                    announce_new_approvals(&mv2, &notifier, "watch");
                 }
-                _ => {}
+                Err(e) => log_error!("[watch] trigger '{}': {e}", trigger.id),
             }
         }
 
         if updated {
             if let Err(e) = save_triggers(&db_loop, &triggers) {
-                eprintln!("[watch] CRITICAL: failed to persist trigger state: {e}");
+                log_error!("[watch] CRITICAL: failed to persist trigger state: {e}");
             }
         }
         thread::sleep(Duration::from_secs(poll_seconds));
@@ -1377,6 +1953,26 @@ pub(crate) fn qdrant_search_text(
     collection: &str,
     query: &str,
     limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    qdrant_query_points(base_url, collection, serde_json::json!(query), limit)
+}
+
+/// Nearest neighbours of an already-indexed point (points are keyed by frame id),
+/// using its stored vector rather than re-embedding any text.
+pub(crate) fn qdrant_search_similar(
+    base_url: &str,
+    collection: &str,
+    frame_id: u64,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    qdrant_query_points(base_url, collection, serde_json::json!(frame_id), limit)
+}
+
+fn qdrant_query_points(
+    base_url: &str,
+    collection: &str,
+    query: serde_json::Value,
+    limit: usize,
 ) -> Result<Vec<SearchHit>, String> {
     let url = format!("{}/collections/{}/points/query", base_url.trim_end_matches('/'), collection);
     let body = serde_json::json!({
@@ -1390,10 +1986,12 @@ pub(crate) fn qdrant_search_text(
         .timeout_read(Duration::from_secs(86400))
         .build();
 
-    let resp = agent.post(&url)
-        .set("content-type", "application/json")
-        .send_string(&serde_json::to_string(&body).map_err(|e| e.to_string())?)
-        .map_err(|e| format!("qdrant request: {e}"))?;
+    let body = serde_json::to_string(&body).map_err(|e| e.to_string())?;
+    // A points query is read-only, so it is safe to repeat.
+    let resp = http_with_retry("qdrant", HttpRetry::Idempotent, 3, 10.0, HttpBody::Text(&body), || {
+        agent.post(&url).set("content-type", "application/json")
+    })
+    .map_err(|e| format!("qdrant request: {e}"))?;
 
     let result: serde_json::Value = resp.into_json().map_err(|e| format!("qdrant parse: {e}"))?;
 
@@ -1438,6 +2036,7 @@ pub(crate) fn qdrant_search_text(
             matches: 0,
             chunk_range: None,
             chunk_text: None,
+            raw_text: None,
             score: Some(score),
             metadata: None,
         });
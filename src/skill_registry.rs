@@ -18,6 +18,16 @@ pub(crate) struct SkillRecord {
     pub(crate) contexts: Vec<String>,
 }
 
+/// Stable id for a skill name; every `skill_store` of the same name lands on
+/// the same URI, so new versions supersede the old one.
+pub(crate) fn skill_slug(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace(' ', "-")
+}
+
+pub(crate) fn skill_uri(slug: &str) -> String {
+    format!("aethervault://skills/{slug}.json")
+}
+
 pub(crate) fn open_skill_db(path: &Path) -> Result<Connection, Box<dyn std::error::Error>> {
     let conn = Connection::open(path)?;
     conn.execute_batch(
@@ -2,6 +2,8 @@ use serde::Deserialize;
 
 use std::collections::HashMap;
 
+use crate::ContextFormat;
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct ToolQueryArgs {
     pub(crate) query: String,
@@ -27,6 +29,8 @@ pub(crate) struct ToolQueryArgs {
     pub(crate) after: Option<String>,
     #[serde(default)]
     pub(crate) feedback_weight: Option<f32>,
+    #[serde(default)]
+    pub(crate) recency_halflife_days: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,6 +79,27 @@ pub(crate) struct ToolEmailSendArgs {
     pub(crate) in_reply_to: Option<String>,
     #[serde(default)]
     pub(crate) references: Option<String>,
+    #[serde(default)]
+    pub(crate) account: Option<String>,
+    /// Files to attach, resolved inside the allowed filesystem roots.
+    #[serde(default)]
+    pub(crate) attachments: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolEmailReplyArgs {
+    pub(crate) id: String,
+    pub(crate) body: String,
+    #[serde(default)]
+    pub(crate) reply_all: bool,
+    #[serde(default)]
+    pub(crate) attachments: Vec<String>,
+    #[serde(default)]
+    pub(crate) from: Option<String>,
+    #[serde(default)]
+    pub(crate) account: Option<String>,
+    #[serde(default)]
+    pub(crate) folder: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -106,6 +131,9 @@ pub(crate) struct ToolMemoryExportArgs {
     pub(crate) workspace: Option<String>,
     #[serde(default)]
     pub(crate) include_daily: Option<bool>,
+    /// Only overwrite files whose local copy is older than the capsule version.
+    #[serde(default)]
+    pub(crate) if_newer: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -113,6 +141,9 @@ pub(crate) struct ToolMemorySearchArgs {
     pub(crate) query: String,
     #[serde(default)]
     pub(crate) limit: Option<usize>,
+    /// `memory` (default), `reflections`, `skills` or `all`.
+    #[serde(default)]
+    pub(crate) scope: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,6 +157,8 @@ pub(crate) struct ToolExecArgs {
     pub(crate) estimated_ms: Option<u64>,
     #[serde(default)]
     pub(crate) background: Option<bool>,
+    #[serde(default)]
+    pub(crate) max_output_bytes: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -225,6 +258,9 @@ pub(crate) struct ToolHttpRequestArgs {
     pub(crate) json: Option<bool>,
     #[serde(default)]
     pub(crate) timeout_ms: Option<u64>,
+    /// Retry transient failures even for non-GET methods (the request is idempotent).
+    #[serde(default)]
+    pub(crate) retry_safe: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -295,6 +331,11 @@ pub(crate) struct ToolTriggerRemoveArgs {
     pub(crate) id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolTriggerTestArgs {
+    pub(crate) id: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct ToolToolSearchArgs {
     pub(crate) query: String,
@@ -318,6 +359,21 @@ pub(crate) struct ToolReflectArgs {
     pub(crate) reason: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolReflectionsListArgs {
+    #[serde(default)]
+    pub(crate) session: Option<String>,
+    #[serde(default)]
+    pub(crate) limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolSkillRunArgs {
+    pub(crate) name_or_uri: String,
+    #[serde(default)]
+    pub(crate) task: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct ToolSkillStoreArgs {
     pub(crate) name: String,
@@ -336,6 +392,23 @@ pub(crate) struct ToolSkillSearchArgs {
     pub(crate) query: String,
     #[serde(default)]
     pub(crate) limit: Option<usize>,
+    /// `relevance` (default) or `usage`.
+    #[serde(default)]
+    pub(crate) sort: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolSubagentRegisterArgs {
+    pub(crate) name: String,
+    pub(crate) system: String,
+    pub(crate) model_hook: String,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolSubagentRemoveArgs {
+    pub(crate) name: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -358,6 +431,12 @@ pub(crate) struct ToolSubagentBatchArgs {
     /// Maximum concurrent threads (default: all at once).
     #[serde(default)]
     pub(crate) max_concurrent: Option<usize>,
+    /// Per-subagent cap on `output` characters; longer outputs are stored as frames.
+    #[serde(default)]
+    pub(crate) max_output_chars: Option<usize>,
+    /// Per-subagent timeout in seconds (default: the subagent spec's timeout).
+    #[serde(default)]
+    pub(crate) timeout_secs: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -373,6 +452,12 @@ pub(crate) struct ToolContextArgs {
     pub(crate) max_bytes: Option<usize>,
     #[serde(default)]
     pub(crate) full: Option<bool>,
+    /// Neighbouring chunks to add on each side of a citation's match.
+    #[serde(default)]
+    pub(crate) window: Option<usize>,
+    /// `plain` (default), `markdown` or `xml`.
+    #[serde(default)]
+    pub(crate) format: Option<ContextFormat>,
     #[serde(default)]
     pub(crate) no_expand: Option<bool>,
     #[serde(default)]
@@ -389,6 +474,8 @@ pub(crate) struct ToolContextArgs {
     pub(crate) after: Option<String>,
     #[serde(default)]
     pub(crate) feedback_weight: Option<f32>,
+    #[serde(default)]
+    pub(crate) recency_halflife_days: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -402,6 +489,15 @@ pub(crate) struct ToolSearchArgs {
     pub(crate) snippet_chars: Option<usize>,
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolSimilarArgs {
+    pub(crate) id: String,
+    #[serde(default)]
+    pub(crate) collection: Option<String>,
+    #[serde(default)]
+    pub(crate) limit: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct ToolGetArgs {
     pub(crate) id: String,
@@ -2,666 +2,47 @@ use std::collections::{HashMap, HashSet};
 
 use serde_json;
 
-use super::{CapsuleConfig, SubagentSpec};
+use super::{is_stopword, is_tool_disabled, requires_approval, tool_registry, CapsuleConfig, SubagentSpec};
 
+/// Tool catalog (name, description, input schema) as exposed to agents and MCP
+/// clients; reflects any tools registered over the built-ins, minus disabled ones.
 pub(crate) fn tool_definitions_json() -> Vec<serde_json::Value> {
-    vec![
-        serde_json::json!({
-            "name": "query",
-            "description": "Hybrid search over the capsule (expansion + fusion + rerank).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "collection": { "type": "string" },
-                    "limit": { "type": "integer" },
-                    "snippet_chars": { "type": "integer" },
-                    "no_expand": { "type": "boolean" },
-                    "max_expansions": { "type": "integer" },
-                    "no_vector": { "type": "boolean" },
-                    "rerank": { "type": "string" },
-                    "asof": { "type": "string" },
-                    "before": { "type": "string" },
-                    "after": { "type": "string" },
-                    "feedback_weight": { "type": "number" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "context",
-            "description": "Build a prompt-ready context pack from the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "collection": { "type": "string" },
-                    "limit": { "type": "integer" },
-                    "snippet_chars": { "type": "integer" },
-                    "max_bytes": { "type": "integer" },
-                    "full": { "type": "boolean" },
-                    "no_expand": { "type": "boolean" },
-                    "max_expansions": { "type": "integer" },
-                    "no_vector": { "type": "boolean" },
-                    "rerank": { "type": "string" },
-                    "asof": { "type": "string" },
-                    "before": { "type": "string" },
-                    "after": { "type": "string" },
-                    "feedback_weight": { "type": "number" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "search",
-            "description": "Lexical search over the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "collection": { "type": "string" },
-                    "limit": { "type": "integer" },
-                    "snippet_chars": { "type": "integer" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "get",
-            "description": "Fetch a document by URI or frame id (#123).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "id": { "type": "string" }
-                },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "put",
-            "description": "Store a text payload into the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "uri": { "type": "string" },
-                    "title": { "type": "string" },
-                    "text": { "type": "string" },
-                    "kind": { "type": "string" },
-                    "track": { "type": "string" }
-                },
-                "required": ["uri", "text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "log",
-            "description": "Append an agent turn to the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "session": { "type": "string" },
-                    "role": { "type": "string" },
-                    "text": { "type": "string" },
-                    "meta": { "type": "object" }
-                },
-                "required": ["text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "feedback",
-            "description": "Store feedback for a URI (range -1.0 to 1.0).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "uri": { "type": "string" },
-                    "score": { "type": "number" },
-                    "note": { "type": "string" },
-                    "session": { "type": "string" }
-                },
-                "required": ["uri", "score"]
-            }
-        }),
-        serde_json::json!({
-            "name": "config_set",
-            "description": "Set a config JSON document at aethervault://config/<key>.json.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "key": { "type": "string" },
-                    "json": { "type": "object" }
-                },
-                "required": ["key", "json"]
-            }
-        }),
-        serde_json::json!({
-            "name": "memory_append_daily",
-            "description": "Append a line to the daily memory log (workspace) and store in the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "text": { "type": "string" },
-                    "date": { "type": "string" }
-                },
-                "required": ["text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "memory_remember",
-            "description": "Append a line to MEMORY.md (workspace) and store in the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "text": { "type": "string" }
-                },
-                "required": ["text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "memory_sync",
-            "description": "Sync workspace memory files into the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "workspace": { "type": "string" },
-                    "include_daily": { "type": "boolean" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "memory_export",
-            "description": "Export capsule memory back to workspace files.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "workspace": { "type": "string" },
-                    "include_daily": { "type": "boolean" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "memory_search",
-            "description": "Search memory stored in the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "limit": { "type": "integer" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "email_list",
-            "description": "List email envelopes via Himalaya.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "account": { "type": "string" },
-                    "folder": { "type": "string" },
-                    "limit": { "type": "integer" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "email_read",
-            "description": "Read a full message via Himalaya.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "id": { "type": "string" },
-                    "account": { "type": "string" },
-                    "folder": { "type": "string" }
-                },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "email_send",
-            "description": "Send an email via Himalaya template.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "to": { "type": "string" },
-                    "cc": { "type": "string" },
-                    "bcc": { "type": "string" },
-                    "subject": { "type": "string" },
-                    "body": { "type": "string" },
-                    "from": { "type": "string" },
-                    "in_reply_to": { "type": "string" },
-                    "references": { "type": "string" }
-                },
-                "required": ["to", "subject", "body"]
-            }
-        }),
-        serde_json::json!({
-            "name": "email_archive",
-            "description": "Archive an email (move to Archive) via Himalaya.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "id": { "type": "string" },
-                    "account": { "type": "string" },
-                    "folder": { "type": "string" }
-                },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "exec",
-            "description": "Execute a shell command. Default timeout: 2 minutes. SSH commands auto-timeout at 60s. Build commands (cargo, npm, make) get 5 minutes. Set timeout_ms to override. Use background=true for commands expected to run >5 minutes. Do NOT use exec to spawn LLM processes (codex, ollama) — use subagent_invoke or subagent_batch instead.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "command": { "type": "string", "description": "Shell command to execute. For LLM delegation, use subagent_invoke instead." },
-                    "cwd": { "type": "string" },
-                    "timeout_ms": { "type": "integer", "description": "Hard timeout in ms. Default: 120000 (2min). Max: 600000 (10min). SSH auto-gets 60s, builds auto-get 300s. Use background=true for longer." },
-                    "estimated_ms": { "type": "integer", "description": "Expected runtime in ms. Helps the system choose appropriate monitoring." },
-                    "background": { "type": "boolean", "description": "Run in background job queue. Required for commands expected to run >10 minutes. Returns a job ID for status checking." }
-                },
-                "required": ["command"]
-            }
-        }),
-        serde_json::json!({
-            "name": "notify",
-            "description": "Send a notification to Slack/Discord/Teams via webhook.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "channel": { "type": "string" },
-                    "text": { "type": "string" },
-                    "webhook": { "type": "string" }
-                },
-                "required": ["text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "signal_send",
-            "description": "Send a Signal message via signal-cli.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "to": { "type": "string" },
-                    "text": { "type": "string" },
-                    "sender": { "type": "string" }
-                },
-                "required": ["to", "text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "imessage_send",
-            "description": "Send an iMessage (macOS only).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "to": { "type": "string" },
-                    "text": { "type": "string" }
-                },
-                "required": ["to", "text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "http_request",
-            "description": "Generic HTTP request (GET allowed without approval; other methods may require approval).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "method": { "type": "string" },
-                    "url": { "type": "string" },
-                    "headers": { "type": "object" },
-                    "body": { "type": "string" },
-                    "json": { "type": "boolean" },
-                    "timeout_ms": { "type": "integer" }
-                },
-                "required": ["url"]
-            }
-        }),
-        serde_json::json!({
-            "name": "browser",
-            "description": "Browser automation via agent-browser CLI. Uses ref-based element selection from accessibility snapshots. Workflow: 1) 'open <url>' to navigate, 2) 'snapshot' to get element refs (@e1, @e2...), 3) interact using refs ('click @e1', 'fill @e2 text'). Sessions persist across calls. Commands: open, snapshot, click, fill, type, press, select, scroll, screenshot, pdf, get text/html/value, wait, eval, cookies, tab, back, forward, reload, close. Use 'find role/text/label' for semantic element finding.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "command": { "type": "string", "description": "The agent-browser command (e.g., 'open https://example.com', 'snapshot', 'click @e2', 'fill @e3 hello')" },
-                    "session": { "type": "string", "description": "Session name for browser isolation. Defaults to 'default'." },
-                    "timeout_ms": { "type": "integer", "description": "Timeout in milliseconds. Default u64::MAX (no deadline)." }
-                },
-                "required": ["command"]
-            }
-        }),
-        serde_json::json!({
-            "name": "excalidraw",
-            "description": "Create hand-drawn diagrams via Excalidraw MCP server. Actions: 'read_me' returns the element format reference (call before first create_view), 'create_view' renders a diagram from Excalidraw JSON elements. Requires excalidraw-mcp server (set EXCALIDRAW_MCP_CMD to override startup command, default: 'npx excalidraw-mcp --stdio').",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "action": { "type": "string", "description": "Action: 'read_me' (get element format reference) or 'create_view' (render diagram)" },
-                    "elements": { "type": "string", "description": "JSON array of Excalidraw elements (required for create_view)" }
-                },
-                "required": ["action"]
-            }
-        }),
-        serde_json::json!({
-            "name": "fs_list",
-            "description": "List files within allowed roots.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string" },
-                    "recursive": { "type": "boolean" },
-                    "max_entries": { "type": "integer" }
-                },
-                "required": ["path"]
-            }
-        }),
-        serde_json::json!({
-            "name": "fs_read",
-            "description": "Read a file within allowed roots.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string" },
-                    "max_bytes": { "type": "integer" }
-                },
-                "required": ["path"]
-            }
-        }),
-        serde_json::json!({
-            "name": "fs_write",
-            "description": "Write a file within allowed roots.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "path": { "type": "string" },
-                    "text": { "type": "string" },
-                    "append": { "type": "boolean" }
-                },
-                "required": ["path", "text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "approval_list",
-            "description": "List pending approval requests.",
-            "inputSchema": { "type": "object", "properties": {} }
-        }),
-        serde_json::json!({
-            "name": "trigger_add",
-            "description": "Add an event trigger. Kinds: email (Gmail query), calendar_free (Google Calendar window), cron (cron expression schedule), webhook (HTTP endpoint change detection).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "kind": { "type": "string", "description": "Trigger kind: email, calendar_free, cron, or webhook" },
-                    "name": { "type": "string", "description": "Human-readable trigger name" },
-                    "query": { "type": "string", "description": "Gmail query (for kind=email)" },
-                    "prompt": { "type": "string", "description": "Prompt to send to agent when trigger fires" },
-                    "start": { "type": "string", "description": "Window start (for kind=calendar_free)" },
-                    "end": { "type": "string", "description": "Window end (for kind=calendar_free)" },
-                    "cron": { "type": "string", "description": "Cron expression: 'min hour dom month dow' (for kind=cron). Example: '0 9 * * 1-5' = weekdays 9am" },
-                    "webhook_url": { "type": "string", "description": "URL to poll (for kind=webhook)" },
-                    "webhook_method": { "type": "string", "description": "HTTP method for webhook (default: GET)" },
-                    "enabled": { "type": "boolean" }
-                },
-                "required": ["kind"]
-            }
-        }),
-        serde_json::json!({
-            "name": "trigger_list",
-            "description": "List configured triggers.",
-            "inputSchema": { "type": "object", "properties": {} }
-        }),
-        serde_json::json!({
-            "name": "trigger_remove",
-            "description": "Remove a trigger by id.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "id": { "type": "string" }
-                },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "tool_search",
-            "description": "Search available tools by name/description.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "limit": { "type": "integer" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "session_context",
-            "description": "Fetch recent log entries for a session.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "session": { "type": "string" },
-                    "limit": { "type": "integer" }
-                },
-                "required": ["session"]
-            }
-        }),
-        serde_json::json!({
-            "name": "reflect",
-            "description": "Store a self-critique reflection in the capsule.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "text": { "type": "string" },
-                    "session": { "type": "string" },
-                    "reason": { "type": "string" }
-                },
-                "required": ["text"]
-            }
-        }),
-        serde_json::json!({
-            "name": "skill_store",
-            "description": "Store a reusable procedure as a skill.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "name": { "type": "string" },
-                    "trigger": { "type": "string" },
-                    "steps": { "type": "array", "items": { "type": "string" } },
-                    "tools": { "type": "array", "items": { "type": "string" } },
-                    "notes": { "type": "string" }
-                },
-                "required": ["name"]
-            }
-        }),
-        serde_json::json!({
-            "name": "skill_search",
-            "description": "Search stored skills.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "limit": { "type": "integer" }
-                },
-                "required": ["query"]
-            }
-        }),
-        serde_json::json!({
-            "name": "subagent_list",
-            "description": "Check subagent configuration. Shows whether dynamic spawning is enabled and any pre-existing agent configs. You can use subagent_invoke with ANY name — you don't need to call this first.",
-            "inputSchema": { "type": "object", "properties": {} }
-        }),
-        serde_json::json!({
-            "name": "subagent_invoke",
-            "description": "Spawn a subagent to perform a task. Use ANY descriptive name — the name should describe what the agent does (e.g., 'log-analyzer', 'api-tester', 'deploy-checker'). The subagent runs with its own session, tools, and memory.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "name": { "type": "string", "description": "Descriptive name for this subagent (e.g., 'log-analyzer', 'code-reviewer'). Choose a name that describes the task." },
-                    "prompt": { "type": "string", "description": "Detailed task description for the subagent. Be specific — the subagent has its own context." },
-                    "system": { "type": "string", "description": "Override the subagent's system prompt" },
-                    "model_hook": { "type": "string", "description": "Override the subagent's model hook" },
-                    "max_steps": { "type": "integer", "description": "Override max reasoning steps for this invocation. Default: from subagent config, fallback 64." }
-                },
-                "required": ["name", "prompt"]
-            }
-        }),
-        serde_json::json!({
-            "name": "subagent_batch",
-            "description": "Spawn multiple subagents concurrently for parallel work. Each invocation runs independently with its own session. Use descriptive names for each agent. Use max_concurrent to limit parallelism.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "invocations": {
-                        "type": "array",
-                        "description": "Array of subagent invocations to run concurrently. Each has name, prompt, and optional overrides.",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "name": { "type": "string", "description": "Descriptive name for this subagent (e.g., 'log-analyzer', 'security-scanner')." },
-                                "prompt": { "type": "string", "description": "Task/prompt for this subagent" },
-                                "system": { "type": "string" },
-                                "model_hook": { "type": "string" },
-                                "max_steps": { "type": "integer" }
-                            },
-                            "required": ["name", "prompt"]
-                        }
-                    },
-                    "max_concurrent": { "type": "integer", "description": "Maximum concurrent subagents. Default: all at once. Set lower to reduce resource usage." }
-                },
-                "required": ["invocations"]
-            }
-        }),
-        serde_json::json!({
-            "name": "gmail_list",
-            "description": "List Gmail messages (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "query": { "type": "string" },
-                    "max_results": { "type": "integer" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "gmail_read",
-            "description": "Read a Gmail message by id (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": { "id": { "type": "string" } },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "gmail_send",
-            "description": "Send a Gmail message (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "to": { "type": "string" },
-                    "subject": { "type": "string" },
-                    "body": { "type": "string" }
-                },
-                "required": ["to", "subject", "body"]
-            }
-        }),
-        serde_json::json!({
-            "name": "gcal_list",
-            "description": "List Google Calendar events (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": { "max_results": { "type": "integer" } }
-            }
-        }),
-        serde_json::json!({
-            "name": "gcal_create",
-            "description": "Create a Google Calendar event on primary calendar (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "summary": { "type": "string" },
-                    "start": { "type": "string" },
-                    "end": { "type": "string" },
-                    "description": { "type": "string" }
-                },
-                "required": ["summary", "start", "end"]
-            }
-        }),
-        serde_json::json!({
-            "name": "ms_mail_list",
-            "description": "List Microsoft mail messages (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": { "top": { "type": "integer" } }
-            }
-        }),
-        serde_json::json!({
-            "name": "ms_mail_read",
-            "description": "Read Microsoft mail message by id (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": { "id": { "type": "string" } },
-                "required": ["id"]
-            }
-        }),
-        serde_json::json!({
-            "name": "ms_calendar_list",
-            "description": "List Microsoft calendar events (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": { "top": { "type": "integer" } }
-            }
-        }),
-        serde_json::json!({
-            "name": "ms_calendar_create",
-            "description": "Create Microsoft calendar event (OAuth).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "subject": { "type": "string" },
-                    "start": { "type": "string" },
-                    "end": { "type": "string" },
-                    "body": { "type": "string" }
-                },
-                "required": ["subject", "start", "end"]
-            }
-        }),
-        serde_json::json!({
-            "name": "scale",
-            "description": "Monitor and scale infrastructure resources. Actions: 'status' (CPU/RAM/disk/load), 'sizes' (list available DigitalOcean droplet sizes with pricing), 'resize' (scale droplet up/down, requires size param and approval).",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "action": {
-                        "type": "string",
-                        "enum": ["status", "resize", "sizes"]
-                    },
-                    "size": {
-                        "type": "string",
-                        "description": "Target droplet size slug (e.g. s-2vcpu-4gb). Required for resize."
-                    }
-                },
-                "required": ["action"]
-            }
-        }),
-        serde_json::json!({
-            "name": "bg_status",
-            "description": "Check status of background tasks. Returns a traffic-light scorecard of all running, completed, and failed background sub-agents.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "task_id": { "type": "string", "description": "Optional: check a specific task by ID" }
-                }
-            }
-        }),
-        serde_json::json!({
-            "name": "self_upgrade",
-            "description": "Trigger a self-upgrade: pull latest code from git, compile, validate, and hot-swap the binary. Uses blue-green deployment with automatic rollback. Requires approval. IMPORTANT: If you edited source files, you MUST git add, commit, and push your changes BEFORE calling this tool — it does `git reset --hard origin/<branch>` which wipes uncommitted changes.",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "branch": { "type": "string", "description": "Git branch to pull from (default: main)" },
-                    "skip_tests": { "type": "boolean", "description": "Skip smoke test (not recommended)" }
-                }
-            }
-        }),
-    ]
+    let mut tools = tool_registry().definitions();
+    tools.retain(|t| !t.get("name").and_then(|n| n.as_str()).is_some_and(is_tool_disabled));
+    tools
+}
+
+/// `tool_definitions_json` with each tool's policy added: `requires_approval`
+/// for a call without arguments (env overrides included) and `write`, which
+/// read-only mode refuses. Backs `aethervault tools`.
+pub(crate) fn tool_catalog_with_policies() -> Vec<serde_json::Value> {
+    let registry = tool_registry();
+    let mut tools = tool_definitions_json();
+    for tool in &mut tools {
+        let name = tool.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let approval = requires_approval(&name, &serde_json::json!({}));
+        let write = registry.get(&name).is_some_and(|t| t.is_write());
+        if let Some(obj) = tool.as_object_mut() {
+            obj.insert("requires_approval".into(), approval.into());
+            obj.insert("write".into(), write.into());
+        }
+    }
+    tools
+}
+
+/// Arguments for a call the harness makes on the model's behalf
+/// (`agent.force_first_tool`): the prompt as `query` when `tool` takes one.
+pub(crate) fn forced_tool_args(tool: &serde_json::Value, prompt: &str) -> serde_json::Value {
+    let takes_query = tool
+        .get("inputSchema")
+        .or_else(|| tool.get("input_schema"))
+        .and_then(|schema| schema.pointer("/properties/query"))
+        .is_some();
+    if takes_query {
+        serde_json::json!({ "query": prompt.trim().chars().take(500).collect::<String>() })
+    } else {
+        serde_json::json!({})
+    }
 }
 
 pub(crate) fn tool_score(query_tokens: &[String], name: &str, description: &str) -> i32 {
@@ -691,6 +72,41 @@ pub(crate) fn tool_score(query_tokens: &[String], name: &str, description: &str)
     score
 }
 
+/// Catalog tools outside `active` worth advertising up front for `prompt`:
+/// `tool_score` against the prompt's non-stopword terms must reach
+/// `min_score`. Best first, at most `limit`.
+pub(crate) fn relevant_tool_names(
+    catalog: &[serde_json::Value],
+    active: &HashSet<String>,
+    prompt: &str,
+    min_score: i32,
+    limit: usize,
+) -> Vec<String> {
+    let tokens: Vec<String> = prompt
+        .to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|t| t.len() > 2 && !is_stopword(t))
+        .map(str::to_string)
+        .collect();
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+    let mut scored: Vec<(i32, String)> = catalog
+        .iter()
+        .filter_map(|tool| {
+            let name = tool.get("name").and_then(|v| v.as_str())?;
+            if active.contains(name) {
+                return None;
+            }
+            let desc = tool.get("description").and_then(|v| v.as_str()).unwrap_or_default();
+            let score = tool_score(&tokens, name, desc);
+            (score >= min_score).then(|| (score, name.to_string()))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    scored.into_iter().take(limit).map(|(_, name)| name).collect()
+}
+
 pub(crate) fn load_subagents_from_config(config: &CapsuleConfig) -> Vec<SubagentSpec> {
     config
         .agent
@@ -719,6 +135,7 @@ pub(crate) fn base_tool_names() -> HashSet<String> {
         "context",
         "search",
         "get",
+        "similar",
         "put",
         "log",
         "feedback",
@@ -730,12 +147,17 @@ pub(crate) fn base_tool_names() -> HashSet<String> {
         "memory_sync",
         "memory_export",
         "reflect",
+        "reflections_list",
         "skill_store",
         "skill_search",
+        "skill_run",
         "trigger_add",
         "trigger_list",
         "trigger_remove",
+        "trigger_test",
         "subagent_list",
+        "subagent_register",
+        "subagent_remove",
         "subagent_invoke",
         "subagent_batch",
         "bg_status",
@@ -773,3 +195,38 @@ pub(crate) fn tools_from_active(
     });
     tools
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_activates_only_relevant_non_base_tools() {
+        let catalog = vec![
+            serde_json::json!({ "name": "query", "description": "Search the capsule." }),
+            serde_json::json!({ "name": "email_send", "description": "Send an email message." }),
+            serde_json::json!({ "name": "calendar_list", "description": "List calendar events." }),
+        ];
+        let active: HashSet<String> = ["query".to_string()].into_iter().collect();
+        let names = relevant_tool_names(&catalog, &active, "Please send an email to the team", 4, 5);
+        assert_eq!(names, vec!["email_send".to_string()]);
+        assert!(relevant_tool_names(&catalog, &active, "the and of", 1, 5).is_empty());
+        assert!(relevant_tool_names(&catalog, &active, "search the capsule", 1, 5).is_empty());
+    }
+
+    #[test]
+    fn catalog_with_policies_flags_approval_and_write_tools() {
+        let catalog = tool_catalog_map(&tool_catalog_with_policies());
+        assert_eq!(catalog["put"]["write"], true);
+        assert_eq!(catalog["query"]["write"], false);
+        assert_eq!(catalog["query"]["requires_approval"], false);
+        assert!(catalog["query"]["inputSchema"].is_object());
+    }
+
+    #[test]
+    fn forced_tool_args_pass_the_prompt_as_query() {
+        let catalog = tool_catalog_map(&tool_registry().definitions());
+        assert_eq!(forced_tool_args(&catalog["context"], " what did we decide? "), serde_json::json!({ "query": "what did we decide?" }));
+        assert_eq!(forced_tool_args(&catalog["session_context"], "hi"), serde_json::json!({}));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
@@ -8,7 +9,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
 
-use crate::memory_db::{MemoryDb, SearchRequest, PutOptions};
+use crate::memory_db::{MemoryDb, SearchRequest, SearchResponse, PutOptions};
 use crate::consolidation::{put_with_consolidation, ConsolidationDecision};
 use base64::Engine;
 use chrono::Utc;
@@ -16,7 +17,24 @@ use walkdir::WalkDir;
 
 use std::sync::mpsc;
 
+mod email;
+mod exec;
+mod files;
+mod google;
+mod memory;
+mod messaging;
+mod microsoft;
+mod ops;
+mod session;
+mod skills;
+mod subagents;
+mod triggers;
+mod web;
+
 const DEFAULT_HTTP_TIMEOUT_MS: u64 = 120_000;
+/// Attempts and total backoff (seconds) for tool HTTP calls; see `http_with_retry`.
+const TOOL_HTTP_ATTEMPTS: usize = 3;
+const TOOL_HTTP_RETRY_BUDGET: f64 = 20.0;
 /// Sentinel: disable timeout for exec policies (Codex CLI, builds).
 const EXEC_NO_TIMEOUT: u64 = u64::MAX;
 
@@ -60,18 +78,19 @@ fn run_himalaya(cmd: &mut std::process::Command, stdin_data: Option<&[u8]>) -> R
 fn oauth_api_get(mv2: &Path, provider: &str, url: &str, label: &str) -> Result<serde_json::Value, String> {
     let token = get_oauth_token(mv2, provider).map_err(|e| e.to_string())?;
     let agent = make_http_agent(DEFAULT_HTTP_TIMEOUT_MS);
-    let resp = agent
-        .get(url)
-        .set("authorization", &format!("Bearer {}", token))
-        .call();
+    let resp = http_with_retry(
+        label,
+        HttpRetry::Idempotent,
+        TOOL_HTTP_ATTEMPTS,
+        TOOL_HTTP_RETRY_BUDGET,
+        HttpBody::Empty,
+        || agent.get(url).set("authorization", &format!("Bearer {}", token)),
+    );
     match resp {
         Ok(resp) => resp
             .into_json::<serde_json::Value>()
             .map_err(|e| e.to_string()),
-        Err(ureq::Error::Status(code, resp)) => {
-            let text = resp.into_string().unwrap_or_default();
-            Err(format!("{label} error {code}: {text}"))
-        }
+        Err(HookHttpError::Status(code, text)) => Err(format!("{label} error {code}: {text}")),
         Err(err) => Err(format!("{label} failed: {err}")),
     }
 }
@@ -80,19 +99,25 @@ fn oauth_api_get(mv2: &Path, provider: &str, url: &str, label: &str) -> Result<s
 fn oauth_api_post(mv2: &Path, provider: &str, url: &str, payload: serde_json::Value, label: &str) -> Result<serde_json::Value, String> {
     let token = get_oauth_token(mv2, provider).map_err(|e| e.to_string())?;
     let agent = make_http_agent(DEFAULT_HTTP_TIMEOUT_MS);
-    let resp = agent
-        .post(url)
-        .set("authorization", &format!("Bearer {}", token))
-        .set("content-type", "application/json")
-        .send_json(payload);
+    // These POSTs send mail or create events: never repeat one the server may have seen.
+    let resp = http_with_retry(
+        label,
+        HttpRetry::ConnectOnly,
+        TOOL_HTTP_ATTEMPTS,
+        TOOL_HTTP_RETRY_BUDGET,
+        HttpBody::Json(&payload),
+        || {
+            agent
+                .post(url)
+                .set("authorization", &format!("Bearer {}", token))
+                .set("content-type", "application/json")
+        },
+    );
     match resp {
         Ok(resp) => resp
             .into_json::<serde_json::Value>()
             .map_err(|e| e.to_string()),
-        Err(ureq::Error::Status(code, resp)) => {
-            let text = resp.into_string().unwrap_or_default();
-            Err(format!("{label} error {code}: {text}"))
-        }
+        Err(HookHttpError::Status(code, text)) => Err(format!("{label} error {code}: {text}")),
         Err(err) => Err(format!("{label} failed: {err}")),
     }
 }
@@ -168,13 +193,415 @@ fn harden_ssh_in_command(command: &str) -> String {
     }
 }
 
+/// Operator gates for `exec`, applied before approval and again on the approved run.
+/// Precedence: `AETHERVAULT_EXEC_DISABLED=1` refuses everything; otherwise a
+/// non-empty `agent.exec_allow` must match the command. Plain entries are
+/// whole-word prefixes and only match commands free of shell metacharacters
+/// (chaining, substitution, redirection, escapes, even inside quotes);
+/// `re:` entries are regexes matched against the whole command string.
+fn exec_policy_refusal(command: &str, exec_allow: &[String]) -> Option<String> {
+    if env_bool("AETHERVAULT_EXEC_DISABLED", false) {
+        return Some("exec refused: disabled by AETHERVAULT_EXEC_DISABLED".to_string());
+    }
+    if exec_allow.is_empty() {
+        return None;
+    }
+    let trimmed = command.trim();
+    let chained = trimmed.contains(['&', '|', ';', '`', '$', '(', ')', '<', '>', '\\', '\n', '\r']);
+    for entry in exec_allow {
+        if let Some(pattern) = entry.strip_prefix("re:") {
+            match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(trimmed) => return None,
+                Ok(_) => {}
+                Err(err) => log_warn!("[tool:exec] invalid exec_allow regex {pattern:?}: {err}"),
+            }
+        } else if !chained
+            && !entry.trim().is_empty()
+            && trimmed
+                .strip_prefix(entry.trim())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            return None;
+        }
+    }
+    Some(format!(
+        "exec refused: command does not match agent.exec_allow ({} entries){}",
+        exec_allow.len(),
+        if chained {
+            "; prefix entries never match commands with shell metacharacters"
+        } else {
+            ""
+        }
+    ))
+}
+
+fn load_agent_config(db: &MemoryDb, workspace: &Option<PathBuf>) -> AgentConfig {
+    let ws = workspace
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+    let config = if crate::config_file_path(&ws).exists() {
+        crate::load_config_from_file(&ws)
+    } else {
+        load_capsule_config(db).unwrap_or_default()
+    };
+    config.agent.unwrap_or_default()
+}
+
+/// `(name, is_default)` for each entry of `himalaya account list --output json`.
+fn parse_himalaya_accounts(stdout: &str) -> Vec<(String, bool)> {
+    let Ok(serde_json::Value::Array(items)) = serde_json::from_str(stdout) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match item {
+            serde_json::Value::String(name) => Some((name.clone(), false)),
+            _ => Some((
+                item.get("name")?.as_str()?.to_string(),
+                item.get("default").and_then(|d| d.as_bool()).unwrap_or(false),
+            )),
+        })
+        .collect()
+}
+
+fn himalaya_accounts() -> Result<Vec<(String, bool)>, String> {
+    let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+    cmd.arg("account").arg("list").arg("--output").arg("json");
+    Ok(parse_himalaya_accounts(&run_himalaya(&mut cmd, None)?))
+}
+
+/// Account an `email_*` call acts as: the requested one, else
+/// `agent.default_email_account`, else Himalaya's own default (`None`).
+fn resolve_email_account(
+    requested: Option<String>,
+    db: &MemoryDb,
+    workspace: &Option<PathBuf>,
+) -> Option<String> {
+    requested
+        .filter(|a| !a.trim().is_empty())
+        .or_else(|| load_agent_config(db, workspace).default_email_account)
+}
+
+fn account_label(account: &Option<String>) -> String {
+    account.clone().unwrap_or_else(|| "himalaya default".to_string())
+}
+
+/// URI prefixes searched by each `memory_search` scope. Reflections are
+/// stored under `aethervault://memory/reflection/`, so `memory` covers them.
+fn memory_search_prefixes(scope: &str) -> Result<&'static [&'static str], String> {
+    match scope {
+        "memory" => Ok(&["aethervault://memory/"]),
+        "reflections" => Ok(&["aethervault://memory/reflection/"]),
+        "skills" => Ok(&["aethervault://skills/"]),
+        "all" => Ok(&["aethervault://memory/", "aethervault://skills/"]),
+        other => Err(format!("unknown memory_search scope '{other}' (memory, reflections, skills, all)")),
+    }
+}
+
+/// Total size allowed for one message's attachments.
+const EMAIL_ATTACHMENTS_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Resolve attachment paths inside the allowed roots, checking that each is
+/// a file and that together they stay under `EMAIL_ATTACHMENTS_MAX_BYTES`.
+fn resolve_email_attachments(paths: &[String], roots: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut total = 0u64;
+    let mut resolved = Vec::new();
+    for path in paths {
+        let file = resolve_fs_path(path, roots).map_err(|e| format!("attachment {path}: {e}"))?;
+        let meta = fs::metadata(&file).map_err(|e| format!("attachment {path}: {e}"))?;
+        if !meta.is_file() {
+            return Err(format!("attachment {path}: not a file"));
+        }
+        total += meta.len();
+        if total > EMAIL_ATTACHMENTS_MAX_BYTES {
+            return Err(format!(
+                "attachments exceed {} MB in total",
+                EMAIL_ATTACHMENTS_MAX_BYTES / (1024 * 1024)
+            ));
+        }
+        let file = fs::canonicalize(&file).map_err(|e| format!("attachment {path}: {e}"))?;
+        if file.to_string_lossy().contains(['"', '<', '>', '\n']) {
+            return Err(format!("attachment {path}: unsupported characters in path"));
+        }
+        resolved.push(file);
+    }
+    Ok(resolved)
+}
+
+/// Send through `himalaya template send`, refusing an unknown account up front.
+/// Attachments become MML `<#part>` elements after the body.
+fn send_email(
+    parsed: ToolEmailSendArgs,
+    db: &MemoryDb,
+    workspace: &Option<PathBuf>,
+) -> Result<ToolExecution, String> {
+    let attachments = resolve_email_attachments(&parsed.attachments, &allowed_fs_roots(workspace))?;
+    // Refuse unknown accounts up front: never send from the wrong identity.
+    let accounts = himalaya_accounts()?;
+    let account = match resolve_email_account(parsed.account, db, workspace) {
+        Some(account) if !accounts.iter().any(|(name, _)| *name == account) => {
+            let names: Vec<&str> = accounts.iter().map(|(n, _)| n.as_str()).collect();
+            return Err(format!(
+                "unknown email account '{account}' (configured: {})",
+                if names.is_empty() { "none".to_string() } else { names.join(", ") }
+            ));
+        }
+        Some(account) => Some(account),
+        None => accounts.iter().find(|(_, d)| *d).map(|(n, _)| n.clone()),
+    };
+    let mut template = String::new();
+    if let Some(from) = parsed.from {
+        template.push_str(&format!("From: {from}\n"));
+    }
+    template.push_str(&format!("To: {}\n", parsed.to));
+    if let Some(cc) = parsed.cc {
+        template.push_str(&format!("Cc: {cc}\n"));
+    }
+    if let Some(bcc) = parsed.bcc {
+        template.push_str(&format!("Bcc: {bcc}\n"));
+    }
+    if let Some(in_reply_to) = parsed.in_reply_to {
+        template.push_str(&format!("In-Reply-To: {in_reply_to}\n"));
+    }
+    if let Some(references) = parsed.references {
+        template.push_str(&format!("References: {references}\n"));
+    }
+    template.push_str(&format!("Subject: {}\n", parsed.subject));
+    template.push('\n');
+    template.push_str(&parsed.body);
+    template.push('\n');
+    for file in &attachments {
+        template.push_str(&format!("<#part filename=\"{}\"><#/part>\n", file.display()));
+    }
+
+    let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+    cmd.arg("template").arg("send");
+    if let Some(account) = &account {
+        cmd.arg("--account").arg(account);
+    }
+    run_himalaya(&mut cmd, Some(template.as_bytes()))?;
+    let names: Vec<String> = attachments
+        .iter()
+        .map(|f| f.file_name().unwrap_or_default().to_string_lossy().into_owned())
+        .collect();
+    let attached = if names.is_empty() {
+        String::new()
+    } else {
+        format!(" with {} attachment(s): {}", names.len(), names.join(", "))
+    };
+    Ok(ToolExecution {
+        output: format!("Sent email (account: {}){attached}.", account_label(&account)),
+        details: serde_json::json!({ "status": "sent", "account": account, "attachments": names }),
+        is_error: false,
+    })
+}
+
+/// Headers at the top of `himalaya message read` output (or a raw message),
+/// keyed by lowercase name. Folded continuation lines are joined.
+fn parse_email_headers(text: &str) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = HashMap::new();
+    let mut last: Option<String> = None;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if headers.is_empty() {
+                continue;
+            }
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = last.as_ref().and_then(|key| headers.get_mut(key)) {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            break;
+        };
+        let key = name.trim().to_ascii_lowercase();
+        headers.insert(key.clone(), value.trim().to_string());
+        last = Some(key);
+    }
+    headers
+}
+
+/// Split an address list on commas outside quotes and angle brackets.
+fn split_addresses(list: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut angle) = (false, false);
+    for c in list.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                out.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    out.push(current);
+    out.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect()
+}
+
+/// Bare lowercase address of `Name <addr>` or `addr`.
+fn email_address(entry: &str) -> String {
+    let addr = match (entry.rfind('<'), entry.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &entry[start + 1..end],
+        _ => entry,
+    };
+    addr.trim().to_ascii_lowercase()
+}
+
+struct EmailReply {
+    to: String,
+    cc: Option<String>,
+    subject: String,
+    in_reply_to: Option<String>,
+    references: Option<String>,
+}
+
+/// Recipients, subject and threading headers for a reply to `original`.
+/// Reply-all copies the original To/Cc, minus the reply target and `own`.
+fn build_email_reply(
+    original: &HashMap<String, String>,
+    reply_all: bool,
+    own: Option<&str>,
+) -> Result<EmailReply, String> {
+    let to = original
+        .get("reply-to")
+        .or_else(|| original.get("from"))
+        .filter(|v| !v.is_empty())
+        .cloned()
+        .ok_or("email_reply: original message has no From or Reply-To header")?;
+    let subject = original.get("subject").map(String::as_str).unwrap_or("").trim();
+    let subject = if subject.to_ascii_lowercase().starts_with("re:") {
+        subject.to_string()
+    } else {
+        format!("Re: {subject}").trim_end().to_string()
+    };
+    let message_id = original.get("message-id").filter(|v| !v.is_empty()).cloned();
+    let references = match (original.get("references").filter(|v| !v.is_empty()), &message_id) {
+        (Some(refs), Some(id)) => Some(format!("{refs} {id}")),
+        (Some(refs), None) => Some(refs.clone()),
+        (None, id) => id.clone(),
+    };
+    let cc = if reply_all {
+        let mut skip: Vec<String> = split_addresses(&to).iter().map(|a| email_address(a)).collect();
+        skip.extend(own.map(email_address));
+        let mut cc = Vec::new();
+        for key in ["to", "cc"] {
+            for entry in original.get(key).map(|v| split_addresses(v)).unwrap_or_default() {
+                let address = email_address(&entry);
+                if !skip.contains(&address) {
+                    skip.push(address);
+                    cc.push(entry);
+                }
+            }
+        }
+        (!cc.is_empty()).then(|| cc.join(", "))
+    } else {
+        None
+    };
+    Ok(EmailReply {
+        to,
+        cc,
+        subject,
+        in_reply_to: message_id,
+        references,
+    })
+}
+
 /// Result from wait_for_child_monitored — owns the captured output.
 struct ChildResult {
     stdout: String,
     stderr: String,
+    /// Bytes the child actually wrote, before truncation.
+    stdout_bytes: u64,
+    stderr_bytes: u64,
+    truncated: bool,
     status: std::process::ExitStatus,
 }
 
+/// Default per-stream capture limit for `exec` (override with
+/// AETHERVAULT_EXEC_MAX_OUTPUT_BYTES; the `max_output_bytes` arg can only lower it).
+const DEFAULT_EXEC_MAX_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// Capture limit for one `exec` call: the model's `max_output_bytes` is
+/// clamped to the operator's cap so a tool call cannot lift it.
+fn exec_output_cap(requested: Option<usize>) -> usize {
+    let cap = env_usize("AETHERVAULT_EXEC_MAX_OUTPUT_BYTES", DEFAULT_EXEC_MAX_OUTPUT_BYTES)
+        .unwrap_or(DEFAULT_EXEC_MAX_OUTPUT_BYTES);
+    requested.map_or(cap, |bytes| bytes.min(cap))
+}
+
+/// Keeps the first `cap` bytes of a stream and counts the rest, so chatty
+/// children are drained without buffering everything they print.
+struct CappedOutput {
+    data: Vec<u8>,
+    total: u64,
+    cap: usize,
+}
+
+impl CappedOutput {
+    fn new(cap: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            total: 0,
+            cap,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len() as u64;
+        let room = self.cap.saturating_sub(self.data.len());
+        self.data.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    fn truncated(&self) -> bool {
+        self.total > self.data.len() as u64
+    }
+
+    fn text(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.data).to_string();
+        if self.truncated() {
+            text.push_str(&format!(
+                "\n[... output truncated: kept {} of {} bytes ...]",
+                self.data.len(),
+                self.total
+            ));
+        }
+        text
+    }
+}
+
+fn spawn_capped_reader<R: Read + Send + 'static>(
+    pipe: R,
+    buf: Arc<Mutex<CappedOutput>>,
+    activity: Arc<AtomicU64>,
+    t0: Instant,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut chunk = [0u8; 4096];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    activity.store(t0.elapsed().as_millis() as u64, Ordering::Release);
+                    if let Ok(mut guard) = buf.lock() {
+                        guard.push(&chunk[..n]);
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+}
+
 fn tail_last_chars(text: &str, max_chars: usize) -> &str {
     if max_chars == 0 {
         return "";
@@ -196,6 +623,7 @@ fn wait_for_child_monitored(
     label: &str,
     cancel_token: &Arc<AtomicBool>,
     policy: &ExecPolicy,
+    max_output_bytes: usize,
 ) -> Result<ChildResult, String> {
     let pid = child.id();
     let start = Instant::now();
@@ -206,54 +634,15 @@ fn wait_for_child_monitored(
 
     // Shared: milliseconds-since-start of last output activity
     let last_activity = Arc::new(AtomicU64::new(0));
-    // Shared output buffers
-    let stdout_buf: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
-    let stderr_buf: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+    // Shared output buffers, capped per stream
+    let stdout_buf = Arc::new(Mutex::new(CappedOutput::new(max_output_bytes)));
+    let stderr_buf = Arc::new(Mutex::new(CappedOutput::new(max_output_bytes)));
 
-    // Spawn reader thread for stdout
     if let Some(pipe) = stdout_pipe {
-        let buf = stdout_buf.clone();
-        let activity = last_activity.clone();
-        let t0 = start;
-        thread::spawn(move || {
-            let mut reader = BufReader::new(pipe);
-            let mut chunk = [0u8; 4096];
-            loop {
-                match reader.read(&mut chunk) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        activity.store(t0.elapsed().as_millis() as u64, Ordering::Release);
-                        if let Ok(mut guard) = buf.lock() {
-                            guard.extend_from_slice(&chunk[..n]);
-                        }
-                    }
-                    Err(_) => break,
-                }
-            }
-        });
+        spawn_capped_reader(pipe, stdout_buf.clone(), last_activity.clone(), start);
     }
-
-    // Spawn reader thread for stderr
     if let Some(pipe) = stderr_pipe {
-        let buf = stderr_buf.clone();
-        let activity = last_activity.clone();
-        let t0 = start;
-        thread::spawn(move || {
-            let mut reader = BufReader::new(pipe);
-            let mut chunk = [0u8; 4096];
-            loop {
-                match reader.read(&mut chunk) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        activity.store(t0.elapsed().as_millis() as u64, Ordering::Release);
-                        if let Ok(mut guard) = buf.lock() {
-                            guard.extend_from_slice(&chunk[..n]);
-                        }
-                    }
-                    Err(_) => break,
-                }
-            }
-        });
+        spawn_capped_reader(pipe, stderr_buf.clone(), last_activity.clone(), start);
     }
 
     let mut last_report = Instant::now();
@@ -269,15 +658,16 @@ fn wait_for_child_monitored(
             Ok(Some(status)) => {
                 // Give reader threads a moment to drain remaining pipe data
                 thread::sleep(Duration::from_millis(100));
-                let stdout = String::from_utf8_lossy(
-                    &stdout_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                )
-                .to_string();
-                let stderr = String::from_utf8_lossy(
-                    &stderr_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                )
-                .to_string();
-                return Ok(ChildResult { stdout, stderr, status });
+                let out = stdout_buf.lock().unwrap_or_else(|e| e.into_inner());
+                let err = stderr_buf.lock().unwrap_or_else(|e| e.into_inner());
+                return Ok(ChildResult {
+                    stdout: out.text(),
+                    stderr: err.text(),
+                    stdout_bytes: out.total,
+                    stderr_bytes: err.total,
+                    truncated: out.truncated() || err.truncated(),
+                    status,
+                });
             }
             Ok(None) => {
                 let now_ms = start.elapsed().as_millis() as u64;
@@ -288,20 +678,14 @@ fn wait_for_child_monitored(
                 if policy.hard_timeout_ms != EXEC_NO_TIMEOUT && now_ms >= policy.hard_timeout_ms {
                     let total_s = now_ms / 1000;
                     let hard = policy.hard_timeout_ms;
-                    eprintln!(
+                    log_warn!(
                         "[tool:{label}] pid={pid} timeout-killed: \
                          exceeded {hard}ms deadline (ran {total_s}s)"
                     );
                     crate::kill_process_tree(child);
                     thread::sleep(Duration::from_millis(100));
-                    let stdout = String::from_utf8_lossy(
-                        &stdout_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                    )
-                    .to_string();
-                    let stderr = String::from_utf8_lossy(
-                        &stderr_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                    )
-                    .to_string();
+                    let stdout = stdout_buf.lock().unwrap_or_else(|e| e.into_inner()).text();
+                    let stderr = stderr_buf.lock().unwrap_or_else(|e| e.into_inner()).text();
                     let stdout_tail = tail_last_chars(&stdout, 500);
                     let stderr_tail = tail_last_chars(&stderr, 500);
                     return Err(format!(
@@ -317,20 +701,14 @@ fn wait_for_child_monitored(
                 if policy.stale_threshold_ms != EXEC_NO_TIMEOUT && idle_ms >= policy.stale_threshold_ms {
                     let idle_min = idle_ms / 60_000;
                     let total_min = now_ms / 60_000;
-                    eprintln!(
+                    log_warn!(
                         "[tool:{label}] pid={pid} stale-killed: \
                          no output for {idle_min}m (total runtime {total_min}m)"
                     );
                     crate::kill_process_tree(child);
                     thread::sleep(Duration::from_millis(100));
-                    let stdout = String::from_utf8_lossy(
-                        &stdout_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                    )
-                    .to_string();
-                    let stderr = String::from_utf8_lossy(
-                        &stderr_buf.lock().unwrap_or_else(|e| e.into_inner()),
-                    )
-                    .to_string();
+                    let stdout = stdout_buf.lock().unwrap_or_else(|e| e.into_inner()).text();
+                    let stderr = stderr_buf.lock().unwrap_or_else(|e| e.into_inner()).text();
                     let stdout_tail = tail_last_chars(&stdout, 500);
                     let stderr_tail = tail_last_chars(&stderr, 500);
                     return Err(format!(
@@ -348,13 +726,13 @@ fn wait_for_child_monitored(
                     let idle_s = idle_ms / 1000;
                     let stdout_len = stdout_buf
                         .lock()
-                        .map(|g| g.len())
+                        .map(|g| g.total)
                         .unwrap_or(0);
                     let stderr_len = stderr_buf
                         .lock()
-                        .map(|g| g.len())
+                        .map(|g| g.total)
                         .unwrap_or(0);
-                    eprintln!(
+                    log_debug!(
                         "[tool:{label}] pid={pid} running {elapsed_s}s \
                          (idle {idle_s}s, stdout={stdout_len}B stderr={stderr_len}B, \
                          hard={}ms stale={}ms)",
@@ -377,40 +755,61 @@ fn wait_for_child_monitored(
 
 
 use crate::{
+    env_bool,
     env_optional,
+    env_usize,
+    http_with_retry,
+    HookHttpError,
+    HttpBody,
+    HttpRetry,
     kill_process_tree,
     load_approvals,
     save_approvals,
     approval_hash,
+    take_idempotency_key,
+    idempotent_replay,
+    remember_idempotent,
     requires_approval,
     scope_prefix,
     execute_query,
+    find_similar,
     build_context_pack,
     append_agent_log,
     append_feedback,
     save_config_to_file,
+    save_subagents_to_file,
     sync_workspace_memory,
     export_capsule_memory,
     load_triggers,
     save_triggers,
     allowed_fs_roots,
     resolve_fs_path,
-    tool_definitions_json,
     tool_score,
     parse_log_ts_from_uri,
     get_oauth_token,
     load_capsule_config,
+    save_config_entry,
     load_subagents_from_config,
     build_bridge_agent_config,
     run_agent_for_bridge,
     build_external_command,
+    external_bin,
+    scrub_command_env,
+    scale_provider_from_env,
     subprocess_exit_info,
     subprocess_output_text,
     blake3_hash,
+    DEFAULT_FAST_PATH_MARGIN,
+    DEFAULT_SEARCH_JOBS,
+    SNIPPET_ELLIPSIS,
     DEFAULT_WORKSPACE_DIR,
     ToolExecution,
     ApprovalEntry,
     TriggerEntry,
+    render_trigger_prompt,
+    evaluate_trigger,
+    resolve_timezone,
+    TriggerOutcome,
     CronExpr,
     AgentLogEntry,
     FeedbackEvent,
@@ -423,6 +822,7 @@ use crate::{
     ToolContextArgs,
     ToolSearchArgs,
     ToolGetArgs,
+    ToolSimilarArgs,
     ToolPutArgs,
     ToolLogArgs,
     ToolFeedbackArgs,
@@ -435,6 +835,7 @@ use crate::{
     ToolEmailListArgs,
     ToolEmailReadArgs,
     ToolEmailSendArgs,
+    ToolEmailReplyArgs,
     ToolEmailArchiveArgs,
     ToolExecArgs,
     ToolNotifyArgs,
@@ -448,13 +849,18 @@ use crate::{
     ToolFsWriteArgs,
     ToolTriggerAddArgs,
     ToolTriggerRemoveArgs,
+    ToolTriggerTestArgs,
     ToolToolSearchArgs,
     ToolSessionContextArgs,
     ToolReflectArgs,
+    ToolReflectionsListArgs,
+    ToolSkillRunArgs,
     ToolSkillStoreArgs,
     ToolSkillSearchArgs,
     ToolSubagentInvokeArgs,
     ToolSubagentBatchArgs,
+    ToolSubagentRegisterArgs,
+    ToolSubagentRemoveArgs,
     SubagentSpec,
     ToolGmailListArgs,
     ToolGmailReadArgs,
@@ -470,16 +876,113 @@ use crate::{
     open_skill_db,
     upsert_skill,
     search_skills,
+    load_skill_frames,
+    skill_slug,
+    skill_uri,
+    skill_prompt_text,
     SkillRecord,
     log_dir_path,
     load_session_logs,
     resolve_workspace,
+    lookup_frame,
+    FrameLookup,
+    format_frame_candidates,
     AgentConfig,
+    CapsuleConfig,
+    tool_registry,
+    is_tool_disabled,
+    approval_summary,
+    AethervaultError,
+    ToolContext,
+    Tool,
 };
 
 const EXEC_BACKGROUND_THRESHOLD_MS: u64 = 300_000;
 const DEFAULT_EXEC_BG_URL: &str = "http://127.0.0.1:8082";
 const DEFAULT_SUBAGENT_HOOK: &str = "codex-hook.sh";
+const DEFAULT_SUBAGENT_OUTPUT_CHARS: usize = 4000;
+const DEFAULT_SUBAGENT_MAX_DEPTH: usize = 2;
+const DEFAULT_SUBAGENT_MAX_CONCURRENT: usize = 4;
+
+thread_local! {
+    /// Subagent nesting depth of the agent running on this thread; `None` means
+    /// "not set here", falling back to `AETHERVAULT_SUBAGENT_DEPTH`.
+    static SUBAGENT_DEPTH: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+/// Depth of the agent on this thread (0 = top level). Processes started by a
+/// subagent's hook inherit it through `AETHERVAULT_SUBAGENT_DEPTH`.
+pub(crate) fn current_subagent_depth() -> usize {
+    SUBAGENT_DEPTH
+        .with(|d| d.get())
+        .unwrap_or_else(|| env_usize("AETHERVAULT_SUBAGENT_DEPTH", 0).unwrap_or(0))
+}
+
+pub(crate) fn set_subagent_depth(depth: usize) {
+    SUBAGENT_DEPTH.with(|d| d.set(Some(depth)));
+}
+
+thread_local! {
+    /// Session of the agent whose tools run on this thread, used to attribute subagents.
+    static AGENT_SESSION: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+static SUBAGENT_RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn set_agent_session(session: Option<String>) {
+    AGENT_SESSION.with(|s| *s.borrow_mut() = session);
+}
+
+/// `{unix_ts}-{short hash}`; unique per call even within the same second.
+fn new_subagent_run_id() -> String {
+    let now = Utc::now();
+    let seed = format!(
+        "{}:{}:{}",
+        std::process::id(),
+        now.timestamp_nanos_opt().unwrap_or_default(),
+        SUBAGENT_RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let hash = blake3_hash(seed.as_bytes()).to_hex();
+    format!("{}-{}", now.timestamp(), &hash[..8])
+}
+
+/// `[{parent}>]subagent:{name}:{run_id}[:{index}]`, so logs name their parent.
+fn subagent_session_id(name: &str, run_id: &str, index: Option<usize>) -> String {
+    let mut session = format!("subagent:{name}:{run_id}");
+    if let Some(index) = index {
+        session.push_str(&format!(":{index}"));
+    }
+    match AGENT_SESSION.with(|s| s.borrow().clone()) {
+        Some(parent) => format!("{parent}>{session}"),
+        None => session,
+    }
+}
+
+/// Depth a new subagent would run at, or an error once `max_depth` is reached.
+fn next_subagent_depth(config: &CapsuleConfig) -> Result<usize, String> {
+    let max_depth = config
+        .agent
+        .as_ref()
+        .and_then(|a| a.subagent_max_depth)
+        .unwrap_or(DEFAULT_SUBAGENT_MAX_DEPTH);
+    let depth = current_subagent_depth() + 1;
+    if depth > max_depth {
+        return Err(format!(
+            "subagent depth limit reached: a subagent at depth {} may not spawn more (agent.subagent_max_depth = {max_depth})",
+            depth - 1
+        ));
+    }
+    Ok(depth)
+}
+
+fn subagent_max_concurrent(config: &CapsuleConfig) -> usize {
+    config
+        .agent
+        .as_ref()
+        .and_then(|a| a.subagent_max_concurrent)
+        .unwrap_or(DEFAULT_SUBAGENT_MAX_CONCURRENT)
+        .max(1)
+}
 const DEFAULT_SUBAGENT_MAX_STEPS: usize = 64;
 const DEFAULT_SUBAGENT_TIMEOUT_SECS: u64 = 600;
 
@@ -513,11 +1016,15 @@ fn submit_exec_background_job(
     });
 
     let agent = make_http_agent(DEFAULT_HTTP_TIMEOUT_MS);
-    let response = agent
-        .post(&endpoint)
-        .set("content-type", "application/json")
-        .send_json(payload)
-        .map_err(|err| format!("background queue request failed: {err}"))?;
+    let response = http_with_retry(
+        "exec_bg",
+        HttpRetry::ConnectOnly,
+        TOOL_HTTP_ATTEMPTS,
+        TOOL_HTTP_RETRY_BUDGET,
+        HttpBody::Json(&payload),
+        || agent.post(&endpoint).set("content-type", "application/json"),
+    )
+    .map_err(|err| format!("background queue request failed: {err}"))?;
     let response_status = response.status();
     if response_status >= 300 {
         let body = response.into_string().unwrap_or_default();
@@ -532,6 +1039,80 @@ fn submit_exec_background_job(
         .map_err(|err| format!("invalid background queue response: {err}"))
 }
 
+/// `skill_search`: skills in `<workspace>/skills.sqlite` first, then the
+/// newest capsule skill frame per skill id.
+fn skill_search(db: &MemoryDb, workspace: &Path, parsed: ToolSkillSearchArgs) -> ToolExecution {
+    let db_path = workspace.join("skills.sqlite");
+    let limit = parsed.limit.unwrap_or(10);
+    let mut out = Vec::new();
+
+    if let Ok(conn) = open_skill_db(&db_path) {
+        let results = search_skills(&conn, &parsed.query, limit);
+        out.extend(results.into_iter().map(|s| {
+            serde_json::json!({
+                "name": s.name,
+                "trigger": s.trigger,
+                "steps": s.steps,
+                "tools": s.tools,
+                "notes": s.notes,
+                "success_rate": s.success_rate,
+                "times_used": s.times_used,
+                "last_used": s.last_used,
+            })
+        }));
+    }
+
+    if let Ok(response) = db.search(SearchRequest {
+        query: parsed.query.clone(),
+        top_k: limit,
+        snippet_chars: 200,
+        scope: Some("aethervault://skills/".to_string()),
+        temporal: None,
+        as_of_frame: None,
+        as_of_ts: None,
+    }) {
+        // Older skill_store calls wrote one URI per version; keep only the
+        // newest hit per skill id.
+        let mut seen = HashSet::new();
+        let mut hits = response.hits;
+        hits.sort_by_key(|h| std::cmp::Reverse(h.frame_id));
+        let mut latest = Vec::new();
+        for hit in hits {
+            let extra = db.frame_by_id(hit.frame_id).map(|f| f.extra_metadata).unwrap_or_default();
+            let skill_id = extra.get("skill_id").cloned().unwrap_or_else(|| {
+                let rest = hit.uri.trim_start_matches("aethervault://skills/");
+                rest.split('/').next().unwrap_or(rest).trim_end_matches(".json").to_string()
+            });
+            if !seen.insert(skill_id.clone()) {
+                continue;
+            }
+            let meta_u64 = |key: &str| extra.get(key).and_then(|v| v.parse::<u64>().ok());
+            latest.push(serde_json::json!({
+                "uri": hit.uri,
+                "title": hit.title,
+                "text": hit.text,
+                "score": hit.score,
+                "skill_id": skill_id,
+                "version": meta_u64("skill_version"),
+                "uses": meta_u64("skill_uses").unwrap_or(0)
+            }));
+        }
+        latest.sort_by(|a, b| {
+            b["score"].as_f64().partial_cmp(&a["score"].as_f64()).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        out.extend(latest);
+    }
+    if parsed.sort.as_deref() == Some("usage") {
+        let uses = |v: &serde_json::Value| v["uses"].as_u64().or(v["times_used"].as_u64()).unwrap_or(0);
+        out.sort_by_key(|v| std::cmp::Reverse(uses(v)));
+    }
+    ToolExecution {
+        output: format!("Found {} skills.", out.len()),
+        details: serde_json::json!({ "results": out }),
+        is_error: false,
+    }
+}
+
 pub(crate) fn execute_tool(
     name: &str,
     args: serde_json::Value,
@@ -539,27 +1120,53 @@ pub(crate) fn execute_tool(
     db: &MemoryDb,
     read_only: bool,
     bg_registry: Option<(i64, Arc<Mutex<BackgroundTaskRegistry>>)>,
-) -> Result<ToolExecution, String> {
-    let is_write = matches!(
-        name,
-        "put"
-            | "log"
-            | "feedback"
-            | "config_set"
-            | "memory_append_daily"
-            | "memory_remember"
-            | "trigger_add"
-            | "trigger_remove"
-            | "reflect"
-            | "skill_store"
-    );
-    if read_only && is_write {
-        return Err("tool disabled in read-only mode".into());
+) -> Result<ToolExecution, AethervaultError> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    execute_tool_with_cancel(name, args, mv2, db, read_only, bg_registry, &cancel)
+}
+
+/// Like [`execute_tool`], but `cancel` can stop it early: `exec`/`browser`
+/// kill their child, `subagent_batch` starts no further chunks.
+pub(crate) fn execute_tool_with_cancel(
+    name: &str,
+    args: serde_json::Value,
+    mv2: &Path,
+    db: &MemoryDb,
+    read_only: bool,
+    bg_registry: Option<(i64, Arc<Mutex<BackgroundTaskRegistry>>)>,
+    cancel: &Arc<AtomicBool>,
+) -> Result<ToolExecution, AethervaultError> {
+    if is_tool_disabled(name) {
+        return Err(AethervaultError::Denied("tool disabled".into()));
+    }
+    let registry = tool_registry();
+    let tool = registry.get(name);
+    if read_only && tool.as_ref().is_some_and(|t| t.is_write()) {
+        return Err(AethervaultError::Denied("tool disabled in read-only mode".into()));
+    }
+    let mut args = args;
+    let idempotency = match tool.as_ref() {
+        Some(t) if t.accepts_idempotency_key() => take_idempotency_key(&mut args)
+            .map(|key| (key, approval_hash(name, &args))),
+        _ => None,
+    };
+    if let Some((key, args_hash)) = &idempotency
+        && let Some(replay) = idempotent_replay(db, key, name, args_hash).map_err(AethervaultError::Validation)?
+    {
+        return Ok(replay);
     }
     let workspace_override = resolve_workspace(None, &AgentConfig::default());
+    if name == "exec" {
+        let command = args.get("command").and_then(|v| v.as_str()).unwrap_or_default();
+        if let Some(reason) = exec_policy_refusal(command, &load_agent_config(db, &workspace_override).exec_allow) {
+            return Err(AethervaultError::Denied(reason));
+        }
+    }
     if requires_approval(name, &args) {
         if read_only {
-            return Err("approval required but tool disabled in read-only mode".into());
+            return Err(AethervaultError::Denied(
+                "approval required but tool disabled in read-only mode".into(),
+            ));
         }
         let args_hash = approval_hash(name, &args);
         let mut approval_id: Option<String> = None;
@@ -572,7 +1179,7 @@ pub(crate) fn execute_tool(
             {
                 approval_id = Some(approvals[pos].id.clone());
                 approvals.remove(pos);
-                save_approvals(db, &approvals)?;
+                save_approvals(db, &approvals).map_err(AethervaultError::Store)?;
                 approved = true;
             } else if let Some(existing) = approvals
                 .iter()
@@ -589,18 +1196,22 @@ pub(crate) fn execute_tool(
                     args: args.clone(),
                     status: "pending".to_string(),
                     created_at: now,
+                    summary: approval_summary(name, &args),
+                    notified_at: None,
                 });
-                save_approvals(db, &approvals)?;
+                save_approvals(db, &approvals).map_err(AethervaultError::Store)?;
                 approval_id = Some(id);
             }
         }
         if !approved {
             let id = approval_id.clone().unwrap_or_else(|| "unknown".to_string());
+            let summary = approval_summary(name, &args);
             return Ok(ToolExecution {
-                output: format!("approval required: {id}\nReply `approve {id}` or `reject {id}`."),
+                output: format!("approval required: {id}\n{summary}\nReply `approve {id}` or `reject {id}`."),
                 details: serde_json::json!({
                     "approval_id": approval_id,
                     "tool": name,
+                    "summary": summary,
                     "args": args
                 }),
                 is_error: true,
@@ -608,2303 +1219,444 @@ pub(crate) fn execute_tool(
         }
     }
 
-    match name {
-        "query" => {
-            let parsed: ToolQueryArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let qargs = QueryArgs {
-                raw_query: parsed.query.clone(),
-                collection: parsed.collection,
-                limit: parsed.limit.unwrap_or(10),
-                snippet_chars: parsed.snippet_chars.unwrap_or(300),
-                no_expand: parsed.no_expand.unwrap_or(false),
-                max_expansions: parsed.max_expansions.unwrap_or(2),
-                expand_hook: None,
-                expand_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
-                no_vector: parsed.no_vector.unwrap_or(false),
-                rerank: parsed.rerank.unwrap_or_else(|| "local".to_string()),
-                rerank_hook: None,
-                rerank_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
-                rerank_hook_full_text: false,
-                embed_model: None,
-                embed_cache: 4096,
-                embed_no_cache: false,
-                rerank_docs: 40,
-                rerank_chunk_chars: 1200,
-                rerank_chunk_overlap: 200,
-                plan: false,
-                asof: parsed.asof,
-                before: parsed.before,
-                after: parsed.after,
-                feedback_weight: parsed.feedback_weight.unwrap_or(0.15),
-            };
-            let response = execute_query(db, qargs).map_err(|e| e.to_string())?;
-            let mut lines = Vec::new();
-            for r in response.results.iter().take(5) {
-                lines.push(format!("{}. {} ({:.3})", r.rank, r.uri, r.score));
-            }
-            let output = if lines.is_empty() {
-                "No results.".to_string()
-            } else {
-                lines.join("\n")
-            };
-            let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "context" => {
-            let parsed: ToolContextArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let qargs = QueryArgs {
-                raw_query: parsed.query.clone(),
-                collection: parsed.collection,
-                limit: parsed.limit.unwrap_or(10),
-                snippet_chars: parsed.snippet_chars.unwrap_or(300),
-                no_expand: parsed.no_expand.unwrap_or(false),
-                max_expansions: parsed.max_expansions.unwrap_or(2),
-                expand_hook: None,
-                expand_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
-                no_vector: parsed.no_vector.unwrap_or(false),
-                rerank: parsed.rerank.unwrap_or_else(|| "local".to_string()),
-                rerank_hook: None,
-                rerank_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
-                rerank_hook_full_text: false,
-                embed_model: None,
-                embed_cache: 4096,
-                embed_no_cache: false,
-                rerank_docs: parsed.limit.unwrap_or(10).max(20),
-                rerank_chunk_chars: 1200,
-                rerank_chunk_overlap: 200,
-                plan: false,
-                asof: parsed.asof,
-                before: parsed.before,
-                after: parsed.after,
-                feedback_weight: parsed.feedback_weight.unwrap_or(0.15),
-            };
-            let pack = build_context_pack(
-                db,
-                qargs,
-                parsed.max_bytes.unwrap_or(12_000),
-                parsed.full.unwrap_or(false),
-            )
-            .map_err(|e| e.to_string())?;
-            let output = pack.context.clone();
-            let details = serde_json::to_value(pack).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "search" => {
-            let parsed: ToolSearchArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let scope = parsed.collection.as_deref().map(scope_prefix);
-            let request = SearchRequest {
-                query: parsed.query.clone(),
-                top_k: parsed.limit.unwrap_or(10),
-                snippet_chars: parsed.snippet_chars.unwrap_or(300),
-                scope,
-                temporal: None,
-                as_of_frame: None,
-                as_of_ts: None,
-            };
-            let response = db.search(request).map_err(|e| e.to_string())?;
-            let mut lines = Vec::new();
-            for hit in response.hits.iter().take(5) {
-                let title = hit.title.clone().unwrap_or_default();
-                lines.push(format!("{}. {} {}", hit.rank, hit.uri, title));
-            }
-            let output = if lines.is_empty() {
-                "No results.".to_string()
-            } else {
-                lines.join("\n")
-            };
-            let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "get" => {
-            let parsed: ToolGetArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let (frame_id, frame) = if let Some(rest) = parsed.id.strip_prefix('#') {
-                let frame_id: u64 = rest.parse().map_err(|_| "invalid frame id")?;
-                let frame = db.frame_by_id(frame_id).map_err(|e| e.to_string())?;
-                (frame_id, frame)
-            } else {
-                let frame = db.frame_by_uri(&parsed.id).map_err(|e| e.to_string())?;
-                (frame.id, frame)
-            };
-            let text = db.frame_text_by_id(frame_id).unwrap_or_default();
-            let details = serde_json::json!({
-                "frame_id": frame_id,
-                "uri": frame.uri,
-                "title": frame.title,
-                "text": text
-            });
-            let output = if details["text"].as_str().unwrap_or("").is_empty() {
-                format!("Frame #{frame_id} (non-text payload)")
-            } else {
-                details["text"].as_str().unwrap_or("").to_string()
-            };
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "put" => {
-            let parsed: ToolPutArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let Some(text) = parsed.text else {
-                return Err("put requires text".into());
-            };
-            let mut options = PutOptions::default();
-            options.uri = Some(parsed.uri.clone());
-            options.title = Some(parsed.title.unwrap_or_else(|| parsed.uri.clone()));
-            options.track = parsed.track;
-            options.kind = parsed.kind;
-            options.search_text = Some(text.clone());
-
-            // Use consolidation only when URI is new (existing URI supersede handles dupes)
-            let uri_exists = db.frame_by_uri(&parsed.uri).is_ok();
-            let (frame_id, decision_str) = if uri_exists {
-                let fid = db
-                    .put_bytes_with_options(text.as_bytes(), options)
-                    .map_err(|e| e.to_string())?;
-                (fid, "supersede".to_string())
-            } else {
-                let result = put_with_consolidation(db, text.as_bytes(), options)
-                    .map_err(|e| e.to_string())?;
-                match result.decision {
-                    ConsolidationDecision::Noop { existing_id } => {
-                        db.commit().map_err(|e| e.to_string())?;
-                        let details = serde_json::json!({
-                            "frame_id": existing_id,
-                            "uri": parsed.uri,
-                            "decision": "noop"
-                        });
-                        return Ok(ToolExecution {
-                            output: format!("Deduplicated (similar to frame #{existing_id})"),
-                            details,
-                            is_error: false,
-                        });
-                    }
-                    _ => (
-                        result.frame_id.unwrap_or(0),
-                        format!("{:?}", result.decision),
-                    ),
-                }
-            };
-            db.commit().map_err(|e| e.to_string())?;
-            let details = serde_json::json!({
-                "frame_id": frame_id,
-                "uri": parsed.uri,
-                "decision": decision_str
-            });
-            let output = format!("Stored frame #{frame_id}");
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "log" => {
-            let parsed: ToolLogArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let entry = AgentLogEntry {
-                session: parsed.session.clone(),
-                role: parsed.role.unwrap_or_else(|| "user".to_string()),
-                text: parsed.text.clone(),
-                meta: parsed.meta.clone(),
-                ts_utc: Some(Utc::now().timestamp()),
-            };
-            let uri = append_agent_log(db, &entry).map_err(|e| e.to_string())?;
-            let details = serde_json::json!({ "uri": uri });
-            Ok(ToolExecution {
-                output: "Logged agent turn.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "feedback" => {
-            let parsed: ToolFeedbackArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let event = FeedbackEvent {
-                uri: parsed.uri.clone(),
-                score: parsed.score.clamp(-1.0, 1.0),
-                note: parsed.note.clone(),
-                session: parsed.session.clone(),
-                ts_utc: Some(Utc::now().timestamp()),
-            };
-            let uri_log = append_feedback(db, &event)?;
-            let details = serde_json::json!({ "uri": uri_log });
-            Ok(ToolExecution {
-                output: "Feedback recorded.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "config_set" => {
-            let parsed: ToolConfigSetArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            save_config_to_file(&workspace, &parsed.key, parsed.json.clone())
-                .map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output: format!("Config saved to file ({})", parsed.key),
-                details: serde_json::json!({ "file": workspace.join("config.json") }),
-                is_error: false,
-            })
-        }
-        "memory_sync" => {
-            let parsed: ToolMemorySyncArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = parsed
-                .workspace
-                .map(PathBuf::from)
-                .or_else(|| workspace_override.clone())
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let include_daily = parsed.include_daily.unwrap_or(true);
-            let ids =
-                sync_workspace_memory(mv2, &workspace, include_daily).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output: format!("Synced {} memory files.", ids.len()),
-                details: serde_json::json!({ "frame_ids": ids }),
-                is_error: false,
-            })
-        }
-        "memory_export" => {
-            let parsed: ToolMemoryExportArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = parsed
-                .workspace
-                .map(PathBuf::from)
-                .or_else(|| workspace_override.clone())
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let include_daily = parsed.include_daily.unwrap_or(true);
-            let paths =
-                export_capsule_memory(mv2, &workspace, include_daily).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output: format!("Exported {} files.", paths.len()),
-                details: serde_json::json!({ "paths": paths }),
-                is_error: false,
-            })
-        }
-        "memory_search" => {
-            let parsed: ToolMemorySearchArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let request = SearchRequest {
-                query: parsed.query.clone(),
-                top_k: parsed.limit.unwrap_or(10),
-                snippet_chars: 300,
-                scope: Some("aethervault://memory/".to_string()),
-                temporal: None,
-                as_of_frame: None,
-                as_of_ts: None,
-            };
-            let response = db.search(request).map_err(|e| e.to_string())?;
-            let mut lines = Vec::new();
-            for hit in response.hits.iter().take(5) {
-                let title = hit.title.clone().unwrap_or_default();
-                lines.push(format!("{}. {} {}", hit.rank, hit.uri, title));
-            }
-            let output = if lines.is_empty() {
-                "No results.".to_string()
-            } else {
-                lines.join("\n")
-            };
-            let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output,
-                details,
-                is_error: false,
-            })
-        }
-        "memory_append_daily" => {
-            let parsed: ToolMemoryAppendArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let date = parsed
-                .date
-                .unwrap_or_else(|| Utc::now().format("%Y-%m-%d").to_string());
-            let dir = workspace.join("memory");
-            fs::create_dir_all(&dir).map_err(|e| format!("workspace: {e}"))?;
-            let path = dir.join(format!("{date}.md"));
-            let mut file = fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&path)
-                .map_err(|e| format!("memory open: {e}"))?;
-            writeln!(file, "{}", parsed.text).map_err(|e| format!("memory write: {e}"))?;
-            let uri = format!("aethervault://memory/daily/{date}.md");
-            let mut options = PutOptions::default();
-            options.uri = Some(uri.clone());
-            options.title = Some(format!("memory daily {date}"));
-            options.kind = Some("text/markdown".to_string());
-            options.track = Some("aethervault.memory".to_string());
-            options.search_text = Some(parsed.text.clone());
-            let frame_id = db
-                .put_bytes_with_options(parsed.text.as_bytes(), options)
-                .map_err(|e| e.to_string())?;
-            db.commit().map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output: format!("Appended to {}", path.display()),
-                details: serde_json::json!({
-                    "path": path.display().to_string(),
-                    "uri": uri,
-                    "frame_id": frame_id
-                }),
-                is_error: false,
-            })
-        }
-        "memory_remember" => {
-            let parsed: ToolMemoryRememberArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            fs::create_dir_all(&workspace).map_err(|e| format!("workspace: {e}"))?;
-            let path = workspace.join("MEMORY.md");
-            let mut file = fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&path)
-                .map_err(|e| format!("memory open: {e}"))?;
-            writeln!(file, "{}", parsed.text).map_err(|e| format!("memory write: {e}"))?;
-            let uri = "aethervault://memory/longterm.md".to_string();
-            let mut options = PutOptions::default();
-            options.uri = Some(uri.clone());
-            options.title = Some("memory longterm".to_string());
-            options.kind = Some("text/markdown".to_string());
-            options.track = Some("aethervault.memory".to_string());
-            options.search_text = Some(parsed.text.clone());
-            let frame_id = db
-                .put_bytes_with_options(parsed.text.as_bytes(), options)
-                .map_err(|e| e.to_string())?;
-            db.commit().map_err(|e| e.to_string())?;
-            Ok(ToolExecution {
-                output: format!("Appended to {}", path.display()),
-                details: serde_json::json!({
-                    "path": path.display().to_string(),
-                    "uri": uri,
-                    "frame_id": frame_id
-                }),
-                is_error: false,
-            })
-        }
-        "email_list" => {
-            let parsed: ToolEmailListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut cmd = build_external_command("himalaya", &[]);
-            cmd.arg("envelope").arg("list").arg("--output").arg("json");
-            if let Some(limit) = parsed.limit {
-                cmd.arg("--limit").arg(limit.to_string());
-            }
-            if let Some(folder) = parsed.folder {
-                cmd.arg("--folder").arg(folder);
-            }
-            if let Some(account) = parsed.account {
-                cmd.arg("--account").arg(account);
-            }
-            let stdout = run_himalaya(&mut cmd, None)?;
-            let details = serde_json::from_str(&stdout)
-                .unwrap_or_else(|_| serde_json::json!({ "raw": stdout }));
-            Ok(ToolExecution {
-                output: "Listed envelopes.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "email_read" => {
-            let parsed: ToolEmailReadArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut cmd = build_external_command("himalaya", &[]);
-            cmd.arg("message")
-                .arg("read")
-                .arg(parsed.id)
-                .arg("--output")
-                .arg("json");
-            if let Some(folder) = parsed.folder {
-                cmd.arg("--folder").arg(folder);
-            }
-            if let Some(account) = parsed.account {
-                cmd.arg("--account").arg(account);
-            }
-            let stdout = run_himalaya(&mut cmd, None)?;
-            let details = serde_json::from_str(&stdout)
-                .unwrap_or_else(|_| serde_json::json!({ "raw": stdout }));
-            Ok(ToolExecution {
-                output: "Read message.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "email_send" => {
-            let parsed: ToolEmailSendArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut template = String::new();
-            if let Some(from) = parsed.from {
-                template.push_str(&format!("From: {from}\n"));
-            }
-            template.push_str(&format!("To: {}\n", parsed.to));
-            if let Some(cc) = parsed.cc {
-                template.push_str(&format!("Cc: {cc}\n"));
-            }
-            if let Some(bcc) = parsed.bcc {
-                template.push_str(&format!("Bcc: {bcc}\n"));
-            }
-            if let Some(in_reply_to) = parsed.in_reply_to {
-                template.push_str(&format!("In-Reply-To: {in_reply_to}\n"));
-            }
-            if let Some(references) = parsed.references {
-                template.push_str(&format!("References: {references}\n"));
-            }
-            template.push_str(&format!("Subject: {}\n", parsed.subject));
-            template.push('\n');
-            template.push_str(&parsed.body);
-            template.push('\n');
-
-            let mut cmd = build_external_command("himalaya", &[]);
-            cmd.arg("template").arg("send");
-            run_himalaya(&mut cmd, Some(template.as_bytes()))?;
-            Ok(ToolExecution {
-                output: "Sent email.".to_string(),
-                details: serde_json::json!({ "status": "sent" }),
-                is_error: false,
-            })
-        }
-        "email_archive" => {
-            let parsed: ToolEmailArchiveArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut cmd = build_external_command("himalaya", &[]);
-            cmd.arg("message").arg("move").arg(parsed.id).arg("Archive");
-            if let Some(folder) = parsed.folder {
-                cmd.arg("--folder").arg(folder);
-            }
-            if let Some(account) = parsed.account {
-                cmd.arg("--account").arg(account);
-            }
-            run_himalaya(&mut cmd, None)?;
-            Ok(ToolExecution {
-                output: "Archived email.".to_string(),
-                details: serde_json::json!({ "status": "archived" }),
-                is_error: false,
-            })
-        }
-        "exec" => {
-            let parsed: ToolExecArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let policy = match parsed.timeout_ms {
-                Some(ms) => ExecPolicy {
-                    hard_timeout_ms: ms,
-                    stale_threshold_ms: 180_000,  // default stale for explicit timeout
-                },
-                None => classify_exec_policy(&parsed.command),
-            };
-            let estimated_ms = parsed.estimated_ms.unwrap_or(policy.hard_timeout_ms);
-            let is_codex_session = parsed.command.to_ascii_lowercase().starts_with("codex ");
-            let should_background = parsed.background.unwrap_or(false)
-                || (is_codex_session && estimated_ms >= EXEC_BACKGROUND_THRESHOLD_MS);
-
-            // Codex-in-exec detection guardrail
-            let codex_warning = if parsed.command.contains("codex exec") || parsed.command.contains("codex --full-auto") {
-                Some("[ROUTING HINT: This command delegates to an LLM. Consider using subagent_invoke(name=\"<descriptive-name>\", prompt=\"...\") instead, which provides better timeout handling and memory access. Continuing with exec as requested.]\n\n")
-            } else {
-                None
-            };
-
-            if should_background {
-                let response = submit_exec_background_job(
-                    &parsed.command,
-                    parsed.cwd.as_ref(),
-                    policy.hard_timeout_ms,
-                    estimated_ms,
-                )?;
-                let job_id = response
-                    .get("id")
-                    .and_then(|value| value.as_str())
-                    .unwrap_or("unknown");
-                let status_url = response
-                    .get("status_url")
-                    .and_then(|value| value.as_str())
-                    .map(std::borrow::ToOwned::to_owned)
-                    .unwrap_or_else(|| format!("/jobs/{job_id}/status"));
-                let details = serde_json::json!({
-                    "background": true,
-                    "job_id": job_id,
-                    "status_url": status_url,
-                    "estimated_ms": estimated_ms,
-                    "timeout_ms": policy.hard_timeout_ms
-                });
-                let mut output = format!("background job started: {job_id}");
-                if let Some(warning) = codex_warning {
-                    output = format!("{warning}{output}");
-                }
-                return Ok(ToolExecution {
-                    output,
-                    details,
-                    is_error: false,
-                });
-            }
-
-            // SSH hardening: inject safety flags before spawning
-            let hardened_command = harden_ssh_in_command(&parsed.command);
+    let Some(tool) = tool else {
+        return Err(AethervaultError::NotFound("unknown tool".into()));
+    };
+    let ctx = ToolContext {
+        mv2,
+        db,
+        bg_registry,
+        cancel,
+    };
+    let result = tool.execute(&ctx, args).map_err(AethervaultError::Tool);
+    if let (Some((key, args_hash)), Ok(execution)) = (&idempotency, &result)
+        && !execution.is_error
+        && let Err(e) = remember_idempotent(db, key, name, args_hash, execution)
+    {
+        log_warn!("idempotency: could not record key {key:?}: {e}");
+    }
+    result
+}
 
-            let command = if cfg!(windows) {
-                vec!["cmd".to_string(), "/C".to_string(), hardened_command]
-            } else {
-                vec!["sh".to_string(), "-c".to_string(), hardened_command]
-            };
-            let mut cmd = build_external_command(&command[0], &command[1..]);
-            if let Some(cwd) = parsed.cwd {
-                cmd.current_dir(cwd);
-            }
-            cmd.stdin(Stdio::null())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            let mut child = cmd.spawn().map_err(|e| format!("exec spawn: {e}"))?;
-            let cancel_token = Arc::new(AtomicBool::new(false));
-            let result = wait_for_child_monitored(&mut child, "exec", &cancel_token, &policy)?;
-            let stdout = result.stdout;
-            let stderr = result.stderr;
-            let is_error = !result.status.success();
-            let exit_code = subprocess_exit_info(&result.status);
-            let details = serde_json::json!({
-                "exit_code": exit_code,
-                "stdout": stdout,
-                "stderr": stderr
-            });
-            let mut output_text = subprocess_output_text(&stdout, &stderr, is_error);
-            if let Some(warning) = codex_warning {
-                output_text = format!("{warning}{output_text}");
-            }
-            Ok(ToolExecution {
-                output: output_text,
-                details,
-                is_error,
-            })
-        }
-        "notify" => {
-            let parsed: ToolNotifyArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let channel = parsed
-                .channel
-                .unwrap_or_else(|| "slack".to_string())
-                .to_ascii_lowercase();
-            let webhook = parsed.webhook.or_else(|| match channel.as_str() {
-                "discord" => env_optional("DISCORD_WEBHOOK_URL"),
-                "teams" => env_optional("TEAMS_WEBHOOK_URL"),
-                _ => env_optional("SLACK_WEBHOOK_URL"),
-            });
-            let Some(webhook) = webhook else {
-                return Err("notify requires webhook url".into());
-            };
-            let payload = match channel.as_str() {
-                "discord" => serde_json::json!({ "content": parsed.text }),
-                "teams" => serde_json::json!({ "text": parsed.text }),
-                _ => serde_json::json!({ "text": parsed.text }),
-            };
-            let agent = make_http_agent(DEFAULT_HTTP_TIMEOUT_MS);
-            let response = agent
-                .post(&webhook)
-                .set("content-type", "application/json")
-                .send_json(payload);
-            match response {
-                Ok(_) => Ok(ToolExecution {
-                    output: "Notification sent.".to_string(),
-                    details: serde_json::json!({ "channel": channel }),
-                    is_error: false,
-                }),
-                Err(err) => Err(format!("notify error: {err}")),
-            }
-        }
-        "signal_send" => {
-            let parsed: ToolSignalSendArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let sender = parsed.sender.or_else(|| env_optional("SIGNAL_SENDER"));
-            let Some(sender) = sender else {
-                return Err("signal_send requires sender".into());
-            };
-            let mut cmd = build_external_command("signal-cli", &[]);
-            cmd.arg("-u")
-                .arg(sender)
-                .arg("send")
-                .arg("-m")
-                .arg(parsed.text)
-                .arg(parsed.to);
-            let output = cmd.output().map_err(|e| format!("signal-cli: {e}"))?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                return Err(format!("signal-cli error: {stderr}"));
-            }
-            Ok(ToolExecution {
-                output: "Signal message sent.".to_string(),
-                details: serde_json::json!({ "status": "sent" }),
-                is_error: false,
-            })
-        }
-        "imessage_send" => {
-            let parsed: ToolIMessageSendArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            if !cfg!(target_os = "macos") {
-                return Err("imessage_send requires macOS".into());
-            }
-            let script = format!(
-                "tell application \"Messages\" to send \"{}\" to buddy \"{}\"",
-                parsed.text.replace('"', "\\\""),
-                parsed.to.replace('"', "\\\"")
-            );
-            let mut cmd = build_external_command("osascript", &[]);
-            cmd.arg("-e").arg(script);
-            let output = cmd.output().map_err(|e| format!("osascript: {e}"))?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                return Err(format!("osascript error: {stderr}"));
-            }
-            Ok(ToolExecution {
-                output: "iMessage sent.".to_string(),
-                details: serde_json::json!({ "status": "sent" }),
-                is_error: false,
-            })
-        }
-        "http_request" => {
-            let parsed: ToolHttpRequestArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let method = parsed
-                .method
-                .unwrap_or_else(|| "GET".to_string())
-                .to_ascii_uppercase();
-            let timeout = parsed.timeout_ms.unwrap_or(DEFAULT_HTTP_TIMEOUT_MS);
-            let agent = make_http_agent(timeout);
-            let mut req = match method.as_str() {
-                "GET" => agent.get(&parsed.url),
-                "POST" => agent.post(&parsed.url),
-                "PUT" => agent.put(&parsed.url),
-                "PATCH" => agent.patch(&parsed.url),
-                "DELETE" => agent.delete(&parsed.url),
-                _ => return Err(format!("unsupported method: {method}")),
-            };
-            if let Some(headers) = parsed.headers {
-                for (k, v) in headers {
-                    req = req.set(&k, &v);
-                }
-            }
-            let resp = if let Some(body) = parsed.body {
-                if parsed.json.unwrap_or(false) {
-                    req.set("content-type", "application/json")
-                        .send_string(&body)
-                } else {
-                    req.send_string(&body)
-                }
-            } else {
-                req.call()
-            };
-            let (status, text) = match resp {
-                Ok(resp) => {
-                    let status = resp.status();
-                    let text = resp.into_string().unwrap_or_default();
-                    (status, text)
-                }
-                Err(ureq::Error::Status(code, resp)) => {
-                    let text = resp.into_string().unwrap_or_default();
-                    (code, text)
-                }
-                Err(err) => return Err(format!("http_request failed: {err}")),
-            };
-            let truncated = if text.len() > 20_000 {
-                let safe: String = text.chars().take(20_000).collect();
-                format!("{safe}...[truncated]")
-            } else {
-                text
-            };
-            Ok(ToolExecution {
-                output: format!("http_request {method} {} -> {status}", parsed.url),
-                details: serde_json::json!({
-                    "status": status,
-                    "body": truncated
-                }),
-                is_error: status >= 400,
-            })
-        }
-        "browser" => {
-            let parsed: ToolBrowserArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let browser_timeout_ms = parsed.timeout_ms.unwrap_or(DEFAULT_HTTP_TIMEOUT_MS);
-            let session = parsed.session.unwrap_or_else(|| "default".to_string());
-
-            let mut cmd_args: Vec<String> = vec!["--session".to_string(), session];
-            let parts = shlex::split(&parsed.command)
-                .ok_or_else(|| "browser: malformed command (unmatched quotes)".to_string())?;
-            if parts.is_empty() {
-                return Err("browser: command is empty".into());
-            }
-            cmd_args.extend(parts);
-
-            let mut cmd = build_external_command("agent-browser", &cmd_args);
-            cmd.stdin(Stdio::null())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            let mut child = cmd.spawn().map_err(|e| format!("browser spawn: {e}"))?;
-            let cancel_token = Arc::new(AtomicBool::new(false));
-            let browser_policy = ExecPolicy {
-                hard_timeout_ms: browser_timeout_ms,
-                stale_threshold_ms: 180_000,  // 3 min stale for browser
-            };
-            let result = wait_for_child_monitored(&mut child, "browser", &cancel_token, &browser_policy)?;
-            let stdout = result.stdout;
-            let stderr = result.stderr;
-            let is_error = !result.status.success();
-            let exit_code = subprocess_exit_info(&result.status);
-            let details = serde_json::json!({
-                "stdout": stdout,
-                "stderr": stderr,
-                "exit_code": exit_code
-            });
-            let output_text = subprocess_output_text(&stdout, &stderr, is_error);
+/// The built-in tools, in catalog order. Each lives in the module for its area
+/// with its schema and policy; a new tool is one impl there plus an entry here.
+pub(crate) fn builtin_tools() -> Vec<Arc<dyn Tool>> {
+    vec![
+        Arc::new(memory::QueryTool),
+        Arc::new(memory::ContextTool),
+        Arc::new(memory::SearchTool),
+        Arc::new(memory::GetTool),
+        Arc::new(memory::SimilarTool),
+        Arc::new(memory::PutTool),
+        Arc::new(memory::LogTool),
+        Arc::new(memory::FeedbackTool),
+        Arc::new(memory::ConfigSetTool),
+        Arc::new(memory::MemoryAppendDailyTool),
+        Arc::new(memory::MemoryRememberTool),
+        Arc::new(memory::MemorySyncTool),
+        Arc::new(memory::MemoryExportTool),
+        Arc::new(memory::MemorySearchTool),
+        Arc::new(email::EmailAccountsTool),
+        Arc::new(email::EmailListTool),
+        Arc::new(email::EmailReadTool),
+        Arc::new(email::EmailSendTool),
+        Arc::new(email::EmailReplyTool),
+        Arc::new(email::EmailArchiveTool),
+        Arc::new(exec::ExecTool),
+        Arc::new(messaging::NotifyTool),
+        Arc::new(messaging::SignalSendTool),
+        Arc::new(messaging::ImessageSendTool),
+        Arc::new(web::HttpRequestTool),
+        Arc::new(web::BrowserTool),
+        Arc::new(web::ExcalidrawTool),
+        Arc::new(files::FsListTool),
+        Arc::new(files::FsReadTool),
+        Arc::new(files::FsWriteTool),
+        Arc::new(triggers::ApprovalListTool),
+        Arc::new(triggers::TriggerAddTool),
+        Arc::new(triggers::TriggerListTool),
+        Arc::new(triggers::TriggerRemoveTool),
+        Arc::new(triggers::TriggerTestTool),
+        Arc::new(session::ToolSearchTool),
+        Arc::new(session::SessionContextTool),
+        Arc::new(skills::ReflectTool),
+        Arc::new(skills::ReflectionsListTool),
+        Arc::new(skills::SkillStoreTool),
+        Arc::new(skills::SkillSearchTool),
+        Arc::new(skills::SkillRunTool),
+        Arc::new(subagents::SubagentListTool),
+        Arc::new(subagents::SubagentRegisterTool),
+        Arc::new(subagents::SubagentRemoveTool),
+        Arc::new(subagents::SubagentInvokeTool),
+        Arc::new(subagents::SubagentBatchTool),
+        Arc::new(google::GmailListTool),
+        Arc::new(google::GmailReadTool),
+        Arc::new(google::GmailSendTool),
+        Arc::new(google::GcalListTool),
+        Arc::new(google::GcalCreateTool),
+        Arc::new(microsoft::MsMailListTool),
+        Arc::new(microsoft::MsMailReadTool),
+        Arc::new(microsoft::MsCalendarListTool),
+        Arc::new(microsoft::MsCalendarCreateTool),
+        Arc::new(ops::ScaleTool),
+        Arc::new(ops::BgStatusTool),
+        Arc::new(ops::SelfUpgradeTool),
+    ]
+}
 
-            Ok(ToolExecution {
-                output: output_text,
-                details,
-                is_error,
-            })
-        }
-        "excalidraw" => {
-            let parsed: ToolExcalidrawArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-
-            let tool_name = match parsed.action.as_str() {
-                "read_me" => "read_me",
-                "create_view" => "create_view",
-                _ => return Err(format!("excalidraw: unknown action '{}', use 'read_me' or 'create_view'", parsed.action)),
-            };
-            let tool_args = if tool_name == "create_view" {
-                let elements = parsed.elements
-                    .ok_or("excalidraw: 'elements' required for create_view")?;
-                serde_json::json!({ "elements": elements })
-            } else {
-                serde_json::json!({})
-            };
-
-            // Spawn excalidraw-mcp server via stdio
-            let mcp_cmd = env_optional("EXCALIDRAW_MCP_CMD")
-                .unwrap_or_else(|| "npx excalidraw-mcp --stdio".to_string());
-            let cmd_parts = shlex::split(&mcp_cmd)
-                .ok_or("excalidraw: malformed EXCALIDRAW_MCP_CMD")?;
-            if cmd_parts.is_empty() {
-                return Err("excalidraw: empty EXCALIDRAW_MCP_CMD".into());
-            }
-            let mut cmd = build_external_command(&cmd_parts[0], &cmd_parts[1..]);
-            cmd.stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            let mut child = cmd.spawn().map_err(|e| format!("excalidraw spawn: {e}"))?;
-            let mut stdin = child.stdin.take().ok_or("excalidraw: no stdin")?;
-            let stdout = child.stdout.take().ok_or("excalidraw: no stdout")?;
-
-            // Run MCP interaction in a thread with cancellation-aware polling.
-            // read_line can't hang the caller forever.  The closure also
-            // guarantees cleanup (kill + wait) on both success and error paths.
-            let tool_name = tool_name.to_string();
-            let (tx, rx) = mpsc::channel();
-            thread::spawn(move || {
-                let mut reader = BufReader::new(stdout);
-
-                // Helper: send JSON-RPC message with Content-Length framing
-                let send_msg = |writer: &mut dyn Write, msg: &serde_json::Value| -> Result<(), String> {
-                    let body = serde_json::to_string(msg).map_err(|e| e.to_string())?;
-                    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)
-                        .map_err(|e| format!("excalidraw write: {e}"))?;
-                    writer.flush().map_err(|e| format!("excalidraw flush: {e}"))?;
-                    Ok(())
-                };
-
-                // Helper: read JSON-RPC response with Content-Length framing.
-                // Reads headers until blank line, extracts Content-Length, then reads body.
-                let read_msg = |reader: &mut BufReader<std::process::ChildStdout>| -> Result<serde_json::Value, String> {
-                    let mut content_length: Option<usize> = None;
-                    // Read headers until blank separator line
-                    loop {
-                        let mut line = String::new();
-                        reader.read_line(&mut line).map_err(|e| format!("excalidraw read: {e}"))?;
-                        let trimmed = line.trim();
-                        if trimmed.is_empty() {
-                            if content_length.is_some() { break; }
-                            continue; // skip leading blank lines before headers
-                        }
-                        if let Some(len_str) = trimmed.strip_prefix("Content-Length:") {
-                            content_length = Some(len_str.trim().parse()
-                                .map_err(|e| format!("excalidraw bad content-length: {e}"))?);
-                        }
-                        // ignore other headers (Content-Type, etc.)
-                    }
-                    let len = content_length.ok_or("excalidraw: missing Content-Length header")?;
-                    if len > 10 * 1024 * 1024 {
-                        return Err(format!("excalidraw: response too large ({len} bytes)"));
-                    }
-                    let mut body = vec![0u8; len];
-                    io::Read::read_exact(reader, &mut body)
-                        .map_err(|e| format!("excalidraw read body: {e}"))?;
-                    serde_json::from_slice(&body).map_err(|e| format!("excalidraw parse: {e}"))
-                };
-
-                let result = (|| -> Result<serde_json::Value, String> {
-                    // 1. Send initialize
-                    send_msg(&mut stdin, &serde_json::json!({
-                        "jsonrpc": "2.0", "id": 1, "method": "initialize",
-                        "params": {
-                            "protocolVersion": "2024-11-05",
-                            "capabilities": {},
-                            "clientInfo": { "name": "aethervault", "version": "0.1" }
-                        }
-                    }))?;
-                    let init_resp = read_msg(&mut reader)?;
-                    if let Some(err) = init_resp.get("error") {
-                        let msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown");
-                        return Err(format!("excalidraw: MCP initialize failed: {msg}"));
-                    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify_pending_approvals;
+
+    #[test]
+    fn himalaya_account_list_is_parsed() {
+        let stdout = r#"[{"name":"work","backend":"IMAP","default":true},{"name":"personal","backend":"IMAP"}]"#;
+        assert_eq!(
+            parse_himalaya_accounts(stdout),
+            vec![("work".to_string(), true), ("personal".to_string(), false)]
+        );
+        assert_eq!(parse_himalaya_accounts(r#"["solo"]"#), vec![("solo".to_string(), false)]);
+        assert!(parse_himalaya_accounts("not json").is_empty());
+    }
 
-                    // 2. Send initialized notification
-                    send_msg(&mut stdin, &serde_json::json!({
-                        "jsonrpc": "2.0", "method": "notifications/initialized"
-                    }))?;
-
-                    // 3. Call the tool
-                    send_msg(&mut stdin, &serde_json::json!({
-                        "jsonrpc": "2.0", "id": 2, "method": "tools/call",
-                        "params": { "name": tool_name, "arguments": tool_args }
-                    }))?;
-                    read_msg(&mut reader)
-                })();
-
-                // Cleanup always runs regardless of success/failure
-                drop(stdin);
-                let _ = tx.send(result);
-            });
+    #[test]
+    fn email_reply_threads_and_picks_recipients() {
+        let original = parse_email_headers(
+            "Message-ID: <m2@example.com>\nReferences: <m1@example.com>\nFrom: \"Lee, Ana\" <ana@example.com>\n\
+             To: me@example.com, Bo <bo@example.com>\nCc: ana@example.com, cy@example.com\nSubject: Budget\n\nBody: text\n",
+        );
+        assert_eq!(original["from"], "\"Lee, Ana\" <ana@example.com>");
+        assert!(!original.contains_key("body"));
+
+        let reply = build_email_reply(&original, false, None).unwrap();
+        assert_eq!(reply.to, "\"Lee, Ana\" <ana@example.com>");
+        assert_eq!(reply.subject, "Re: Budget");
+        assert_eq!(reply.in_reply_to.as_deref(), Some("<m2@example.com>"));
+        assert_eq!(reply.references.as_deref(), Some("<m1@example.com> <m2@example.com>"));
+        assert!(reply.cc.is_none());
+
+        let all = build_email_reply(&original, true, Some("Me <ME@example.com>")).unwrap();
+        assert_eq!(all.cc.as_deref(), Some("Bo <bo@example.com>, cy@example.com"));
+
+        let mut again = original.clone();
+        again.insert("subject".into(), "RE: Budget".into());
+        again.insert("reply-to".into(), "list@example.com".into());
+        let reply = build_email_reply(&again, false, None).unwrap();
+        assert_eq!((reply.to.as_str(), reply.subject.as_str()), ("list@example.com", "RE: Budget"));
+        assert!(build_email_reply(&HashMap::new(), false, None).is_err());
+    }
 
-            let cancel_token = Arc::new(AtomicBool::new(false));
-            let mut last_update = Instant::now();
-            let tool_resp: serde_json::Value = loop {
-                if cancel_token.load(Ordering::Acquire) {
-                    kill_process_tree(&mut child);
-                    return Err("excalidraw: canceled while waiting for MCP response".into());
-                }
-                match rx.recv_timeout(Duration::from_millis(PROCESS_POLL_MS)) {
-                    Ok(result) => {
-                        // Thread completed; child will exit after stdin is dropped
-                        kill_process_tree(&mut child);
-                        break result?;
-                    }
-                    Err(mpsc::RecvTimeoutError::Timeout) => {
-                        if last_update.elapsed() >= Duration::from_millis(STATUS_REPORT_MS) {
-                            eprintln!("[tool:excalidraw] waiting for MCP response (no deadline)");
-                            last_update = Instant::now();
-                        }
-                        continue;
-                    }
-                    Err(mpsc::RecvTimeoutError::Disconnected) => {
-                        kill_process_tree(&mut child);
-                        return Err("excalidraw: MCP worker channel disconnected".into());
-                    }
-                }
-            };
+    #[test]
+    fn email_attachments_stay_in_roots() {
+        let root = std::env::temp_dir().join(format!("aethervault_attach_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("reports")).unwrap();
+        fs::write(root.join("reports/q3.csv"), "a,b\n").unwrap();
+        let roots = vec![root.clone()];
+
+        let files = resolve_email_attachments(&["reports/q3.csv".to_string()], &roots).unwrap();
+        assert!(files[0].ends_with("reports/q3.csv") && files[0].is_absolute());
+        assert!(resolve_email_attachments(&["reports".to_string()], &roots).unwrap_err().contains("not a file"));
+        assert!(resolve_email_attachments(&["missing.pdf".to_string()], &roots).is_err());
+        assert!(resolve_email_attachments(&["/etc/hostname".to_string()], &roots).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
 
-            // Check for JSON-RPC error
-            if let Some(err) = tool_resp.get("error") {
-                let msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown");
-                let code = err.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
-                return Err(format!("excalidraw: MCP error {code}: {msg}"));
-            }
-            let result = tool_resp.get("result")
-                .cloned()
-                .ok_or("excalidraw: MCP response missing 'result' field")?;
-            let content_text = result.get("content")
-                .and_then(|c| c.as_array())
-                .and_then(|arr| arr.first())
-                .and_then(|item| item.get("text"))
-                .and_then(|t| t.as_str())
-                .unwrap_or("");
-
-            let is_error = result.get("isError").and_then(|v| v.as_bool()).unwrap_or(false);
-            Ok(ToolExecution {
-                output: content_text.to_string(),
-                details: result,
-                is_error,
-            })
+    #[test]
+    fn memory_search_scopes_select_prefixes() {
+        let path = std::env::temp_dir().join(format!("aethervault_memscope_{}.mv2", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for uri in [
+            "aethervault://memory/notes/tide.md",
+            "aethervault://memory/reflection/s1/tide.json",
+            "aethervault://skills/tide/1",
+        ] {
+            let options = PutOptions { uri: Some(uri.into()), ..Default::default() };
+            db.put_bytes_with_options(b"tide tables for the harbor", options).unwrap();
         }
-        "fs_list" => {
-            let parsed: ToolFsListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let roots = allowed_fs_roots(&workspace_override);
-            let resolved = resolve_fs_path(&parsed.path, &roots)?;
-            let mut items = Vec::new();
-            let max_entries = parsed.max_entries.unwrap_or(200);
-            if parsed.recursive.unwrap_or(false) {
-                for entry in WalkDir::new(&resolved).max_depth(6) {
-                    let entry = entry.map_err(|e| e.to_string())?;
-                    if items.len() >= max_entries {
-                        break;
-                    }
-                    items.push(entry.path().display().to_string());
-                }
-            } else if resolved.is_dir() {
-                for entry in fs::read_dir(&resolved).map_err(|e| e.to_string())? {
-                    let entry = entry.map_err(|e| e.to_string())?;
-                    items.push(entry.path().display().to_string());
-                    if items.len() >= max_entries {
-                        break;
-                    }
-                }
-            } else if resolved.exists() {
-                items.push(resolved.display().to_string());
-            }
-            Ok(ToolExecution {
-                output: format!("Listed {} entries.", items.len()),
-                details: serde_json::json!({ "entries": items }),
-                is_error: false,
-            })
-        }
-        "fs_read" => {
-            let parsed: ToolFsReadArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let roots = allowed_fs_roots(&workspace_override);
-            let resolved = resolve_fs_path(&parsed.path, &roots)?;
-            let max_bytes = parsed.max_bytes.unwrap_or(200_000);
-            let file = fs::File::open(&resolved).map_err(|e| e.to_string())?;
-            let mut buf = Vec::new();
-            file.take(max_bytes as u64)
-                .read_to_end(&mut buf)
-                .map_err(|e| e.to_string())?;
-            let text = String::from_utf8_lossy(&buf).to_string();
-            Ok(ToolExecution {
-                output: format!("Read {} bytes.", buf.len()),
-                details: serde_json::json!({
-                    "path": resolved.display().to_string(),
-                    "text": text
-                }),
-                is_error: false,
-            })
-        }
-        "fs_write" => {
-            let parsed: ToolFsWriteArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let roots = allowed_fs_roots(&workspace_override);
-            let resolved = resolve_fs_path(&parsed.path, &roots)?;
-            if parsed.append.unwrap_or(false) {
-                let mut file = fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&resolved)
-                    .map_err(|e| e.to_string())?;
-                file.write_all(parsed.text.as_bytes())
-                    .map_err(|e| e.to_string())?;
-            } else {
-                fs::write(&resolved, parsed.text.as_bytes()).map_err(|e| e.to_string())?;
-            }
-            Ok(ToolExecution {
-                output: "File written.".to_string(),
-                details: serde_json::json!({ "path": resolved.display().to_string() }),
-                is_error: false,
-            })
-        }
-        "approval_list" => {
-            let approvals = load_approvals(db);
-            let pending: Vec<ApprovalEntry> = approvals
-                .into_iter()
-                .filter(|a| a.status == "pending")
-                .collect();
-            Ok(ToolExecution {
-                output: format!("{} pending approvals.", pending.len()),
-                details: serde_json::json!({ "approvals": pending }),
-                is_error: false,
-            })
-        }
-        "trigger_add" => {
-            let parsed: ToolTriggerAddArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut triggers = load_triggers(db);
-            let id = format!(
-                "trg_{}_{}",
-                chrono::Utc::now().timestamp(),
-                triggers.len() + 1
-            );
-            // Validate kind-specific required fields
-            match parsed.kind.as_str() {
-                "cron" => {
-                    if parsed.cron.is_none() {
-                        return Err("kind=cron requires a 'cron' expression".into());
-                    }
-                }
-                "webhook" => {
-                    if parsed.webhook_url.is_none() {
-                        return Err("kind=webhook requires a 'webhook_url'".into());
-                    }
-                }
-                "email" | "calendar_free" => {}
-                other => {
-                    return Err(format!("Unknown trigger kind: '{other}'"));
-                }
-            }
-            // Validate cron expression if provided
-            if let Some(ref cron_str) = parsed.cron {
-                if let Err(e) = CronExpr::parse(cron_str) {
-                    return Err(format!("Invalid cron expression: {e}"));
-                }
-            }
-            // Validate webhook URL (SSRF protection)
-            if let Some(ref url) = parsed.webhook_url {
-                if !url.starts_with("https://") && !url.starts_with("http://") {
-                    return Err("webhook_url must use http:// or https://".into());
-                }
-                let lower = url.to_lowercase();
-                if lower.contains("localhost") || lower.contains("127.0.0.1")
-                    || lower.contains("[::1]") || lower.contains("169.254.169.254")
-                    || lower.contains("10.0.") || lower.contains("192.168.") {
-                    return Err("webhook_url cannot target private/internal addresses".into());
-                }
-            }
-            // Validate webhook method
-            if let Some(ref m) = parsed.webhook_method {
-                let upper = m.to_uppercase();
-                if upper != "GET" && upper != "POST" {
-                    return Err(format!("webhook_method must be GET or POST, got '{m}'"));
-                }
-            }
-            let entry = TriggerEntry {
-                id: id.clone(),
-                kind: parsed.kind,
-                name: parsed.name,
-                query: parsed.query,
-                prompt: parsed.prompt,
-                start: parsed.start,
-                end: parsed.end,
-                enabled: parsed.enabled.unwrap_or(true),
-                last_seen: None,
-                last_fired: None,
-                cron: parsed.cron,
-                webhook_url: parsed.webhook_url,
-                webhook_method: parsed.webhook_method,
-                schedule_name: None,
-            };
-            triggers.push(entry);
-            save_triggers(db, &triggers)?;
-            Ok(ToolExecution {
-                output: "Trigger added.".to_string(),
-                details: serde_json::json!({ "id": id }),
-                is_error: false,
-            })
-        }
-        "trigger_list" => {
-            let triggers = load_triggers(db);
-            Ok(ToolExecution {
-                output: format!("{} triggers.", triggers.len()),
-                details: serde_json::json!({ "triggers": triggers }),
-                is_error: false,
-            })
-        }
-        "trigger_remove" => {
-            let parsed: ToolTriggerRemoveArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut triggers = load_triggers(db);
-            let before = triggers.len();
-            triggers.retain(|t| t.id != parsed.id);
-            let updated = triggers.len() != before;
-            if updated {
-                save_triggers(db, &triggers)?;
-            }
-            Ok(ToolExecution {
-                output: if updated {
-                    "Trigger removed.".to_string()
-                } else {
-                    "Trigger not found.".to_string()
-                },
-                details: serde_json::json!({ "id": parsed.id, "updated": updated }),
-                is_error: !updated,
-            })
-        }
-        "tool_search" => {
-            let parsed: ToolToolSearchArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let query_tokens: Vec<String> = parsed
-                .query
-                .to_ascii_lowercase()
-                .split_whitespace()
-                .map(|s| s.to_string())
+        let uris = |scope: &str| -> Vec<String> {
+            let args = serde_json::json!({ "query": "tide", "scope": scope });
+            let result = execute_tool("memory_search", args, &path, &db, true, None).unwrap();
+            let mut uris: Vec<String> = result.details["hits"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|h| h["uri"].as_str().unwrap().to_string())
                 .collect();
-            let mut results = Vec::new();
-            for tool in tool_definitions_json() {
-                let name = tool
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or_default()
-                    .to_string();
-                let desc = tool
-                    .get("description")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or_default()
-                    .to_string();
-                let score = tool_score(&query_tokens, &name, &desc);
-                if score > 0 {
-                    results.push(serde_json::json!({
-                        "name": name,
-                        "description": desc,
-                        "score": score
-                    }));
-                }
-            }
-            results.sort_by(|a, b| {
-                b.get("score")
-                    .and_then(|v| v.as_i64())
-                    .cmp(&a.get("score").and_then(|v| v.as_i64()))
-            });
-            let limit = parsed.limit.unwrap_or(8);
-            let results: Vec<serde_json::Value> = results.into_iter().take(limit).collect();
-            Ok(ToolExecution {
-                output: format!("Found {} tools.", results.len()),
-                details: serde_json::json!({ "results": results }),
-                is_error: false,
-            })
-        }
-        "session_context" => {
-            let parsed: ToolSessionContextArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let limit = parsed.limit.unwrap_or(20);
-            // Try JSONL logs first, fall back to db search
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let log_dir = log_dir_path(&workspace);
-            let jsonl_entries = load_session_logs(&log_dir, &parsed.session, limit);
-            if !jsonl_entries.is_empty() {
-                let results: Vec<serde_json::Value> = jsonl_entries
-                    .into_iter()
-                    .map(|e| {
-                        serde_json::json!({
-                            "ts": e.ts_utc,
-                            "role": e.role,
-                            "text": e.text,
-                            "meta": e.meta,
-                            "source": "jsonl"
-                        })
-                    })
-                    .collect();
-                Ok(ToolExecution {
-                    output: format!("Loaded {} entries from logs.", results.len()),
-                    details: serde_json::json!({ "entries": results }),
-                    is_error: false,
-                })
-            } else {
-                // Fallback: search db for legacy data
-                let scope = format!("aethervault://agent-log/{}/", parsed.session);
-                let request = SearchRequest {
-                    query: parsed.session.clone(),
-                    top_k: 200,
-                    snippet_chars: 200,
-                    scope: Some(scope),
-                    temporal: None,
-                    as_of_frame: None,
-                    as_of_ts: None,
-                };
-                let response = db.search(request).map_err(|e| e.to_string())?;
-                let mut entries = Vec::new();
-                for hit in response.hits {
-                    let uri = hit.uri.clone();
-                    let ts = parse_log_ts_from_uri(&uri).unwrap_or_default();
-                    if let Ok(text) = db.frame_text_by_id(hit.frame_id) {
-                        if let Ok(entry) = serde_json::from_str::<AgentLogEntry>(&text) {
-                            entries.push(serde_json::json!({
-                                "ts": entry.ts_utc.unwrap_or(ts),
-                                "role": entry.role,
-                                "text": entry.text,
-                                "meta": entry.meta,
-                                "source": "db"
-                            }));
-                        }
-                    }
-                }
-                entries.sort_by(|a, b| {
-                    b.get("ts")
-                        .and_then(|v| v.as_i64())
-                        .cmp(&a.get("ts").and_then(|v| v.as_i64()))
-                });
-                let results: Vec<serde_json::Value> = entries.into_iter().take(limit).collect();
-                Ok(ToolExecution {
-                    output: format!("Loaded {} entries from db.", results.len()),
-                    details: serde_json::json!({ "entries": results }),
-                    is_error: false,
-                })
-            }
-        }
-        "reflect" => {
-            let parsed: ToolReflectArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let session = parsed
-                .session
-                .clone()
-                .unwrap_or_else(|| "default".to_string());
-            let ts = Utc::now().timestamp();
-            let payload = serde_json::json!({
-                "session": session,
-                "text": parsed.text,
-                "reason": parsed.reason,
-                "ts_utc": ts
-            });
-            let bytes = serde_json::to_vec_pretty(&payload).map_err(|e| e.to_string())?;
-            let hash = blake3_hash(&bytes);
-            let uri = format!(
-                "aethervault://memory/reflection/{}/{}-{}",
-                session,
-                ts,
-                hash.to_hex()
-            );
-            {
-                let mut options = PutOptions::default();
-                options.uri = Some(uri.clone());
-                options.title = Some("reflection".to_string());
-                options.kind = Some("application/json".to_string());
-                options.track = Some("aethervault.reflection".to_string());
-                options.search_text = Some(payload.to_string());
-                let result = put_with_consolidation(db, &bytes, options)
-                    .map_err(|e| e.to_string())?;
-                db.commit().map_err(|e| e.to_string())?;
-                let output = match result.decision {
-                    ConsolidationDecision::Noop { existing_id } => {
-                        format!("Reflection deduplicated (similar to frame #{existing_id}).")
-                    }
-                    ConsolidationDecision::Update { supersede_id } => {
-                        format!("Reflection updated (superseded frame #{supersede_id}).")
-                    }
-                    ConsolidationDecision::Add => "Reflection stored.".to_string(),
-                };
-                Ok(ToolExecution {
-                    output,
-                    details: serde_json::json!({ "uri": uri, "decision": format!("{:?}", result.decision) }),
-                    is_error: false,
-                })
-            }
-        }
-        "skill_store" => {
-            let parsed: ToolSkillStoreArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let db_path = workspace.join("skills.sqlite");
-            let conn = open_skill_db(&db_path).map_err(|e| format!("skill db: {e}"))?;
-            let now = Utc::now().to_rfc3339();
-            let skill_name = parsed.name.clone();
-            let skill_trigger = parsed.trigger.clone();
-            let skill_steps = parsed.steps.clone().unwrap_or_default();
-            let skill_tools = parsed.tools.clone().unwrap_or_default();
-            let skill_notes = parsed.notes.clone();
-
-            let skill = SkillRecord {
-                name: skill_name.clone(),
-                trigger: skill_trigger.clone(),
-                steps: skill_steps.clone(),
-                tools: skill_tools.clone(),
-                notes: skill_notes.clone(),
-                success_rate: 0.0,
-                times_used: 0,
-                times_succeeded: 0,
-                last_used: None,
-                created_at: now,
-                contexts: Vec::new(),
-            };
-            upsert_skill(&conn, &skill).map_err(|e| format!("upsert: {e}"))?;
-
-            let ts = Utc::now().timestamp();
-            let payload = serde_json::json!({
-                "name": skill_name,
-                "trigger": skill_trigger,
-                "steps": skill_steps,
-                "tools": skill_tools,
-                "notes": skill_notes,
-                "ts_utc": ts
-            });
-            let bytes = serde_json::to_vec_pretty(&payload).map_err(|e| e.to_string())?;
-            let hash = blake3_hash(&bytes);
-            let slug = payload
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("skill")
-                .to_ascii_lowercase()
-                .replace(' ', "-");
-            let uri = format!("aethervault://skills/{}/{}-{}", slug, ts, hash.to_hex());
-            let mut details = serde_json::json!({
-                "uri": uri,
-                "stored_in_sqlite": true,
-                "name": parsed.name,
-                "db": db_path.display().to_string(),
-            });
-            let capsule_write = (|| -> Result<(), String> {
-                let mut options = PutOptions::default();
-                options.uri = Some(details["uri"].as_str().unwrap_or_default().to_string());
-                options.title = Some("skill".to_string());
-                options.kind = Some("application/json".to_string());
-                options.track = Some("aethervault.skill".to_string());
-                options.search_text = Some(payload.to_string());
-                db.put_bytes_with_options(&bytes, options)
-                    .map_err(|e| e.to_string())?;
-                db.commit().map_err(|e| e.to_string())?;
-                Ok(())
-            })();
-            if let Some(obj) = details.as_object_mut() {
-                obj.insert(
-                    "stored_in_db".into(),
-                    serde_json::Value::Bool(capsule_write.is_ok()),
-                );
-            }
-            if let Err(err) = &capsule_write {
-                if let Some(obj) = details.as_object_mut() {
-                    obj.insert(
-                        "db_error".into(),
-                        serde_json::Value::String(format!(
-                            "DB write skipped after SQLite upsert: {err}"
-                        )),
-                    );
-                }
-            }
-            Ok(ToolExecution {
-                output: if capsule_write.is_ok() {
-                    "Skill stored.".to_string()
-                } else {
-                    "Skill stored in SQLite; db write skipped.".to_string()
-                },
-                details,
-                is_error: false,
-            })
-        }
-        "skill_search" => {
-            let parsed: ToolSkillSearchArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let workspace = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let db_path = workspace.join("skills.sqlite");
-            let limit = parsed.limit.unwrap_or(10);
-            let mut out = Vec::new();
-
-            if let Ok(conn) = open_skill_db(&db_path) {
-                let results = search_skills(&conn, &parsed.query, limit);
-                out.extend(results.into_iter().map(|s| {
-                    serde_json::json!({
-                        "name": s.name,
-                        "trigger": s.trigger,
-                        "steps": s.steps,
-                        "tools": s.tools,
-                        "notes": s.notes,
-                        "success_rate": s.success_rate,
-                        "times_used": s.times_used,
-                        "last_used": s.last_used,
-                    })
-                }));
-            }
+            uris.sort();
+            uris
+        };
+        assert_eq!(uris("memory").len(), 2);
+        assert_eq!(uris("reflections"), vec!["aethervault://memory/reflection/s1/tide.json"]);
+        assert_eq!(uris("skills"), vec!["aethervault://skills/tide/1"]);
+        assert_eq!(uris("all").len(), 3);
+        assert!(execute_tool("memory_search", serde_json::json!({ "query": "tide", "scope": "x" }), &path, &db, true, None).is_err());
+        let _ = fs::remove_file(&path);
+    }
 
-            if let Ok(response) = db.search(SearchRequest {
-                query: parsed.query.clone(),
-                top_k: limit,
-                snippet_chars: 200,
-                scope: Some("aethervault://skills/".to_string()),
-                temporal: None,
-                as_of_frame: None,
-                as_of_ts: None,
-            }) {
-                for hit in response.hits {
-                    out.push(serde_json::json!({
-                        "uri": hit.uri,
-                        "title": hit.title,
-                        "text": hit.text,
-                        "score": hit.score
-                    }));
-                }
-            }
-            Ok(ToolExecution {
-                output: format!("Found {} skills.", out.len()),
-                details: serde_json::json!({ "results": out }),
-                is_error: false,
-            })
-        }
-        "subagent_list" => {
-            let ws = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let cfg_path = crate::config_file_path(&ws);
-            let config = if cfg_path.exists() {
-                crate::load_config_from_file(&ws)
-            } else {
-                load_capsule_config(db).unwrap_or_default()
-            };
-            let subagents = load_subagents_from_config(&config);
-            let details: Vec<serde_json::Value> = subagents.iter().map(|s| {
-                serde_json::json!({
-                    "name": s.name,
-                    "description": s.description,
-                    "tools": s.tools,
-                    "disallowed_tools": s.disallowed_tools,
-                    "max_steps": s.max_steps,
-                })
-            }).collect();
-            // Check if dynamic spawning is supported
-            let has_default_hook = config.agent.as_ref()
-                .and_then(|a| a.default_subagent_hook.as_ref())
-                .is_some();
-            Ok(ToolExecution {
-                output: if !has_default_hook && details.is_empty() {
-                    "No subagent hook configured. Define default_subagent_hook in config.json.".to_string()
-                } else {
-                    format!("Dynamic spawning enabled. Use subagent_invoke with any descriptive name.{}",
-                        if details.is_empty() { String::new() } else { format!(" {} pre-configured agents also available.", details.len()) })
-                },
-                details: serde_json::json!({
-                    "subagents": details,
-                    "dynamic_spawning": has_default_hook,
-                }),
-                is_error: false,
-            })
+    #[test]
+    fn pending_approvals_are_announced_once() {
+        let path = std::env::temp_dir().join(format!("aethervault_aprnotify_{}.mv2", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let gated = execute_tool("notify", serde_json::json!({ "text": "deploy finished" }), &path, &db, false, None).unwrap();
+        assert!(gated.output.starts_with("approval required"));
+
+        let failing = notify_pending_approvals(&db, &mut |_| Err("offline".into())).unwrap();
+        assert_eq!(failing, 0);
+        let mut sent = Vec::new();
+        let count = notify_pending_approvals(&db, &mut |text| {
+            sent.push(text.to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 1);
+        assert!(sent[0].contains("Send notification: \"deploy finished\""));
+        assert_eq!(notify_pending_approvals(&db, &mut |_| Ok(())).unwrap(), 0);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reflections_list_filters_by_session() {
+        let path = std::env::temp_dir().join(format!("aethervault_reflist_{}.mv2", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        for (session, text) in [("s1", "checked the wrong calendar"), ("s2", "forgot to cite the source document")] {
+            let args = serde_json::json!({ "text": text, "session": session, "reason": "user correction" });
+            execute_tool("reflect", args, &path, &db, false, None).unwrap();
         }
-        "subagent_invoke" => {
-            let parsed: ToolSubagentInvokeArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let ws = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let cfg_path = crate::config_file_path(&ws);
-            let config = if cfg_path.exists() {
-                crate::load_config_from_file(&ws)
-            } else {
-                load_capsule_config(db).unwrap_or_default()
-            };
-            let subagents = load_subagents_from_config(&config);
-            let resolved_hook = config.agent.as_ref()
-                .and_then(|a| a.default_subagent_hook.clone())
-                .unwrap_or_else(|| DEFAULT_SUBAGENT_HOOK.to_string());
-            let config_max_steps = config.agent.as_ref()
-                .and_then(|a| a.subagent_max_steps)
-                .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
-            let synth_spec = SubagentSpec {
-                name: parsed.name.clone(),
-                description: None,
-                system: None,
-                model_hook: Some(resolved_hook),
-                tools: Vec::new(),
-                disallowed_tools: Vec::new(),
-                max_steps: Some(config_max_steps),
-                timeout_secs: Some(DEFAULT_SUBAGENT_TIMEOUT_SECS),
-            };
-            let spec = subagents
-                .iter()
-                .find(|s| s.name == parsed.name)
-                .unwrap_or(&synth_spec);
-            if spec.name != parsed.name {
-                eprintln!(
-                    "[subagent_invoke] '{}' not in config, using dynamic spec (hook: {})",
-                    parsed.name,
-                    spec.model_hook.as_deref().unwrap_or("none"),
-                );
-            }
+        let list = |args: serde_json::Value| execute_tool("reflections_list", args, &path, &db, true, None).unwrap().details;
+        let all = list(serde_json::json!({}));
+        assert_eq!(all["reflections"].as_array().unwrap().len(), 2);
+        let s1 = list(serde_json::json!({ "session": "s1" }));
+        let s1 = s1["reflections"].as_array().unwrap();
+        assert_eq!(s1.len(), 1);
+        assert_eq!(s1[0]["text"], "checked the wrong calendar");
+        assert_eq!(s1[0]["reason"], "user correction");
+        assert_eq!(list(serde_json::json!({ "limit": 1 }))["reflections"].as_array().unwrap().len(), 1);
+        let _ = fs::remove_file(&path);
+    }
 
-            let mut system = parsed.system.clone();
-            let mut model_hook = parsed.model_hook.clone();
-            if system.is_none() {
-                system = spec.system.clone();
-            }
-            if model_hook.is_none() {
-                model_hook = spec.model_hook.clone();
-            }
+    #[test]
+    fn skill_run_returns_plan_and_known_tools() {
+        let path = std::env::temp_dir().join(format!("aethervault_skillrun_{}.mv2", std::process::id()));
+        let _ = fs::remove_file(&path);
+        // skill_search opens <workspace>/skills.sqlite; keep it out of ./assistant.
+        let workspace = std::env::temp_dir().join(format!("aethervault_skillrun_ws_{}", std::process::id()));
+        fs::create_dir_all(&workspace).unwrap();
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let payload = serde_json::json!({
+            "name": "Weekly Report",
+            "steps": ["collect notes", "draft summary"],
+            "tools": ["memory_search", "frobnicate"],
+        });
+        let options = PutOptions { uri: Some("aethervault://skills/weekly-report/1".into()), ..Default::default() };
+        db.put_bytes_with_options(payload.to_string().as_bytes(), options).unwrap();
+        db.commit().unwrap();
+
+        for wanted in ["weekly report", "aethervault://skills/weekly-report/1"] {
+            let args = serde_json::json!({ "name_or_uri": wanted, "task": "october" });
+            let result = execute_tool("skill_run", args, &path, &db, true, None).unwrap();
+            assert!(result.output.contains("1. collect notes"));
+            assert!(result.output.contains("Task: october"));
+            assert_eq!(result.details["tools"], serde_json::json!(["memory_search"]));
+            assert_eq!(result.details["unknown_tools"], serde_json::json!(["frobnicate"]));
+        }
+        let frame = db.frame_by_uri("aethervault://skills/weekly-report/1").unwrap();
+        assert_eq!(frame.extra_metadata.get("skill_uses").map(String::as_str), Some("2"));
+
+        // An older per-version URI and the stable one: search keeps the newest.
+        let options = PutOptions {
+            uri: Some(skill_uri(&skill_slug("Weekly Report"))),
+            search_text: Some("weekly report".into()),
+            ..Default::default()
+        };
+        db.put_bytes_with_options(payload.to_string().as_bytes(), options).unwrap();
+        let args = serde_json::json!({ "query": "weekly report", "sort": "usage" });
+        let found = skill_search(&db, &workspace, serde_json::from_value(args).unwrap()).details;
+        let uris: Vec<&str> = found["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|r| r["uri"].as_str())
+            .collect();
+        assert_eq!(uris, vec!["aethervault://skills/weekly-report.json"]);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir_all(&workspace);
+    }
 
-            // Enforce tool restrictions from SubagentSpec via system prompt
-            if !spec.tools.is_empty() {
-                let list = spec.tools.join(", ");
-                let restriction = format!(
-                    "\n\nIMPORTANT: You are ONLY allowed to use the following tools: {list}. \
-                     Do not attempt to use any other tools."
-                );
-                if let Some(ref mut sys) = system {
-                    sys.push_str(&restriction);
-                } else {
-                    system = Some(restriction);
-                }
-            }
-            if !spec.disallowed_tools.is_empty() {
-                let list = spec.disallowed_tools.join(", ");
-                let restriction = format!(
-                    "\n\nIMPORTANT: You are NOT allowed to use the following tools: {list}. \
-                     If you attempt to use them, the call will fail."
-                );
-                if let Some(ref mut sys) = system {
-                    sys.push_str(&restriction);
-                } else {
-                    system = Some(restriction);
-                }
-            }
+    #[test]
+    fn trigger_test_reports_without_firing() {
+        let path = std::env::temp_dir().join(format!("aethervault_trgtest_{}.mv2", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let cron = |id: &str, expr: &str| TriggerEntry {
+            id: id.into(),
+            kind: "cron".into(),
+            name: Some("standup".into()),
+            query: None,
+            prompt: Some("Run {{trigger_name}} ({{cron}})".into()),
+            start: None,
+            end: None,
+            enabled: true,
+            last_seen: None,
+            last_fired: None,
+            cron: Some(expr.into()),
+            webhook_url: None,
+            webhook_method: None,
+            schedule_name: None,
+        };
+        save_triggers(&db, &[cron("always", "* * * * *"), cron("never", "0 0 31 2 *")]).unwrap();
+        let test = |id: &str| execute_tool("trigger_test", serde_json::json!({ "id": id }), &path, &db, true, None);
+
+        let fired = test("always").unwrap().details;
+        assert_eq!(fired["outcome"], "fire");
+        assert!(fired["prompt"].as_str().unwrap().starts_with("Run standup (* * * * *)"));
+        assert_eq!(test("never").unwrap().details["outcome"], "idle");
+        assert!(test("missing").is_err());
+        assert!(load_triggers(&db).iter().all(|t| t.last_fired.is_none()));
+        let _ = fs::remove_file(&path);
+    }
 
-            // Resolve max_steps: invocation arg > spec > default 64
-            let max_steps = parsed.max_steps.or(spec.max_steps).unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
-
-            let cfg = build_bridge_agent_config(
-                mv2.to_path_buf(),
-                model_hook,
-                system,
-                false,
-                None,
-                8,
-                12_000,
-                max_steps,
-                true,
-                8,
-            )
-            .map_err(|e| e.to_string())?;
-            let session = format!("subagent:{}:{}", parsed.name, Utc::now().timestamp());
-            let prompt = parsed.prompt.clone();
-
-            // Non-blocking path: when bg_registry is present, register and return immediately
-            if let Some((chat_id, registry)) = bg_registry.as_ref() {
-                let task_id = {
-                    let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                    reg.next_id()
-                };
-                let now_epoch = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                let preview: String = parsed.prompt.chars().take(100).collect();
-                let bg_task = BackgroundTask {
-                    task_id: task_id.clone(),
-                    name: parsed.name.clone(),
-                    prompt_preview: preview,
-                    status: BackgroundTaskStatus::Running,
-                    started_at_epoch: now_epoch,
-                    completed_at_epoch: None,
-                    step_count: 0,
-                    max_steps,
-                    result_text: None,
-                    session: session.clone(),
-                };
-                {
-                    let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                    reg.register(*chat_id, bg_task);
-                }
-                let reg_clone = registry.clone();
-                let tid = task_id.clone();
-                thread::spawn(move || {
-                    let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
-                    let mut reg = reg_clone.lock().unwrap_or_else(|e| e.into_inner());
-                    match r {
-                        Ok(output) => {
-                            reg.update_status(
-                                &tid,
-                                BackgroundTaskStatus::Completed,
-                                output.final_text,
-                            );
-                        }
-                        Err(err) => {
-                            reg.update_status(
-                                &tid,
-                                BackgroundTaskStatus::Failed(err.to_string()),
-                                None,
-                            );
-                        }
-                    }
-                });
-                return Ok(ToolExecution {
-                    output: format!("Background task started: {} (id: {})", parsed.name, task_id),
-                    details: serde_json::json!({ "task_id": task_id, "name": parsed.name }),
-                    is_error: false,
-                });
-            }
+    #[test]
+    fn memory_export_if_newer_keeps_local_edits() {
+        let dir = std::env::temp_dir().join(format!("aethervault_memexport_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("t.mv2");
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let options = PutOptions {
+            uri: Some("aethervault://memory/daily/2023-11-14.md".into()),
+            timestamp: Some(1_700_000_000),
+            ..Default::default()
+        };
+        db.put_bytes_with_options(b"from capsule", options).unwrap();
+        db.commit().unwrap();
+        let daily = dir.join("ws/memory/2023-11-14.md");
+        let export = |if_newer: bool| export_capsule_memory(&path, &dir.join("ws"), true, if_newer).unwrap();
+
+        assert_eq!(export(true).written.len(), 1);
+        let modified = fs::metadata(&daily).unwrap().modified().unwrap();
+        assert_eq!(modified, std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        fs::write(&daily, "local edit").unwrap();
+        assert_eq!(export(true).skipped.len(), 1);
+        assert_eq!(fs::read_to_string(&daily).unwrap(), "local edit");
+
+        export(false);
+        assert_eq!(fs::read_to_string(&daily).unwrap(), "from capsule");
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-            // Blocking path (CLI / non-bridge contexts): spawn and wait
-            let (tx, rx) = std::sync::mpsc::channel();
-            thread::spawn(move || {
-                let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
-                let _ = tx.send(r);
-            });
+    #[test]
+    fn memory_status_tracks_sync_base() {
+        use crate::{memory_sync_status, ConflictStrategy, MemorySyncState};
+        let dir = std::env::temp_dir().join(format!("aethervault_memstatus_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let ws = dir.join("ws");
+        fs::create_dir_all(&ws).unwrap();
+        let path = dir.join("t.mv2");
+        fs::write(ws.join("MEMORY.md"), "v1").unwrap();
+        sync_workspace_memory(&path, &ws, true).unwrap();
+        let state = |strategy| memory_sync_status(&path, &ws, strategy).unwrap()[0].state;
+        assert_eq!(state(None), MemorySyncState::InSync);
+
+        fs::write(ws.join("MEMORY.md"), "local v2").unwrap();
+        assert_eq!(state(None), MemorySyncState::LocalAhead);
+
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let options = PutOptions { uri: Some("aethervault://memory/longterm.md".into()), ..Default::default() };
+        db.put_bytes_with_options(b"capsule v2", options).unwrap();
+        db.commit().unwrap();
+        assert_eq!(state(None), MemorySyncState::Conflict);
+
+        assert_eq!(state(Some(ConflictStrategy::Both)), MemorySyncState::Conflict);
+        assert_eq!(fs::read_to_string(ws.join("MEMORY.md.conflict")).unwrap(), "capsule v2");
+        assert_eq!(state(Some(ConflictStrategy::Capsule)), MemorySyncState::Conflict);
+        assert_eq!(fs::read_to_string(ws.join("MEMORY.md")).unwrap(), "capsule v2");
+        assert_eq!(state(None), MemorySyncState::InSync);
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-            let result = rx.recv()
-                .map_err(|e| format!("channel error: {e}"))?
-                .map_err(|e| e.to_string())?;
+    #[test]
+    fn exec_allow_prefix_and_regex() {
+        let allow = vec!["git status".to_string(), "re:^ls( -[a-z]+)? /tmp$".to_string()];
+        assert!(exec_policy_refusal("git status --short", &allow).is_none());
+        assert!(exec_policy_refusal("ls -la /tmp", &allow).is_none());
+        assert!(exec_policy_refusal("rm -rf /", &allow).is_some());
+        assert!(exec_policy_refusal("", &[]).is_none());
+    }
 
-            Ok(ToolExecution {
-                output: result.final_text.unwrap_or_default(),
-                details: serde_json::json!({ "session": result.session, "messages": result.messages.len() }),
-                is_error: false,
-            })
-        }
-        "subagent_batch" => {
-            let parsed: ToolSubagentBatchArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            if parsed.invocations.is_empty() {
-                return Err("subagent_batch requires at least one invocation".into());
-            }
-            let ws = workspace_override
-                .clone()
-                .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
-            let cfg_path = crate::config_file_path(&ws);
-            let config_snapshot = if cfg_path.exists() {
-                crate::load_config_from_file(&ws)
-            } else {
-                load_capsule_config(db).unwrap_or_default()
-            };
-            let subagents = load_subagents_from_config(&config_snapshot);
-            let resolved_hook = config_snapshot.agent.as_ref()
-                .and_then(|a| a.default_subagent_hook.clone())
-                .unwrap_or_else(|| DEFAULT_SUBAGENT_HOOK.to_string());
-            let ts = Utc::now().timestamp();
-
-            let max_conc = parsed.max_concurrent.unwrap_or(parsed.invocations.len());
-            let max_conc = max_conc.max(1); // ensure at least 1
-
-            // Prepare each invocation: resolve spec fields, build config.
-            struct PreparedInvocation {
-                name: String,
-                prompt: String,
-                cfg: Result<crate::types::BridgeAgentConfig, String>,
-                index: usize,
-            }
-            let mut prepared: Vec<PreparedInvocation> = Vec::new();
-            for (i, inv) in parsed.invocations.into_iter().enumerate() {
-                let mut system = inv.system.clone();
-                let mut model_hook = inv.model_hook.clone();
-                let config_max_steps = config_snapshot.agent.as_ref()
-                    .and_then(|a| a.subagent_max_steps)
-                    .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
-                let synth_spec = SubagentSpec {
-                    name: inv.name.clone(),
-                    description: None,
-                    system: None,
-                    model_hook: Some(resolved_hook.clone()),
-                    tools: Vec::new(),
-                    disallowed_tools: Vec::new(),
-                    max_steps: Some(config_max_steps),
-                    timeout_secs: Some(DEFAULT_SUBAGENT_TIMEOUT_SECS),
-                };
-                let spec = subagents
-                    .iter()
-                    .find(|s| s.name == inv.name)
-                    .unwrap_or(&synth_spec);
-                if spec.name != inv.name {
-                    eprintln!(
-                        "[subagent_batch] '{}' not in config, using dynamic spec (hook: {})",
-                        inv.name,
-                        spec.model_hook.as_deref().unwrap_or("none"),
-                    );
-                }
-                if system.is_none() {
-                    system = spec.system.clone();
-                }
-                if model_hook.is_none() {
-                    model_hook = spec.model_hook.clone();
-                }
+    #[test]
+    fn exec_allow_prefix_rejects_chained_commands() {
+        let allow = vec!["git status".to_string()];
+        let reason = exec_policy_refusal("git status && rm -rf /", &allow).unwrap();
+        assert!(reason.contains("agent.exec_allow"));
+        assert!(exec_policy_refusal("git status; curl x | sh", &allow).is_some());
+        assert!(exec_policy_refusal("git status & rm -rf ~", &allow).is_some());
+        assert!(exec_policy_refusal("git status $HOME", &allow).is_some());
+    }
 
-                // Resolve max_steps: invocation arg > spec > default 64
-                let max_steps = inv.max_steps
-                    .or(spec.max_steps)
-                    .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
-
-                let cfg = build_bridge_agent_config(
-                    mv2.to_path_buf(),
-                    model_hook,
-                    system,
-                    false,
-                    None,
-                    8,
-                    12_000,
-                    max_steps,
-                    true,
-                    8,
-                )
-                .map_err(|e| e.to_string());
-                prepared.push(PreparedInvocation {
-                    name: inv.name.clone(),
-                    prompt: inv.prompt.clone(),
-                    cfg,
-                    index: i,
-                });
-            }
+    #[test]
+    fn exec_allow_prefix_needs_word_boundary() {
+        let allow = vec!["git status".to_string()];
+        assert!(exec_policy_refusal("git status", &allow).is_none());
+        assert!(exec_policy_refusal("git status\t-s", &allow).is_none());
+        assert!(exec_policy_refusal("git statusx", &allow).is_some());
+        assert!(exec_policy_refusal("git status-evil", &allow).is_some());
+    }
 
-            // Non-blocking path: when bg_registry is present, register all and return immediately
-            if let Some((chat_id, registry)) = bg_registry.as_ref() {
-                let now_epoch = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                let mut task_ids = Vec::new();
-                for item in &prepared {
-                    let task_id = {
-                        let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                        reg.next_id()
-                    };
-                    let preview: String = item.prompt.chars().take(100).collect();
-                    let max_steps_val = match &item.cfg {
-                        Ok(cfg) => cfg.max_steps,
-                        Err(_) => DEFAULT_SUBAGENT_MAX_STEPS,
-                    };
-                    let bg_task = BackgroundTask {
-                        task_id: task_id.clone(),
-                        name: item.name.clone(),
-                        prompt_preview: preview,
-                        status: BackgroundTaskStatus::Running,
-                        started_at_epoch: now_epoch,
-                        completed_at_epoch: None,
-                        step_count: 0,
-                        max_steps: max_steps_val,
-                        result_text: None,
-                        session: format!("subagent:{}:{}:{}", item.name, ts, item.index),
-                    };
-                    {
-                        let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                        reg.register(*chat_id, bg_task);
-                    }
-                    match &item.cfg {
-                        Ok(cfg) => {
-                            let cfg = cfg.clone();
-                            let session = format!("subagent:{}:{}:{}", item.name, ts, item.index);
-                            let prompt = item.prompt.clone();
-                            let reg_clone = registry.clone();
-                            let tid = task_id.clone();
-                            thread::spawn(move || {
-                                let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
-                                let mut reg = reg_clone.lock().unwrap_or_else(|e| e.into_inner());
-                                match r {
-                                    Ok(output) => {
-                                        reg.update_status(&tid, BackgroundTaskStatus::Completed, output.final_text);
-                                    }
-                                    Err(err) => {
-                                        reg.update_status(&tid, BackgroundTaskStatus::Failed(err.to_string()), None);
-                                    }
-                                }
-                            });
-                        }
-                        Err(err) => {
-                            let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                            reg.update_status(&task_id, BackgroundTaskStatus::Failed(err.clone()), None);
-                        }
-                    }
-                    task_ids.push(serde_json::json!({ "task_id": task_id, "name": item.name }));
-                }
-                return Ok(ToolExecution {
-                    output: format!("{} background tasks started.", task_ids.len()),
-                    details: serde_json::json!({ "tasks": task_ids }),
-                    is_error: false,
-                });
-            }
+    #[test]
+    fn exec_output_cap_only_lowers_the_operator_limit() {
+        assert_eq!(exec_output_cap(None), DEFAULT_EXEC_MAX_OUTPUT_BYTES);
+        assert_eq!(exec_output_cap(Some(4096)), 4096);
+        assert_eq!(exec_output_cap(Some(usize::MAX)), DEFAULT_EXEC_MAX_OUTPUT_BYTES);
+    }
 
-            // Blocking path (CLI): process invocations in chunks of max_conc for concurrency limiting.
-            let mut all_results: Vec<serde_json::Value> = Vec::new();
-            let mut all_ok = true;
-
-            for chunk in prepared.chunks(max_conc) {
-                let mut handles: Vec<(String, std::thread::JoinHandle<Result<AgentRunOutput, String>>)> = Vec::new();
-                for item in chunk {
-                    let name = item.name.clone();
-                    match &item.cfg {
-                        Err(err) => {
-                            let err = err.clone();
-                            handles.push((name, thread::spawn(move || Err(err))));
-                        }
-                        Ok(cfg) => {
-                            let cfg = cfg.clone();
-                            let session = format!("subagent:{}:{}:{}", item.name, ts, item.index);
-                            let prompt = item.prompt.clone();
-                            handles.push((name, thread::spawn(move || {
-                                run_agent_for_bridge(&cfg, &prompt, session, None, None, None)
-                            })));
-                        }
-                    }
-                }
+    #[test]
+    fn capped_output_keeps_head_and_counts_total() {
+        let mut out = CappedOutput::new(4);
+        out.push(b"ab");
+        out.push(b"cdef");
+        assert_eq!(out.total, 6);
+        assert!(out.truncated());
+        let text = out.text();
+        assert!(text.starts_with("abcd"));
+        assert!(text.contains("kept 4 of 6 bytes"));
+    }
 
-                // Collect results from this chunk before starting the next.
-                for (name, handle) in handles {
-                    match handle.join() {
-                        Ok(Ok(output)) => {
-                            all_results.push(serde_json::json!({
-                                "name": name,
-                                "status": "ok",
-                                "output": output.final_text.unwrap_or_default(),
-                                "session": output.session,
-                                "messages": output.messages.len(),
-                            }));
-                        }
-                        Ok(Err(err)) => {
-                            all_ok = false;
-                            all_results.push(serde_json::json!({
-                                "name": name,
-                                "status": "error",
-                                "error": err,
-                            }));
-                        }
-                        Err(_) => {
-                            all_ok = false;
-                            all_results.push(serde_json::json!({
-                                "name": name,
-                                "status": "error",
-                                "error": "subagent thread panicked",
-                            }));
-                        }
-                    }
-                }
-            }
+    #[test]
+    fn subagent_depth_limit() {
+        let mut config = CapsuleConfig::default();
+        set_subagent_depth(0);
+        assert_eq!(next_subagent_depth(&config), Ok(1));
+        set_subagent_depth(DEFAULT_SUBAGENT_MAX_DEPTH);
+        let err = next_subagent_depth(&config).unwrap_err();
+        assert!(err.contains("depth limit"), "{err}");
+
+        config.agent = Some(AgentConfig {
+            subagent_max_depth: Some(DEFAULT_SUBAGENT_MAX_DEPTH + 1),
+            subagent_max_concurrent: Some(0),
+            ..AgentConfig::default()
+        });
+        assert_eq!(next_subagent_depth(&config), Ok(DEFAULT_SUBAGENT_MAX_DEPTH + 1));
+        assert_eq!(subagent_max_concurrent(&config), 1);
+    }
 
-            let summary = if all_ok {
-                format!("{} subagents completed successfully.", all_results.len())
-            } else {
-                let ok_count = all_results.iter().filter(|r| r["status"] == "ok").count();
-                let err_count = all_results.len() - ok_count;
-                format!("{} subagents completed, {} failed.", ok_count, err_count)
-            };
-            Ok(ToolExecution {
-                output: summary,
-                details: serde_json::json!({ "results": all_results }),
-                is_error: !all_ok,
-            })
-        }
-        "bg_status" => {
-            match bg_registry.as_ref() {
-                Some((chat_id, registry)) => {
-                    let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
-                    let scorecard = reg.scorecard(*chat_id);
-                    Ok(ToolExecution {
-                        output: scorecard,
-                        details: serde_json::json!(null),
-                        is_error: false,
-                    })
-                }
-                None => {
-                    Ok(ToolExecution {
-                        output: "No background task registry available (not running in bridge mode).".to_string(),
-                        details: serde_json::json!(null),
-                        is_error: false,
-                    })
-                }
-            }
-        }
-        "gmail_list" => {
-            let parsed: ToolGmailListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let mut url = format!(
-                "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults={}",
-                parsed.max_results.unwrap_or(10)
-            );
-            if let Some(q) = parsed.query {
-                url.push_str("&q=");
-                url.push_str(&urlencoding::encode(&q));
-            }
-            let payload = oauth_api_get(mv2, "google", &url, "gmail_list")?;
-            Ok(ToolExecution {
-                output: "Gmail messages listed.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "gmail_read" => {
-            let parsed: ToolGmailReadArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let url = format!(
-                "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=full",
-                parsed.id
-            );
-            let payload = oauth_api_get(mv2, "google", &url, "gmail_read")?;
-            Ok(ToolExecution {
-                output: "Gmail message read.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "gmail_send" => {
-            let parsed: ToolGmailSendArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let raw = format!(
-                "To: {}\r\nSubject: {}\r\n\r\n{}\r\n",
-                parsed.to, parsed.subject, parsed.body
-            );
-            let encoded = base64::engine::general_purpose::STANDARD
-                .encode(raw.as_bytes())
-                .replace('+', "-")
-                .replace('/', "_")
-                .trim_end_matches('=')
-                .to_string();
-            let payload = serde_json::json!({ "raw": encoded });
-            let details = oauth_api_post(
-                mv2, "google",
-                "https://gmail.googleapis.com/gmail/v1/users/me/messages/send",
-                payload, "gmail_send",
-            )?;
-            Ok(ToolExecution {
-                output: "Gmail message sent.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "gcal_list" => {
-            let parsed: ToolGCalListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let url = format!(
-                "https://www.googleapis.com/calendar/v3/calendars/primary/events?maxResults={}",
-                parsed.max_results.unwrap_or(10)
-            );
-            let payload = oauth_api_get(mv2, "google", &url, "gcal_list")?;
-            Ok(ToolExecution {
-                output: "Calendar events listed.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "gcal_create" => {
-            let parsed: ToolGCalCreateArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let payload = serde_json::json!({
-                "summary": parsed.summary,
-                "description": parsed.description,
-                "start": { "dateTime": parsed.start },
-                "end": { "dateTime": parsed.end }
-            });
-            let details = oauth_api_post(
-                mv2, "google",
-                "https://www.googleapis.com/calendar/v3/calendars/primary/events",
-                payload, "gcal_create",
-            )?;
-            Ok(ToolExecution {
-                output: "Calendar event created.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "ms_mail_list" => {
-            let parsed: ToolMsMailListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let url = format!(
-                "https://graph.microsoft.com/v1.0/me/messages?$top={}",
-                parsed.top.unwrap_or(10)
-            );
-            let payload = oauth_api_get(mv2, "microsoft", &url, "ms_mail_list")?;
-            Ok(ToolExecution {
-                output: "Microsoft mail listed.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "ms_mail_read" => {
-            let parsed: ToolMsMailReadArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let url = format!("https://graph.microsoft.com/v1.0/me/messages/{}", parsed.id);
-            let payload = oauth_api_get(mv2, "microsoft", &url, "ms_mail_read")?;
-            Ok(ToolExecution {
-                output: "Microsoft mail read.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "ms_calendar_list" => {
-            let parsed: ToolMsCalendarListArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let url = format!(
-                "https://graph.microsoft.com/v1.0/me/events?$top={}",
-                parsed.top.unwrap_or(10)
-            );
-            let payload = oauth_api_get(mv2, "microsoft", &url, "ms_calendar_list")?;
-            Ok(ToolExecution {
-                output: "Microsoft calendar listed.".to_string(),
-                details: payload,
-                is_error: false,
-            })
-        }
-        "ms_calendar_create" => {
-            let parsed: ToolMsCalendarCreateArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let payload = serde_json::json!({
-                "subject": parsed.subject,
-                "body": {
-                    "contentType": "Text",
-                    "content": parsed.body.unwrap_or_default()
-                },
-                "start": { "dateTime": parsed.start, "timeZone": "UTC" },
-                "end": { "dateTime": parsed.end, "timeZone": "UTC" }
-            });
-            let details = oauth_api_post(
-                mv2, "microsoft",
-                "https://graph.microsoft.com/v1.0/me/events",
-                payload, "ms_calendar_create",
-            )?;
-            Ok(ToolExecution {
-                output: "Microsoft calendar event created.".to_string(),
-                details,
-                is_error: false,
-            })
-        }
-        "scale" => {
-            let parsed: ToolScaleArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            match parsed.action.as_str() {
-                "status" => {
-                    // Pure local: read /proc files + df for system stats
-                    let cpu_count = std::thread::available_parallelism()
-                        .map(|n| n.get())
-                        .unwrap_or(1);
-                    let (load_1m, load_5m) =
-                        std::fs::read_to_string("/proc/loadavg")
-                            .ok()
-                            .and_then(|s| {
-                                let parts: Vec<&str> = s.split_whitespace().collect();
-                                if parts.len() >= 2 {
-                                    Some((
-                                        parts[0].parse::<f64>().unwrap_or(0.0),
-                                        parts[1].parse::<f64>().unwrap_or(0.0),
-                                    ))
-                                } else {
-                                    None
-                                }
-                            })
-                            .unwrap_or((0.0, 0.0));
-                    let (mem_total_mb, mem_avail_mb) =
-                        std::fs::read_to_string("/proc/meminfo")
-                            .ok()
-                            .map(|s| {
-                                let mut total: u64 = 0;
-                                let mut avail: u64 = 0;
-                                for line in s.lines() {
-                                    if line.starts_with("MemTotal:") {
-                                        total = line
-                                            .split_whitespace()
-                                            .nth(1)
-                                            .and_then(|v| v.parse::<u64>().ok())
-                                            .unwrap_or(0)
-                                            / 1024;
-                                    } else if line.starts_with("MemAvailable:") {
-                                        avail = line
-                                            .split_whitespace()
-                                            .nth(1)
-                                            .and_then(|v| v.parse::<u64>().ok())
-                                            .unwrap_or(0)
-                                            / 1024;
-                                    }
-                                }
-                                (total, avail)
-                            })
-                            .unwrap_or((0, 0));
-                    let mem_used_pct = if mem_total_mb > 0 {
-                        ((mem_total_mb - mem_avail_mb) as f64 / mem_total_mb as f64 * 100.0)
-                            .round()
-                    } else {
-                        0.0
-                    };
-                    // Disk via df
-                    let (disk_total_gb, disk_used_gb, disk_used_pct) = std::process::Command::new("df")
-                        .args(["-BG", "/"])
-                        .output()
-                        .ok()
-                        .and_then(|out| {
-                            let text = String::from_utf8_lossy(&out.stdout);
-                            let line = text.lines().nth(1)?;
-                            let parts: Vec<&str> = line.split_whitespace().collect();
-                            if parts.len() >= 5 {
-                                let total = parts[1]
-                                    .trim_end_matches('G')
-                                    .parse::<f64>()
-                                    .unwrap_or(0.0);
-                                let used = parts[2]
-                                    .trim_end_matches('G')
-                                    .parse::<f64>()
-                                    .unwrap_or(0.0);
-                                let pct = parts[4]
-                                    .trim_end_matches('%')
-                                    .parse::<f64>()
-                                    .unwrap_or(0.0);
-                                Some((total, used, pct))
-                            } else {
-                                None
-                            }
-                        })
-                        .unwrap_or((0.0, 0.0, 0.0));
-                    let details = serde_json::json!({
-                        "cpu_count": cpu_count,
-                        "load_1m": load_1m,
-                        "load_5m": load_5m,
-                        "mem_total_mb": mem_total_mb,
-                        "mem_avail_mb": mem_avail_mb,
-                        "mem_used_pct": mem_used_pct,
-                        "disk_total_gb": disk_total_gb,
-                        "disk_used_gb": disk_used_gb,
-                        "disk_used_pct": disk_used_pct,
-                    });
-                    Ok(ToolExecution {
-                        output: format!(
-                            "CPU: {} cores, load {:.1}/{:.1} | RAM: {}MB/{} MB ({:.0}% used) | Disk: {:.0}G/{:.0}G ({:.0}% used)",
-                            cpu_count, load_1m, load_5m, mem_total_mb - mem_avail_mb, mem_total_mb, mem_used_pct,
-                            disk_used_gb, disk_total_gb, disk_used_pct,
-                        ),
-                        details,
-                        is_error: false,
-                    })
-                }
-                "sizes" => {
-                    let do_token = env_optional("DO_TOKEN")
-                        .ok_or_else(|| "DO_TOKEN not set — cannot query DigitalOcean API".to_string())?;
-                    let out = std::process::Command::new("curl")
-                        .args([
-                            "-s",
-                            "-X", "GET",
-                            "https://api.digitalocean.com/v2/sizes",
-                            "-H", &format!("Authorization: Bearer {}", do_token),
-                        ])
-                        .output()
-                        .map_err(|e| format!("curl failed: {e}"))?;
-                    let body: serde_json::Value =
-                        serde_json::from_slice(&out.stdout)
-                            .map_err(|e| format!("invalid JSON from DO API: {e}"))?;
-                    let sizes = body
-                        .get("sizes")
-                        .and_then(|v| v.as_array())
-                        .cloned()
-                        .unwrap_or_default();
-                    // Filter to ≤8 vCPU / ≤32GB to prevent cost overruns
-                    let filtered: Vec<serde_json::Value> = sizes
-                        .into_iter()
-                        .filter(|s| {
-                            let vcpus = s.get("vcpus").and_then(|v| v.as_u64()).unwrap_or(99);
-                            let mem = s.get("memory").and_then(|v| v.as_u64()).unwrap_or(999999);
-                            let available = s.get("available").and_then(|v| v.as_bool()).unwrap_or(false);
-                            vcpus <= 8 && mem <= 32768 && available
-                        })
-                        .map(|s| {
-                            serde_json::json!({
-                                "slug": s.get("slug").and_then(|v| v.as_str()).unwrap_or(""),
-                                "vcpus": s.get("vcpus").and_then(|v| v.as_u64()).unwrap_or(0),
-                                "memory_mb": s.get("memory").and_then(|v| v.as_u64()).unwrap_or(0),
-                                "disk_gb": s.get("disk").and_then(|v| v.as_u64()).unwrap_or(0),
-                                "price_monthly": s.get("price_monthly").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                            })
-                        })
-                        .collect();
-                    let details = serde_json::json!({ "sizes": filtered });
-                    Ok(ToolExecution {
-                        output: format!("{} available sizes (≤8 vCPU, ≤32GB).", filtered.len()),
-                        details,
-                        is_error: false,
-                    })
-                }
-                "resize" => {
-                    let target_size = parsed
-                        .size
-                        .ok_or_else(|| "size parameter is required for resize".to_string())?;
-                    let do_token = env_optional("DO_TOKEN")
-                        .ok_or_else(|| "DO_TOKEN not set — cannot call DigitalOcean API".to_string())?;
-                    // Get droplet ID: env var or auto-detect via DO metadata
-                    let droplet_id = env_optional("DO_DROPLET_ID").or_else(|| {
-                        std::process::Command::new("curl")
-                            .args(["-s", "http://169.254.169.254/metadata/v1/id"])
-                            .output()
-                            .ok()
-                            .and_then(|o| {
-                                let id = String::from_utf8_lossy(&o.stdout).trim().to_string();
-                                if id.chars().all(|c| c.is_ascii_digit()) && !id.is_empty() {
-                                    Some(id)
-                                } else {
-                                    None
-                                }
-                            })
-                    }).ok_or_else(|| "DO_DROPLET_ID not set and metadata API unreachable".to_string())?;
-                    let url = format!(
-                        "https://api.digitalocean.com/v2/droplets/{}/actions",
-                        droplet_id
-                    );
-                    let payload = serde_json::json!({
-                        "type": "resize",
-                        "disk": false,
-                        "size": target_size,
-                    });
-                    let out = std::process::Command::new("curl")
-                        .args([
-                            "-s",
-                            "-X", "POST",
-                            &url,
-                            "-H", &format!("Authorization: Bearer {}", do_token),
-                            "-H", "Content-Type: application/json",
-                            "-d", &payload.to_string(),
-                        ])
-                        .output()
-                        .map_err(|e| format!("curl failed: {e}"))?;
-                    let resp: serde_json::Value =
-                        serde_json::from_slice(&out.stdout)
-                            .map_err(|e| format!("invalid JSON from DO API: {e}"))?;
-                    let action_status = resp
-                        .get("action")
-                        .and_then(|a| a.get("status"))
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("unknown");
-                    let action_id = resp
-                        .get("action")
-                        .and_then(|a| a.get("id"))
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    if action_status == "errored" || resp.get("id").is_some_and(|v| v.as_str() == Some("not_found")) {
-                        let msg = resp.get("message").and_then(|v| v.as_str()).unwrap_or("resize failed");
-                        return Err(format!("DO resize error: {msg}"));
-                    }
-                    Ok(ToolExecution {
-                        output: format!(
-                            "Resize to {} initiated (action {}, status: {}). Note: CPU resizes require a power cycle to take effect.",
-                            target_size, action_id, action_status
-                        ),
-                        details: resp,
-                        is_error: false,
-                    })
-                }
-                other => Err(format!("unknown scale action: {other} (use status, sizes, or resize)")),
-            }
-        }
-        "self_upgrade" => {
-            let parsed: ToolSelfUpgradeArgs =
-                serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
-            let branch = parsed.branch.as_deref().unwrap_or("main");
-            let skip_tests = parsed.skip_tests.unwrap_or(false);
-            let upgrade_script = "/opt/aethervault/upgrade.sh";
-            if !std::path::Path::new(upgrade_script).exists() {
-                return Err("upgrade.sh not found at /opt/aethervault/upgrade.sh — deploy it first".into());
-            }
-            let mut cmd = std::process::Command::new("bash");
-            cmd.arg(upgrade_script)
-                .arg("--branch").arg(branch);
-            if skip_tests {
-                cmd.arg("--skip-tests");
-            }
-            cmd.stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            let output = cmd.output().map_err(|e| format!("failed to run upgrade.sh: {e}"))?;
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            let combined = if stderr.is_empty() {
-                stdout.clone()
-            } else {
-                format!("{stdout}\n--- stderr ---\n{stderr}")
-            };
-            if output.status.success() {
-                Ok(ToolExecution {
-                    output: format!("Upgrade succeeded (branch: {branch}). Binary hot-swapped. Service will restart momentarily.\n\n{combined}"),
-                    details: serde_json::json!({
-                        "branch": branch,
-                        "skip_tests": skip_tests,
-                        "exit_code": 0,
-                    }),
-                    is_error: false,
-                })
-            } else {
-                let code = output.status.code().unwrap_or(-1);
-                Err(format!("upgrade.sh failed (exit {code}):\n{combined}"))
-            }
-        }
-        _ => Err("unknown tool".into()),
+    #[test]
+    fn subagent_sessions_are_unique_and_attributed() {
+        set_agent_session(None);
+        let first = subagent_session_id("worker", &new_subagent_run_id(), Some(0));
+        let second = subagent_session_id("worker", &new_subagent_run_id(), Some(0));
+        assert_ne!(first, second);
+        assert!(first.starts_with("subagent:worker:"));
+        assert!(first.ends_with(":0"));
+
+        set_agent_session(Some("telegram:42".to_string()));
+        let nested = subagent_session_id("worker", &new_subagent_run_id(), None);
+        assert!(nested.starts_with("telegram:42>subagent:worker:"));
+        set_agent_session(None);
     }
 }
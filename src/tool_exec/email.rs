@@ -0,0 +1,328 @@
+//! Himalaya-backed email tools.
+
+use super::*;
+
+pub(crate) struct EmailAccountsTool;
+
+impl Tool for EmailAccountsTool {
+    fn name(&self) -> &str {
+        "email_accounts"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_accounts",
+            "description": "List the email accounts configured in Himalaya and which one the email_* tools use by default.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {}
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, _args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let accounts = himalaya_accounts()?;
+        let configured = load_agent_config(db, &workspace_override).default_email_account;
+        let default = configured
+            .clone()
+            .or_else(|| accounts.iter().find(|(_, d)| *d).map(|(n, _)| n.clone()));
+        let names: Vec<&str> = accounts.iter().map(|(n, _)| n.as_str()).collect();
+        Ok(ToolExecution {
+            output: format!(
+                "{} email account(s): {} (default: {})",
+                names.len(),
+                names.join(", "),
+                default.as_deref().unwrap_or("none")
+            ),
+            details: serde_json::json!({
+                "accounts": accounts
+                    .iter()
+                    .map(|(name, himalaya_default)| serde_json::json!({
+                        "name": name,
+                        "himalaya_default": himalaya_default,
+                        "default": default.as_deref() == Some(name.as_str()),
+                    }))
+                    .collect::<Vec<_>>(),
+                "default": default,
+                "default_source": if configured.is_some() { "agent.default_email_account" } else { "himalaya" },
+            }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct EmailListTool;
+
+impl Tool for EmailListTool {
+    fn name(&self) -> &str {
+        "email_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_list",
+            "description": "List email envelopes via Himalaya.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "account": { "type": "string" },
+                    "folder": { "type": "string" },
+                    "limit": { "type": "integer" }
+                }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolEmailListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let account = resolve_email_account(parsed.account, db, &workspace_override);
+        let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+        cmd.arg("envelope").arg("list").arg("--output").arg("json");
+        if let Some(limit) = parsed.limit {
+            cmd.arg("--limit").arg(limit.to_string());
+        }
+        if let Some(folder) = parsed.folder {
+            cmd.arg("--folder").arg(folder);
+        }
+        if let Some(account) = &account {
+            cmd.arg("--account").arg(account);
+        }
+        let stdout = run_himalaya(&mut cmd, None)?;
+        let details = serde_json::from_str(&stdout)
+            .unwrap_or_else(|_| serde_json::json!({ "raw": stdout }));
+        Ok(ToolExecution {
+            output: format!("Listed envelopes (account: {}).", account_label(&account)),
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct EmailReadTool;
+
+impl Tool for EmailReadTool {
+    fn name(&self) -> &str {
+        "email_read"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_read",
+            "description": "Read a full message via Himalaya.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "account": { "type": "string" },
+                    "folder": { "type": "string" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolEmailReadArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let account = resolve_email_account(parsed.account, db, &workspace_override);
+        let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+        cmd.arg("message")
+            .arg("read")
+            .arg(parsed.id)
+            .arg("--output")
+            .arg("json");
+        if let Some(folder) = parsed.folder {
+            cmd.arg("--folder").arg(folder);
+        }
+        if let Some(account) = &account {
+            cmd.arg("--account").arg(account);
+        }
+        let stdout = run_himalaya(&mut cmd, None)?;
+        let details = serde_json::from_str(&stdout)
+            .unwrap_or_else(|_| serde_json::json!({ "raw": stdout }));
+        Ok(ToolExecution {
+            output: format!("Read message (account: {}).", account_label(&account)),
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct EmailSendTool;
+
+impl Tool for EmailSendTool {
+    fn name(&self) -> &str {
+        "email_send"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_send",
+            "description": "Send an email via Himalaya template.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": { "type": "string" },
+                    "cc": { "type": "string" },
+                    "bcc": { "type": "string" },
+                    "subject": { "type": "string" },
+                    "body": { "type": "string" },
+                    "from": { "type": "string" },
+                    "in_reply_to": { "type": "string" },
+                    "references": { "type": "string" },
+                    "account": { "type": "string", "description": "Himalaya account to send from (see email_accounts)" },
+                    "attachments": { "type": "array", "items": { "type": "string" }, "description": "Paths of files to attach (within the allowed filesystem roots; 20 MB total)" }
+                },
+                "required": ["to", "subject", "body"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolEmailSendArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        send_email(parsed, db, &workspace_override)
+    }
+}
+
+pub(crate) struct EmailReplyTool;
+
+impl Tool for EmailReplyTool {
+    fn name(&self) -> &str {
+        "email_reply"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_reply",
+            "description": "Reply to a message via Himalaya, keeping the thread (Re: subject, In-Reply-To, References). reply_all also copies the original To/Cc.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Himalaya id of the message to answer" },
+                    "body": { "type": "string" },
+                    "reply_all": { "type": "boolean" },
+                    "attachments": { "type": "array", "items": { "type": "string" } },
+                    "from": { "type": "string", "description": "Your address; also dropped from reply-all recipients" },
+                    "account": { "type": "string" },
+                    "folder": { "type": "string" }
+                },
+                "required": ["id", "body"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolEmailReplyArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let account = resolve_email_account(parsed.account.clone(), db, &workspace_override);
+        let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+        cmd.arg("message").arg("read").arg(&parsed.id);
+        for header in ["Message-ID", "References", "From", "Reply-To", "To", "Cc", "Subject"] {
+            cmd.arg("--header").arg(header);
+        }
+        if let Some(folder) = &parsed.folder {
+            cmd.arg("--folder").arg(folder);
+        }
+        if let Some(account) = &account {
+            cmd.arg("--account").arg(account);
+        }
+        let original = run_himalaya(&mut cmd, None)?;
+        let reply = build_email_reply(&parse_email_headers(&original), parsed.reply_all, parsed.from.as_deref())?;
+        let mut sent = send_email(
+            ToolEmailSendArgs {
+                to: reply.to,
+                cc: reply.cc,
+                bcc: None,
+                subject: reply.subject,
+                body: parsed.body,
+                from: parsed.from,
+                in_reply_to: reply.in_reply_to,
+                references: reply.references,
+                account,
+                attachments: parsed.attachments,
+            },
+            db,
+            &workspace_override,
+        )?;
+        sent.output = format!("Replied to message {}. {}", parsed.id, sent.output);
+        Ok(sent)
+    }
+}
+
+pub(crate) struct EmailArchiveTool;
+
+impl Tool for EmailArchiveTool {
+    fn name(&self) -> &str {
+        "email_archive"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "email_archive",
+            "description": "Archive an email (move to Archive) via Himalaya.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "account": { "type": "string" },
+                    "folder": { "type": "string" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolEmailArchiveArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let account = resolve_email_account(parsed.account, db, &workspace_override);
+        let mut cmd = build_external_command(&external_bin("himalaya"), &[]);
+        cmd.arg("message").arg("move").arg(parsed.id).arg("Archive");
+        if let Some(folder) = parsed.folder {
+            cmd.arg("--folder").arg(folder);
+        }
+        if let Some(account) = &account {
+            cmd.arg("--account").arg(account);
+        }
+        run_himalaya(&mut cmd, None)?;
+        Ok(ToolExecution {
+            output: format!("Archived email (account: {}).", account_label(&account)),
+            details: serde_json::json!({ "status": "archived", "account": account }),
+            is_error: false,
+        })
+    }
+}
@@ -0,0 +1,149 @@
+//! Shell command execution.
+
+use super::*;
+
+pub(crate) struct ExecTool;
+
+impl Tool for ExecTool {
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "exec",
+            "description": "Execute a shell command. Default timeout: 2 minutes. SSH commands auto-timeout at 60s. Build commands (cargo, npm, make) get 5 minutes. Set timeout_ms to override. Use background=true for commands expected to run >5 minutes. Do NOT use exec to spawn LLM processes (codex, ollama) — use subagent_invoke or subagent_batch instead.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Shell command to execute. For LLM delegation, use subagent_invoke instead." },
+                    "cwd": { "type": "string" },
+                    "timeout_ms": { "type": "integer", "description": "Hard timeout in ms. Default: 120000 (2min). Max: 600000 (10min). SSH auto-gets 60s, builds auto-get 300s. Use background=true for longer." },
+                    "estimated_ms": { "type": "integer", "description": "Expected runtime in ms. Helps the system choose appropriate monitoring." },
+                    "background": { "type": "boolean", "description": "Run in background job queue. Required for commands expected to run >10 minutes. Returns a job ID for status checking." },
+                    "max_output_bytes": { "type": "integer", "description": "Per-stream capture limit for stdout/stderr, at most the operator limit. Default: 262144 (256KB). Excess output is dropped with a truncation marker." }
+                },
+                "required": ["command"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let cancel = ctx.cancel;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolExecArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let policy = match parsed.timeout_ms {
+            Some(ms) => ExecPolicy {
+                hard_timeout_ms: ms,
+                stale_threshold_ms: 180_000,  // default stale for explicit timeout
+            },
+            None => classify_exec_policy(&parsed.command),
+        };
+        // Without an explicit cwd, run inside the first allowed fs root rather
+        // than wherever the host process happens to be.
+        let cwd = parsed.cwd.clone().or_else(|| {
+            allowed_fs_roots(&workspace_override)
+                .into_iter()
+                .find(|root| root.is_dir())
+                .map(|root| root.display().to_string())
+        });
+        let estimated_ms = parsed.estimated_ms.unwrap_or(policy.hard_timeout_ms);
+        let is_codex_session = parsed.command.to_ascii_lowercase().starts_with("codex ");
+        let should_background = parsed.background.unwrap_or(false)
+            || (is_codex_session && estimated_ms >= EXEC_BACKGROUND_THRESHOLD_MS);
+
+        // Codex-in-exec detection guardrail
+        let codex_warning = if parsed.command.contains("codex exec") || parsed.command.contains("codex --full-auto") {
+            Some("[ROUTING HINT: This command delegates to an LLM. Consider using subagent_invoke(name=\"<descriptive-name>\", prompt=\"...\") instead, which provides better timeout handling and memory access. Continuing with exec as requested.]\n\n")
+        } else {
+            None
+        };
+
+        if should_background {
+            let response = submit_exec_background_job(
+                &parsed.command,
+                cwd.as_ref(),
+                policy.hard_timeout_ms,
+                estimated_ms,
+            )?;
+            let job_id = response
+                .get("id")
+                .and_then(|value| value.as_str())
+                .unwrap_or("unknown");
+            let status_url = response
+                .get("status_url")
+                .and_then(|value| value.as_str())
+                .map(std::borrow::ToOwned::to_owned)
+                .unwrap_or_else(|| format!("/jobs/{job_id}/status"));
+            let details = serde_json::json!({
+                "background": true,
+                "job_id": job_id,
+                "status_url": status_url,
+                "estimated_ms": estimated_ms,
+                "timeout_ms": policy.hard_timeout_ms
+            });
+            let mut output = format!("background job started: {job_id}");
+            if let Some(warning) = codex_warning {
+                output = format!("{warning}{output}");
+            }
+            return Ok(ToolExecution {
+                output,
+                details,
+                is_error: false,
+            });
+        }
+
+        // SSH hardening: inject safety flags before spawning
+        let hardened_command = harden_ssh_in_command(&parsed.command);
+
+        let command = if cfg!(windows) {
+            vec!["cmd".to_string(), "/C".to_string(), hardened_command]
+        } else {
+            vec!["sh".to_string(), "-c".to_string(), hardened_command]
+        };
+        let mut cmd = build_external_command(&command[0], &command[1..]);
+        scrub_command_env(&mut cmd);
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = cmd.spawn().map_err(|e| format!("exec spawn: {e}"))?;
+        let max_output_bytes = exec_output_cap(parsed.max_output_bytes);
+        let result = wait_for_child_monitored(
+            &mut child,
+            "exec",
+            cancel,
+            &policy,
+            max_output_bytes,
+        )?;
+        let stdout = result.stdout;
+        let stderr = result.stderr;
+        let is_error = !result.status.success();
+        let exit_code = subprocess_exit_info(&result.status);
+        let details = serde_json::json!({
+            "exit_code": exit_code,
+            "stdout": stdout,
+            "stderr": stderr,
+            "stdout_bytes": result.stdout_bytes,
+            "stderr_bytes": result.stderr_bytes,
+            "truncated": result.truncated,
+            "max_output_bytes": max_output_bytes
+        });
+        let mut output_text = subprocess_output_text(&stdout, &stderr, is_error);
+        if let Some(warning) = codex_warning {
+            output_text = format!("{warning}{output_text}");
+        }
+        Ok(ToolExecution {
+            output: output_text,
+            details,
+            is_error,
+        })
+    }
+}
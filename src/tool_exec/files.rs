@@ -0,0 +1,159 @@
+//! Filesystem access within the allowed roots.
+
+use super::*;
+
+pub(crate) struct FsListTool;
+
+impl Tool for FsListTool {
+    fn name(&self) -> &str {
+        "fs_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "fs_list",
+            "description": "List files within allowed roots.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "recursive": { "type": "boolean" },
+                    "max_entries": { "type": "integer" }
+                },
+                "required": ["path"]
+            }
+        })
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolFsListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let roots = allowed_fs_roots(&workspace_override);
+        let resolved = resolve_fs_path(&parsed.path, &roots)?;
+        let mut items = Vec::new();
+        let max_entries = parsed.max_entries.unwrap_or(200);
+        if parsed.recursive.unwrap_or(false) {
+            for entry in WalkDir::new(&resolved).max_depth(6) {
+                let entry = entry.map_err(|e| e.to_string())?;
+                if items.len() >= max_entries {
+                    break;
+                }
+                items.push(entry.path().display().to_string());
+            }
+        } else if resolved.is_dir() {
+            for entry in fs::read_dir(&resolved).map_err(|e| e.to_string())? {
+                let entry = entry.map_err(|e| e.to_string())?;
+                items.push(entry.path().display().to_string());
+                if items.len() >= max_entries {
+                    break;
+                }
+            }
+        } else if resolved.exists() {
+            items.push(resolved.display().to_string());
+        }
+        Ok(ToolExecution {
+            output: format!("Listed {} entries.", items.len()),
+            details: serde_json::json!({ "entries": items }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct FsReadTool;
+
+impl Tool for FsReadTool {
+    fn name(&self) -> &str {
+        "fs_read"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "fs_read",
+            "description": "Read a file within allowed roots.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "max_bytes": { "type": "integer" }
+                },
+                "required": ["path"]
+            }
+        })
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolFsReadArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let roots = allowed_fs_roots(&workspace_override);
+        let resolved = resolve_fs_path(&parsed.path, &roots)?;
+        let max_bytes = parsed.max_bytes.unwrap_or(200_000);
+        let file = fs::File::open(&resolved).map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        file.take(max_bytes as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| e.to_string())?;
+        let text = String::from_utf8_lossy(&buf).to_string();
+        Ok(ToolExecution {
+            output: format!("Read {} bytes.", buf.len()),
+            details: serde_json::json!({
+                "path": resolved.display().to_string(),
+                "text": text
+            }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct FsWriteTool;
+
+impl Tool for FsWriteTool {
+    fn name(&self) -> &str {
+        "fs_write"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "fs_write",
+            "description": "Write a file within allowed roots.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "text": { "type": "string" },
+                    "append": { "type": "boolean" }
+                },
+                "required": ["path", "text"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolFsWriteArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let roots = allowed_fs_roots(&workspace_override);
+        let resolved = resolve_fs_path(&parsed.path, &roots)?;
+        if parsed.append.unwrap_or(false) {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&resolved)
+                .map_err(|e| e.to_string())?;
+            file.write_all(parsed.text.as_bytes())
+                .map_err(|e| e.to_string())?;
+        } else {
+            fs::write(&resolved, parsed.text.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        Ok(ToolExecution {
+            output: "File written.".to_string(),
+            details: serde_json::json!({ "path": resolved.display().to_string() }),
+            is_error: false,
+        })
+    }
+}
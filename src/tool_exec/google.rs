@@ -0,0 +1,230 @@
+//! Gmail and Google Calendar.
+
+use super::*;
+
+pub(crate) struct GmailListTool;
+
+impl Tool for GmailListTool {
+    fn name(&self) -> &str {
+        "gmail_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "gmail_list",
+            "description": "List Gmail messages (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "max_results": { "type": "integer" }
+                }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolGmailListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let mut url = format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults={}",
+            parsed.max_results.unwrap_or(10)
+        );
+        if let Some(q) = parsed.query {
+            url.push_str("&q=");
+            url.push_str(&urlencoding::encode(&q));
+        }
+        let payload = oauth_api_get(mv2, "google", &url, "gmail_list")?;
+        Ok(ToolExecution {
+            output: "Gmail messages listed.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct GmailReadTool;
+
+impl Tool for GmailReadTool {
+    fn name(&self) -> &str {
+        "gmail_read"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "gmail_read",
+            "description": "Read a Gmail message by id (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "string" } },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolGmailReadArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let url = format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=full",
+            parsed.id
+        );
+        let payload = oauth_api_get(mv2, "google", &url, "gmail_read")?;
+        Ok(ToolExecution {
+            output: "Gmail message read.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct GmailSendTool;
+
+impl Tool for GmailSendTool {
+    fn name(&self) -> &str {
+        "gmail_send"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "gmail_send",
+            "description": "Send a Gmail message (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": { "type": "string" },
+                    "subject": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["to", "subject", "body"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolGmailSendArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let raw = format!(
+            "To: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+            parsed.to, parsed.subject, parsed.body
+        );
+        let encoded = base64::engine::general_purpose::STANDARD
+            .encode(raw.as_bytes())
+            .replace('+', "-")
+            .replace('/', "_")
+            .trim_end_matches('=')
+            .to_string();
+        let payload = serde_json::json!({ "raw": encoded });
+        let details = oauth_api_post(
+            mv2, "google",
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/send",
+            payload, "gmail_send",
+        )?;
+        Ok(ToolExecution {
+            output: "Gmail message sent.".to_string(),
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct GcalListTool;
+
+impl Tool for GcalListTool {
+    fn name(&self) -> &str {
+        "gcal_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "gcal_list",
+            "description": "List Google Calendar events (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "max_results": { "type": "integer" } }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolGCalListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let url = format!(
+            "https://www.googleapis.com/calendar/v3/calendars/primary/events?maxResults={}",
+            parsed.max_results.unwrap_or(10)
+        );
+        let payload = oauth_api_get(mv2, "google", &url, "gcal_list")?;
+        Ok(ToolExecution {
+            output: "Calendar events listed.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct GcalCreateTool;
+
+impl Tool for GcalCreateTool {
+    fn name(&self) -> &str {
+        "gcal_create"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "gcal_create",
+            "description": "Create a Google Calendar event on primary calendar (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "summary": { "type": "string" },
+                    "start": { "type": "string" },
+                    "end": { "type": "string" },
+                    "description": { "type": "string" }
+                },
+                "required": ["summary", "start", "end"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolGCalCreateArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let payload = serde_json::json!({
+            "summary": parsed.summary,
+            "description": parsed.description,
+            "start": { "dateTime": parsed.start },
+            "end": { "dateTime": parsed.end }
+        });
+        let details = oauth_api_post(
+            mv2, "google",
+            "https://www.googleapis.com/calendar/v3/calendars/primary/events",
+            payload, "gcal_create",
+        )?;
+        Ok(ToolExecution {
+            output: "Calendar event created.".to_string(),
+            details,
+            is_error: false,
+        })
+    }
+}
@@ -0,0 +1,901 @@
+//! Capsule search, frame and memory-file tools.
+
+use super::*;
+
+pub(crate) struct QueryTool;
+
+impl Tool for QueryTool {
+    fn name(&self) -> &str {
+        "query"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "query",
+            "description": "Hybrid search over the capsule (expansion + fusion + rerank).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "collection": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "snippet_chars": { "type": "integer" },
+                    "no_expand": { "type": "boolean" },
+                    "max_expansions": { "type": "integer" },
+                    "no_vector": { "type": "boolean" },
+                    "rerank": { "type": "string" },
+                    "asof": { "type": "string" },
+                    "before": { "type": "string" },
+                    "after": { "type": "string" },
+                    "feedback_weight": { "type": "number" },
+                    "recency_halflife_days": { "type": "number" }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolQueryArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let qargs = QueryArgs {
+            raw_query: parsed.query.clone(),
+            collection: parsed.collection,
+            limit: parsed.limit.unwrap_or(10),
+            snippet_chars: parsed.snippet_chars.unwrap_or(300),
+            no_expand: parsed.no_expand.unwrap_or(false),
+            max_expansions: parsed.max_expansions.unwrap_or(2),
+            expand_hook: None,
+            expand_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
+            no_vector: parsed.no_vector.unwrap_or(false),
+            rerank: parsed.rerank.unwrap_or_else(|| "local".to_string()),
+            rerank_hook: None,
+            rerank_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
+            rerank_hook_full_text: false,
+            embed_model: None,
+            embed_cache: 4096,
+            embed_no_cache: false,
+            rerank_docs: 40,
+            rerank_weight: None,
+            rerank_chunk_chars: 1200,
+            rerank_chunk_overlap: 200,
+            plan: false,
+            asof: parsed.asof,
+            before: parsed.before,
+            after: parsed.after,
+            feedback_weight: parsed.feedback_weight.unwrap_or(0.15),
+            recency_halflife_days: parsed.recency_halflife_days.unwrap_or(0.0),
+            hook_debug: false,
+            vec_debug: false,
+            plain_snippets: false,
+            raw_snippets: false,
+            snippet_ellipsis: SNIPPET_ELLIPSIS.to_string(),
+            explain: false,
+            no_skip_expansion_probe: false,
+            fast_path_margin: DEFAULT_FAST_PATH_MARGIN,
+            fast_path: false,
+            search_jobs: DEFAULT_SEARCH_JOBS,
+        };
+        let response = execute_query(db, qargs).map_err(|e| e.to_string())?;
+        let mut lines = Vec::new();
+        for r in response.results.iter().take(5) {
+            lines.push(format!("{}. {} ({:.3})", r.rank, r.uri, r.score));
+        }
+        let output = if lines.is_empty() {
+            "No results.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct ContextTool;
+
+impl Tool for ContextTool {
+    fn name(&self) -> &str {
+        "context"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "context",
+            "description": "Build a prompt-ready context pack from the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "collection": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "snippet_chars": { "type": "integer" },
+                    "max_bytes": { "type": "integer" },
+                    "full": { "type": "boolean" },
+                    "window": { "type": "integer", "description": "Add this many neighbouring chunks on each side of every citation's match." },
+                    "format": { "type": "string", "enum": ["plain", "markdown", "xml"] },
+                    "no_expand": { "type": "boolean" },
+                    "max_expansions": { "type": "integer" },
+                    "no_vector": { "type": "boolean" },
+                    "rerank": { "type": "string" },
+                    "asof": { "type": "string" },
+                    "before": { "type": "string" },
+                    "after": { "type": "string" },
+                    "feedback_weight": { "type": "number" },
+                    "recency_halflife_days": { "type": "number" }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolContextArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let qargs = QueryArgs {
+            raw_query: parsed.query.clone(),
+            collection: parsed.collection,
+            limit: parsed.limit.unwrap_or(10),
+            snippet_chars: parsed.snippet_chars.unwrap_or(300),
+            no_expand: parsed.no_expand.unwrap_or(false),
+            max_expansions: parsed.max_expansions.unwrap_or(2),
+            expand_hook: None,
+            expand_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
+            no_vector: parsed.no_vector.unwrap_or(false),
+            rerank: parsed.rerank.unwrap_or_else(|| "local".to_string()),
+            rerank_hook: None,
+            rerank_hook_timeout_ms: DEFAULT_HTTP_TIMEOUT_MS,
+            rerank_hook_full_text: false,
+            embed_model: None,
+            embed_cache: 4096,
+            embed_no_cache: false,
+            rerank_docs: parsed.limit.unwrap_or(10).max(20),
+            rerank_weight: None,
+            rerank_chunk_chars: 1200,
+            rerank_chunk_overlap: 200,
+            plan: false,
+            asof: parsed.asof,
+            before: parsed.before,
+            after: parsed.after,
+            feedback_weight: parsed.feedback_weight.unwrap_or(0.15),
+            recency_halflife_days: parsed.recency_halflife_days.unwrap_or(0.0),
+            hook_debug: false,
+            vec_debug: false,
+            plain_snippets: false,
+            raw_snippets: false,
+            snippet_ellipsis: SNIPPET_ELLIPSIS.to_string(),
+            explain: false,
+            no_skip_expansion_probe: false,
+            fast_path_margin: DEFAULT_FAST_PATH_MARGIN,
+            fast_path: false,
+            search_jobs: DEFAULT_SEARCH_JOBS,
+        };
+        let pack = build_context_pack(
+            db,
+            qargs,
+            parsed.max_bytes.unwrap_or(12_000),
+            parsed.full.unwrap_or(false),
+            parsed.window.unwrap_or(0),
+            parsed.format.unwrap_or_default(),
+        )
+        .map_err(|e| e.to_string())?;
+        let output = pack.context.clone();
+        let details = serde_json::to_value(pack).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SearchTool;
+
+impl Tool for SearchTool {
+    fn name(&self) -> &str {
+        "search"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "search",
+            "description": "Lexical search over the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "collection": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "snippet_chars": { "type": "integer" }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolSearchArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let scope = parsed.collection.as_deref().map(scope_prefix);
+        let request = SearchRequest {
+            query: parsed.query.clone(),
+            top_k: parsed.limit.unwrap_or(10),
+            snippet_chars: parsed.snippet_chars.unwrap_or(300),
+            scope,
+            temporal: None,
+            as_of_frame: None,
+            as_of_ts: None,
+        };
+        let response = db.search(request).map_err(|e| e.to_string())?;
+        let mut lines = Vec::new();
+        for hit in response.hits.iter().take(5) {
+            let title = hit.title.clone().unwrap_or_default();
+            lines.push(format!("{}. {} {}", hit.rank, hit.uri, title));
+        }
+        let output = if lines.is_empty() {
+            "No results.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct GetTool;
+
+impl Tool for GetTool {
+    fn name(&self) -> &str {
+        "get"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "get",
+            "description": "Fetch a document by URI or frame id (#123). A partial URI resolves when it matches one document; otherwise candidates are listed.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolGetArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let frame = match lookup_frame(db, &parsed.id)? {
+            FrameLookup::Found(frame) => frame,
+            FrameLookup::Ambiguous(candidates) => {
+                return Ok(ToolExecution {
+                    output: format!(
+                        "No frame is exactly {}; {} candidates:\n{}",
+                        parsed.id,
+                        candidates.len(),
+                        format_frame_candidates(&candidates)
+                    ),
+                    details: serde_json::json!({ "candidates": candidates }),
+                    is_error: false,
+                });
+            }
+        };
+        let frame_id = frame.id;
+        let text = db.frame_text_by_id(frame_id).unwrap_or_default();
+        let details = serde_json::json!({
+            "frame_id": frame_id,
+            "uri": frame.uri,
+            "title": frame.title,
+            "text": text
+        });
+        let output = if details["text"].as_str().unwrap_or("").is_empty() {
+            format!("Frame #{frame_id} (non-text payload)")
+        } else {
+            details["text"].as_str().unwrap_or("").to_string()
+        };
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SimilarTool;
+
+impl Tool for SimilarTool {
+    fn name(&self) -> &str {
+        "similar"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "similar",
+            "description": "Find documents most like a given one (URI or frame id #123), excluding itself.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "collection": { "type": "string" },
+                    "limit": { "type": "integer" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolSimilarArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let response = find_similar(
+            db,
+            &parsed.id,
+            parsed.limit.unwrap_or(10),
+            parsed.collection.as_deref(),
+            300,
+        )?;
+        let mut lines = Vec::new();
+        for hit in response.hits.iter().take(5) {
+            let title = hit.title.clone().unwrap_or_default();
+            lines.push(format!("{}. {} {}", hit.rank, hit.uri, title));
+        }
+        let output = if lines.is_empty() {
+            format!("No documents similar to {}.", response.source_uri)
+        } else {
+            lines.join("\n")
+        };
+        let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct PutTool;
+
+impl Tool for PutTool {
+    fn name(&self) -> &str {
+        "put"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "put",
+            "description": "Store a text payload into the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "uri": { "type": "string" },
+                    "title": { "type": "string" },
+                    "text": { "type": "string" },
+                    "kind": { "type": "string" },
+                    "track": { "type": "string" }
+                },
+                "required": ["uri", "text"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolPutArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let Some(text) = parsed.text else {
+            return Err("put requires text".into());
+        };
+        let mut options = PutOptions::default();
+        options.uri = Some(parsed.uri.clone());
+        options.title = Some(parsed.title.unwrap_or_else(|| parsed.uri.clone()));
+        options.track = parsed.track;
+        options.kind = parsed.kind;
+        options.search_text = Some(text.clone());
+
+        // Use consolidation only when URI is new (existing URI supersede handles dupes)
+        let uri_exists = db.frame_by_uri(&parsed.uri).is_ok();
+        let (frame_id, decision_str) = if uri_exists {
+            let fid = db
+                .put_bytes_with_options(text.as_bytes(), options)
+                .map_err(|e| e.to_string())?;
+            (fid, "supersede".to_string())
+        } else {
+            let result = put_with_consolidation(db, text.as_bytes(), options)
+                .map_err(|e| e.to_string())?;
+            match result.decision {
+                ConsolidationDecision::Noop { existing_id } => {
+                    db.commit().map_err(|e| e.to_string())?;
+                    let details = serde_json::json!({
+                        "frame_id": existing_id,
+                        "uri": parsed.uri,
+                        "decision": "noop"
+                    });
+                    return Ok(ToolExecution {
+                        output: format!("Deduplicated (similar to frame #{existing_id})"),
+                        details,
+                        is_error: false,
+                    });
+                }
+                _ => (
+                    result.frame_id.unwrap_or(0),
+                    format!("{:?}", result.decision),
+                ),
+            }
+        };
+        db.commit().map_err(|e| e.to_string())?;
+        let details = serde_json::json!({
+            "frame_id": frame_id,
+            "uri": parsed.uri,
+            "decision": decision_str
+        });
+        let output = format!("Stored frame #{frame_id}");
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct LogTool;
+
+impl Tool for LogTool {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "log",
+            "description": "Append an agent turn to the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session": { "type": "string" },
+                    "role": { "type": "string" },
+                    "text": { "type": "string" },
+                    "meta": { "type": "object" }
+                },
+                "required": ["text"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolLogArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let entry = AgentLogEntry {
+            session: parsed.session.clone(),
+            role: parsed.role.unwrap_or_else(|| "user".to_string()),
+            text: parsed.text.clone(),
+            meta: parsed.meta.clone(),
+            ts_utc: Some(Utc::now().timestamp()),
+        };
+        let uri = append_agent_log(db, &entry).map_err(|e| e.to_string())?;
+        let details = serde_json::json!({ "uri": uri });
+        Ok(ToolExecution {
+            output: "Logged agent turn.".to_string(),
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct FeedbackTool;
+
+impl Tool for FeedbackTool {
+    fn name(&self) -> &str {
+        "feedback"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "feedback",
+            "description": "Store feedback for a URI (range -1.0 to 1.0).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "uri": { "type": "string" },
+                    "score": { "type": "number" },
+                    "note": { "type": "string" },
+                    "session": { "type": "string" }
+                },
+                "required": ["uri", "score"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolFeedbackArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let event = FeedbackEvent {
+            uri: parsed.uri.clone(),
+            score: parsed.score.clamp(-1.0, 1.0),
+            note: parsed.note.clone(),
+            session: parsed.session.clone(),
+            ts_utc: Some(Utc::now().timestamp()),
+        };
+        let uri_log = append_feedback(db, &event)?;
+        let details = serde_json::json!({ "uri": uri_log });
+        Ok(ToolExecution {
+            output: "Feedback recorded.".to_string(),
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct ConfigSetTool;
+
+impl Tool for ConfigSetTool {
+    fn name(&self) -> &str {
+        "config_set"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "config_set",
+            "description": "Set a config JSON document at aethervault://config/<key>.json.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "key": { "type": "string" },
+                    "json": { "type": "object" }
+                },
+                "required": ["key", "json"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolConfigSetArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        save_config_to_file(&workspace, &parsed.key, parsed.json.clone())
+            .map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output: format!("Config saved to file ({})", parsed.key),
+            details: serde_json::json!({ "file": workspace.join("config.json") }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MemoryAppendDailyTool;
+
+impl Tool for MemoryAppendDailyTool {
+    fn name(&self) -> &str {
+        "memory_append_daily"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "memory_append_daily",
+            "description": "Append a line to the daily memory log (workspace) and store in the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" },
+                    "date": { "type": "string" }
+                },
+                "required": ["text"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolMemoryAppendArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let date = parsed
+            .date
+            .unwrap_or_else(|| Utc::now().format("%Y-%m-%d").to_string());
+        let dir = workspace.join("memory");
+        fs::create_dir_all(&dir).map_err(|e| format!("workspace: {e}"))?;
+        let path = dir.join(format!("{date}.md"));
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("memory open: {e}"))?;
+        writeln!(file, "{}", parsed.text).map_err(|e| format!("memory write: {e}"))?;
+        let uri = format!("aethervault://memory/daily/{date}.md");
+        let mut options = PutOptions::default();
+        options.uri = Some(uri.clone());
+        options.title = Some(format!("memory daily {date}"));
+        options.kind = Some("text/markdown".to_string());
+        options.track = Some("aethervault.memory".to_string());
+        options.search_text = Some(parsed.text.clone());
+        let frame_id = db
+            .put_bytes_with_options(parsed.text.as_bytes(), options)
+            .map_err(|e| e.to_string())?;
+        db.commit().map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output: format!("Appended to {}", path.display()),
+            details: serde_json::json!({
+                "path": path.display().to_string(),
+                "uri": uri,
+                "frame_id": frame_id
+            }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MemoryRememberTool;
+
+impl Tool for MemoryRememberTool {
+    fn name(&self) -> &str {
+        "memory_remember"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "memory_remember",
+            "description": "Append a line to MEMORY.md (workspace) and store in the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" }
+                },
+                "required": ["text"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolMemoryRememberArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        fs::create_dir_all(&workspace).map_err(|e| format!("workspace: {e}"))?;
+        let path = workspace.join("MEMORY.md");
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("memory open: {e}"))?;
+        writeln!(file, "{}", parsed.text).map_err(|e| format!("memory write: {e}"))?;
+        let uri = "aethervault://memory/longterm.md".to_string();
+        let mut options = PutOptions::default();
+        options.uri = Some(uri.clone());
+        options.title = Some("memory longterm".to_string());
+        options.kind = Some("text/markdown".to_string());
+        options.track = Some("aethervault.memory".to_string());
+        options.search_text = Some(parsed.text.clone());
+        let frame_id = db
+            .put_bytes_with_options(parsed.text.as_bytes(), options)
+            .map_err(|e| e.to_string())?;
+        db.commit().map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output: format!("Appended to {}", path.display()),
+            details: serde_json::json!({
+                "path": path.display().to_string(),
+                "uri": uri,
+                "frame_id": frame_id
+            }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MemorySyncTool;
+
+impl Tool for MemorySyncTool {
+    fn name(&self) -> &str {
+        "memory_sync"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "memory_sync",
+            "description": "Sync workspace memory files into the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "workspace": { "type": "string" },
+                    "include_daily": { "type": "boolean" }
+                }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolMemorySyncArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = parsed
+            .workspace
+            .map(PathBuf::from)
+            .or_else(|| workspace_override.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let include_daily = parsed.include_daily.unwrap_or(true);
+        let ids =
+            sync_workspace_memory(mv2, &workspace, include_daily).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output: format!("Synced {} memory files.", ids.len()),
+            details: serde_json::json!({ "frame_ids": ids }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MemoryExportTool;
+
+impl Tool for MemoryExportTool {
+    fn name(&self) -> &str {
+        "memory_export"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "memory_export",
+            "description": "Export capsule memory back to workspace files. Files are stamped with the capsule date; set if_newer to keep local files edited since then.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "workspace": { "type": "string" },
+                    "include_daily": { "type": "boolean" },
+                    "if_newer": { "type": "boolean", "description": "Only overwrite a file when the capsule version is newer than its mtime" }
+                }
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolMemoryExportArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = parsed
+            .workspace
+            .map(PathBuf::from)
+            .or_else(|| workspace_override.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let include_daily = parsed.include_daily.unwrap_or(true);
+        let export = export_capsule_memory(mv2, &workspace, include_daily, parsed.if_newer)
+            .map_err(|e| e.to_string())?;
+        let mut output = format!("Exported {} files.", export.written.len());
+        if !export.skipped.is_empty() {
+            output.push_str(&format!(
+                " Skipped {} with newer local edits: {}",
+                export.skipped.len(),
+                export.skipped.join(", ")
+            ));
+        }
+        Ok(ToolExecution {
+            output,
+            details: serde_json::json!({ "paths": export.written, "skipped": export.skipped }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MemorySearchTool;
+
+impl Tool for MemorySearchTool {
+    fn name(&self) -> &str {
+        "memory_search"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "memory_search",
+            "description": "Search memory stored in the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "scope": {
+                        "type": "string",
+                        "enum": ["memory", "reflections", "skills", "all"],
+                        "description": "memory = aethervault://memory/ (default; includes reflections), reflections = aethervault://memory/reflection/, skills = aethervault://skills/, all = memory + skills"
+                    }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolMemorySearchArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let top_k = parsed.limit.unwrap_or(10);
+        let mut hits = Vec::new();
+        for prefix in memory_search_prefixes(parsed.scope.as_deref().unwrap_or("memory"))? {
+            let request = SearchRequest {
+                query: parsed.query.clone(),
+                top_k,
+                snippet_chars: 300,
+                scope: Some(prefix.to_string()),
+                temporal: None,
+                as_of_frame: None,
+                as_of_ts: None,
+            };
+            hits.extend(db.search(request).map_err(|e| e.to_string())?.hits);
+        }
+        // Merge the per-prefix results by score and renumber.
+        hits.sort_by(|a, b| b.score.unwrap_or(0.0).total_cmp(&a.score.unwrap_or(0.0)));
+        hits.truncate(top_k);
+        for (i, hit) in hits.iter_mut().enumerate() {
+            hit.rank = i + 1;
+        }
+        let response = SearchResponse { hits };
+        let mut lines = Vec::new();
+        for hit in response.hits.iter().take(5) {
+            let title = hit.title.clone().unwrap_or_default();
+            lines.push(format!("{}. {} {}", hit.rank, hit.uri, title));
+        }
+        let output = if lines.is_empty() {
+            "No results.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        let details = serde_json::to_value(response).map_err(|e| e.to_string())?;
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
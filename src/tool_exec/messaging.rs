@@ -0,0 +1,177 @@
+//! Outbound notifications and messages.
+
+use super::*;
+
+pub(crate) struct NotifyTool;
+
+impl Tool for NotifyTool {
+    fn name(&self) -> &str {
+        "notify"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "notify",
+            "description": "Send a notification to Slack/Discord/Teams via webhook.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "channel": { "type": "string" },
+                    "text": { "type": "string" },
+                    "webhook": { "type": "string" }
+                },
+                "required": ["text"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolNotifyArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let channel = parsed
+            .channel
+            .unwrap_or_else(|| "slack".to_string())
+            .to_ascii_lowercase();
+        let webhook = parsed.webhook.or_else(|| match channel.as_str() {
+            "discord" => env_optional("DISCORD_WEBHOOK_URL"),
+            "teams" => env_optional("TEAMS_WEBHOOK_URL"),
+            _ => env_optional("SLACK_WEBHOOK_URL"),
+        });
+        let Some(webhook) = webhook else {
+            return Err("notify requires webhook url".into());
+        };
+        let payload = match channel.as_str() {
+            "discord" => serde_json::json!({ "content": parsed.text }),
+            "teams" => serde_json::json!({ "text": parsed.text }),
+            _ => serde_json::json!({ "text": parsed.text }),
+        };
+        let agent = make_http_agent(DEFAULT_HTTP_TIMEOUT_MS);
+        let response = http_with_retry(
+            "notify",
+            HttpRetry::ConnectOnly,
+            TOOL_HTTP_ATTEMPTS,
+            TOOL_HTTP_RETRY_BUDGET,
+            HttpBody::Json(&payload),
+            || agent.post(&webhook).set("content-type", "application/json"),
+        );
+        match response {
+            Ok(_) => Ok(ToolExecution {
+                output: "Notification sent.".to_string(),
+                details: serde_json::json!({ "channel": channel }),
+                is_error: false,
+            }),
+            Err(err) => Err(format!("notify error: {err}")),
+        }
+    }
+}
+
+pub(crate) struct SignalSendTool;
+
+impl Tool for SignalSendTool {
+    fn name(&self) -> &str {
+        "signal_send"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "signal_send",
+            "description": "Send a Signal message via signal-cli.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": { "type": "string" },
+                    "text": { "type": "string" },
+                    "sender": { "type": "string" }
+                },
+                "required": ["to", "text"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolSignalSendArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let sender = parsed.sender.or_else(|| env_optional("SIGNAL_SENDER"));
+        let Some(sender) = sender else {
+            return Err("signal_send requires sender".into());
+        };
+        crate::bridges::signal::send_signal_message(&sender, &parsed.to, None, &parsed.text)?;
+        Ok(ToolExecution {
+            output: "Signal message sent.".to_string(),
+            details: serde_json::json!({ "status": "sent" }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct ImessageSendTool;
+
+impl Tool for ImessageSendTool {
+    fn name(&self) -> &str {
+        "imessage_send"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "imessage_send",
+            "description": "Send an iMessage (macOS only).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": { "type": "string" },
+                    "text": { "type": "string" }
+                },
+                "required": ["to", "text"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolIMessageSendArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        if !cfg!(target_os = "macos") {
+            return Err("imessage_send requires macOS".into());
+        }
+        let script = format!(
+            "tell application \"Messages\" to send \"{}\" to buddy \"{}\"",
+            parsed.text.replace('"', "\\\""),
+            parsed.to.replace('"', "\\\"")
+        );
+        let mut cmd = build_external_command(&external_bin("osascript"), &[]);
+        cmd.arg("-e").arg(script);
+        let output = cmd.output().map_err(|e| format!("osascript: {e}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(format!("osascript error: {stderr}"));
+        }
+        Ok(ToolExecution {
+            output: "iMessage sent.".to_string(),
+            details: serde_json::json!({ "status": "sent" }),
+            is_error: false,
+        })
+    }
+}
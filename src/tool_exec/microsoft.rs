@@ -0,0 +1,164 @@
+//! Microsoft Graph mail and calendar.
+
+use super::*;
+
+pub(crate) struct MsMailListTool;
+
+impl Tool for MsMailListTool {
+    fn name(&self) -> &str {
+        "ms_mail_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "ms_mail_list",
+            "description": "List Microsoft mail messages (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "top": { "type": "integer" } }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolMsMailListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let url = format!(
+            "https://graph.microsoft.com/v1.0/me/messages?$top={}",
+            parsed.top.unwrap_or(10)
+        );
+        let payload = oauth_api_get(mv2, "microsoft", &url, "ms_mail_list")?;
+        Ok(ToolExecution {
+            output: "Microsoft mail listed.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MsMailReadTool;
+
+impl Tool for MsMailReadTool {
+    fn name(&self) -> &str {
+        "ms_mail_read"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "ms_mail_read",
+            "description": "Read Microsoft mail message by id (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "string" } },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolMsMailReadArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let url = format!("https://graph.microsoft.com/v1.0/me/messages/{}", parsed.id);
+        let payload = oauth_api_get(mv2, "microsoft", &url, "ms_mail_read")?;
+        Ok(ToolExecution {
+            output: "Microsoft mail read.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MsCalendarListTool;
+
+impl Tool for MsCalendarListTool {
+    fn name(&self) -> &str {
+        "ms_calendar_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "ms_calendar_list",
+            "description": "List Microsoft calendar events (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "top": { "type": "integer" } }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolMsCalendarListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let url = format!(
+            "https://graph.microsoft.com/v1.0/me/events?$top={}",
+            parsed.top.unwrap_or(10)
+        );
+        let payload = oauth_api_get(mv2, "microsoft", &url, "ms_calendar_list")?;
+        Ok(ToolExecution {
+            output: "Microsoft calendar listed.".to_string(),
+            details: payload,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct MsCalendarCreateTool;
+
+impl Tool for MsCalendarCreateTool {
+    fn name(&self) -> &str {
+        "ms_calendar_create"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "ms_calendar_create",
+            "description": "Create Microsoft calendar event (OAuth).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "subject": { "type": "string" },
+                    "start": { "type": "string" },
+                    "end": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["subject", "start", "end"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn accepts_idempotency_key(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let parsed: ToolMsCalendarCreateArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let payload = serde_json::json!({
+            "subject": parsed.subject,
+            "body": {
+                "contentType": "Text",
+                "content": parsed.body.unwrap_or_default()
+            },
+            "start": { "dateTime": parsed.start, "timeZone": "UTC" },
+            "end": { "dateTime": parsed.end, "timeZone": "UTC" }
+        });
+        let details = oauth_api_post(
+            mv2, "microsoft",
+            "https://graph.microsoft.com/v1.0/me/events",
+            payload, "ms_calendar_create",
+        )?;
+        Ok(ToolExecution {
+            output: "Microsoft calendar event created.".to_string(),
+            details,
+            is_error: false,
+        })
+    }
+}
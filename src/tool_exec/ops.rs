@@ -0,0 +1,163 @@
+//! Host operations: scaling, background jobs and upgrades.
+
+use super::*;
+
+pub(crate) struct ScaleTool;
+
+impl Tool for ScaleTool {
+    fn name(&self) -> &str {
+        "scale"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "scale",
+            "description": "Monitor and scale infrastructure resources. Actions: 'status' (CPU/RAM/disk/load), 'sizes' (list available instance sizes with pricing), 'resize' (scale the host up/down, requires size param and approval). Provider is set by AETHERVAULT_SCALE_PROVIDER (digitalocean, local).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["status", "resize", "sizes"]
+                    },
+                    "size": {
+                        "type": "string",
+                        "description": "Target size slug (e.g. s-2vcpu-4gb on DigitalOcean). Required for resize."
+                    }
+                },
+                "required": ["action"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, args: &serde_json::Value) -> bool {
+        args.get("action").and_then(|v| v.as_str()) == Some("resize")
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolScaleArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let provider = scale_provider_from_env()?;
+        match parsed.action.as_str() {
+            "status" => provider.status(),
+            "sizes" => provider.sizes(),
+            "resize" => {
+                let target_size = parsed
+                    .size
+                    .ok_or_else(|| "size parameter is required for resize".to_string())?;
+                provider.resize(&target_size)
+            }
+            other => Err(format!("unknown scale action: {other} (use status, sizes, or resize)")),
+        }
+    }
+}
+
+pub(crate) struct BgStatusTool;
+
+impl Tool for BgStatusTool {
+    fn name(&self) -> &str {
+        "bg_status"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "bg_status",
+            "description": "Check status of background tasks. Returns a traffic-light scorecard of all running, completed, and failed background sub-agents.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "task_id": { "type": "string", "description": "Optional: check a specific task by ID" }
+                }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, _args: serde_json::Value) -> Result<ToolExecution, String> {
+        let bg_registry = &ctx.bg_registry;
+        match bg_registry.as_ref() {
+            Some((chat_id, registry)) => {
+                let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                let scorecard = reg.scorecard(*chat_id);
+                Ok(ToolExecution {
+                    output: scorecard,
+                    details: serde_json::json!(null),
+                    is_error: false,
+                })
+            }
+            None => {
+                Ok(ToolExecution {
+                    output: "No background task registry available (not running in bridge mode).".to_string(),
+                    details: serde_json::json!(null),
+                    is_error: false,
+                })
+            }
+        }
+    }
+}
+
+pub(crate) struct SelfUpgradeTool;
+
+impl Tool for SelfUpgradeTool {
+    fn name(&self) -> &str {
+        "self_upgrade"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "self_upgrade",
+            "description": "Trigger a self-upgrade: pull latest code from git, compile, validate, and hot-swap the binary. Uses blue-green deployment with automatic rollback. Requires approval. IMPORTANT: If you edited source files, you MUST git add, commit, and push your changes BEFORE calling this tool — it does `git reset --hard origin/<branch>` which wipes uncommitted changes.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "branch": { "type": "string", "description": "Git branch to pull from (default: main)" },
+                    "skip_tests": { "type": "boolean", "description": "Skip smoke test (not recommended)" }
+                }
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolSelfUpgradeArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let branch = parsed.branch.as_deref().unwrap_or("main");
+        let skip_tests = parsed.skip_tests.unwrap_or(false);
+        let upgrade_script = "/opt/aethervault/upgrade.sh";
+        if !std::path::Path::new(upgrade_script).exists() {
+            return Err("upgrade.sh not found at /opt/aethervault/upgrade.sh — deploy it first".into());
+        }
+        let mut cmd = std::process::Command::new("bash");
+        cmd.arg(upgrade_script)
+            .arg("--branch").arg(branch);
+        if skip_tests {
+            cmd.arg("--skip-tests");
+        }
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = cmd.output().map_err(|e| format!("failed to run upgrade.sh: {e}"))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let combined = if stderr.is_empty() {
+            stdout.clone()
+        } else {
+            format!("{stdout}\n--- stderr ---\n{stderr}")
+        };
+        if output.status.success() {
+            Ok(ToolExecution {
+                output: format!("Upgrade succeeded (branch: {branch}). Binary hot-swapped. Service will restart momentarily.\n\n{combined}"),
+                details: serde_json::json!({
+                    "branch": branch,
+                    "skip_tests": skip_tests,
+                    "exit_code": 0,
+                }),
+                is_error: false,
+            })
+        } else {
+            let code = output.status.code().unwrap_or(-1);
+            Err(format!("upgrade.sh failed (exit {code}):\n{combined}"))
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Tool discovery and session history.
+
+use super::*;
+
+pub(crate) struct ToolSearchTool;
+
+impl Tool for ToolSearchTool {
+    fn name(&self) -> &str {
+        "tool_search"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "tool_search",
+            "description": "Search available tools by name/description.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer" }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolToolSearchArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let query_tokens: Vec<String> = parsed
+            .query
+            .to_ascii_lowercase()
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        let mut results = Vec::new();
+        for tool in tool_registry().definitions() {
+            let name = tool
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let desc = tool
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let score = tool_score(&query_tokens, &name, &desc);
+            if score > 0 {
+                let mut entry = serde_json::json!({
+                    "name": name,
+                    "description": desc,
+                    "score": score
+                });
+                if is_tool_disabled(&name) {
+                    entry["available"] = serde_json::json!(false);
+                    entry["note"] = serde_json::json!("disabled by the operator");
+                }
+                results.push(entry);
+            }
+        }
+        results.sort_by(|a, b| {
+            b.get("score")
+                .and_then(|v| v.as_i64())
+                .cmp(&a.get("score").and_then(|v| v.as_i64()))
+        });
+        let limit = parsed.limit.unwrap_or(8);
+        let results: Vec<serde_json::Value> = results.into_iter().take(limit).collect();
+        Ok(ToolExecution {
+            output: format!("Found {} tools.", results.len()),
+            details: serde_json::json!({ "results": results }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SessionContextTool;
+
+impl Tool for SessionContextTool {
+    fn name(&self) -> &str {
+        "session_context"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "session_context",
+            "description": "Fetch recent log entries for a session.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session": { "type": "string" },
+                    "limit": { "type": "integer" }
+                },
+                "required": ["session"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSessionContextArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let limit = parsed.limit.unwrap_or(20);
+        // Try JSONL logs first, fall back to db search
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let log_dir = log_dir_path(&workspace);
+        let jsonl_entries = load_session_logs(&log_dir, &parsed.session, limit);
+        if !jsonl_entries.is_empty() {
+            let results: Vec<serde_json::Value> = jsonl_entries
+                .into_iter()
+                .map(|e| {
+                    serde_json::json!({
+                        "ts": e.ts_utc,
+                        "role": e.role,
+                        "text": e.text,
+                        "meta": e.meta,
+                        "source": "jsonl"
+                    })
+                })
+                .collect();
+            Ok(ToolExecution {
+                output: format!("Loaded {} entries from logs.", results.len()),
+                details: serde_json::json!({ "entries": results }),
+                is_error: false,
+            })
+        } else {
+            // Fallback: search db for legacy data
+            let scope = format!("aethervault://agent-log/{}/", parsed.session);
+            let request = SearchRequest {
+                query: parsed.session.clone(),
+                top_k: 200,
+                snippet_chars: 200,
+                scope: Some(scope),
+                temporal: None,
+                as_of_frame: None,
+                as_of_ts: None,
+            };
+            let response = db.search(request).map_err(|e| e.to_string())?;
+            let mut entries = Vec::new();
+            for hit in response.hits {
+                let uri = hit.uri.clone();
+                let ts = parse_log_ts_from_uri(&uri).unwrap_or_default();
+                if let Ok(text) = db.frame_text_by_id(hit.frame_id) {
+                    if let Ok(entry) = serde_json::from_str::<AgentLogEntry>(&text) {
+                        entries.push(serde_json::json!({
+                            "ts": entry.ts_utc.unwrap_or(ts),
+                            "role": entry.role,
+                            "text": entry.text,
+                            "meta": entry.meta,
+                            "source": "db"
+                        }));
+                    }
+                }
+            }
+            entries.sort_by(|a, b| {
+                b.get("ts")
+                    .and_then(|v| v.as_i64())
+                    .cmp(&a.get("ts").and_then(|v| v.as_i64()))
+            });
+            let results: Vec<serde_json::Value> = entries.into_iter().take(limit).collect();
+            Ok(ToolExecution {
+                output: format!("Loaded {} entries from db.", results.len()),
+                details: serde_json::json!({ "entries": results }),
+                is_error: false,
+            })
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Reflections and stored skills.
+
+use super::*;
+
+pub(crate) struct ReflectTool;
+
+impl Tool for ReflectTool {
+    fn name(&self) -> &str {
+        "reflect"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "reflect",
+            "description": "Store a self-critique reflection in the capsule.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" },
+                    "session": { "type": "string" },
+                    "reason": { "type": "string" }
+                },
+                "required": ["text"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolReflectArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let session = parsed
+            .session
+            .clone()
+            .unwrap_or_else(|| "default".to_string());
+        let ts = Utc::now().timestamp();
+        let payload = serde_json::json!({
+            "session": session,
+            "text": parsed.text,
+            "reason": parsed.reason,
+            "ts_utc": ts
+        });
+        let bytes = serde_json::to_vec_pretty(&payload).map_err(|e| e.to_string())?;
+        let hash = blake3_hash(&bytes);
+        let uri = format!(
+            "aethervault://memory/reflection/{}/{}-{}",
+            session,
+            ts,
+            hash.to_hex()
+        );
+        {
+            let mut options = PutOptions::default();
+            options.uri = Some(uri.clone());
+            options.title = Some("reflection".to_string());
+            options.kind = Some("application/json".to_string());
+            options.track = Some("aethervault.reflection".to_string());
+            options.search_text = Some(payload.to_string());
+            let result = put_with_consolidation(db, &bytes, options)
+                .map_err(|e| e.to_string())?;
+            db.commit().map_err(|e| e.to_string())?;
+            let output = match result.decision {
+                ConsolidationDecision::Noop { existing_id } => {
+                    format!("Reflection deduplicated (similar to frame #{existing_id}).")
+                }
+                ConsolidationDecision::Update { supersede_id } => {
+                    format!("Reflection updated (superseded frame #{supersede_id}).")
+                }
+                ConsolidationDecision::Add => "Reflection stored.".to_string(),
+            };
+            Ok(ToolExecution {
+                output,
+                details: serde_json::json!({ "uri": uri, "decision": format!("{:?}", result.decision) }),
+                is_error: false,
+            })
+        }
+    }
+}
+
+pub(crate) struct ReflectionsListTool;
+
+impl Tool for ReflectionsListTool {
+    fn name(&self) -> &str {
+        "reflections_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "reflections_list",
+            "description": "List recent reflections, newest first, optionally for one session. Check these before repeating a task that went wrong before.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session": { "type": "string" },
+                    "limit": { "type": "integer" }
+                }
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolReflectionsListArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let limit = parsed.limit.unwrap_or(10);
+        let scope = match &parsed.session {
+            Some(session) => format!("aethervault://memory/reflection/{session}/"),
+            None => "aethervault://memory/reflection/".to_string(),
+        };
+        let mut reflections = Vec::new();
+        for frame_id in db.collect_active_frame_ids(Some(&scope)) {
+            let Ok(frame) = db.frame_by_id(frame_id) else {
+                continue;
+            };
+            if frame.track.as_deref() != Some("aethervault.reflection") {
+                continue;
+            }
+            let Ok(text) = db.frame_text_by_id(frame_id) else {
+                continue;
+            };
+            let Ok(payload) = serde_json::from_str::<serde_json::Value>(&text) else {
+                continue;
+            };
+            reflections.push(serde_json::json!({
+                "uri": frame.uri,
+                "session": payload.get("session"),
+                "text": payload.get("text"),
+                "reason": payload.get("reason"),
+                "ts_utc": payload.get("ts_utc").and_then(|v| v.as_i64()).unwrap_or(frame.timestamp),
+            }));
+        }
+        reflections.sort_by_key(|r| std::cmp::Reverse(r["ts_utc"].as_i64()));
+        reflections.truncate(limit);
+        let mut output = format!("Found {} reflections.", reflections.len());
+        for r in &reflections {
+            output.push_str(&format!("\n- {}", r["text"].as_str().unwrap_or_default()));
+            if let Some(reason) = r["reason"].as_str() {
+                output.push_str(&format!(" (reason: {reason})"));
+            }
+        }
+        Ok(ToolExecution {
+            output,
+            details: serde_json::json!({ "reflections": reflections }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SkillStoreTool;
+
+impl Tool for SkillStoreTool {
+    fn name(&self) -> &str {
+        "skill_store"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "skill_store",
+            "description": "Store a reusable procedure as a skill.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "trigger": { "type": "string" },
+                    "steps": { "type": "array", "items": { "type": "string" } },
+                    "tools": { "type": "array", "items": { "type": "string" } },
+                    "notes": { "type": "string" }
+                },
+                "required": ["name"]
+            }
+        })
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSkillStoreArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let db_path = workspace.join("skills.sqlite");
+        let conn = open_skill_db(&db_path).map_err(|e| format!("skill db: {e}"))?;
+        let now = Utc::now().to_rfc3339();
+        let skill_name = parsed.name.clone();
+        let skill_trigger = parsed.trigger.clone();
+        let skill_steps = parsed.steps.clone().unwrap_or_default();
+        let skill_tools = parsed.tools.clone().unwrap_or_default();
+        let skill_notes = parsed.notes.clone();
+
+        let skill = SkillRecord {
+            name: skill_name.clone(),
+            trigger: skill_trigger.clone(),
+            steps: skill_steps.clone(),
+            tools: skill_tools.clone(),
+            notes: skill_notes.clone(),
+            success_rate: 0.0,
+            times_used: 0,
+            times_succeeded: 0,
+            last_used: None,
+            created_at: now,
+            contexts: Vec::new(),
+        };
+        upsert_skill(&conn, &skill).map_err(|e| format!("upsert: {e}"))?;
+
+        let ts = Utc::now().timestamp();
+        let slug = skill_slug(&skill_name);
+        let uri = skill_uri(&slug);
+        // Storing an existing name adds a version; the usage count carries over.
+        let previous = db.frame_by_uri(&uri).ok();
+        let meta_u64 = |key: &str| {
+            previous
+                .as_ref()
+                .and_then(|f| f.extra_metadata.get(key))
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        let version = meta_u64("skill_version") + 1;
+        let uses = meta_u64("skill_uses");
+        let payload = serde_json::json!({
+            "id": slug,
+            "version": version,
+            "name": skill_name,
+            "trigger": skill_trigger,
+            "steps": skill_steps,
+            "tools": skill_tools,
+            "notes": skill_notes,
+            "ts_utc": ts
+        });
+        let bytes = serde_json::to_vec_pretty(&payload).map_err(|e| e.to_string())?;
+        let mut details = serde_json::json!({
+            "uri": uri,
+            "skill_id": slug,
+            "version": version,
+            "stored_in_sqlite": true,
+            "name": parsed.name,
+            "db": db_path.display().to_string(),
+        });
+        let capsule_write = (|| -> Result<(), String> {
+            let mut options = PutOptions::default();
+            options.uri = Some(details["uri"].as_str().unwrap_or_default().to_string());
+            options.title = Some("skill".to_string());
+            options.kind = Some("application/json".to_string());
+            options.track = Some("aethervault.skill".to_string());
+            options.search_text = Some(payload.to_string());
+            options.extra_metadata.insert("skill_id".into(), slug.clone());
+            options.extra_metadata.insert("skill_version".into(), version.to_string());
+            options.extra_metadata.insert("skill_uses".into(), uses.to_string());
+            db.put_bytes_with_options(&bytes, options)
+                .map_err(|e| e.to_string())?;
+            db.commit().map_err(|e| e.to_string())?;
+            Ok(())
+        })();
+        if let Some(obj) = details.as_object_mut() {
+            obj.insert(
+                "stored_in_db".into(),
+                serde_json::Value::Bool(capsule_write.is_ok()),
+            );
+        }
+        if let Err(err) = &capsule_write {
+            if let Some(obj) = details.as_object_mut() {
+                obj.insert(
+                    "db_error".into(),
+                    serde_json::Value::String(format!(
+                        "DB write skipped after SQLite upsert: {err}"
+                    )),
+                );
+            }
+        }
+        Ok(ToolExecution {
+            output: if capsule_write.is_ok() {
+                format!("Skill stored (version {version}).")
+            } else {
+                "Skill stored in SQLite; db write skipped.".to_string()
+            },
+            details,
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SkillSearchTool;
+
+impl Tool for SkillSearchTool {
+    fn name(&self) -> &str {
+        "skill_search"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "skill_search",
+            "description": "Search stored skills (latest version of each).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer" },
+                    "sort": { "type": "string", "enum": ["relevance", "usage"], "description": "usage puts the most-run skills first" }
+                },
+                "required": ["query"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSkillSearchArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let workspace = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        Ok(skill_search(db, &workspace, parsed))
+    }
+}
+
+pub(crate) struct SkillRunTool;
+
+impl Tool for SkillRunTool {
+    fn name(&self) -> &str {
+        "skill_run"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "skill_run",
+            "description": "Load a stored skill by name or URI as a step-by-step plan and activate the tools it uses. Nothing runs automatically: call each tool yourself.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name_or_uri": { "type": "string" },
+                    "task": { "type": "string", "description": "What to apply the skill to" }
+                },
+                "required": ["name_or_uri"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSkillRunArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let wanted = parsed.name_or_uri.trim();
+        let (frame_id, skill) = if wanted.starts_with("aethervault://") {
+            let frame = db.frame_by_uri(wanted).map_err(|_| format!("no skill at {wanted}"))?;
+            let text = db.frame_text_by_id(frame.id)?;
+            let skill = serde_json::from_str::<serde_json::Value>(&text).map_err(|e| format!("skill payload: {e}"))?;
+            (Some(frame.id), skill)
+        } else {
+            let from_capsule = load_skill_frames(db)
+                .into_iter()
+                .find(|(_, s)| {
+                    s.get("name")
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|n| n.eq_ignore_ascii_case(wanted))
+                })
+                .map(|(id, s)| (Some(id), s));
+            let from_sqlite = || {
+                let workspace = workspace_override
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+                let conn = open_skill_db(&workspace.join("skills.sqlite")).ok()?;
+                search_skills(&conn, wanted, 20)
+                    .into_iter()
+                    .find(|s| s.name.eq_ignore_ascii_case(wanted))
+                    .and_then(|s| serde_json::to_value(s).ok())
+                    .map(|s| (None, s))
+            };
+            from_capsule
+                .or_else(from_sqlite)
+                .ok_or_else(|| format!("unknown skill: {wanted} (use skill_search to find one)"))?
+        };
+        let tools: Vec<String> = skill
+            .get("tools")
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        let registry = tool_registry();
+        let (known, unknown): (Vec<String>, Vec<String>) = tools
+            .into_iter()
+            .partition(|name| registry.get(name).is_some() || name.starts_with("mcp__"));
+        let uses = match frame_id {
+            Some(id) => {
+                let uses = db
+                    .frame_by_id(id)?
+                    .extra_metadata
+                    .get("skill_uses")
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(0)
+                    + 1;
+                db.set_extra_metadata(id, "skill_uses", &uses.to_string())?;
+                Some(uses)
+            }
+            None => None,
+        };
+        let mut output = skill_prompt_text(&skill, parsed.task.as_deref());
+        output.push_str(
+            "\nWork through the steps yourself, calling each tool as needed; tools that need approval still ask for it.",
+        );
+        if !unknown.is_empty() {
+            output.push_str(&format!("\nUnavailable tools: {}", unknown.join(", ")));
+        }
+        Ok(ToolExecution {
+            output,
+            details: serde_json::json!({
+                "name": skill.get("name"),
+                "trigger": skill.get("trigger"),
+                "steps": skill.get("steps").cloned().unwrap_or_else(|| serde_json::json!([])),
+                "tools": known,
+                "unknown_tools": unknown,
+                "notes": skill.get("notes"),
+                "version": skill.get("version"),
+                "uses": uses,
+            }),
+            is_error: false,
+        })
+    }
+}
@@ -0,0 +1,759 @@
+//! Subagent registry and invocation.
+
+use super::*;
+
+pub(crate) struct SubagentListTool;
+
+impl Tool for SubagentListTool {
+    fn name(&self) -> &str {
+        "subagent_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "subagent_list",
+            "description": "Check subagent configuration. Shows whether dynamic spawning is enabled and any pre-existing agent configs. You can use subagent_invoke with ANY name — you don't need to call this first.",
+            "inputSchema": { "type": "object", "properties": {} }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, _args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let ws = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let cfg_path = crate::config_file_path(&ws);
+        let config = if cfg_path.exists() {
+            crate::load_config_from_file(&ws)
+        } else {
+            load_capsule_config(db).unwrap_or_default()
+        };
+        let subagents = load_subagents_from_config(&config);
+        let details: Vec<serde_json::Value> = subagents.iter().map(|s| {
+            serde_json::json!({
+                "name": s.name,
+                "description": s.description,
+                "tools": s.tools,
+                "disallowed_tools": s.disallowed_tools,
+                "max_steps": s.max_steps,
+            })
+        }).collect();
+        // Check if dynamic spawning is supported
+        let has_default_hook = config.agent.as_ref()
+            .and_then(|a| a.default_subagent_hook.as_ref())
+            .is_some();
+        Ok(ToolExecution {
+            output: if !has_default_hook && details.is_empty() {
+                "No subagent hook configured. Define default_subagent_hook in config.json.".to_string()
+            } else {
+                format!("Dynamic spawning enabled. Use subagent_invoke with any descriptive name.{}",
+                    if details.is_empty() { String::new() } else { format!(" {} pre-configured agents also available.", details.len()) })
+            },
+            details: serde_json::json!({
+                "subagents": details,
+                "dynamic_spawning": has_default_hook,
+            }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SubagentRegisterTool;
+
+impl Tool for SubagentRegisterTool {
+    fn name(&self) -> &str {
+        "subagent_register"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "subagent_register",
+            "description": "Register a named subagent in config so it can be invoked later. Requires approval.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Unique subagent name" },
+                    "system": { "type": "string", "description": "System prompt for the subagent" },
+                    "model_hook": { "type": "string", "description": "Model hook command the subagent runs" },
+                    "description": { "type": "string", "description": "What the subagent is for (used for routing)" }
+                },
+                "required": ["name", "system", "model_hook"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolSubagentRegisterArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        update_subagents(ctx, |subagents| {
+            let sub_name = parsed.name.trim().to_string();
+            if sub_name.is_empty() || parsed.system.trim().is_empty() || parsed.model_hook.trim().is_empty() {
+                return Err("subagent_register requires non-empty name, system and model_hook".into());
+            }
+            if subagents.iter().any(|s| s.name == sub_name) {
+                return Err(format!("subagent '{sub_name}' already exists"));
+            }
+            subagents.push(SubagentSpec {
+                name: sub_name.clone(),
+                description: parsed.description.filter(|d| !d.trim().is_empty()),
+                system: Some(parsed.system),
+                model_hook: Some(parsed.model_hook),
+                tools: Vec::new(),
+                disallowed_tools: Vec::new(),
+                max_steps: None,
+                timeout_secs: None,
+            });
+            Ok((format!("Subagent '{sub_name}' registered."), sub_name))
+        })
+    }
+}
+
+pub(crate) struct SubagentRemoveTool;
+
+impl Tool for SubagentRemoveTool {
+    fn name(&self) -> &str {
+        "subagent_remove"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "subagent_remove",
+            "description": "Remove a registered subagent from config. Requires approval.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" }
+                },
+                "required": ["name"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolSubagentRemoveArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        update_subagents(ctx, |subagents| {
+            let before = subagents.len();
+            subagents.retain(|s| s.name != parsed.name);
+            if subagents.len() == before {
+                return Err(format!("subagent '{}' not found", parsed.name));
+            }
+            Ok((format!("Subagent '{}' removed.", parsed.name), parsed.name))
+        })
+    }
+}
+
+pub(crate) struct SubagentInvokeTool;
+
+impl Tool for SubagentInvokeTool {
+    fn name(&self) -> &str {
+        "subagent_invoke"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "subagent_invoke",
+            "description": "Spawn a subagent to perform a task. Use ANY descriptive name — the name should describe what the agent does (e.g., 'log-analyzer', 'api-tester', 'deploy-checker'). The subagent runs with its own session, tools, and memory.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Descriptive name for this subagent (e.g., 'log-analyzer', 'code-reviewer'). Choose a name that describes the task." },
+                    "prompt": { "type": "string", "description": "Detailed task description for the subagent. Be specific — the subagent has its own context." },
+                    "system": { "type": "string", "description": "Override the subagent's system prompt" },
+                    "model_hook": { "type": "string", "description": "Override the subagent's model hook" },
+                    "max_steps": { "type": "integer", "description": "Override max reasoning steps for this invocation. Default: from subagent config, fallback 64." }
+                },
+                "required": ["name", "prompt"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let db = ctx.db;
+        let bg_registry = &ctx.bg_registry;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSubagentInvokeArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let ws = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let cfg_path = crate::config_file_path(&ws);
+        let config = if cfg_path.exists() {
+            crate::load_config_from_file(&ws)
+        } else {
+            load_capsule_config(db).unwrap_or_default()
+        };
+        let child_depth = next_subagent_depth(&config)?;
+        let subagents = load_subagents_from_config(&config);
+        let resolved_hook = config.agent.as_ref()
+            .and_then(|a| a.default_subagent_hook.clone())
+            .unwrap_or_else(|| DEFAULT_SUBAGENT_HOOK.to_string());
+        let config_max_steps = config.agent.as_ref()
+            .and_then(|a| a.subagent_max_steps)
+            .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
+        let synth_spec = SubagentSpec {
+            name: parsed.name.clone(),
+            description: None,
+            system: None,
+            model_hook: Some(resolved_hook),
+            tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            max_steps: Some(config_max_steps),
+            timeout_secs: Some(DEFAULT_SUBAGENT_TIMEOUT_SECS),
+        };
+        let spec = subagents
+            .iter()
+            .find(|s| s.name == parsed.name)
+            .unwrap_or(&synth_spec);
+        if spec.name != parsed.name {
+            log_info!(
+                "[subagent_invoke] '{}' not in config, using dynamic spec (hook: {})",
+                parsed.name,
+                spec.model_hook.as_deref().unwrap_or("none"),
+            );
+        }
+
+        let mut system = parsed.system.clone();
+        let mut model_hook = parsed.model_hook.clone();
+        if system.is_none() {
+            system = spec.system.clone();
+        }
+        if model_hook.is_none() {
+            model_hook = spec.model_hook.clone();
+        }
+
+        // Enforce tool restrictions from SubagentSpec via system prompt
+        if !spec.tools.is_empty() {
+            let list = spec.tools.join(", ");
+            let restriction = format!(
+                "\n\nIMPORTANT: You are ONLY allowed to use the following tools: {list}. \
+                 Do not attempt to use any other tools."
+            );
+            if let Some(ref mut sys) = system {
+                sys.push_str(&restriction);
+            } else {
+                system = Some(restriction);
+            }
+        }
+        if !spec.disallowed_tools.is_empty() {
+            let list = spec.disallowed_tools.join(", ");
+            let restriction = format!(
+                "\n\nIMPORTANT: You are NOT allowed to use the following tools: {list}. \
+                 If you attempt to use them, the call will fail."
+            );
+            if let Some(ref mut sys) = system {
+                sys.push_str(&restriction);
+            } else {
+                system = Some(restriction);
+            }
+        }
+
+        // Resolve max_steps: invocation arg > spec > default 64
+        let max_steps = parsed.max_steps.or(spec.max_steps).unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
+
+        let cfg = build_bridge_agent_config(
+            mv2.to_path_buf(),
+            model_hook,
+            system,
+            false,
+            None,
+            8,
+            12_000,
+            max_steps,
+            true,
+            8,
+        )
+        .map_err(|e| e.to_string())?;
+        let session = subagent_session_id(&parsed.name, &new_subagent_run_id(), None);
+        let prompt = parsed.prompt.clone();
+
+        // Non-blocking path: when bg_registry is present, register and return immediately
+        if let Some((chat_id, registry)) = bg_registry.as_ref() {
+            let task_id = {
+                let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                reg.next_id()
+            };
+            let now_epoch = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let preview: String = parsed.prompt.chars().take(100).collect();
+            let bg_task = BackgroundTask {
+                task_id: task_id.clone(),
+                name: parsed.name.clone(),
+                prompt_preview: preview,
+                status: BackgroundTaskStatus::Running,
+                started_at_epoch: now_epoch,
+                completed_at_epoch: None,
+                step_count: 0,
+                max_steps,
+                result_text: None,
+                session: session.clone(),
+            };
+            {
+                let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                reg.register(*chat_id, bg_task);
+            }
+            let reg_clone = registry.clone();
+            let tid = task_id.clone();
+            thread::spawn(move || {
+                set_subagent_depth(child_depth);
+                let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
+                let mut reg = reg_clone.lock().unwrap_or_else(|e| e.into_inner());
+                match r {
+                    Ok(output) => {
+                        reg.update_status(
+                            &tid,
+                            BackgroundTaskStatus::Completed,
+                            output.final_text,
+                        );
+                    }
+                    Err(err) => {
+                        reg.update_status(
+                            &tid,
+                            BackgroundTaskStatus::Failed(err.to_string()),
+                            None,
+                        );
+                    }
+                }
+            });
+            return Ok(ToolExecution {
+                output: format!("Background task started: {} (id: {})", parsed.name, task_id),
+                details: serde_json::json!({ "task_id": task_id, "name": parsed.name }),
+                is_error: false,
+            });
+        }
+
+        // Blocking path (CLI / non-bridge contexts): spawn and wait
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            set_subagent_depth(child_depth);
+            let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
+            let _ = tx.send(r);
+        });
+
+        let result = rx.recv()
+            .map_err(|e| format!("channel error: {e}"))?
+            .map_err(|e| e.to_string())?;
+
+        Ok(ToolExecution {
+            output: result.final_text.unwrap_or_default(),
+            details: serde_json::json!({ "session": result.session, "messages": result.messages.len() }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct SubagentBatchTool;
+
+impl Tool for SubagentBatchTool {
+    fn name(&self) -> &str {
+        "subagent_batch"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "subagent_batch",
+            "description": "Spawn multiple subagents concurrently for parallel work. Each invocation runs independently with its own session. Use descriptive names for each agent. Use max_concurrent to limit parallelism.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "invocations": {
+                        "type": "array",
+                        "description": "Array of subagent invocations to run concurrently. Each has name, prompt, and optional overrides.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string", "description": "Descriptive name for this subagent (e.g., 'log-analyzer', 'security-scanner')." },
+                                "prompt": { "type": "string", "description": "Task/prompt for this subagent" },
+                                "system": { "type": "string" },
+                                "model_hook": { "type": "string" },
+                                "max_steps": { "type": "integer" }
+                            },
+                            "required": ["name", "prompt"]
+                        }
+                    },
+                    "max_concurrent": { "type": "integer", "description": "Maximum concurrent subagents. Default: all at once. Set lower to reduce resource usage." },
+                    "max_output_chars": { "type": "integer", "description": "Cap on each subagent's returned output (default 4000). Longer outputs are saved to a frame; fetch it with get on the returned uri." },
+                    "timeout_secs": { "type": "integer", "description": "Per-subagent timeout in seconds. Timed-out subagents are reported, not awaited." }
+                },
+                "required": ["invocations"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let db = ctx.db;
+        let bg_registry = &ctx.bg_registry;
+        let cancel = ctx.cancel;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolSubagentBatchArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        if parsed.invocations.is_empty() {
+            return Err("subagent_batch requires at least one invocation".into());
+        }
+        let ws = workspace_override
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+        let cfg_path = crate::config_file_path(&ws);
+        let config_snapshot = if cfg_path.exists() {
+            crate::load_config_from_file(&ws)
+        } else {
+            load_capsule_config(db).unwrap_or_default()
+        };
+        let child_depth = next_subagent_depth(&config_snapshot)?;
+        let subagents = load_subagents_from_config(&config_snapshot);
+        let resolved_hook = config_snapshot.agent.as_ref()
+            .and_then(|a| a.default_subagent_hook.clone())
+            .unwrap_or_else(|| DEFAULT_SUBAGENT_HOOK.to_string());
+        let run_id = new_subagent_run_id();
+
+        let max_conc = parsed.max_concurrent.unwrap_or(parsed.invocations.len());
+        // At least 1, never above the configured fan-out cap.
+        let max_conc = max_conc.clamp(1, subagent_max_concurrent(&config_snapshot));
+
+        // Prepare each invocation: resolve spec fields, build config.
+        struct PreparedInvocation {
+            name: String,
+            prompt: String,
+            cfg: Result<crate::types::BridgeAgentConfig, String>,
+            index: usize,
+            timeout_secs: u64,
+        }
+        let mut prepared: Vec<PreparedInvocation> = Vec::new();
+        let max_output_chars = parsed.max_output_chars.unwrap_or(DEFAULT_SUBAGENT_OUTPUT_CHARS);
+        for (i, inv) in parsed.invocations.into_iter().enumerate() {
+            let mut system = inv.system.clone();
+            let mut model_hook = inv.model_hook.clone();
+            let config_max_steps = config_snapshot.agent.as_ref()
+                .and_then(|a| a.subagent_max_steps)
+                .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
+            let synth_spec = SubagentSpec {
+                name: inv.name.clone(),
+                description: None,
+                system: None,
+                model_hook: Some(resolved_hook.clone()),
+                tools: Vec::new(),
+                disallowed_tools: Vec::new(),
+                max_steps: Some(config_max_steps),
+                timeout_secs: Some(DEFAULT_SUBAGENT_TIMEOUT_SECS),
+            };
+            let spec = subagents
+                .iter()
+                .find(|s| s.name == inv.name)
+                .unwrap_or(&synth_spec);
+            if spec.name != inv.name {
+                log_info!(
+                    "[subagent_batch] '{}' not in config, using dynamic spec (hook: {})",
+                    inv.name,
+                    spec.model_hook.as_deref().unwrap_or("none"),
+                );
+            }
+            if system.is_none() {
+                system = spec.system.clone();
+            }
+            if model_hook.is_none() {
+                model_hook = spec.model_hook.clone();
+            }
+
+            // Resolve max_steps: invocation arg > spec > default 64
+            let max_steps = inv.max_steps
+                .or(spec.max_steps)
+                .unwrap_or(DEFAULT_SUBAGENT_MAX_STEPS);
+
+            let cfg = build_bridge_agent_config(
+                mv2.to_path_buf(),
+                model_hook,
+                system,
+                false,
+                None,
+                8,
+                12_000,
+                max_steps,
+                true,
+                8,
+            )
+            .map_err(|e| e.to_string());
+            prepared.push(PreparedInvocation {
+                name: inv.name.clone(),
+                prompt: inv.prompt.clone(),
+                cfg,
+                index: i,
+                timeout_secs: parsed
+                    .timeout_secs
+                    .or(spec.timeout_secs)
+                    .unwrap_or(DEFAULT_SUBAGENT_TIMEOUT_SECS)
+                    .max(1),
+            });
+        }
+
+        // Non-blocking path: when bg_registry is present, register all and return immediately
+        if let Some((chat_id, registry)) = bg_registry.as_ref() {
+            let now_epoch = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let mut task_ids = Vec::new();
+            let mut jobs = std::collections::VecDeque::new();
+            for item in &prepared {
+                let task_id = {
+                    let reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                    reg.next_id()
+                };
+                let preview: String = item.prompt.chars().take(100).collect();
+                let max_steps_val = match &item.cfg {
+                    Ok(cfg) => cfg.max_steps,
+                    Err(_) => DEFAULT_SUBAGENT_MAX_STEPS,
+                };
+                let bg_task = BackgroundTask {
+                    task_id: task_id.clone(),
+                    name: item.name.clone(),
+                    prompt_preview: preview,
+                    status: BackgroundTaskStatus::Running,
+                    started_at_epoch: now_epoch,
+                    completed_at_epoch: None,
+                    step_count: 0,
+                    max_steps: max_steps_val,
+                    result_text: None,
+                    session: subagent_session_id(&item.name, &run_id, Some(item.index)),
+                };
+                {
+                    let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                    reg.register(*chat_id, bg_task);
+                }
+                match &item.cfg {
+                    Ok(cfg) => {
+                        let session = subagent_session_id(&item.name, &run_id, Some(item.index));
+                        jobs.push_back((task_id.clone(), cfg.clone(), session, item.prompt.clone()));
+                    }
+                    Err(err) => {
+                        let mut reg = registry.lock().unwrap_or_else(|e| e.into_inner());
+                        reg.update_status(&task_id, BackgroundTaskStatus::Failed(err.clone()), None);
+                    }
+                }
+                task_ids.push(serde_json::json!({ "task_id": task_id, "name": item.name }));
+            }
+            // Bounded pool: max_conc workers drain the queue instead of one thread per task.
+            let workers = max_conc.min(jobs.len());
+            let jobs = Arc::new(Mutex::new(jobs));
+            for _ in 0..workers {
+                let jobs = jobs.clone();
+                let reg_clone = registry.clone();
+                thread::spawn(move || {
+                    set_subagent_depth(child_depth);
+                    loop {
+                        let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+                        let Some((tid, cfg, session, prompt)) = job else {
+                            break;
+                        };
+                        let r = run_agent_for_bridge(&cfg, &prompt, session, None, None, None);
+                        let mut reg = reg_clone.lock().unwrap_or_else(|e| e.into_inner());
+                        match r {
+                            Ok(output) => {
+                                reg.update_status(&tid, BackgroundTaskStatus::Completed, output.final_text);
+                            }
+                            Err(err) => {
+                                reg.update_status(&tid, BackgroundTaskStatus::Failed(err.to_string()), None);
+                            }
+                        }
+                    }
+                });
+            }
+            return Ok(ToolExecution {
+                output: format!("{} background tasks started.", task_ids.len()),
+                details: serde_json::json!({ "tasks": task_ids }),
+                is_error: false,
+            });
+        }
+
+        // Blocking path (CLI): process invocations in chunks of max_conc for concurrency limiting.
+        let mut all_results: Vec<serde_json::Value> = Vec::new();
+        let mut all_ok = true;
+
+        for chunk in prepared.chunks(max_conc) {
+            if cancel.load(Ordering::Acquire) {
+                let skipped = prepared.len() - all_results.len();
+                return Err(format!(
+                    "subagent_batch canceled; {} finished, {skipped} not started",
+                    all_results.len()
+                ));
+            }
+            type Pending = (String, usize, u64, mpsc::Receiver<Result<AgentRunOutput, String>>);
+            let mut pending: Vec<Pending> = Vec::new();
+            let chunk_started = Instant::now();
+            for item in chunk {
+                let (tx, rx) = mpsc::channel();
+                match &item.cfg {
+                    Err(err) => {
+                        let _ = tx.send(Err(err.clone()));
+                    }
+                    Ok(cfg) => {
+                        let cfg = cfg.clone();
+                        let session = subagent_session_id(&item.name, &run_id, Some(item.index));
+                        let prompt = item.prompt.clone();
+                        thread::spawn(move || {
+                            set_subagent_depth(child_depth);
+                            let _ = tx.send(run_agent_for_bridge(&cfg, &prompt, session, None, None, None));
+                        });
+                    }
+                }
+                pending.push((item.name.clone(), item.index, item.timeout_secs, rx));
+            }
+
+            // Collect results from this chunk before starting the next. A hung
+            // subagent is reported as timed out and left to finish on its own.
+            for (name, index, timeout_secs, rx) in pending {
+                let deadline = chunk_started + Duration::from_secs(timeout_secs);
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(wait) {
+                    Ok(Ok(output)) => {
+                        let text = output.final_text.unwrap_or_default();
+                        let mut entry = serde_json::json!({
+                            "name": name,
+                            "status": "ok",
+                            "session": output.session,
+                            "messages": output.messages.len(),
+                            "output_chars": text.chars().count(),
+                            "truncated": false,
+                        });
+                        if text.chars().count() > max_output_chars {
+                            let uri = format!("aethervault://subagents/{name}/{run_id}-{index}");
+                            let options = PutOptions {
+                                uri: Some(uri.clone()),
+                                title: Some(format!("subagent output: {name}")),
+                                kind: Some("text/markdown".to_string()),
+                                track: Some("aethervault.subagent".to_string()),
+                                ..PutOptions::default()
+                            };
+                            let stored = db
+                                .put_bytes_with_options(text.as_bytes(), options)
+                                .and_then(|_| db.commit())
+                                .is_ok();
+                            let kept: String = text.chars().take(max_output_chars).collect();
+                            let hint = if stored {
+                                format!("full output: get {uri}")
+                            } else {
+                                "full output could not be stored".to_string()
+                            };
+                            entry["output"] = serde_json::json!(format!(
+                                "{kept}\n[... truncated at {max_output_chars} chars; {hint}]"
+                            ));
+                            entry["truncated"] = serde_json::json!(true);
+                            if stored {
+                                entry["uri"] = serde_json::json!(uri);
+                            }
+                        } else {
+                            entry["output"] = serde_json::json!(text);
+                        }
+                        all_results.push(entry);
+                    }
+                    Ok(Err(err)) => {
+                        all_ok = false;
+                        all_results.push(serde_json::json!({
+                            "name": name,
+                            "status": "error",
+                            "error": err,
+                        }));
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        all_ok = false;
+                        all_results.push(serde_json::json!({
+                            "name": name,
+                            "status": "timeout",
+                            "error": format!("no result after {timeout_secs}s"),
+                        }));
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        all_ok = false;
+                        all_results.push(serde_json::json!({
+                            "name": name,
+                            "status": "error",
+                            "error": "subagent thread panicked",
+                        }));
+                    }
+                }
+            }
+        }
+
+        let mut summary = if all_ok {
+            format!("{} subagents completed successfully.", all_results.len())
+        } else {
+            let ok_count = all_results.iter().filter(|r| r["status"] == "ok").count();
+            let err_count = all_results.len() - ok_count;
+            format!("{} subagents completed, {} failed.", ok_count, err_count)
+        };
+        let names_where = |pred: &dyn Fn(&serde_json::Value) -> bool| -> Vec<String> {
+            all_results
+                .iter()
+                .filter(|r| pred(r))
+                .filter_map(|r| r["name"].as_str().map(|s| s.to_string()))
+                .collect()
+        };
+        let timed_out = names_where(&|r| r["status"] == "timeout");
+        let truncated = names_where(&|r| r["truncated"] == true);
+        if !timed_out.is_empty() {
+            summary.push_str(&format!(" Timed out: {}.", timed_out.join(", ")));
+        }
+        if !truncated.is_empty() {
+            summary.push_str(&format!(" Truncated: {}.", truncated.join(", ")));
+        }
+        Ok(ToolExecution {
+            output: summary,
+            details: serde_json::json!({ "results": all_results }),
+            is_error: !all_ok,
+        })
+    }
+}
+
+/// Apply `edit` to the subagent list where it is stored: the workspace config
+/// file when there is one, else the capsule config. `edit` returns the output
+/// line and the subagent's name.
+fn update_subagents(
+    ctx: &ToolContext,
+    edit: impl FnOnce(&mut Vec<SubagentSpec>) -> Result<(String, String), String>,
+) -> Result<ToolExecution, String> {
+    let ws = resolve_workspace(None, &AgentConfig::default())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DIR));
+    let file_backed = crate::config_file_path(&ws).exists();
+    let mut config = if file_backed {
+        crate::load_config_from_file(&ws)
+    } else {
+        load_capsule_config(ctx.db).unwrap_or_default()
+    };
+    let mut subagents = load_subagents_from_config(&config);
+    let (output, subagent_name) = edit(&mut subagents)?;
+    if file_backed {
+        save_subagents_to_file(&ws, &subagents)?;
+    } else {
+        config.agent.get_or_insert_with(AgentConfig::default).subagents = subagents.clone();
+        let bytes = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+        save_config_entry(ctx.db, "index", &bytes)?;
+    }
+    Ok(ToolExecution {
+        output,
+        details: serde_json::json!({
+            "name": subagent_name,
+            "subagents": subagents.iter().map(|s| s.name.clone()).collect::<Vec<_>>(),
+        }),
+        is_error: false,
+    })
+}
@@ -0,0 +1,304 @@
+//! Approvals and event triggers.
+
+use super::*;
+
+pub(crate) struct ApprovalListTool;
+
+impl Tool for ApprovalListTool {
+    fn name(&self) -> &str {
+        "approval_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "approval_list",
+            "description": "List pending approval requests.",
+            "inputSchema": { "type": "object", "properties": {} }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, _args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let approvals = load_approvals(db);
+        let mut pending: Vec<ApprovalEntry> = approvals
+            .into_iter()
+            .filter(|a| a.status == "pending")
+            .collect();
+        let mut output = format!("{} pending approvals.", pending.len());
+        for entry in pending.iter_mut() {
+            // Entries queued before summaries existed.
+            if entry.summary.is_empty() {
+                entry.summary = approval_summary(&entry.tool, &entry.args);
+            }
+            output.push_str(&format!("\n- {}: {}", entry.id, entry.summary));
+        }
+        Ok(ToolExecution {
+            output,
+            details: serde_json::json!({ "approvals": pending }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct TriggerAddTool;
+
+impl Tool for TriggerAddTool {
+    fn name(&self) -> &str {
+        "trigger_add"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "trigger_add",
+            "description": "Add an event trigger. Kinds: email (Gmail query), calendar_free (Google Calendar window), cron (cron expression schedule), webhook (HTTP endpoint change detection).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "kind": { "type": "string", "description": "Trigger kind: email, calendar_free, cron, or webhook" },
+                    "name": { "type": "string", "description": "Human-readable trigger name" },
+                    "query": { "type": "string", "description": "Gmail query (for kind=email)" },
+                    "prompt": { "type": "string", "description": "Prompt to send to agent when trigger fires. May use {{trigger_id}}, {{trigger_name}}, {{fired_at}} and per kind: email {{sender}} {{subject}} {{date}} {{snippet}} {{message_id}} {{query}}; calendar_free {{start}} {{end}}; cron {{cron}}; webhook {{url}} {{response}}" },
+                    "start": { "type": "string", "description": "Window start (for kind=calendar_free)" },
+                    "end": { "type": "string", "description": "Window end (for kind=calendar_free)" },
+                    "cron": { "type": "string", "description": "Cron expression: 'min hour dom month dow' (for kind=cron). Example: '0 9 * * 1-5' = weekdays 9am" },
+                    "webhook_url": { "type": "string", "description": "URL to poll (for kind=webhook)" },
+                    "webhook_method": { "type": "string", "description": "HTTP method for webhook (default: GET)" },
+                    "enabled": { "type": "boolean" }
+                },
+                "required": ["kind"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolTriggerAddArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let mut triggers = load_triggers(db);
+        let id = format!(
+            "trg_{}_{}",
+            chrono::Utc::now().timestamp(),
+            triggers.len() + 1
+        );
+        // Validate kind-specific required fields
+        match parsed.kind.as_str() {
+            "cron" => {
+                if parsed.cron.is_none() {
+                    return Err("kind=cron requires a 'cron' expression".into());
+                }
+            }
+            "webhook" => {
+                if parsed.webhook_url.is_none() {
+                    return Err("kind=webhook requires a 'webhook_url'".into());
+                }
+            }
+            "email" | "calendar_free" => {}
+            other => {
+                return Err(format!("Unknown trigger kind: '{other}'"));
+            }
+        }
+        // Validate cron expression if provided
+        if let Some(ref cron_str) = parsed.cron {
+            if let Err(e) = CronExpr::parse(cron_str) {
+                return Err(format!("Invalid cron expression: {e}"));
+            }
+        }
+        // Validate webhook URL (SSRF protection)
+        if let Some(ref url) = parsed.webhook_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err("webhook_url must use http:// or https://".into());
+            }
+            let lower = url.to_lowercase();
+            if lower.contains("localhost") || lower.contains("127.0.0.1")
+                || lower.contains("[::1]") || lower.contains("169.254.169.254")
+                || lower.contains("10.0.") || lower.contains("192.168.") {
+                return Err("webhook_url cannot target private/internal addresses".into());
+            }
+        }
+        // Validate webhook method
+        if let Some(ref m) = parsed.webhook_method {
+            let upper = m.to_uppercase();
+            if upper != "GET" && upper != "POST" {
+                return Err(format!("webhook_method must be GET or POST, got '{m}'"));
+            }
+        }
+        let known: std::collections::BTreeMap<&str, String> = TriggerEntry::template_vars(&parsed.kind)
+            .into_iter()
+            .map(|name| (name, String::new()))
+            .collect();
+        let unknown = parsed
+            .prompt
+            .as_deref()
+            .map(|prompt| render_trigger_prompt(prompt, &known).1)
+            .unwrap_or_default();
+        let entry = TriggerEntry {
+            id: id.clone(),
+            kind: parsed.kind,
+            name: parsed.name,
+            query: parsed.query,
+            prompt: parsed.prompt,
+            start: parsed.start,
+            end: parsed.end,
+            enabled: parsed.enabled.unwrap_or(true),
+            last_seen: None,
+            last_fired: None,
+            cron: parsed.cron,
+            webhook_url: parsed.webhook_url,
+            webhook_method: parsed.webhook_method,
+            schedule_name: None,
+        };
+        triggers.push(entry);
+        save_triggers(db, &triggers)?;
+        let mut output = "Trigger added.".to_string();
+        if !unknown.is_empty() {
+            output.push_str(&format!(
+                " Unknown placeholders will be left as-is: {} (available: {}).",
+                unknown.join(", "),
+                known.keys().copied().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        Ok(ToolExecution {
+            output,
+            details: serde_json::json!({ "id": id, "unknown_placeholders": unknown }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct TriggerListTool;
+
+impl Tool for TriggerListTool {
+    fn name(&self) -> &str {
+        "trigger_list"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "trigger_list",
+            "description": "List configured triggers.",
+            "inputSchema": { "type": "object", "properties": {} }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, _args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let triggers = load_triggers(db);
+        Ok(ToolExecution {
+            output: format!("{} triggers.", triggers.len()),
+            details: serde_json::json!({ "triggers": triggers }),
+            is_error: false,
+        })
+    }
+}
+
+pub(crate) struct TriggerRemoveTool;
+
+impl Tool for TriggerRemoveTool {
+    fn name(&self) -> &str {
+        "trigger_remove"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "trigger_remove",
+            "description": "Remove a trigger by id.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let db = ctx.db;
+        let parsed: ToolTriggerRemoveArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let mut triggers = load_triggers(db);
+        let before = triggers.len();
+        triggers.retain(|t| t.id != parsed.id);
+        let updated = triggers.len() != before;
+        if updated {
+            save_triggers(db, &triggers)?;
+        }
+        Ok(ToolExecution {
+            output: if updated {
+                "Trigger removed.".to_string()
+            } else {
+                "Trigger not found.".to_string()
+            },
+            details: serde_json::json!({ "id": parsed.id, "updated": updated }),
+            is_error: !updated,
+        })
+    }
+}
+
+pub(crate) struct TriggerTestTool;
+
+impl Tool for TriggerTestTool {
+    fn name(&self) -> &str {
+        "trigger_test"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "trigger_test",
+            "description": "Evaluate a trigger against the live inbox/calendar/endpoint now and show what it matched and the prompt it would send. Does not run the agent or update trigger state.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let mv2 = ctx.mv2;
+        let db = ctx.db;
+        let workspace_override = resolve_workspace(None, &AgentConfig::default());
+        let parsed: ToolTriggerTestArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let trigger = load_triggers(db)
+            .into_iter()
+            .find(|t| t.id == parsed.id)
+            .ok_or_else(|| format!("Trigger not found: {}", parsed.id))?;
+        let agent_cfg = load_capsule_config(db).unwrap_or_default().agent.unwrap_or_default();
+        let now = Utc::now().with_timezone(&resolve_timezone(&agent_cfg, None));
+        let workspace = resolve_workspace(workspace_override.clone(), &agent_cfg);
+        let outcome = evaluate_trigger(&trigger, mv2, now, workspace.as_deref())?;
+        let output = match &outcome {
+            TriggerOutcome::Idle { reason } => format!("Would not fire: {reason}"),
+            TriggerOutcome::Baseline { .. } => {
+                "Would record the first webhook response as the baseline without firing.".to_string()
+            }
+            TriggerOutcome::Fire { matched, prompt, .. } => {
+                format!("Would fire ({matched}) with prompt:\n{prompt}")
+            }
+        };
+        Ok(ToolExecution {
+            output,
+            details: serde_json::to_value(&outcome).map_err(|e| e.to_string())?,
+            is_error: false,
+        })
+    }
+}
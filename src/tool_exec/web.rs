@@ -0,0 +1,366 @@
+//! HTTP, browser and Excalidraw tools.
+
+use super::*;
+
+pub(crate) struct HttpRequestTool;
+
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &str {
+        "http_request"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "http_request",
+            "description": "Generic HTTP request (GET allowed without approval; other methods may require approval).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "method": { "type": "string" },
+                    "url": { "type": "string" },
+                    "headers": { "type": "object" },
+                    "body": { "type": "string" },
+                    "json": { "type": "boolean" },
+                    "timeout_ms": { "type": "integer" },
+                    "retry_safe": { "type": "boolean", "description": "Request is idempotent: retry 429/5xx and network errors for non-GET methods too." }
+                },
+                "required": ["url"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, args: &serde_json::Value) -> bool {
+        let method = args
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .to_ascii_uppercase();
+        method != "GET"
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolHttpRequestArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let method = parsed
+            .method
+            .unwrap_or_else(|| "GET".to_string())
+            .to_ascii_uppercase();
+        let timeout = parsed.timeout_ms.unwrap_or(DEFAULT_HTTP_TIMEOUT_MS);
+        let agent = make_http_agent(timeout);
+        if !matches!(method.as_str(), "GET" | "POST" | "PUT" | "PATCH" | "DELETE") {
+            return Err(format!("unsupported method: {method}"));
+        }
+        let retry = if method == "GET" || parsed.retry_safe.unwrap_or(false) {
+            HttpRetry::Idempotent
+        } else {
+            HttpRetry::ConnectOnly
+        };
+        let body = parsed.body.as_deref().map_or(HttpBody::Empty, HttpBody::Text);
+        let resp = http_with_retry("http_request", retry, TOOL_HTTP_ATTEMPTS, TOOL_HTTP_RETRY_BUDGET, body, || {
+            let mut req = agent.request(&method, &parsed.url);
+            if let Some(headers) = &parsed.headers {
+                for (k, v) in headers {
+                    req = req.set(k, v);
+                }
+            }
+            if parsed.body.is_some() && parsed.json.unwrap_or(false) {
+                req = req.set("content-type", "application/json");
+            }
+            req
+        });
+        let (status, text) = match resp {
+            Ok(resp) => {
+                let status = resp.status();
+                let text = resp.into_string().unwrap_or_default();
+                (status, text)
+            }
+            Err(HookHttpError::Status(code, text)) => (code, text),
+            Err(err) => return Err(format!("http_request failed: {err}")),
+        };
+        let truncated = if text.len() > 20_000 {
+            let safe: String = text.chars().take(20_000).collect();
+            format!("{safe}...[truncated]")
+        } else {
+            text
+        };
+        Ok(ToolExecution {
+            output: format!("http_request {method} {} -> {status}", parsed.url),
+            details: serde_json::json!({
+                "status": status,
+                "body": truncated
+            }),
+            is_error: status >= 400,
+        })
+    }
+}
+
+pub(crate) struct BrowserTool;
+
+impl Tool for BrowserTool {
+    fn name(&self) -> &str {
+        "browser"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "browser",
+            "description": "Browser automation via agent-browser CLI. Uses ref-based element selection from accessibility snapshots. Workflow: 1) 'open <url>' to navigate, 2) 'snapshot' to get element refs (@e1, @e2...), 3) interact using refs ('click @e1', 'fill @e2 text'). Sessions persist across calls. Commands: open, snapshot, click, fill, type, press, select, scroll, screenshot, pdf, get text/html/value, wait, eval, cookies, tab, back, forward, reload, close. Use 'find role/text/label' for semantic element finding.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "The agent-browser command (e.g., 'open https://example.com', 'snapshot', 'click @e2', 'fill @e3 hello')" },
+                    "session": { "type": "string", "description": "Session name for browser isolation. Defaults to 'default'." },
+                    "timeout_ms": { "type": "integer", "description": "Timeout in milliseconds. Default u64::MAX (no deadline)." }
+                },
+                "required": ["command"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let cancel = ctx.cancel;
+        let parsed: ToolBrowserArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+        let browser_timeout_ms = parsed.timeout_ms.unwrap_or(DEFAULT_HTTP_TIMEOUT_MS);
+        let session = parsed.session.unwrap_or_else(|| "default".to_string());
+
+        let mut cmd_args: Vec<String> = vec!["--session".to_string(), session];
+        let parts = shlex::split(&parsed.command)
+            .ok_or_else(|| "browser: malformed command (unmatched quotes)".to_string())?;
+        if parts.is_empty() {
+            return Err("browser: command is empty".into());
+        }
+        cmd_args.extend(parts);
+
+        let mut cmd = build_external_command(&external_bin("agent-browser"), &cmd_args);
+        scrub_command_env(&mut cmd);
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn().map_err(|e| format!("browser spawn: {e}"))?;
+        let browser_policy = ExecPolicy {
+            hard_timeout_ms: browser_timeout_ms,
+            stale_threshold_ms: 180_000,  // 3 min stale for browser
+        };
+        let result = wait_for_child_monitored(
+            &mut child,
+            "browser",
+            cancel,
+            &browser_policy,
+            DEFAULT_EXEC_MAX_OUTPUT_BYTES,
+        )?;
+        let stdout = result.stdout;
+        let stderr = result.stderr;
+        let is_error = !result.status.success();
+        let exit_code = subprocess_exit_info(&result.status);
+        let details = serde_json::json!({
+            "stdout": stdout,
+            "stderr": stderr,
+            "exit_code": exit_code
+        });
+        let output_text = subprocess_output_text(&stdout, &stderr, is_error);
+
+        Ok(ToolExecution {
+            output: output_text,
+            details,
+            is_error,
+        })
+    }
+}
+
+pub(crate) struct ExcalidrawTool;
+
+impl Tool for ExcalidrawTool {
+    fn name(&self) -> &str {
+        "excalidraw"
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": "excalidraw",
+            "description": "Create hand-drawn diagrams via Excalidraw MCP server. Actions: 'read_me' returns the element format reference (call before first create_view), 'create_view' renders a diagram from Excalidraw JSON elements. Requires excalidraw-mcp server (set EXCALIDRAW_MCP_CMD to override startup command, default: 'npx excalidraw-mcp --stdio').",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "description": "Action: 'read_me' (get element format reference) or 'create_view' (render diagram)" },
+                    "elements": { "type": "string", "description": "JSON array of Excalidraw elements (required for create_view)" }
+                },
+                "required": ["action"]
+            }
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let parsed: ToolExcalidrawArgs =
+            serde_json::from_value(args).map_err(|e| format!("args: {e}"))?;
+
+        let tool_name = match parsed.action.as_str() {
+            "read_me" => "read_me",
+            "create_view" => "create_view",
+            _ => return Err(format!("excalidraw: unknown action '{}', use 'read_me' or 'create_view'", parsed.action)),
+        };
+        let tool_args = if tool_name == "create_view" {
+            let elements = parsed.elements
+                .ok_or("excalidraw: 'elements' required for create_view")?;
+            serde_json::json!({ "elements": elements })
+        } else {
+            serde_json::json!({})
+        };
+
+        // Spawn excalidraw-mcp server via stdio
+        let mcp_cmd = env_optional("EXCALIDRAW_MCP_CMD")
+            .unwrap_or_else(|| "npx excalidraw-mcp --stdio".to_string());
+        let cmd_parts = shlex::split(&mcp_cmd)
+            .ok_or("excalidraw: malformed EXCALIDRAW_MCP_CMD")?;
+        if cmd_parts.is_empty() {
+            return Err("excalidraw: empty EXCALIDRAW_MCP_CMD".into());
+        }
+        let mut cmd = build_external_command(&cmd_parts[0], &cmd_parts[1..]);
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn().map_err(|e| format!("excalidraw spawn: {e}"))?;
+        let mut stdin = child.stdin.take().ok_or("excalidraw: no stdin")?;
+        let stdout = child.stdout.take().ok_or("excalidraw: no stdout")?;
+
+        // Run MCP interaction in a thread with cancellation-aware polling.
+        // read_line can't hang the caller forever.  The closure also
+        // guarantees cleanup (kill + wait) on both success and error paths.
+        let tool_name = tool_name.to_string();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+
+            // Helper: send JSON-RPC message with Content-Length framing
+            let send_msg = |writer: &mut dyn Write, msg: &serde_json::Value| -> Result<(), String> {
+                let body = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+                write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+                    .map_err(|e| format!("excalidraw write: {e}"))?;
+                writer.flush().map_err(|e| format!("excalidraw flush: {e}"))?;
+                Ok(())
+            };
+
+            // Helper: read JSON-RPC response with Content-Length framing.
+            // Reads headers until blank line, extracts Content-Length, then reads body.
+            let read_msg = |reader: &mut BufReader<std::process::ChildStdout>| -> Result<serde_json::Value, String> {
+                let mut content_length: Option<usize> = None;
+                // Read headers until blank separator line
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).map_err(|e| format!("excalidraw read: {e}"))?;
+                    let trimmed = line.trim();
+                    if trimmed.is_empty() {
+                        if content_length.is_some() { break; }
+                        continue; // skip leading blank lines before headers
+                    }
+                    if let Some(len_str) = trimmed.strip_prefix("Content-Length:") {
+                        content_length = Some(len_str.trim().parse()
+                            .map_err(|e| format!("excalidraw bad content-length: {e}"))?);
+                    }
+                    // ignore other headers (Content-Type, etc.)
+                }
+                let len = content_length.ok_or("excalidraw: missing Content-Length header")?;
+                if len > 10 * 1024 * 1024 {
+                    return Err(format!("excalidraw: response too large ({len} bytes)"));
+                }
+                let mut body = vec![0u8; len];
+                io::Read::read_exact(reader, &mut body)
+                    .map_err(|e| format!("excalidraw read body: {e}"))?;
+                serde_json::from_slice(&body).map_err(|e| format!("excalidraw parse: {e}"))
+            };
+
+            let result = (|| -> Result<serde_json::Value, String> {
+                // 1. Send initialize
+                send_msg(&mut stdin, &serde_json::json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {},
+                        "clientInfo": { "name": "aethervault", "version": "0.1" }
+                    }
+                }))?;
+                let init_resp = read_msg(&mut reader)?;
+                if let Some(err) = init_resp.get("error") {
+                    let msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown");
+                    return Err(format!("excalidraw: MCP initialize failed: {msg}"));
+                }
+
+                // 2. Send initialized notification
+                send_msg(&mut stdin, &serde_json::json!({
+                    "jsonrpc": "2.0", "method": "notifications/initialized"
+                }))?;
+
+                // 3. Call the tool
+                send_msg(&mut stdin, &serde_json::json!({
+                    "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                    "params": { "name": tool_name, "arguments": tool_args }
+                }))?;
+                read_msg(&mut reader)
+            })();
+
+            // Cleanup always runs regardless of success/failure
+            drop(stdin);
+            let _ = tx.send(result);
+        });
+
+        let cancel_token = Arc::new(AtomicBool::new(false));
+        let mut last_update = Instant::now();
+        let tool_resp: serde_json::Value = loop {
+            if cancel_token.load(Ordering::Acquire) {
+                kill_process_tree(&mut child);
+                return Err("excalidraw: canceled while waiting for MCP response".into());
+            }
+            match rx.recv_timeout(Duration::from_millis(PROCESS_POLL_MS)) {
+                Ok(result) => {
+                    // Thread completed; child will exit after stdin is dropped
+                    kill_process_tree(&mut child);
+                    break result?;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if last_update.elapsed() >= Duration::from_millis(STATUS_REPORT_MS) {
+                        log_debug!("[tool:excalidraw] waiting for MCP response (no deadline)");
+                        last_update = Instant::now();
+                    }
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    kill_process_tree(&mut child);
+                    return Err("excalidraw: MCP worker channel disconnected".into());
+                }
+            }
+        };
+
+        // Check for JSON-RPC error
+        if let Some(err) = tool_resp.get("error") {
+            let msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("unknown");
+            let code = err.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
+            return Err(format!("excalidraw: MCP error {code}: {msg}"));
+        }
+        let result = tool_resp.get("result")
+            .cloned()
+            .ok_or("excalidraw: MCP response missing 'result' field")?;
+        let content_text = result.get("content")
+            .and_then(|c| c.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|item| item.get("text"))
+            .and_then(|t| t.as_str())
+            .unwrap_or("");
+
+        let is_error = result.get("isError").and_then(|v| v.as_bool()).unwrap_or(false);
+        Ok(ToolExecution {
+            output: content_text.to_string(),
+            details: result,
+            is_error,
+        })
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use crate::memory_db::MemoryDb;
+use crate::{
+    builtin_tools, command_spec_to_vec, run_hook_command, BackgroundTaskRegistry,
+    CustomToolSpec, ToolExecution,
+};
+
+/// Everything a tool may need while it runs.
+pub(crate) struct ToolContext<'a> {
+    pub(crate) mv2: &'a Path,
+    pub(crate) db: &'a MemoryDb,
+    pub(crate) bg_registry: Option<(i64, Arc<Mutex<BackgroundTaskRegistry>>)>,
+    pub(crate) cancel: &'a Arc<AtomicBool>,
+}
+
+/// One agent tool: its catalog entry, approval policy and implementation.
+/// The read-only, exec-policy and approval gates in `execute_tool` run before
+/// [`Tool::execute`] is called.
+pub(crate) trait Tool: Send + Sync {
+    fn name(&self) -> &str;
+    /// `{name, description, inputSchema}`, as listed to agents and MCP clients.
+    fn schema(&self) -> serde_json::Value;
+    /// Default approval policy; env overrides in `requires_approval` win.
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        false
+    }
+    /// Write tools are refused in read-only mode.
+    fn is_write(&self) -> bool {
+        false
+    }
+    /// Whether retries may pass an `idempotency_key` to replay the first
+    /// result instead of running again. Defaults to write tools; outbound
+    /// senders opt in too, since a double send is the most visible retry failure.
+    fn accepts_idempotency_key(&self) -> bool {
+        self.is_write()
+    }
+    fn execute(&self, ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String>;
+}
+
+/// Tools by name, in catalog order. Registering a name that already exists
+/// replaces that tool in place.
+#[derive(Clone, Default)]
+pub(crate) struct ToolRegistry {
+    tools: Vec<Arc<dyn Tool>>,
+    index: HashMap<String, usize>,
+}
+
+impl ToolRegistry {
+    pub(crate) fn builtin() -> Self {
+        let mut registry = Self::default();
+        for tool in builtin_tools() {
+            registry.register(tool);
+        }
+        registry
+    }
+
+    /// Add `tool`, returning the one it replaced, if any.
+    pub(crate) fn register(&mut self, tool: Arc<dyn Tool>) -> Option<Arc<dyn Tool>> {
+        match self.index.get(tool.name()) {
+            Some(&pos) => Some(std::mem::replace(&mut self.tools[pos], tool)),
+            None => {
+                self.index.insert(tool.name().to_string(), self.tools.len());
+                self.tools.push(tool);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.index.get(name).map(|&pos| self.tools[pos].clone())
+    }
+
+    /// Each tool's schema; tools that take an `idempotency_key` list it.
+    pub(crate) fn definitions(&self) -> Vec<serde_json::Value> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                let mut schema = tool.schema();
+                if tool.accepts_idempotency_key()
+                    && let Some(props) = schema["inputSchema"]["properties"].as_object_mut()
+                {
+                    props.insert(
+                        "idempotency_key".into(),
+                        serde_json::json!({
+                            "type": "string",
+                            "description": "Retry-safe key: a repeat within the replay window returns the first result instead of running again."
+                        }),
+                    );
+                }
+                schema
+            })
+            .collect()
+    }
+
+    /// Unknown tools need no approval here; `mcp__` tools are handled by the caller.
+    pub(crate) fn requires_approval(&self, name: &str, args: &serde_json::Value) -> bool {
+        self.get(name).is_some_and(|t| t.requires_approval(args))
+    }
+}
+
+static TOOL_REGISTRY: OnceLock<RwLock<Arc<ToolRegistry>>> = OnceLock::new();
+
+/// Tools switched off for this process (`--disable-tool`, `agent.disabled_tools`).
+static DISABLED_TOOLS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Disable `names` for the rest of the process: they leave the advertised
+/// catalog and calls to them fail with "tool disabled".
+pub(crate) fn disable_tools<I: IntoIterator<Item = String>>(names: I) {
+    let mut disabled = DISABLED_TOOLS.write().unwrap_or_else(|e| e.into_inner());
+    for name in names {
+        let name = name.trim().to_string();
+        if !name.is_empty() && !disabled.contains(&name) {
+            disabled.push(name);
+        }
+    }
+}
+
+pub(crate) fn is_tool_disabled(name: &str) -> bool {
+    DISABLED_TOOLS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|n| n == name)
+}
+
+fn global_registry() -> &'static RwLock<Arc<ToolRegistry>> {
+    TOOL_REGISTRY.get_or_init(|| RwLock::new(Arc::new(ToolRegistry::builtin())))
+}
+
+/// Snapshot of the process-wide registry. Cheap to call; tools running from it
+/// may look tools up again without holding any lock.
+pub(crate) fn tool_registry() -> Arc<ToolRegistry> {
+    global_registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Add or override a tool for every later `execute_tool` and catalog listing.
+pub(crate) fn register_tool(tool: Arc<dyn Tool>) -> Option<Arc<dyn Tool>> {
+    let mut guard = global_registry().write().unwrap_or_else(|e| e.into_inner());
+    Arc::make_mut(&mut guard).register(tool)
+}
+
+/// Prefix of tools from `agent.custom_tools`.
+pub(crate) const CUSTOM_TOOL_PREFIX: &str = "custom__";
+const DEFAULT_CUSTOM_TOOL_TIMEOUT_MS: u64 = 60_000;
+
+/// Register each `agent.custom_tools` entry as `custom__{name}`. Entries with
+/// a name outside `[A-Za-z0-9_-]` or an empty command are skipped with a warning.
+pub(crate) fn register_custom_tools(specs: &[CustomToolSpec]) {
+    for spec in specs {
+        let name = spec.name.trim();
+        let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let command = command_spec_to_vec(&spec.command);
+        if !valid || command.first().is_none_or(|c| c.trim().is_empty()) {
+            log_warn!("agent.custom_tools: skipping {:?} (needs a [A-Za-z0-9_-] name and a command)", spec.name);
+            continue;
+        }
+        register_tool(Arc::new(CommandTool {
+            name: format!("{CUSTOM_TOOL_PREFIX}{name}"),
+            command,
+            spec: spec.clone(),
+        }));
+    }
+}
+
+/// A tool from `agent.custom_tools`, run through the hook runner.
+struct CommandTool {
+    name: String,
+    command: Vec<String>,
+    spec: CustomToolSpec,
+}
+
+impl Tool for CommandTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.spec.description,
+            "inputSchema": self.spec.input_schema.clone().unwrap_or_else(|| serde_json::json!({ "type": "object" })),
+        })
+    }
+
+    fn requires_approval(&self, _args: &serde_json::Value) -> bool {
+        self.spec.requires_approval.unwrap_or(true)
+    }
+
+    fn is_write(&self) -> bool {
+        self.spec.write.unwrap_or(true)
+    }
+
+    fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+        let timeout_ms = self.spec.timeout_ms.unwrap_or(DEFAULT_CUSTOM_TOOL_TIMEOUT_MS);
+        let stdout = run_hook_command(&self.command, &args, timeout_ms, "tool")
+            .map_err(|e| format!("{}: {e}", self.name))?;
+        let output = stdout.trim_end().to_string();
+        let details = serde_json::from_str(&output).unwrap_or_else(|_| serde_json::json!({}));
+        Ok(ToolExecution {
+            output,
+            details,
+            is_error: false,
+        })
+    }
+}
+
+/// One line telling a human approver what `tool` would do with `args`.
+pub(crate) fn approval_summary(tool: &str, args: &serde_json::Value) -> String {
+    let field = |key: &str| -> String {
+        match args.get(key) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Some(serde_json::Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        }
+    };
+    let preview = |text: String| -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() > 80 {
+            format!("{}…", text.chars().take(80).collect::<String>())
+        } else {
+            text
+        }
+    };
+    let summary = match tool {
+        "exec" => format!("Run command: {}", field("command")),
+        "email_send" | "gmail_send" => {
+            let mut s = format!("Send email to {}, subject \"{}\"", field("to"), field("subject"));
+            let attachments = args.get("attachments").and_then(|v| v.as_array()).map_or(0, Vec::len);
+            if attachments > 0 {
+                s.push_str(&format!(" with {attachments} attachment(s)"));
+            }
+            s
+        }
+        "email_reply" => {
+            let all = args.get("reply_all").and_then(|v| v.as_bool()).unwrap_or(false);
+            format!(
+                "Reply{} to email {}: \"{}\"",
+                if all { " all" } else { "" },
+                field("id"),
+                preview(field("body"))
+            )
+        }
+        "email_archive" => format!("Archive email {}", field("id")),
+        "signal_send" | "imessage_send" => format!(
+            "Send {} message to {}: \"{}\"",
+            if tool == "signal_send" { "Signal" } else { "iMessage" },
+            field("to"),
+            preview(field("text"))
+        ),
+        "notify" => format!("Send notification: \"{}\"", preview(field("text"))),
+        "gcal_create" => format!("Create calendar event \"{}\" from {} to {}", field("summary"), field("start"), field("end")),
+        "ms_calendar_create" => {
+            format!("Create calendar event \"{}\" from {} to {}", field("subject"), field("start"), field("end"))
+        }
+        "config_set" => format!("Set config key {}", field("key")),
+        "trigger_add" => format!("Add {} trigger {}", field("kind"), field("name")),
+        "trigger_remove" => format!("Remove trigger {}", field("id")),
+        "memory_export" => "Overwrite workspace memory files with the capsule copies".to_string(),
+        "fs_write" => {
+            let verb = if args.get("append").and_then(|v| v.as_bool()).unwrap_or(false) { "Append to" } else { "Write" };
+            format!("{verb} file {} ({} bytes)", field("path"), field("text").len())
+        }
+        "http_request" => format!(
+            "{} {}",
+            args.get("method").and_then(|v| v.as_str()).unwrap_or("GET").to_ascii_uppercase(),
+            field("url")
+        ),
+        "scale" => format!("Resize the host to {}", field("size")),
+        "browser" => format!("Browser: {}", preview(field("command"))),
+        "excalidraw" => format!("Excalidraw {}", field("action")),
+        "self_upgrade" => format!("Upgrade and restart from branch {}", field("branch")),
+        "subagent_register" => format!("Register subagent {}", field("name")),
+        "subagent_remove" => format!("Remove subagent {}", field("name")),
+        _ => format!("{tool} {}", preview(args.to_string())),
+    };
+    summary.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execute_tool;
+
+    struct Echo(&'static str);
+
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn schema(&self) -> serde_json::Value {
+            serde_json::json!({ "name": self.0, "description": "echo", "inputSchema": { "type": "object" } })
+        }
+
+        fn is_write(&self) -> bool {
+            true
+        }
+
+        fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+            Ok(ToolExecution {
+                output: args.to_string(),
+                details: args,
+                is_error: false,
+            })
+        }
+    }
+
+    #[test]
+    fn builtin_catalog_and_policies() {
+        let registry = ToolRegistry::builtin();
+        let names: Vec<_> = registry
+            .definitions()
+            .iter()
+            .filter_map(|d| d.get("name").and_then(|v| v.as_str()).map(str::to_string))
+            .collect();
+        assert_eq!(names.first().map(String::as_str), Some("query"));
+        assert!(names.iter().any(|n| n == "subagent_batch"));
+        assert!(registry.requires_approval("exec", &serde_json::json!({})));
+        assert!(!registry.requires_approval("http_request", &serde_json::json!({ "method": "get" })));
+        assert!(registry.requires_approval("http_request", &serde_json::json!({ "method": "POST" })));
+        assert!(registry.get("put").is_some_and(|t| t.is_write()));
+        assert!(!registry.requires_approval("no_such_tool", &serde_json::json!({})));
+    }
+
+    #[test]
+    fn approval_summaries_describe_the_action() {
+        let args = serde_json::json!({ "to": ["a@x.com", "b@x.com"], "subject": "Q3", "attachments": ["r.pdf"] });
+        assert_eq!(approval_summary("email_send", &args), "Send email to a@x.com, b@x.com, subject \"Q3\" with 1 attachment(s)");
+        assert_eq!(approval_summary("exec", &serde_json::json!({ "command": "rm -rf build" })), "Run command: rm -rf build");
+        assert_eq!(approval_summary("scale", &serde_json::json!({ "action": "resize", "size": "s-2vcpu-4gb" })), "Resize the host to s-2vcpu-4gb");
+        assert_eq!(approval_summary("mcp__x__y", &serde_json::json!({ "a": 1 })), "mcp__x__y {\"a\":1}");
+    }
+
+    #[test]
+    fn register_overrides_in_place() {
+        let mut registry = ToolRegistry::builtin();
+        let count = registry.definitions().len();
+        assert!(registry.register(Arc::new(Echo("query"))).is_some());
+        assert_eq!(registry.definitions().len(), count);
+        assert_eq!(registry.definitions()[0]["description"], "echo");
+        assert!(registry.register(Arc::new(Echo("echo_extra"))).is_none());
+        assert_eq!(registry.definitions().len(), count + 1);
+    }
+
+    #[test]
+    fn registered_tool_runs_through_execute_tool() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("tool_registry_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        register_tool(Arc::new(Echo("registry_test_echo")));
+
+        let result = execute_tool("registry_test_echo", serde_json::json!({ "x": 1 }), &path, &db, false, None).unwrap();
+        assert_eq!(result.details["x"], 1);
+        let refused = execute_tool("registry_test_echo", serde_json::json!({}), &path, &db, true, None);
+        let refused = refused.unwrap_err();
+        assert_eq!(refused.to_string(), "tool disabled in read-only mode");
+        assert!(matches!(refused, crate::AethervaultError::Denied(_)));
+        let unknown = execute_tool("no_such_tool", serde_json::json!({}), &path, &db, false, None).unwrap_err();
+        assert_eq!(unknown.to_string(), "unknown tool");
+        assert!(matches!(unknown, crate::AethervaultError::NotFound(_)));
+    }
+
+    #[test]
+    fn custom_tools_run_their_command_with_args_on_stdin() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("custom_tools_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        let spec = |name: &str| CustomToolSpec {
+            name: name.to_string(),
+            description: "Echo the arguments back.".to_string(),
+            input_schema: None,
+            command: crate::CommandSpec::Array(vec!["cat".to_string()]),
+            timeout_ms: Some(5_000),
+            requires_approval: Some(false),
+            write: Some(false),
+        };
+        register_custom_tools(&[spec("registry_test_cat"), spec("bad name"), spec("query")]);
+
+        let advertised = crate::tool_definitions_json();
+        assert!(advertised.iter().any(|t| t["name"] == "custom__registry_test_cat"));
+        assert!(!advertised.iter().any(|t| t["name"] == "custom__bad name"));
+        assert_ne!(tool_registry().get("query").unwrap().schema()["description"], "Echo the arguments back.");
+        let result =
+            execute_tool("custom__registry_test_cat", serde_json::json!({ "x": 1 }), &path, &db, true, None).unwrap();
+        assert_eq!(result.output, r#"{"x":1}"#);
+        assert_eq!(result.details["x"], 1);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn disabled_tools_leave_the_catalog_and_refuse_calls() {
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("disabled_tools_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        register_tool(Arc::new(Echo("registry_test_disabled")));
+        disable_tools(["registry_test_disabled".to_string()]);
+
+        let advertised = crate::tool_definitions_json();
+        assert!(!advertised.iter().any(|t| t["name"] == "registry_test_disabled"));
+        assert!(advertised.iter().any(|t| t["name"] == "query"));
+        let refused = execute_tool("registry_test_disabled", serde_json::json!({}), &path, &db, false, None);
+        assert_eq!(refused.unwrap_err().to_string(), "tool disabled");
+        let found = execute_tool("tool_search", serde_json::json!({ "query": "registry_test_disabled echo" }), &path, &db, false, None)
+            .unwrap();
+        let entry = found.details["results"].as_array().unwrap().iter().find(|r| r["name"] == "registry_test_disabled").cloned();
+        assert_eq!(entry.unwrap()["available"], false);
+    }
+
+    #[test]
+    fn idempotency_key_replays_the_first_result() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+        struct Counter;
+        impl Tool for Counter {
+            fn name(&self) -> &str {
+                "registry_test_counter"
+            }
+            fn schema(&self) -> serde_json::Value {
+                serde_json::json!({ "name": self.name(), "inputSchema": { "type": "object" } })
+            }
+            fn is_write(&self) -> bool {
+                true
+            }
+            fn execute(&self, _ctx: &ToolContext, args: serde_json::Value) -> Result<ToolExecution, String> {
+                assert!(args.get("idempotency_key").is_none());
+                let run = RUNS.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(ToolExecution { output: format!("run {run}"), details: serde_json::json!({}), is_error: false })
+            }
+        }
+
+        let dir = std::env::temp_dir().join("aethervault_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("idempotency_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = MemoryDb::open_or_create(&path).unwrap();
+        register_tool(Arc::new(Counter));
+        let call = |args: serde_json::Value| execute_tool("registry_test_counter", args, &path, &db, false, None);
+
+        let first = call(serde_json::json!({ "text": "hi", "idempotency_key": "k1" })).unwrap();
+        let retry = call(serde_json::json!({ "text": "hi", "idempotency_key": "k1" })).unwrap();
+        assert_eq!((first.output.as_str(), retry.output.as_str()), ("run 1", "run 1"));
+        assert_eq!(retry.details["idempotent_replay"], true);
+        assert!(call(serde_json::json!({ "text": "changed", "idempotency_key": "k1" })).is_err());
+        assert_eq!(call(serde_json::json!({ "text": "hi" })).unwrap().output, "run 2");
+        let catalog = crate::tool_catalog_map(&ToolRegistry::builtin().definitions());
+        assert!(catalog["email_send"]["inputSchema"]["properties"].get("idempotency_key").is_some());
+        assert!(catalog["query"]["inputSchema"]["properties"].get("idempotency_key").is_none());
+    }
+}
@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::memory_db::TemporalFilter;
+use crate::memory_db::{SearchHit, TemporalFilter};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
@@ -14,6 +14,14 @@ pub(crate) struct GetResponse {
     pub(crate) text: String,
 }
 
+/// A frame that a partial `get` id could have meant.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct FrameCandidate {
+    pub(crate) frame_id: u64,
+    pub(crate) uri: String,
+    pub(crate) title: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct StatusResponse {
     pub(crate) mv2: String,
@@ -49,6 +57,30 @@ pub(crate) struct DiffReport {
     pub(crate) changed: Vec<DiffChange>,
 }
 
+/// A collection seen in frame URIs and/or declared in `config.collections`.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CollectionSummary {
+    pub(crate) name: String,
+    /// Active frames under the collection.
+    pub(crate) frames: usize,
+    /// Newest frame timestamp (unix seconds).
+    pub(crate) last_updated: Option<i64>,
+    pub(crate) configured: bool,
+    pub(crate) description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CollectionInfo {
+    #[serde(flatten)]
+    pub(crate) summary: CollectionSummary,
+    pub(crate) first_updated: Option<i64>,
+    pub(crate) roots: Vec<String>,
+    pub(crate) globs: Vec<String>,
+    pub(crate) context: Option<String>,
+    /// Most recently updated URIs, newest first.
+    pub(crate) recent: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct ConfigEntry {
     pub(crate) key: String,
@@ -56,37 +88,100 @@ pub(crate) struct ConfigEntry {
     pub(crate) timestamp: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct QueryPlan {
-    pub(crate) cleaned_query: String,
-    pub(crate) scope: Option<String>,
-    pub(crate) as_of_ts: Option<i64>,
-    pub(crate) temporal: Option<TemporalFilter>,
-    pub(crate) skipped_expansion: bool,
-    pub(crate) lex_queries: Vec<String>,
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlan {
+    pub cleaned_query: String,
+    pub scope: Option<String>,
+    pub as_of_ts: Option<i64>,
+    /// Set when `asof` was `#N`: only frames with id <= N are searched.
+    pub as_of_frame: Option<u64>,
+    pub temporal: Option<TemporalFilter>,
+    pub skipped_expansion: bool,
+    pub lex_queries: Vec<String>,
+    pub vec_queries: Vec<String>,
+    /// True when the probe's dominant hit was returned without fusion or rerank.
+    pub fast_path: bool,
+    /// Recency boost half-life in days, when one was applied to the scores.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recency_halflife_days: Option<f32>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    pub rank: usize,
+    pub frame_id: u64,
+    pub uri: String,
+    pub title: Option<String>,
+    pub snippet: String,
+    /// Snippet before `plain_snippets` stripped its Markdown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_snippet: Option<String>,
+    /// In [0,1] before the feedback and recency adjustments: the rerank blend,
+    /// or the RRF total over its best possible value when rerank is off.
+    pub score: f32,
+    /// Score calibrated to [0,1] across the candidate pool (before `limit`); comparable between rerank and RRF-only runs.
+    pub confidence: f32,
+    pub rrf_rank: usize,
+    pub rrf_score: f32,
+    pub rerank_score: Option<f32>,
+    pub feedback_score: Option<f32>,
+    /// Multiplier from the recency boost (1.0–1.3), when enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recency_factor: Option<f32>,
+    pub sources: Vec<String>,
+    /// Human-readable account of the lanes, adjustments and terms behind this hit (`--explain`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub why: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResponse {
+    pub query: String,
+    pub plan: QueryPlan,
+    pub warnings: Vec<String>,
+    pub results: Vec<QueryResult>,
+    /// Vector-lane queries and their raw hits, when `vec_debug` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vec_debug: Option<Vec<VecLaneDebug>>,
+}
+
+/// One vector-lane search: the query text sent for embedding and every hit it
+/// returned, before fusion.
+#[derive(Debug, Clone, Serialize)]
+pub struct VecLaneDebug {
+    pub query: String,
+    pub hits: Vec<VecHitDebug>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VecHitDebug {
+    pub rank: usize,
+    pub frame_id: u64,
+    pub uri: String,
+    /// Raw similarity reported by the vector store (cosine for the default collection).
+    pub similarity: f32,
+}
+
+/// Results sharing a collection or track (`query --group-by`), best first.
 #[derive(Debug, Serialize)]
-pub(crate) struct QueryResult {
-    pub(crate) rank: usize,
-    pub(crate) frame_id: u64,
-    pub(crate) uri: String,
-    pub(crate) title: Option<String>,
-    pub(crate) snippet: String,
-    pub(crate) score: f32,
-    pub(crate) rrf_rank: usize,
-    pub(crate) rrf_score: f32,
-    pub(crate) rerank_score: Option<f32>,
-    pub(crate) feedback_score: Option<f32>,
-    pub(crate) sources: Vec<String>,
+pub(crate) struct ResultGroup {
+    pub(crate) key: String,
+    pub(crate) results: Vec<QueryResult>,
 }
 
+/// Neighbours of a source frame (`similar`). `method` is `vector` when the
+/// Qdrant lane answered, `lexical` when the doc's salient `terms` were used.
 #[derive(Debug, Serialize)]
-pub(crate) struct QueryResponse {
-    pub(crate) query: String,
-    pub(crate) plan: QueryPlan,
+pub(crate) struct SimilarResponse {
+    pub(crate) source_frame_id: u64,
+    pub(crate) source_uri: String,
+    pub(crate) method: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) terms: Vec<String>,
     pub(crate) warnings: Vec<String>,
-    pub(crate) results: Vec<QueryResult>,
+    pub(crate) hits: Vec<SearchHit>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -114,21 +209,21 @@ pub(crate) struct AgentLogEntry {
 }
 
 #[derive(Debug, Serialize)]
-pub(crate) struct ContextCitation {
-    pub(crate) rank: usize,
-    pub(crate) frame_id: u64,
-    pub(crate) uri: String,
-    pub(crate) title: Option<String>,
-    pub(crate) score: f32,
+pub struct ContextCitation {
+    pub rank: usize,
+    pub frame_id: u64,
+    pub uri: String,
+    pub title: Option<String>,
+    pub score: f32,
 }
 
 #[derive(Debug, Serialize)]
-pub(crate) struct ContextPack {
-    pub(crate) query: String,
-    pub(crate) plan: QueryPlan,
-    pub(crate) warnings: Vec<String>,
-    pub(crate) citations: Vec<ContextCitation>,
-    pub(crate) context: String,
+pub struct ContextPack {
+    pub query: String,
+    pub plan: QueryPlan,
+    pub warnings: Vec<String>,
+    pub citations: Vec<ContextCitation>,
+    pub context: String,
 }
 
 #[derive(Debug)]
@@ -150,6 +245,8 @@ pub(crate) struct QueryArgs {
     pub(crate) embed_cache: usize,
     pub(crate) embed_no_cache: bool,
     pub(crate) rerank_docs: usize,
+    /// Fixed rerank share of the blend; `None` keeps the position-aware default.
+    pub(crate) rerank_weight: Option<f32>,
     pub(crate) rerank_chunk_chars: usize,
     pub(crate) rerank_chunk_overlap: usize,
     pub(crate) plan: bool,
@@ -157,6 +254,28 @@ pub(crate) struct QueryArgs {
     pub(crate) before: Option<String>,
     pub(crate) after: Option<String>,
     pub(crate) feedback_weight: f32,
+    /// Half-life in days of the recency boost (0 = off).
+    pub(crate) recency_halflife_days: f32,
+    /// Max concurrent vector lane searches (1 = serial).
+    pub(crate) search_jobs: usize,
+    /// Skip expansion, vector lane and rerank when the probe finds a dominant hit.
+    pub(crate) fast_path: bool,
+    /// Top/second probe score ratio that counts as dominant.
+    pub(crate) fast_path_margin: f32,
+    /// Ignore the strong-signal probe and always run expansion.
+    pub(crate) no_skip_expansion_probe: bool,
+    /// Report expansion/rerank hook stderr as warnings even when they succeed.
+    pub(crate) hook_debug: bool,
+    /// Record raw vector-lane similarities in `QueryResponse::vec_debug`.
+    pub(crate) vec_debug: bool,
+    /// Strip Markdown markup from result snippets (raw kept in `raw_snippet`).
+    pub(crate) plain_snippets: bool,
+    /// Keep snippets as extracted: no whitespace cleanup or cut marker.
+    pub(crate) raw_snippets: bool,
+    /// Shown where a snippet was cut short.
+    pub(crate) snippet_ellipsis: String,
+    /// Fill each result's `why`.
+    pub(crate) explain: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -164,13 +283,46 @@ pub(crate) struct CapsuleConfig {
     #[serde(default)]
     pub(crate) context: Option<String>,
     #[serde(default)]
-    pub(crate) collections: HashMap<String, CollectionConfig>,
+    pub(crate) collections: BTreeMap<String, CollectionConfig>,
     #[serde(default)]
     pub(crate) hooks: Option<HookConfig>,
     #[serde(default)]
     pub(crate) agent: Option<AgentConfig>,
+    #[serde(default)]
+    pub(crate) signal_thresholds: Option<SignalThresholds>,
+    /// Sorted so re-saving unchanged config serializes byte-for-byte the same.
     #[serde(default, flatten)]
-    pub(crate) extra: HashMap<String, serde_json::Value>,
+    pub(crate) extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// When the lexical probe counts as a "strong signal" (query expansion is then
+/// skipped). Scores are raw lexical scores from the probe's top two hits, so the
+/// right values depend on the backend's score scale. Set in `index.signal_thresholds`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SignalThresholds {
+    /// Top scores at or below this use the absolute-gap test, above it the ratio test.
+    pub(crate) low_score_ceiling: f32,
+    /// Gap test: minimum top score.
+    pub(crate) low_min_score: f32,
+    /// Gap test: minimum `top - second` difference.
+    pub(crate) low_min_gap: f32,
+    /// Ratio test: minimum top score.
+    pub(crate) high_min_score: f32,
+    /// Ratio test: minimum `top / second` ratio.
+    pub(crate) high_min_ratio: f32,
+}
+
+impl Default for SignalThresholds {
+    fn default() -> Self {
+        Self {
+            low_score_ceiling: 1.5,
+            low_min_score: 0.85,
+            low_min_gap: 0.15,
+            high_min_score: 2.0,
+            high_min_ratio: 1.3,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -209,6 +361,9 @@ pub(crate) struct AgentConfig {
     pub(crate) telegram_token: Option<String>,
     #[serde(default)]
     pub(crate) telegram_chat_id: Option<String>,
+    /// Webhook (Slack/Discord/Teams) told about approvals queued by `schedule`/`watch` runs.
+    #[serde(default)]
+    pub(crate) approval_webhook: Option<String>,
     #[serde(default)]
     pub(crate) context_query: Option<String>,
     #[serde(default)]
@@ -219,6 +374,23 @@ pub(crate) struct AgentConfig {
     pub(crate) max_steps: Option<usize>,
     #[serde(default)]
     pub(crate) subagent_max_steps: Option<usize>,
+    /// How deep subagents may nest (a subagent spawning a subagent is depth 2).
+    #[serde(default)]
+    pub(crate) subagent_max_depth: Option<usize>,
+    /// Upper bound on concurrently running subagents in one `subagent_batch`.
+    #[serde(default)]
+    pub(crate) subagent_max_concurrent: Option<usize>,
+    /// Max tool calls from one model turn executed at once (`--tool-concurrency`).
+    #[serde(default)]
+    pub(crate) tool_concurrency: Option<usize>,
+    /// Himalaya account the `email_*` tools use when a call names none.
+    #[serde(default)]
+    pub(crate) default_email_account: Option<String>,
+    /// Wall-clock limit for one model hook call (`--model-timeout-ms`).
+    #[serde(default)]
+    pub(crate) model_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub(crate) model_timeout_policy: Option<crate::ModelTimeoutPolicy>,
     #[serde(default)]
     pub(crate) log: Option<bool>,
     #[serde(default)]
@@ -234,6 +406,56 @@ pub(crate) struct AgentConfig {
     /// MCP servers to spawn as long-lived sidecars (generic plugin system)
     #[serde(default)]
     pub(crate) mcp_servers: Vec<McpServerConfig>,
+    /// Commands `exec` may run: plain entries are prefixes, `re:` entries are regexes.
+    /// Empty means unrestricted (approval still applies).
+    #[serde(default)]
+    pub(crate) exec_allow: Vec<String>,
+    /// Tools never advertised to the model and refused if called anyway.
+    #[serde(default)]
+    pub(crate) disabled_tools: Vec<String>,
+    /// Tool the harness runs before the first model turn (e.g. `context`), priming the run with memory (`--force-tool`).
+    #[serde(default)]
+    pub(crate) force_first_tool: Option<String>,
+    /// External commands exposed as agent tools (`custom__{name}`).
+    #[serde(default)]
+    pub(crate) custom_tools: Vec<CustomToolSpec>,
+    /// Per-bridge sender allowlist (`{"telegram": ["123456", "@alice"]}`); see `SenderAllowlist`.
+    #[serde(default)]
+    pub(crate) bridge_allow: BTreeMap<String, Vec<String>>,
+    /// Bridge group chats as one shared session or one per sender (`per-user`, the default).
+    #[serde(default)]
+    pub(crate) group_sessions: Option<crate::GroupSessions>,
+    /// Advertise the whole catalog instead of base tools plus prompt-relevant ones.
+    #[serde(default)]
+    pub(crate) all_tools: Option<bool>,
+    /// Minimum `tool_score` against the prompt for a non-base tool to start active (default 4).
+    #[serde(default)]
+    pub(crate) tool_activation_min_score: Option<i32>,
+    /// Max non-base tools activated from the prompt (default 6).
+    #[serde(default)]
+    pub(crate) tool_activation_limit: Option<usize>,
+}
+
+/// An agent tool backed by an external command: the call's arguments go to its
+/// stdin as JSON and its stdout is the tool output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CustomToolSpec {
+    /// Advertised as `custom__{name}`, so it never shadows a built-in.
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) description: String,
+    /// JSON schema for the arguments (default: any object).
+    #[serde(default)]
+    pub(crate) input_schema: Option<serde_json::Value>,
+    pub(crate) command: CommandSpec,
+    #[serde(default)]
+    pub(crate) timeout_ms: Option<u64>,
+    /// Queue each call for approval, like MCP tools (default true).
+    #[serde(default)]
+    pub(crate) requires_approval: Option<bool>,
+    /// Refuse the tool in read-only mode (default true; set false for commands that only read).
+    #[serde(default)]
+    pub(crate) write: Option<bool>,
 }
 
 /// Configuration for an external MCP server (tool plugin)
@@ -245,7 +467,7 @@ pub(crate) struct McpServerConfig {
     pub(crate) command: String,
     /// Environment variables to pass to the server
     #[serde(default)]
-    pub(crate) env: HashMap<String, String>,
+    pub(crate) env: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -264,11 +486,17 @@ pub(crate) struct HookSpec {
     pub(crate) full_text: Option<bool>,
 }
 
+/// Version of the expansion hook input/output shape (see docs/ARCHITECTURE.md).
+pub(crate) const EXPANSION_HOOK_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Serialize)]
 pub(crate) struct ExpansionHookInput {
+    pub(crate) schema_version: u32,
     pub(crate) query: String,
     pub(crate) max_expansions: usize,
+    /// URI prefix the user restricted the query to, e.g. `aethervault://notes/`.
     pub(crate) scope: Option<String>,
+    /// `{start_utc, end_utc}` in Unix seconds; either bound may be absent.
     pub(crate) temporal: Option<TemporalFilter>,
 }
 
@@ -280,6 +508,15 @@ pub(crate) struct ExpansionHookOutput {
     pub(crate) vec: Vec<String>,
     #[serde(default)]
     pub(crate) warnings: Vec<String>,
+    /// Narrower URI prefix to search instead of the requested scope.
+    #[serde(default)]
+    pub(crate) scope: Option<String>,
+    /// Time window to apply, intersected with the requested one.
+    #[serde(default)]
+    pub(crate) temporal: Option<TemporalFilter>,
+    /// Hook stderr, surfaced as a warning with `--hook-debug`.
+    #[serde(skip)]
+    pub(crate) stderr: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -309,6 +546,9 @@ pub(crate) struct RerankHookOutput {
     pub(crate) items: Vec<RerankHookScore>,
     #[serde(default)]
     pub(crate) warnings: Vec<String>,
+    /// Hook stderr, surfaced as a warning with `--hook-debug`.
+    #[serde(skip)]
+    pub(crate) stderr: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -338,7 +578,11 @@ pub(crate) struct AgentMessage {
     pub(crate) thinking_blocks: Vec<serde_json::Value>,
 }
 
+/// Accepts `{id, name, args}` or the OpenAI shape
+/// `{id, type: "function", function: {name, arguments}}` (arguments as a JSON
+/// string or object); always serializes as `{id, name, args}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "AgentToolCallWire")]
 pub(crate) struct AgentToolCall {
     pub(crate) id: String,
     pub(crate) name: String,
@@ -346,8 +590,68 @@ pub(crate) struct AgentToolCall {
     pub(crate) args: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Deserialize)]
+struct AgentToolCallWire {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    args: Option<serde_json::Value>,
+    #[serde(default)]
+    function: Option<AgentToolCallFunction>,
+}
+
+#[derive(Deserialize)]
+struct AgentToolCallFunction {
+    name: String,
+    #[serde(default)]
+    arguments: Option<serde_json::Value>,
+}
+
+impl TryFrom<AgentToolCallWire> for AgentToolCall {
+    type Error = String;
+
+    fn try_from(wire: AgentToolCallWire) -> Result<Self, Self::Error> {
+        let id = wire.id.unwrap_or_default();
+        if let Some(function) = wire.function {
+            let args = match function.arguments {
+                Some(serde_json::Value::String(raw)) if raw.trim().is_empty() => serde_json::json!({}),
+                Some(serde_json::Value::String(raw)) => serde_json::from_str(&raw).map_err(|e| {
+                    format!("tool call {id}: function.arguments is not valid JSON: {e}")
+                })?,
+                Some(value) => value,
+                None => serde_json::Value::Null,
+            };
+            return Ok(Self {
+                id,
+                name: function.name,
+                args,
+            });
+        }
+        let name = wire
+            .name
+            .ok_or_else(|| format!("tool call {id}: missing `name` (or `function.name`)"))?;
+        Ok(Self {
+            id,
+            name,
+            args: wire.args.unwrap_or_default(),
+        })
+    }
+}
+
+/// Version of the agent hook JSON contract sent as `protocol_version`.
+/// Hooks may echo it back; a response naming any other version is rejected.
+pub(crate) const AGENT_HOOK_PROTOCOL_VERSION: u32 = 1;
+
+fn default_hook_protocol_version() -> u32 {
+    AGENT_HOOK_PROTOCOL_VERSION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct AgentHookRequest {
+    #[serde(default = "default_hook_protocol_version")]
+    pub(crate) protocol_version: u32,
     pub(crate) messages: Vec<AgentMessage>,
     pub(crate) tools: Vec<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -356,9 +660,22 @@ pub(crate) struct AgentHookRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct AgentHookResponse {
+    /// Omitted by older hooks, which are treated as speaking version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) protocol_version: Option<u32>,
     pub(crate) message: AgentMessage,
 }
 
+/// Error unless `version` (from a hook request or response) is one we speak.
+pub(crate) fn check_hook_protocol_version(version: Option<u32>) -> Result<(), String> {
+    match version {
+        None | Some(AGENT_HOOK_PROTOCOL_VERSION) => Ok(()),
+        Some(other) => Err(format!(
+            "agent hook protocol version {other} is not supported (aethervault speaks version {AGENT_HOOK_PROTOCOL_VERSION})"
+        )),
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct AgentToolResult {
     pub(crate) id: String,
@@ -384,6 +701,17 @@ pub(crate) struct AgentRunOutput {
     pub(crate) final_text: Option<String>,
 }
 
+/// Per-run agent switches; bridges take the defaults.
+#[derive(Default)]
+pub(crate) struct AgentRunOptions<'a> {
+    pub(crate) events: Option<&'a crate::AgentEventSink>,
+    pub(crate) tool_concurrency: Option<usize>,
+    pub(crate) all_tools: bool,
+    pub(crate) model_timeout_ms: Option<u64>,
+    pub(crate) model_timeout_policy: Option<crate::ModelTimeoutPolicy>,
+    pub(crate) force_tool: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ContinuationCheckpoint {
     pub(crate) session: String,
@@ -395,6 +723,37 @@ pub(crate) struct ContinuationCheckpoint {
     pub(crate) chain_depth: usize,
 }
 
+/// One line of the `agent --events-file` NDJSON stream.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum AgentEvent<'a> {
+    Step {
+        step: usize,
+        max_steps: usize,
+    },
+    Message {
+        step: usize,
+        text: &'a str,
+    },
+    ToolCall {
+        step: usize,
+        id: &'a str,
+        name: &'a str,
+        args: &'a serde_json::Value,
+    },
+    ToolResult {
+        step: usize,
+        id: &'a str,
+        name: &'a str,
+        is_error: bool,
+        output: &'a str,
+    },
+    Final {
+        text: Option<&'a str>,
+        tool_calls: usize,
+    },
+}
+
 pub(crate) struct AgentProgress {
     pub(crate) step: usize,
     pub(crate) max_steps: usize,
@@ -582,6 +941,7 @@ impl BackgroundTaskRegistry {
 
 pub(crate) struct CompletionEvent {
     pub(crate) chat_id: i64,
+    pub(crate) session: String,
     pub(crate) reply_to_id: Option<i64>,
     pub(crate) result: Result<AgentRunOutput, String>,
 }
@@ -649,6 +1009,7 @@ pub(crate) struct BridgeAgentConfig {
     pub(crate) log: bool,
     pub(crate) log_commit_interval: usize,
     pub(crate) session_prefix: String,
+    pub(crate) group_sessions: crate::GroupSessions,
 }
 
     #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -693,6 +1054,23 @@ pub(crate) struct ApprovalEntry {
     pub(crate) args: serde_json::Value,
     pub(crate) status: String,
     pub(crate) created_at: String,
+    /// One-line description of the action shown to the approver; see `approval_summary`.
+    #[serde(default)]
+    pub(crate) summary: String,
+    /// When the approver was last pinged about this entry (autonomous runs only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) notified_at: Option<String>,
+}
+
+/// A successful write-tool result remembered under its `idempotency_key`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct IdempotencyEntry {
+    pub(crate) key: String,
+    pub(crate) tool: String,
+    pub(crate) args_hash: String,
+    pub(crate) output: String,
+    pub(crate) details: serde_json::Value,
+    pub(crate) created_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -722,6 +1100,47 @@ pub(crate) struct TriggerEntry {
     pub(crate) schedule_name: Option<String>,
 }
 
+impl TriggerEntry {
+    /// `{{name}}` placeholders a prompt may use for this trigger kind.
+    pub(crate) fn template_vars(kind: &str) -> Vec<&'static str> {
+        let mut vars = vec!["trigger_id", "trigger_name", "fired_at"];
+        vars.extend_from_slice(match kind {
+            "email" => &["query", "message_id", "sender", "subject", "date", "snippet"][..],
+            "calendar_free" => &["start", "end"][..],
+            "cron" => &["cron"][..],
+            "webhook" => &["url", "response"][..],
+            _ => &[][..],
+        });
+        vars
+    }
+}
+
+/// Substitute `{{name}}` placeholders from `vars`. Unknown placeholders are
+/// left in place and returned so the caller can warn about them.
+pub(crate) fn render_trigger_prompt(template: &str, vars: &BTreeMap<&str, String>) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(template.len());
+    let mut unknown = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else {
+            break;
+        };
+        let raw = &rest[open..open + 2 + close + 2];
+        let name = raw[2..raw.len() - 2].trim();
+        out.push_str(&rest[..open]);
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                out.push_str(raw);
+                unknown.push(name.to_string());
+            }
+        }
+        rest = &rest[open + raw.len()..];
+    }
+    out.push_str(rest);
+    (out, unknown)
+}
+
 /// Simple cron expression matcher (minute hour dom month dow)
 pub(crate) struct CronExpr {
     pub(crate) minute: CronField,
@@ -886,3 +1305,47 @@ pub(crate) fn format_tool_message_content(name: &str, output: &str, details: &se
     format!("{output}\n\n[details]\n{details_str}")
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_prompt_substitutes_known_placeholders() {
+        let vars = BTreeMap::from([("sender", "ana@example.com".to_string()), ("subject", "Invoice".to_string())]);
+        let (prompt, unknown) = render_trigger_prompt("Mail from {{sender}}: {{ subject }} ({{event_title}})", &vars);
+        assert_eq!(prompt, "Mail from ana@example.com: Invoice ({{event_title}})");
+        assert_eq!(unknown, vec!["event_title"]);
+        assert_eq!(render_trigger_prompt("no vars {{", &vars).0, "no vars {{");
+        assert!(TriggerEntry::template_vars("email").contains(&"subject"));
+    }
+
+    #[test]
+    fn hook_response_accepts_both_tool_call_shapes() {
+        let raw = r#"{"protocol_version":1,"message":{"role":"assistant","tool_calls":[
+            {"id":"a","name":"query","args":{"query":"x"}},
+            {"id":"b","type":"function","function":{"name":"search","arguments":"{\"query\":\"y\"}"}}
+        ]}}"#;
+        let response: AgentHookResponse = serde_json::from_str(raw).unwrap();
+        let calls = &response.message.tool_calls;
+        assert_eq!((calls[0].name.as_str(), calls[0].args["query"].as_str()), ("query", Some("x")));
+        assert_eq!((calls[1].name.as_str(), calls[1].args["query"].as_str()), ("search", Some("y")));
+        assert_eq!(
+            serde_json::to_value(&calls[1]).unwrap(),
+            serde_json::json!({"id": "b", "name": "search", "args": {"query": "y"}})
+        );
+
+        let bad = r#"{"message":{"role":"assistant","tool_calls":[{"id":"c","function":{"name":"x","arguments":"{"}}]}}"#;
+        assert!(serde_json::from_str::<AgentHookResponse>(bad).is_err());
+    }
+
+    #[test]
+    fn hook_protocol_version_negotiation() {
+        assert!(check_hook_protocol_version(None).is_ok());
+        assert!(check_hook_protocol_version(Some(AGENT_HOOK_PROTOCOL_VERSION)).is_ok());
+        let err = check_hook_protocol_version(Some(AGENT_HOOK_PROTOCOL_VERSION + 1)).unwrap_err();
+        assert!(err.contains("not supported"), "{err}");
+        let legacy: AgentHookRequest = serde_json::from_str(r#"{"messages":[],"tools":[]}"#).unwrap();
+        assert_eq!(legacy.protocol_version, AGENT_HOOK_PROTOCOL_VERSION);
+    }
+}
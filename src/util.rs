@@ -4,7 +4,11 @@ use std::io;
 use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
 
+use std::sync::LazyLock;
+
 use blake3::Hash;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
 use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
 
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -19,6 +23,15 @@ pub(crate) fn scope_prefix(collection: &str) -> String {
     format!("aethervault://{}/", normalize_collection(collection))
 }
 
+/// First path segment after the scheme (`aethervault://docs/a.md` -> `docs`).
+pub(crate) fn uri_collection(uri: &str) -> Option<String> {
+    let (_, rest) = uri.split_once("://")?;
+    rest.split('/')
+        .next()
+        .filter(|segment| !segment.is_empty())
+        .map(normalize_collection)
+}
+
 pub(crate) fn uri_for_path(collection: &str, relative: &Path) -> String {
     let rel = relative
         .components()
@@ -70,9 +83,9 @@ pub(crate) fn open_or_create_db(path: &Path) -> Result<crate::memory_db::MemoryD
     }
 
     // ── MV2 vault detected — auto-migrate to SQLite WAL ──────────────
-    eprintln!("[migrate] Detected MV2 vault at {}", path.display());
+    log_info!("[migrate] Detected MV2 vault at {}", path.display());
     let file_size_mb = std::fs::metadata(path).map(|m| m.len() / (1024 * 1024)).unwrap_or(0);
-    eprintln!("[migrate] File size: {file_size_mb} MB — migrating to SQLite WAL...");
+    log_info!("[migrate] File size: {file_size_mb} MB — migrating to SQLite WAL...");
 
     // Step 1: Rename original MV2 to a backup path (preserves it intact).
     let backup = path.with_extension("mv2.pre-sqlite");
@@ -84,14 +97,14 @@ pub(crate) fn open_or_create_db(path: &Path) -> Result<crate::memory_db::MemoryD
     }
     std::fs::rename(path, &backup)
         .map_err(|e| format!("rename MV2 → backup: {e}"))?;
-    eprintln!("[migrate] Backed up MV2 to {}", backup.display());
+    log_info!("[migrate] Backed up MV2 to {}", backup.display());
 
     // Step 2: Create fresh SQLite database at the original path.
     let db = match MemoryDb::open_or_create(path) {
         Ok(db) => db,
         Err(e) => {
-            eprintln!("[migrate] FAILED to create SQLite DB: {e}");
-            eprintln!("[migrate] Rolling back — restoring original MV2");
+            log_error!("[migrate] FAILED to create SQLite DB: {e}");
+            log_error!("[migrate] Rolling back — restoring original MV2");
             let _ = std::fs::remove_file(path);
             std::fs::rename(&backup, path)
                 .map_err(|e2| format!("rollback also failed: {e2} (original: {e})"))?;
@@ -102,27 +115,27 @@ pub(crate) fn open_or_create_db(path: &Path) -> Result<crate::memory_db::MemoryD
     // Step 3: Migrate all data from the backed-up MV2 vault.
     match db.migrate_from_vault(&backup) {
         Ok(report) => {
-            eprintln!(
+            log_info!(
                 "[migrate] SUCCESS: {}/{} frames migrated, {} skipped",
                 report.migrated, report.total_frames, report.skipped
             );
             if !report.errors.is_empty() {
-                eprintln!("[migrate] {} non-fatal errors:", report.errors.len());
+                log_warn!("[migrate] {} non-fatal errors:", report.errors.len());
                 for (i, err) in report.errors.iter().enumerate().take(20) {
-                    eprintln!("[migrate]   {}: {err}", i + 1);
+                    log_warn!("[migrate]   {}: {err}", i + 1);
                 }
                 if report.errors.len() > 20 {
-                    eprintln!("[migrate]   ... and {} more", report.errors.len() - 20);
+                    log_warn!("[migrate]   ... and {} more", report.errors.len() - 20);
                 }
             }
             let new_size_mb = std::fs::metadata(path).map(|m| m.len() / (1024 * 1024)).unwrap_or(0);
-            eprintln!("[migrate] SQLite size: {new_size_mb} MB (was {file_size_mb} MB MV2)");
-            eprintln!("[migrate] Original MV2 preserved at: {}", backup.display());
+            log_info!("[migrate] SQLite size: {new_size_mb} MB (was {file_size_mb} MB MV2)");
+            log_info!("[migrate] Original MV2 preserved at: {}", backup.display());
             Ok(db)
         }
         Err(e) => {
-            eprintln!("[migrate] MIGRATION FAILED: {e}");
-            eprintln!("[migrate] Rolling back — restoring original MV2");
+            log_error!("[migrate] MIGRATION FAILED: {e}");
+            log_error!("[migrate] Rolling back — restoring original MV2");
             drop(db);
             let _ = std::fs::remove_file(path);
             // Clean up WAL/SHM files SQLite may have created
@@ -135,6 +148,57 @@ pub(crate) fn open_or_create_db(path: &Path) -> Result<crate::memory_db::MemoryD
     }
 }
 
+/// Open an existing SQLite capsule read-only. Unlike [`open_or_create_db`]
+/// this never creates the file or migrates an MV2 vault.
+pub(crate) fn open_db_read_only(path: &Path) -> Result<crate::memory_db::MemoryDb, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Err(format!("capsule not found: {}", path.display()).into());
+    }
+    if !is_sqlite_file(path) {
+        return Err(format!(
+            "{} is an MV2 vault; open it once without --read-only to migrate it",
+            path.display()
+        )
+        .into());
+    }
+    crate::memory_db::MemoryDb::open_read_only(path)
+}
+
+/// `GET /healthz` and `GET /readyz` for the HTTP servers: the version and
+/// whether `mv2` opens. Readiness answers 503 when it does not; `None` for
+/// any other route. Never authenticated.
+pub(crate) fn health_reply(method: &tiny_http::Method, url: &str, mv2: &Path) -> Option<(u16, serde_json::Value)> {
+    if *method != tiny_http::Method::Get {
+        return None;
+    }
+    let readiness = match url.split('?').next().unwrap_or(url) {
+        "/healthz" => false,
+        "/readyz" => true,
+        _ => return None,
+    };
+    let capsule_ok = open_db_read_only(mv2).is_ok();
+    let status = if readiness && !capsule_ok { 503 } else { 200 };
+    let body = serde_json::json!({
+        "status": if capsule_ok { "ok" } else { "degraded" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "capsule": capsule_ok,
+    });
+    Some((status, body))
+}
+
+/// [`health_reply`] as a JSON response, for servers without their own router.
+pub(crate) fn health_response(
+    request: &tiny_http::Request,
+    mv2: &Path,
+) -> Option<tiny_http::Response<io::Cursor<Vec<u8>>>> {
+    let (status, body) = health_reply(request.method(), request.url(), mv2)?;
+    let mut response = tiny_http::Response::from_string(body.to_string()).with_status_code(status);
+    if let Ok(header) = tiny_http::Header::from_bytes("Content-Type", "application/json") {
+        response.add_header(header);
+    }
+    Some(response)
+}
+
 fn is_sqlite_file(path: &Path) -> bool {
     use std::io::Read;
     let mut f = match std::fs::File::open(path) {
@@ -156,10 +220,75 @@ pub(crate) fn is_extension_allowed(path: &Path, exts: &[String]) -> bool {
     exts.iter().any(|allowed| ext.eq_ignore_ascii_case(allowed))
 }
 
+/// Sniff the first 8 KiB: any NUL byte, or more than 10% invalid UTF-8 /
+/// control characters, means binary.
+pub(crate) fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(8192)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let text = String::from_utf8_lossy(sample);
+    let mut total = 0usize;
+    let mut junk = 0usize;
+    for ch in text.chars() {
+        total += 1;
+        if ch == '\u{FFFD}' || (ch.is_control() && !matches!(ch, '\n' | '\r' | '\t' | '\x0c')) {
+            junk += 1;
+        }
+    }
+    total > 0 && junk * 10 > total
+}
+
+/// Best-effort MIME type from a file extension, for binary payloads.
+pub(crate) fn kind_for_extension(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "sqlite" | "db" => "application/vnd.sqlite3",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Gitignore-syntax matcher for `globs`, relative to `root`.
+pub(crate) fn glob_matcher(root: &Path, globs: &[String]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.allow_unclosed_class(false);
+    for glob in globs {
+        builder
+            .add_line(None, glob)
+            .map_err(|e| format!("invalid glob {glob:?}: {e}"))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// True when `path` (or a parent directory under the matcher's root) matches.
+pub(crate) fn glob_matches(matcher: &Gitignore, path: &Path, is_dir: bool) -> bool {
+    !matcher.is_empty() && matcher.matched_path_or_any_parents(path, is_dir).is_ignore()
+}
+
 #[derive(Default, Debug)]
 pub(crate) struct ParsedMarkup {
     pub(crate) collection: Option<String>,
     pub(crate) asof_ts: Option<i64>,
+    /// `asof:#N` pins the query to frames up to id N.
+    pub(crate) asof_frame: Option<u64>,
     pub(crate) before_ts: Option<i64>,
     pub(crate) after_ts: Option<i64>,
 }
@@ -175,6 +304,11 @@ pub(crate) fn parse_date_to_ts(value: &str) -> Option<i64> {
     None
 }
 
+/// The `#N` form of `--asof` / `asof:`: a frame id to pin the query to.
+pub(crate) fn parse_asof_frame(value: &str) -> Option<u64> {
+    value.trim().strip_prefix('#')?.parse().ok()
+}
+
 pub(crate) fn parse_query_markup(raw: &str) -> (String, ParsedMarkup) {
     let mut parsed = ParsedMarkup::default();
     let mut kept = Vec::new();
@@ -191,7 +325,12 @@ pub(crate) fn parse_query_markup(raw: &str) -> (String, ParsedMarkup) {
                 }
             }
             "asof" => {
-                parsed.asof_ts = parse_date_to_ts(value);
+                if let Some(frame) = parse_asof_frame(value) {
+                    parsed.asof_frame = Some(frame);
+                    parsed.asof_ts = None;
+                } else if parsed.asof_frame.is_none() {
+                    parsed.asof_ts = parse_date_to_ts(value);
+                }
             }
             "before" => {
                 parsed.before_ts = parse_date_to_ts(value);
@@ -207,6 +346,71 @@ pub(crate) fn parse_query_markup(raw: &str) -> (String, ParsedMarkup) {
     (cleaned, parsed)
 }
 
+/// How the snippet builders (SQLite's `snippet()`, `centered_snippet`) mark a cut.
+pub(crate) const SNIPPET_ELLIPSIS: &str = "…";
+
+/// Display form of a snippet: whitespace runs collapsed to one space and
+/// trimmed, then cut at a word boundary to at most `max_chars` (0 = no cap).
+/// Each cut, made here or marked by the builder, shows as `ellipsis`.
+pub(crate) fn tidy_snippet(text: &str, max_chars: usize, ellipsis: &str) -> String {
+    let joined = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut body = joined.as_str();
+    let head_cut = match body.strip_prefix(SNIPPET_ELLIPSIS) {
+        Some(rest) => {
+            body = rest.trim_start();
+            true
+        }
+        None => false,
+    };
+    let mut tail_cut = match body.strip_suffix(SNIPPET_ELLIPSIS) {
+        Some(rest) => {
+            body = rest.trim_end();
+            true
+        }
+        None => false,
+    };
+    if max_chars > 0
+        && let Some((end, _)) = body.char_indices().nth(max_chars)
+    {
+        let end = body[..end].rfind(' ').filter(|&ws| ws > 0).unwrap_or(end);
+        body = body[..end].trim_end();
+        tail_cut = true;
+    }
+    if body.is_empty() {
+        return String::new();
+    }
+    let mut out = String::with_capacity(body.len() + 2 * ellipsis.len());
+    if head_cut {
+        out.push_str(ellipsis);
+    }
+    out.push_str(body);
+    if tail_cut {
+        out.push_str(ellipsis);
+    }
+    out
+}
+
+/// Drop common Markdown markup for display: heading and quote markers,
+/// emphasis and code delimiters, and link/image targets (the text stays).
+/// Intra-word underscores (`snake_case`) are kept.
+pub(crate) fn strip_markdown(text: &str) -> String {
+    static LINK: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"!?\[([^\]\n]*)\]\([^)\n]*\)?").expect("link regex"));
+    static BLOCK: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?m)^([ \t]*)(?:#{1,6}[ \t]+|>[ \t]?)").expect("block regex"));
+    static CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`+|~~").expect("code regex"));
+    static OPEN_EMPHASIS: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(^|[^\w*])[*_]{1,3}([^\s*_])").expect("emphasis regex"));
+    static CLOSE_EMPHASIS: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"([^\s*_])[*_]{1,3}([^\w*]|$)").expect("emphasis regex"));
+
+    let text = LINK.replace_all(text, "$1");
+    let text = BLOCK.replace_all(&text, "$1");
+    let text = CODE.replace_all(&text, "");
+    let text = OPEN_EMPHASIS.replace_all(&text, "$1$2");
+    CLOSE_EMPHASIS.replace_all(&text, "$1$2").into_owned()
+}
+
 pub(crate) fn is_stopword(token: &str) -> bool {
     matches!(
         token,
@@ -256,11 +460,14 @@ pub(crate) fn tokenize(query: &str) -> Vec<String> {
         .collect()
 }
 
+/// Drop blank values and repeats, keeping the first spelling. Values equal
+/// up to whitespace count as repeats, so a lane never searches one query twice.
 pub(crate) fn dedup_keep_order(values: Vec<String>) -> Vec<String> {
     let mut seen = std::collections::HashSet::new();
     let mut out = Vec::new();
     for v in values {
-        if seen.insert(v.clone()) {
+        let key = v.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !key.is_empty() && seen.insert(key) {
             out.push(v);
         }
     }
@@ -301,6 +508,54 @@ pub(crate) fn env_optional(name: &str) -> Option<String> {
     env::var(name).ok().filter(|value| !value.trim().is_empty())
 }
 
+const DEFAULT_MV2_PATH: &str = "./data/knowledge.mv2";
+
+/// Capsule path: the explicit argument, else `AETHERVAULT_MV2`, else
+/// `./data/knowledge.mv2`.
+pub(crate) fn resolve_mv2_path(cli_mv2: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = cli_mv2 {
+        return path;
+    }
+    if let Some(value) = env_optional("AETHERVAULT_MV2") {
+        return PathBuf::from(value);
+    }
+    PathBuf::from(DEFAULT_MV2_PATH)
+}
+
+/// Like [`resolve_mv2_path`], but for commands that read an existing capsule:
+/// falling back to a default that does not exist is an error rather than
+/// silently creating an empty capsule.
+pub(crate) fn resolve_existing_mv2(cli_mv2: Option<PathBuf>) -> Result<PathBuf, String> {
+    if cli_mv2.is_some() || env_optional("AETHERVAULT_MV2").is_some() {
+        return Ok(resolve_mv2_path(cli_mv2));
+    }
+    let path = PathBuf::from(DEFAULT_MV2_PATH);
+    if !path.exists() {
+        return Err(format!(
+            "no capsule given and {DEFAULT_MV2_PATH} does not exist; pass the MV2 path or set AETHERVAULT_MV2"
+        ));
+    }
+    Ok(path)
+}
+
+/// Split `[MV2] <operand>` positionals: with a single value it is the
+/// operand (unless it names an existing `.mv2` file) and the capsule comes
+/// from [`resolve_existing_mv2`].
+pub(crate) fn mv2_and_operand(
+    mv2: Option<PathBuf>,
+    operand: Option<String>,
+    name: &str,
+) -> Result<(PathBuf, String), String> {
+    match (mv2, operand) {
+        (Some(path), Some(operand)) => Ok((path, operand)),
+        (Some(only), None) if only.extension() == Some(OsStr::new("mv2")) && only.is_file() => {
+            Err(format!("missing <{name}> argument after {}", only.display()))
+        }
+        (Some(only), None) => Ok((resolve_existing_mv2(None)?, only.to_string_lossy().into_owned())),
+        (None, _) => Err(format!("missing <{name}> argument")),
+    }
+}
+
 pub(crate) fn env_u64(name: &str, default: u64) -> Result<u64, Box<dyn std::error::Error>> {
     match env_optional(name) {
         Some(value) => Ok(value
@@ -346,9 +601,208 @@ pub(crate) fn jitter_ratio() -> f64 {
     (nanos % 1000) as f64 / 1000.0
 }
 
+/// Longest `Retry-After` we honor; a bogus far-future date must not stall a run.
+const MAX_RETRY_AFTER_SECS: f64 = 300.0;
+
 pub(crate) fn parse_retry_after(resp: &ureq::Response) -> Option<f64> {
     resp.header("retry-after")
-        .and_then(|v| v.trim().parse::<f64>().ok())
+        .and_then(|v| retry_after_secs(v, Utc::now()))
+}
+
+/// `Retry-After` as seconds from `now`: either delay-seconds or an HTTP-date
+/// (RFC 7231), clamped to `0..=MAX_RETRY_AFTER_SECS`.
+pub(crate) fn retry_after_secs(value: &str, now: chrono::DateTime<Utc>) -> Option<f64> {
+    let value = value.trim();
+    let secs = match value.parse::<f64>() {
+        Ok(secs) if secs.is_finite() => secs,
+        Ok(_) => return None,
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.with_timezone(&Utc) - now).num_milliseconds() as f64 / 1000.0
+        }
+    };
+    Some(secs.clamp(0.0, MAX_RETRY_AFTER_SECS))
+}
+
+/// Retry policy for the built-in model hooks, read from `{PREFIX}_MAX_RETRIES`,
+/// `_RETRY_BASE`, `_RETRY_MAX` (seconds per wait) and `_RETRY_BUDGET` (seconds
+/// of total waiting across all attempts).
+#[derive(Debug, Clone)]
+pub(crate) struct HookRetryPolicy {
+    pub(crate) max_retries: usize,
+    pub(crate) base: f64,
+    pub(crate) max_delay: f64,
+    pub(crate) budget: f64,
+}
+
+impl HookRetryPolicy {
+    pub(crate) fn from_env(prefix: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            max_retries: env_usize(&format!("{prefix}_MAX_RETRIES"), 2)?,
+            base: env_f64(&format!("{prefix}_RETRY_BASE"), 0.5)?,
+            max_delay: env_f64(&format!("{prefix}_RETRY_MAX"), 4.0)?,
+            budget: env_f64(&format!("{prefix}_RETRY_BUDGET"), 120.0)?,
+        })
+    }
+
+    /// Exponential backoff for `attempt` (0-based), raised to the server's
+    /// `Retry-After` when that is longer, plus up to 20% jitter.
+    pub(crate) fn delay(&self, attempt: usize, retry_after: Option<f64>) -> f64 {
+        let mut delay = (self.base * 2.0_f64.powi(attempt as i32)).min(self.max_delay);
+        if let Some(retry_after) = retry_after {
+            delay = delay.max(retry_after);
+        }
+        delay * (1.0 + jitter_ratio() * 0.2)
+    }
+}
+
+/// An HTTP call (model API or tool) that failed for good: either an HTTP
+/// status with its body or a transport error.
+#[derive(Debug)]
+pub(crate) enum HookHttpError {
+    Status(u16, String),
+    Transport(String),
+}
+
+impl std::fmt::Display for HookHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookHttpError::Status(code, text) => write!(f, "{code} {text}"),
+            HookHttpError::Transport(err) => write!(f, "transport error: {err}"),
+        }
+    }
+}
+
+/// 429 and 5xx (including Anthropic's 529 "overloaded") are worth retrying.
+pub(crate) fn is_retryable_status(code: u16) -> bool {
+    code == 429 || (500..600).contains(&code)
+}
+
+/// POST `payload` on a fresh request from `build` until it succeeds, fails
+/// with a non-retryable status, or the policy's retry count or backoff budget
+/// runs out. Each retry is noted on stderr under `label`; stdout is left alone
+/// for the hook JSON contract.
+pub(crate) fn send_with_retry(
+    label: &str,
+    policy: &HookRetryPolicy,
+    payload: &serde_json::Value,
+    build: impl Fn() -> ureq::Request,
+) -> Result<String, HookHttpError> {
+    let mut waited = 0.0;
+    let mut attempt = 0;
+    loop {
+        let (err, retry_after) = match build().send_json(payload.clone()) {
+            Ok(resp) => {
+                return resp
+                    .into_string()
+                    .map_err(|e| HookHttpError::Transport(e.to_string()));
+            }
+            Err(ureq::Error::Status(code, resp)) => {
+                let retry_after = parse_retry_after(&resp);
+                let text = resp.into_string().unwrap_or_default();
+                if !is_retryable_status(code) {
+                    return Err(HookHttpError::Status(code, text));
+                }
+                (HookHttpError::Status(code, text), retry_after)
+            }
+            Err(ureq::Error::Transport(err)) => (HookHttpError::Transport(err.to_string()), None),
+        };
+        if attempt >= policy.max_retries {
+            return Err(err);
+        }
+        let delay = policy.delay(attempt, retry_after);
+        if waited + delay > policy.budget {
+            log_warn!(
+                "[{label}] attempt {} failed ({err}); retry budget of {:.0}s exhausted",
+                attempt + 1,
+                policy.budget
+            );
+            return Err(err);
+        }
+        log_warn!(
+            "[{label}] attempt {}/{} failed ({err}); retrying in {delay:.1}s",
+            attempt + 1,
+            policy.max_retries + 1
+        );
+        std::thread::sleep(std::time::Duration::from_secs_f64(delay));
+        waited += delay;
+        attempt += 1;
+    }
+}
+
+/// Which failures of a request may be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HttpRetry {
+    /// Safe to repeat (GETs, read-only POSTs): transport errors, 429 and 5xx.
+    Idempotent,
+    /// Has side effects (sends, creates): only failures where the request
+    /// never reached the server, so a retry cannot double-send.
+    ConnectOnly,
+}
+
+/// Request body for `http_with_retry`, re-sent on every attempt.
+#[derive(Clone, Copy)]
+pub(crate) enum HttpBody<'a> {
+    Empty,
+    Json(&'a serde_json::Value),
+    Text(&'a str),
+}
+
+/// Send a fresh request from `build` up to `max_attempts` times with jittered
+/// exponential backoff, honoring `Retry-After`, while total waiting stays
+/// within `budget` seconds. Failures are logged on stderr under `label`.
+pub(crate) fn http_with_retry(
+    label: &str,
+    retry: HttpRetry,
+    max_attempts: usize,
+    budget: f64,
+    body: HttpBody<'_>,
+    build: impl Fn() -> ureq::Request,
+) -> Result<ureq::Response, HookHttpError> {
+    let policy = HookRetryPolicy {
+        max_retries: max_attempts.saturating_sub(1),
+        base: 0.5,
+        max_delay: 4.0,
+        budget,
+    };
+    let mut waited = 0.0;
+    let mut attempt = 0;
+    loop {
+        let request = build();
+        let sent = match body {
+            HttpBody::Empty => request.call(),
+            HttpBody::Json(value) => request.send_json(value),
+            HttpBody::Text(text) => request.send_string(text),
+        };
+        let (err, retryable, retry_after) = match sent {
+            Ok(resp) => return Ok(resp),
+            Err(ureq::Error::Status(code, resp)) => {
+                let retry_after = parse_retry_after(&resp);
+                let text = resp.into_string().unwrap_or_default();
+                let retryable = retry == HttpRetry::Idempotent && is_retryable_status(code);
+                (HookHttpError::Status(code, text), retryable, retry_after)
+            }
+            Err(ureq::Error::Transport(t)) => {
+                let unsent = matches!(
+                    t.kind(),
+                    ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::ProxyConnect
+                );
+                let retryable = retry == HttpRetry::Idempotent || unsent;
+                (HookHttpError::Transport(t.to_string()), retryable, None)
+            }
+        };
+        if !retryable || attempt >= policy.max_retries {
+            return Err(err);
+        }
+        let delay = policy.delay(attempt, retry_after);
+        if waited + delay > policy.budget {
+            return Err(err);
+        }
+        log_warn!("[{label}] attempt {} failed ({err}); retrying in {delay:.1}s", attempt + 1);
+        std::thread::sleep(std::time::Duration::from_secs_f64(delay));
+        waited += delay;
+        attempt += 1;
+    }
 }
 
 pub(crate) fn command_wrapper() -> Option<Vec<String>> {
@@ -359,6 +813,47 @@ pub(crate) fn command_wrapper() -> Option<Vec<String>> {
     })
 }
 
+/// Variables a scrubbed child keeps even when `AETHERVAULT_EXEC_ENV_ALLOW` lists none.
+const EXEC_SAFE_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_ALL", "LC_CTYPE", "TERM", "TMPDIR", "TZ",
+];
+
+/// Whether `name` survives scrubbing under `allow`: the safe base set plus the
+/// comma/space-separated names in `allow` (`NAME`, or `PREFIX_*`).
+pub(crate) fn exec_env_allowed(name: &str, allow: &str) -> bool {
+    EXEC_SAFE_ENV.contains(&name)
+        || allow
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|p| !p.is_empty())
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+}
+
+/// With `AETHERVAULT_EXEC_ENV_ALLOW` set, give the child only the safe base
+/// environment plus the allowed names, so commands run for the model cannot
+/// read provider tokens. Unset inherits everything, secrets included.
+pub(crate) fn scrub_command_env(cmd: &mut ProcessCommand) {
+    let Some(allow) = env_optional("AETHERVAULT_EXEC_ENV_ALLOW") else {
+        return;
+    };
+    cmd.env_clear();
+    for (name, value) in env::vars_os() {
+        if name.to_str().is_some_and(|n| exec_env_allowed(n, &allow)) {
+            cmd.env(name, value);
+        }
+    }
+}
+
+/// Path for an external CLI, overridable per tool via `AETHERVAULT_<NAME>_BIN`
+/// (`signal-cli` reads `AETHERVAULT_SIGNAL_CLI_BIN`). Falls back to the bare
+/// name, resolved on PATH.
+pub(crate) fn external_bin(name: &str) -> String {
+    let var = format!("AETHERVAULT_{}_BIN", name.to_ascii_uppercase().replace('-', "_"));
+    env_optional(&var).unwrap_or_else(|| name.to_string())
+}
+
 pub(crate) fn build_external_command(program: &str, args: &[String]) -> ProcessCommand {
     let mut cmd = if let Some(wrapper) = command_wrapper() {
         let mut c = ProcessCommand::new(&wrapper[0]);
@@ -470,3 +965,182 @@ pub(crate) fn resolve_workspace(cli: Option<PathBuf>, agent_cfg: &AgentConfig) -
     Some(PathBuf::from(DEFAULT_WORKSPACE_DIR))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flaky_server(statuses: Vec<u16>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for status in statuses {
+                let Ok(request) = server.recv() else { return };
+                let header = tiny_http::Header::from_bytes("Retry-After", "0").unwrap();
+                let response = tiny_http::Response::from_string(format!("status {status}"))
+                    .with_status_code(status)
+                    .with_header(header);
+                let _ = request.respond(response);
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn dedup_keep_order_ignores_whitespace_and_blanks() {
+        let values = ["alpha beta", " alpha  beta ", "", "gamma", "alpha beta", "  "]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(dedup_keep_order(values), vec!["alpha beta", "gamma"]);
+    }
+
+    #[test]
+    fn health_routes_report_version_and_capsule() {
+        let missing = std::env::temp_dir().join(format!("aethervault_health_{}.mv2", std::process::id()));
+        let _ = std::fs::remove_file(&missing);
+        let get = tiny_http::Method::Get;
+        let (status, body) = health_reply(&get, "/healthz", &missing).unwrap();
+        assert_eq!((status, &body["capsule"]), (200, &serde_json::json!(false)));
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(health_reply(&get, "/readyz", &missing).unwrap().0, 503);
+
+        crate::memory_db::MemoryDb::open_or_create(&missing).unwrap();
+        let (status, body) = health_reply(&get, "/readyz?probe=1", &missing).unwrap();
+        assert_eq!((status, body["status"].as_str()), (200, Some("ok")));
+        assert!(health_reply(&get, "/query", &missing).is_none());
+        assert!(health_reply(&tiny_http::Method::Post, "/healthz", &missing).is_none());
+        let _ = std::fs::remove_file(&missing);
+    }
+
+    #[test]
+    fn exec_env_allowlist_keeps_safe_set_and_listed_names() {
+        assert!(exec_env_allowed("PATH", "-"));
+        assert!(!exec_env_allowed("TELEGRAM_BOT_TOKEN", "-"));
+        assert!(exec_env_allowed("GITHUB_TOKEN", "GITHUB_TOKEN, NPM_*"));
+        assert!(exec_env_allowed("NPM_CONFIG_CACHE", "GITHUB_TOKEN,NPM_*"));
+        assert!(!exec_env_allowed("DO_TOKEN", "GITHUB_TOKEN,NPM_*"));
+    }
+
+    #[test]
+    fn mv2_and_operand_accepts_an_omitted_capsule() {
+        let (path, query) = mv2_and_operand(Some("k.mv2".into()), Some("tea".into()), "QUERY").unwrap();
+        assert_eq!((path, query.as_str()), (PathBuf::from("k.mv2"), "tea"));
+        assert!(mv2_and_operand(None, None, "QUERY").unwrap_err().contains("<QUERY>"));
+        if env_optional("AETHERVAULT_MV2").is_none() && !Path::new(DEFAULT_MV2_PATH).exists() {
+            let err = mv2_and_operand(Some("tea".into()), None, "QUERY").unwrap_err();
+            assert!(err.contains("AETHERVAULT_MV2"));
+        }
+    }
+
+    #[test]
+    fn glob_matcher_matches_files_and_directories() {
+        let root = Path::new("/src");
+        let matcher = glob_matcher(root, &["node_modules".into(), "*.lock".into(), "docs/**/*.md".into()]).unwrap();
+        assert!(glob_matches(&matcher, Path::new("/src/a/node_modules/x/readme.md"), false));
+        assert!(glob_matches(&matcher, Path::new("/src/Cargo.lock"), false));
+        assert!(glob_matches(&matcher, Path::new("/src/docs/guide/intro.md"), false));
+        assert!(!glob_matches(&matcher, Path::new("/src/notes/intro.md"), false));
+        assert!(!glob_matches(&glob_matcher(root, &[]).unwrap(), Path::new("/src/a.md"), false));
+        assert!(glob_matcher(root, &["[".into()]).is_err());
+    }
+
+    #[test]
+    fn asof_markup_prefers_frame_ids() {
+        let (cleaned, parsed) = parse_query_markup("budget asof:#42 asof:2024-01-01");
+        assert_eq!(cleaned, "budget");
+        assert_eq!((parsed.asof_frame, parsed.asof_ts), (Some(42), None));
+        let (_, parsed) = parse_query_markup("asof:2024-01-01");
+        assert_eq!(parsed.asof_frame, None);
+        assert!(parsed.asof_ts.is_some());
+        assert_eq!(parse_asof_frame("#x"), None);
+    }
+
+    #[test]
+    fn tidy_snippet_collapses_whitespace_and_marks_cuts() {
+        assert_eq!(tidy_snippet("  one\n\n two\tthree  ", 300, "…"), "one two three");
+        assert_eq!(tidy_snippet("…mid sentence text…", 300, "..."), "...mid sentence text...");
+        assert_eq!(tidy_snippet("alpha beta gamma delta", 12, "…"), "alpha beta…");
+        assert_eq!(tidy_snippet("unbroken", 3, "…"), "unb…");
+        assert_eq!(tidy_snippet("…", 300, "…"), "");
+    }
+
+    #[test]
+    fn strip_markdown_keeps_text() {
+        let md = "## Setup\n> **Note:** see [the guide](https://x.io/g) and ![logo](l.png)\n- run `make_all` *now*, 2 * 3 ~~old~~ [cut](http://tr";
+        assert_eq!(
+            strip_markdown(md),
+            "Setup\nNote: see the guide and logo\n- run make_all now, 2 * 3 old cut"
+        );
+    }
+
+    #[test]
+    fn binary_sniffing() {
+        assert!(!looks_binary(b"# Notes\n\nplain text with tabs\tand unicode \xc3\xa9"));
+        assert!(!looks_binary(b""));
+        assert!(looks_binary(b"PK\x03\x04\x00\x00"));
+        assert!(looks_binary(&[0xff, 0xfe, 0x80, 0x81, 0x82, b'a', 0x90, 0x91]));
+        assert_eq!(kind_for_extension(Path::new("scan.PDF")), "application/pdf");
+        assert_eq!(kind_for_extension(Path::new("blob")), "application/octet-stream");
+    }
+
+    fn policy(max_retries: usize, budget: f64) -> HookRetryPolicy {
+        HookRetryPolicy {
+            max_retries,
+            base: 0.01,
+            max_delay: 0.05,
+            budget,
+        }
+    }
+
+    #[test]
+    fn send_with_retry_recovers_from_transient_statuses() {
+        let url = flaky_server(vec![429, 503, 200]);
+        let body = send_with_retry("test", &policy(2, 10.0), &serde_json::json!({}), || ureq::post(&url)).unwrap();
+        assert_eq!(body, "status 200");
+    }
+
+    #[test]
+    fn send_with_retry_stops_on_client_error_and_budget() {
+        let url = flaky_server(vec![400]);
+        let err = send_with_retry("test", &policy(3, 10.0), &serde_json::json!({}), || ureq::post(&url)).unwrap_err();
+        assert!(matches!(err, HookHttpError::Status(400, _)));
+
+        let url = flaky_server(vec![503, 200]);
+        let err = send_with_retry("test", &policy(3, 0.0), &serde_json::json!({}), || ureq::post(&url)).unwrap_err();
+        assert!(matches!(err, HookHttpError::Status(503, _)));
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        assert_eq!(retry_after_secs(" 7 ", now), Some(7.0));
+        assert_eq!(retry_after_secs("Mon, 02 Mar 2026 12:00:30 GMT", now), Some(30.0));
+        assert_eq!(retry_after_secs("Mon, 02 Mar 2026 11:00:00 GMT", now), Some(0.0));
+        assert_eq!(retry_after_secs("Fri, 01 Jan 2100 00:00:00 GMT", now), Some(MAX_RETRY_AFTER_SECS));
+        assert_eq!(retry_after_secs("86400", now), Some(MAX_RETRY_AFTER_SECS));
+        assert_eq!(retry_after_secs("soon", now), None);
+    }
+
+    #[test]
+    fn http_with_retry_repeats_only_what_is_safe() {
+        let url = flaky_server(vec![503, 200]);
+        let resp = http_with_retry("test", HttpRetry::Idempotent, 3, 10.0, HttpBody::Empty, || ureq::get(&url)).unwrap();
+        assert_eq!(resp.status(), 200);
+
+        let url = flaky_server(vec![503, 200]);
+        let body = serde_json::json!({ "text": "hi" });
+        let err = http_with_retry("test", HttpRetry::ConnectOnly, 3, 10.0, HttpBody::Json(&body), || ureq::post(&url))
+            .unwrap_err();
+        assert!(matches!(err, HookHttpError::Status(503, _)));
+
+        // Nothing listens on port 9 locally: the connection is refused before sending.
+        let calls = std::cell::Cell::new(0);
+        let err = http_with_retry("test", HttpRetry::ConnectOnly, 2, 10.0, HttpBody::Text("hi"), || {
+            calls.set(calls.get() + 1);
+            ureq::post("http://127.0.0.1:9/")
+        })
+        .unwrap_err();
+        assert!(matches!(err, HookHttpError::Transport(_)));
+        assert_eq!(calls.get(), 2);
+    }
+}